@@ -13,7 +13,7 @@ use crate::windowing::{
 use crate::CompositionPipeline;
 use crate::SendableFrameTree;
 use crossbeam_channel::Sender;
-use embedder_traits::Cursor;
+use embedder_traits::{Cursor, DragData};
 use euclid::{Point2D, Scale, Vector2D};
 use gfx_traits::Epoch;
 #[cfg(feature = "gl")]
@@ -26,7 +26,9 @@ use num_traits::FromPrimitive;
 #[cfg(feature = "gl")]
 use pixels::PixelFormat;
 use profile_traits::time::{self as profile_time, profile, ProfilerCategory};
-use script_traits::CompositorEvent::{MouseButtonEvent, MouseMoveEvent, TouchEvent, WheelEvent};
+use script_traits::CompositorEvent::{
+    DropEvent, MouseButtonEvent, MouseMoveEvent, TouchEvent, WheelEvent,
+};
 use script_traits::{AnimationState, AnimationTickType, ConstellationMsg, LayoutControlMsg};
 use script_traits::{
     MouseButton, MouseEventType, ScrollState, TouchEventType, TouchId, WheelDelta,
@@ -893,6 +895,22 @@ impl<Window: WindowMethods + ?Sized> IOCompositor<Window> {
         self.send_wheel_event(delta, p);
     }
 
+    pub fn on_drop_event(&mut self, point: DevicePoint, data: DragData) {
+        let results = self.hit_test_at_point(point);
+        if let Some(item) = results.items.first() {
+            let event = DropEvent(
+                item.point_in_viewport.to_untyped(),
+                Some(UntrustedNodeAddress(item.tag.0 as *const c_void)),
+                data,
+            );
+            let pipeline_id = PipelineId::from_webrender(item.pipeline);
+            let msg = ConstellationMsg::ForwardEvent(pipeline_id, event);
+            if let Err(e) = self.constellation_chan.send(msg) {
+                warn!("Sending event to constellation failed ({:?}).", e);
+            }
+        }
+    }
+
     pub fn on_scroll_event(
         &mut self,
         delta: ScrollLocation,
@@ -4,17 +4,19 @@
 
 //! Abstract windowing methods. The concrete implementations of these can be found in `platform/`.
 
-use embedder_traits::EventLoopWaker;
+use embedder_traits::{DragData, EventLoopWaker};
 use euclid::Scale;
 #[cfg(feature = "gl")]
 use gleam::gl;
 use keyboard_types::KeyboardEvent;
 use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId, TraversalDirection};
+use net_traits::request_interceptor::RequestInterceptor;
 use script_traits::{MouseButton, TouchEventType, TouchId, WheelDelta};
 use servo_geometry::DeviceIndependentPixel;
 use servo_media::player::context::{GlApi, GlContext, NativeDisplay};
 use servo_url::ServoUrl;
 use std::fmt::{Debug, Error, Formatter};
+use std::path::PathBuf;
 #[cfg(feature = "gl")]
 use std::rc::Rc;
 use std::time::Duration;
@@ -67,6 +69,9 @@ pub enum WindowEvent {
     Touch(TouchEventType, TouchId, DevicePoint),
     /// Sent when user moves the mouse wheel.
     Wheel(WheelDelta, DevicePoint),
+    /// Sent when the user drops data dragged from another application, e.g. files
+    /// from the system's file manager, at the given point.
+    Drop(DevicePoint, DragData),
     /// Sent when the user scrolls. The first point is the delta and the second point is the
     /// origin.
     Scroll(ScrollLocation, DeviceIntPoint, TouchEventType),
@@ -101,6 +106,11 @@ pub enum WindowEvent {
     CaptureWebRender,
     /// Toggle sampling profiler with the given sampling rate and max duration.
     ToggleSamplingProfiler(Duration, Duration),
+    /// Start sending the requests matching the interceptor's filters to the embedder, or
+    /// stop intercepting requests if `None`.
+    SetRequestInterceptor(Option<RequestInterceptor>),
+    /// Write the network requests recorded so far as a HAR file at the given path.
+    SaveHar(PathBuf),
 }
 
 impl Debug for WindowEvent {
@@ -116,6 +126,7 @@ impl Debug for WindowEvent {
             WindowEvent::MouseWindowMoveEventClass(..) => write!(f, "MouseMove"),
             WindowEvent::Touch(..) => write!(f, "Touch"),
             WindowEvent::Wheel(..) => write!(f, "Wheel"),
+            WindowEvent::Drop(..) => write!(f, "Drop"),
             WindowEvent::Scroll(..) => write!(f, "Scroll"),
             WindowEvent::Zoom(..) => write!(f, "Zoom"),
             WindowEvent::PinchZoom(..) => write!(f, "PinchZoom"),
@@ -130,6 +141,8 @@ impl Debug for WindowEvent {
             WindowEvent::ToggleWebRenderDebug(..) => write!(f, "ToggleWebRenderDebug"),
             WindowEvent::CaptureWebRender => write!(f, "CaptureWebRender"),
             WindowEvent::ToggleSamplingProfiler(..) => write!(f, "ToggleSamplingProfiler"),
+            WindowEvent::SetRequestInterceptor(..) => write!(f, "SetRequestInterceptor"),
+            WindowEvent::SaveHar(..) => write!(f, "SaveHar"),
             WindowEvent::ExitFullScreen(..) => write!(f, "ExitFullScreen"),
         }
     }
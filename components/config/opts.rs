@@ -234,6 +234,9 @@ pub struct Opts {
 
     /// Only shutdown once all theads are finished.
     pub clean_shutdown: bool,
+
+    /// Record network responses into an archive, or replay them from one.
+    pub network_archive: Option<NetworkArchiveMode>,
 }
 
 fn print_usage(app: &str, opts: &Options) {
@@ -490,6 +493,26 @@ pub enum OutputOptions {
     Stdout(f64),
 }
 
+/// How the resource threads use a network archive.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum NetworkArchiveMode {
+    /// Record every network response into the archive at the given path.
+    Record(PathBuf),
+    /// Answer requests from the archive at the given path instead of the network.
+    Replay(PathBuf, ReplayMissPolicy),
+}
+
+/// What to do with a request that is not in the replayed network archive.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ReplayMissPolicy {
+    /// Fail the request with a network error.
+    Error,
+    /// Answer the request with an empty 404 response.
+    NotFound,
+    /// Fetch the request from the network.
+    Passthrough,
+}
+
 fn args_fail(msg: &str) -> ! {
     writeln!(io::stderr(), "{}", msg).unwrap();
     process::exit(1)
@@ -610,6 +633,7 @@ pub fn default_opts() -> Opts {
         unminify_js: false,
         print_pwm: false,
         clean_shutdown: false,
+        network_archive: None,
     }
 }
 
@@ -778,6 +802,24 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
     opts.optopt("", "profiler-db-pass", "Profiler database password", "");
     opts.optopt("", "profiler-db-name", "Profiler database name", "");
     opts.optflag("", "print-pwm", "Print Progressive Web Metrics");
+    opts.optopt(
+        "",
+        "record-network",
+        "Record all network responses into the given archive",
+        "archive.json",
+    );
+    opts.optopt(
+        "",
+        "replay-network",
+        "Replay network responses from the given archive instead of using the network",
+        "archive.json",
+    );
+    opts.optopt(
+        "",
+        "replay-miss",
+        "How to answer requests missing from the replayed archive (error, 404 or passthrough)",
+        "error",
+    );
 
     let opt_match = match opts.parse(args) {
         Ok(m) => m,
@@ -994,6 +1036,28 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
 
     let is_printing_version = opt_match.opt_present("v") || opt_match.opt_present("version");
 
+    let replay_miss_policy = match opt_match.opt_str("replay-miss") {
+        None => ReplayMissPolicy::Error,
+        Some(ref policy) if policy == "error" => ReplayMissPolicy::Error,
+        Some(ref policy) if policy == "404" => ReplayMissPolicy::NotFound,
+        Some(ref policy) if policy == "passthrough" => ReplayMissPolicy::Passthrough,
+        Some(policy) => args_fail(&format!(
+            "Error parsing option: --replay-miss ({} is not error, 404 or passthrough)",
+            policy
+        )),
+    };
+    let network_archive = match (
+        opt_match.opt_str("record-network"),
+        opt_match.opt_str("replay-network"),
+    ) {
+        (Some(_), Some(_)) => {
+            args_fail("--record-network and --replay-network can't be used together")
+        },
+        (Some(path), None) => Some(NetworkArchiveMode::Record(path.into())),
+        (None, Some(path)) => Some(NetworkArchiveMode::Replay(path.into(), replay_miss_policy)),
+        (None, None) => None,
+    };
+
     let opts = Opts {
         is_running_problem_test: is_running_problem_test,
         url: url_opt,
@@ -1056,6 +1120,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         unminify_js: opt_match.opt_present("unminify-js"),
         print_pwm: opt_match.opt_present("print-pwm"),
         clean_shutdown: opt_match.opt_present("clean-shutdown"),
+        network_archive,
     };
 
     set_options(opts);
@@ -190,6 +190,9 @@ mod gen {
                 gamepad: {
                     enabled: bool,
                 },
+                indexeddb: {
+                    enabled: bool,
+                },
                 microdata: {
                     testing: {
                         enabled: bool,
@@ -456,12 +459,39 @@ mod gen {
                 }
             },
             network: {
+                cookies: {
+                    #[serde(rename = "network.cookies.max-bytes")]
+                    max_bytes: i64,
+                    #[serde(rename = "network.cookies.max-count")]
+                    max_count: i64,
+                    partitioned: bool,
+                },
+                har: {
+                    enabled: bool,
+                    path: String,
+                },
                 http_cache: {
                     #[serde(rename = "network.http-cache.disabled")]
                     disabled: bool,
+                    disk: {
+                        #[serde(rename = "network.http-cache.disk.enabled")]
+                        enabled: bool,
+                        #[serde(rename = "network.http-cache.disk.max-size")]
+                        max_size: i64,
+                    }
                 },
                 mime: {
                     sniff: bool,
+                },
+                proxy: {
+                    http: String,
+                    https: String,
+                    #[serde(rename = "network.proxy.no-proxy")]
+                    no_proxy: String,
+                },
+                tls: {
+                    #[serde(rename = "network.tls.client-certificate")]
+                    client_certificate: String,
                 }
             },
             session_history: {
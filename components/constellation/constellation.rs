@@ -125,15 +125,18 @@ use layout_traits::LayoutThreadFactory;
 use log::{Level, LevelFilter, Log, Metadata, Record};
 use media::{GLPlayerThreads, WindowGLContext};
 use msg::constellation_msg::{BackgroundHangMonitorRegister, HangMonitorAlert, SamplerControlMsg};
+use msg::constellation_msg::{BroadcastChannelRouterId, MessagePortId, MessagePortRouterId};
 use msg::constellation_msg::{
     BrowsingContextGroupId, BrowsingContextId, HistoryStateId, PipelineId,
     TopLevelBrowsingContextId,
 };
 use msg::constellation_msg::{PipelineNamespace, PipelineNamespaceId, TraversalDirection};
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
 use net_traits::pub_domains::reg_host;
 use net_traits::request::RequestBuilder;
+use net_traits::request_interceptor::RequestInterceptor;
 use net_traits::storage_thread::{StorageThreadMsg, StorageType};
-use net_traits::{self, FetchResponseMsg, IpcSend, ResourceThreads};
+use net_traits::{self, CoreResourceMsg, FetchResponseMsg, IpcSend, ResourceThreads};
 use profile_traits::mem;
 use profile_traits::time;
 use script_traits::CompositorEvent::{MouseButtonEvent, MouseMoveEvent};
@@ -142,6 +145,7 @@ use script_traits::{webdriver_msg, LogEntry, ScriptToConstellationChan, ServiceW
 use script_traits::{
     AnimationState, AnimationTickType, AuxiliaryBrowsingContextLoadInfo, CompositorEvent,
 };
+use script_traits::{BroadcastMsg, MessagePortMsg, StructuredSerializedData};
 use script_traits::{
     ConstellationControlMsg, ConstellationMsg as FromCompositorMsg, DiscardBrowsingContext,
 };
@@ -203,6 +207,46 @@ struct BrowsingContextGroup {
     event_loops: HashMap<Host, Weak<EventLoop>>,
 }
 
+/// The message port router of a global that owns message ports.
+struct MessagePortRouter {
+    /// The pipeline the global belongs to.
+    pipeline_id: PipelineId,
+
+    /// The channel on which messages for the global's ports are delivered.
+    sender: IpcSender<MessagePortMsg>,
+}
+
+/// Where messages posted to a message port are delivered.
+enum TransferState {
+    /// The port is owned by the global with the given router.
+    Managed(MessagePortRouterId),
+
+    /// The port is being transferred to another global, and the messages
+    /// posted to it are buffered until it arrives.
+    TransferInProgress(VecDeque<StructuredSerializedData>),
+}
+
+/// The constellation's record of a message port.
+struct MessagePortInfo {
+    /// Where messages posted to the port are delivered.
+    state: TransferState,
+
+    /// The port this port is entangled with, if any.
+    entangled_with: Option<MessagePortId>,
+}
+
+/// The broadcast channel router of a global that uses broadcast channels.
+struct BroadcastChannelRouter {
+    /// The pipeline the global belongs to.
+    pipeline_id: PipelineId,
+
+    /// The origin of the global.
+    origin: ImmutableOrigin,
+
+    /// The channel on which messages broadcast to the global's channels are delivered.
+    sender: IpcSender<BroadcastMsg>,
+}
+
 /// The `Constellation` itself. In the servo browser, there is one
 /// constellation, which maintains all of the browser global data.
 /// In embedded applications, there may be more than one constellation,
@@ -358,6 +402,18 @@ pub struct Constellation<Message, LTF, STF> {
     /// and the namespaces are allocated by the constellation.
     next_pipeline_namespace_id: PipelineNamespaceId,
 
+    /// The message port routers of the globals that own message ports.
+    message_port_routers: HashMap<MessagePortRouterId, MessagePortRouter>,
+
+    /// The message ports that are owned by, or being transferred between, globals.
+    message_ports: HashMap<MessagePortId, MessagePortInfo>,
+
+    /// The broadcast channel routers of the globals that use broadcast channels.
+    broadcast_routers: HashMap<BroadcastChannelRouterId, BroadcastChannelRouter>,
+
+    /// For each origin, the routers subscribed to each channel name.
+    broadcast_channels: HashMap<ImmutableOrigin, HashMap<String, Vec<BroadcastChannelRouterId>>>,
+
     /// The size of the top-level window.
     window_size: WindowSizeData,
 
@@ -741,6 +797,10 @@ where
                     // We initialize the namespace at 2, since we reserved
                     // namespace 0 for the embedder, and 0 for the constellation
                     next_pipeline_namespace_id: PipelineNamespaceId(2),
+                    message_port_routers: HashMap::new(),
+                    message_ports: HashMap::new(),
+                    broadcast_routers: HashMap::new(),
+                    broadcast_channels: HashMap::new(),
                     time_profiler_chan: state.time_profiler_chan,
                     mem_profiler_chan: state.mem_profiler_chan,
                     window_size: WindowSizeData {
@@ -1438,6 +1498,15 @@ where
             FromCompositorMsg::ExitFullScreen(top_level_browsing_context_id) => {
                 self.handle_exit_fullscreen_msg(top_level_browsing_context_id);
             },
+            FromCompositorMsg::SetRequestInterceptor(interceptor) => {
+                self.handle_set_request_interceptor(interceptor);
+            },
+            FromCompositorMsg::SaveHar(path) => {
+                let msg = CoreResourceMsg::SaveHar(path);
+                if let Err(e) = self.public_resource_threads.send(msg) {
+                    warn!("Sending HAR export to resource thread failed ({})", e);
+                }
+            },
         }
     }
 
@@ -1538,6 +1607,57 @@ where
             } => {
                 self.handle_post_message_msg(browsing_context_id, source_pipeline_id, origin, data);
             },
+            FromScriptMsg::NewMessagePortRouter(router_id, sender) => {
+                self.handle_new_message_port_router(source_pipeline_id, router_id, sender);
+            },
+            FromScriptMsg::RemoveMessagePortRouter(router_id) => {
+                self.handle_remove_message_port_router(router_id);
+            },
+            FromScriptMsg::NewMessagePort(router_id, port_id) => {
+                self.handle_new_message_port(router_id, port_id);
+            },
+            FromScriptMsg::EntanglePorts(port1, port2) => {
+                self.handle_entangle_ports(port1, port2);
+            },
+            FromScriptMsg::MessagePortShipped(port_id) => {
+                self.handle_message_port_shipped(port_id);
+            },
+            FromScriptMsg::PostMessageToEntangledPort(port_id, data) => {
+                match self
+                    .message_ports
+                    .get(&port_id)
+                    .and_then(|info| info.entangled_with)
+                {
+                    Some(entangled_id) => self.route_message_to_port(entangled_id, data),
+                    None => warn!("Message posted to disentangled port {}.", port_id),
+                }
+            },
+            FromScriptMsg::RerouteMessagePort(port_id, data) => {
+                self.route_message_to_port(port_id, data);
+            },
+            FromScriptMsg::RemoveMessagePort(port_id) => {
+                self.handle_remove_message_port(port_id);
+            },
+            FromScriptMsg::NewBroadcastChannelRouter(router_id, sender, origin) => {
+                self.handle_new_broadcast_channel_router(
+                    source_pipeline_id,
+                    router_id,
+                    sender,
+                    origin,
+                );
+            },
+            FromScriptMsg::RemoveBroadcastChannelRouter(router_id) => {
+                self.handle_remove_broadcast_channel_router(router_id);
+            },
+            FromScriptMsg::NewBroadcastChannelNameInRouter(router_id, channel_name) => {
+                self.handle_new_broadcast_channel_name_in_router(router_id, channel_name);
+            },
+            FromScriptMsg::RemoveBroadcastChannelNameInRouter(router_id, channel_name) => {
+                self.handle_remove_broadcast_channel_name_in_router(router_id, &channel_name);
+            },
+            FromScriptMsg::ScheduleBroadcast(router_id, msg) => {
+                self.handle_schedule_broadcast(router_id, msg);
+            },
             FromScriptMsg::Focus => {
                 self.handle_focus_msg(source_pipeline_id);
             },
@@ -1599,6 +1719,12 @@ where
                     );
                 }
             },
+            FromScriptMsg::GetPipelineNamespaceId(sender) => {
+                let namespace_id = self.next_pipeline_namespace_id();
+                if let Err(e) = sender.send(namespace_id) {
+                    warn!("Sending reply to get pipeline namespace failed ({:?}).", e);
+                }
+            },
             FromScriptMsg::GetChildBrowsingContextId(browsing_context_id, index, sender) => {
                 let result = self
                     .browsing_contexts
@@ -1763,6 +1889,9 @@ where
         let (core_sender, core_receiver) = ipc::channel().expect("Failed to create IPC channel!");
         let (storage_sender, storage_receiver) =
             ipc::channel().expect("Failed to create IPC channel!");
+        let (idb_sender, idb_receiver) = ipc::channel().expect("Failed to create IPC channel!");
+        let (private_idb_sender, private_idb_receiver) =
+            ipc::channel().expect("Failed to create IPC channel!");
 
         debug!("Exiting core resource threads.");
         if let Err(e) = self
@@ -1792,6 +1921,20 @@ where
             warn!("Exit storage thread failed ({})", e);
         }
 
+        debug!("Exiting IndexedDB threads.");
+        if let Err(e) = self
+            .public_resource_threads
+            .send(IndexedDBThreadMsg::Exit(idb_sender))
+        {
+            warn!("Exit IndexedDB thread failed ({})", e);
+        }
+        if let Err(e) = self
+            .private_resource_threads
+            .send(IndexedDBThreadMsg::Exit(private_idb_sender))
+        {
+            warn!("Exit private IndexedDB thread failed ({})", e);
+        }
+
         debug!("Exiting bluetooth thread.");
         if let Err(e) = self.bluetooth_thread.send(BluetoothRequest::Exit) {
             warn!("Exit bluetooth thread failed ({})", e);
@@ -1845,6 +1988,12 @@ where
         if let Err(e) = storage_receiver.recv() {
             warn!("Exit storage thread failed ({})", e);
         }
+        if let Err(e) = idb_receiver.recv() {
+            warn!("Exit IndexedDB thread failed ({})", e);
+        }
+        if let Err(e) = private_idb_receiver.recv() {
+            warn!("Exit private IndexedDB thread failed ({})", e);
+        }
 
         debug!("Asking compositor to complete shutdown.");
         self.compositor_proxy
@@ -1854,6 +2003,241 @@ where
     fn handle_pipeline_exited(&mut self, pipeline_id: PipelineId) {
         debug!("Pipeline {:?} exited.", pipeline_id);
         self.pipelines.remove(&pipeline_id);
+
+        // The globals of the pipeline are gone, along with the ports they owned.
+        let router_ids: Vec<MessagePortRouterId> = self
+            .message_port_routers
+            .iter()
+            .filter(|(_, router)| router.pipeline_id == pipeline_id)
+            .map(|(router_id, _)| *router_id)
+            .collect();
+        for router_id in router_ids {
+            self.handle_remove_message_port_router(router_id);
+        }
+        let router_ids: Vec<BroadcastChannelRouterId> = self
+            .broadcast_routers
+            .iter()
+            .filter(|(_, router)| router.pipeline_id == pipeline_id)
+            .map(|(router_id, _)| *router_id)
+            .collect();
+        for router_id in router_ids {
+            self.handle_remove_broadcast_channel_router(router_id);
+        }
+    }
+
+    fn handle_new_message_port_router(
+        &mut self,
+        pipeline_id: PipelineId,
+        router_id: MessagePortRouterId,
+        sender: IpcSender<MessagePortMsg>,
+    ) {
+        self.message_port_routers.insert(
+            router_id,
+            MessagePortRouter {
+                pipeline_id,
+                sender,
+            },
+        );
+    }
+
+    fn handle_remove_message_port_router(&mut self, router_id: MessagePortRouterId) {
+        if self.message_port_routers.remove(&router_id).is_none() {
+            return warn!("Removing unknown message port router {}.", router_id);
+        }
+        let port_ids: Vec<MessagePortId> = self
+            .message_ports
+            .iter()
+            .filter(|(_, info)| match info.state {
+                TransferState::Managed(id) => id == router_id,
+                TransferState::TransferInProgress(_) => false,
+            })
+            .map(|(port_id, _)| *port_id)
+            .collect();
+        for port_id in port_ids {
+            self.handle_remove_message_port(port_id);
+        }
+    }
+
+    fn handle_new_message_port(&mut self, router_id: MessagePortRouterId, port_id: MessagePortId) {
+        let info = self
+            .message_ports
+            .entry(port_id)
+            .or_insert_with(|| MessagePortInfo {
+                state: TransferState::TransferInProgress(VecDeque::new()),
+                entangled_with: None,
+            });
+        let buffer = match replace(&mut info.state, TransferState::Managed(router_id)) {
+            TransferState::TransferInProgress(buffer) => buffer,
+            TransferState::Managed(_) => {
+                return warn!("Message port {} is already owned by a global.", port_id);
+            },
+        };
+        // Deliver the messages posted while the port was being transferred.
+        for data in buffer {
+            self.route_message_to_port(port_id, data);
+        }
+    }
+
+    fn handle_entangle_ports(&mut self, port1: MessagePortId, port2: MessagePortId) {
+        if !self.message_ports.contains_key(&port1) || !self.message_ports.contains_key(&port2) {
+            return warn!("Entangling unknown message ports {} and {}.", port1, port2);
+        }
+        for (port_id, entangled_id) in &[(port1, port2), (port2, port1)] {
+            if let Some(info) = self.message_ports.get_mut(port_id) {
+                info.entangled_with = Some(*entangled_id);
+            }
+        }
+    }
+
+    fn handle_message_port_shipped(&mut self, port_id: MessagePortId) {
+        match self.message_ports.get_mut(&port_id) {
+            Some(info) => {
+                info.state = TransferState::TransferInProgress(VecDeque::new());
+            },
+            None => warn!("Shipping unknown message port {}.", port_id),
+        }
+    }
+
+    /// Deliver a message to the global owning a port, or buffer it if the port is
+    /// being transferred.
+    fn route_message_to_port(&mut self, port_id: MessagePortId, data: StructuredSerializedData) {
+        let router_id = match self.message_ports.get_mut(&port_id) {
+            None => return warn!("Message posted to closed port {}.", port_id),
+            Some(info) => match info.state {
+                TransferState::Managed(router_id) => router_id,
+                TransferState::TransferInProgress(ref mut buffer) => {
+                    return buffer.push_back(data);
+                },
+            },
+        };
+        let result = match self.message_port_routers.get(&router_id) {
+            Some(router) => router.sender.send(MessagePortMsg::NewTask(port_id, data)),
+            None => return warn!("Message posted to port {} of closed global.", port_id),
+        };
+        if let Err(e) = result {
+            warn!("Failed to deliver message to port {} ({:?}).", port_id, e);
+        }
+    }
+
+    fn handle_remove_message_port(&mut self, port_id: MessagePortId) {
+        let entangled_id = match self.message_ports.remove(&port_id) {
+            Some(info) => info.entangled_with,
+            None => return,
+        };
+        if let Some(info) = entangled_id.and_then(|id| self.message_ports.get_mut(&id)) {
+            info.entangled_with = None;
+        }
+    }
+
+    fn handle_new_broadcast_channel_router(
+        &mut self,
+        pipeline_id: PipelineId,
+        router_id: BroadcastChannelRouterId,
+        sender: IpcSender<BroadcastMsg>,
+        origin: ImmutableOrigin,
+    ) {
+        self.broadcast_routers.insert(
+            router_id,
+            BroadcastChannelRouter {
+                pipeline_id,
+                origin,
+                sender,
+            },
+        );
+    }
+
+    fn handle_remove_broadcast_channel_router(&mut self, router_id: BroadcastChannelRouterId) {
+        let origin = match self.broadcast_routers.remove(&router_id) {
+            Some(router) => router.origin,
+            None => return warn!("Removing unknown broadcast channel router {}.", router_id),
+        };
+        if let Some(channels) = self.broadcast_channels.get_mut(&origin) {
+            for routers in channels.values_mut() {
+                routers.retain(|id| *id != router_id);
+            }
+            channels.retain(|_, routers| !routers.is_empty());
+            if channels.is_empty() {
+                self.broadcast_channels.remove(&origin);
+            }
+        }
+    }
+
+    fn handle_new_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        channel_name: String,
+    ) {
+        let origin = match self.broadcast_routers.get(&router_id) {
+            Some(router) => router.origin.clone(),
+            None => return warn!("Subscribing unknown broadcast router {}.", router_id),
+        };
+        let routers = self
+            .broadcast_channels
+            .entry(origin)
+            .or_insert_with(HashMap::new)
+            .entry(channel_name)
+            .or_insert_with(Vec::new);
+        if !routers.contains(&router_id) {
+            routers.push(router_id);
+        }
+    }
+
+    fn handle_remove_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        channel_name: &str,
+    ) {
+        let origin = match self.broadcast_routers.get(&router_id) {
+            Some(router) => router.origin.clone(),
+            None => return warn!("Unsubscribing unknown broadcast router {}.", router_id),
+        };
+        let channels = match self.broadcast_channels.get_mut(&origin) {
+            Some(channels) => channels,
+            None => return,
+        };
+        let now_empty = match channels.get_mut(channel_name) {
+            Some(routers) => {
+                routers.retain(|id| *id != router_id);
+                routers.is_empty()
+            },
+            None => return,
+        };
+        if now_empty {
+            channels.remove(channel_name);
+        }
+        if channels.is_empty() {
+            self.broadcast_channels.remove(&origin);
+        }
+    }
+
+    /// Deliver a broadcast message to every other global subscribed to its channel.
+    fn handle_schedule_broadcast(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        msg: BroadcastMsg,
+    ) {
+        match self.broadcast_routers.get(&router_id) {
+            Some(router) if router.origin == msg.origin => {},
+            Some(_) => return warn!("Broadcast from router {} for another origin.", router_id),
+            None => return warn!("Broadcast from unknown router {}.", router_id),
+        }
+        let routers = match self
+            .broadcast_channels
+            .get(&msg.origin)
+            .and_then(|channels| channels.get(&msg.channel_name))
+        {
+            Some(routers) => routers,
+            None => return,
+        };
+        for id in routers.iter().filter(|id| **id != router_id) {
+            let result = match self.broadcast_routers.get(id) {
+                Some(router) => router.sender.send(msg.clone()),
+                None => continue,
+            };
+            if let Err(e) = result {
+                warn!("Failed to broadcast to router {} ({:?}).", id, e);
+            }
+        }
     }
 
     fn handle_send_error(&mut self, pipeline_id: PipelineId, err: IpcError) {
@@ -3174,7 +3558,7 @@ where
         browsing_context_id: BrowsingContextId,
         source_pipeline: PipelineId,
         origin: Option<ImmutableOrigin>,
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     ) {
         let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
             None => {
@@ -3930,6 +4314,23 @@ where
         self.switch_fullscreen_mode(browsing_context_id);
     }
 
+    /// Forward the embedder's request interceptor to both the public and private resource
+    /// threads, so that it applies to every browsing context.
+    fn handle_set_request_interceptor(&mut self, interceptor: Option<RequestInterceptor>) {
+        for resource_threads in &[
+            &self.public_resource_threads,
+            &self.private_resource_threads,
+        ] {
+            let msg = CoreResourceMsg::SetRequestInterceptor(interceptor.clone());
+            if let Err(e) = resource_threads.send(msg) {
+                warn!(
+                    "Sending request interceptor to resource thread failed ({})",
+                    e
+                );
+            }
+        }
+    }
+
     /// Handle updating actual viewport / zoom due to @viewport rules
     fn handle_viewport_constrained_msg(
         &mut self,
@@ -126,12 +126,15 @@ pub enum EmbedderMsg {
     BrowserCreated(TopLevelBrowsingContextId),
     /// Wether or not to unload a document
     AllowUnload(IpcSender<bool>),
+    /// Whether or not to trust the certificate of a host despite the given verification
+    /// error, for this and later connections to that host.
+    AllowCertificateException(String, String, IpcSender<bool>),
     /// Sends an unconsumed key event back to the embedder.
     Keyboard(KeyboardEvent),
     /// Gets system clipboard contents
-    GetClipboardContents(IpcSender<String>),
+    GetClipboardContents(IpcSender<ClipboardContents>),
     /// Sets system clipboard contents
-    SetClipboardContents(String),
+    SetClipboardContents(ClipboardContents),
     /// Changes the cursor.
     SetCursor(Cursor),
     /// A favicon was detected
@@ -173,6 +176,7 @@ impl Debug for EmbedderMsg {
             EmbedderMsg::ResizeTo(..) => write!(f, "ResizeTo"),
             EmbedderMsg::Alert(..) => write!(f, "Alert"),
             EmbedderMsg::AllowUnload(..) => write!(f, "AllowUnload"),
+            EmbedderMsg::AllowCertificateException(..) => write!(f, "AllowCertificateException"),
             EmbedderMsg::AllowNavigationRequest(..) => write!(f, "AllowNavigationRequest"),
             EmbedderMsg::Keyboard(..) => write!(f, "Keyboard"),
             EmbedderMsg::GetClipboardContents(..) => write!(f, "GetClipboardContents"),
@@ -202,3 +206,48 @@ impl Debug for EmbedderMsg {
 /// the `String` content is expected to be extension (e.g, "doc", without the prefixing ".")
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FilterPattern(pub String);
+
+/// The contents of the system clipboard: the same data in one or more formats,
+/// each identified by its MIME type, e.g. `text/plain`, `text/html` or
+/// `image/png`, in order of preference.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ClipboardContents {
+    pub items: Vec<(String, Vec<u8>)>,
+}
+
+impl ClipboardContents {
+    pub fn from_text(text: String) -> ClipboardContents {
+        ClipboardContents {
+            items: vec![("text/plain".to_owned(), text.into_bytes())],
+        }
+    }
+
+    /// The data of the given format, if the clipboard has it.
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.items
+            .iter()
+            .find(|&&(ref item_type, _)| item_type.eq_ignore_ascii_case(mime_type))
+            .map(|&(_, ref data)| &**data)
+    }
+
+    /// The plain text on the clipboard, if any.
+    pub fn text(&self) -> Option<String> {
+        self.get("text/plain")
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+    }
+}
+
+/// Data dragged from another application, e.g. the system's file manager, and
+/// dropped onto a page.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DragData {
+    /// The dragged strings, each with its MIME type.
+    pub items: Vec<(String, String)>,
+    pub files: Vec<DraggedFile>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DraggedFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
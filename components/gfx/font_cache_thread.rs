@@ -13,6 +13,7 @@ use crate::platform::font_list::SANS_SERIF_FONT_FAMILY;
 use crate::platform::font_template::FontTemplateData;
 use app_units::Au;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::content_security_policy::CspList;
 use net_traits::request::{Destination, RequestBuilder};
 use net_traits::{fetch_async, CoreResourceThread, FetchResponseMsg};
 use servo_atoms::Atom;
@@ -113,7 +114,12 @@ pub enum Command {
         Au,
         IpcSender<webrender_api::FontInstanceKey>,
     ),
-    AddWebFont(LowercaseString, EffectiveSources, IpcSender<()>),
+    AddWebFont(
+        LowercaseString,
+        EffectiveSources,
+        Option<CspList>,
+        IpcSender<()>,
+    ),
     AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, IpcSender<()>),
     Exit(IpcSender<()>),
     Ping,
@@ -195,8 +201,8 @@ impl FontCache {
 
                     let _ = result.send(instance_key);
                 },
-                Command::AddWebFont(family_name, sources, result) => {
-                    self.handle_add_web_font(family_name, sources, result);
+                Command::AddWebFont(family_name, sources, csp_list, result) => {
+                    self.handle_add_web_font(family_name, sources, csp_list, result);
                 },
                 Command::AddDownloadedWebFont(family_name, url, bytes, result) => {
                     let templates = &mut self.web_families.get_mut(&family_name).unwrap();
@@ -216,6 +222,7 @@ impl FontCache {
         &mut self,
         family_name: LowercaseString,
         mut sources: EffectiveSources,
+        csp_list: Option<CspList>,
         sender: IpcSender<()>,
     ) {
         let src = if let Some(src) = sources.next() {
@@ -238,7 +245,9 @@ impl FontCache {
                     None => return,
                 };
 
-                let request = RequestBuilder::new(url.clone()).destination(Destination::Font);
+                let request = RequestBuilder::new(url.clone())
+                    .destination(Destination::Font)
+                    .csp_list(csp_list.clone());
 
                 let channel_to_self = self.channel_to_self.clone();
                 let bytes = Mutex::new(Vec::new());
@@ -247,7 +256,8 @@ impl FontCache {
                 fetch_async(request, &self.core_resource_thread, move |response| {
                     match response {
                         FetchResponseMsg::ProcessRequestBody |
-                        FetchResponseMsg::ProcessRequestEOF => (),
+                        FetchResponseMsg::ProcessRequestEOF |
+                        FetchResponseMsg::ProcessCspViolations(..) => (),
                         FetchResponseMsg::ProcessResponse(meta_result) => {
                             trace!(
                                 "@font-face {} metadata ok={:?}",
@@ -268,6 +278,7 @@ impl FontCache {
                                 let msg = Command::AddWebFont(
                                     family_name.clone(),
                                     sources.clone(),
+                                    csp_list.clone(),
                                     sender.clone(),
                                 );
                                 channel_to_self.send(msg).unwrap();
@@ -287,6 +298,7 @@ impl FontCache {
                                     let msg = Command::AddWebFont(
                                         family_name.clone(),
                                         sources.clone(),
+                                        csp_list.clone(),
                                         sender.clone(),
                                     );
                                     channel_to_self.send(msg).unwrap();
@@ -315,7 +327,7 @@ impl FontCache {
                 if found {
                     sender.send(()).unwrap();
                 } else {
-                    let msg = Command::AddWebFont(family_name, sources, sender);
+                    let msg = Command::AddWebFont(family_name, sources, csp_list, sender);
                     self.channel_to_self.send(msg).unwrap();
                 }
             },
@@ -477,12 +489,14 @@ impl FontCacheThread {
         &self,
         family: FamilyName,
         sources: EffectiveSources,
+        csp_list: Option<CspList>,
         sender: IpcSender<()>,
     ) {
         self.chan
             .send(Command::AddWebFont(
                 LowercaseString::new(&family.name),
                 sources,
+                csp_list,
                 sender,
             ))
             .unwrap();
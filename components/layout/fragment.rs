@@ -2773,7 +2773,7 @@ impl Fragment {
         }
 
         match self.style().get_box().display {
-            Display::Flex => self.style().get_position().z_index.integer_or(0),
+            Display::Flex | Display::Grid => self.style().get_position().z_index.integer_or(0),
             _ => 0,
         }
     }
@@ -0,0 +1,1767 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Layout for elements with a CSS `display` property of `grid`.
+
+use crate::block::{AbsoluteAssignBSizesTraversal, BlockFlow};
+use crate::context::LayoutContext;
+use crate::display_list::{
+    BorderPaintingMode, DisplayListBuildState, StackingContextCollectionState,
+};
+use crate::floats::FloatKind;
+use crate::flow::{Flow, FlowClass, FlowFlags, GetBaseFlow, OpaqueFlow};
+use crate::fragment::{Fragment, FragmentBorderBoxIterator, Overflow};
+use crate::layout_debug;
+use crate::model::{self, AdjoiningMargins, CollapsibleMargins};
+use crate::traversal::PreorderFlowTraversal;
+use app_units::{Au, MAX_AU};
+use euclid::default::Point2D;
+use servo_atoms::Atom;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use style::computed_values::align_content::T as AlignContent;
+use style::computed_values::align_items::T as AlignItems;
+use style::computed_values::align_self::T as AlignSelf;
+use style::computed_values::justify_content::T as JustifyContent;
+use style::computed_values::justify_items::T as JustifyItems;
+use style::computed_values::justify_self::T as JustifySelf;
+use style::logical_geometry::{Direction, LogicalSize};
+use style::properties::ComputedValues;
+use style::servo::restyle_damage::ServoRestyleDamage;
+use style::values::computed::length::NonNegativeLengthPercentageOrNormal;
+use style::values::computed::{GridLine, GridTemplateAreas, GridTemplateComponent};
+use style::values::computed::{Integer, LengthPercentage, MaxSize, Size};
+use style::values::generics::grid::{RepeatCount, TrackBreadth, TrackListType};
+use style::values::generics::grid::{TrackListValue, TrackRepeat, TrackSize, MAX_GRID_LINE};
+use style::values::specified::position::AutoFlow;
+use style::values::CustomIdent;
+
+/// A sizing function of a grid track, with its percentages resolved.
+#[derive(Clone, Copy, Debug, Serialize)]
+enum SizingFunction {
+    Fixed(Au),
+    Flexible(f32),
+    Auto,
+    MinContent,
+    MaxContent,
+    /// `fit-content()`, which is only ever a maximum sizing function.
+    FitContent(Au),
+}
+
+impl SizingFunction {
+    /// Resolves a `<track-breadth>` against the given size of the grid container.
+    /// Percentages of an indefinite size behave as `auto`.
+    fn new(breadth: &TrackBreadth<LengthPercentage>, basis: Option<Au>) -> SizingFunction {
+        match *breadth {
+            TrackBreadth::Breadth(ref length) => match length.maybe_to_used_value(basis) {
+                Some(length) => SizingFunction::Fixed(length),
+                None => SizingFunction::Auto,
+            },
+            TrackBreadth::Fr(flex) => SizingFunction::Flexible(flex),
+            TrackBreadth::Auto => SizingFunction::Auto,
+            TrackBreadth::MinContent => SizingFunction::MinContent,
+            TrackBreadth::MaxContent => SizingFunction::MaxContent,
+        }
+    }
+
+    fn is_intrinsic(&self) -> bool {
+        match *self {
+            SizingFunction::Fixed(_) | SizingFunction::Flexible(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// A row or a column of the grid.
+#[derive(Debug, Serialize)]
+struct GridTrack {
+    /// The minimum track sizing function.
+    min: SizingFunction,
+    /// The maximum track sizing function.
+    max: SizingFunction,
+    /// The used size of the track.
+    base_size: Au,
+    /// The size the track may grow to before flexible tracks are expanded, or `None` if
+    /// it is infinite.
+    growth_limit: Option<Au>,
+    /// The offset of the track from the start of the content box of the grid container.
+    position: Au,
+    /// True if this is an empty track created by `repeat(auto-fit, ...)`, which collapses
+    /// along with its gutters.
+    is_collapsed: bool,
+}
+
+impl GridTrack {
+    fn new(size: &TrackSize<LengthPercentage>, basis: Option<Au>) -> GridTrack {
+        let (min, max) = match *size {
+            TrackSize::Breadth(ref breadth) => {
+                let breadth = SizingFunction::new(breadth, basis);
+                (breadth, breadth)
+            },
+            TrackSize::Minmax(ref min, ref max) => (
+                SizingFunction::new(min, basis),
+                SizingFunction::new(max, basis),
+            ),
+            TrackSize::FitContent(ref limit) => {
+                let max = match SizingFunction::new(limit, basis) {
+                    SizingFunction::Fixed(limit) => SizingFunction::FitContent(limit),
+                    _ => SizingFunction::MaxContent,
+                };
+                (SizingFunction::Auto, max)
+            },
+        };
+        // A flexible minimum sizing function is treated as `auto`.
+        // https://drafts.csswg.org/css-grid/#valdef-grid-template-columns-minmax
+        let min = match min {
+            SizingFunction::Flexible(_) => SizingFunction::Auto,
+            min => min,
+        };
+        let base_size = match min {
+            SizingFunction::Fixed(length) => length,
+            _ => Au(0),
+        };
+        let growth_limit = match max {
+            SizingFunction::Fixed(length) => Some(max(length, base_size)),
+            _ => None,
+        };
+        GridTrack {
+            min: min,
+            max: max,
+            base_size: base_size,
+            growth_limit: growth_limit,
+            position: Au(0),
+            is_collapsed: false,
+        }
+    }
+
+    fn collapse(&mut self) {
+        self.min = SizingFunction::Fixed(Au(0));
+        self.max = SizingFunction::Fixed(Au(0));
+        self.base_size = Au(0);
+        self.growth_limit = Some(Au(0));
+        self.is_collapsed = true;
+    }
+
+    fn flex_factor(&self) -> Option<f32> {
+        match self.max {
+            SizingFunction::Flexible(flex) => Some(flex),
+            _ => None,
+        }
+    }
+
+    /// The size this track needs to fit the given contribution with its minimum sizing
+    /// function, if that is intrinsic.
+    fn min_content_target(&self, contribution: &Contribution) -> Option<Au> {
+        match self.min {
+            SizingFunction::Auto | SizingFunction::MinContent => Some(contribution.min_content),
+            SizingFunction::MaxContent => Some(contribution.max_content),
+            _ => None,
+        }
+    }
+
+    /// The size this track needs to fit the given contribution with its maximum sizing
+    /// function, if that is intrinsic.
+    fn max_content_target(&self, contribution: &Contribution) -> Option<Au> {
+        match self.max {
+            SizingFunction::MinContent => Some(contribution.min_content),
+            SizingFunction::Auto | SizingFunction::MaxContent => Some(contribution.max_content),
+            SizingFunction::FitContent(limit) => Some(max(
+                contribution.min_content,
+                min(contribution.max_content, limit),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The outer sizes of a grid item in one axis.
+struct Contribution {
+    /// The tracks spanned by the item.
+    span: Range<usize>,
+    min_content: Au,
+    max_content: Au,
+}
+
+/// The space available to the tracks of the grid in one axis.
+#[derive(Clone, Copy)]
+enum AvailableSpace {
+    Definite(Au),
+    MinContent,
+    MaxContent,
+}
+
+/// How the tracks of the grid are distributed in the content box of the grid container,
+/// as given by `justify-content` or `align-content`.
+#[derive(Clone, Copy, PartialEq)]
+enum ContentAlignment {
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    Stretch,
+}
+
+impl From<JustifyContent> for ContentAlignment {
+    fn from(justify_content: JustifyContent) -> ContentAlignment {
+        match justify_content {
+            JustifyContent::FlexStart => ContentAlignment::Start,
+            JustifyContent::FlexEnd => ContentAlignment::End,
+            JustifyContent::Center => ContentAlignment::Center,
+            JustifyContent::SpaceBetween => ContentAlignment::SpaceBetween,
+            JustifyContent::SpaceAround => ContentAlignment::SpaceAround,
+            JustifyContent::Stretch => ContentAlignment::Stretch,
+        }
+    }
+}
+
+impl From<AlignContent> for ContentAlignment {
+    fn from(align_content: AlignContent) -> ContentAlignment {
+        match align_content {
+            AlignContent::FlexStart => ContentAlignment::Start,
+            AlignContent::FlexEnd => ContentAlignment::End,
+            AlignContent::Center => ContentAlignment::Center,
+            AlignContent::SpaceBetween => ContentAlignment::SpaceBetween,
+            AlignContent::SpaceAround => ContentAlignment::SpaceAround,
+            AlignContent::Stretch => ContentAlignment::Stretch,
+        }
+    }
+}
+
+/// How a grid item is aligned in its grid area, as given by `justify-self` or `align-self`.
+#[derive(Clone, Copy, PartialEq)]
+enum ItemAlignment {
+    Stretch,
+    Start,
+    End,
+    Center,
+}
+
+impl ItemAlignment {
+    fn justify(style: &ComputedValues, justify_items: JustifyItems) -> ItemAlignment {
+        let justify_items = match style.get_position().justify_self {
+            JustifySelf::Auto => justify_items,
+            JustifySelf::Stretch => JustifyItems::Stretch,
+            JustifySelf::Start => JustifyItems::Start,
+            JustifySelf::End => JustifyItems::End,
+            JustifySelf::Center => JustifyItems::Center,
+        };
+        match justify_items {
+            JustifyItems::Stretch => ItemAlignment::Stretch,
+            JustifyItems::Start => ItemAlignment::Start,
+            JustifyItems::End => ItemAlignment::End,
+            JustifyItems::Center => ItemAlignment::Center,
+        }
+    }
+
+    fn align(style: &ComputedValues, align_items: AlignItems) -> ItemAlignment {
+        let align_items = match style.get_position().align_self {
+            AlignSelf::Auto => align_items,
+            AlignSelf::Stretch => AlignItems::Stretch,
+            AlignSelf::FlexStart => AlignItems::FlexStart,
+            AlignSelf::FlexEnd => AlignItems::FlexEnd,
+            AlignSelf::Center => AlignItems::Center,
+            AlignSelf::Baseline => AlignItems::Baseline,
+        };
+        // TODO: support baseline alignment.
+        match align_items {
+            AlignItems::Stretch => ItemAlignment::Stretch,
+            AlignItems::FlexStart | AlignItems::Baseline => ItemAlignment::Start,
+            AlignItems::FlexEnd => ItemAlignment::End,
+            AlignItems::Center => ItemAlignment::Center,
+        }
+    }
+}
+
+/// The explicit grid in one axis, as defined by the `grid-template-*` properties.
+struct ExplicitGrid {
+    /// The sizing functions of the explicit tracks.
+    track_sizes: Vec<TrackSize<LengthPercentage>>,
+    /// The names of each line of the explicit grid.
+    line_names: Vec<Vec<Atom>>,
+    /// The line names that will be given to the next line.
+    pending_line_names: Vec<Atom>,
+    /// The tracks generated by `repeat(auto-fit, ...)`, which collapse when empty.
+    auto_fit_tracks: Range<usize>,
+}
+
+impl ExplicitGrid {
+    /// Builds the explicit grid from a `grid-template-rows` or `grid-template-columns`
+    /// value, and the number of rows or columns of `grid-template-areas`.
+    ///
+    /// The available size is used to determine the number of repetitions of
+    /// `repeat(auto-fill, ...)` and `repeat(auto-fit, ...)`.
+    fn new(
+        template: &GridTemplateComponent,
+        area_track_count: usize,
+        implicit_track_size: &TrackSize<LengthPercentage>,
+        available_size: Option<Au>,
+        gap: Au,
+    ) -> ExplicitGrid {
+        let mut grid = ExplicitGrid {
+            track_sizes: vec![],
+            line_names: vec![],
+            pending_line_names: vec![],
+            auto_fit_tracks: 0..0,
+        };
+
+        // TODO: support subgrids.
+        if let GridTemplateComponent::TrackList(ref list) = *template {
+            let auto_repeat_index = match list.list_type {
+                TrackListType::Auto(index) => Some(index as usize),
+                _ => None,
+            };
+            let mut line_names = list.line_names.iter();
+            for index in 0..list.values.len() + 1 {
+                if let (Some(repeat), true) = (&list.auto_repeat, auto_repeat_index == Some(index))
+                {
+                    if let Some(names) = line_names.next() {
+                        grid.add_line_names(names);
+                    }
+                    let start = grid.track_sizes.len();
+                    let count = auto_repetitions(&list.values, repeat, available_size, gap);
+                    grid.add_repeat(repeat, count);
+                    if repeat.count == RepeatCount::AutoFit {
+                        grid.auto_fit_tracks = start..grid.track_sizes.len();
+                    }
+                }
+                if let Some(names) = line_names.next() {
+                    grid.add_line_names(names);
+                }
+                match list.values.get(index) {
+                    Some(&TrackListValue::TrackSize(ref size)) => grid.add_track(size),
+                    Some(&TrackListValue::TrackRepeat(ref repeat)) => {
+                        let count = match repeat.count {
+                            RepeatCount::Number(count) => max(count, 1) as usize,
+                            _ => 1,
+                        };
+                        grid.add_repeat(repeat, count);
+                    },
+                    None => {},
+                }
+            }
+            for names in line_names {
+                grid.add_line_names(names);
+            }
+        }
+
+        // The tracks of the areas that don't have a size are sized as implicit tracks.
+        while grid.track_sizes.len() < area_track_count {
+            grid.add_track(implicit_track_size);
+        }
+        let names = mem::replace(&mut grid.pending_line_names, vec![]);
+        grid.line_names.push(names);
+        grid
+    }
+
+    fn add_line_names(&mut self, names: &[CustomIdent]) {
+        self.pending_line_names
+            .extend(names.iter().map(|name| name.0.clone()));
+    }
+
+    fn add_track(&mut self, size: &TrackSize<LengthPercentage>) {
+        let names = mem::replace(&mut self.pending_line_names, vec![]);
+        self.line_names.push(names);
+        self.track_sizes.push(size.clone());
+    }
+
+    fn add_repeat(&mut self, repeat: &TrackRepeat<LengthPercentage, Integer>, count: usize) {
+        for _ in 0..count {
+            for (size, names) in repeat.track_sizes.iter().zip(repeat.line_names.iter()) {
+                self.add_line_names(names);
+                self.add_track(size);
+            }
+            if let Some(names) = repeat.line_names.get(repeat.track_sizes.len()) {
+                self.add_line_names(names);
+            }
+        }
+    }
+
+    /// Gives the lines at the edges of the named areas of `grid-template-areas` the implicit
+    /// names `<area>-start` and `<area>-end`.
+    ///
+    /// <https://drafts.csswg.org/css-grid/#implicit-named-lines>
+    fn add_area_line_names(&mut self, areas: &GridTemplateAreas, is_row: bool) {
+        let areas = match *areas {
+            GridTemplateAreas::Areas(ref areas) => areas,
+            GridTemplateAreas::None => return,
+        };
+        for area in areas.0.areas.iter() {
+            let range = if is_row { &area.rows } else { &area.columns };
+            let start = Atom::from(format!("{}-start", area.name));
+            let end = Atom::from(format!("{}-end", area.name));
+            self.line_names[range.start as usize].push(start);
+            self.line_names[range.end as usize].push(end);
+        }
+    }
+
+    fn track_count(&self) -> i32 {
+        self.track_sizes.len() as i32
+    }
+
+    /// Whether the given line has the given name. Lines outside of the explicit grid are
+    /// assumed to have every name.
+    fn line_has_name(&self, line: i32, name: &Atom) -> bool {
+        line < 0 || line > self.track_count() || self.line_names[line as usize].contains(name)
+    }
+
+    /// Resolves a `<grid-line>` which is neither `auto` nor a span to the index of a line.
+    /// Negative indices are lines of the implicit grid before the explicit grid.
+    ///
+    /// <https://drafts.csswg.org/css-grid/#line-placement>
+    fn resolve_line(&self, line: &GridLine, side: &str) -> i32 {
+        let track_count = self.track_count();
+        if line.ident.is_empty() {
+            return if line.line_num > 0 {
+                line.line_num - 1
+            } else {
+                track_count + 1 + line.line_num
+            };
+        }
+
+        // A lone name first refers to the edge of the named area.
+        if line.line_num == 0 {
+            let edge_name = Atom::from(format!("{}-{}", line.ident, side));
+            if let Some(index) = self
+                .line_names
+                .iter()
+                .position(|names| names.contains(&edge_name))
+            {
+                return index as i32;
+            }
+        }
+
+        let nth = if line.line_num == 0 { 1 } else { line.line_num };
+        let named_lines: Vec<i32> = (0..track_count + 1)
+            .filter(|&index| self.line_names[index as usize].contains(&line.ident))
+            .collect();
+        let named_count = named_lines.len() as i32;
+        if nth > 0 {
+            if nth <= named_count {
+                named_lines[(nth - 1) as usize]
+            } else {
+                track_count + nth - named_count
+            }
+        } else if -nth <= named_count {
+            named_lines[(named_count + nth) as usize]
+        } else {
+            nth + named_count
+        }
+    }
+
+    /// Returns the line that a span reaches from the given line, searching forwards or
+    /// backwards.
+    fn resolve_span(&self, from: i32, span: &GridLine, forwards: bool) -> i32 {
+        let mut remaining = max(span.line_num, 1);
+        let step = if forwards { 1 } else { -1 };
+        if span.ident.is_empty() {
+            return from + remaining * step;
+        }
+        let mut line = from;
+        loop {
+            line += step;
+            if self.line_has_name(line, &span.ident) {
+                remaining -= 1;
+                if remaining == 0 {
+                    return line;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the number of repetitions of `repeat(auto-fill, ...)` or `repeat(auto-fit, ...)`
+/// that fit in the available size.
+///
+/// <https://drafts.csswg.org/css-grid/#auto-repeat>
+fn auto_repetitions(
+    values: &[TrackListValue<LengthPercentage, Integer>],
+    repeat: &TrackRepeat<LengthPercentage, Integer>,
+    available_size: Option<Au>,
+    gap: Au,
+) -> usize {
+    let available_size = match available_size {
+        Some(size) => size,
+        None => return 1,
+    };
+
+    // Each track is treated as its maximum sizing function if it is definite, or as its
+    // minimum sizing function otherwise.
+    let fixed_size = |size: &TrackSize<LengthPercentage>| -> Au {
+        let (min, max) = match *size {
+            TrackSize::Breadth(ref breadth) => (breadth, breadth),
+            TrackSize::Minmax(ref min, ref max) => (min, max),
+            TrackSize::FitContent(ref limit) => (limit, limit),
+        };
+        let definite = |breadth: &TrackBreadth<LengthPercentage>| match *breadth {
+            TrackBreadth::Breadth(ref length) => Some(length.to_used_value(available_size)),
+            _ => None,
+        };
+        definite(max).or_else(|| definite(min)).unwrap_or(Au(0))
+    };
+
+    let mut other_size = -gap;
+    for value in values {
+        if let TrackListValue::TrackSize(ref size) = *value {
+            other_size += fixed_size(size) + gap;
+        }
+    }
+    let mut repeat_size = Au(0);
+    for size in repeat.track_sizes.iter() {
+        repeat_size += fixed_size(size) + gap;
+    }
+    if repeat_size <= Au(0) {
+        return 1;
+    }
+    let count = (available_size - other_size).0 / repeat_size.0;
+    min(max(count, 1), MAX_GRID_LINE) as usize
+}
+
+/// The position of a grid item in one axis, before auto-placement.
+#[derive(Clone, Copy)]
+enum AxisPlacement {
+    /// Between two lines, as indices of the lines of the explicit grid.
+    Definite(i32, i32),
+    /// Spanning the given number of tracks, at a position found by auto-placement.
+    Auto(usize),
+}
+
+impl AxisPlacement {
+    /// <https://drafts.csswg.org/css-grid/#line-placement>
+    fn new(start: &GridLine, end: &GridLine, grid: &ExplicitGrid) -> AxisPlacement {
+        let definite_start = if start.is_auto() || start.is_span {
+            None
+        } else {
+            Some(grid.resolve_line(start, "start"))
+        };
+        let definite_end = if end.is_auto() || end.is_span {
+            None
+        } else {
+            Some(grid.resolve_line(end, "end"))
+        };
+        match (definite_start, definite_end) {
+            (Some(start), Some(end)) if start == end => AxisPlacement::Definite(start, start + 1),
+            (Some(start), Some(end)) => AxisPlacement::Definite(min(start, end), max(start, end)),
+            (Some(start), None) if end.is_span => {
+                AxisPlacement::Definite(start, grid.resolve_span(start, end, true))
+            },
+            (Some(start), None) => AxisPlacement::Definite(start, start + 1),
+            (None, Some(end)) if start.is_span => {
+                AxisPlacement::Definite(grid.resolve_span(end, start, false), end)
+            },
+            (None, Some(end)) => AxisPlacement::Definite(end - 1, end),
+            (None, None) => {
+                // A span of named lines counts as a span of one track here.
+                let span = if start.is_span { start } else { end };
+                if span.is_span && span.ident.is_empty() {
+                    AxisPlacement::Auto(max(span.line_num, 1) as usize)
+                } else {
+                    AxisPlacement::Auto(1)
+                }
+            },
+        }
+    }
+
+    fn start(&self) -> Option<i32> {
+        match *self {
+            AxisPlacement::Definite(start, _) => Some(start),
+            AxisPlacement::Auto(_) => None,
+        }
+    }
+
+    /// Returns the tracks spanned by this placement if it is definite, once the given number
+    /// of implicit tracks have been added before the explicit grid.
+    fn tracks(&self, leading_track_count: i32) -> Option<Range<usize>> {
+        match *self {
+            AxisPlacement::Definite(start, end) => {
+                Some((start + leading_track_count) as usize..(end + leading_track_count) as usize)
+            },
+            AxisPlacement::Auto(_) => None,
+        }
+    }
+
+    fn span(&self) -> usize {
+        match *self {
+            AxisPlacement::Definite(start, end) => (end - start) as usize,
+            AxisPlacement::Auto(span) => span,
+        }
+    }
+}
+
+/// The size of the implicit grid in one axis.
+#[derive(Clone, Copy)]
+struct GridExtent {
+    /// The number of implicit tracks before the explicit grid.
+    leading_track_count: usize,
+    /// The total number of tracks.
+    track_count: usize,
+}
+
+/// Whether a grid area overlaps any of the already placed grid areas.
+fn overlaps(placed: &[(Range<usize>, Range<usize>)], area: &(Range<usize>, Range<usize>)) -> bool {
+    placed.iter().any(|other| {
+        other.0.start < area.0.end &&
+            area.0.start < other.0.end &&
+            other.1.start < area.1.end &&
+            area.1.start < other.1.end
+    })
+}
+
+/// Returns the size of the gutters between the given tracks.
+fn gutters(tracks: &[GridTrack], gap: Au) -> Au {
+    let count = tracks.iter().filter(|track| !track.is_collapsed).count() as i32;
+    gap * max(count - 1, 0)
+}
+
+/// Returns the size of the grid area made of the given tracks, including their gutters.
+fn span_size(tracks: &[GridTrack], span: &Range<usize>) -> Au {
+    let last = &tracks[span.end - 1];
+    last.position + last.base_size - tracks[span.start].position
+}
+
+/// Returns the size of the given tracks, including their gutters.
+fn tracks_size(tracks: &[GridTrack], gap: Au) -> Au {
+    tracks
+        .iter()
+        .fold(gutters(tracks, gap), |size, track| size + track.base_size)
+}
+
+/// Grows the base sizes of the tracks with a growth limit up to it, evenly distributing the
+/// given free space.
+fn distribute_to_growth_limits(tracks: &mut [GridTrack], mut free_space: Au) {
+    loop {
+        let growable: Vec<usize> = (0..tracks.len())
+            .filter(|&index| {
+                tracks[index]
+                    .growth_limit
+                    .map_or(false, |limit| limit > tracks[index].base_size)
+            })
+            .collect();
+        if free_space <= Au(0) || growable.is_empty() {
+            return;
+        }
+        let share = free_space / growable.len() as i32;
+        if share == Au(0) {
+            return;
+        }
+        for index in growable {
+            let track = &mut tracks[index];
+            let growth = min(share, track.growth_limit.unwrap() - track.base_size);
+            track.base_size += growth;
+            free_space -= growth;
+        }
+    }
+}
+
+/// Returns the used size of `1fr` when the flexible tracks fill the given space.
+///
+/// <https://drafts.csswg.org/css-grid/#algo-find-fr-size>
+fn find_fr_size(tracks: &[GridTrack], space: Au) -> Au {
+    let mut is_inflexible: Vec<bool> = tracks
+        .iter()
+        .map(|track| track.flex_factor().is_none())
+        .collect();
+    loop {
+        let mut leftover_space = space;
+        let mut flex_factor_sum = 0.0;
+        for (track, &is_inflexible) in tracks.iter().zip(is_inflexible.iter()) {
+            if is_inflexible {
+                leftover_space -= track.base_size;
+            } else {
+                flex_factor_sum += track.flex_factor().unwrap();
+            }
+        }
+        if flex_factor_sum == 0.0 || leftover_space <= Au(0) {
+            return Au(0);
+        }
+        let fr_size = leftover_space.scale_by(1.0 / flex_factor_sum.max(1.0));
+
+        // The tracks whose base size is larger than their share are treated as inflexible.
+        let mut changed = false;
+        for (track, is_inflexible) in tracks.iter().zip(is_inflexible.iter_mut()) {
+            if !*is_inflexible && track.base_size > fr_size.scale_by(track.flex_factor().unwrap()) {
+                *is_inflexible = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            return fr_size;
+        }
+    }
+}
+
+/// Sizes the tracks of the grid in one axis, given the contributions of the grid items.
+///
+/// <https://drafts.csswg.org/css-grid/#algo-track-sizing>
+fn size_tracks(
+    tracks: &mut [GridTrack],
+    contributions: &[Contribution],
+    available_space: AvailableSpace,
+    gap: Au,
+    alignment: ContentAlignment,
+) {
+    let spans_flexible_track = |tracks: &[GridTrack], contribution: &Contribution| {
+        contribution
+            .span
+            .clone()
+            .any(|index| tracks[index].flex_factor().is_some())
+    };
+
+    // Resolve the intrinsic track sizes, starting with the items spanning a single track.
+    // https://drafts.csswg.org/css-grid/#algo-content
+    let mut sorted_contributions: Vec<&Contribution> = contributions.iter().collect();
+    sorted_contributions.sort_by_key(|contribution| contribution.span.len());
+    for contribution in sorted_contributions {
+        let span = contribution.span.clone();
+        if span.len() == 1 {
+            let track = &mut tracks[span.start];
+            if let Some(size) = track.min_content_target(contribution) {
+                track.base_size = max(track.base_size, size);
+            }
+            if let Some(size) = track.max_content_target(contribution) {
+                track.growth_limit = Some(max(track.growth_limit.unwrap_or(Au(0)), size));
+            }
+            continue;
+        }
+
+        // The items spanning flexible tracks are taken into account when expanding them.
+        if spans_flexible_track(tracks, contribution) {
+            continue;
+        }
+        let span_gutters = gap * (span.len() as i32 - 1);
+
+        let intrinsic_min: Vec<usize> = span
+            .clone()
+            .filter(|&index| tracks[index].min.is_intrinsic())
+            .collect();
+        let base_sizes = span
+            .clone()
+            .fold(span_gutters, |size, index| size + tracks[index].base_size);
+        let extra_space = contribution.min_content - base_sizes;
+        if !intrinsic_min.is_empty() && extra_space > Au(0) {
+            let share = extra_space / intrinsic_min.len() as i32;
+            for index in intrinsic_min {
+                tracks[index].base_size += share;
+            }
+        }
+
+        let intrinsic_max: Vec<usize> = span
+            .clone()
+            .filter(|&index| tracks[index].max.is_intrinsic())
+            .collect();
+        let growth_limits = span.clone().fold(span_gutters, |size, index| {
+            size + tracks[index]
+                .growth_limit
+                .unwrap_or(tracks[index].base_size)
+        });
+        let extra_space = contribution.max_content - growth_limits;
+        if !intrinsic_max.is_empty() && extra_space > Au(0) {
+            let share = extra_space / intrinsic_max.len() as i32;
+            for index in intrinsic_max {
+                let track = &mut tracks[index];
+                track.growth_limit = Some(track.growth_limit.unwrap_or(track.base_size) + share);
+            }
+        }
+    }
+    for track in tracks.iter_mut() {
+        if track.flex_factor().is_none() {
+            let growth_limit = track.growth_limit.unwrap_or(track.base_size);
+            track.growth_limit = Some(max(growth_limit, track.base_size));
+        }
+    }
+
+    // Maximize the tracks.
+    // https://drafts.csswg.org/css-grid/#algo-grow-tracks
+    match available_space {
+        AvailableSpace::Definite(size) => {
+            let free_space = size - tracks_size(tracks, gap);
+            distribute_to_growth_limits(tracks, free_space);
+        },
+        AvailableSpace::MaxContent => {
+            for track in tracks.iter_mut() {
+                if let Some(growth_limit) = track.growth_limit {
+                    track.base_size = growth_limit;
+                }
+            }
+        },
+        AvailableSpace::MinContent => {},
+    }
+
+    // Expand the flexible tracks.
+    // https://drafts.csswg.org/css-grid/#algo-flex-tracks
+    let fr_size = match available_space {
+        AvailableSpace::Definite(size) => find_fr_size(tracks, size - gutters(tracks, gap)),
+        AvailableSpace::MaxContent => {
+            let mut fr_size = Au(0);
+            for track in tracks.iter() {
+                if let Some(flex) = track.flex_factor() {
+                    fr_size = max(fr_size, track.base_size.scale_by(1.0 / flex.max(1.0)));
+                }
+            }
+            for contribution in contributions {
+                if !spans_flexible_track(tracks, contribution) {
+                    continue;
+                }
+                let mut leftover_space =
+                    contribution.max_content - gap * (contribution.span.len() as i32 - 1);
+                let mut flex_factor_sum = 0.0;
+                for track in &tracks[contribution.span.clone()] {
+                    match track.flex_factor() {
+                        Some(flex) => flex_factor_sum += flex,
+                        None => leftover_space -= track.base_size,
+                    }
+                }
+                fr_size = max(
+                    fr_size,
+                    leftover_space.scale_by(1.0 / flex_factor_sum.max(1.0)),
+                );
+            }
+            fr_size
+        },
+        AvailableSpace::MinContent => Au(0),
+    };
+    for track in tracks.iter_mut() {
+        if let Some(flex) = track.flex_factor() {
+            track.base_size = max(track.base_size, fr_size.scale_by(flex));
+        }
+    }
+
+    // Stretch the tracks with an `auto` maximum sizing function.
+    // https://drafts.csswg.org/css-grid/#algo-stretch
+    if let (AvailableSpace::Definite(size), ContentAlignment::Stretch) =
+        (available_space, alignment)
+    {
+        let free_space = size - tracks_size(tracks, gap);
+        let auto_tracks: Vec<usize> = (0..tracks.len())
+            .filter(|&index| match tracks[index].max {
+                SizingFunction::Auto => !tracks[index].is_collapsed,
+                _ => false,
+            })
+            .collect();
+        if free_space > Au(0) && !auto_tracks.is_empty() {
+            let share = free_space / auto_tracks.len() as i32;
+            for index in auto_tracks {
+                tracks[index].base_size += share;
+            }
+        }
+    }
+}
+
+/// Positions the tracks in the content box of the grid container, distributing the free
+/// space according to `justify-content` or `align-content`.
+///
+/// <https://drafts.csswg.org/css-align/#distribution-grid>
+fn position_tracks(
+    tracks: &mut [GridTrack],
+    content_size: Au,
+    gap: Au,
+    alignment: ContentAlignment,
+) {
+    let free_space = content_size - tracks_size(tracks, gap);
+    let track_count = tracks.iter().filter(|track| !track.is_collapsed).count() as i32;
+    let (offset, spacing) = match alignment {
+        ContentAlignment::End => (free_space, Au(0)),
+        ContentAlignment::Center => (free_space / 2, Au(0)),
+        ContentAlignment::SpaceBetween if free_space > Au(0) && track_count > 1 => {
+            (Au(0), free_space / (track_count - 1))
+        },
+        ContentAlignment::SpaceAround if free_space > Au(0) && track_count > 0 => {
+            (free_space / (track_count * 2), free_space / track_count)
+        },
+        _ => (Au(0), Au(0)),
+    };
+
+    let mut position = offset;
+    for track in tracks.iter_mut() {
+        track.position = position;
+        if !track.is_collapsed {
+            position += track.base_size + gap + spacing;
+        }
+    }
+}
+
+/// Resolves a `row-gap` or `column-gap` value against the size of the content box.
+fn used_gap(gap: &NonNegativeLengthPercentageOrNormal, basis: Option<Au>) -> Au {
+    match *gap {
+        NonNegativeLengthPercentageOrNormal::LengthPercentage(ref length) => {
+            length.maybe_to_used_value(basis).unwrap_or(Au(0))
+        },
+        NonNegativeLengthPercentageOrNormal::Normal => Au(0),
+    }
+}
+
+/// Resolves a size against a possibly indefinite percentage basis.
+fn used_size(size: &Size, basis: Option<Au>) -> Option<Au> {
+    match *size {
+        Size::Auto => None,
+        Size::LengthPercentage(ref length) => length.maybe_to_used_value(basis),
+    }
+}
+
+/// Resolves a maximum size against a possibly indefinite percentage basis.
+fn used_max_size(size: &MaxSize, basis: Option<Au>) -> Option<Au> {
+    match *size {
+        MaxSize::None => None,
+        MaxSize::LengthPercentage(ref length) => length.maybe_to_used_value(basis),
+    }
+}
+
+/// Represents a child in a grid container, and the grid area it is placed in. Items are
+/// sorted by the 'order' property.
+#[derive(Debug, Serialize)]
+struct GridItem {
+    /// The index of the actual flow in our child list.
+    index: usize,
+    /// The 'order' property of this item.
+    order: i32,
+    /// The rows spanned by this item.
+    rows: Range<usize>,
+    /// The columns spanned by this item.
+    columns: Range<usize>,
+}
+
+#[allow(unsafe_code)]
+unsafe impl crate::flow::HasBaseFlow for GridFlow {}
+
+/// A block with the CSS `display` property equal to `grid`.
+#[derive(Debug, Serialize)]
+#[repr(C)]
+pub struct GridFlow {
+    /// Data common to all block flows.
+    block_flow: BlockFlow,
+    /// List of grid items that belong to this grid container.
+    items: Vec<GridItem>,
+    /// The rows of the grid.
+    rows: Vec<GridTrack>,
+    /// The columns of the grid.
+    columns: Vec<GridTrack>,
+    /// The used value of `row-gap`.
+    row_gap: Au,
+    /// The used value of `column-gap`.
+    column_gap: Au,
+}
+
+impl GridFlow {
+    pub fn from_fragment(fragment: Fragment, flotation: Option<FloatKind>) -> GridFlow {
+        GridFlow {
+            block_flow: BlockFlow::from_fragment_and_float_kind(fragment, flotation),
+            items: Vec::new(),
+            rows: Vec::new(),
+            columns: Vec::new(),
+            row_gap: Au(0),
+            column_gap: Au(0),
+        }
+    }
+
+    /// Builds the explicit grid in both axes.
+    fn explicit_grids(
+        &self,
+        available_block_size: Option<Au>,
+        available_inline_size: Option<Au>,
+    ) -> (ExplicitGrid, ExplicitGrid) {
+        let position = self.block_flow.fragment.style().get_position();
+        let (area_rows, area_columns) = match position.grid_template_areas {
+            GridTemplateAreas::Areas(ref areas) => (areas.0.strings.len(), areas.0.width as usize),
+            GridTemplateAreas::None => (0, 0),
+        };
+        let mut rows = ExplicitGrid::new(
+            &position.grid_template_rows,
+            area_rows,
+            &position.grid_auto_rows,
+            available_block_size,
+            self.row_gap,
+        );
+        rows.add_area_line_names(&position.grid_template_areas, true);
+        let mut columns = ExplicitGrid::new(
+            &position.grid_template_columns,
+            area_columns,
+            &position.grid_auto_columns,
+            available_inline_size,
+            self.column_gap,
+        );
+        columns.add_area_line_names(&position.grid_template_areas, false);
+        (rows, columns)
+    }
+
+    /// Places the items in the grid, and returns the size of the implicit grid in the block
+    /// and inline axes.
+    ///
+    /// <https://drafts.csswg.org/css-grid/#auto-placement-algo>
+    fn place_items(
+        &mut self,
+        rows: &ExplicitGrid,
+        columns: &ExplicitGrid,
+    ) -> (GridExtent, GridExtent) {
+        let auto_flow = self
+            .block_flow
+            .fragment
+            .style()
+            .get_position()
+            .grid_auto_flow;
+        let is_column_flow = auto_flow.autoflow == AutoFlow::Column;
+
+        // The auto-placement cursor moves along the "flow" axis (the inline axis for
+        // `grid-auto-flow: row`), then to the next line of the "cross" axis.
+        let (cross_grid, flow_grid) = if is_column_flow {
+            (columns, rows)
+        } else {
+            (rows, columns)
+        };
+        let mut placements = Vec::with_capacity(self.items.len());
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let position = children
+                    .get(item.index)
+                    .as_block()
+                    .fragment
+                    .style()
+                    .get_position();
+                let row =
+                    AxisPlacement::new(&position.grid_row_start, &position.grid_row_end, rows);
+                let column = AxisPlacement::new(
+                    &position.grid_column_start,
+                    &position.grid_column_end,
+                    columns,
+                );
+                placements.push(if is_column_flow {
+                    (column, row)
+                } else {
+                    (row, column)
+                });
+            }
+        }
+
+        // Add implicit tracks before the explicit grid for the lines before it.
+        let leading_cross = -placements
+            .iter()
+            .filter_map(|placement| placement.0.start())
+            .fold(0, min);
+        let leading_flow = -placements
+            .iter()
+            .filter_map(|placement| placement.1.start())
+            .fold(0, min);
+
+        let mut placed: Vec<(Range<usize>, Range<usize>)> = vec![];
+        let mut areas: Vec<Option<(Range<usize>, Range<usize>)>> = vec![None; placements.len()];
+
+        // Place the items with a definite position in both axes.
+        for (index, placement) in placements.iter().enumerate() {
+            if let (Some(cross), Some(flow)) = (
+                placement.0.tracks(leading_cross),
+                placement.1.tracks(leading_flow),
+            ) {
+                placed.push((cross.clone(), flow.clone()));
+                areas[index] = Some((cross, flow));
+            }
+        }
+
+        // Place the items locked to a line of the cross axis.
+        let mut line_cursors: HashMap<usize, usize> = HashMap::new();
+        for (index, placement) in placements.iter().enumerate() {
+            if areas[index].is_some() {
+                continue;
+            }
+            let cross = match placement.0.tracks(leading_cross) {
+                Some(cross) => cross,
+                None => continue,
+            };
+            let span = placement.1.span();
+            let mut flow_start = if auto_flow.dense {
+                0
+            } else {
+                *line_cursors.get(&cross.start).unwrap_or(&0)
+            };
+            while overlaps(&placed, &(cross.clone(), flow_start..flow_start + span)) {
+                flow_start += 1;
+            }
+            line_cursors.insert(cross.start, flow_start + span);
+            let area = (cross, flow_start..flow_start + span);
+            placed.push(area.clone());
+            areas[index] = Some(area);
+        }
+
+        // Determine the number of tracks in the flow axis.
+        let explicit_flow_end = leading_flow as usize + flow_grid.track_sizes.len();
+        let flow_track_count = placements
+            .iter()
+            .map(|placement| match placement.1 {
+                AxisPlacement::Definite(_, end) => (end + leading_flow) as usize,
+                AxisPlacement::Auto(span) => span,
+            })
+            .chain(placed.iter().map(|area| area.1.end))
+            .fold(explicit_flow_end, max);
+
+        // Place the remaining items with the auto-placement cursor.
+        let (mut cross_cursor, mut flow_cursor) = (0, 0);
+        for (index, placement) in placements.iter().enumerate() {
+            if areas[index].is_some() {
+                continue;
+            }
+            let cross_span = placement.0.span();
+            let area = match placement.1.tracks(leading_flow) {
+                Some(flow) => {
+                    if auto_flow.dense {
+                        cross_cursor = 0;
+                    } else if flow.start < flow_cursor {
+                        cross_cursor += 1;
+                    }
+                    flow_cursor = flow.start;
+                    while overlaps(
+                        &placed,
+                        &(cross_cursor..cross_cursor + cross_span, flow.clone()),
+                    ) {
+                        cross_cursor += 1;
+                    }
+                    (cross_cursor..cross_cursor + cross_span, flow)
+                },
+                None => {
+                    let flow_span = placement.1.span();
+                    if auto_flow.dense {
+                        cross_cursor = 0;
+                        flow_cursor = 0;
+                    }
+                    loop {
+                        if flow_cursor + flow_span > flow_track_count {
+                            cross_cursor += 1;
+                            flow_cursor = 0;
+                            continue;
+                        }
+                        let area = (
+                            cross_cursor..cross_cursor + cross_span,
+                            flow_cursor..flow_cursor + flow_span,
+                        );
+                        if !overlaps(&placed, &area) {
+                            break area;
+                        }
+                        flow_cursor += 1;
+                    }
+                },
+            };
+            placed.push(area.clone());
+            areas[index] = Some(area);
+        }
+
+        let explicit_cross_end = leading_cross as usize + cross_grid.track_sizes.len();
+        let cross_track_count = placed
+            .iter()
+            .map(|area| area.0.end)
+            .fold(explicit_cross_end, max);
+
+        let cross_extent = GridExtent {
+            leading_track_count: leading_cross as usize,
+            track_count: cross_track_count,
+        };
+        let flow_extent = GridExtent {
+            leading_track_count: leading_flow as usize,
+            track_count: flow_track_count,
+        };
+        for (item, area) in self.items.iter_mut().zip(areas.into_iter()) {
+            let (cross, flow) = area.unwrap();
+            if is_column_flow {
+                item.columns = cross;
+                item.rows = flow;
+            } else {
+                item.rows = cross;
+                item.columns = flow;
+            }
+        }
+        if is_column_flow {
+            (flow_extent, cross_extent)
+        } else {
+            (cross_extent, flow_extent)
+        }
+    }
+
+    /// Creates the tracks of the implicit grid in one axis.
+    fn tracks(
+        explicit_grid: &ExplicitGrid,
+        extent: GridExtent,
+        implicit_track_size: &TrackSize<LengthPercentage>,
+        basis: Option<Au>,
+        occupied: &[Range<usize>],
+    ) -> Vec<GridTrack> {
+        (0..extent.track_count)
+            .map(|index| {
+                let explicit_index = index as isize - extent.leading_track_count as isize;
+                let size = if explicit_index >= 0 {
+                    explicit_grid
+                        .track_sizes
+                        .get(explicit_index as usize)
+                        .unwrap_or(implicit_track_size)
+                } else {
+                    implicit_track_size
+                };
+                let mut track = GridTrack::new(size, basis);
+                let is_auto_fit = explicit_index >= 0 &&
+                    explicit_grid
+                        .auto_fit_tracks
+                        .contains(&(explicit_index as usize));
+                if is_auto_fit && !occupied.iter().any(|span| span.contains(&index)) {
+                    track.collapse();
+                }
+                track
+            })
+            .collect()
+    }
+
+    /// Places the items and sizes the columns of the grid for the given available inline
+    /// size, or for the min-content or max-content inline size of the grid if it is `None`.
+    fn size_columns(
+        &mut self,
+        available_inline_size: AvailableSpace,
+        available_block_size: Option<Au>,
+    ) {
+        let definite_inline_size = match available_inline_size {
+            AvailableSpace::Definite(size) => Some(size),
+            _ => None,
+        };
+        let (row_grid, column_grid) =
+            self.explicit_grids(available_block_size, definite_inline_size);
+        let (row_extent, column_extent) = self.place_items(&row_grid, &column_grid);
+
+        let mut contributions = Vec::with_capacity(self.items.len());
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let intrinsic_sizes = children.get(item.index).base().intrinsic_inline_sizes;
+                contributions.push(Contribution {
+                    span: item.columns.clone(),
+                    min_content: intrinsic_sizes.minimum_inline_size,
+                    max_content: intrinsic_sizes.preferred_inline_size,
+                });
+            }
+        }
+
+        let position = self.block_flow.fragment.style().get_position();
+        let occupied_columns: Vec<Range<usize>> =
+            self.items.iter().map(|item| item.columns.clone()).collect();
+        let occupied_rows: Vec<Range<usize>> =
+            self.items.iter().map(|item| item.rows.clone()).collect();
+        let justify_content = ContentAlignment::from(position.justify_content);
+        self.columns = GridFlow::tracks(
+            &column_grid,
+            column_extent,
+            &position.grid_auto_columns,
+            definite_inline_size,
+            &occupied_columns,
+        );
+        self.rows = GridFlow::tracks(
+            &row_grid,
+            row_extent,
+            &position.grid_auto_rows,
+            available_block_size,
+            &occupied_rows,
+        );
+        size_tracks(
+            &mut self.columns,
+            &contributions,
+            available_inline_size,
+            self.column_gap,
+            justify_content,
+        );
+        if let Some(size) = definite_inline_size {
+            position_tracks(&mut self.columns, size, self.column_gap, justify_content);
+        }
+    }
+}
+
+impl Flow for GridFlow {
+    fn class(&self) -> FlowClass {
+        FlowClass::Grid
+    }
+
+    fn as_grid(&self) -> &GridFlow {
+        self
+    }
+
+    fn as_block(&self) -> &BlockFlow {
+        &self.block_flow
+    }
+
+    fn as_mut_block(&mut self) -> &mut BlockFlow {
+        &mut self.block_flow
+    }
+
+    fn mark_as_root(&mut self) {
+        self.block_flow.mark_as_root();
+    }
+
+    fn bubble_inline_sizes(&mut self) {
+        let _scope = layout_debug_scope!(
+            "grid::bubble_inline_sizes {:x}",
+            self.block_flow.base.debug_id()
+        );
+
+        // The anonymous grid items were generated by the flow constructor.
+        // FIXME: Reordering the items according to 'order' should be done during flow
+        // construction.
+        let mut items: Vec<GridItem> = self
+            .block_flow
+            .base
+            .children
+            .iter()
+            .enumerate()
+            .filter(|&(_, flow)| {
+                !flow
+                    .as_block()
+                    .base
+                    .flags
+                    .contains(FlowFlags::IS_ABSOLUTELY_POSITIONED)
+            })
+            .map(|(index, flow)| GridItem {
+                index: index,
+                order: flow.as_block().fragment.style().get_position().order,
+                rows: 0..1,
+                columns: 0..1,
+            })
+            .collect();
+        items.sort_by_key(|item| item.order);
+        self.items = items;
+
+        let fixed_width =
+            !model::style_length(self.block_flow.fragment.style().get_position().width, None)
+                .is_auto();
+        let mut computation = self.block_flow.fragment.compute_intrinsic_inline_sizes();
+        if !fixed_width {
+            // Percentages of the size of the grid container resolve against zero here.
+            let style = self.block_flow.fragment.style();
+            self.column_gap = used_gap(&style.get_position().column_gap, None);
+            self.row_gap = used_gap(&style.get_position().row_gap, None);
+
+            self.size_columns(AvailableSpace::MinContent, None);
+            let minimum_inline_size = tracks_size(&self.columns, self.column_gap);
+            self.size_columns(AvailableSpace::MaxContent, None);
+            let preferred_inline_size = tracks_size(&self.columns, self.column_gap);
+
+            computation.content_intrinsic_sizes.minimum_inline_size = minimum_inline_size;
+            computation.content_intrinsic_sizes.preferred_inline_size =
+                max(minimum_inline_size, preferred_inline_size);
+        }
+        self.block_flow.base.intrinsic_inline_sizes = computation.finish();
+    }
+
+    fn assign_inline_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!(
+            "grid::assign_inline_sizes {:x}",
+            self.block_flow.base.debug_id()
+        );
+        debug!("assign_inline_sizes");
+
+        if !self
+            .block_flow
+            .base
+            .restyle_damage
+            .intersects(ServoRestyleDamage::REFLOW_OUT_OF_FLOW | ServoRestyleDamage::REFLOW)
+        {
+            return;
+        }
+
+        self.block_flow
+            .initialize_container_size_for_root(layout_context.shared_context());
+
+        // Our inline-size was set to the inline-size of the containing block by the flow's parent.
+        // Now compute the real value.
+        let containing_block_inline_size = self.block_flow.base.block_container_inline_size;
+        self.block_flow.compute_used_inline_size(
+            layout_context.shared_context(),
+            containing_block_inline_size,
+        );
+        if self.block_flow.base.flags.is_float() {
+            self.block_flow
+                .float
+                .as_mut()
+                .unwrap()
+                .containing_inline_size = containing_block_inline_size
+        }
+
+        // Move in from the inline-start border edge.
+        let inline_start_content_edge = self.block_flow.fragment.border_box.start.i +
+            self.block_flow.fragment.border_padding.inline_start;
+        let padding_and_borders = self.block_flow.fragment.border_padding.inline_start_end();
+        let content_inline_size =
+            self.block_flow.fragment.border_box.size.inline - padding_and_borders;
+
+        // Calculate the block size available to the rows, if it is definite.
+        let box_border = self
+            .block_flow
+            .fragment
+            .box_sizing_boundary(Direction::Block);
+        let parent_container_size = self
+            .block_flow
+            .explicit_block_containing_size(layout_context.shared_context());
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let explicit_content_size = self
+            .block_flow
+            .explicit_block_size(parent_container_size)
+            .map(|x| max(x - box_border, Au(0)));
+        let available_block_size = explicit_content_size.or_else(|| {
+            used_max_size(
+                &self.block_flow.fragment.style().max_block_size(),
+                parent_container_size,
+            )
+            .map(|x| max(x - box_border, Au(0)))
+        });
+
+        let (justify_items, text_align) = {
+            let style = self.block_flow.fragment.style();
+            self.column_gap = used_gap(&style.get_position().column_gap, Some(content_inline_size));
+            self.row_gap = used_gap(&style.get_position().row_gap, explicit_content_size);
+            (
+                style.get_position().justify_items,
+                style.get_inherited_text().text_align,
+            )
+        };
+
+        self.size_columns(
+            AvailableSpace::Definite(content_inline_size),
+            available_block_size,
+        );
+
+        let container_mode = self.block_flow.base.block_container_writing_mode;
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let area_inline_size = span_size(&self.columns, &item.columns);
+                let block = children.get(item.index).as_mut_block();
+
+                block.base.block_container_writing_mode = container_mode;
+                block.base.block_container_inline_size = area_inline_size;
+                block.base.block_container_explicit_block_size = None;
+                // Per CSS 2.1 § 16.3.1, text alignment propagates to all children in flow.
+                //
+                // TODO(#2265, pcwalton): Do this in the cascade instead.
+                block.base.flags.set_text_align(text_align);
+
+                // These methods compute auto margins to zero length, they are resolved below.
+                block.fragment.compute_border_and_padding(area_inline_size);
+                block
+                    .fragment
+                    .compute_inline_direction_margins(area_inline_size);
+                block
+                    .fragment
+                    .compute_block_direction_margins(area_inline_size);
+
+                let alignment = ItemAlignment::justify(block.fragment.style(), justify_items);
+                let border_padding = block.fragment.border_padding.inline_start_end();
+                let box_sizing_boundary = block.fragment.box_sizing_boundary(Direction::Inline);
+                let mut margin_inline_start = block.fragment.margin.inline_start;
+                let mut margin_inline_end = block.fragment.margin.inline_end;
+                let available_size = area_inline_size - margin_inline_start - margin_inline_end;
+
+                let inline_size = {
+                    let style = block.fragment.style();
+                    let inline_size = match style
+                        .content_inline_size()
+                        .to_used_value(area_inline_size)
+                    {
+                        Some(size) => size - box_sizing_boundary + border_padding,
+                        None if alignment == ItemAlignment::Stretch => available_size,
+                        // Otherwise, the item is sized as `fit-content`.
+                        None => {
+                            let intrinsic_sizes = &block.base.intrinsic_inline_sizes;
+                            let margin = margin_inline_start + margin_inline_end;
+                            min(
+                                intrinsic_sizes.preferred_inline_size - margin,
+                                max(intrinsic_sizes.minimum_inline_size - margin, available_size),
+                            )
+                        },
+                    };
+                    let min_inline_size = style
+                        .min_inline_size()
+                        .to_used_value(area_inline_size)
+                        .unwrap_or(Au(0));
+                    let max_inline_size = style
+                        .max_inline_size()
+                        .to_used_value(area_inline_size)
+                        .unwrap_or(MAX_AU);
+                    let inline_size = max(
+                        min_inline_size,
+                        min(
+                            inline_size - border_padding + box_sizing_boundary,
+                            max_inline_size,
+                        ),
+                    );
+                    max(
+                        inline_size + border_padding - box_sizing_boundary,
+                        border_padding,
+                    )
+                };
+
+                // Auto margins absorb the free space, otherwise it is used for alignment.
+                let free_space = max(available_size - inline_size, Au(0));
+                let margin = block.fragment.style().logical_margin();
+                let offset = match (margin.inline_start.is_auto(), margin.inline_end.is_auto()) {
+                    (true, true) => {
+                        margin_inline_start += free_space / 2;
+                        margin_inline_end += free_space - free_space / 2;
+                        Au(0)
+                    },
+                    (true, false) => {
+                        margin_inline_start += free_space;
+                        Au(0)
+                    },
+                    (false, true) => {
+                        margin_inline_end += free_space;
+                        Au(0)
+                    },
+                    (false, false) => match alignment {
+                        ItemAlignment::End => free_space,
+                        ItemAlignment::Center => free_space / 2,
+                        ItemAlignment::Stretch | ItemAlignment::Start => Au(0),
+                    },
+                };
+
+                block.fragment.margin.inline_start = margin_inline_start;
+                block.fragment.margin.inline_end = margin_inline_end;
+                block.fragment.border_box.start.i = margin_inline_start;
+                block.fragment.border_box.size.inline = inline_size;
+                block.base.position.start.i =
+                    inline_start_content_edge + self.columns[item.columns.start].position + offset;
+                block.base.position.size.inline =
+                    inline_size + margin_inline_start + margin_inline_end;
+            }
+        }
+
+        // The grid container is the containing block of its absolutely positioned children.
+        for kid in self.block_flow.base.children.iter_mut() {
+            let kid_base = kid.mut_base();
+            if !kid_base.flags.contains(FlowFlags::IS_ABSOLUTELY_POSITIONED) {
+                continue;
+            }
+            if kid_base
+                .flags
+                .contains(FlowFlags::INLINE_POSITION_IS_STATIC)
+            {
+                kid_base.position.start.i = inline_start_content_edge;
+            }
+            kid_base.block_container_inline_size = content_inline_size;
+            kid_base.block_container_writing_mode = container_mode;
+        }
+    }
+
+    fn assign_block_size(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!(
+            "grid::assign_block_size {:x}",
+            self.block_flow.base.debug_id()
+        );
+
+        let box_border = self
+            .block_flow
+            .fragment
+            .box_sizing_boundary(Direction::Block);
+        let parent_container_size = self
+            .block_flow
+            .explicit_block_containing_size(layout_context.shared_context());
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let explicit_content_size = self
+            .block_flow
+            .explicit_block_size(parent_container_size)
+            .map(|x| max(x - box_border, Au(0)));
+        let (align_items, align_content) = {
+            let position = self.block_flow.fragment.style().get_position();
+            (
+                position.align_items,
+                ContentAlignment::from(position.align_content),
+            )
+        };
+
+        // Size the rows to fit the items, which have been laid out at the size of the
+        // columns they span.
+        let mut contributions = Vec::with_capacity(self.items.len());
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let fragment = &children.get(item.index).as_block().fragment;
+                let block_size = fragment.border_box.size.block + fragment.margin.block_start_end();
+                contributions.push(Contribution {
+                    span: item.rows.clone(),
+                    min_content: block_size,
+                    max_content: block_size,
+                });
+            }
+        }
+        let available_space = match explicit_content_size {
+            Some(size) => AvailableSpace::Definite(size),
+            None => AvailableSpace::MaxContent,
+        };
+        size_tracks(
+            &mut self.rows,
+            &contributions,
+            available_space,
+            self.row_gap,
+            align_content,
+        );
+
+        let content_block_size = match explicit_content_size {
+            Some(size) => size,
+            None => {
+                let style = self.block_flow.fragment.style();
+                let min_block_size = used_size(&style.min_block_size(), parent_container_size)
+                    .map_or(Au(0), |x| max(x - box_border, Au(0)));
+                let max_block_size = used_max_size(&style.max_block_size(), parent_container_size)
+                    .map_or(MAX_AU, |x| max(x - box_border, Au(0)));
+                max(
+                    min_block_size,
+                    min(tracks_size(&self.rows, self.row_gap), max_block_size),
+                )
+            },
+        };
+        position_tracks(
+            &mut self.rows,
+            content_block_size,
+            self.row_gap,
+            align_content,
+        );
+
+        let block_start_content_edge = self.block_flow.fragment.border_padding.block_start;
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let area_block_size = span_size(&self.rows, &item.rows);
+                let block = children.get(item.index).as_mut_block();
+                let alignment = ItemAlignment::align(block.fragment.style(), align_items);
+                let margin = block.fragment.style().logical_margin();
+
+                let mut margin_block_start = block.fragment.margin.block_start;
+                let mut margin_block_end = block.fragment.margin.block_end;
+                let free_space = area_block_size -
+                    block.base.position.size.block -
+                    margin_block_start -
+                    margin_block_end;
+
+                // Auto margins absorb the free space, otherwise it is used for alignment.
+                let offset = match (margin.block_start.is_auto(), margin.block_end.is_auto()) {
+                    _ if free_space <= Au(0) => Au(0),
+                    (true, true) => {
+                        margin_block_start += free_space / 2;
+                        margin_block_end += free_space - free_space / 2;
+                        Au(0)
+                    },
+                    (true, false) => {
+                        margin_block_start += free_space;
+                        Au(0)
+                    },
+                    (false, true) => {
+                        margin_block_end += free_space;
+                        Au(0)
+                    },
+                    (false, false) => match alignment {
+                        ItemAlignment::Stretch
+                            if block.fragment.style().content_block_size().is_auto() =>
+                        {
+                            block.base.block_container_explicit_block_size = Some(area_block_size);
+                            block.base.position.size.block =
+                                area_block_size - margin_block_start - margin_block_end;
+                            block.fragment.border_box.size.block = block.base.position.size.block;
+                            // FIXME: an item with 'align-self: stretch' and auto block size should
+                            // act as if it has a fixed block size, all child blocks should resolve
+                            // against it.
+                            // block.assign_block_size(layout_context);
+                            Au(0)
+                        }
+                        ItemAlignment::End => free_space,
+                        ItemAlignment::Center => free_space / 2,
+                        ItemAlignment::Stretch | ItemAlignment::Start => Au(0),
+                    },
+                };
+
+                block.fragment.margin.block_start = margin_block_start;
+                block.fragment.margin.block_end = margin_block_end;
+                block.base.position.start.b = block_start_content_edge +
+                    self.rows[item.rows.start].position +
+                    margin_block_start +
+                    offset;
+            }
+        }
+
+        let total_block_size =
+            content_block_size + self.block_flow.fragment.border_padding.block_start_end();
+        self.block_flow.fragment.border_box.size.block = total_block_size;
+        self.block_flow.base.position.size.block = total_block_size;
+
+        let block_start =
+            AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_start);
+        let block_end = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_end);
+        self.block_flow.base.collapsible_margins =
+            CollapsibleMargins::Collapse(block_start, block_end);
+
+        // TODO: assign proper static position for absolute descendants.
+        if (&*self as &dyn Flow).contains_roots_of_absolute_flow_tree() {
+            // Assign block-sizes for all flows in this absolute flow tree.
+            // This is preorder because the block-size of an absolute flow may depend on
+            // the block-size of its containing block, which may also be an absolute flow.
+            let assign_abs_b_sizes = AbsoluteAssignBSizesTraversal(layout_context.shared_context());
+            assign_abs_b_sizes.traverse_absolute_flows(&mut *self);
+        }
+    }
+
+    fn compute_stacking_relative_position(&mut self, layout_context: &LayoutContext) {
+        self.block_flow
+            .compute_stacking_relative_position(layout_context)
+    }
+
+    fn place_float_if_applicable<'a>(&mut self) {
+        self.block_flow.place_float_if_applicable()
+    }
+
+    fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
+        self.block_flow
+            .update_late_computed_inline_position_if_necessary(inline_position)
+    }
+
+    fn update_late_computed_block_position_if_necessary(&mut self, block_position: Au) {
+        self.block_flow
+            .update_late_computed_block_position_if_necessary(block_position)
+    }
+
+    fn build_display_list(&mut self, state: &mut DisplayListBuildState) {
+        // Draw the rest of the block.
+        self.as_mut_block()
+            .build_display_list_for_block(state, BorderPaintingMode::Separate)
+    }
+
+    fn collect_stacking_contexts(&mut self, state: &mut StackingContextCollectionState) {
+        self.block_flow.collect_stacking_contexts(state);
+    }
+
+    fn repair_style(&mut self, new_style: &crate::ServoArc<ComputedValues>) {
+        self.block_flow.repair_style(new_style)
+    }
+
+    fn compute_overflow(&self) -> Overflow {
+        self.block_flow.compute_overflow()
+    }
+
+    fn contains_roots_of_absolute_flow_tree(&self) -> bool {
+        self.block_flow.contains_roots_of_absolute_flow_tree()
+    }
+
+    fn is_absolute_containing_block(&self) -> bool {
+        self.block_flow.is_absolute_containing_block()
+    }
+
+    fn generated_containing_block_size(&self, flow: OpaqueFlow) -> LogicalSize<Au> {
+        self.block_flow.generated_containing_block_size(flow)
+    }
+
+    fn iterate_through_fragment_border_boxes(
+        &self,
+        iterator: &mut dyn FragmentBorderBoxIterator,
+        level: i32,
+        stacking_context_position: &Point2D<Au>,
+    ) {
+        self.block_flow.iterate_through_fragment_border_boxes(
+            iterator,
+            level,
+            stacking_context_position,
+        );
+    }
+
+    fn mutate_fragments(&mut self, mutator: &mut dyn FnMut(&mut Fragment)) {
+        self.block_flow.mutate_fragments(mutator);
+    }
+}
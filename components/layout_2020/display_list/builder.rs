@@ -2,20 +2,202 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-//! Builds display lists from flows and fragments.
+//! Builds display lists from the fragment tree.
 //!
 //! Other browser engines sometimes call this "painting", but it is more accurately called display
 //! list building, as the actual painting does not happen here—only deciding *what* we're going to
 //! paint.
 
-use crate::display_list::items::OpaqueNode;
+use crate::display_list::conversions::ToLayout;
+use crate::display_list::items::{empty_common_item_properties, DisplayItemMetadata};
+use crate::display_list::items::{BaseDisplayItem, CommonDisplayItem, DisplayItem};
+use crate::display_list::items::{DisplayList, OpaqueNode};
+use crate::fragments::{to_physical, BoxFragment, Fragment, TextFragment};
+use crate::style_ext::ComputedValuesExt;
 use app_units::Au;
-use euclid::default::Point2D;
+use embedder_traits::Cursor;
+use euclid::default::{Point2D, Rect};
 use fnv::FnvHashMap;
-use gfx::text::glyph::ByteIndex;
+use gfx::text::glyph::{ByteIndex, GlyphStore};
 use gfx::text::TextRun;
 use range::Range;
 use std::sync::Arc;
+use style::logical_geometry::WritingMode;
+use style::values::computed::{BorderStyle, Color};
+use webrender_api::units::LayoutRect;
+use webrender_api::{self as wr, GlyphInstance};
+
+impl Fragment {
+    /// `containing_block` is the physical rectangle the fragment is relative
+    /// to, and `mode` the writing mode of the fragment.
+    pub(crate) fn build_display_list(
+        &self,
+        display_list: &mut DisplayList,
+        containing_block: &Rect<Au>,
+        mode: WritingMode,
+    ) {
+        match self {
+            Fragment::Box(fragment) => {
+                fragment.build_display_list(display_list, containing_block, mode)
+            },
+            Fragment::Anonymous(fragment) => {
+                let rect = to_physical(&fragment.rect, containing_block, mode);
+                for child in &fragment.children {
+                    child.build_display_list(display_list, &rect, mode);
+                }
+            },
+            Fragment::Text(fragment) => {
+                fragment.build_display_list(display_list, containing_block, mode)
+            },
+        }
+    }
+}
+
+impl BoxFragment {
+    fn build_display_list(
+        &self,
+        display_list: &mut DisplayList,
+        containing_block: &Rect<Au>,
+        mode: WritingMode,
+    ) {
+        let relative_offset = self.relative_offset(containing_block, mode);
+        let border_rect =
+            to_physical(&self.border_rect(), containing_block, mode).translate(relative_offset);
+        // TODO: use the `cursor` property, once it is supported.
+        let base = BaseDisplayItem::new(
+            DisplayItemMetadata {
+                node: self.tag,
+                pointing: Some(Cursor::Default as u16),
+            },
+            border_rect.to_layout(),
+        );
+
+        let background_color = self
+            .style
+            .resolve_color(self.style.get_background().background_color);
+        if background_color.alpha > 0 {
+            display_list
+                .list
+                .push(DisplayItem::Rectangle(CommonDisplayItem::new(
+                    base.clone(),
+                    wr::RectangleDisplayItem {
+                        color: background_color.to_layout(),
+                        common: empty_common_item_properties(),
+                    },
+                )));
+        }
+        self.build_border_display_list(display_list, base, &border_rect, mode);
+
+        let content_rect =
+            to_physical(&self.content_rect, containing_block, mode).translate(relative_offset);
+        let mode_for_children = self.style.writing_mode_for_contents(mode);
+        for child in &self.children {
+            child.build_display_list(display_list, &content_rect, mode_for_children);
+        }
+    }
+
+    fn build_border_display_list(
+        &self,
+        display_list: &mut DisplayList,
+        base: BaseDisplayItem,
+        border_rect: &Rect<Au>,
+        mode: WritingMode,
+    ) {
+        let border_widths = self.border.to_physical(mode);
+        if border_widths.top == Au(0) &&
+            border_widths.right == Au(0) &&
+            border_widths.bottom == Au(0) &&
+            border_widths.left == Au(0)
+        {
+            return;
+        }
+        let border = self.style.get_border();
+        let side = |color: Color, border_style: BorderStyle| wr::BorderSide {
+            color: self.style.resolve_color(color).to_layout(),
+            style: border_style.to_layout(),
+        };
+        let details = wr::BorderDetails::Normal(wr::NormalBorder {
+            top: side(border.border_top_color, border.border_top_style),
+            right: side(border.border_right_color, border.border_right_style),
+            bottom: side(border.border_bottom_color, border.border_bottom_style),
+            left: side(border.border_left_color, border.border_left_style),
+            radius: wr::BorderRadius::zero(),
+            do_aa: true,
+        });
+        display_list
+            .list
+            .push(DisplayItem::Border(CommonDisplayItem::new(
+                base,
+                wr::BorderDisplayItem {
+                    bounds: border_rect.to_layout(),
+                    common: empty_common_item_properties(),
+                    widths: border_widths.to_layout(),
+                    details,
+                },
+            )));
+    }
+}
+
+impl TextFragment {
+    fn build_display_list(
+        &self,
+        display_list: &mut DisplayList,
+        containing_block: &Rect<Au>,
+        mode: WritingMode,
+    ) {
+        let rect = to_physical(&self.content_rect, containing_block, mode);
+        // Vertical text is not supported yet.
+        let mut baseline_origin = rect.origin;
+        baseline_origin.y += self.ascent;
+        let glyphs = glyphs(&self.glyphs, baseline_origin);
+        if glyphs.is_empty() {
+            return;
+        }
+        // Glyphs may overflow the content box of the fragment, so they are
+        // not clipped to it.
+        let base = BaseDisplayItem::new(
+            DisplayItemMetadata {
+                node: self.tag,
+                pointing: Some(Cursor::Text as u16),
+            },
+            LayoutRect::max_rect(),
+        );
+        let color = self.parent_style.clone_color();
+        display_list
+            .list
+            .push(DisplayItem::Text(CommonDisplayItem::with_data(
+                base,
+                wr::TextDisplayItem {
+                    bounds: rect.to_layout(),
+                    common: empty_common_item_properties(),
+                    font_key: self.font_key,
+                    color: color.to_layout(),
+                    glyph_options: None,
+                },
+                glyphs,
+            )));
+    }
+}
+
+/// Positions the glyphs of the given runs one after the other, starting at
+/// the given baseline origin.
+fn glyphs(glyph_runs: &[Arc<GlyphStore>], mut origin: Point2D<Au>) -> Vec<GlyphInstance> {
+    let mut glyphs = vec![];
+    for run in glyph_runs {
+        for glyph in run.iter_glyphs_for_byte_range(&Range::new(ByteIndex(0), run.len())) {
+            if !run.is_whitespace() {
+                let glyph_offset = glyph.offset().unwrap_or(Point2D::zero());
+                let point = origin + glyph_offset.to_vector();
+                glyphs.push(GlyphInstance {
+                    index: glyph.id(),
+                    point: point.to_layout(),
+                });
+            }
+            origin.x += glyph.advance();
+        }
+    }
+    glyphs
+}
 
 pub struct IndexableTextItem {
     /// The placement of the text item on the plane.
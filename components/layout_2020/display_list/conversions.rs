@@ -0,0 +1,77 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::default::{Point2D, Rect, SideOffsets2D, Size2D};
+use style::values::computed::BorderStyle;
+use style::values::RGBA;
+use webrender_api as wr;
+
+pub trait ToLayout {
+    type Type;
+    fn to_layout(&self) -> Self::Type;
+}
+
+impl ToLayout for BorderStyle {
+    type Type = wr::BorderStyle;
+    fn to_layout(&self) -> Self::Type {
+        match *self {
+            BorderStyle::None => wr::BorderStyle::None,
+            BorderStyle::Solid => wr::BorderStyle::Solid,
+            BorderStyle::Double => wr::BorderStyle::Double,
+            BorderStyle::Dotted => wr::BorderStyle::Dotted,
+            BorderStyle::Dashed => wr::BorderStyle::Dashed,
+            BorderStyle::Hidden => wr::BorderStyle::Hidden,
+            BorderStyle::Groove => wr::BorderStyle::Groove,
+            BorderStyle::Ridge => wr::BorderStyle::Ridge,
+            BorderStyle::Inset => wr::BorderStyle::Inset,
+            BorderStyle::Outset => wr::BorderStyle::Outset,
+        }
+    }
+}
+
+impl ToLayout for RGBA {
+    type Type = wr::ColorF;
+    fn to_layout(&self) -> Self::Type {
+        wr::ColorF::new(
+            self.red_f32(),
+            self.green_f32(),
+            self.blue_f32(),
+            self.alpha_f32(),
+        )
+    }
+}
+
+impl ToLayout for Point2D<Au> {
+    type Type = wr::units::LayoutPoint;
+    fn to_layout(&self) -> Self::Type {
+        wr::units::LayoutPoint::new(self.x.to_f32_px(), self.y.to_f32_px())
+    }
+}
+
+impl ToLayout for Rect<Au> {
+    type Type = wr::units::LayoutRect;
+    fn to_layout(&self) -> Self::Type {
+        wr::units::LayoutRect::new(self.origin.to_layout(), self.size.to_layout())
+    }
+}
+
+impl ToLayout for SideOffsets2D<Au> {
+    type Type = wr::units::LayoutSideOffsets;
+    fn to_layout(&self) -> Self::Type {
+        wr::units::LayoutSideOffsets::new(
+            self.top.to_f32_px(),
+            self.right.to_f32_px(),
+            self.bottom.to_f32_px(),
+            self.left.to_f32_px(),
+        )
+    }
+}
+
+impl ToLayout for Size2D<Au> {
+    type Type = wr::units::LayoutSize;
+    fn to_layout(&self) -> Self::Type {
+        wr::units::LayoutSize::new(self.width.to_f32_px(), self.height.to_f32_px())
+    }
+}
@@ -6,17 +6,105 @@ use euclid::Vector2D;
 use gfx_traits;
 use std::collections::HashMap;
 use std::f32;
-use webrender_api::units::LayoutPixel;
-use webrender_api::ExternalScrollId;
+use webrender_api as wr;
+use webrender_api::units::{LayoutPixel, LayoutRect};
+use webrender_api::{ClipId, CommonItemProperties, ExternalScrollId, GlyphInstance, SpatialId};
 
 pub use style::dom::OpaqueNode;
 
+/// The display list of a document, with its items in painting order.
 #[derive(Serialize)]
-pub struct DisplayList {}
+pub struct DisplayList {
+    pub list: Vec<DisplayItem>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        DisplayList { list: Vec::new() }
+    }
+}
 
 impl gfx_traits::DisplayList for DisplayList {
+    /// Analyze the display list to figure out if this may be the first
+    /// contentful paint (i.e. the display list contains items of type text).
+    /// Used by metrics.
     fn is_contentful(&self) -> bool {
-        false
+        self.list.iter().any(|item| match *item {
+            DisplayItem::Text(_) => true,
+            DisplayItem::Rectangle(_) | DisplayItem::Border(_) => false,
+        })
+    }
+}
+
+/// One drawing command in the list.
+#[derive(Serialize)]
+pub enum DisplayItem {
+    Rectangle(Box<CommonDisplayItem<wr::RectangleDisplayItem>>),
+    Text(Box<CommonDisplayItem<wr::TextDisplayItem, Vec<GlyphInstance>>>),
+    Border(Box<CommonDisplayItem<wr::BorderDisplayItem>>),
+}
+
+/// Information common to all display items.
+#[derive(Clone, Serialize)]
+pub struct BaseDisplayItem {
+    /// Metadata attached to this display item.
+    pub metadata: DisplayItemMetadata,
+
+    /// The clip rectangle to use for this item.
+    pub clip_rect: LayoutRect,
+}
+
+impl BaseDisplayItem {
+    pub fn new(metadata: DisplayItemMetadata, clip_rect: LayoutRect) -> BaseDisplayItem {
+        BaseDisplayItem {
+            metadata,
+            clip_rect,
+        }
+    }
+}
+
+pub fn empty_common_item_properties() -> CommonItemProperties {
+    CommonItemProperties {
+        clip_rect: LayoutRect::max_rect(),
+        clip_id: ClipId::root(wr::PipelineId::dummy()),
+        spatial_id: SpatialId::root_scroll_node(wr::PipelineId::dummy()),
+        hit_info: None,
+        is_backface_visible: false,
+    }
+}
+
+/// Metadata attached to each display item. This is useful for performing auxiliary threads with
+/// the display list involving hit testing: finding the originating DOM node and determining the
+/// cursor to use when the element is hovered over.
+#[derive(Clone, Copy, Serialize)]
+pub struct DisplayItemMetadata {
+    /// The DOM node from which this display item originated.
+    pub node: OpaqueNode,
+    /// The value of the `cursor` property when the mouse hovers over this display item. If `None`,
+    /// this display item is ineligible for pointer events (`pointer-events: none`).
+    pub pointing: Option<u16>,
+}
+
+#[derive(Serialize)]
+pub struct CommonDisplayItem<T, U = ()> {
+    pub base: BaseDisplayItem,
+    pub item: T,
+    pub data: U,
+}
+
+impl<T> CommonDisplayItem<T> {
+    pub fn new(base: BaseDisplayItem, item: T) -> Box<CommonDisplayItem<T>> {
+        Box::new(CommonDisplayItem {
+            base,
+            item,
+            data: (),
+        })
+    }
+}
+
+impl<T, U> CommonDisplayItem<T, U> {
+    pub fn with_data(base: BaseDisplayItem, item: T, data: U) -> Box<CommonDisplayItem<T, U>> {
+        Box::new(CommonDisplayItem { base, item, data })
     }
 }
 
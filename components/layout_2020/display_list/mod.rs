@@ -3,8 +3,10 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 pub use self::builder::IndexableText;
+pub use self::conversions::ToLayout;
 pub use self::webrender_helpers::WebRenderDisplayListConverter;
 
 mod builder;
+mod conversions;
 pub mod items;
 mod webrender_helpers;
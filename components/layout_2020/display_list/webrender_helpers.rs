@@ -2,25 +2,82 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::display_list::items::DisplayList;
+use crate::display_list::items::{BaseDisplayItem, DisplayItem, DisplayList};
 use msg::constellation_msg::PipelineId;
 use webrender_api::units::LayoutSize;
-use webrender_api::{self, DisplayListBuilder};
+use webrender_api::{self, ClipId, CommonItemProperties, DisplayItem as WrDisplayItem};
+use webrender_api::{DisplayListBuilder, SpatialId};
 
 pub trait WebRenderDisplayListConverter {
     fn convert_to_webrender(&mut self, pipeline_id: PipelineId) -> DisplayListBuilder;
 }
 
+trait WebRenderDisplayItemConverter {
+    fn convert_to_webrender(
+        &mut self,
+        pipeline_id: webrender_api::PipelineId,
+        builder: &mut DisplayListBuilder,
+    );
+}
+
 impl WebRenderDisplayListConverter for DisplayList {
     fn convert_to_webrender(&mut self, pipeline_id: PipelineId) -> DisplayListBuilder {
         let webrender_pipeline = pipeline_id.to_webrender();
 
-        let builder = DisplayListBuilder::with_capacity(
+        let mut builder = DisplayListBuilder::with_capacity(
             webrender_pipeline,
             LayoutSize::zero(),
             1024 * 1024, // 1 MB of space
         );
 
+        for item in &mut self.list {
+            item.convert_to_webrender(webrender_pipeline, &mut builder);
+        }
+
         builder
     }
 }
+
+impl WebRenderDisplayItemConverter for DisplayItem {
+    fn convert_to_webrender(
+        &mut self,
+        pipeline_id: webrender_api::PipelineId,
+        builder: &mut DisplayListBuilder,
+    ) {
+        match *self {
+            DisplayItem::Rectangle(ref mut item) => {
+                item.item.common = build_common_item_properties(&item.base, pipeline_id);
+                builder.push_item(&WrDisplayItem::Rectangle(item.item));
+            },
+            DisplayItem::Text(ref mut item) => {
+                item.item.common = build_common_item_properties(&item.base, pipeline_id);
+                builder.push_item(&WrDisplayItem::Text(item.item));
+                builder.push_iter(item.data.iter());
+            },
+            DisplayItem::Border(ref mut item) => {
+                item.item.common = build_common_item_properties(&item.base, pipeline_id);
+                builder.push_item(&WrDisplayItem::Border(item.item));
+            },
+        }
+    }
+}
+
+fn build_common_item_properties(
+    base: &BaseDisplayItem,
+    pipeline_id: webrender_api::PipelineId,
+) -> CommonItemProperties {
+    let tag = match base.metadata.pointing {
+        Some(cursor) => Some((base.metadata.node.0 as u64, cursor)),
+        None => None,
+    };
+    // There are no stacking contexts or scrolling yet, so every item is
+    // positioned and clipped relative to the root of the pipeline.
+    CommonItemProperties {
+        clip_rect: base.clip_rect,
+        spatial_id: SpatialId::root_scroll_node(pipeline_id),
+        clip_id: ClipId::root(pipeline_id),
+        // TODO(gw): Make use of the WR backface visibility functionality.
+        is_backface_visible: true,
+        hit_info: tag,
+    }
+}
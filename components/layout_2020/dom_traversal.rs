@@ -0,0 +1,82 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Walks the styled DOM on behalf of box construction.
+
+use crate::style_ext::{Display, DisplayGeneratingBox};
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::{PseudoElementType, ThreadSafeLayoutNode};
+use style::context::SharedStyleContext;
+use style::dom::NodeInfo;
+use style::properties::ComputedValues;
+use style::values::generics::counters::{Content, ContentItem};
+
+/// Receives the contents of an element, in tree order.
+pub(crate) trait TraversalHandler<Node> {
+    fn handle_text(&mut self, node: Node, text: String, parent_style: &ServoArc<ComputedValues>);
+
+    /// Called for the elements which generate a box, that is the ones whose
+    /// `display` is not `none`.
+    fn handle_element(
+        &mut self,
+        node: Node,
+        style: &ServoArc<ComputedValues>,
+        display: DisplayGeneratingBox,
+    );
+}
+
+/// Hands the children of the given element, including its `::before` and
+/// `::after` pseudo-elements, to the given handler.
+pub(crate) fn traverse_children_of<Node>(
+    parent: Node,
+    context: &SharedStyleContext,
+    handler: &mut impl TraversalHandler<Node>,
+) where
+    Node: ThreadSafeLayoutNode,
+{
+    match parent.get_pseudo_element_type() {
+        PseudoElementType::Before | PseudoElementType::After => {
+            traverse_generated_content(parent, context, handler);
+            return;
+        },
+        _ => {},
+    }
+
+    for child in parent.children() {
+        if child.is_text_node() {
+            handler.handle_text(child, child.node_text_content(), &child.style(context));
+        } else if child.is_element() {
+            let style = child.style(context);
+            match Display::from(style.get_box().display) {
+                // The descendants of elements which are not rendered are not
+                // styled either.
+                Display::None => {},
+                Display::GeneratingBox(display) => handler.handle_element(child, &style, display),
+            }
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/css-content/#content-property>
+///
+/// Only strings are supported for now.
+fn traverse_generated_content<Node>(
+    pseudo_element: Node,
+    context: &SharedStyleContext,
+    handler: &mut impl TraversalHandler<Node>,
+) where
+    Node: ThreadSafeLayoutNode,
+{
+    let style = pseudo_element.style(context);
+    if let Content::Items(ref items) = style.get_counters().content {
+        let text: String = items
+            .iter()
+            .filter_map(|item| match *item {
+                ContentItem::String(ref string) => Some(&**string),
+                _ => None,
+            })
+            .collect();
+        handler.handle_text(pseudo_element, text, &style);
+    }
+}
@@ -0,0 +1,349 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Box construction for block containers, from the styled DOM.
+
+use crate::dom_traversal::{traverse_children_of, TraversalHandler};
+use crate::flow::inline::{InlineBox, InlineFormattingContext, InlineLevelBox, TextRun};
+use crate::flow::{BlockContainer, BlockFormattingContext, BlockLevelBox, FloatBox};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::positioned::AbsolutelyPositionedBox;
+use crate::style_ext::{DisplayGeneratingBox, DisplayInside, DisplayOutside};
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use std::mem;
+use style::context::SharedStyleContext;
+use style::properties::ComputedValues;
+use style::selector_parser::PseudoElement;
+
+impl BlockFormattingContext {
+    pub(crate) fn construct<Node: ThreadSafeLayoutNode>(
+        context: &SharedStyleContext,
+        node: Node,
+        style: &ServoArc<ComputedValues>,
+    ) -> Self {
+        BlockFormattingContext {
+            contents: BlockContainer::construct(context, node, style),
+        }
+    }
+}
+
+impl BlockContainer {
+    fn construct<Node: ThreadSafeLayoutNode>(
+        context: &SharedStyleContext,
+        node: Node,
+        style: &ServoArc<ComputedValues>,
+    ) -> Self {
+        let mut builder = BlockContainerBuilder {
+            context,
+            node,
+            block_container_style: style,
+            block_level_boxes: Vec::new(),
+            ongoing_inline_formatting_context: InlineFormattingContext::default(),
+            ongoing_inline_boxes_stack: Vec::new(),
+            anonymous_style: None,
+        };
+        traverse_children_of(node, context, &mut builder);
+        debug_assert!(builder.ongoing_inline_boxes_stack.is_empty());
+
+        if builder.block_level_boxes.is_empty() {
+            let mut ifc = builder.ongoing_inline_formatting_context;
+            trim_trailing_white_space(&mut ifc.inline_level_boxes);
+            return BlockContainer::InlineFormattingContext(ifc);
+        }
+        builder.end_ongoing_inline_formatting_context();
+        BlockContainer::BlockLevelBoxes(builder.block_level_boxes)
+    }
+}
+
+/// Collects the boxes of the children of a block container. As long as only
+/// inline-level children are found they go into an inline formatting
+/// context; block-level children turn the container into one of block-level
+/// boxes, in which inline-level content is wrapped in anonymous blocks.
+///
+/// <https://drafts.csswg.org/css2/visuren.html#anonymous-block-level>
+struct BlockContainerBuilder<'a, 'b, Node> {
+    context: &'a SharedStyleContext<'b>,
+    node: Node,
+    block_container_style: &'a ServoArc<ComputedValues>,
+    block_level_boxes: Vec<BlockLevelBox>,
+    ongoing_inline_formatting_context: InlineFormattingContext,
+    /// The inline boxes we are currently in, innermost last.
+    ongoing_inline_boxes_stack: Vec<InlineBox>,
+    /// The style of the anonymous blocks wrapping inline-level content, lazily
+    /// computed.
+    anonymous_style: Option<ServoArc<ComputedValues>>,
+}
+
+impl<'a, 'b, Node> TraversalHandler<Node> for BlockContainerBuilder<'a, 'b, Node>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    fn handle_text(&mut self, node: Node, input: String, parent_style: &ServoArc<ComputedValues>) {
+        // https://drafts.csswg.org/css-text/#white-space-phase-1
+        // Only `white-space: normal` is supported for now: any sequence of
+        // white space collapses into a single space, and there is none at the
+        // start of a line.
+        let mut previous_is_space = self.ongoing_inline_content_ends_with_white_space();
+        let mut output = String::with_capacity(input.len());
+        for character in input.chars() {
+            if character.is_ascii_whitespace() {
+                if !previous_is_space {
+                    output.push(' ');
+                }
+                previous_is_space = true;
+            } else {
+                output.push(character);
+                previous_is_space = false;
+            }
+        }
+        if output.is_empty() {
+            return;
+        }
+
+        let inline_level_boxes = self.current_inline_level_boxes();
+        if let Some(InlineLevelBox::TextRun(text_run)) = inline_level_boxes.last_mut() {
+            text_run.text.push_str(&output);
+            return;
+        }
+        inline_level_boxes.push(InlineLevelBox::TextRun(TextRun {
+            tag: node.opaque(),
+            parent_style: parent_style.clone(),
+            text: output,
+        }));
+    }
+
+    fn handle_element(
+        &mut self,
+        node: Node,
+        style: &ServoArc<ComputedValues>,
+        display: DisplayGeneratingBox,
+    ) {
+        // Out-of-flow boxes were blockified, and establish an independent
+        // formatting context whatever their `display`. They stay where they
+        // are found in the box tree, which gives their static position.
+        if style.out_of_flow_positioned() {
+            let contents =
+                IndependentFormattingContext::construct(self.context, node, style.clone());
+            let box_ = AbsolutelyPositionedBox { contents };
+            if self.has_ongoing_inline_content() {
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(box_));
+            } else {
+                self.block_level_boxes
+                    .push(BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(box_));
+            }
+            return;
+        }
+        if style.floated() {
+            let contents =
+                IndependentFormattingContext::construct(self.context, node, style.clone());
+            let box_ = FloatBox { contents };
+            if self.has_ongoing_inline_content() {
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::OutOfFlowFloatBox(box_));
+            } else {
+                self.block_level_boxes
+                    .push(BlockLevelBox::OutOfFlowFloatBox(box_));
+            }
+            return;
+        }
+        match display.outside {
+            DisplayOutside::Inline => {
+                let inline_level_box = match display.inside {
+                    DisplayInside::Flow => self.handle_inline_box(node, style),
+                    DisplayInside::FlowRoot => InlineLevelBox::Atomic(
+                        IndependentFormattingContext::construct(self.context, node, style.clone()),
+                    ),
+                };
+                self.current_inline_level_boxes().push(inline_level_box);
+            },
+            DisplayOutside::Block => {
+                let block_level_box = match display.inside {
+                    DisplayInside::Flow => BlockLevelBox::SameFormattingContextBlock {
+                        tag: node.opaque(),
+                        style: style.clone(),
+                        contents: BlockContainer::construct(self.context, node, style),
+                    },
+                    DisplayInside::FlowRoot => BlockLevelBox::Independent(
+                        IndependentFormattingContext::construct(self.context, node, style.clone()),
+                    ),
+                };
+                self.handle_block_level_box(block_level_box);
+            },
+        }
+    }
+}
+
+impl<'a, 'b, Node> BlockContainerBuilder<'a, 'b, Node>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    fn handle_inline_box(
+        &mut self,
+        node: Node,
+        style: &ServoArc<ComputedValues>,
+    ) -> InlineLevelBox {
+        self.ongoing_inline_boxes_stack.push(InlineBox {
+            tag: node.opaque(),
+            style: style.clone(),
+            first_fragment: true,
+            last_fragment: false,
+            children: Vec::new(),
+        });
+        let context = self.context;
+        traverse_children_of(node, context, self);
+        let mut inline_box = self
+            .ongoing_inline_boxes_stack
+            .pop()
+            .expect("no ongoing inline box found");
+        inline_box.last_fragment = true;
+        InlineLevelBox::InlineBox(inline_box)
+    }
+
+    fn handle_block_level_box(&mut self, block_level_box: BlockLevelBox) {
+        // A block-level box inside of inline boxes splits them: the part of
+        // each of them before the block goes into the current inline
+        // formatting context, and the rest of their contents will go into
+        // the next one.
+        let mut fragmented_inline_boxes =
+            self.ongoing_inline_boxes_stack
+                .iter_mut()
+                .rev()
+                .map(|ongoing| {
+                    let fragmented = InlineBox {
+                        tag: ongoing.tag,
+                        style: ongoing.style.clone(),
+                        first_fragment: ongoing.first_fragment,
+                        last_fragment: false,
+                        children: mem::replace(&mut ongoing.children, Vec::new()),
+                    };
+                    ongoing.first_fragment = false;
+                    fragmented
+                });
+        if let Some(innermost) = fragmented_inline_boxes.next() {
+            let mut fragmented_inline = InlineLevelBox::InlineBox(innermost);
+            for mut fragmented_parent in fragmented_inline_boxes {
+                fragmented_parent.children.push(fragmented_inline);
+                fragmented_inline = InlineLevelBox::InlineBox(fragmented_parent);
+            }
+            self.ongoing_inline_formatting_context
+                .inline_level_boxes
+                .push(fragmented_inline);
+        }
+
+        self.end_ongoing_inline_formatting_context();
+        self.block_level_boxes.push(block_level_box);
+    }
+
+    /// Wraps the inline-level content found so far in an anonymous block.
+    fn end_ongoing_inline_formatting_context(&mut self) {
+        let mut ifc = mem::replace(
+            &mut self.ongoing_inline_formatting_context,
+            InlineFormattingContext::default(),
+        );
+        trim_trailing_white_space(&mut ifc.inline_level_boxes);
+        if ifc.inline_level_boxes.is_empty() {
+            return;
+        }
+
+        let context = self.context;
+        let block_container_style = self.block_container_style;
+        let anonymous_style = self.anonymous_style.get_or_insert_with(|| {
+            context
+                .stylist
+                .style_for_anonymous::<Node::ConcreteElement>(
+                    &context.guards,
+                    &PseudoElement::ServoAnonymousBlock,
+                    block_container_style,
+                )
+        });
+        self.block_level_boxes
+            .push(BlockLevelBox::SameFormattingContextBlock {
+                tag: self.node.opaque(),
+                style: anonymous_style.clone(),
+                contents: BlockContainer::InlineFormattingContext(ifc),
+            });
+    }
+
+    fn current_inline_level_boxes(&mut self) -> &mut Vec<InlineLevelBox> {
+        match self.ongoing_inline_boxes_stack.last_mut() {
+            Some(ongoing) => &mut ongoing.children,
+            None => &mut self.ongoing_inline_formatting_context.inline_level_boxes,
+        }
+    }
+
+    /// Whether inline-level boxes were found since the last block-level box.
+    fn has_ongoing_inline_content(&self) -> bool {
+        !self.ongoing_inline_boxes_stack.is_empty() ||
+            !self
+                .ongoing_inline_formatting_context
+                .inline_level_boxes
+                .is_empty()
+    }
+
+    /// Whether the inline-level content collected so far in the current
+    /// inline formatting context is empty or ends with a space.
+    fn ongoing_inline_content_ends_with_white_space(&self) -> bool {
+        self.ongoing_inline_boxes_stack
+            .iter()
+            .rev()
+            .map(|ongoing| &ongoing.children)
+            .chain(Some(
+                &self.ongoing_inline_formatting_context.inline_level_boxes,
+            ))
+            .filter_map(|inline_level_boxes| ends_with_white_space(inline_level_boxes))
+            .next()
+            .unwrap_or(true)
+    }
+}
+
+/// Whether the last text in these boxes ends with a space, or `None` if they
+/// contain no text nor atomic inline.
+fn ends_with_white_space(inline_level_boxes: &[InlineLevelBox]) -> Option<bool> {
+    for inline_level_box in inline_level_boxes.iter().rev() {
+        match inline_level_box {
+            InlineLevelBox::TextRun(text_run) => {
+                if let Some(last) = text_run.text.chars().last() {
+                    return Some(last == ' ');
+                }
+            },
+            InlineLevelBox::InlineBox(inline_box) => {
+                if let Some(result) = ends_with_white_space(&inline_box.children) {
+                    return Some(result);
+                }
+            },
+            InlineLevelBox::Atomic(_) => return Some(false),
+            InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_) |
+            InlineLevelBox::OutOfFlowFloatBox(_) => {},
+        }
+    }
+    None
+}
+
+/// Removes the space at the end of the last line of an inline formatting
+/// context, if any. Returns whether any text or atomic inline was found.
+fn trim_trailing_white_space(inline_level_boxes: &mut [InlineLevelBox]) -> bool {
+    for inline_level_box in inline_level_boxes.iter_mut().rev() {
+        match inline_level_box {
+            InlineLevelBox::TextRun(text_run) => {
+                if !text_run.text.is_empty() {
+                    if text_run.text.ends_with(' ') {
+                        text_run.text.pop();
+                    }
+                    return true;
+                }
+            },
+            InlineLevelBox::InlineBox(inline_box) => {
+                if trim_trailing_white_space(&mut inline_box.children) {
+                    return true;
+                }
+            },
+            InlineLevelBox::Atomic(_) => return true,
+            InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_) |
+            InlineLevelBox::OutOfFlowFloatBox(_) => {},
+        }
+    }
+    false
+}
@@ -0,0 +1,332 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Floats, and the line boxes and blocks with `clear` that avoid them.
+//!
+//! <https://drafts.csswg.org/css2/visuren.html#floats>
+
+use crate::context::LayoutContext;
+use crate::flow::ContainingBlock;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{BoxFragment, CollapsedBlockMargins};
+use crate::positioned::{
+    adjust_static_positions, layout_hoisted_boxes_in_fragment, HoistedAbsolutelyPositionedBox,
+};
+use crate::sizing::clamp_between_extremums;
+use crate::style_ext::ComputedValuesExt;
+use app_units::Au;
+use std::cmp::{max, min};
+use style::computed_values::clear::T as Clear;
+use style::computed_values::float::T as Float;
+use style::logical_geometry::{LogicalMargin, LogicalRect, WritingMode};
+
+pub(crate) struct FloatBox {
+    pub contents: IndependentFormattingContext,
+}
+
+/// The floats placed so far in a block formatting context.
+///
+/// Positions given to and returned by its methods are relative to the
+/// content box of the block container being laid out, which is somewhere
+/// in the content box of the block formatting context root.
+pub(crate) struct FloatContext {
+    /// The margin boxes of the floats, relative to the content box of the
+    /// block formatting context root.
+    floats: Vec<PlacedFloat>,
+    /// The position of the content box of the current block container in
+    /// the one of the block formatting context root.
+    offset_inline: Au,
+    offset_block: Au,
+    /// The block-start of a float can't be above the one of an earlier
+    /// float.
+    ceiling: Au,
+}
+
+struct PlacedFloat {
+    side: FloatSide,
+    inline_start: Au,
+    inline_end: Au,
+    block_start: Au,
+    block_end: Au,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FloatSide {
+    InlineStart,
+    InlineEnd,
+}
+
+impl FloatSide {
+    fn new(float: Float, mode: WritingMode) -> Option<Self> {
+        let (left, right) = if mode.is_bidi_ltr() {
+            (FloatSide::InlineStart, FloatSide::InlineEnd)
+        } else {
+            (FloatSide::InlineEnd, FloatSide::InlineStart)
+        };
+        match float {
+            Float::None => None,
+            Float::Left => Some(left),
+            Float::Right => Some(right),
+        }
+    }
+}
+
+/// The floats found along a band of the block axis, and the inline range
+/// they leave free in a containing block.
+struct Band {
+    inline_start: Au,
+    inline_end: Au,
+    has_floats: bool,
+    /// Where the first of these floats ends, if any.
+    next_block_position: Option<Au>,
+}
+
+impl FloatContext {
+    pub fn new() -> Self {
+        FloatContext {
+            floats: Vec::new(),
+            offset_inline: Au(0),
+            offset_block: Au(0),
+            ceiling: Au(0),
+        }
+    }
+
+    /// Runs `f` with positions relative to a child block container, whose
+    /// content box starts at the given position in the current one.
+    pub fn with_offset<T>(
+        &mut self,
+        inline_start: Au,
+        block_start: Au,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        self.offset_inline += inline_start;
+        self.offset_block += block_start;
+        let result = f(self);
+        self.offset_inline -= inline_start;
+        self.offset_block -= block_start;
+        result
+    }
+
+    /// The inline range of a containing block of the given inline size that
+    /// is not taken by floats at the given block position.
+    pub fn available_inline_range(
+        &self,
+        block_position: Au,
+        containing_block_inline_size: Au,
+    ) -> (Au, Au) {
+        let band = self.band(
+            self.offset_block + block_position,
+            Au(0),
+            containing_block_inline_size,
+        );
+        (
+            band.inline_start - self.offset_inline,
+            band.inline_end - self.offset_inline,
+        )
+    }
+
+    /// The block position below the floats that an element with the given
+    /// `clear` must be placed after, if any.
+    pub fn clearance_position(&self, clear: Clear, mode: WritingMode) -> Option<Au> {
+        let (left, right) = match clear {
+            Clear::None => return None,
+            Clear::Left => (true, false),
+            Clear::Right => (false, true),
+            Clear::Both => (true, true),
+        };
+        let (clear_start, clear_end) = if mode.is_bidi_ltr() {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        self.floats
+            .iter()
+            .filter(|float| match float.side {
+                FloatSide::InlineStart => clear_start,
+                FloatSide::InlineEnd => clear_end,
+            })
+            .map(|float| float.block_end - self.offset_block)
+            .max()
+    }
+
+    /// How far below the content box of the block formatting context root
+    /// floats go, if they do.
+    pub fn block_end_of_floats(&self) -> Option<Au> {
+        self.floats
+            .iter()
+            .map(|float| float.block_end - self.offset_block)
+            .max()
+    }
+
+    /// Places the margin box of a float as high as possible, but not above
+    /// `min_block_start`, and as far as possible towards its side of the
+    /// containing block. Returns the start corner of the margin box.
+    fn place(
+        &mut self,
+        side: FloatSide,
+        inline_size: Au,
+        block_size: Au,
+        min_block_start: Au,
+        containing_block_inline_size: Au,
+    ) -> (Au, Au) {
+        let mut block_start = max(self.offset_block + min_block_start, self.ceiling);
+        let band = loop {
+            let band = self.band(block_start, block_size, containing_block_inline_size);
+            match band.next_block_position {
+                Some(next) if band.inline_end - band.inline_start < inline_size => {
+                    block_start = next
+                },
+                _ => break band,
+            }
+        };
+        let inline_start = match side {
+            FloatSide::InlineStart => band.inline_start,
+            FloatSide::InlineEnd if band.has_floats => band.inline_end - inline_size,
+            // A float wider than its containing block overflows it on the
+            // inline-end side, whatever its side.
+            FloatSide::InlineEnd => max(band.inline_start, band.inline_end - inline_size),
+        };
+        self.floats.push(PlacedFloat {
+            side,
+            inline_start,
+            inline_end: inline_start + inline_size,
+            block_start,
+            block_end: block_start + block_size,
+        });
+        self.ceiling = block_start;
+        (
+            inline_start - self.offset_inline,
+            block_start - self.offset_block,
+        )
+    }
+
+    /// `block_start` is relative to the block formatting context root.
+    fn band(&self, block_start: Au, block_size: Au, containing_block_inline_size: Au) -> Band {
+        let block_end = block_start + block_size;
+        let mut band = Band {
+            inline_start: self.offset_inline,
+            inline_end: self.offset_inline + containing_block_inline_size,
+            has_floats: false,
+            next_block_position: None,
+        };
+        let overlapping = self.floats.iter().filter(|float| {
+            float.block_end > block_start &&
+                (float.block_start < block_end || float.block_start == block_start)
+        });
+        for float in overlapping {
+            match float.side {
+                FloatSide::InlineStart => {
+                    band.inline_start = max(band.inline_start, float.inline_end)
+                },
+                FloatSide::InlineEnd => band.inline_end = min(band.inline_end, float.inline_start),
+            }
+            band.has_floats = true;
+            band.next_block_position = Some(
+                band.next_block_position
+                    .map_or(float.block_end, |next| min(next, float.block_end)),
+            );
+        }
+        band
+    }
+}
+
+impl FloatBox {
+    /// Lays out the float and places it in `float_context`, no higher than
+    /// `min_block_start`. The fragment is at its final position.
+    ///
+    /// <https://drafts.csswg.org/css2/visudet.html#float-width>
+    pub(crate) fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        float_context: &mut FloatContext,
+        min_block_start: Au,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> BoxFragment {
+        let style = &self.contents.style;
+        let side = FloatSide::new(style.get_box().float, containing_block.mode)
+            .expect("float box without a float side");
+        let padding = style.used_padding(containing_block);
+        let border = style.used_border_width(containing_block);
+        let computed_margin = style.used_margin(containing_block);
+        let margin = LogicalMargin::new(
+            containing_block.mode,
+            computed_margin.block_start.unwrap_or(Au(0)),
+            computed_margin.inline_end.unwrap_or(Au(0)),
+            computed_margin.block_end.unwrap_or(Au(0)),
+            computed_margin.inline_start.unwrap_or(Au(0)),
+        );
+        let pbm = padding + border + margin;
+        let box_size = style.used_box_size(containing_block);
+        let min_box_size = style.used_min_box_size(containing_block);
+        let max_box_size = style.used_max_box_size(containing_block);
+
+        let inline_size = box_size.inline.unwrap_or_else(|| {
+            let available_size = containing_block.inline_size - pbm.inline_start_end();
+            self.contents
+                .content_sizes(layout_context)
+                .shrink_to_fit(available_size)
+        });
+        let inline_size =
+            clamp_between_extremums(inline_size, min_box_size.inline, max_box_size.inline);
+        let block_size = box_size
+            .block
+            .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode: style.writing_mode_for_contents(containing_block.mode),
+        };
+        let first_hoisted_box = hoisted_boxes.len();
+        let flow_layout = self.contents.layout(
+            layout_context,
+            &containing_block_for_children,
+            hoisted_boxes,
+        );
+        let block_size = block_size.unwrap_or_else(|| {
+            clamp_between_extremums(
+                flow_layout.content_block_size,
+                min_box_size.block,
+                max_box_size.block,
+            )
+        });
+
+        let (inline_start, block_start) = float_context.place(
+            side,
+            inline_size + pbm.inline_start_end(),
+            block_size + pbm.block_start_end(),
+            min_block_start,
+            containing_block.inline_size,
+        );
+        let content_rect = LogicalRect::new(
+            containing_block.mode,
+            inline_start + pbm.inline_start,
+            block_start + pbm.block_start,
+            inline_size,
+            block_size,
+        );
+        let mut fragment = BoxFragment {
+            tag: self.contents.tag,
+            style: style.clone(),
+            children: flow_layout.fragments,
+            content_rect,
+            padding,
+            border,
+            margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+        };
+        layout_hoisted_boxes_in_fragment(
+            layout_context,
+            &mut fragment,
+            containing_block.mode,
+            hoisted_boxes,
+            first_hoisted_box,
+        );
+        adjust_static_positions(
+            &mut hoisted_boxes[first_hoisted_box..],
+            &fragment.content_rect.start,
+        );
+        fragment
+    }
+}
@@ -0,0 +1,598 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Inline formatting contexts: breaking inline-level content into line boxes.
+//!
+//! <https://drafts.csswg.org/css2/visuren.html#inline-formatting>
+
+use crate::context::{with_thread_local_font_context, LayoutContext};
+use crate::display_list::items::OpaqueNode;
+use crate::flow::float::{FloatBox, FloatContext};
+use crate::flow::{ContainingBlock, FlowLayout};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::TextFragment;
+use crate::fragments::{AnonymousFragment, BoxFragment, CollapsedBlockMargins, Fragment};
+use crate::positioned::{AbsolutelyPositionedBox, HoistedAbsolutelyPositionedBox};
+use crate::sizing::{inline_padding_border_margin, outer_inline_content_sizes, ContentSizes};
+use crate::style_ext::ComputedValuesExt;
+use crate::ServoArc;
+use app_units::Au;
+use gfx::font::{FontMetrics, ShapingFlags, ShapingOptions};
+use gfx::text::glyph::ByteIndex;
+use gfx::text::text_run::{GlyphRun, TextRun as ShapedTextRun};
+use ordered_float::NotNan;
+use range::Range;
+use std::cmp::max;
+use std::mem;
+use style::computed_values::text_align::T as TextAlign;
+use style::logical_geometry::{LogicalMargin, LogicalPoint, LogicalRect, WritingMode};
+use style::properties::ComputedValues;
+use style::values::generics::text::LineHeight;
+use unicode_script::Script;
+use webrender_api::FontInstanceKey;
+
+#[derive(Default)]
+pub(crate) struct InlineFormattingContext {
+    pub inline_level_boxes: Vec<InlineLevelBox>,
+}
+
+pub(crate) enum InlineLevelBox {
+    InlineBox(InlineBox),
+    TextRun(TextRun),
+    /// An inline-level box that establishes a new formatting context, such as
+    /// `display: inline-block`.
+    Atomic(IndependentFormattingContext),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+    OutOfFlowFloatBox(FloatBox),
+}
+
+/// A non-replaced inline box. When a block-level box is found in the middle
+/// of its contents, it is split in several box tree nodes: `first_fragment`
+/// and `last_fragment` say which sides of the box are its actual edges.
+pub(crate) struct InlineBox {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    pub first_fragment: bool,
+    pub last_fragment: bool,
+    pub children: Vec<InlineLevelBox>,
+}
+
+/// <https://drafts.csswg.org/css-display/#css-text-run>
+pub(crate) struct TextRun {
+    pub tag: OpaqueNode,
+    pub parent_style: ServoArc<ComputedValues>,
+    /// The text, once white space was processed.
+    pub text: String,
+}
+
+struct InlineNestingLevelState<'box_tree> {
+    remaining_boxes: std::slice::Iter<'box_tree, InlineLevelBox>,
+    fragments_so_far: Vec<Fragment>,
+    /// The position on the current line where the fragments of this level
+    /// start, relative to the start of the line.
+    inline_start: Au,
+    max_block_size_of_fragments_so_far: Au,
+    /// The writing mode of the fragments of this level.
+    mode: WritingMode,
+}
+
+/// The part of an inline box laid out so far on the current line.
+struct PartialInlineBoxFragment<'box_tree> {
+    tag: OpaqueNode,
+    style: ServoArc<ComputedValues>,
+    /// The start corner of the content box, relative to the fragments of the
+    /// parent nesting level.
+    start_corner: LogicalPoint<Au>,
+    padding: LogicalMargin<Au>,
+    border: LogicalMargin<Au>,
+    margin: LogicalMargin<Au>,
+    last_box_tree_fragment: bool,
+    parent_nesting_level: InlineNestingLevelState<'box_tree>,
+}
+
+struct InlineFormattingContextState<'box_tree, 'a> {
+    containing_block: &'a ContainingBlock,
+    float_context: &'a mut FloatContext,
+    hoisted_boxes: &'a mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    text_align: TextAlign,
+    line_boxes: LinesBoxes,
+    /// The inline position of the next fragment, relative to the start of
+    /// the current line.
+    inline_position: Au,
+    partial_inline_boxes_stack: Vec<PartialInlineBoxFragment<'box_tree>>,
+    current_nesting_level: InlineNestingLevelState<'box_tree>,
+}
+
+struct LinesBoxes {
+    boxes: Vec<Fragment>,
+    next_line_block_position: Au,
+}
+
+/// Computes the intrinsic inline sizes of an inline formatting context:
+/// lines are only broken at spaces.
+struct ContentSizesComputation<'a> {
+    layout_context: &'a LayoutContext<'a>,
+    /// The sizes of the lines and words traversed so far, except for the
+    /// current ones.
+    sizes: ContentSizes,
+    current_line: Au,
+    current_word: Au,
+}
+
+impl InlineFormattingContext {
+    /// `style` is the style of the block container, which gives
+    /// `text-align`.
+    pub(super) fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        style: &ComputedValues,
+        float_context: &mut FloatContext,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> FlowLayout {
+        let mut ifc = InlineFormattingContextState {
+            containing_block,
+            float_context,
+            hoisted_boxes,
+            text_align: style.get_inherited_text().text_align,
+            line_boxes: LinesBoxes {
+                boxes: Vec::new(),
+                next_line_block_position: Au(0),
+            },
+            inline_position: Au(0),
+            partial_inline_boxes_stack: Vec::new(),
+            current_nesting_level: InlineNestingLevelState {
+                remaining_boxes: self.inline_level_boxes.iter(),
+                fragments_so_far: Vec::with_capacity(self.inline_level_boxes.len()),
+                inline_start: Au(0),
+                max_block_size_of_fragments_so_far: Au(0),
+                mode: containing_block.mode,
+            },
+        };
+        loop {
+            if let Some(child) = ifc.current_nesting_level.remaining_boxes.next() {
+                match child {
+                    InlineLevelBox::InlineBox(inline_box) => {
+                        let partial = inline_box.start_layout(&mut ifc);
+                        ifc.partial_inline_boxes_stack.push(partial)
+                    },
+                    InlineLevelBox::TextRun(text_run) => text_run.layout(layout_context, &mut ifc),
+                    InlineLevelBox::Atomic(_) => {
+                        // TODO: lay out inline-blocks.
+                    },
+                    InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
+                        let (line_start, _) = ifc.current_line_inline_range();
+                        let hoisted = box_.hoist(
+                            line_start + ifc.inline_position,
+                            ifc.line_boxes.next_line_block_position,
+                        );
+                        ifc.hoisted_boxes.push(hoisted);
+                    },
+                    InlineLevelBox::OutOfFlowFloatBox(float_box) => {
+                        // Floats are placed relative to the block container,
+                        // next to the current line.
+                        let fragment = float_box.layout(
+                            layout_context,
+                            containing_block,
+                            ifc.float_context,
+                            ifc.line_boxes.next_line_block_position,
+                            ifc.hoisted_boxes,
+                        );
+                        ifc.line_boxes.boxes.push(Fragment::Box(fragment));
+                    },
+                }
+            } else if let Some(mut partial) = ifc.partial_inline_boxes_stack.pop() {
+                partial.finish_layout(
+                    &mut ifc.current_nesting_level,
+                    &mut ifc.inline_position,
+                    false,
+                );
+                ifc.current_nesting_level = partial.parent_nesting_level
+            } else {
+                let line_inline_range = ifc.current_line_inline_range();
+                ifc.line_boxes.finish_line(
+                    &mut ifc.current_nesting_level,
+                    containing_block.mode,
+                    line_inline_range,
+                    ifc.inline_position,
+                    ifc.text_align,
+                );
+                let content_block_size = ifc.line_boxes.next_line_block_position;
+                return FlowLayout {
+                    fragments: ifc.line_boxes.boxes,
+                    content_block_size,
+                    collapsible_margins_in_children: CollapsedBlockMargins {
+                        // Margins collapse through an inline formatting
+                        // context whose line boxes are all empty.
+                        collapsed_through: content_block_size == Au(0),
+                        ..CollapsedBlockMargins::zero()
+                    },
+                };
+            }
+        }
+    }
+
+    pub(super) fn content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        let mut computation = ContentSizesComputation {
+            layout_context,
+            sizes: ContentSizes::zero(),
+            current_line: Au(0),
+            current_word: Au(0),
+        };
+        computation.traverse(&self.inline_level_boxes);
+        computation.end_word();
+        computation.sizes.max_content =
+            max(computation.sizes.max_content, computation.current_line);
+        computation.sizes
+    }
+}
+
+impl ContentSizesComputation<'_> {
+    fn traverse(&mut self, inline_level_boxes: &[InlineLevelBox]) {
+        for inline_level_box in inline_level_boxes {
+            match inline_level_box {
+                InlineLevelBox::InlineBox(inline_box) => {
+                    let (start, end) = inline_padding_border_margin(&inline_box.style);
+                    if inline_box.first_fragment {
+                        self.add(start);
+                    }
+                    self.traverse(&inline_box.children);
+                    if inline_box.last_fragment {
+                        self.add(end);
+                    }
+                },
+                InlineLevelBox::TextRun(text_run) => {
+                    let (_, _, runs) = text_run.break_and_shape(self.layout_context);
+                    for run in &runs {
+                        let advance = run.glyph_store.advance_for_byte_range(
+                            &Range::new(ByteIndex(0), run.glyph_store.len()),
+                            Au(0),
+                        );
+                        if run.glyph_store.is_whitespace() {
+                            self.end_word();
+                            self.current_line += advance;
+                        } else {
+                            self.add(advance);
+                        }
+                    }
+                },
+                InlineLevelBox::Atomic(contents) => {
+                    let outer = outer_inline_content_sizes(&contents.style, || {
+                        contents.content_sizes(self.layout_context)
+                    });
+                    self.end_word();
+                    self.sizes.min_content = max(self.sizes.min_content, outer.min_content);
+                    self.current_line += outer.max_content;
+                },
+                InlineLevelBox::OutOfFlowFloatBox(FloatBox { contents }) => {
+                    let outer = outer_inline_content_sizes(&contents.style, || {
+                        contents.content_sizes(self.layout_context)
+                    });
+                    self.sizes = self.sizes.max(outer);
+                },
+                InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => {},
+            }
+        }
+    }
+
+    fn add(&mut self, inline_size: Au) {
+        self.current_word += inline_size;
+        self.current_line += inline_size;
+    }
+
+    fn end_word(&mut self) {
+        self.sizes.min_content = max(self.sizes.min_content, self.current_word);
+        self.current_word = Au(0);
+    }
+}
+
+impl InlineFormattingContextState<'_, '_> {
+    /// The inline range of the current line that floats leave free, relative
+    /// to the content box of the block container.
+    fn current_line_inline_range(&self) -> (Au, Au) {
+        self.float_context.available_inline_range(
+            self.line_boxes.next_line_block_position,
+            self.containing_block.inline_size,
+        )
+    }
+}
+
+impl LinesBoxes {
+    /// `inline_range` is the part of the containing block the line can take.
+    fn finish_line(
+        &mut self,
+        top_nesting_level: &mut InlineNestingLevelState,
+        mode: WritingMode,
+        inline_range: (Au, Au),
+        line_inline_size: Au,
+        text_align: TextAlign,
+    ) {
+        let block_size = mem::replace(
+            &mut top_nesting_level.max_block_size_of_fragments_so_far,
+            Au(0),
+        );
+        let (range_start, range_end) = inline_range;
+        let free_space = max(range_end - range_start - line_inline_size, Au(0));
+        let rect = LogicalRect::new(
+            mode,
+            range_start + line_inline_start(text_align, mode, free_space),
+            self.next_line_block_position,
+            line_inline_size,
+            block_size,
+        );
+        self.next_line_block_position += block_size;
+        self.boxes.push(Fragment::Anonymous(AnonymousFragment {
+            rect,
+            children: mem::replace(&mut top_nesting_level.fragments_so_far, Vec::new()),
+        }));
+    }
+}
+
+/// The offset of a line box from the inline-start edge of the space
+/// available for it, given the free space left by its contents. `justify` is
+/// not supported yet, and behaves like `start`.
+///
+/// <https://drafts.csswg.org/css-text/#text-align-property>
+fn line_inline_start(text_align: TextAlign, mode: WritingMode, free_space: Au) -> Au {
+    let ltr = mode.is_bidi_ltr();
+    match text_align {
+        TextAlign::Start | TextAlign::Justify => Au(0),
+        TextAlign::End => free_space,
+        TextAlign::Center | TextAlign::ServoCenter => free_space / 2,
+        TextAlign::Left | TextAlign::ServoLeft => {
+            if ltr {
+                Au(0)
+            } else {
+                free_space
+            }
+        },
+        TextAlign::Right | TextAlign::ServoRight => {
+            if ltr {
+                free_space
+            } else {
+                Au(0)
+            }
+        },
+    }
+}
+
+impl InlineBox {
+    fn start_layout<'box_tree>(
+        &'box_tree self,
+        ifc: &mut InlineFormattingContextState<'box_tree, '_>,
+    ) -> PartialInlineBoxFragment<'box_tree> {
+        let style = self.style.clone();
+        let parent_mode = ifc.current_nesting_level.mode;
+        let containing_block = ContainingBlock {
+            mode: parent_mode,
+            ..*ifc.containing_block
+        };
+        let mut padding = style.used_padding(&containing_block);
+        let mut border = style.used_border_width(&containing_block);
+        let used_margin = style.used_margin(&containing_block);
+        // Block-direction margins do not apply to non-replaced inline boxes,
+        // and their padding and borders do not take room in the line box.
+        let mut margin = LogicalMargin::new(
+            parent_mode,
+            Au(0),
+            used_margin.inline_end.unwrap_or(Au(0)),
+            Au(0),
+            used_margin.inline_start.unwrap_or(Au(0)),
+        );
+        if self.first_fragment {
+            ifc.inline_position += padding.inline_start + border.inline_start + margin.inline_start;
+        } else {
+            padding.inline_start = Au(0);
+            border.inline_start = Au(0);
+            margin.inline_start = Au(0);
+        }
+        let start_corner = LogicalPoint::new(
+            parent_mode,
+            ifc.inline_position - ifc.current_nesting_level.inline_start,
+            Au(0),
+        );
+        let mode = style.writing_mode_for_contents(parent_mode);
+        PartialInlineBoxFragment {
+            tag: self.tag,
+            style,
+            start_corner,
+            padding,
+            border,
+            margin,
+            last_box_tree_fragment: self.last_fragment,
+            parent_nesting_level: mem::replace(
+                &mut ifc.current_nesting_level,
+                InlineNestingLevelState {
+                    remaining_boxes: self.children.iter(),
+                    fragments_so_far: Vec::with_capacity(self.children.len()),
+                    inline_start: ifc.inline_position,
+                    max_block_size_of_fragments_so_far: Au(0),
+                    mode,
+                },
+            ),
+        }
+    }
+}
+
+impl<'box_tree> PartialInlineBoxFragment<'box_tree> {
+    /// Turns what was laid out of the box so far into a fragment of the
+    /// parent nesting level, either because the box ends or because the
+    /// line does.
+    fn finish_layout(
+        &mut self,
+        nesting_level: &mut InlineNestingLevelState,
+        inline_position: &mut Au,
+        at_line_break: bool,
+    ) {
+        let mut fragment = BoxFragment {
+            tag: self.tag,
+            style: self.style.clone(),
+            children: mem::replace(&mut nesting_level.fragments_so_far, Vec::new()),
+            content_rect: LogicalRect::new(
+                self.parent_nesting_level.mode,
+                self.start_corner.i,
+                self.start_corner.b,
+                *inline_position - nesting_level.inline_start,
+                nesting_level.max_block_size_of_fragments_so_far,
+            ),
+            padding: self.padding,
+            border: self.border,
+            margin: self.margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&self.margin),
+        };
+        let last_fragment = self.last_box_tree_fragment && !at_line_break;
+        if last_fragment {
+            *inline_position += fragment.padding.inline_end +
+                fragment.border.inline_end +
+                fragment.margin.inline_end;
+        } else {
+            fragment.padding.inline_end = Au(0);
+            fragment.border.inline_end = Au(0);
+            fragment.margin.inline_end = Au(0);
+        }
+        self.parent_nesting_level.max_block_size_of_fragments_so_far = max(
+            self.parent_nesting_level.max_block_size_of_fragments_so_far,
+            fragment.content_rect.size.block,
+        );
+        self.parent_nesting_level
+            .fragments_so_far
+            .push(Fragment::Box(fragment));
+    }
+}
+
+impl TextRun {
+    /// Shapes the text into glyph runs, each of which is either a word or
+    /// the white space following it, or returns `None` if no font is
+    /// available for the run's style.
+    fn break_and_shape(
+        &self,
+        layout_context: &LayoutContext,
+    ) -> Option<(FontMetrics, FontInstanceKey, Vec<GlyphRun>)> {
+        let font_style = self.parent_style.clone_font();
+        let shaping_options = ShapingOptions {
+            letter_spacing: None,
+            word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+            script: Script::Common,
+            flags: ShapingFlags::empty(),
+        };
+        with_thread_local_font_context(layout_context, |font_context| {
+            let font_group = font_context.font_group(font_style);
+            let font = font_group.borrow_mut().first(font_context)?;
+            let mut font = font.borrow_mut();
+            let (runs, _break_at_start) =
+                ShapedTextRun::break_and_shape(&mut font, &self.text, &shaping_options, &mut None);
+            Some((font.metrics.clone(), font.font_key, runs))
+        })
+    }
+
+    fn layout(&self, layout_context: &LayoutContext, ifc: &mut InlineFormattingContextState) {
+        if self.text.is_empty() {
+            return;
+        }
+        // Without any font, not even a fallback one, there is nothing to draw
+        // the text with, so the run takes up no space.
+        let (font_metrics, font_key, runs) = match self.break_and_shape(layout_context) {
+            Some(shaped) => shaped,
+            None => return,
+        };
+        let line_height = line_height(&self.parent_style, &font_metrics);
+        let font_block_size = font_metrics.ascent + font_metrics.descent;
+        // https://drafts.csswg.org/css2/visudet.html#leading
+        let half_leading = (line_height - font_block_size) / 2;
+
+        // Every glyph run is either a word or the white space following it,
+        // where lines can be broken.
+        let mut runs = runs.iter();
+        loop {
+            let mut glyphs = vec![];
+            let mut advance_width = Au(0);
+            let mut last_break_opportunity = None;
+            loop {
+                let next = runs.next();
+                if next.map_or(true, |run| run.glyph_store.is_whitespace()) {
+                    let (line_start, line_end) = ifc.current_line_inline_range();
+                    let available = line_end - line_start - ifc.inline_position;
+                    if advance_width > available {
+                        if let Some((len, width, iter)) = last_break_opportunity.take() {
+                            glyphs.truncate(len);
+                            advance_width = width;
+                            runs = iter;
+                        }
+                        break;
+                    }
+                }
+                if let Some(run) = next {
+                    if run.glyph_store.is_whitespace() {
+                        last_break_opportunity = Some((glyphs.len(), advance_width, runs.clone()));
+                    }
+                    glyphs.push(run.glyph_store.clone());
+                    advance_width += run.glyph_store.advance_for_byte_range(
+                        &Range::new(ByteIndex(0), run.glyph_store.len()),
+                        Au(0),
+                    );
+                } else {
+                    break;
+                }
+            }
+
+            let content_rect = LogicalRect::new(
+                ifc.current_nesting_level.mode,
+                ifc.inline_position - ifc.current_nesting_level.inline_start,
+                half_leading,
+                advance_width,
+                font_block_size,
+            );
+            ifc.inline_position += advance_width;
+            ifc.current_nesting_level.max_block_size_of_fragments_so_far = max(
+                ifc.current_nesting_level.max_block_size_of_fragments_so_far,
+                line_height,
+            );
+            ifc.current_nesting_level
+                .fragments_so_far
+                .push(Fragment::Text(TextFragment {
+                    tag: self.tag,
+                    parent_style: self.parent_style.clone(),
+                    content_rect,
+                    ascent: font_metrics.ascent,
+                    font_key,
+                    glyphs,
+                }));
+            if runs.as_slice().is_empty() {
+                break;
+            }
+
+            // New line
+            let line_inline_range = ifc.current_line_inline_range();
+            ifc.current_nesting_level.inline_start = Au(0);
+            let mut nesting_level = &mut ifc.current_nesting_level;
+            for partial in ifc.partial_inline_boxes_stack.iter_mut().rev() {
+                partial.finish_layout(nesting_level, &mut ifc.inline_position, true);
+                partial.start_corner.i = Au(0);
+                partial.padding.inline_start = Au(0);
+                partial.border.inline_start = Au(0);
+                partial.margin.inline_start = Au(0);
+                partial.parent_nesting_level.inline_start = Au(0);
+                nesting_level = &mut partial.parent_nesting_level;
+            }
+            ifc.line_boxes.finish_line(
+                nesting_level,
+                ifc.containing_block.mode,
+                line_inline_range,
+                ifc.inline_position,
+                ifc.text_align,
+            );
+            ifc.inline_position = Au(0);
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/css2/visudet.html#propdef-line-height>
+fn line_height(style: &ComputedValues, font_metrics: &FontMetrics) -> Au {
+    let font_size = style.get_font().font_size.size();
+    match style.get_inherited_text().line_height {
+        LineHeight::Normal => font_metrics.line_gap,
+        LineHeight::Number(number) => font_size.scale_by(number.0),
+        LineHeight::Length(length) => Au::from(length),
+    }
+}
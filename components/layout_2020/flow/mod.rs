@@ -0,0 +1,564 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Flow layout: block formatting contexts, the block-level boxes they
+//! contain, and the inline formatting contexts of block containers.
+//!
+//! <https://drafts.csswg.org/css2/visuren.html#normal-flow>
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::flow::float::{FloatBox, FloatContext};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{BoxFragment, CollapsedBlockMargins, CollapsedMargin, Fragment};
+use crate::positioned::{adjust_static_positions, layout_hoisted_boxes_in_fragment};
+use crate::positioned::{AbsolutelyPositionedBox, HoistedAbsolutelyPositionedBox};
+use crate::sizing::{clamp_between_extremums, outer_inline_content_sizes, ContentSizes};
+use crate::style_ext::ComputedValuesExt;
+use crate::ServoArc;
+use app_units::Au;
+use std::cmp::max;
+use std::mem;
+use style::logical_geometry::{LogicalMargin, LogicalRect, WritingMode};
+use style::properties::ComputedValues;
+
+mod construct;
+mod float;
+mod inline;
+mod root;
+
+pub(crate) use self::float::FloatBox;
+pub(crate) use self::inline::InlineFormattingContext;
+pub use self::root::{BoxTreeRoot, FragmentTreeRoot};
+
+/// The contents of a box that establishes a new block formatting context.
+///
+/// <https://drafts.csswg.org/css2/visuren.html#block-formatting>
+pub(crate) struct BlockFormattingContext {
+    pub contents: BlockContainer,
+}
+
+/// <https://drafts.csswg.org/css-display/#block-container>
+pub(crate) enum BlockContainer {
+    BlockLevelBoxes(Vec<BlockLevelBox>),
+    InlineFormattingContext(InlineFormattingContext),
+}
+
+pub(crate) enum BlockLevelBox {
+    SameFormattingContextBlock {
+        tag: OpaqueNode,
+        style: ServoArc<ComputedValues>,
+        contents: BlockContainer,
+    },
+    Independent(IndependentFormattingContext),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+    OutOfFlowFloatBox(FloatBox),
+}
+
+/// The rectangle in reference to which boxes are sized and positioned.
+///
+/// <https://drafts.csswg.org/css2/visudet.html#containing-block-details>
+#[derive(Clone, Copy)]
+pub(crate) struct ContainingBlock {
+    pub inline_size: Au,
+    /// `None` when the block size depends on the contents.
+    pub block_size: Option<Au>,
+    pub mode: WritingMode,
+}
+
+/// The result of laying out the contents of a block container.
+pub(crate) struct FlowLayout {
+    pub fragments: Vec<Fragment>,
+    pub content_block_size: Au,
+    /// The margins of the first and last in-flow children, when they are
+    /// adjoining with the ones of the block container itself.
+    pub collapsible_margins_in_children: CollapsedBlockMargins,
+}
+
+#[derive(Clone, Copy)]
+struct CollapsibleWithParentStartMargin(bool);
+
+impl BlockFormattingContext {
+    /// Absolutely positioned boxes found in the contents are added to
+    /// `hoisted_boxes`, unless a positioned box in them is their containing
+    /// block.
+    pub(crate) fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        style: &ComputedValues,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> FlowLayout {
+        let mut float_context = FloatContext::new();
+        let mut flow_layout = self.contents.layout(
+            layout_context,
+            containing_block,
+            style,
+            &mut float_context,
+            hoisted_boxes,
+            CollapsibleWithParentStartMargin(false),
+        );
+        // The margins of the children of a block formatting context root
+        // never collapse with its own margins.
+        flow_layout.content_block_size += flow_layout.collapsible_margins_in_children.end.solve();
+        flow_layout.collapsible_margins_in_children = CollapsedBlockMargins::zero();
+        // https://drafts.csswg.org/css2/visudet.html#root-height
+        if let Some(block_end_of_floats) = float_context.block_end_of_floats() {
+            flow_layout.content_block_size =
+                max(flow_layout.content_block_size, block_end_of_floats);
+        }
+        flow_layout
+    }
+
+    pub(crate) fn content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        self.contents.content_sizes(layout_context)
+    }
+}
+
+impl BlockContainer {
+    /// `style` is the style of the box this is the contents of.
+    fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        style: &ComputedValues,
+        float_context: &mut FloatContext,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+        collapsible_with_parent_start_margin: CollapsibleWithParentStartMargin,
+    ) -> FlowLayout {
+        match self {
+            BlockContainer::BlockLevelBoxes(child_boxes) => layout_block_level_children(
+                layout_context,
+                child_boxes,
+                containing_block,
+                float_context,
+                hoisted_boxes,
+                collapsible_with_parent_start_margin,
+            ),
+            BlockContainer::InlineFormattingContext(ifc) => ifc.layout(
+                layout_context,
+                containing_block,
+                style,
+                float_context,
+                hoisted_boxes,
+            ),
+        }
+    }
+
+    fn content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        match self {
+            BlockContainer::BlockLevelBoxes(child_boxes) => child_boxes
+                .iter()
+                .map(|child| child.content_sizes(layout_context))
+                .fold(ContentSizes::zero(), ContentSizes::max),
+            BlockContainer::InlineFormattingContext(ifc) => ifc.content_sizes(layout_context),
+        }
+    }
+}
+
+fn layout_block_level_children<'box_tree>(
+    layout_context: &LayoutContext,
+    child_boxes: &'box_tree [BlockLevelBox],
+    containing_block: &ContainingBlock,
+    float_context: &mut FloatContext,
+    hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    collapsible_with_parent_start_margin: CollapsibleWithParentStartMargin,
+) -> FlowLayout {
+    let mut placement_state = PlacementState {
+        next_in_flow_margin_collapses_with_parent_start_margin:
+            collapsible_with_parent_start_margin.0,
+        start_margin: CollapsedMargin::zero(),
+        current_margin: CollapsedMargin::zero(),
+        current_block_direction_position: Au(0),
+    };
+    let mut fragments = Vec::with_capacity(child_boxes.len());
+    for child in child_boxes {
+        let style = match child {
+            BlockLevelBox::SameFormattingContextBlock { style, .. } => style,
+            BlockLevelBox::Independent(contents) => &contents.style,
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
+                let static_block_position = placement_state.block_start_of_next_in_flow(Au(0));
+                hoisted_boxes.push(box_.hoist(Au(0), static_block_position));
+                continue;
+            },
+            BlockLevelBox::OutOfFlowFloatBox(float_box) => {
+                let fragment = float_box.layout(
+                    layout_context,
+                    containing_block,
+                    float_context,
+                    placement_state.block_start_of_next_in_flow(Au(0)),
+                    hoisted_boxes,
+                );
+                fragments.push(Fragment::Box(fragment));
+                continue;
+            },
+        };
+        let margin_block_start = style
+            .used_margin(containing_block)
+            .block_start
+            .unwrap_or(Au(0));
+        let clear = style.get_box().clear;
+        if let Some(position) = float_context.clearance_position(clear, containing_block.mode) {
+            placement_state.clear_floats(position, margin_block_start);
+        }
+
+        // Floats in the child are placed as if its margins did not collapse
+        // with the ones of its own children.
+        let block_start = placement_state.block_start_of_next_in_flow(margin_block_start);
+        let first_hoisted_box = hoisted_boxes.len();
+        let mut fragment = float_context.with_offset(Au(0), block_start, |float_context| {
+            child.layout(
+                layout_context,
+                containing_block,
+                float_context,
+                hoisted_boxes,
+            )
+        });
+        placement_state.place_fragment(&mut fragment);
+        adjust_static_positions(
+            &mut hoisted_boxes[first_hoisted_box..],
+            &fragment.content_rect.start,
+        );
+        fragments.push(Fragment::Box(fragment));
+    }
+    FlowLayout {
+        fragments,
+        content_block_size: placement_state.current_block_direction_position,
+        collapsible_margins_in_children: CollapsedBlockMargins {
+            collapsed_through: placement_state
+                .next_in_flow_margin_collapses_with_parent_start_margin,
+            start: placement_state.start_margin,
+            end: placement_state.current_margin,
+        },
+    }
+}
+
+/// Positions the in-flow children of a block container one after the other,
+/// collapsing their adjoining margins.
+struct PlacementState {
+    next_in_flow_margin_collapses_with_parent_start_margin: bool,
+    start_margin: CollapsedMargin,
+    current_margin: CollapsedMargin,
+    current_block_direction_position: Au,
+}
+
+impl PlacementState {
+    /// Where the border box of the next in-flow child would start, if its
+    /// block-start margin were the given one and did not collapse with the
+    /// ones of its children.
+    fn block_start_of_next_in_flow(&self, margin_block_start: Au) -> Au {
+        if self.next_in_flow_margin_collapses_with_parent_start_margin {
+            return self.current_block_direction_position;
+        }
+        self.current_block_direction_position +
+            self.current_margin
+                .adjoin(&CollapsedMargin::new(margin_block_start))
+                .solve()
+    }
+
+    /// Adds clearance before the next in-flow child if needed, so that its
+    /// border box starts after `clearance_position`.
+    ///
+    /// <https://drafts.csswg.org/css2/visuren.html#flow-control>
+    fn clear_floats(&mut self, clearance_position: Au, margin_block_start: Au) {
+        if self.block_start_of_next_in_flow(margin_block_start) >= clearance_position {
+            return;
+        }
+        // Clearance stops the margins before the child from collapsing with
+        // its own.
+        self.current_block_direction_position += self.current_margin.solve();
+        self.current_margin = CollapsedMargin::zero();
+        self.next_in_flow_margin_collapses_with_parent_start_margin = false;
+        self.current_block_direction_position = max(
+            self.current_block_direction_position,
+            clearance_position - margin_block_start,
+        );
+    }
+
+    fn place_fragment(&mut self, fragment: &mut BoxFragment) {
+        let fragment_block_margins = fragment.block_margins_collapsed_with_children;
+        let fragment_block_size = fragment.padding.block_start_end() +
+            fragment.border.block_start_end() +
+            fragment.content_rect.size.block;
+
+        if self.next_in_flow_margin_collapses_with_parent_start_margin {
+            debug_assert_eq!(self.current_margin.solve(), Au(0));
+            self.start_margin
+                .adjoin_assign(&fragment_block_margins.start);
+            if fragment_block_margins.collapsed_through {
+                self.start_margin.adjoin_assign(&fragment_block_margins.end);
+                return;
+            }
+            self.next_in_flow_margin_collapses_with_parent_start_margin = false;
+        } else {
+            self.current_margin
+                .adjoin_assign(&fragment_block_margins.start);
+        }
+        fragment.content_rect.start.b +=
+            self.current_margin.solve() + self.current_block_direction_position;
+        if fragment_block_margins.collapsed_through {
+            self.current_margin
+                .adjoin_assign(&fragment_block_margins.end);
+            return;
+        }
+        self.current_block_direction_position += self.current_margin.solve() + fragment_block_size;
+        self.current_margin = fragment_block_margins.end;
+    }
+}
+
+impl BlockLevelBox {
+    /// Lays out an in-flow block-level box.
+    fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        float_context: &mut FloatContext,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> BoxFragment {
+        match self {
+            BlockLevelBox::SameFormattingContextBlock {
+                tag,
+                style,
+                contents,
+            } => layout_in_flow_non_replaced_block_level(
+                layout_context,
+                containing_block,
+                *tag,
+                style,
+                NonReplacedContents::SameFormattingContextBlock(contents),
+                float_context,
+                hoisted_boxes,
+            ),
+            BlockLevelBox::Independent(contents) => layout_in_flow_non_replaced_block_level(
+                layout_context,
+                containing_block,
+                contents.tag,
+                &contents.style,
+                NonReplacedContents::EstablishesAnIndependentFormattingContext(contents),
+                float_context,
+                hoisted_boxes,
+            ),
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) |
+            BlockLevelBox::OutOfFlowFloatBox(_) => {
+                unreachable!("out-of-flow boxes are laid out by their container")
+            },
+        }
+    }
+
+    fn content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        match self {
+            BlockLevelBox::SameFormattingContextBlock {
+                style, contents, ..
+            } => outer_inline_content_sizes(style, || contents.content_sizes(layout_context)),
+            BlockLevelBox::Independent(contents) |
+            BlockLevelBox::OutOfFlowFloatBox(FloatBox { contents }) => {
+                outer_inline_content_sizes(&contents.style, || {
+                    contents.content_sizes(layout_context)
+                })
+            },
+            // Absolutely positioned boxes do not contribute to the size of
+            // their container.
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => ContentSizes::zero(),
+        }
+    }
+}
+
+enum NonReplacedContents<'a> {
+    SameFormattingContextBlock(&'a BlockContainer),
+    EstablishesAnIndependentFormattingContext(&'a IndependentFormattingContext),
+}
+
+/// Sizes a block-level non-replaced box in normal flow and lays out its
+/// contents. The resulting fragment is positioned at the block-start edge of
+/// the containing block; `PlacementState` moves it where it belongs.
+///
+/// <https://drafts.csswg.org/css2/visudet.html#blockwidth>
+/// <https://drafts.csswg.org/css2/visudet.html#normal-block>
+fn layout_in_flow_non_replaced_block_level<'box_tree>(
+    layout_context: &LayoutContext,
+    containing_block: &ContainingBlock,
+    tag: OpaqueNode,
+    style: &ServoArc<ComputedValues>,
+    contents: NonReplacedContents<'box_tree>,
+    float_context: &mut FloatContext,
+    hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+) -> BoxFragment {
+    let padding = style.used_padding(containing_block);
+    let border = style.used_border_width(containing_block);
+    let margin = style.used_margin(containing_block);
+    let pb = padding + border;
+    let box_size = style.used_box_size(containing_block);
+    let min_box_size = style.used_min_box_size(containing_block);
+    let max_box_size = style.used_max_box_size(containing_block);
+
+    // https://drafts.csswg.org/css2/visudet.html#min-max-widths
+    let solve_inline_margins = |inline_size| {
+        solve_inline_margins_for_in_flow_block_level(
+            containing_block,
+            pb.inline_start_end(),
+            margin.inline_start,
+            margin.inline_end,
+            inline_size,
+        )
+    };
+    let (mut inline_size, mut inline_margins) = match box_size.inline {
+        Some(inline_size) => (inline_size, solve_inline_margins(inline_size)),
+        None => {
+            let margin_inline_start = margin.inline_start.unwrap_or(Au(0));
+            let margin_inline_end = margin.inline_end.unwrap_or(Au(0));
+            let inline_size = containing_block.inline_size -
+                pb.inline_start_end() -
+                margin_inline_start -
+                margin_inline_end;
+            (inline_size, (margin_inline_start, margin_inline_end))
+        },
+    };
+    if let Some(max_inline_size) = max_box_size.inline {
+        if inline_size > max_inline_size {
+            inline_size = max_inline_size;
+            inline_margins = solve_inline_margins(inline_size);
+        }
+    }
+    if inline_size < min_box_size.inline {
+        inline_size = min_box_size.inline;
+        inline_margins = solve_inline_margins(inline_size);
+    }
+    let margin = LogicalMargin::new(
+        containing_block.mode,
+        margin.block_start.unwrap_or(Au(0)),
+        inline_margins.1,
+        margin.block_end.unwrap_or(Au(0)),
+        inline_margins.0,
+    );
+
+    // https://drafts.csswg.org/css2/visudet.html#min-max-heights
+    let block_size = box_size
+        .block
+        .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+
+    let containing_block_for_children = ContainingBlock {
+        inline_size,
+        block_size,
+        mode: style.writing_mode_for_contents(containing_block.mode),
+    };
+
+    let first_hoisted_box = hoisted_boxes.len();
+    let this_start_margin_can_collapse_with_children;
+    let this_end_margin_can_collapse_with_children;
+    let mut flow_layout = match contents {
+        NonReplacedContents::SameFormattingContextBlock(contents) => {
+            this_start_margin_can_collapse_with_children =
+                CollapsibleWithParentStartMargin(pb.block_start == Au(0));
+            this_end_margin_can_collapse_with_children =
+                pb.block_end == Au(0) && block_size.is_none() && min_box_size.block == Au(0);
+            float_context.with_offset(
+                margin.inline_start + pb.inline_start,
+                pb.block_start,
+                |float_context| {
+                    contents.layout(
+                        layout_context,
+                        &containing_block_for_children,
+                        style,
+                        float_context,
+                        hoisted_boxes,
+                        this_start_margin_can_collapse_with_children,
+                    )
+                },
+            )
+        },
+        NonReplacedContents::EstablishesAnIndependentFormattingContext(contents) => {
+            this_start_margin_can_collapse_with_children = CollapsibleWithParentStartMargin(false);
+            this_end_margin_can_collapse_with_children = false;
+            contents.layout(
+                layout_context,
+                &containing_block_for_children,
+                hoisted_boxes,
+            )
+        },
+    };
+
+    let mut block_margins_collapsed_with_children = CollapsedBlockMargins::from_margin(&margin);
+    if this_start_margin_can_collapse_with_children.0 {
+        block_margins_collapsed_with_children
+            .start
+            .adjoin_assign(&flow_layout.collapsible_margins_in_children.start);
+        if flow_layout
+            .collapsible_margins_in_children
+            .collapsed_through
+        {
+            block_margins_collapsed_with_children
+                .start
+                .adjoin_assign(&mem::replace(
+                    &mut flow_layout.collapsible_margins_in_children.end,
+                    CollapsedMargin::zero(),
+                ));
+        }
+    }
+    if this_end_margin_can_collapse_with_children {
+        block_margins_collapsed_with_children
+            .end
+            .adjoin_assign(&flow_layout.collapsible_margins_in_children.end);
+    } else {
+        flow_layout.content_block_size += flow_layout.collapsible_margins_in_children.end.solve();
+    }
+    block_margins_collapsed_with_children.collapsed_through =
+        this_start_margin_can_collapse_with_children.0 &&
+            this_end_margin_can_collapse_with_children &&
+            flow_layout
+                .collapsible_margins_in_children
+                .collapsed_through;
+
+    let block_size = block_size.unwrap_or_else(|| {
+        clamp_between_extremums(
+            flow_layout.content_block_size,
+            min_box_size.block,
+            max_box_size.block,
+        )
+    });
+    let content_rect = LogicalRect::new(
+        containing_block.mode,
+        pb.inline_start + margin.inline_start,
+        pb.block_start,
+        inline_size,
+        block_size,
+    );
+    let mut fragment = BoxFragment {
+        tag,
+        style: style.clone(),
+        children: flow_layout.fragments,
+        content_rect,
+        padding,
+        border,
+        margin,
+        block_margins_collapsed_with_children,
+    };
+    layout_hoisted_boxes_in_fragment(
+        layout_context,
+        &mut fragment,
+        containing_block.mode,
+        hoisted_boxes,
+        first_hoisted_box,
+    );
+    fragment
+}
+
+/// Resolves the `auto` inline margins of a block-level box in normal flow,
+/// once its inline size is known. Over-constrained boxes keep their
+/// inline-start margin.
+///
+/// <https://drafts.csswg.org/css2/visudet.html#blockwidth>
+fn solve_inline_margins_for_in_flow_block_level(
+    containing_block: &ContainingBlock,
+    padding_border_inline_sum: Au,
+    computed_margin_inline_start: Option<Au>,
+    computed_margin_inline_end: Option<Au>,
+    inline_size: Au,
+) -> (Au, Au) {
+    let inline_margins = containing_block.inline_size - padding_border_inline_sum - inline_size;
+    match (computed_margin_inline_start, computed_margin_inline_end) {
+        (None, None) => (inline_margins / 2, inline_margins / 2),
+        (None, Some(end)) => (inline_margins - end, end),
+        (Some(start), _) => (start, inline_margins - start),
+    }
+}
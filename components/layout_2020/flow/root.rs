@@ -0,0 +1,270 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::context::LayoutContext;
+use crate::display_list::items::{DisplayList, OpaqueNode};
+use crate::flow::{BlockContainer, BlockFormattingContext, BlockLevelBox, ContainingBlock};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{to_physical, BoxFragment, Fragment};
+use crate::positioned::{
+    layout_hoisted_boxes_in_initial_containing_block, DefiniteContainingBlock,
+};
+use crate::style_ext::{ComputedValuesExt, Display};
+use app_units::Au;
+use euclid::default::{Point2D, Rect, Size2D};
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use style::computed_values::position::T as Position;
+use style::context::SharedStyleContext;
+use style::logical_geometry::{LogicalSize, WritingMode};
+use style::properties::ComputedValues;
+
+/// The box tree of a document: the boxes generated by the root element, in
+/// the block formatting context of the initial containing block.
+pub struct BoxTreeRoot(BlockFormattingContext);
+
+/// The result of laying out a `BoxTreeRoot`.
+pub struct FragmentTreeRoot {
+    /// The fragments of the root element, relative to the initial containing
+    /// block.
+    children: Vec<Fragment>,
+    initial_containing_block_size: Size2D<Au>,
+}
+
+impl BoxTreeRoot {
+    pub fn construct<Node>(context: &SharedStyleContext, root_element: Node) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let style = root_element.style(context);
+        let boxes = match Display::from(style.get_box().display) {
+            Display::None => Vec::new(),
+            // The root element always establishes a new block formatting
+            // context, whatever its `display`.
+            Display::GeneratingBox(_) => vec![BlockLevelBox::Independent(
+                IndependentFormattingContext::construct(context, root_element, style),
+            )],
+        };
+        BoxTreeRoot(BlockFormattingContext {
+            contents: BlockContainer::BlockLevelBoxes(boxes),
+        })
+    }
+
+    pub fn layout(&self, layout_context: &LayoutContext, viewport: Size2D<Au>) -> FragmentTreeRoot {
+        let initial_containing_block = ContainingBlock {
+            inline_size: viewport.width,
+            block_size: Some(viewport.height),
+            // FIXME: use the writing mode of the root element.
+            // https://drafts.csswg.org/css-writing-modes/#principal-flow
+            mode: WritingMode::empty(),
+        };
+        // The initial containing block has no style of its own.
+        let mut hoisted_boxes = Vec::new();
+        let flow_layout = self.0.layout(
+            layout_context,
+            &initial_containing_block,
+            ComputedValues::initial_values(),
+            &mut hoisted_boxes,
+        );
+        let mut children = flow_layout.fragments;
+        children.extend(layout_hoisted_boxes_in_initial_containing_block(
+            layout_context,
+            hoisted_boxes,
+            &DefiniteContainingBlock {
+                size: LogicalSize::new(
+                    initial_containing_block.mode,
+                    viewport.width,
+                    viewport.height,
+                ),
+                mode: initial_containing_block.mode,
+            },
+        ));
+        FragmentTreeRoot {
+            children,
+            initial_containing_block_size: viewport,
+        }
+    }
+}
+
+impl FragmentTreeRoot {
+    pub fn build_display_list(&self, display_list: &mut DisplayList) {
+        let initial_containing_block =
+            Rect::new(Point2D::zero(), self.initial_containing_block_size);
+        for fragment in &self.children {
+            fragment.build_display_list(
+                display_list,
+                &initial_containing_block,
+                WritingMode::empty(),
+            );
+        }
+    }
+
+    /// The union of the border boxes of the fragments of a node.
+    pub fn get_content_box_for_node(&self, requested_node: OpaqueNode) -> Option<Rect<Au>> {
+        let mut content_box: Option<Rect<Au>> = None;
+        self.find(|found| {
+            if found.fragment.tag == requested_node {
+                content_box = Some(match content_box {
+                    Some(content_box) => content_box.union(&found.border_rect),
+                    None => found.border_rect,
+                });
+            }
+            None::<()>
+        });
+        content_box
+    }
+
+    /// The border boxes of the fragments of a node.
+    pub fn get_content_boxes_for_node(&self, requested_node: OpaqueNode) -> Vec<Rect<Au>> {
+        let mut content_boxes = Vec::new();
+        self.find(|found| {
+            if found.fragment.tag == requested_node {
+                content_boxes.push(found.border_rect);
+            }
+            None::<()>
+        });
+        content_boxes
+    }
+
+    /// The padding box of the first fragment of a node, relative to its
+    /// border box: what `clientTop`, `clientLeft`, `clientWidth` and
+    /// `clientHeight` return.
+    ///
+    /// <https://drafts.csswg.org/cssom-view/#dom-element-clienttop>
+    pub fn get_border_dimensions_for_node(&self, requested_node: OpaqueNode) -> Rect<i32> {
+        self.find(|found| {
+            if found.fragment.tag != requested_node {
+                return None;
+            }
+            let border_widths = found.padding_rect.origin - found.border_rect.origin;
+            Some(Rect::new(
+                Point2D::new(border_widths.x.to_px(), border_widths.y.to_px()),
+                Size2D::new(
+                    found.padding_rect.size.width.to_px(),
+                    found.padding_rect.size.height.to_px(),
+                ),
+            ))
+        })
+        .unwrap_or_else(Rect::zero)
+    }
+
+    /// The offset parent of a node, and the border box of its first fragment
+    /// relative to the padding box of that parent. The offset parent is the
+    /// nearest positioned ancestor, or the body element.
+    ///
+    /// <https://drafts.csswg.org/cssom-view/#extensions-to-the-htmlelement-interface>
+    pub fn get_offset_parent_for_node(
+        &self,
+        requested_node: OpaqueNode,
+    ) -> Option<(OpaqueNode, Rect<Au>)> {
+        // The padding box origins of the positioned ancestors of the current
+        // fragment, at each level of the tree.
+        let mut positioned_ancestors: Vec<Option<(OpaqueNode, Point2D<Au>)>> = Vec::new();
+        let mut body = None;
+        self.find(|found| {
+            let fragment = found.fragment;
+            let position = fragment.style.get_box().position;
+            positioned_ancestors.truncate(found.level);
+            if fragment.tag == requested_node {
+                // `offsetParent` is null for fixed boxes.
+                if position == Position::Fixed {
+                    return Some(None);
+                }
+                let parent = positioned_ancestors
+                    .iter()
+                    .rev()
+                    .filter_map(|ancestor| *ancestor)
+                    .next()
+                    .or(body);
+                return Some(parent.map(|(parent, origin)| {
+                    let offset = found.border_rect.origin - origin;
+                    (parent, Rect::new(offset.to_point(), found.border_rect.size))
+                }));
+            }
+            let padding_box_origin = (fragment.tag, found.padding_rect.origin);
+            // TODO: tell the body element apart from other children of the
+            // root element, rather than assuming it is the first of them.
+            if found.level == 1 && body.is_none() {
+                body = Some(padding_box_origin);
+            }
+            positioned_ancestors.push(if position != Position::Static {
+                Some(padding_box_origin)
+            } else {
+                None
+            });
+            None
+        })
+        .and_then(|offset_parent| offset_parent)
+    }
+
+    /// Calls `process` with the box fragments of the tree in tree order,
+    /// until it returns `Some`.
+    fn find<'a, T>(
+        &'a self,
+        mut process: impl FnMut(FoundBoxFragment<'a>) -> Option<T>,
+    ) -> Option<T> {
+        let initial_containing_block =
+            Rect::new(Point2D::zero(), self.initial_containing_block_size);
+        find_in_fragments(
+            &self.children,
+            &initial_containing_block,
+            WritingMode::empty(),
+            0,
+            &mut process,
+        )
+    }
+}
+
+/// A box fragment of the tree, with its absolute geometry.
+struct FoundBoxFragment<'a> {
+    fragment: &'a BoxFragment,
+    border_rect: Rect<Au>,
+    padding_rect: Rect<Au>,
+    /// The number of box fragments containing this one.
+    level: usize,
+}
+
+fn find_in_fragments<'a, T>(
+    fragments: &'a [Fragment],
+    containing_block: &Rect<Au>,
+    mode: WritingMode,
+    level: usize,
+    process: &mut impl FnMut(FoundBoxFragment<'a>) -> Option<T>,
+) -> Option<T> {
+    for fragment in fragments {
+        let found = match fragment {
+            Fragment::Box(fragment) => {
+                let relative_offset = fragment.relative_offset(containing_block, mode);
+                let physical =
+                    |rect| to_physical(rect, containing_block, mode).translate(relative_offset);
+                let found = process(FoundBoxFragment {
+                    fragment,
+                    border_rect: physical(&fragment.border_rect()),
+                    padding_rect: physical(&fragment.padding_rect()),
+                    level,
+                });
+                found.or_else(|| {
+                    find_in_fragments(
+                        &fragment.children,
+                        &physical(&fragment.content_rect),
+                        fragment.style.writing_mode_for_contents(mode),
+                        level + 1,
+                        process,
+                    )
+                })
+            },
+            Fragment::Anonymous(fragment) => find_in_fragments(
+                &fragment.children,
+                &to_physical(&fragment.rect, containing_block, mode),
+                mode,
+                level,
+                process,
+            ),
+            Fragment::Text(_) => None,
+        };
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
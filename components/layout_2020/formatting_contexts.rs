@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Boxes whose contents are laid out independently from their surroundings.
+//!
+//! <https://drafts.csswg.org/css-display/#independent-formatting-context>
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::flow::{BlockFormattingContext, ContainingBlock, FlowLayout};
+use crate::positioned::HoistedAbsolutelyPositionedBox;
+use crate::sizing::ContentSizes;
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use style::context::SharedStyleContext;
+use style::properties::ComputedValues;
+
+/// A box that establishes a new formatting context for its contents. Only
+/// block formatting contexts are supported for now.
+pub(crate) struct IndependentFormattingContext {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    pub contents: BlockFormattingContext,
+}
+
+impl IndependentFormattingContext {
+    pub fn construct<Node: ThreadSafeLayoutNode>(
+        context: &SharedStyleContext,
+        node: Node,
+        style: ServoArc<ComputedValues>,
+    ) -> Self {
+        IndependentFormattingContext {
+            tag: node.opaque(),
+            contents: BlockFormattingContext::construct(context, node, &style),
+            style,
+        }
+    }
+
+    /// The margins of the contents never collapse with the ones of this box.
+    pub fn layout<'box_tree>(
+        &'box_tree self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> FlowLayout {
+        self.contents
+            .layout(layout_context, containing_block, &self.style, hoisted_boxes)
+    }
+
+    /// The intrinsic inline sizes of the contents of this box.
+    pub fn content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        self.contents.content_sizes(layout_context)
+    }
+}
@@ -0,0 +1,160 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The fragment tree: the result of laying out the box tree, ready to be
+//! turned into a display list.
+//!
+//! The rectangles of a fragment are relative to the content box of the
+//! fragment containing it, and in the writing mode of that containing
+//! fragment. The children of a `BoxFragment` are in the writing mode its
+//! contents are laid out in (see `ComputedValuesExt::writing_mode_for_contents`),
+//! the children of an `AnonymousFragment` in the same writing mode as itself.
+
+use crate::display_list::items::OpaqueNode;
+use crate::positioned::relative_adjustment;
+use crate::ServoArc;
+use app_units::Au;
+use euclid::default::{Rect, Vector2D};
+use gfx::text::glyph::GlyphStore;
+use std::cmp::{max, min};
+use std::sync::Arc;
+use style::logical_geometry::{LogicalMargin, LogicalRect, WritingMode};
+use style::properties::ComputedValues;
+use webrender_api::FontInstanceKey;
+
+pub(crate) enum Fragment {
+    Box(BoxFragment),
+    Anonymous(AnonymousFragment),
+    Text(TextFragment),
+}
+
+pub(crate) struct BoxFragment {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    pub children: Vec<Fragment>,
+
+    /// From the containing block’s start corner.
+    pub content_rect: LogicalRect<Au>,
+
+    pub padding: LogicalMargin<Au>,
+    pub border: LogicalMargin<Au>,
+    pub margin: LogicalMargin<Au>,
+
+    pub block_margins_collapsed_with_children: CollapsedBlockMargins,
+}
+
+/// The block-direction margins of a box, once collapsed with the ones of its
+/// children where they are adjoining.
+///
+/// <https://drafts.csswg.org/css2/box.html#collapsing-margins>
+#[derive(Clone, Copy)]
+pub(crate) struct CollapsedBlockMargins {
+    /// Whether the top and bottom margins of the box are adjoining, because
+    /// it has no in-flow content, padding, border or block size.
+    pub collapsed_through: bool,
+    pub start: CollapsedMargin,
+    pub end: CollapsedMargin,
+}
+
+/// A set of adjoining margins, which collapse into a single one.
+#[derive(Clone, Copy)]
+pub(crate) struct CollapsedMargin {
+    max_positive: Au,
+    min_negative: Au,
+}
+
+/// Can contain child fragments with relative coordinates, but does not
+/// contribute to painting itself. Line boxes are anonymous fragments.
+pub(crate) struct AnonymousFragment {
+    pub rect: LogicalRect<Au>,
+    pub children: Vec<Fragment>,
+}
+
+pub(crate) struct TextFragment {
+    pub tag: OpaqueNode,
+    pub parent_style: ServoArc<ComputedValues>,
+    pub content_rect: LogicalRect<Au>,
+    /// The distance from the block-start edge of the content box to the
+    /// baseline.
+    pub ascent: Au,
+    pub font_key: FontInstanceKey,
+    pub glyphs: Vec<Arc<GlyphStore>>,
+}
+
+impl BoxFragment {
+    pub fn padding_rect(&self) -> LogicalRect<Au> {
+        self.content_rect + self.padding
+    }
+
+    pub fn border_rect(&self) -> LogicalRect<Au> {
+        self.padding_rect() + self.border
+    }
+
+    /// How far relative positioning moves the fragment, given the absolute
+    /// rectangle it is relative to and the writing mode it is laid out in.
+    pub fn relative_offset(&self, containing_block: &Rect<Au>, mode: WritingMode) -> Vector2D<Au> {
+        relative_adjustment(&self.style, containing_block.size, mode)
+    }
+}
+
+impl CollapsedBlockMargins {
+    pub fn from_margin(margin: &LogicalMargin<Au>) -> Self {
+        Self {
+            collapsed_through: false,
+            start: CollapsedMargin::new(margin.block_start),
+            end: CollapsedMargin::new(margin.block_end),
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self {
+            collapsed_through: false,
+            start: CollapsedMargin::zero(),
+            end: CollapsedMargin::zero(),
+        }
+    }
+}
+
+impl CollapsedMargin {
+    pub fn zero() -> Self {
+        Self {
+            max_positive: Au(0),
+            min_negative: Au(0),
+        }
+    }
+
+    pub fn new(margin: Au) -> Self {
+        Self {
+            max_positive: max(margin, Au(0)),
+            min_negative: min(margin, Au(0)),
+        }
+    }
+
+    pub fn adjoin(&self, other: &Self) -> Self {
+        Self {
+            max_positive: max(self.max_positive, other.max_positive),
+            min_negative: min(self.min_negative, other.min_negative),
+        }
+    }
+
+    pub fn adjoin_assign(&mut self, other: &Self) {
+        *self = self.adjoin(other);
+    }
+
+    /// The size of the single margin these collapse into.
+    pub fn solve(&self) -> Au {
+        self.max_positive + self.min_negative
+    }
+}
+
+/// Converts a rectangle of the fragment tree to an absolute one, given the
+/// absolute rectangle it is relative to.
+pub(crate) fn to_physical(
+    rect: &LogicalRect<Au>,
+    containing_block: &Rect<Au>,
+    mode: WritingMode,
+) -> Rect<Au> {
+    rect.to_physical(mode, containing_block.size)
+        .translate(containing_block.origin.to_vector())
+}
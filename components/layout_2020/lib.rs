@@ -10,17 +10,24 @@ extern crate serde;
 pub mod context;
 pub mod data;
 pub mod display_list;
-pub mod flow;
-pub mod flow_ref;
+mod dom_traversal;
+mod flow;
+mod formatting_contexts;
 mod fragment;
+mod fragments;
 pub mod opaque_node;
+mod positioned;
 pub mod query;
+mod sizing;
+mod style_ext;
 pub mod traversal;
 pub mod wrapper;
 
+pub use crate::flow::{BoxTreeRoot, FragmentTreeRoot};
+
 // For unit tests:
 pub use crate::fragment::Fragment;
 
-// We can't use servo_arc for everything in layout, because the Flow stuff uses
-// weak references.
+// We can't use servo_arc for everything in layout, because fonts and glyph
+// stores are shared with gfx through std::sync::Arc.
 use servo_arc::Arc as ServoArc;
@@ -0,0 +1,496 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Positioned layout: absolutely positioned boxes, and the offsets of
+//! relatively positioned ones.
+//!
+//! An absolutely positioned box is laid out once its containing block is:
+//! while laying out the contents of its ancestors, it is "hoisted" up to the
+//! nearest one that is positioned, or to the initial containing block for
+//! `position: fixed`, along with its static position.
+//!
+//! <https://drafts.csswg.org/css-position/>
+
+use crate::context::LayoutContext;
+use crate::flow::ContainingBlock;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{BoxFragment, CollapsedBlockMargins, Fragment};
+use crate::sizing::clamp_between_extremums;
+use crate::style_ext::ComputedValuesExt;
+use app_units::Au;
+use euclid::default::{Size2D, Vector2D};
+use style::computed_values::position::T as Position;
+use style::logical_geometry::{LogicalMargin, LogicalPoint, LogicalRect, LogicalSize};
+use style::logical_geometry::{PhysicalSide, WritingMode};
+use style::properties::ComputedValues;
+use style::values::computed::LengthPercentageOrAuto;
+
+pub(crate) struct AbsolutelyPositionedBox {
+    pub contents: IndependentFormattingContext,
+}
+
+/// An absolutely positioned box waiting for the size of its containing block
+/// to be known.
+pub(crate) struct HoistedAbsolutelyPositionedBox<'box_tree> {
+    absolutely_positioned_box: &'box_tree AbsolutelyPositionedBox,
+    /// The position of the margin box of this box if it were
+    /// `position: static`, relative to the content box of the box being laid
+    /// out, in the writing mode of its contents.
+    static_position: StaticPosition,
+}
+
+#[derive(Clone, Copy)]
+struct StaticPosition {
+    inline: Au,
+    block: Au,
+}
+
+/// A containing block whose size does not depend on the contents of the
+/// boxes it contains, like the ones of absolutely positioned boxes.
+pub(crate) struct DefiniteContainingBlock {
+    pub size: LogicalSize<Au>,
+    pub mode: WritingMode,
+}
+
+impl From<&DefiniteContainingBlock> for ContainingBlock {
+    fn from(definite: &DefiniteContainingBlock) -> Self {
+        ContainingBlock {
+            inline_size: definite.size.inline,
+            block_size: Some(definite.size.block),
+            mode: definite.mode,
+        }
+    }
+}
+
+impl AbsolutelyPositionedBox {
+    pub(crate) fn hoist(
+        &self,
+        static_inline_position: Au,
+        static_block_position: Au,
+    ) -> HoistedAbsolutelyPositionedBox {
+        HoistedAbsolutelyPositionedBox {
+            absolutely_positioned_box: self,
+            static_position: StaticPosition {
+                inline: static_inline_position,
+                block: static_block_position,
+            },
+        }
+    }
+}
+
+/// Makes the static positions of boxes hoisted out of a child fragment
+/// relative to the content box of its parent, given the position of the
+/// content box of the child.
+pub(crate) fn adjust_static_positions(
+    hoisted_boxes: &mut [HoistedAbsolutelyPositionedBox],
+    child_content_start: &LogicalPoint<Au>,
+) {
+    for hoisted in hoisted_boxes {
+        hoisted.static_position.inline += child_content_start.i;
+        hoisted.static_position.block += child_content_start.b;
+    }
+}
+
+/// If `fragment` is positioned, lays out the absolutely positioned boxes
+/// hoisted out of its contents, which are `hoisted_boxes[first_in_fragment..]`,
+/// in its padding box. Their fragments are added to its children, and boxes
+/// with `position: fixed` keep going up to the initial containing block.
+///
+/// `containing_block_mode` is the writing mode `fragment` was laid out in.
+pub(crate) fn layout_hoisted_boxes_in_fragment<'box_tree>(
+    layout_context: &LayoutContext,
+    fragment: &mut BoxFragment,
+    containing_block_mode: WritingMode,
+    hoisted_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    first_in_fragment: usize,
+) {
+    // TODO: positioned inline boxes should be containing blocks too.
+    if fragment.style.get_box().position == Position::Static ||
+        hoisted_boxes.len() == first_in_fragment
+    {
+        return;
+    }
+    let (fixed, absolute) = hoisted_boxes
+        .split_off(first_in_fragment)
+        .into_iter()
+        .partition::<Vec<_>, _>(|hoisted| hoisted.is_fixed());
+    hoisted_boxes.extend(fixed);
+
+    let mode = fragment
+        .style
+        .writing_mode_for_contents(containing_block_mode);
+    let padding = fragment.padding.convert(containing_block_mode, mode);
+    let content_size = fragment
+        .content_rect
+        .size
+        .convert(containing_block_mode, mode);
+    let containing_block = DefiniteContainingBlock {
+        size: LogicalSize::new(
+            mode,
+            content_size.inline + padding.inline_start_end(),
+            content_size.block + padding.block_start_end(),
+        ),
+        mode,
+    };
+    let padding_start = StaticPosition {
+        inline: padding.inline_start,
+        block: padding.block_start,
+    };
+    let fragments = layout_hoisted_boxes(
+        layout_context,
+        absolute,
+        &containing_block,
+        padding_start,
+        hoisted_boxes,
+    );
+    fragment.children.extend(fragments);
+}
+
+/// Lays out the boxes that were hoisted all the way up to the initial
+/// containing block, which is the containing block of the ones with
+/// `position: fixed` and of those with no positioned ancestor.
+pub(crate) fn layout_hoisted_boxes_in_initial_containing_block(
+    layout_context: &LayoutContext,
+    mut hoisted_boxes: Vec<HoistedAbsolutelyPositionedBox>,
+    initial_containing_block: &DefiniteContainingBlock,
+) -> Vec<Fragment> {
+    let mut fragments = Vec::new();
+    // Boxes with `position: fixed` inside of absolutely positioned boxes are
+    // only found once those are laid out.
+    while !hoisted_boxes.is_empty() {
+        let mut fixed = Vec::new();
+        fragments.extend(layout_hoisted_boxes(
+            layout_context,
+            hoisted_boxes,
+            initial_containing_block,
+            StaticPosition {
+                inline: Au(0),
+                block: Au(0),
+            },
+            &mut fixed,
+        ));
+        hoisted_boxes = fixed;
+    }
+    fragments
+}
+
+/// Lays out boxes in the padding box of their containing block, whose
+/// inline-start and block-start padding is given by `padding_start`. The
+/// resulting fragments and the static positions of the boxes with
+/// `position: fixed` found in them are relative to the content box.
+fn layout_hoisted_boxes<'box_tree>(
+    layout_context: &LayoutContext,
+    boxes: Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    containing_block: &DefiniteContainingBlock,
+    padding_start: StaticPosition,
+    fixed_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+) -> Vec<Fragment> {
+    boxes
+        .into_iter()
+        .map(|mut hoisted| {
+            hoisted.static_position.inline += padding_start.inline;
+            hoisted.static_position.block += padding_start.block;
+            let first_fixed_box = fixed_boxes.len();
+            let mut fragment = hoisted.layout(layout_context, containing_block, fixed_boxes);
+            fragment.content_rect.start.i -= padding_start.inline;
+            fragment.content_rect.start.b -= padding_start.block;
+            adjust_static_positions(
+                &mut fixed_boxes[first_fixed_box..],
+                &fragment.content_rect.start,
+            );
+            Fragment::Box(fragment)
+        })
+        .collect()
+}
+
+impl<'box_tree> HoistedAbsolutelyPositionedBox<'box_tree> {
+    fn is_fixed(&self) -> bool {
+        self.absolutely_positioned_box
+            .contents
+            .style
+            .get_box()
+            .position ==
+            Position::Fixed
+    }
+
+    /// Lays out the box relative to the padding box of its containing block,
+    /// which is also what its static position is relative to. Boxes with
+    /// `position: fixed` found in its contents are added to `fixed_boxes`,
+    /// relative to its own content box.
+    ///
+    /// <https://drafts.csswg.org/css2/visudet.html#abs-non-replaced-width>
+    /// <https://drafts.csswg.org/css2/visudet.html#abs-non-replaced-height>
+    fn layout(
+        &self,
+        layout_context: &LayoutContext,
+        containing_block: &DefiniteContainingBlock,
+        fixed_boxes: &mut Vec<HoistedAbsolutelyPositionedBox<'box_tree>>,
+    ) -> BoxFragment {
+        let contents = &self.absolutely_positioned_box.contents;
+        let style = &contents.style;
+        let containing_block = ContainingBlock::from(containing_block);
+        let padding = style.used_padding(&containing_block);
+        let border = style.used_border_width(&containing_block);
+        let margin = style.used_margin(&containing_block);
+        let pb = padding + border;
+        let box_offsets = style.used_box_offsets(&containing_block);
+        let box_size = style.used_box_size(&containing_block);
+        let min_box_size = style.used_min_box_size(&containing_block);
+        let max_box_size = style.used_max_box_size(&containing_block);
+
+        let inline_axis = AxisConstraints {
+            containing_size: containing_block.inline_size,
+            static_start: self.static_position.inline,
+            offset_start: box_offsets.inline_start,
+            offset_end: box_offsets.inline_end,
+            margin_start: margin.inline_start,
+            margin_end: margin.inline_end,
+            padding_border_start: pb.inline_start,
+            padding_border_end: pb.inline_end,
+        };
+        let block_axis = AxisConstraints {
+            containing_size: containing_block.block_size.unwrap(),
+            static_start: self.static_position.block,
+            offset_start: box_offsets.block_start,
+            offset_end: box_offsets.block_end,
+            margin_start: margin.block_start,
+            margin_end: margin.block_end,
+            padding_border_start: pb.block_start,
+            padding_border_end: pb.block_end,
+        };
+
+        let inline_size = box_size
+            .inline
+            .or_else(|| inline_axis.size_between_offsets())
+            .unwrap_or_else(|| {
+                contents
+                    .content_sizes(layout_context)
+                    .shrink_to_fit(inline_axis.available_size())
+            });
+        let inline_size =
+            clamp_between_extremums(inline_size, min_box_size.inline, max_box_size.inline);
+        let block_size = box_size
+            .block
+            .or_else(|| block_axis.size_between_offsets())
+            .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode: style.writing_mode_for_contents(containing_block.mode),
+        };
+        let mut hoisted_boxes = Vec::new();
+        let flow_layout = contents.layout(
+            layout_context,
+            &containing_block_for_children,
+            &mut hoisted_boxes,
+        );
+        let block_size = block_size.unwrap_or_else(|| {
+            clamp_between_extremums(
+                flow_layout.content_block_size,
+                min_box_size.block,
+                max_box_size.block,
+            )
+        });
+
+        let (margin_inline_start, margin_inline_end, inline_start) =
+            inline_axis.solve_margins_and_position(inline_size, true);
+        let (margin_block_start, margin_block_end, block_start) =
+            block_axis.solve_margins_and_position(block_size, false);
+        let margin = LogicalMargin::new(
+            containing_block.mode,
+            margin_block_start,
+            margin_inline_end,
+            margin_block_end,
+            margin_inline_start,
+        );
+        let mut fragment = BoxFragment {
+            tag: contents.tag,
+            style: style.clone(),
+            children: flow_layout.fragments,
+            content_rect: LogicalRect::new(
+                containing_block.mode,
+                inline_start,
+                block_start,
+                inline_size,
+                block_size,
+            ),
+            padding,
+            border,
+            margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+        };
+        // This box is the containing block of the boxes with
+        // `position: absolute` in its contents.
+        layout_hoisted_boxes_in_fragment(
+            layout_context,
+            &mut fragment,
+            containing_block.mode,
+            &mut hoisted_boxes,
+            0,
+        );
+        fixed_boxes.extend(hoisted_boxes);
+        fragment
+    }
+}
+
+/// What determines the size and position of an absolutely positioned box in
+/// one axis of its containing block.
+struct AxisConstraints {
+    containing_size: Au,
+    static_start: Au,
+    /// `None` stands for `auto`.
+    offset_start: Option<Au>,
+    offset_end: Option<Au>,
+    margin_start: Option<Au>,
+    margin_end: Option<Au>,
+    padding_border_start: Au,
+    padding_border_end: Au,
+}
+
+impl AxisConstraints {
+    fn margins_or_zero(&self) -> Au {
+        self.margin_start.unwrap_or(Au(0)) + self.margin_end.unwrap_or(Au(0))
+    }
+
+    /// For an `auto` size, the size given by offsets on both sides.
+    fn size_between_offsets(&self) -> Option<Au> {
+        match (self.offset_start, self.offset_end) {
+            (Some(start), Some(end)) => Some(
+                self.containing_size -
+                    start -
+                    end -
+                    self.padding_border_start -
+                    self.padding_border_end -
+                    self.margins_or_zero(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The available space for a shrink-to-fit size.
+    fn available_size(&self) -> Au {
+        let start = match (self.offset_start, self.offset_end) {
+            (Some(start), _) => start,
+            (None, Some(_)) => Au(0),
+            (None, None) => self.static_start,
+        };
+        self.containing_size -
+            start -
+            self.offset_end.unwrap_or(Au(0)) -
+            self.padding_border_start -
+            self.padding_border_end -
+            self.margins_or_zero()
+    }
+
+    /// Returns the used start and end margins, and the start position of the
+    /// content box, once the size of the content box is known. When `auto`
+    /// margins would be negative and `avoid_negative_margin_start` is set,
+    /// the start margin is zero instead of being equal to the end one.
+    fn solve_margins_and_position(
+        &self,
+        size: Au,
+        avoid_negative_margin_start: bool,
+    ) -> (Au, Au, Au) {
+        let margin_start = self.margin_start.unwrap_or(Au(0));
+        let margin_end = self.margin_end.unwrap_or(Au(0));
+        let (margin_start, margin_end, margin_box_start) =
+            match (self.offset_start, self.offset_end) {
+                (None, None) => (margin_start, margin_end, self.static_start),
+                (Some(start), None) => (margin_start, margin_end, start),
+                (None, Some(end)) => {
+                    let margin_box_size = margin_start +
+                        self.padding_border_start +
+                        size +
+                        self.padding_border_end +
+                        margin_end;
+                    (
+                        margin_start,
+                        margin_end,
+                        self.containing_size - end - margin_box_size,
+                    )
+                },
+                (Some(start), Some(end)) => {
+                    let free_space = self.containing_size -
+                        start -
+                        end -
+                        self.padding_border_start -
+                        size -
+                        self.padding_border_end;
+                    let (margin_start, margin_end) = match (self.margin_start, self.margin_end) {
+                        (None, None) => {
+                            if avoid_negative_margin_start && free_space < Au(0) {
+                                (Au(0), free_space)
+                            } else {
+                                (free_space / 2, free_space - free_space / 2)
+                            }
+                        },
+                        (None, Some(end)) => (free_space - end, end),
+                        (Some(start), None) => (start, free_space - start),
+                        // Over-constrained: the end offset is ignored.
+                        (Some(start), Some(end)) => (start, end),
+                    };
+                    (margin_start, margin_end, start)
+                },
+            };
+        (
+            margin_start,
+            margin_end,
+            margin_box_start + margin_start + self.padding_border_start,
+        )
+    }
+}
+
+/// The offset of a relatively positioned box from its position in normal
+/// flow, given the size and the writing mode of its containing block. When
+/// both offsets of an axis are specified, the one on the start side of that
+/// axis in the containing block wins.
+///
+/// <https://drafts.csswg.org/css-position/#relpos-insets>
+pub(crate) fn relative_adjustment(
+    style: &ComputedValues,
+    containing_block_size: Size2D<Au>,
+    containing_block_mode: WritingMode,
+) -> Vector2D<Au> {
+    if style.get_box().position != Position::Relative {
+        return Vector2D::zero();
+    }
+    // One of these is horizontal and the other vertical.
+    let start_sides = [
+        containing_block_mode.inline_start_physical_side(),
+        containing_block_mode.block_start_physical_side(),
+    ];
+    let offsets = style.get_position();
+    Vector2D::new(
+        axis_adjustment(
+            &offsets.left,
+            &offsets.right,
+            containing_block_size.width,
+            !start_sides.contains(&PhysicalSide::Right),
+        ),
+        axis_adjustment(
+            &offsets.top,
+            &offsets.bottom,
+            containing_block_size.height,
+            !start_sides.contains(&PhysicalSide::Bottom),
+        ),
+    )
+}
+
+/// The relative adjustment along one axis, given the offset that moves the box
+/// towards the positive direction of that axis, i.e. `left` or `top`, and the
+/// one that moves it towards the negative direction.
+fn axis_adjustment(
+    positive: &LengthPercentageOrAuto,
+    negative: &LengthPercentageOrAuto,
+    basis: Au,
+    positive_wins: bool,
+) -> Au {
+    match (positive.to_used_value(basis), negative.to_used_value(basis)) {
+        (Some(positive), Some(_)) if positive_wins => positive,
+        (_, Some(negative)) => -negative,
+        (Some(positive), None) => positive,
+        (None, None) => Au(0),
+    }
+}
@@ -9,8 +9,9 @@ use crate::display_list::items::{DisplayList, OpaqueNode, ScrollOffsetMap};
 use crate::display_list::IndexableText;
 use crate::fragment::{Fragment, FragmentBorderBoxIterator};
 use crate::opaque_node::OpaqueNodeMethods;
+use crate::FragmentTreeRoot;
 use app_units::Au;
-use euclid::default::{Point2D, Rect, Size2D, Vector2D};
+use euclid::default::{Point2D, Rect, Size2D};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use script_layout_interface::rpc::TextIndexResponse;
@@ -189,57 +190,6 @@ impl LayoutRPC for LayoutRPCImpl {
     }
 }
 
-struct UnioningFragmentBorderBoxIterator {
-    node_address: OpaqueNode,
-    rect: Option<Rect<Au>>,
-}
-
-impl UnioningFragmentBorderBoxIterator {
-    fn new(node_address: OpaqueNode) -> UnioningFragmentBorderBoxIterator {
-        UnioningFragmentBorderBoxIterator {
-            node_address: node_address,
-            rect: None,
-        }
-    }
-}
-
-impl FragmentBorderBoxIterator for UnioningFragmentBorderBoxIterator {
-    fn process(&mut self, _: &Fragment, _: i32, border_box: &Rect<Au>) {
-        self.rect = match self.rect {
-            Some(rect) => Some(rect.union(border_box)),
-            None => Some(*border_box),
-        };
-    }
-
-    fn should_process(&mut self, fragment: &Fragment) -> bool {
-        fragment.contains_node(self.node_address)
-    }
-}
-
-struct CollectingFragmentBorderBoxIterator {
-    node_address: OpaqueNode,
-    rects: Vec<Rect<Au>>,
-}
-
-impl CollectingFragmentBorderBoxIterator {
-    fn new(node_address: OpaqueNode) -> CollectingFragmentBorderBoxIterator {
-        CollectingFragmentBorderBoxIterator {
-            node_address: node_address,
-            rects: Vec::new(),
-        }
-    }
-}
-
-impl FragmentBorderBoxIterator for CollectingFragmentBorderBoxIterator {
-    fn process(&mut self, _: &Fragment, _: i32, border_box: &Rect<Au>) {
-        self.rects.push(*border_box);
-    }
-
-    fn should_process(&mut self, fragment: &Fragment) -> bool {
-        fragment.contains_node(self.node_address)
-    }
-}
-
 enum Side {
     Left,
     Right,
@@ -358,27 +308,20 @@ impl FragmentBorderBoxIterator for MarginRetrievingFragmentBorderBoxIterator {
     }
 }
 
-pub fn process_content_box_request(requested_node: OpaqueNode) -> Option<Rect<Au>> {
-    UnioningFragmentBorderBoxIterator::new(requested_node).rect
+pub fn process_content_box_request(
+    requested_node: OpaqueNode,
+    fragment_tree_root: Option<&FragmentTreeRoot>,
+) -> Option<Rect<Au>> {
+    fragment_tree_root?.get_content_box_for_node(requested_node)
 }
 
-pub fn process_content_boxes_request(requested_node: OpaqueNode) -> Vec<Rect<Au>> {
-    // FIXME(pcwalton): This has not been updated to handle the stacking context relative
-    // stuff. So the position is wrong in most cases.
-    CollectingFragmentBorderBoxIterator::new(requested_node).rects
-}
-
-struct FragmentLocatingFragmentIterator {
-    node_address: OpaqueNode,
-    client_rect: Rect<i32>,
-}
-
-impl FragmentLocatingFragmentIterator {
-    fn new(node_address: OpaqueNode) -> FragmentLocatingFragmentIterator {
-        FragmentLocatingFragmentIterator {
-            node_address: node_address,
-            client_rect: Rect::zero(),
-        }
+pub fn process_content_boxes_request(
+    requested_node: OpaqueNode,
+    fragment_tree_root: Option<&FragmentTreeRoot>,
+) -> Vec<Rect<Au>> {
+    match fragment_tree_root {
+        Some(fragment_tree_root) => fragment_tree_root.get_content_boxes_for_node(requested_node),
+        None => Vec::new(),
     }
 }
 
@@ -405,58 +348,6 @@ impl UnioningFragmentScrollAreaIterator {
     }
 }
 
-struct NodeOffsetBoxInfo {
-    offset: Point2D<Au>,
-    rectangle: Rect<Au>,
-}
-
-struct ParentBorderBoxInfo {
-    node_address: OpaqueNode,
-    origin: Point2D<Au>,
-}
-
-struct ParentOffsetBorderBoxIterator {
-    node_address: OpaqueNode,
-    has_processed_node: bool,
-    node_offset_box: Option<NodeOffsetBoxInfo>,
-    parent_nodes: Vec<Option<ParentBorderBoxInfo>>,
-}
-
-impl ParentOffsetBorderBoxIterator {
-    fn new(node_address: OpaqueNode) -> ParentOffsetBorderBoxIterator {
-        ParentOffsetBorderBoxIterator {
-            node_address: node_address,
-            has_processed_node: false,
-            node_offset_box: None,
-            parent_nodes: Vec::new(),
-        }
-    }
-}
-
-impl FragmentBorderBoxIterator for FragmentLocatingFragmentIterator {
-    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
-        let style_structs::Border {
-            border_top_width: top_width,
-            border_right_width: right_width,
-            border_bottom_width: bottom_width,
-            border_left_width: left_width,
-            ..
-        } = *fragment.style.get_border();
-        let (left_width, right_width) = (left_width.px(), right_width.px());
-        let (top_width, bottom_width) = (top_width.px(), bottom_width.px());
-        self.client_rect.origin.y = top_width as i32;
-        self.client_rect.origin.x = left_width as i32;
-        self.client_rect.size.width =
-            (border_box.size.width.to_f32_px() - left_width - right_width) as i32;
-        self.client_rect.size.height =
-            (border_box.size.height.to_f32_px() - top_width - bottom_width) as i32;
-    }
-
-    fn should_process(&mut self, fragment: &Fragment) -> bool {
-        fragment.node == self.node_address
-    }
-}
-
 // https://drafts.csswg.org/cssom-view/#scrolling-area
 impl FragmentBorderBoxIterator for UnioningFragmentScrollAreaIterator {
     fn process(&mut self, fragment: &Fragment, level: i32, border_box: &Rect<Au>) {
@@ -516,103 +407,18 @@ impl FragmentBorderBoxIterator for UnioningFragmentScrollAreaIterator {
     }
 }
 
-// https://drafts.csswg.org/cssom-view/#extensions-to-the-htmlelement-interface
-impl FragmentBorderBoxIterator for ParentOffsetBorderBoxIterator {
-    fn process(&mut self, fragment: &Fragment, level: i32, border_box: &Rect<Au>) {
-        if self.node_offset_box.is_none() {
-            // We haven't found the node yet, so we're still looking
-            // for its parent. Remove all nodes at this level or
-            // higher, as they can't be parents of this node.
-            self.parent_nodes.truncate(level as usize);
-            assert_eq!(
-                self.parent_nodes.len(),
-                level as usize,
-                "Skipped at least one level in the flow tree!"
-            );
-        }
-
-        if !fragment.is_primary_fragment() {
-            // This fragment doesn't correspond to anything worth
-            // taking measurements from.
-
-            if self.node_offset_box.is_none() {
-                // If this is the only fragment in the flow, we need to
-                // do this to avoid failing the above assertion.
-                self.parent_nodes.push(None);
-            }
-
-            return;
-        }
-
-        if fragment.node == self.node_address {
-            // Found the fragment in the flow tree that matches the
-            // DOM node being looked for.
-
-            assert!(
-                self.node_offset_box.is_none(),
-                "Node was being treated as inline, but it has an associated fragment!"
-            );
-
-            self.has_processed_node = true;
-            self.node_offset_box = Some(NodeOffsetBoxInfo {
-                offset: border_box.origin,
-                rectangle: *border_box,
-            });
-
-            // offsetParent returns null if the node is fixed.
-            if fragment.style.get_box().position == Position::Fixed {
-                self.parent_nodes.clear();
-            }
-        } else if self.node_offset_box.is_none() {
-            // TODO(gw): Is there a less fragile way of checking whether this
-            // fragment is the body element, rather than just checking that
-            // it's at level 1 (below the root node)?
-            let is_body_element = level == 1;
-
-            let is_valid_parent = match (
-                is_body_element,
-                fragment.style.get_box().position,
-                &fragment.specific,
-            ) {
-                // Spec says it's valid if any of these are true:
-                //  1) Is the body element
-                //  2) Is static position *and* is a table or table cell
-                //  3) Is not static position
-                (true, _, _) |
-                (false, Position::Absolute, _) |
-                (false, Position::Relative, _) |
-                (false, Position::Fixed, _) => true,
-
-                // Otherwise, it's not a valid parent
-                (false, Position::Static, _) => false,
-            };
-
-            let parent_info = if is_valid_parent {
-                let border_width = fragment
-                    .border_width()
-                    .to_physical(fragment.style.writing_mode);
-
-                Some(ParentBorderBoxInfo {
-                    node_address: fragment.node,
-                    origin: border_box.origin + Vector2D::new(border_width.left, border_width.top),
-                })
-            } else {
-                None
-            };
-
-            self.parent_nodes.push(parent_info);
-        }
-    }
-
-    fn should_process(&mut self, _: &Fragment) -> bool {
-        !self.has_processed_node
+pub fn process_node_geometry_request(
+    requested_node: OpaqueNode,
+    fragment_tree_root: Option<&FragmentTreeRoot>,
+) -> Rect<i32> {
+    match fragment_tree_root {
+        Some(fragment_tree_root) => {
+            fragment_tree_root.get_border_dimensions_for_node(requested_node)
+        },
+        None => Rect::zero(),
     }
 }
 
-pub fn process_node_geometry_request(requested_node: OpaqueNode) -> Rect<i32> {
-    FragmentLocatingFragmentIterator::new(requested_node).client_rect
-}
-
 pub fn process_node_scroll_id_request<N: LayoutNode>(
     id: PipelineId,
     requested_node: N,
@@ -846,26 +652,16 @@ where
     }
 }
 
-pub fn process_offset_parent_query(requested_node: OpaqueNode) -> OffsetParentResponse {
-    let iterator = ParentOffsetBorderBoxIterator::new(requested_node);
-
-    let node_offset_box = iterator.node_offset_box;
-    let parent_info = iterator
-        .parent_nodes
-        .into_iter()
-        .rev()
-        .filter_map(|info| info)
-        .next();
-    match (node_offset_box, parent_info) {
-        (Some(node_offset_box), Some(parent_info)) => {
-            let origin = node_offset_box.offset - parent_info.origin.to_vector();
-            let size = node_offset_box.rectangle.size;
-            OffsetParentResponse {
-                node_address: Some(parent_info.node_address.to_untrusted_node_address()),
-                rect: Rect::new(origin, size),
-            }
+pub fn process_offset_parent_query(
+    requested_node: OpaqueNode,
+    fragment_tree_root: Option<&FragmentTreeRoot>,
+) -> OffsetParentResponse {
+    match fragment_tree_root.and_then(|root| root.get_offset_parent_for_node(requested_node)) {
+        Some((parent, rect)) => OffsetParentResponse {
+            node_address: Some(parent.to_untrusted_node_address()),
+            rect,
         },
-        _ => OffsetParentResponse::empty(),
+        None => OffsetParentResponse::empty(),
     }
 }
 
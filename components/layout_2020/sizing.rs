@@ -0,0 +1,94 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Intrinsic inline sizes, needed for shrink-to-fit boxes such as floats and
+//! absolutely positioned boxes with an `auto` inline size.
+//!
+//! <https://drafts.csswg.org/css-sizing/#intrinsic-sizes>
+
+use crate::flow::ContainingBlock;
+use crate::style_ext::ComputedValuesExt;
+use app_units::Au;
+use std::cmp::{max, min};
+use style::properties::ComputedValues;
+
+/// The min-content and max-content inline sizes of some contents.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ContentSizes {
+    pub min_content: Au,
+    pub max_content: Au,
+}
+
+impl ContentSizes {
+    pub fn zero() -> Self {
+        Self {
+            min_content: Au(0),
+            max_content: Au(0),
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            min_content: max(self.min_content, other.min_content),
+            max_content: max(self.max_content, other.max_content),
+        }
+    }
+
+    /// <https://drafts.csswg.org/css2/visudet.html#shrink-to-fit-float>
+    pub fn shrink_to_fit(&self, available_size: Au) -> Au {
+        min(max(self.min_content, available_size), self.max_content)
+    }
+}
+
+/// The contribution of a box to the intrinsic sizes of its container, given
+/// the intrinsic sizes of its contents.
+///
+/// <https://drafts.csswg.org/css-sizing/#intrinsic-contribution>
+pub(crate) fn outer_inline_content_sizes(
+    style: &ComputedValues,
+    inner_content_sizes: impl FnOnce() -> ContentSizes,
+) -> ContentSizes {
+    let (inline_size, min_inline_size, max_inline_size) =
+        style.inline_size_without_containing_block();
+    let inner = match inline_size {
+        Some(inline_size) => ContentSizes {
+            min_content: inline_size,
+            max_content: inline_size,
+        },
+        None => inner_content_sizes(),
+    };
+    let (start, end) = inline_padding_border_margin(style);
+    let outer =
+        |size| clamp_between_extremums(size, min_inline_size, max_inline_size) + start + end;
+    ContentSizes {
+        min_content: outer(inner.min_content),
+        max_content: outer(inner.max_content),
+    }
+}
+
+/// The sums of the padding, border and margin of a box on its inline-start
+/// and inline-end sides. Percentages, which would depend on the size of the
+/// container being computed, are treated as zero.
+pub(crate) fn inline_padding_border_margin(style: &ComputedValues) -> (Au, Au) {
+    let containing_block = ContainingBlock {
+        inline_size: Au(0),
+        block_size: None,
+        mode: style.writing_mode,
+    };
+    let padding = style.used_padding(&containing_block);
+    let border = style.used_border_width(&containing_block);
+    let margin = style.used_margin(&containing_block);
+    (
+        padding.inline_start + border.inline_start + margin.inline_start.unwrap_or(Au(0)),
+        padding.inline_end + border.inline_end + margin.inline_end.unwrap_or(Au(0)),
+    )
+}
+
+pub(crate) fn clamp_between_extremums(size: Au, min_size: Au, max_size: Option<Au>) -> Au {
+    let size = match max_size {
+        Some(max_size) => min(size, max_size),
+        None => size,
+    };
+    max(size, min_size)
+}
@@ -0,0 +1,212 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Helpers to read the computed values that box construction and layout need.
+
+use crate::flow::ContainingBlock;
+use app_units::Au;
+use style::logical_geometry::{LogicalMargin, LogicalSize, WritingMode};
+use style::properties::ComputedValues;
+use style::values::computed::{Display as PackedDisplay, LengthPercentageOrAuto, MaxSize, Size};
+use style::values::generics::length::{GenericLengthPercentageOrAuto, GenericMaxSize, GenericSize};
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Display {
+    None,
+    GeneratingBox(DisplayGeneratingBox),
+}
+
+/// <https://drafts.csswg.org/css-display/#the-display-properties>
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(crate) struct DisplayGeneratingBox {
+    pub outside: DisplayOutside,
+    pub inside: DisplayInside,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(crate) enum DisplayOutside {
+    Block,
+    Inline,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(crate) enum DisplayInside {
+    Flow,
+    FlowRoot,
+}
+
+impl From<PackedDisplay> for Display {
+    fn from(packed: PackedDisplay) -> Self {
+        let (outside, inside) = match packed {
+            PackedDisplay::None => return Display::None,
+            PackedDisplay::Block => (DisplayOutside::Block, DisplayInside::Flow),
+            PackedDisplay::Inline => (DisplayOutside::Inline, DisplayInside::Flow),
+            PackedDisplay::InlineBlock => (DisplayOutside::Inline, DisplayInside::FlowRoot),
+        };
+        Display::GeneratingBox(DisplayGeneratingBox { outside, inside })
+    }
+}
+
+/// Used values of the box model properties, in the writing mode of the
+/// given containing block.
+pub(crate) trait ComputedValuesExt {
+    fn used_padding(&self, containing_block: &ContainingBlock) -> LogicalMargin<Au>;
+    fn used_border_width(&self, containing_block: &ContainingBlock) -> LogicalMargin<Au>;
+    /// `None` stands for `auto`.
+    fn used_margin(&self, containing_block: &ContainingBlock) -> LogicalMargin<Option<Au>>;
+    /// `None` stands for `auto`, or for a percentage of an indefinite size.
+    fn used_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Option<Au>>;
+    fn used_min_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Au>;
+    /// `None` stands for `none`, or for a percentage of an indefinite size.
+    fn used_max_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Option<Au>>;
+    /// The `top`, `right`, `bottom` and `left` properties. `None` stands for
+    /// `auto`, or for a percentage of an indefinite size.
+    fn used_box_offsets(&self, containing_block: &ContainingBlock) -> LogicalMargin<Option<Au>>;
+    /// The inline size of the box and its minimum and maximum, in the
+    /// box's own writing mode, as far as they can be known without a
+    /// containing block: percentages are treated like `auto`, `0` and `none`.
+    fn inline_size_without_containing_block(&self) -> (Option<Au>, Au, Option<Au>);
+    /// The writing mode in which the contents of the box are laid out.
+    ///
+    /// Orthogonal flows are not supported yet: the contents of a box whose
+    /// writing mode is orthogonal to the one of its container are laid out
+    /// in the writing mode of the container instead.
+    fn writing_mode_for_contents(&self, container_mode: WritingMode) -> WritingMode;
+}
+
+impl ComputedValuesExt for ComputedValues {
+    fn used_padding(&self, containing_block: &ContainingBlock) -> LogicalMargin<Au> {
+        let padding = self
+            .logical_padding()
+            .convert(self.writing_mode, containing_block.mode);
+        let basis = containing_block.inline_size;
+        LogicalMargin::new(
+            containing_block.mode,
+            padding.block_start.to_used_value(basis),
+            padding.inline_end.to_used_value(basis),
+            padding.block_end.to_used_value(basis),
+            padding.inline_start.to_used_value(basis),
+        )
+    }
+
+    fn used_border_width(&self, containing_block: &ContainingBlock) -> LogicalMargin<Au> {
+        self.border_width_for_writing_mode(containing_block.mode)
+    }
+
+    fn used_margin(&self, containing_block: &ContainingBlock) -> LogicalMargin<Option<Au>> {
+        let margin = self
+            .logical_margin()
+            .convert(self.writing_mode, containing_block.mode);
+        let basis = containing_block.inline_size;
+        LogicalMargin::new(
+            containing_block.mode,
+            margin.block_start.to_used_value(basis),
+            margin.inline_end.to_used_value(basis),
+            margin.block_end.to_used_value(basis),
+            margin.inline_start.to_used_value(basis),
+        )
+    }
+
+    fn used_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Option<Au>> {
+        let position = self.get_position();
+        let (inline, block) = if containing_block.mode.is_vertical() {
+            (&position.height, &position.width)
+        } else {
+            (&position.width, &position.height)
+        };
+        LogicalSize::new(
+            containing_block.mode,
+            used_size(inline, Some(containing_block.inline_size)),
+            used_size(block, containing_block.block_size),
+        )
+    }
+
+    fn used_min_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Au> {
+        let position = self.get_position();
+        let (inline, block) = if containing_block.mode.is_vertical() {
+            (&position.min_height, &position.min_width)
+        } else {
+            (&position.min_width, &position.min_height)
+        };
+        LogicalSize::new(
+            containing_block.mode,
+            used_size(inline, Some(containing_block.inline_size)).unwrap_or(Au(0)),
+            used_size(block, containing_block.block_size).unwrap_or(Au(0)),
+        )
+    }
+
+    fn used_max_box_size(&self, containing_block: &ContainingBlock) -> LogicalSize<Option<Au>> {
+        let position = self.get_position();
+        let (inline, block) = if containing_block.mode.is_vertical() {
+            (&position.max_height, &position.max_width)
+        } else {
+            (&position.max_width, &position.max_height)
+        };
+        LogicalSize::new(
+            containing_block.mode,
+            used_max_size(inline, Some(containing_block.inline_size)),
+            used_max_size(block, containing_block.block_size),
+        )
+    }
+
+    fn used_box_offsets(&self, containing_block: &ContainingBlock) -> LogicalMargin<Option<Au>> {
+        let offsets = self
+            .logical_position()
+            .convert(self.writing_mode, containing_block.mode);
+        let inline_basis = Some(containing_block.inline_size);
+        let block_basis = containing_block.block_size;
+        LogicalMargin::new(
+            containing_block.mode,
+            used_offset(&offsets.block_start, block_basis),
+            used_offset(&offsets.inline_end, inline_basis),
+            used_offset(&offsets.block_end, block_basis),
+            used_offset(&offsets.inline_start, inline_basis),
+        )
+    }
+
+    fn inline_size_without_containing_block(&self) -> (Option<Au>, Au, Option<Au>) {
+        let position = self.get_position();
+        let (size, min_size, max_size) = if self.writing_mode.is_vertical() {
+            (&position.height, &position.min_height, &position.max_height)
+        } else {
+            (&position.width, &position.min_width, &position.max_width)
+        };
+        (
+            used_size(size, None),
+            used_size(min_size, None).unwrap_or(Au(0)),
+            used_max_size(max_size, None),
+        )
+    }
+
+    fn writing_mode_for_contents(&self, container_mode: WritingMode) -> WritingMode {
+        if self.writing_mode.is_vertical() == container_mode.is_vertical() {
+            self.writing_mode
+        } else {
+            container_mode
+        }
+    }
+}
+
+fn used_size(size: &Size, percentage_basis: Option<Au>) -> Option<Au> {
+    match *size {
+        GenericSize::Auto => None,
+        GenericSize::LengthPercentage(ref lp) => lp.maybe_to_used_value(percentage_basis),
+    }
+}
+
+fn used_max_size(size: &MaxSize, percentage_basis: Option<Au>) -> Option<Au> {
+    match *size {
+        GenericMaxSize::None => None,
+        GenericMaxSize::LengthPercentage(ref lp) => lp.maybe_to_used_value(percentage_basis),
+    }
+}
+
+fn used_offset(offset: &LengthPercentageOrAuto, percentage_basis: Option<Au>) -> Option<Au> {
+    match *offset {
+        GenericLengthPercentageOrAuto::Auto => None,
+        GenericLengthPercentageOrAuto::LengthPercentage(ref lp) => {
+            lp.maybe_to_used_value(percentage_basis)
+        },
+    }
+}
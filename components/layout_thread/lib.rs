@@ -73,6 +73,7 @@ use msg::constellation_msg::{
 };
 use msg::constellation_msg::{BrowsingContextId, MonitoredComponentId, TopLevelBrowsingContextId};
 use msg::constellation_msg::{LayoutHangAnnotation, MonitoredComponentType, PipelineId};
+use net_traits::content_security_policy::CspList;
 use net_traits::image_cache::{ImageCache, UsePlaceholder};
 use parking_lot::RwLock;
 use profile_traits::mem::{self as profile_mem, Report, ReportKind, ReportsChan};
@@ -111,6 +112,7 @@ use style::driver;
 use style::error_reporting::RustLogReporter;
 use style::global_style_data::{GLOBAL_STYLE_DATA, STYLE_THREAD_POOL};
 use style::invalidation::element::restyle_hints::RestyleHint;
+use style::invalidation::element::state_and_attributes::invalidate_relative_selector_anchors;
 use style::logical_geometry::LogicalPoint;
 use style::media_queries::{Device, MediaList, MediaType};
 use style::properties::PropertyId;
@@ -481,6 +483,7 @@ fn add_font_face_rules(
     stylesheet: &Stylesheet,
     guard: &SharedRwLockReadGuard,
     device: &Device,
+    csp_list: Option<&CspList>,
     font_cache_thread: &FontCacheThread,
     font_cache_sender: &IpcSender<()>,
     outstanding_web_fonts_counter: &Arc<AtomicUsize>,
@@ -494,6 +497,7 @@ fn add_font_face_rules(
                 font_cache_thread.add_web_font(
                     font_face.family().clone(),
                     effective_sources,
+                    csp_list.cloned(),
                     sender.clone(),
                 );
                 receiver.recv().unwrap();
@@ -507,6 +511,7 @@ fn add_font_face_rules(
                 font_cache_thread.add_web_font(
                     font_face.family().clone(),
                     effective_sources,
+                    csp_list.cloned(),
                     (*font_cache_sender).clone(),
                 );
             }
@@ -785,9 +790,9 @@ impl LayoutThread {
         self.notify_activity_to_hang_monitor(&request);
 
         match request {
-            Msg::AddStylesheet(stylesheet, before_stylesheet) => {
+            Msg::AddStylesheet(stylesheet, before_stylesheet, csp_list) => {
                 let guard = stylesheet.shared_lock.read();
-                self.handle_add_stylesheet(&stylesheet, &guard);
+                self.handle_add_stylesheet(&stylesheet, csp_list.as_ref(), &guard);
 
                 match before_stylesheet {
                     Some(insertion_point) => self.stylist.insert_stylesheet_before(
@@ -1011,7 +1016,12 @@ impl LayoutThread {
         self.background_hang_monitor.unregister();
     }
 
-    fn handle_add_stylesheet(&self, stylesheet: &Stylesheet, guard: &SharedRwLockReadGuard) {
+    fn handle_add_stylesheet(
+        &self,
+        stylesheet: &Stylesheet,
+        csp_list: Option<&CspList>,
+        guard: &SharedRwLockReadGuard,
+    ) {
         // Find all font-face rules and notify the font cache of them.
         // GWTODO: Need to handle unloading web fonts.
         if stylesheet.is_effective_for_device(self.stylist.device(), &guard) {
@@ -1019,6 +1029,7 @@ impl LayoutThread {
                 &*stylesheet,
                 &guard,
                 self.stylist.device(),
+                csp_list,
                 &self.font_cache_thread,
                 &self.font_cache_sender,
                 &self.outstanding_web_fonts,
@@ -1416,7 +1427,8 @@ impl LayoutThread {
                 for stylesheet in &ua_stylesheets.user_or_user_agent_stylesheets {
                     self.stylist
                         .append_stylesheet(stylesheet.clone(), &ua_or_user_guard);
-                    self.handle_add_stylesheet(&stylesheet.0, &ua_or_user_guard);
+                    // User and user agent sheets are not subject to the page's CSP.
+                    self.handle_add_stylesheet(&stylesheet.0, None, &ua_or_user_guard);
                 }
 
                 if self.stylist.quirks_mode() != QuirksMode::NoQuirks {
@@ -1426,6 +1438,7 @@ impl LayoutThread {
                     );
                     self.handle_add_stylesheet(
                         &ua_stylesheets.quirks_mode_stylesheet.0,
+                        None,
                         &ua_or_user_guard,
                     );
                 }
@@ -1517,6 +1530,11 @@ impl LayoutThread {
                 <RecalcStyleAndConstructFlows as DomTraversal<ServoLayoutElement>>::shared_context(
                     &traversal,
                 );
+            // The anchors of :has() selectors are styled before the elements
+            // that changed, so invalidate them before traversing.
+            for el in &elements_with_snapshot {
+                invalidate_relative_selector_anchors(*el, shared);
+            }
             RecalcStyleAndConstructFlows::pre_traverse(element, shared)
         };
 
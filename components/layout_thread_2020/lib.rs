@@ -37,10 +37,8 @@ use histogram::Histogram;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout::context::LayoutContext;
-use layout::display_list::items::OpaqueNode;
+use layout::display_list::items::{DisplayList, OpaqueNode};
 use layout::display_list::{IndexableText, WebRenderDisplayListConverter};
-use layout::flow::{Flow, GetBaseFlow};
-use layout::flow_ref::FlowRef;
 use layout::query::{
     process_content_box_request, process_content_boxes_request, LayoutRPCImpl, LayoutThreadData,
 };
@@ -50,6 +48,7 @@ use layout::query::{
     process_offset_parent_query, process_resolved_style_request, process_style_query,
 };
 use layout::traversal::RecalcStyleAndConstructFlows;
+use layout::{BoxTreeRoot, FragmentTreeRoot};
 use layout_traits::LayoutThreadFactory;
 use libc::c_void;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
@@ -59,6 +58,7 @@ use msg::constellation_msg::{
 };
 use msg::constellation_msg::{LayoutHangAnnotation, MonitoredComponentType, PipelineId};
 use msg::constellation_msg::{MonitoredComponentId, TopLevelBrowsingContextId};
+use net_traits::content_security_policy::CspList;
 use net_traits::image_cache::ImageCache;
 use parking_lot::RwLock;
 use profile_traits::mem::{self as profile_mem, Report, ReportKind, ReportsChan};
@@ -96,10 +96,10 @@ use style::driver;
 use style::error_reporting::RustLogReporter;
 use style::global_style_data::{GLOBAL_STYLE_DATA, STYLE_THREAD_POOL};
 use style::invalidation::element::restyle_hints::RestyleHint;
+use style::invalidation::element::state_and_attributes::invalidate_relative_selector_anchors;
 use style::media_queries::{Device, MediaList, MediaType};
 use style::properties::PropertyId;
 use style::selector_parser::SnapshotMap;
-use style::servo::restyle_damage::ServoRestyleDamage;
 use style::shared_lock::{SharedRwLock, SharedRwLockReadGuard, StylesheetGuards};
 use style::stylesheets::{
     DocumentStyleSheet, Origin, Stylesheet, StylesheetInDocument, UserAgentStylesheets,
@@ -177,8 +177,11 @@ pub struct LayoutThread {
     /// The number of Web fonts that have been requested but not yet loaded.
     outstanding_web_fonts: Arc<AtomicUsize>,
 
-    /// The root of the flow tree.
-    root_flow: RefCell<Option<FlowRef>>,
+    /// The root of the box tree.
+    box_tree_root: RefCell<Option<BoxTreeRoot>>,
+
+    /// The root of the fragment tree.
+    fragment_tree_root: RefCell<Option<FragmentTreeRoot>>,
 
     /// The document-specific shared lock used for author-origin stylesheets
     document_shared_lock: Option<SharedRwLock>,
@@ -453,6 +456,7 @@ fn add_font_face_rules(
     stylesheet: &Stylesheet,
     guard: &SharedRwLockReadGuard,
     device: &Device,
+    csp_list: Option<&CspList>,
     font_cache_thread: &FontCacheThread,
     font_cache_sender: &IpcSender<()>,
     outstanding_web_fonts_counter: &Arc<AtomicUsize>,
@@ -466,6 +470,7 @@ fn add_font_face_rules(
                 font_cache_thread.add_web_font(
                     font_face.family().clone(),
                     effective_sources,
+                    csp_list.cloned(),
                     sender.clone(),
                 );
                 receiver.recv().unwrap();
@@ -479,6 +484,7 @@ fn add_font_face_rules(
                 font_cache_thread.add_web_font(
                     font_face.family().clone(),
                     effective_sources,
+                    csp_list.cloned(),
                     (*font_cache_sender).clone(),
                 );
             }
@@ -557,7 +563,8 @@ impl LayoutThread {
             new_animations_sender: new_animations_sender,
             _new_animations_receiver: new_animations_receiver,
             outstanding_web_fonts: Arc::new(AtomicUsize::new(0)),
-            root_flow: RefCell::new(None),
+            box_tree_root: Default::default(),
+            fragment_tree_root: Default::default(),
             document_shared_lock: None,
             running_animations: ServoArc::new(RwLock::new(Default::default())),
             expired_animations: ServoArc::new(RwLock::new(Default::default())),
@@ -642,6 +649,7 @@ impl LayoutThread {
                 traversal_flags: TraversalFlags::empty(),
                 snapshot_map: snapshot_map,
             },
+            font_cache_thread: Mutex::new(self.font_cache_thread.clone()),
         }
     }
 
@@ -737,9 +745,9 @@ impl LayoutThread {
         self.notify_activity_to_hang_monitor(&request);
 
         match request {
-            Msg::AddStylesheet(stylesheet, before_stylesheet) => {
+            Msg::AddStylesheet(stylesheet, before_stylesheet, csp_list) => {
                 let guard = stylesheet.shared_lock.read();
-                self.handle_add_stylesheet(&stylesheet, &guard);
+                self.handle_add_stylesheet(&stylesheet, csp_list.as_ref(), &guard);
 
                 match before_stylesheet {
                     Some(insertion_point) => self.stylist.insert_stylesheet_before(
@@ -924,7 +932,7 @@ impl LayoutThread {
     /// Shuts down the layout thread now. If there are any DOM nodes left, layout will now (safely)
     /// crash.
     fn exit_now(&mut self) {
-        // Drop the root flow explicitly to avoid holding style data, such as
+        // Drop the layout trees explicitly to avoid holding style data, such as
         // rule nodes.  The `Stylist` checks when it is dropped that all rule
         // nodes have been GCed, so we want drop anyone who holds them first.
         let waiting_time_min = self.layout_query_waiting_time.minimum().unwrap_or(0);
@@ -936,11 +944,17 @@ impl LayoutThread {
             waiting_time_min, waiting_time_max, waiting_time_mean, waiting_time_stddev
         );
 
-        self.root_flow.borrow_mut().take();
+        self.box_tree_root.borrow_mut().take();
+        self.fragment_tree_root.borrow_mut().take();
         self.background_hang_monitor.unregister();
     }
 
-    fn handle_add_stylesheet(&self, stylesheet: &Stylesheet, guard: &SharedRwLockReadGuard) {
+    fn handle_add_stylesheet(
+        &self,
+        stylesheet: &Stylesheet,
+        csp_list: Option<&CspList>,
+        guard: &SharedRwLockReadGuard,
+    ) {
         // Find all font-face rules and notify the font cache of them.
         // GWTODO: Need to handle unloading web fonts.
         if stylesheet.is_effective_for_device(self.stylist.device(), &guard) {
@@ -948,6 +962,7 @@ impl LayoutThread {
                 &*stylesheet,
                 &guard,
                 self.stylist.device(),
+                csp_list,
                 &self.font_cache_thread,
                 &self.font_cache_sender,
                 &self.outstanding_web_fonts,
@@ -974,17 +989,13 @@ impl LayoutThread {
         self.stylist.set_quirks_mode(quirks_mode);
     }
 
-    fn try_get_layout_root<N: LayoutNode>(&self, _node: N) -> Option<FlowRef> {
-        None
-    }
-
-    /// Computes the stacking-relative positions of all flows and, if the painting is dirty and the
-    /// reflow type need it, builds the display list.
+    /// If the reflow type needs it, builds the display list of the fragment tree and sends it to
+    /// the painter.
     fn compute_abs_pos_and_build_display_list(
         &self,
         reflow_goal: &ReflowGoal,
         document: Option<&ServoLayoutDocument>,
-        layout_root: &mut dyn Flow,
+        fragment_tree: &FragmentTreeRoot,
         rw_data: &mut LayoutThreadData,
     ) {
         let (metadata, sender) = (self.profiler_metadata(), self.time_profiler_chan.clone());
@@ -993,18 +1004,6 @@ impl LayoutThread {
             metadata.clone(),
             sender.clone(),
             || {
-                if layout_root
-                    .base()
-                    .restyle_damage
-                    .contains(ServoRestyleDamage::REPAINT) ||
-                    rw_data.display_list.is_none()
-                {
-                    layout_root
-                        .mut_base()
-                        .restyle_damage
-                        .remove(ServoRestyleDamage::REPAINT);
-                }
-
                 if !reflow_goal.needs_display() {
                     // Defer the paint step until the next ForDisplay.
                     //
@@ -1019,6 +1018,9 @@ impl LayoutThread {
                     document.will_paint();
                 }
 
+                let mut display_list = DisplayList::new();
+                fragment_tree.build_display_list(&mut display_list);
+                rw_data.display_list = Some(display_list);
                 let display_list = rw_data.display_list.as_mut().unwrap();
 
                 debug!("Layout done!");
@@ -1202,7 +1204,8 @@ impl LayoutThread {
                 for stylesheet in &ua_stylesheets.user_or_user_agent_stylesheets {
                     self.stylist
                         .append_stylesheet(stylesheet.clone(), &ua_or_user_guard);
-                    self.handle_add_stylesheet(&stylesheet.0, &ua_or_user_guard);
+                    // User and user agent sheets are not subject to the page's CSP.
+                    self.handle_add_stylesheet(&stylesheet.0, None, &ua_or_user_guard);
                 }
 
                 if self.stylist.quirks_mode() != QuirksMode::NoQuirks {
@@ -1212,6 +1215,7 @@ impl LayoutThread {
                     );
                     self.handle_add_stylesheet(
                         &ua_stylesheets.quirks_mode_stylesheet.0,
+                        None,
                         &ua_or_user_guard,
                     );
                 }
@@ -1224,12 +1228,6 @@ impl LayoutThread {
             }
         }
 
-        if viewport_size_changed {
-            if let Some(mut flow) = self.try_get_layout_root(element.as_node()) {
-                LayoutThread::reflow_all_nodes(FlowRef::deref_mut(&mut flow));
-            }
-        }
-
         debug!(
             "Shadow roots in document {:?}",
             document.shadow_roots().len()
@@ -1303,6 +1301,11 @@ impl LayoutThread {
                 <RecalcStyleAndConstructFlows as DomTraversal<ServoLayoutElement>>::shared_context(
                     &traversal,
                 );
+            // The anchors of :has() selectors are styled before the elements
+            // that changed, so invalidate them before traversing.
+            for el in &elements_with_snapshot {
+                invalidate_relative_selector_anchors(*el, shared);
+            }
             RecalcStyleAndConstructFlows::pre_traverse(element, shared)
         };
 
@@ -1334,8 +1337,13 @@ impl LayoutThread {
                 0,
             );
 
-            // Retrieve the (possibly rebuilt) root flow.
-            *self.root_flow.borrow_mut() = self.try_get_layout_root(element.as_node());
+            // Rebuild the box tree from the new styles.
+            let shared =
+                <RecalcStyleAndConstructFlows as DomTraversal<ServoLayoutElement>>::shared_context(
+                    &traversal,
+                );
+            let box_tree_root = BoxTreeRoot::construct(shared, element.as_node().to_threadsafe());
+            *self.box_tree_root.borrow_mut() = Some(box_tree_root);
         }
 
         for element in elements_with_snapshot {
@@ -1361,10 +1369,15 @@ impl LayoutThread {
             layout_context.style_context.stylist.rule_tree().maybe_gc();
         }
 
+        if let Some(box_tree_root) = &*self.box_tree_root.borrow() {
+            let fragment_tree_root = box_tree_root.layout(&layout_context, self.viewport_size);
+            *self.fragment_tree_root.borrow_mut() = Some(fragment_tree_root);
+        }
+
         // Perform post-style recalculation layout passes.
-        if let Some(mut root_flow) = self.root_flow.borrow().clone() {
+        if let Some(fragment_tree_root) = &*self.fragment_tree_root.borrow() {
             self.perform_post_style_recalc_layout_passes(
-                &mut root_flow,
+                fragment_tree_root,
                 &data.reflow_goal,
                 Some(&document),
                 &mut rw_data,
@@ -1381,13 +1394,17 @@ impl LayoutThread {
         rw_data: &mut LayoutThreadData,
         context: &mut LayoutContext,
     ) {
+        let fragment_tree_root = self.fragment_tree_root.borrow();
+        let fragment_tree_root = fragment_tree_root.as_ref();
         match *reflow_goal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::ContentBoxQuery(node) => {
-                    rw_data.content_box_response = process_content_box_request(node);
+                    rw_data.content_box_response =
+                        process_content_box_request(node, fragment_tree_root);
                 },
                 &QueryMsg::ContentBoxesQuery(node) => {
-                    rw_data.content_boxes_response = process_content_boxes_request(node);
+                    rw_data.content_boxes_response =
+                        process_content_boxes_request(node, fragment_tree_root);
                 },
                 &QueryMsg::TextIndexQuery(node, point_in_node) => {
                     let point_in_node = Point2D::new(
@@ -1398,7 +1415,8 @@ impl LayoutThread {
                         TextIndexResponse(rw_data.indexable_text.text_index(node, point_in_node));
                 },
                 &QueryMsg::NodeGeometryQuery(node) => {
-                    rw_data.client_rect_response = process_node_geometry_request(node);
+                    rw_data.client_rect_response =
+                        process_node_geometry_request(node, fragment_tree_root);
                 },
                 &QueryMsg::NodeScrollGeometryQuery(node) => {
                     rw_data.scroll_area_response = process_node_scroll_area_request(node);
@@ -1414,7 +1432,8 @@ impl LayoutThread {
                         process_resolved_style_request(context, node, pseudo, property);
                 },
                 &QueryMsg::OffsetParentQuery(node) => {
-                    rw_data.offset_parent_response = process_offset_parent_query(node);
+                    rw_data.offset_parent_response =
+                        process_offset_parent_query(node, fragment_tree_root);
                 },
                 &QueryMsg::StyleQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
@@ -1494,9 +1513,9 @@ impl LayoutThread {
             );
         }
 
-        if let Some(mut root_flow) = self.root_flow.borrow().clone() {
-            // Unwrap here should not panic since self.root_flow is only ever set to Some(_)
-            // in handle_reflow() where self.document_shared_lock is as well.
+        if let Some(fragment_tree_root) = &*self.fragment_tree_root.borrow() {
+            // Unwrap here should not panic since self.fragment_tree_root is only ever set to
+            // Some(_) in handle_reflow() where self.document_shared_lock is as well.
             let author_shared_lock = self.document_shared_lock.clone().unwrap();
             let author_guard = author_shared_lock.read();
             let ua_or_user_guard = UA_STYLESHEETS.shared_lock.read();
@@ -1506,7 +1525,7 @@ impl LayoutThread {
             };
 
             self.perform_post_style_recalc_layout_passes(
-                &mut root_flow,
+                fragment_tree_root,
                 &ReflowGoal::TickAnimations,
                 None,
                 &mut *rw_data,
@@ -1516,7 +1535,7 @@ impl LayoutThread {
 
     fn perform_post_style_recalc_layout_passes(
         &self,
-        root_flow: &mut FlowRef,
+        fragment_tree: &FragmentTreeRoot,
         reflow_goal: &ReflowGoal,
         document: Option<&ServoLayoutDocument>,
         rw_data: &mut LayoutThreadData,
@@ -1528,37 +1547,22 @@ impl LayoutThread {
             || {},
         );
 
-        self.perform_post_main_layout_passes(root_flow, reflow_goal, document, rw_data);
+        self.perform_post_main_layout_passes(fragment_tree, reflow_goal, document, rw_data);
     }
 
     fn perform_post_main_layout_passes(
         &self,
-        mut root_flow: &mut FlowRef,
+        fragment_tree: &FragmentTreeRoot,
         reflow_goal: &ReflowGoal,
         document: Option<&ServoLayoutDocument>,
         rw_data: &mut LayoutThreadData,
     ) {
         // Build the display list if necessary, and send it to the painter.
-        self.compute_abs_pos_and_build_display_list(
-            reflow_goal,
-            document,
-            FlowRef::deref_mut(&mut root_flow),
-            rw_data,
-        );
+        self.compute_abs_pos_and_build_display_list(reflow_goal, document, fragment_tree, rw_data);
 
         self.generation.set(self.generation.get() + 1);
     }
 
-    fn reflow_all_nodes(flow: &mut dyn Flow) {
-        debug!("reflowing all nodes!");
-        flow.mut_base().restyle_damage.insert(
-            ServoRestyleDamage::REPAINT |
-                ServoRestyleDamage::STORE_OVERFLOW |
-                ServoRestyleDamage::REFLOW |
-                ServoRestyleDamage::REPOSITION,
-        );
-    }
-
     /// Returns profiling information which is passed to the time profiler.
     fn profiler_metadata(&self) -> Option<TimerMetadata> {
         Some(TimerMetadata {
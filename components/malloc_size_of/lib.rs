@@ -734,6 +734,9 @@ where
         match self {
             Component::AttributeOther(ref attr_selector) => attr_selector.size_of(ops),
             Component::Negation(ref components) => components.size_of(ops),
+            Component::Is(ref list) | Component::Where(ref list) | Component::Has(ref list) => {
+                list.size_of(ops)
+            },
             Component::NonTSPseudoClass(ref pseudo) => (*pseudo).size_of(ops),
             Component::Slotted(ref selector) | Component::Host(Some(ref selector)) => {
                 selector.size_of(ops)
@@ -764,7 +767,8 @@ where
             Component::FirstOfType |
             Component::LastOfType |
             Component::OnlyOfType |
-            Component::Host(None) => 0,
+            Component::Host(None) |
+            Component::RelativeSelectorAnchor => 0,
         }
     }
 }
@@ -3,45 +3,208 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::hosts::replace_host;
-use hyper::client::connect::{Connect, Destination};
+use crate::proxy::{http_tunnel, resolve_socks5_address, socks5_connect};
+use crate::proxy::{ProxyConfig, ProxyKind, Socks5Address};
+use hyper::client::connect::{Connect, Connected, Destination};
 use hyper::client::HttpConnector as HyperHttpConnector;
 use hyper::rt::Future;
 use hyper::{Body, Client};
-use hyper_openssl::HttpsConnector;
-use openssl::ssl::{SslConnector, SslConnectorBuilder, SslMethod, SslOptions};
-use openssl::x509;
-use tokio::prelude::future::Executor;
+use hyper_openssl::MaybeHttpsStream;
+use net_traits::{CertificateError, CertificateErrorKind};
+use openssl::error::ErrorStack;
+use openssl::ex_data::Index;
+use openssl::pkey::PKey;
+use openssl::ssl::{Ssl, SslConnector, SslConnectorBuilder, SslMethod, SslOptions, SslVerifyMode};
+use openssl::x509::{self, X509StoreContext, X509StoreContextRef, X509};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::prelude::future::{self, Executor};
+use tokio_openssl::ConnectConfigurationExt;
 
 pub const BUF_SIZE: usize = 32768;
 
+/// The protocols offered during the TLS handshake, in order of preference, encoded
+/// as a list of length-prefixed ALPN protocol IDs.
+/// <https://tools.ietf.org/html/rfc7301#section-3.1>
+const ALPN_PROTOCOLS: &'static [u8] = b"\x02h2\x08http/1.1";
+
+lazy_static! {
+    /// The index at which the host a TLS connection is made to is stored, for the
+    /// certificate verification callback.
+    static ref HOST_INDEX: Index<Ssl, String> = Ssl::new_ex_index().unwrap();
+    /// The index at which a TLS connection keeps the certificate rejected by its
+    /// verification, so that the connection can fail with it.
+    static ref REJECTED_INDEX: Index<Ssl, Arc<Mutex<Option<CertificateError>>>> =
+        Ssl::new_ex_index().unwrap();
+}
+
 pub struct HttpConnector {
     inner: HyperHttpConnector,
+    proxy_config: Arc<ProxyConfig>,
 }
 
 impl HttpConnector {
-    fn new() -> HttpConnector {
+    fn new(proxy_config: Arc<ProxyConfig>) -> HttpConnector {
         let mut inner = HyperHttpConnector::new(4);
         inner.enforce_http(false);
         inner.set_happy_eyeballs_timeout(None);
-        HttpConnector { inner }
+        HttpConnector {
+            inner,
+            proxy_config,
+        }
     }
 }
 
 impl Connect for HttpConnector {
     type Transport = <HyperHttpConnector as Connect>::Transport;
     type Error = <HyperHttpConnector as Connect>::Error;
-    type Future = <HyperHttpConnector as Connect>::Future;
+    type Future = Box<dyn Future<Item = (Self::Transport, Connected), Error = Self::Error> + Send>;
+
+    fn connect(&self, dest: Destination) -> Self::Future {
+        let proxy = match self.proxy_config.proxy_for(dest.scheme(), dest.host()) {
+            Some(proxy) => proxy.clone(),
+            None => {
+                // Perform host replacement when making the actual TCP connection.
+                let mut new_dest = dest.clone();
+                let addr = replace_host(dest.host());
+                new_dest.set_host(&*addr).unwrap();
+                return Box::new(self.inner.connect(new_dest));
+            },
+        };
+
+        // The TCP connection is made to the proxy, which then connects to the destination.
+        let mut proxy_dest = dest.clone();
+        let addr = replace_host(&proxy.host);
+        if proxy_dest.set_host(&*addr).is_err() {
+            return Box::new(future::err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid proxy host",
+            )));
+        }
+        proxy_dest.set_port(proxy.port);
+        let host = dest.host().to_owned();
+        let port = dest
+            .port()
+            .unwrap_or(if dest.scheme() == "https" { 443 } else { 80 });
+        let connection = self.inner.connect(proxy_dest);
+
+        match (proxy.kind, dest.scheme()) {
+            // Requests are sent to a plain HTTP proxy with their absolute URL.
+            (ProxyKind::Http, "http") => {
+                Box::new(connection.map(|(stream, connected)| (stream, connected.proxy(true))))
+            },
+            (ProxyKind::Http, _) => Box::new(connection.and_then(move |(stream, connected)| {
+                http_tunnel(stream, host, port, proxy.credentials)
+                    .map(move |stream| (stream, connected))
+            })),
+            // A `socks5` proxy is given the address of the destination, while a `socks5h`
+            // proxy resolves its host name itself.
+            (ProxyKind::Socks5, _) => Box::new(
+                resolve_socks5_address(replace_host(&host).into_owned())
+                    .join(connection)
+                    .and_then(move |(address, (stream, connected))| {
+                        socks5_connect(stream, address, port, proxy.credentials)
+                            .map(move |stream| (stream, connected))
+                    }),
+            ),
+            (ProxyKind::Socks5h, _) => Box::new(connection.and_then(move |(stream, connected)| {
+                let address = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+                    Ok(ip) => Socks5Address::Ip(ip),
+                    Err(_) => Socks5Address::Domain(host),
+                };
+                socks5_connect(stream, address, port, proxy.credentials)
+                    .map(move |stream| (stream, connected))
+            })),
+        }
+    }
+}
+
+/// A TLS connector which fails connections with the certificate rejected during their
+/// handshake, and tells hyper to speak HTTP/2 over the connection when the server
+/// selected `h2` during ALPN negotiation.
+pub struct Connector {
+    http: HttpConnector,
+    ssl_connector: SslConnector,
+}
+
+impl Connect for Connector {
+    type Transport = MaybeHttpsStream<<HttpConnector as Connect>::Transport>;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Self::Transport, Connected), Error = Self::Error> + Send>;
 
     fn connect(&self, dest: Destination) -> Self::Future {
-        // Perform host replacement when making the actual TCP connection.
-        let mut new_dest = dest.clone();
-        let addr = replace_host(dest.host());
-        new_dest.set_host(&*addr).unwrap();
-        self.inner.connect(new_dest)
+        let https = dest.scheme() == "https";
+        let host = dest.host().to_owned();
+        let connection = self.http.connect(dest);
+        if !https {
+            return Box::new(
+                connection.map(|(stream, connected)| (MaybeHttpsStream::Http(stream), connected)),
+            );
+        }
+
+        let rejected = Arc::new(Mutex::new(None));
+        let configuration = match self.ssl_connector.configure() {
+            Ok(mut configuration) => {
+                configuration.set_ex_data(*HOST_INDEX, host.clone());
+                configuration.set_ex_data(*REJECTED_INDEX, rejected.clone());
+                configuration
+            },
+            Err(error) => {
+                return Box::new(future::err(io::Error::new(io::ErrorKind::Other, error)))
+            },
+        };
+        Box::new(connection.and_then(move |(stream, connected)| {
+            configuration
+                .connect_async(&host, stream)
+                .then(move |result| match result {
+                    Ok(stream) => {
+                        let negotiated_h2 =
+                            stream.get_ref().ssl().selected_alpn_protocol() == Some(&b"h2"[..]);
+                        let connected = if negotiated_h2 {
+                            connected.negotiated_h2()
+                        } else {
+                            connected
+                        };
+                        Ok((MaybeHttpsStream::Https(stream), connected))
+                    },
+                    // A connection closed by our certificate verification fails with the
+                    // certificate, so that users can decide to trust it anyway.
+                    Err(error) => Err(match rejected.lock().unwrap().take() {
+                        Some(certificate_error) => io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            RejectedCertificate(certificate_error),
+                        ),
+                        None => io::Error::new(io::ErrorKind::Other, error.to_string()),
+                    }),
+                })
+        }))
+    }
+}
+
+/// The error with which a TLS connection fails when its certificate was rejected.
+#[derive(Debug)]
+struct RejectedCertificate(CertificateError);
+
+impl fmt::Display for RejectedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0.description())
     }
 }
 
-pub type Connector = HttpsConnector<HttpConnector>;
+impl Error for RejectedCertificate {}
+
+/// The certificate rejected by the connection whose failure caused `error`, if any.
+pub fn rejected_certificate(error: &hyper::Error) -> Option<&CertificateError> {
+    error
+        .cause2()
+        .and_then(|cause| cause.downcast_ref::<io::Error>())
+        .and_then(|error| error.get_ref())
+        .and_then(|error| error.downcast_ref::<RejectedCertificate>())
+        .map(|rejected| &rejected.0)
+}
 
 pub fn create_ssl_connector_builder(certs: &str) -> SslConnectorBuilder {
     // certs include multiple certificates. We could add all of them at once,
@@ -87,15 +250,167 @@ pub fn create_ssl_connector_builder(certs: &str) -> SslConnectorBuilder {
     ssl_connector_builder
 }
 
+/// Certificates trusted for a host even though they failed verification, because the user
+/// chose to make an exception for them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CertificateExceptions {
+    hosts: HashMap<String, Vec<String>>,
+}
+
+impl CertificateExceptions {
+    pub fn new() -> CertificateExceptions {
+        CertificateExceptions::default()
+    }
+
+    pub fn add(&mut self, host: String, certificate: String) {
+        let certificates = self.hosts.entry(host).or_insert_with(Vec::new);
+        if !certificates.contains(&certificate) {
+            certificates.push(certificate);
+        }
+    }
+
+    pub fn contains(&self, host: &str, certificate: &str) -> bool {
+        self.hosts.get(host).map_or(false, |certificates| {
+            certificates.iter().any(|c| c == certificate)
+        })
+    }
+}
+
+/// Checks the result of OpenSSL's verification of server certificates, so that exceptions
+/// can be made for rejected certificates, and so that a failed connection can be reported
+/// with the certificate that caused it.
+#[derive(Clone, Default)]
+pub struct CertificateVerifier {
+    exceptions: Arc<RwLock<CertificateExceptions>>,
+    /// The certificate last rejected for each host, which is the only one the user can be
+    /// asked to make an exception for.
+    rejected: Arc<Mutex<HashMap<String, CertificateError>>>,
+}
+
+impl CertificateVerifier {
+    pub fn new(exceptions: CertificateExceptions) -> CertificateVerifier {
+        CertificateVerifier {
+            exceptions: Arc::new(RwLock::new(exceptions)),
+            rejected: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn exceptions(&self) -> CertificateExceptions {
+        self.exceptions.read().unwrap().clone()
+    }
+
+    pub fn add_exception(&self, host: String, certificate: String) {
+        self.exceptions.write().unwrap().add(host, certificate);
+    }
+
+    /// The certificate that was last rejected for `host`, if any.
+    pub fn last_rejected(&self, host: &str) -> Option<CertificateError> {
+        self.rejected.lock().unwrap().get(host).cloned()
+    }
+
+    fn verify(&self, preverified: bool, context: &mut X509StoreContextRef) -> bool {
+        if preverified {
+            return true;
+        }
+        let ssl = match X509StoreContext::ssl_idx()
+            .ok()
+            .and_then(|index| context.ex_data(index))
+        {
+            Some(ssl) => ssl,
+            None => return false,
+        };
+        let host = match ssl.ex_data(*HOST_INDEX) {
+            Some(host) => host.clone(),
+            None => return false,
+        };
+        // Exceptions are made for the certificate of the server itself, whichever
+        // certificate of its chain failed verification.
+        let certificate = match context
+            .chain()
+            .and_then(|chain| chain.get(0))
+            .and_then(|cert| cert.to_pem().ok())
+        {
+            Some(pem) => String::from_utf8_lossy(&pem).into_owned(),
+            None => return false,
+        };
+        if self
+            .exceptions
+            .read()
+            .unwrap()
+            .contains(&host, &certificate)
+        {
+            return true;
+        }
+
+        let error = context.error();
+        let rejected = CertificateError {
+            kind: certificate_error_kind(error.as_raw()),
+            host,
+            certificate,
+            reason: error.error_string().to_owned(),
+        };
+        self.rejected
+            .lock()
+            .unwrap()
+            .insert(rejected.host.clone(), rejected.clone());
+        if let Some(slot) = ssl.ex_data(*REJECTED_INDEX) {
+            *slot.lock().unwrap() = Some(rejected);
+        }
+        false
+    }
+}
+
+/// Classify an `X509_V_ERR_*` verification error code.
+fn certificate_error_kind(code: i32) -> CertificateErrorKind {
+    match code {
+        9 => CertificateErrorKind::NotYetValid,
+        10 => CertificateErrorKind::Expired,
+        18 | 19 => CertificateErrorKind::SelfSigned,
+        2 | 20 | 21 => CertificateErrorKind::UnknownIssuer,
+        62 => CertificateErrorKind::HostnameMismatch,
+        _ => CertificateErrorKind::Other,
+    }
+}
+
+/// Present the certificate chain and private key in the PEM file `pem` to servers asking
+/// for a client certificate. The first certificate of the file is the client's.
+pub fn add_client_certificate(
+    ssl_connector_builder: &mut SslConnectorBuilder,
+    pem: &str,
+) -> Result<(), ErrorStack> {
+    let mut chain = X509::stack_from_pem(pem.as_bytes())?.into_iter();
+    if let Some(certificate) = chain.next() {
+        ssl_connector_builder.set_certificate(&certificate)?;
+    }
+    for certificate in chain {
+        ssl_connector_builder.add_extra_chain_cert(certificate)?;
+    }
+    let key = PKey::private_key_from_pem(pem.as_bytes())?;
+    ssl_connector_builder.set_private_key(&key)?;
+    ssl_connector_builder.check_private_key()
+}
+
 pub fn create_http_client<E>(
-    ssl_connector_builder: SslConnectorBuilder,
+    mut ssl_connector_builder: SslConnectorBuilder,
+    proxy_config: Arc<ProxyConfig>,
+    certificate_verifier: CertificateVerifier,
     executor: E,
 ) -> Client<Connector, Body>
 where
     E: Executor<Box<dyn Future<Error = (), Item = ()> + Send + 'static>> + Sync + Send + 'static,
 {
-    let connector =
-        HttpsConnector::with_connector(HttpConnector::new(), ssl_connector_builder).unwrap();
+    // ALPN is only configured here rather than in `create_ssl_connector_builder`, since
+    // the websocket loader shares that TLS configuration and must stay on HTTP/1.1.
+    ssl_connector_builder
+        .set_alpn_protos(ALPN_PROTOCOLS)
+        .expect("could not set ALPN protocols");
+    ssl_connector_builder.set_verify_callback(SslVerifyMode::PEER, move |preverified, context| {
+        certificate_verifier.verify(preverified, context)
+    });
+    let connector = Connector {
+        http: HttpConnector::new(proxy_config),
+        ssl_connector: ssl_connector_builder.build(),
+    };
     Client::builder()
         .http1_title_case_headers(true)
         .executor(executor)
@@ -36,6 +36,10 @@ pub struct Cookie {
     )]
     pub last_access: Tm,
     pub expiry_time: Option<Serde<Tm>>,
+    /// The top-level site this cookie was set in, if it was set in a third-party
+    /// context while cookie partitioning was enabled.
+    #[serde(default)]
+    pub partition_key: Option<String>,
 }
 
 impl Cookie {
@@ -136,6 +140,7 @@ impl Cookie {
             creation_time: now(),
             last_access: now(),
             expiry_time: expiry_time.map(Serde),
+            partition_key: None,
         })
     }
 
@@ -7,7 +7,7 @@
 
 use crate::cookie::Cookie;
 use net_traits::pub_domains::reg_suffix;
-use net_traits::CookieSource;
+use net_traits::{CookieContext, CookieSource};
 use servo_url::ServoUrl;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -18,6 +18,13 @@ pub struct CookieStorage {
     version: u32,
     cookies_map: HashMap<String, Vec<Cookie>>,
     max_per_host: usize,
+    /// The maximum number of cookies stored across all hosts, or 0 for no limit.
+    #[serde(skip)]
+    max_cookies: usize,
+    /// The maximum total size of the names and values of the cookies stored across all
+    /// hosts, or 0 for no limit.
+    #[serde(skip)]
+    max_bytes: usize,
 }
 
 impl CookieStorage {
@@ -26,9 +33,19 @@ impl CookieStorage {
             version: 1,
             cookies_map: HashMap::new(),
             max_per_host: max_cookies,
+            max_cookies: 0,
+            max_bytes: 0,
         }
     }
 
+    /// Limit the number and total size of the cookies stored across all hosts. Once a
+    /// limit is exceeded, the least recently accessed cookies are evicted.
+    pub fn set_global_limits(&mut self, max_cookies: usize, max_bytes: usize) {
+        self.max_cookies = max_cookies;
+        self.max_bytes = max_bytes;
+        self.enforce_global_limits();
+    }
+
     // http://tools.ietf.org/html/rfc6265#section-5.3
     pub fn remove(
         &mut self,
@@ -64,7 +81,8 @@ impl CookieStorage {
         let position = cookies.iter().position(|c| {
             c.cookie.domain() == cookie.cookie.domain() &&
                 c.cookie.path() == cookie.cookie.path() &&
-                c.cookie.name() == cookie.cookie.name()
+                c.cookie.name() == cookie.cookie.name() &&
+                c.partition_key == cookie.partition_key
         });
 
         if let Some(ind) = position {
@@ -92,7 +110,25 @@ impl CookieStorage {
     }
 
     // http://tools.ietf.org/html/rfc6265#section-5.3
-    pub fn push(&mut self, mut cookie: Cookie, url: &ServoUrl, source: CookieSource) {
+    pub fn push(&mut self, cookie: Cookie, url: &ServoUrl, source: CookieSource) {
+        self.push_in_context(cookie, url, source, &CookieContext::first_party())
+    }
+
+    /// Store a cookie set by a response to, or a script running at, `url`, with `context`
+    /// describing how `url` relates to the site it was loaded from.
+    pub fn push_in_context(
+        &mut self,
+        mut cookie: Cookie,
+        url: &ServoUrl,
+        source: CookieSource,
+        context: &CookieContext,
+    ) {
+        // https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.4 Step 13
+        if !context.allows_setting(cookie.cookie.same_site()) {
+            return;
+        }
+        cookie.partition_key = context.partition_key.clone();
+
         // https://www.ietf.org/id/draft-ietf-httpbis-cookie-alone-01.txt Step 1
         if cookie.cookie.secure().unwrap_or(false) && !url.is_secure_scheme() {
             return;
@@ -127,6 +163,66 @@ impl CookieStorage {
             }
         }
         cookies.push(cookie);
+        self.enforce_global_limits();
+    }
+
+    /// Evict the least recently accessed cookies, across all hosts, until the global
+    /// limits are respected again.
+    fn enforce_global_limits(&mut self) {
+        if self.max_cookies == 0 && self.max_bytes == 0 {
+            return;
+        }
+        let (count, bytes) = self.usage();
+        if !self.exceeds_global_limits(count, bytes) {
+            return;
+        }
+
+        // Expired cookies go first.
+        for cookies in self.cookies_map.values_mut() {
+            cookies.retain(|c| !is_cookie_expired(c));
+        }
+        let (mut count, mut bytes) = self.usage();
+
+        while self.exceeds_global_limits(count, bytes) {
+            let oldest = self
+                .cookies_map
+                .iter()
+                .flat_map(|(domain, cookies)| {
+                    cookies
+                        .iter()
+                        .enumerate()
+                        .map(move |(index, c)| (domain, index, c.last_access.to_timespec()))
+                })
+                .min_by_key(|&(_, _, last_access)| last_access)
+                .map(|(domain, index, _)| (domain.clone(), index));
+            let (domain, index) = match oldest {
+                Some(oldest) => oldest,
+                None => return,
+            };
+            if let Some(cookies) = self.cookies_map.get_mut(&domain) {
+                let evicted = cookies.remove(index);
+                count -= 1;
+                bytes -= cookie_size(&evicted);
+                if cookies.is_empty() {
+                    self.cookies_map.remove(&domain);
+                }
+            }
+        }
+    }
+
+    fn exceeds_global_limits(&self, count: usize, bytes: usize) -> bool {
+        (self.max_cookies != 0 && count > self.max_cookies) ||
+            (self.max_bytes != 0 && bytes > self.max_bytes)
+    }
+
+    /// The number and total size of the cookies stored.
+    fn usage(&self) -> (usize, usize) {
+        self.cookies_map
+            .values()
+            .flat_map(|cookies| cookies.iter())
+            .fold((0, 0), |(count, bytes), c| {
+                (count + 1, bytes + cookie_size(c))
+            })
     }
 
     pub fn cookie_comparator(a: &Cookie, b: &Cookie) -> Ordering {
@@ -146,6 +242,17 @@ impl CookieStorage {
 
     // http://tools.ietf.org/html/rfc6265#section-5.4
     pub fn cookies_for_url(&mut self, url: &ServoUrl, source: CookieSource) -> Option<String> {
+        self.cookies_for_url_in_context(url, source, &CookieContext::first_party())
+    }
+
+    /// The cookies to send to `url`, with `context` describing how `url` relates to the
+    /// site it is being loaded from.
+    pub fn cookies_for_url_in_context(
+        &mut self,
+        url: &ServoUrl,
+        source: CookieSource,
+        context: &CookieContext,
+    ) -> Option<String> {
         let filterer = |c: &&mut Cookie| -> bool {
             info!(
                 " === SENT COOKIE : {} {} {:?} {:?}",
@@ -159,7 +266,9 @@ impl CookieStorage {
                 c.appropriate_for_url(url, source)
             );
             // Step 1
-            c.appropriate_for_url(url, source)
+            c.appropriate_for_url(url, source) &&
+                c.partition_key == context.partition_key &&
+                context.allows(c.cookie.same_site())
         };
         // Step 2
         let domain = reg_host(url.host_str().unwrap_or(""));
@@ -211,6 +320,11 @@ fn reg_host<'a>(url: &'a str) -> String {
     reg_suffix(url).to_lowercase()
 }
 
+/// The size a cookie counts for against the global byte limit.
+fn cookie_size(cookie: &Cookie) -> usize {
+    cookie.cookie.name().len() + cookie.cookie.value().len()
+}
+
 fn is_cookie_expired(cookie: &Cookie) -> bool {
     match cookie.expiry_time {
         Some(ref t) => t.to_timespec() <= time::get_time(),
@@ -0,0 +1,321 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! An on-disk store backing the HTTP cache, persisting response metadata and bodies
+//! across sessions. Bodies are stored as individual files, while the metadata of all
+//! entries lives in a single JSON index which is written back when the cache is flushed,
+//! and every few changes so that a crash loses few of them.
+
+use crate::resource_thread::{read_json_from_file, write_json_to_file};
+use base64;
+use http::header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION};
+use http::HeaderMap;
+use net_traits::response::HttpsState;
+use openssl::sha::sha256;
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const INDEX_FILE_NAME: &'static str = "index.json";
+const BODY_FILE_EXTENSION: &'static str = "body";
+/// Number of changes to the stored entries after which the index is written to disk.
+const FLUSH_INTERVAL: usize = 16;
+
+/// The persisted form of a cached resource, minus its body.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct DiskCacheEntry {
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub request_headers: HeaderMap,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub response_headers: HeaderMap,
+    pub final_url: ServoUrl,
+    pub content_type: Option<String>,
+    pub charset: Option<String>,
+    pub metadata_status: Option<(u16, Vec<u8>)>,
+    pub location_url: Option<Result<ServoUrl, String>>,
+    pub https_state: HttpsState,
+    pub status: Option<(u16, String)>,
+    pub raw_status: Option<(u16, Vec<u8>)>,
+    pub url_list: Vec<ServoUrl>,
+    /// Freshness lifetime, in seconds.
+    pub expires: i64,
+    /// Time of the last validation, in seconds since the epoch.
+    pub last_validated: i64,
+    /// Name of the file holding the body, relative to the cache directory.
+    body_file: String,
+    body_size: u64,
+    /// Base64 of the SHA-256 digest of the body.
+    #[serde(default)]
+    body_hash: String,
+    /// Value of the access counter the last time this entry was used, for LRU eviction.
+    last_accessed: u64,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct DiskCacheIndex {
+    /// Entries keyed by the serialization of their cache key.
+    entries: HashMap<String, Vec<DiskCacheEntry>>,
+    access_counter: u64,
+}
+
+impl DiskCacheIndex {
+    fn total_size(&self) -> u64 {
+        self.entries
+            .values()
+            .flat_map(|entries| entries.iter())
+            .map(|entry| entry.body_size)
+            .sum()
+    }
+
+    fn next_access(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
+    /// Returns the key and position of the least recently used entry.
+    fn least_recently_used(&self) -> Option<(String, usize)> {
+        self.entries
+            .iter()
+            .flat_map(|(key, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, entry)| (entry.last_accessed, key, index))
+            })
+            .min_by_key(|&(last_accessed, _, _)| last_accessed)
+            .map(|(_, key, index)| (key.clone(), index))
+    }
+}
+
+/// A size-capped store of cached responses under a directory, with LRU eviction.
+pub struct DiskCache {
+    directory: PathBuf,
+    max_size: u64,
+    index: Mutex<DiskCacheIndex>,
+    /// Changes to the index since it was last written to disk.
+    unflushed_changes: AtomicUsize,
+}
+
+impl DiskCache {
+    /// Open the cache stored in `directory`, creating it if needed.
+    pub fn new(directory: PathBuf, max_size: u64) -> DiskCache {
+        if let Err(e) = fs::create_dir_all(&directory) {
+            warn!("couldn't create {}: {}", directory.display(), e);
+        }
+        let mut index = DiskCacheIndex::default();
+        if directory.join(INDEX_FILE_NAME).exists() {
+            read_json_from_file(&mut index, &directory, INDEX_FILE_NAME);
+        }
+
+        // Drop entries whose body went missing, for example after a crash.
+        for entries in index.entries.values_mut() {
+            entries.retain(|entry| directory.join(&entry.body_file).is_file());
+        }
+        index.entries.retain(|_, entries| !entries.is_empty());
+
+        // Remove bodies which are no longer referenced by the index.
+        if let Ok(files) = fs::read_dir(&directory) {
+            for file in files.filter_map(Result::ok) {
+                let path = file.path();
+                if path
+                    .extension()
+                    .map_or(true, |ext| ext != BODY_FILE_EXTENSION)
+                {
+                    continue;
+                }
+                let name = file.file_name().to_string_lossy().into_owned();
+                let referenced = index
+                    .entries
+                    .values()
+                    .flat_map(|entries| entries.iter())
+                    .any(|entry| entry.body_file == name);
+                if !referenced {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+
+        let cache = DiskCache {
+            directory,
+            max_size,
+            index: Mutex::new(index),
+            unflushed_changes: AtomicUsize::new(0),
+        };
+        cache.evict_to_fit();
+        cache
+    }
+
+    /// Returns all the stored entries for a key, along with their bodies,
+    /// marking them as recently used.
+    pub fn load(&self, key: &str) -> Vec<(DiskCacheEntry, Vec<u8>)> {
+        let mut index = self.index.lock().unwrap();
+        let access = index.next_access();
+        let entries = match index.entries.get_mut(key) {
+            Some(entries) => entries,
+            None => return vec![],
+        };
+        let directory = &self.directory;
+        entries
+            .iter_mut()
+            .filter_map(|entry| match fs::read(directory.join(&entry.body_file)) {
+                Ok(body) => {
+                    entry.last_accessed = access;
+                    Some((entry.clone(), body))
+                },
+                Err(e) => {
+                    warn!("couldn't read cached body {}: {}", entry.body_file, e);
+                    None
+                },
+            })
+            .collect()
+    }
+
+    /// Store an entry and its body, replacing any stored entry with the same status.
+    /// If an entry with the same status and body is already stored, only its
+    /// metadata is updated.
+    pub fn store(&self, key: &str, mut entry: DiskCacheEntry, body: &[u8]) {
+        let body_size = body.len() as u64;
+        if body_size > self.max_size {
+            return;
+        }
+        let body_hash = base64::encode(&sha256(body));
+        // Credentials are only needed to make the request, don't keep them on disk.
+        for name in &[AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+            entry.request_headers.remove(name);
+        }
+        let mut index = self.index.lock().unwrap();
+        entry.last_accessed = index.next_access();
+
+        let existing = index.entries.get(key).and_then(|entries| {
+            entries
+                .iter()
+                .position(|stored| stored.raw_status == entry.raw_status)
+        });
+        if let Some(position) = existing {
+            let stored = &mut index.entries.get_mut(key).unwrap()[position];
+            if stored.body_hash == body_hash && stored.body_size == body_size {
+                entry.body_file = stored.body_file.clone();
+                entry.body_size = stored.body_size;
+                entry.body_hash = body_hash;
+                *stored = entry;
+                drop(index);
+                self.note_change();
+                return;
+            }
+            let replaced = index.entries.get_mut(key).unwrap().remove(position);
+            self.remove_body(&replaced);
+        }
+
+        let body_file = format!("{}.{}", Uuid::new_v4(), BODY_FILE_EXTENSION);
+        if let Err(e) = fs::write(self.directory.join(&body_file), body) {
+            warn!("couldn't write cached body {}: {}", body_file, e);
+            return;
+        }
+        entry.body_file = body_file;
+        entry.body_size = body_size;
+        entry.body_hash = body_hash;
+        index
+            .entries
+            .entry(key.to_owned())
+            .or_insert(vec![])
+            .push(entry);
+        drop(index);
+        self.evict_to_fit();
+        self.note_change();
+    }
+
+    /// Mark all the entries stored for a key as requiring validation.
+    pub fn invalidate(&self, key: &str) {
+        {
+            let mut index = self.index.lock().unwrap();
+            if let Some(entries) = index.entries.get_mut(key) {
+                for entry in entries.iter_mut() {
+                    entry.expires = 0;
+                }
+            }
+        }
+        self.note_change();
+    }
+
+    /// Write the index to disk, so that the stored entries survive a restart.
+    pub fn flush(&self) {
+        let index = self.index.lock().unwrap();
+        write_json_to_file(&*index, &self.directory, INDEX_FILE_NAME);
+        self.unflushed_changes.store(0, Ordering::SeqCst);
+    }
+
+    /// Flush the index once enough changes piled up since the last flush.
+    fn note_change(&self) {
+        if self.unflushed_changes.fetch_add(1, Ordering::SeqCst) + 1 >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Evict least recently used entries until the stored bodies fit under the size cap.
+    fn evict_to_fit(&self) {
+        let mut index = self.index.lock().unwrap();
+        let mut total_size = index.total_size();
+        while total_size > self.max_size {
+            let (key, position) = match index.least_recently_used() {
+                Some(lru) => lru,
+                None => break,
+            };
+            let evicted = {
+                let entries = index.entries.get_mut(&key).unwrap();
+                let evicted = entries.remove(position);
+                if entries.is_empty() {
+                    index.entries.remove(&key);
+                }
+                evicted
+            };
+            total_size -= evicted.body_size;
+            self.remove_body(&evicted);
+        }
+    }
+
+    fn remove_body(&self, entry: &DiskCacheEntry) {
+        if let Err(e) = fs::remove_file(self.directory.join(&entry.body_file)) {
+            warn!("couldn't remove cached body {}: {}", entry.body_file, e);
+        }
+    }
+}
+
+impl DiskCacheEntry {
+    /// Create an entry which has not been written to disk yet.
+    pub fn new(
+        request_headers: HeaderMap,
+        response_headers: HeaderMap,
+        final_url: ServoUrl,
+    ) -> DiskCacheEntry {
+        DiskCacheEntry {
+            request_headers,
+            response_headers,
+            final_url,
+            content_type: None,
+            charset: None,
+            metadata_status: None,
+            location_url: None,
+            https_state: HttpsState::None,
+            status: None,
+            raw_status: None,
+            url_list: vec![],
+            expires: 0,
+            last_validated: 0,
+            body_file: String::new(),
+            body_size: 0,
+            body_hash: String::new(),
+            last_accessed: 0,
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::fetch::cors_cache::CorsCache;
 use crate::filemanager_thread::{fetch_file_in_chunks, FileManager, FILE_CHUNK_SIZE};
 use crate::http_loader::{determine_request_referrer, http_fetch, HttpState};
 use crate::http_loader::{set_default_accept, set_default_accept_language};
+use crate::request_interceptor::intercept_request;
 use crate::subresource_integrity::is_response_integrity_valid;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use devtools_traits::DevtoolsControlMsg;
@@ -18,6 +19,7 @@ use ipc_channel::ipc::IpcReceiver;
 use mime::{self, Mime};
 use mime_guess::guess_mime_type;
 use net_traits::blob_url_store::{parse_blob_url, BlobURLStoreError};
+use net_traits::content_security_policy::CheckResult;
 use net_traits::filemanager_thread::RelativePos;
 use net_traits::request::{CredentialsMode, Destination, Referrer, Request, RequestMode};
 use net_traits::request::{Origin, ResponseTainting, Window};
@@ -137,6 +139,30 @@ pub fn fetch_with_cors_cache(
     main_fetch(request, cache, false, false, target, &mut None, &context);
 }
 
+/// The checks of step 5 of [main fetch](https://fetch.spec.whatwg.org/#concept-main-fetch),
+/// which return the network error to respond with if the request is blocked.
+fn blocked_response(request: &Request, target: Target) -> Option<Response> {
+    let mut response = None;
+    if should_be_blocked_due_to_bad_port(&request.current_url()) {
+        response = Some(Response::network_error(NetworkError::Internal(
+            "Request attempted on bad port".into(),
+        )));
+    }
+    // TODO: handle blocking as mixed content.
+    if let Some(ref csp_list) = request.csp_list {
+        let (result, violations) = csp_list.should_request_be_blocked(request);
+        if !violations.is_empty() {
+            target.process_csp_violations(request, violations);
+        }
+        if result == CheckResult::Blocked {
+            response = Some(Response::network_error(NetworkError::Internal(
+                "Blocked by Content-Security-Policy".into(),
+            )));
+        }
+    }
+    response
+}
+
 /// [Main fetch](https://fetch.spec.whatwg.org/#concept-main-fetch)
 pub fn main_fetch(
     request: &mut Request,
@@ -163,19 +189,15 @@ pub fn main_fetch(
     }
 
     // Step 3.
-    // TODO: handle content security policy violations.
+    // Violations are reported together with the blocking check below.
 
     // Step 4.
     // TODO: handle upgrade to a potentially secure URL.
 
     // Step 5.
-    if should_be_blocked_due_to_bad_port(&request.current_url()) {
-        response = Some(Response::network_error(NetworkError::Internal(
-            "Request attempted on bad port".into(),
-        )));
+    if let Some(blocked_response) = blocked_response(request, target) {
+        response = Some(blocked_response);
     }
-    // TODO: handle blocking as mixed content.
-    // TODO: handle blocking by content security policy.
 
     // Step 6
     // TODO: handle request's client's referrer policy.
@@ -225,6 +247,21 @@ pub fn main_fetch(
     // Step 11.
     // Not applicable: see fetch_async.
 
+    // Let the embedder continue, rewrite, fulfil or fail the request if it intercepts it.
+    if response.is_none() {
+        // The lock isn't held while the embedder decides, which would block the
+        // resource thread from replacing the interceptor.
+        let interceptor = context.state.request_interceptor.read().unwrap().clone();
+        if let Some(ref interceptor) = interceptor {
+            let url = request.current_url();
+            response = intercept_request(interceptor, request, context);
+            // A request rewritten to another URL has to pass step 5 again.
+            if response.is_none() && request.current_url() != url {
+                response = blocked_response(request, target);
+            }
+        }
+    }
+
     // Step 12.
     let mut response = response.unwrap_or_else(|| {
         let current_url = request.current_url();
@@ -0,0 +1,384 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Recording of the HTTP requests made during a session, which can be exported as an
+//! [HTTP Archive](https://w3c.github.io/web-performance/specs/HAR/Overview.html) (HAR 1.2).
+
+use http::header::{self, HeaderMap};
+use http::Version;
+use net_traits::request::Request;
+use net_traits::response::{Response, ResponseBody};
+use servo_url::ServoUrl;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use time::Tm;
+
+/// <https://w3c.github.io/web-performance/specs/HAR/Overview.html#sec-object-types-log>
+#[derive(Serialize)]
+struct HarLog<'a> {
+    version: &'static str,
+    creator: HarCreator,
+    entries: &'a [HarEntry],
+}
+
+#[derive(Serialize)]
+struct HarCreator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct HarFile<'a> {
+    log: HarLog<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: String,
+    pub time: i64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: HarCache,
+    pub timings: HarTimings,
+    /// Whether the response was served from the HTTP cache without a network request.
+    #[serde(rename = "_fromCache")]
+    pub from_cache: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+}
+
+/// Information about the cache entry used for a request. Servo does not record the state
+/// of its cache around requests, so this is always empty.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HarCache {}
+
+/// The time spent in each phase of a request, in milliseconds, or -1 if the phase does not
+/// apply or is not measured.
+#[derive(Clone, Debug, Serialize)]
+pub struct HarTimings {
+    pub blocked: i64,
+    pub dns: i64,
+    pub connect: i64,
+    pub send: i64,
+    pub wait: i64,
+    pub receive: i64,
+    pub ssl: i64,
+}
+
+impl HarTimings {
+    fn new(wait: u64, receive: u64) -> HarTimings {
+        HarTimings {
+            blocked: -1,
+            dns: -1,
+            connect: -1,
+            send: 0,
+            wait: wait as i64,
+            receive: receive as i64,
+            ssl: -1,
+        }
+    }
+
+    /// The total time of the request, which is the sum of the measured phases.
+    fn total(&self) -> i64 {
+        [
+            self.blocked,
+            self.dns,
+            self.connect,
+            self.send,
+            self.wait,
+            self.receive,
+        ]
+        .iter()
+        .filter(|&&time| time > 0)
+        .sum()
+    }
+}
+
+/// Collects an entry for every request that goes to the network or is served from the
+/// HTTP cache.
+#[derive(Default)]
+pub struct HarRecorder {
+    entries: Mutex<Vec<HarEntry>>,
+}
+
+impl HarRecorder {
+    pub fn new() -> HarRecorder {
+        HarRecorder::default()
+    }
+
+    /// Record a request sent to the network once its response headers were received,
+    /// `wait` milliseconds after it was sent. The returned index identifies the entry
+    /// when its body is complete.
+    pub fn record_network_response(
+        &self,
+        started: Tm,
+        request: &Request,
+        version: Version,
+        response: &Response,
+        wait: u64,
+    ) -> usize {
+        let entry = HarEntry {
+            started_date_time: format_date_time(started),
+            time: 0,
+            request: har_request(request, version),
+            response: har_response(response, version),
+            cache: HarCache::default(),
+            timings: HarTimings::new(wait, 0),
+            from_cache: false,
+        };
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(entry);
+        entries.len() - 1
+    }
+
+    /// Complete the entry at `index` once `body_size` bytes of decoded body were received,
+    /// `receive` milliseconds after the response headers.
+    pub fn complete_network_response(&self, index: usize, body_size: usize, receive: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(index) {
+            entry.response.content.size = body_size as i64;
+            if !entry
+                .response
+                .headers
+                .iter()
+                .any(|h| h.name.eq_ignore_ascii_case("content-encoding"))
+            {
+                entry.response.body_size = body_size as i64;
+            }
+            entry.timings.receive = receive as i64;
+            entry.time = entry.timings.total();
+        }
+    }
+
+    /// Record a request answered from the HTTP cache.
+    pub fn record_cached_response(&self, started: Tm, request: &Request, response: &Response) {
+        let mut har_response = har_response(response, Version::HTTP_11);
+        har_response.content.size = match *response.body.lock().unwrap() {
+            ResponseBody::Done(ref body) => body.len() as i64,
+            _ => -1,
+        };
+        har_response.body_size = 0;
+        let entry = HarEntry {
+            started_date_time: format_date_time(started),
+            time: 0,
+            request: har_request(request, Version::HTTP_11),
+            response: har_response,
+            cache: HarCache::default(),
+            timings: HarTimings::new(0, 0),
+            from_cache: true,
+        };
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    pub fn entries(&self) -> Vec<HarEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Serialize the recorded entries as a HAR 1.2 document.
+    pub fn to_json(&self) -> String {
+        let entries = self.entries.lock().unwrap();
+        let har = HarFile {
+            log: HarLog {
+                version: "1.2",
+                creator: HarCreator {
+                    name: "Servo",
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries: &entries,
+            },
+        };
+        serde_json::to_string_pretty(&har).unwrap_or_default()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_json().as_bytes())
+    }
+}
+
+fn format_date_time(time: Tm) -> String {
+    time.to_utc().rfc3339().to_string()
+}
+
+fn http_version(version: Version) -> String {
+    format!("{:?}", version)
+}
+
+fn name_values(headers: &HeaderMap) -> Vec<HarNameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| HarNameValue {
+            name: name.as_str().to_owned(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn query_string(url: &ServoUrl) -> Vec<HarNameValue> {
+    url.as_url()
+        .query_pairs()
+        .map(|(name, value)| HarNameValue {
+            name: name.into_owned(),
+            value: value.into_owned(),
+        })
+        .collect()
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_owned()
+}
+
+fn request_cookies(headers: &HeaderMap) -> Vec<HarCookie> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next()?.trim();
+            let value = parts.next().unwrap_or("").trim();
+            Some(HarCookie {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                path: None,
+                domain: None,
+                http_only: None,
+                secure: None,
+            })
+        })
+        .collect()
+}
+
+fn response_cookies(headers: &HeaderMap) -> Vec<HarCookie> {
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| cookie_rs::Cookie::parse(value).ok())
+        .map(|cookie| HarCookie {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            path: cookie.path().map(ToOwned::to_owned),
+            domain: cookie.domain().map(ToOwned::to_owned),
+            http_only: cookie.http_only(),
+            secure: cookie.secure(),
+        })
+        .collect()
+}
+
+fn har_request(request: &Request, version: Version) -> HarRequest {
+    let url = request.current_url();
+    let post_data = request.body.as_ref().map(|body| HarPostData {
+        mime_type: content_type(&request.headers),
+        text: String::from_utf8_lossy(body).into_owned(),
+    });
+    HarRequest {
+        method: request.method.as_str().to_owned(),
+        url: url.as_str().to_owned(),
+        http_version: http_version(version),
+        cookies: request_cookies(&request.headers),
+        headers: name_values(&request.headers),
+        query_string: query_string(&url),
+        post_data,
+        headers_size: -1,
+        body_size: request.body.as_ref().map_or(0, |body| body.len() as i64),
+    }
+}
+
+fn har_response(response: &Response, version: Version) -> HarResponse {
+    let (status, status_text) = match response.raw_status {
+        Some((status, ref text)) => (status, String::from_utf8_lossy(text).into_owned()),
+        None => (0, String::new()),
+    };
+    HarResponse {
+        status,
+        status_text,
+        http_version: http_version(version),
+        cookies: response_cookies(&response.headers),
+        headers: name_values(&response.headers),
+        content: HarContent {
+            size: 0,
+            mime_type: content_type(&response.headers),
+        },
+        redirect_url: response
+            .headers
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_owned(),
+        headers_size: -1,
+        body_size: -1,
+    }
+}
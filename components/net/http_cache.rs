@@ -5,8 +5,9 @@
 #![deny(missing_docs)]
 
 //! A memory cache implementing the logic specified in <http://tools.ietf.org/html/rfc7234>
-//! and <http://tools.ietf.org/html/rfc7232>.
+//! and <http://tools.ietf.org/html/rfc7232>, optionally backed by a persistent disk cache.
 
+use crate::disk_cache::{DiskCache, DiskCacheEntry};
 use crate::fetch::methods::{Data, DoneChannel};
 use crossbeam_channel::{unbounded, Sender};
 use headers::{
@@ -24,8 +25,9 @@ use net_traits::response::{HttpsState, Response, ResponseBody};
 use net_traits::{FetchMetadata, Metadata, ResourceFetchTiming};
 use servo_arc::Arc;
 use servo_url::ServoUrl;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Bound;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::time::SystemTime;
@@ -49,6 +51,11 @@ impl CacheKey {
             url: servo_url.clone(),
         }
     }
+
+    /// The key under which resources are stored in the disk cache.
+    fn as_disk_key(&self) -> &str {
+        self.url.as_str()
+    }
 }
 
 /// A complete cached resource.
@@ -118,6 +125,12 @@ pub struct CachedResponse {
     pub response: Response,
     /// The revalidation flag for the stored response
     pub needs_validation: bool,
+    /// Whether the stored response is stale, but can be used while it is revalidated in the
+    /// background <https://tools.ietf.org/html/rfc5861#section-3>
+    pub revalidate_in_background: bool,
+    /// Whether the stored response can be used if revalidating it fails
+    /// <https://tools.ietf.org/html/rfc5861#section-4>
+    pub usable_on_error: bool,
 }
 
 /// A memory cache.
@@ -125,6 +138,11 @@ pub struct CachedResponse {
 pub struct HttpCache {
     /// cached responses.
     entries: HashMap<CacheKey, Vec<CachedResource>>,
+    /// persistent storage for completed responses, if enabled.
+    #[ignore_malloc_size_of = "Stored on disk"]
+    disk_cache: Option<DiskCache>,
+    /// resources being revalidated in the background.
+    background_revalidations: HashSet<CacheKey>,
 }
 
 /// Determine if a given response is cacheable based on the initial metadata received.
@@ -258,6 +276,25 @@ fn get_response_expiry(response: &Response) -> Duration {
     Duration::seconds(0i64)
 }
 
+/// The number of seconds given to a Cache-Control extension directive, such as those of
+/// <https://tools.ietf.org/html/rfc5861>.
+fn get_cache_control_extension(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|directive| {
+            let mut parts = directive.splitn(2, '=');
+            if !parts.next()?.trim().eq_ignore_ascii_case(name) {
+                return None;
+            }
+            parts.next()?.trim().trim_matches('"').parse::<i64>().ok()
+        })
+        .next()
+        .map(Duration::seconds)
+}
+
 /// Request Cache-Control Directives
 /// <https://tools.ietf.org/html/rfc7234#section-5.2.1>
 fn get_expiry_adjustment_from_request_headers(request: &Request, expires: Duration) -> Duration {
@@ -331,9 +368,82 @@ fn create_cached_response(
     // <https://tools.ietf.org/html/rfc7234#section-5.2.2.7>
     let has_expired =
         (adjusted_expires < time_since_validated) || (adjusted_expires == time_since_validated);
+    // An expired response can still be used for as long as the response allows it to be stale,
+    // <https://tools.ietf.org/html/rfc5861#section-3>.
+    let staleness = time_since_validated.checked_sub(&adjusted_expires);
+    let is_within_stale_window = |directive| match staleness {
+        Some(staleness) if has_expired => get_cache_control_extension(cached_headers, directive)
+            .map_or(false, |window| staleness <= window),
+        _ => false,
+    };
+    let revalidate_in_background = is_within_stale_window("stale-while-revalidate");
     CachedResponse {
         response: response,
-        needs_validation: has_expired,
+        needs_validation: has_expired && !revalidate_in_background,
+        revalidate_in_background,
+        usable_on_error: is_within_stale_window("stale-if-error"),
+    }
+}
+
+/// Persist a resource to a disk cache, if its body is complete.
+fn store_resource_to_disk(disk_cache: &DiskCache, entry_key: &CacheKey, resource: &CachedResource) {
+    if resource.aborted.load(Ordering::Relaxed) {
+        return;
+    }
+    let body = resource.body.lock().unwrap();
+    let body = match *body {
+        ResponseBody::Done(ref body) => body,
+        ResponseBody::Empty | ResponseBody::Receiving(..) => return,
+    };
+    let mut entry = DiskCacheEntry::new(
+        resource.request_headers.lock().unwrap().clone(),
+        resource.data.metadata.headers.lock().unwrap().clone(),
+        resource.data.metadata.data.final_url.clone(),
+    );
+    entry.content_type = resource.data.metadata.data.content_type.clone();
+    entry.charset = resource.data.metadata.data.charset.clone();
+    entry.metadata_status = resource.data.metadata.data.status.clone();
+    entry.location_url = resource.data.location_url.clone();
+    entry.https_state = resource.data.https_state;
+    entry.status = resource
+        .data
+        .status
+        .as_ref()
+        .map(|&(ref code, ref reason)| (code.as_u16(), reason.clone()));
+    entry.raw_status = resource.data.raw_status.clone();
+    entry.url_list = resource.data.url_list.clone();
+    entry.expires = resource.data.expires.num_seconds();
+    entry.last_validated = resource.data.last_validated.to_timespec().sec;
+    disk_cache.store(entry_key.as_disk_key(), entry, body);
+}
+
+/// Re-create a resource from its persisted form.
+fn resource_from_disk_entry(entry: DiskCacheEntry, body: Vec<u8>) -> CachedResource {
+    CachedResource {
+        request_headers: Arc::new(Mutex::new(entry.request_headers)),
+        body: Arc::new(Mutex::new(ResponseBody::Done(body))),
+        aborted: Arc::new(AtomicBool::new(false)),
+        awaiting_body: Arc::new(Mutex::new(vec![])),
+        data: Measurable(MeasurableCachedResource {
+            metadata: CachedMetadata {
+                headers: Arc::new(Mutex::new(entry.response_headers)),
+                data: Measurable(MeasurableCachedMetadata {
+                    final_url: entry.final_url,
+                    content_type: entry.content_type,
+                    charset: entry.charset,
+                    status: entry.metadata_status,
+                }),
+            },
+            location_url: entry.location_url,
+            https_state: entry.https_state,
+            status: entry.status.and_then(|(code, reason)| {
+                StatusCode::from_u16(code).ok().map(|code| (code, reason))
+            }),
+            raw_status: entry.raw_status,
+            url_list: entry.url_list,
+            expires: Duration::seconds(entry.expires),
+            last_validated: time::at(Timespec::new(entry.last_validated, 0)),
+        }),
     }
 }
 
@@ -560,13 +670,64 @@ impl HttpCache {
     pub fn new() -> HttpCache {
         HttpCache {
             entries: HashMap::new(),
+            disk_cache: None,
+            background_revalidations: HashSet::new(),
+        }
+    }
+
+    /// Create a new memory cache instance, backed by a disk cache stored in `directory`
+    /// which holds at most `max_size` bytes of response bodies.
+    pub fn new_with_disk_cache(directory: PathBuf, max_size: u64) -> HttpCache {
+        HttpCache {
+            entries: HashMap::new(),
+            disk_cache: Some(DiskCache::new(directory, max_size)),
+            background_revalidations: HashSet::new(),
+        }
+    }
+
+    /// Write the state of the disk cache, if any, so that it survives a restart.
+    pub fn flush(&self) {
+        if let Some(ref disk_cache) = self.disk_cache {
+            disk_cache.flush();
+        }
+    }
+
+    /// Load the resources for a key from the disk cache.
+    fn load_from_disk(&self, entry_key: &CacheKey) -> Vec<CachedResource> {
+        match self.disk_cache {
+            Some(ref disk_cache) => disk_cache
+                .load(entry_key.as_disk_key())
+                .into_iter()
+                .map(|(entry, body)| resource_from_disk_entry(entry, body))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Move the resources for a key from the disk cache into memory, unless
+    /// there already are resources for it in memory, so that later lookups
+    /// don't read their bodies from disk again.
+    fn promote_from_disk(&mut self, entry_key: &CacheKey) {
+        if self.disk_cache.is_none() || self.entries.contains_key(entry_key) {
+            return;
+        }
+        let disk_resources = self.load_from_disk(entry_key);
+        if !disk_resources.is_empty() {
+            self.entries.insert(entry_key.clone(), disk_resources);
+        }
+    }
+
+    /// Persist a resource to the disk cache, once its body is complete.
+    fn store_to_disk(&self, entry_key: &CacheKey, resource: &CachedResource) {
+        if let Some(ref disk_cache) = self.disk_cache {
+            store_resource_to_disk(disk_cache, entry_key, resource);
         }
     }
 
     /// Constructing Responses from Caches.
     /// <https://tools.ietf.org/html/rfc7234#section-4>
     pub fn construct_response(
-        &self,
+        &mut self,
         request: &Request,
         done_chan: &mut DoneChannel,
     ) -> Option<CachedResponse> {
@@ -576,9 +737,10 @@ impl HttpCache {
             return None;
         }
         let entry_key = CacheKey::new(request.clone());
-        let resources = self
-            .entries
-            .get(&entry_key)?
+        // Fall back to resources persisted in a previous session.
+        self.promote_from_disk(&entry_key);
+        let resources = self.entries.get(&entry_key)?;
+        let resources = resources
             .into_iter()
             .filter(|r| !r.aborted.load(Ordering::Relaxed));
         let mut candidates = vec![];
@@ -667,6 +829,19 @@ impl HttpCache {
         None
     }
 
+    /// Mark the resource for `request` as being revalidated in the background, returning
+    /// false if it already is.
+    pub fn begin_background_revalidation(&mut self, request: &Request) -> bool {
+        self.background_revalidations
+            .insert(CacheKey::new(request.clone()))
+    }
+
+    /// Mark the background revalidation of the resource for `request` as finished.
+    pub fn end_background_revalidation(&mut self, request: &Request) {
+        self.background_revalidations
+            .remove(&CacheKey::new(request.clone()));
+    }
+
     /// Updating consumers who received a response constructed with a ResponseBody::Receiving.
     pub fn update_awaiting_consumers(&mut self, request: &Request, response: &Response) {
         if let ResponseBody::Done(ref completed_body) = *response.body.lock().unwrap() {
@@ -678,6 +853,7 @@ impl HttpCache {
                     .iter()
                     .filter(|resource| resource.data.raw_status == response.raw_status);
                 for cached_resource in relevant_cached_resources {
+                    self.store_to_disk(&entry_key, cached_resource);
                     let mut awaiting_consumers = cached_resource.awaiting_body.lock().unwrap();
                     for done_sender in awaiting_consumers.drain(..) {
                         if cached_resource.aborted.load(Ordering::Relaxed) ||
@@ -708,6 +884,8 @@ impl HttpCache {
     ) -> Option<Response> {
         assert_eq!(response.status.map(|s| s.0), Some(StatusCode::NOT_MODIFIED));
         let entry_key = CacheKey::new(request.clone());
+        // The resource being refreshed may have been constructed from the disk cache.
+        self.promote_from_disk(&entry_key);
         if let Some(cached_resources) = self.entries.get_mut(&entry_key) {
             for cached_resource in cached_resources.iter_mut() {
                 // done_chan will have been set to Some(..) by http_network_fetch.
@@ -745,9 +923,14 @@ impl HttpCache {
                 constructed_response.raw_status = cached_resource.data.raw_status.clone();
                 constructed_response.url_list = cached_resource.data.url_list.clone();
                 cached_resource.data.expires = get_response_expiry(&constructed_response);
-                let mut stored_headers = cached_resource.data.metadata.headers.lock().unwrap();
-                stored_headers.extend(response.headers);
-                constructed_response.headers = stored_headers.clone();
+                {
+                    let mut stored_headers = cached_resource.data.metadata.headers.lock().unwrap();
+                    stored_headers.extend(response.headers);
+                    constructed_response.headers = stored_headers.clone();
+                }
+                if let Some(ref disk_cache) = self.disk_cache {
+                    store_resource_to_disk(disk_cache, &entry_key, cached_resource);
+                }
                 return Some(constructed_response);
             }
         }
@@ -761,6 +944,9 @@ impl HttpCache {
                 cached_resource.data.expires = Duration::seconds(0i64);
             }
         }
+        if let Some(ref disk_cache) = self.disk_cache {
+            disk_cache.invalidate(entry_key.as_disk_key());
+        }
     }
 
     /// Invalidation.
@@ -845,6 +1031,9 @@ impl HttpCache {
                 last_validated: time::now(),
             }),
         };
+        // Responses whose body is already complete can be persisted right away,
+        // others will be once they are, see `update_awaiting_consumers`.
+        self.store_to_disk(&entry_key, &entry_resource);
         let entry = self.entries.entry(entry_key).or_insert(vec![]);
         entry.push(entry_resource);
         // TODO: Complete incomplete responses, including 206 response, when stored here.
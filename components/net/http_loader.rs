@@ -2,18 +2,21 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::connector::{create_http_client, Connector};
+use crate::connector::{create_http_client, rejected_certificate, CertificateVerifier, Connector};
 use crate::cookie;
 use crate::cookie_storage::CookieStorage;
 use crate::decoder::Decoder;
 use crate::fetch::cors_cache::CorsCache;
+use crate::fetch::methods::{fetch, CancellationListener, Data, DoneChannel, FetchContext, Target};
 use crate::fetch::methods::{
     is_cors_safelisted_method, is_cors_safelisted_request_header, main_fetch,
 };
-use crate::fetch::methods::{Data, DoneChannel, FetchContext, Target};
+use crate::har::HarRecorder;
 use crate::hsts::HstsList;
 use crate::http_cache::HttpCache;
-use crate::resource_thread::AuthCache;
+use crate::network_archive::NetworkArchive;
+use crate::proxy::ProxyConfig;
+use crate::resource_thread::{AuthCache, AuthCacheEntry};
 use crossbeam_channel::{unbounded, Sender};
 use devtools_traits::{
     ChromeToDevtoolsControlMsg, DevtoolsControlMsg, HttpRequest as DevtoolsHttpRequest,
@@ -28,20 +31,24 @@ use headers::{
 use headers::{AccessControlAllowOrigin, AccessControlMaxAge};
 use headers::{CacheControl, ContentEncoding, ContentLength};
 use headers::{
-    Host, IfModifiedSince, LastModified, Origin as HyperOrigin, Pragma, Referer, UserAgent,
+    Host, IfModifiedSince, LastModified, Origin as HyperOrigin, Pragma, ProxyAuthorization,
+    Referer, UserAgent,
 };
 use http::header::{self, HeaderName, HeaderValue};
 use http::uri::Authority;
-use http::{HeaderMap, Request as HyperRequest};
+use http::{HeaderMap, Request as HyperRequest, Version};
 use hyper::{Body, Client, Method, Response as HyperResponse, StatusCode};
 use hyper_serde::Serde;
 use msg::constellation_msg::{HistoryStateId, PipelineId};
+use net_traits::content_security_policy::Violation;
 use net_traits::quality::{quality_to_value, Quality, QualityItem};
 use net_traits::request::{CacheMode, CredentialsMode, Destination, Origin};
 use net_traits::request::{RedirectMode, Referrer, Request, RequestMode};
 use net_traits::request::{ResponseTainting, ServiceWorkersMode};
+use net_traits::request_interceptor::RequestInterceptor;
 use net_traits::response::{HttpsState, Response, ResponseBody, ResponseType};
-use net_traits::{CookieSource, FetchMetadata, NetworkError, ReferrerPolicy};
+use net_traits::{CookieContext, CookieSource, FetchMetadata, FetchTaskTarget};
+use net_traits::{NetworkError, ReferrerPolicy};
 use net_traits::{RedirectEndValue, RedirectStartValue, ResourceAttribute, ResourceFetchTiming};
 use openssl::ssl::SslConnectorBuilder;
 use servo_url::{ImmutableOrigin, ServoUrl};
@@ -66,19 +73,47 @@ pub struct HttpState {
     pub cookie_jar: RwLock<CookieStorage>,
     pub http_cache: RwLock<HttpCache>,
     pub auth_cache: RwLock<AuthCache>,
+    /// The credentials sent to the HTTP proxies that requests are forwarded to, keyed by
+    /// `Proxy::credentials_key`. They are neither persisted nor shared with `auth_cache`.
+    pub proxy_auth_cache: RwLock<HashMap<String, AuthCacheEntry>>,
     pub history_states: RwLock<HashMap<HistoryStateId, Vec<u8>>>,
+    pub proxy_config: Arc<ProxyConfig>,
+    pub request_interceptor: RwLock<Option<RequestInterceptor>>,
+    pub har_recorder: Option<Arc<HarRecorder>>,
+    pub network_archive: Option<Arc<NetworkArchive>>,
+    pub certificate_verifier: CertificateVerifier,
     pub client: Client<Connector, Body>,
 }
 
 impl HttpState {
     pub fn new(ssl_connector_builder: SslConnectorBuilder) -> HttpState {
+        HttpState::new_with_proxy_config(ssl_connector_builder, ProxyConfig::default())
+    }
+
+    pub fn new_with_proxy_config(
+        ssl_connector_builder: SslConnectorBuilder,
+        proxy_config: ProxyConfig,
+    ) -> HttpState {
+        let proxy_config = Arc::new(proxy_config);
+        let certificate_verifier = CertificateVerifier::default();
         HttpState {
             hsts_list: RwLock::new(HstsList::new()),
             cookie_jar: RwLock::new(CookieStorage::new(150)),
             auth_cache: RwLock::new(AuthCache::new()),
+            proxy_auth_cache: RwLock::new(HashMap::new()),
             history_states: RwLock::new(HashMap::new()),
             http_cache: RwLock::new(HttpCache::new()),
-            client: create_http_client(ssl_connector_builder, HANDLE.lock().unwrap().executor()),
+            request_interceptor: RwLock::new(None),
+            har_recorder: None,
+            network_archive: None,
+            client: create_http_client(
+                ssl_connector_builder,
+                proxy_config.clone(),
+                certificate_verifier.clone(),
+                HANDLE.lock().unwrap().executor(),
+            ),
+            proxy_config,
+            certificate_verifier,
         }
     }
 }
@@ -231,9 +266,12 @@ pub fn set_request_cookies(
     url: &ServoUrl,
     headers: &mut HeaderMap,
     cookie_jar: &RwLock<CookieStorage>,
+    context: &CookieContext,
 ) {
     let mut cookie_jar = cookie_jar.write().unwrap();
-    if let Some(cookie_list) = cookie_jar.cookies_for_url(url, CookieSource::HTTP) {
+    if let Some(cookie_list) =
+        cookie_jar.cookies_for_url_in_context(url, CookieSource::HTTP, context)
+    {
         headers.insert(
             header::COOKIE,
             HeaderValue::from_bytes(cookie_list.as_bytes()).unwrap(),
@@ -241,12 +279,17 @@ pub fn set_request_cookies(
     }
 }
 
-fn set_cookie_for_url(cookie_jar: &RwLock<CookieStorage>, request: &ServoUrl, cookie_val: &str) {
+fn set_cookie_for_url(
+    cookie_jar: &RwLock<CookieStorage>,
+    request: &ServoUrl,
+    cookie_val: &str,
+    context: &CookieContext,
+) {
     let mut cookie_jar = cookie_jar.write().unwrap();
     let source = CookieSource::HTTP;
 
     if let Some(cookie) = cookie::Cookie::from_cookie_string(cookie_val.into(), request, source) {
-        cookie_jar.push(cookie, request, source);
+        cookie_jar.push_in_context(cookie, request, source, context);
     }
 }
 
@@ -254,14 +297,27 @@ fn set_cookies_from_headers(
     url: &ServoUrl,
     headers: &HeaderMap,
     cookie_jar: &RwLock<CookieStorage>,
+    context: &CookieContext,
 ) {
     for cookie in headers.get_all(header::SET_COOKIE) {
         if let Ok(cookie_str) = cookie.to_str() {
-            set_cookie_for_url(&cookie_jar, &url, &cookie_str);
+            set_cookie_for_url(&cookie_jar, &url, &cookie_str, context);
         }
     }
 }
 
+/// How the current URL of `request` relates to the site it is made from, which decides
+/// the cookies that are sent with it and that its response can set.
+fn cookie_context(request: &Request) -> CookieContext {
+    CookieContext::new(
+        &request.current_url(),
+        request.site_for_cookies.as_ref(),
+        request.top_level_navigation,
+        request.method.is_safe(),
+        pref!(network.cookies.partitioned),
+    )
+}
+
 fn prepare_devtools_request(
     request_id: String,
     url: ServoUrl,
@@ -337,6 +393,40 @@ fn auth_from_cache(
     }
 }
 
+/// The credentials cached for the HTTP proxy that a request for `url` is sent to as is.
+/// They are only cached once the proxy asked for them.
+fn cached_proxy_credentials(url: &ServoUrl, context: &FetchContext) -> Option<AuthCacheEntry> {
+    let proxy = context.state.proxy_config.forwarding_proxy_for(url)?;
+    let proxy_auth_cache = context.state.proxy_auth_cache.read().ok()?;
+    proxy_auth_cache.get(&proxy.credentials_key()).cloned()
+}
+
+/// Cache the credentials configured for the HTTP proxy that a request for `url` is sent
+/// to as is, returning whether that changed the credentials sent to it.
+fn cache_configured_proxy_credentials(url: &ServoUrl, context: &FetchContext) -> bool {
+    let proxy = match context.state.proxy_config.forwarding_proxy_for(url) {
+        Some(proxy) => proxy,
+        None => return false,
+    };
+    let credentials = match proxy.credentials {
+        Some(ref credentials) => credentials,
+        None => return false,
+    };
+    let mut proxy_auth_cache = match context.state.proxy_auth_cache.write() {
+        Ok(proxy_auth_cache) => proxy_auth_cache,
+        Err(_) => return false,
+    };
+    let key = proxy.credentials_key();
+    let already_sent = proxy_auth_cache.get(&key).map_or(false, |cached| {
+        cached.user_name == credentials.user_name && cached.password == credentials.password
+    });
+    if already_sent {
+        return false;
+    }
+    proxy_auth_cache.insert(key, credentials.clone());
+    true
+}
+
 fn obtain_response(
     client: &Client<Connector, Body>,
     url: &ServoUrl,
@@ -411,6 +501,12 @@ fn obtain_response(
         Err(e) => return Box::new(future::result(Err(NetworkError::from_http_error(&e)))),
     };
     *request.headers_mut() = headers.clone();
+    if let Some(credentials) = cached_proxy_credentials(url, context) {
+        let basic = Authorization::basic(&credentials.user_name, &credentials.password);
+        request
+            .headers_mut()
+            .typed_insert(ProxyAuthorization(basic.0));
+    }
 
     let connect_end = precise_time_ms();
     context
@@ -422,6 +518,7 @@ fn obtain_response(
     let request_id = request_id.map(|v| v.to_owned());
     let pipeline_id = pipeline_id.clone();
     let closure_url = url.clone();
+    let error_url = url.clone();
     let method = method.clone();
     let send_start = precise_time_ms();
 
@@ -461,10 +558,28 @@ fn obtain_response(
                 };
                 Ok((Decoder::detect(res), msg))
             })
-            .map_err(move |e| NetworkError::from_hyper_error(&e)),
+            .map_err(move |e| {
+                // A connection closed by our certificate verification is reported with the
+                // certificate, so that users can decide to trust it anyway.
+                match rejected_certificate(&e) {
+                    Some(error) => NetworkError::SslValidation(error_url, error.clone()),
+                    None => NetworkError::from_hyper_error(&e),
+                }
+            }),
     )
 }
 
+/// The ALPN protocol ID corresponding to the HTTP version of a response.
+/// <https://www.iana.org/assignments/tls-extensiontype-values/tls-extensiontype-values.xhtml#alpn-protocol-ids>
+fn alpn_protocol_id(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "http/0.9",
+        Version::HTTP_10 => "http/1.0",
+        Version::HTTP_2 => "h2",
+        _ => "http/1.1",
+    }
+}
+
 /// [HTTP fetch](https://fetch.spec.whatwg.org#http-fetch)
 pub fn http_fetch(
     request: &mut Request,
@@ -974,6 +1089,7 @@ fn http_network_or_cache_fetch(
             &current_url,
             &mut http_request.headers,
             &context.state.cookie_jar,
+            &cookie_context(&http_request),
         );
         // Substep 2
         if !http_request.headers.contains_key(header::AUTHORIZATION) {
@@ -1005,42 +1121,48 @@ fn http_network_or_cache_fetch(
     }
 
     // Step 5.18
-    // TODO If there’s a proxy-authentication entry, use it as appropriate.
+    // The proxy's credentials are added to the outgoing request by `obtain_response`,
+    // so that they are never stored with the request.
+
+    // A stale response which can be used if revalidating it fails.
+    let mut stale_response = None;
+    // Whether a stale response is served while it is revalidated in the background.
+    let mut revalidate_in_background = false;
 
     // Step 5.19
-    if let Ok(http_cache) = context.state.http_cache.read() {
+    if let Ok(mut http_cache) = context.state.http_cache.write() {
         if let Some(response_from_cache) = http_cache.construct_response(&http_request, done_chan) {
             let response_headers = response_from_cache.response.headers.clone();
+            let usable_on_error = response_from_cache.usable_on_error;
             // Substep 1, 2, 3, 4
-            let (cached_response, needs_revalidation) =
+            let (cached_response, needs_revalidation, in_background) =
                 match (http_request.cache_mode, &http_request.mode) {
-                    (CacheMode::ForceCache, _) => (Some(response_from_cache.response), false),
+                    (CacheMode::ForceCache, _) => {
+                        (Some(response_from_cache.response), false, false)
+                    },
                     (CacheMode::OnlyIfCached, &RequestMode::SameOrigin) => {
-                        (Some(response_from_cache.response), false)
+                        (Some(response_from_cache.response), false, false)
                     },
                     (CacheMode::OnlyIfCached, _) |
                     (CacheMode::NoStore, _) |
-                    (CacheMode::Reload, _) => (None, false),
+                    (CacheMode::Reload, _) => (None, false, false),
+                    (CacheMode::NoCache, _) => (Some(response_from_cache.response), true, false),
                     (_, _) => (
                         Some(response_from_cache.response),
                         response_from_cache.needs_validation,
+                        response_from_cache.revalidate_in_background,
                     ),
                 };
             if needs_revalidation {
                 revalidating_flag = true;
-                // Substep 5
-                if let Some(http_date) = response_headers.typed_get::<LastModified>() {
-                    let http_date: SystemTime = http_date.into();
-                    http_request
-                        .headers
-                        .typed_insert(IfModifiedSince::from(http_date));
-                }
-                if let Some(entity_tag) = response_headers.get(header::ETAG) {
-                    http_request
-                        .headers
-                        .insert(header::IF_NONE_MATCH, entity_tag.clone());
+                if usable_on_error {
+                    stale_response =
+                        cached_response.filter(|response| response.body.lock().unwrap().is_done());
                 }
+                // Substep 5
+                add_validation_headers(&mut http_request.headers, &response_headers);
             } else {
+                revalidate_in_background = in_background;
                 // Substep 6
                 response = cached_response;
             }
@@ -1075,6 +1197,14 @@ fn http_network_or_cache_fetch(
 
     wait_for_cached_response(done_chan, &mut response);
 
+    if let (Some(recorder), Some(response)) = (&context.state.har_recorder, &response) {
+        recorder.record_cached_response(time::now(), http_request, response);
+    }
+
+    if revalidate_in_background && response.is_some() {
+        revalidate_stale_response(http_request, context);
+    }
+
     // Step 6
     // TODO: https://infra.spec.whatwg.org/#if-aborted
 
@@ -1092,6 +1222,12 @@ fn http_network_or_cache_fetch(
         // Substep 2
         let forward_response =
             http_network_fetch(http_request, credentials_flag, done_chan, context);
+        // Use the stale response rather than an error, if it allows so.
+        // <https://tools.ietf.org/html/rfc5861#section-4>
+        if stale_response.is_some() && is_error_for_stale_response(&forward_response) {
+            response = stale_response.take();
+            *done_chan = None;
+        }
         // Substep 3
         if let Some((200..=399, _)) = forward_response.raw_status {
             if !http_request.method.is_safe() {
@@ -1169,6 +1305,20 @@ fn http_network_or_cache_fetch(
 
     // Step 11
     if let Some((StatusCode::PROXY_AUTHENTICATION_REQUIRED, _)) = response.status.as_ref() {
+        // The credentials the proxy was configured with answer its challenge without
+        // prompting, unless they were already sent.
+        if !authentication_fetch_flag &&
+            cache_configured_proxy_credentials(&http_request.current_url(), context)
+        {
+            return http_network_or_cache_fetch(
+                http_request,
+                true, /* authentication flag */
+                cors_flag,
+                done_chan,
+                context,
+            );
+        }
+
         // Step 1
         if request_has_no_window {
             return Response::network_error(NetworkError::Internal(
@@ -1222,6 +1372,92 @@ impl Drop for ResponseEndTimer {
     }
 }
 
+/// Make a request conditional on the stored response with `cached_headers` having changed.
+fn add_validation_headers(headers: &mut HeaderMap, cached_headers: &HeaderMap) {
+    if let Some(http_date) = cached_headers.typed_get::<LastModified>() {
+        let http_date: SystemTime = http_date.into();
+        headers.typed_insert(IfModifiedSince::from(http_date));
+    }
+    if let Some(entity_tag) = cached_headers.get(header::ETAG) {
+        headers.insert(header::IF_NONE_MATCH, entity_tag.clone());
+    }
+}
+
+/// Whether a response to a revalidation request is an error allowing the use of a stale
+/// response instead. <https://tools.ietf.org/html/rfc5861#section-4>
+fn is_error_for_stale_response(response: &Response) -> bool {
+    match response.raw_status {
+        Some((500, _)) | Some((502, _)) | Some((503, _)) | Some((504, _)) => true,
+        _ => response.is_network_error(),
+    }
+}
+
+/// The target of a background revalidation, whose only effect is on the cache.
+struct RevalidationTarget;
+
+impl FetchTaskTarget for RevalidationTarget {
+    fn process_request_body(&mut self, _: &Request) {}
+    fn process_request_eof(&mut self, _: &Request) {}
+    fn process_response(&mut self, _: &Response) {}
+    fn process_response_chunk(&mut self, _: Vec<u8>) {}
+    fn process_response_eof(&mut self, _: &Response) {}
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<Violation>) {}
+}
+
+/// Marks the resource of a request as being revalidated in the background, until it is
+/// dropped.
+struct BackgroundRevalidation {
+    request: Request,
+    state: Arc<HttpState>,
+}
+
+impl BackgroundRevalidation {
+    /// Mark the resource of `request` as being revalidated, unless it already is.
+    fn begin(request: &Request, state: &Arc<HttpState>) -> Option<BackgroundRevalidation> {
+        let mut http_cache = state.http_cache.write().ok()?;
+        if !http_cache.begin_background_revalidation(request) {
+            return None;
+        }
+        Some(BackgroundRevalidation {
+            request: request.clone(),
+            state: state.clone(),
+        })
+    }
+}
+
+impl Drop for BackgroundRevalidation {
+    fn drop(&mut self) {
+        if let Ok(mut http_cache) = self.state.http_cache.write() {
+            http_cache.end_background_revalidation(&self.request);
+        }
+    }
+}
+
+/// Revalidate a stale response served from the cache in the background, with a `no-cache`
+/// fetch whose response updates the cache. <https://tools.ietf.org/html/rfc5861#section-3>
+fn revalidate_stale_response(request: &Request, context: &FetchContext) {
+    let revalidation = match BackgroundRevalidation::begin(request, &context.state) {
+        Some(revalidation) => revalidation,
+        None => return,
+    };
+
+    let mut request = request.clone();
+    request.cache_mode = CacheMode::NoCache;
+    let context = FetchContext {
+        state: context.state.clone(),
+        user_agent: context.user_agent.clone(),
+        devtools_chan: None,
+        filemanager: context.filemanager.clone(),
+        cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
+        timing: Arc::new(Mutex::new(ResourceFetchTiming::new(request.timing_type()))),
+    };
+    // Fetches run on the resource threads' fetch pool, which this is spawned onto.
+    rayon::spawn(move || {
+        fetch(&mut request, &mut RevalidationTarget, &context);
+        drop(revalidation);
+    });
+}
+
 /// [HTTP network fetch](https://fetch.spec.whatwg.org/#http-network-fetch)
 fn http_network_fetch(
     request: &Request,
@@ -1241,11 +1477,26 @@ fn http_network_fetch(
     // TODO be able to tell if the connection is a failure
 
     // Step 4
-    // TODO: check whether the connection is HTTP/2
+    // The connection is HTTP/2 when the server selected `h2` during ALPN negotiation,
+    // in which case hyper multiplexes requests to that origin over it.
 
     // Step 5
     let url = request.current_url();
 
+    if let Some(ref archive) = context.state.network_archive {
+        if let Some(response) = archive.replay(request) {
+            if credentials_flag && !response.is_network_error() {
+                set_cookies_from_headers(
+                    &url,
+                    &response.headers,
+                    &context.state.cookie_jar,
+                    &cookie_context(request),
+                );
+            }
+            return response;
+        }
+    }
+
     let request_id = context
         .devtools_chan
         .as_ref()
@@ -1262,6 +1513,7 @@ fn http_network_fetch(
     // do not. Once we support other kinds of fetches we'll need to be more fine grained here
     // since things like image fetches are classified differently by devtools
     let is_xhr = request.destination == Destination::None;
+    let har_start = (time::now(), precise_time_ms());
     let response_future = obtain_response(
         &context.state.client,
         &url,
@@ -1290,6 +1542,14 @@ fn http_network_fetch(
         }
     }
 
+    context
+        .timing
+        .lock()
+        .unwrap()
+        .set_attribute(ResourceAttribute::NextHopProtocol(
+            alpn_protocol_id(res.version()).to_owned(),
+        ));
+
     let timing = context.timing.lock().unwrap().clone();
     let mut response = Response::new(url.clone(), timing);
     response.status = Some((
@@ -1304,6 +1564,25 @@ fn http_network_fetch(
     response.referrer = request.referrer.to_url().cloned();
     response.referrer_policy = request.referrer_policy.clone();
 
+    let headers_received = precise_time_ms();
+    let har_entry = context.state.har_recorder.as_ref().map(|recorder| {
+        let (started, start_ms) = har_start;
+        let index = recorder.record_network_response(
+            started,
+            request,
+            res.version(),
+            &response,
+            headers_received - start_ms,
+        );
+        (recorder.clone(), index)
+    });
+    let har_entry2 = har_entry.clone();
+    let archive_entry = context.state.network_archive.as_ref().and_then(|archive| {
+        archive
+            .record(request, &response)
+            .map(|entry| (archive.clone(), entry))
+    });
+
     let res_body = response.body.clone();
 
     // We're about to spawn a future to be waited on here
@@ -1372,6 +1651,13 @@ fn http_network_fetch(
                     ResponseBody::Receiving(ref mut body) => mem::replace(body, vec![]),
                     _ => vec![],
                 };
+                if let Some((recorder, index)) = har_entry {
+                    let receive = precise_time_ms() - headers_received;
+                    recorder.complete_network_response(index, completed_body.len(), receive);
+                }
+                if let Some((archive, entry)) = archive_entry {
+                    archive.complete(entry, completed_body.clone());
+                }
                 *body = ResponseBody::Done(completed_body);
                 timing_ptr2
                     .lock()
@@ -1386,6 +1672,10 @@ fn http_network_fetch(
                     ResponseBody::Receiving(ref mut body) => mem::replace(body, vec![]),
                     _ => vec![],
                 };
+                if let Some((recorder, index)) = har_entry2 {
+                    let receive = precise_time_ms() - headers_received;
+                    recorder.complete_network_response(index, completed_body.len(), receive);
+                }
                 *body = ResponseBody::Done(completed_body);
                 timing_ptr3
                     .lock()
@@ -1427,7 +1717,12 @@ fn http_network_fetch(
     // TODO this step isn't possible yet
     // Step 15
     if credentials_flag {
-        set_cookies_from_headers(&url, &response.headers, &context.state.cookie_jar);
+        set_cookies_from_headers(
+            &url,
+            &response.headers,
+            &context.state.cookie_jar,
+            &cookie_context(request),
+        );
     }
 
     // TODO these steps
@@ -0,0 +1,1084 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::resource_thread;
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::indexeddb_thread::IndexedDBConnectionMsg;
+use net_traits::indexeddb_thread::{CursorDirection, DatabaseMetadata, IndexMetadata};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBOperation, IndexedDBOperationResult};
+use net_traits::indexeddb_thread::{IndexedDBRecord, IndexedDBSource, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTxnMode, IndexedDBTxnReply};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreMetadata};
+use std::borrow::ToOwned;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread;
+use uuid::Uuid;
+
+/// <https://w3c.github.io/IndexedDB/#key-generator-construct>
+const MAX_GENERATED_KEY: u64 = 1 << 53;
+
+/// The size the journal of an origin can grow to before it is folded into the snapshot of
+/// its databases, unless that snapshot is larger.
+const MIN_JOURNAL_SIZE_TO_COMPACT: u64 = 1 << 20;
+
+pub trait IndexedDBThreadFactory {
+    fn new(config_dir: Option<PathBuf>) -> Self;
+}
+
+impl IndexedDBThreadFactory for IpcSender<IndexedDBThreadMsg> {
+    /// Create an IndexedDB thread
+    fn new(config_dir: Option<PathBuf>) -> IpcSender<IndexedDBThreadMsg> {
+        let (chan, port) = ipc::channel().unwrap();
+        thread::Builder::new()
+            .name("IndexedDBManager".to_owned())
+            .spawn(move || {
+                IndexedDBManager::new(port, config_dir).start();
+            })
+            .expect("Thread spawning failed");
+        chan
+    }
+}
+
+/// Records are kept sorted by key, and stored as a list since JSON objects only have
+/// string keys.
+mod records {
+    use net_traits::indexeddb_thread::IndexedDBKeyType;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S>(
+        records: &BTreeMap<IndexedDBKeyType, Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(records.iter())
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<IndexedDBKeyType, Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let records: Vec<(IndexedDBKeyType, Vec<u8>)> = Deserialize::deserialize(deserializer)?;
+        Ok(records.into_iter().collect())
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Index {
+    metadata: IndexMetadata,
+    /// The index keys of the records of the object store, with their primary keys.
+    entries: BTreeSet<(IndexedDBKeyType, IndexedDBKeyType)>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct ObjectStore {
+    key_path: Option<KeyPath>,
+    auto_increment: bool,
+    /// The next key of the key generator, for object stores that have one.
+    current_number: u64,
+    #[serde(with = "records")]
+    records: BTreeMap<IndexedDBKeyType, Vec<u8>>,
+    indexes: BTreeMap<String, Index>,
+}
+
+impl ObjectStore {
+    fn new(key_path: Option<KeyPath>, auto_increment: bool) -> ObjectStore {
+        ObjectStore {
+            key_path: key_path,
+            auto_increment: auto_increment,
+            current_number: 1,
+            records: BTreeMap::new(),
+            indexes: BTreeMap::new(),
+        }
+    }
+
+    fn metadata(&self, name: &str) -> ObjectStoreMetadata {
+        ObjectStoreMetadata {
+            name: name.to_owned(),
+            key_path: self.key_path.clone(),
+            auto_increment: self.auto_increment,
+            indexes: self
+                .indexes
+                .values()
+                .map(|index| index.metadata.clone())
+                .collect(),
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#generate-a-key>
+    fn generate_key(&mut self) -> Result<IndexedDBKeyType, IndexedDBError> {
+        if self.current_number > MAX_GENERATED_KEY {
+            return Err(IndexedDBError::KeyGeneratorExhausted);
+        }
+        let key = self.current_number;
+        self.current_number += 1;
+        Ok(IndexedDBKeyType::Number(key as f64))
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#possibly-update-the-key-generator>
+    fn possibly_update_key_generator(&mut self, key: &IndexedDBKeyType) {
+        if let IndexedDBKeyType::Number(value) = *key {
+            let value = value.floor().min(MAX_GENERATED_KEY as f64);
+            if value >= self.current_number as f64 {
+                self.current_number = value as u64 + 1;
+            }
+        }
+    }
+
+    /// Remove the entries of a record from the indexes, returning them with the names of
+    /// their indexes.
+    fn remove_index_entries(
+        &mut self,
+        primary_key: &IndexedDBKeyType,
+    ) -> Vec<(String, IndexedDBKeyType)> {
+        let mut removed = vec![];
+        for (name, index) in self.indexes.iter_mut() {
+            index.entries.retain(|&(ref index_key, ref key)| {
+                if key != primary_key {
+                    return true;
+                }
+                removed.push((name.clone(), index_key.clone()));
+                false
+            });
+        }
+        removed
+    }
+
+    /// Undo the changes of an operation to some records and to the key generator.
+    fn restore_records(&mut self, current_number: u64, records: Vec<PreviousRecord>) {
+        self.current_number = current_number;
+        for record in records.into_iter().rev() {
+            self.remove_index_entries(&record.key);
+            match record.value {
+                Some(value) => self.records.insert(record.key.clone(), value),
+                None => self.records.remove(&record.key),
+            };
+            for (name, index_key) in record.index_keys {
+                if let Some(index) = self.indexes.get_mut(&name) {
+                    index.entries.insert((index_key, record.key.clone()));
+                }
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>, returning
+    /// the key of the record and the record it replaced.
+    fn store_record(
+        &mut self,
+        key: Option<IndexedDBKeyType>,
+        value: Vec<u8>,
+        index_keys: Vec<(String, Vec<IndexedDBKeyType>)>,
+        no_overwrite: bool,
+    ) -> Result<(IndexedDBKeyType, PreviousRecord), IndexedDBError> {
+        let key = match key {
+            Some(key) => {
+                if self.auto_increment {
+                    self.possibly_update_key_generator(&key);
+                }
+                key
+            },
+            None => self.generate_key()?,
+        };
+        if no_overwrite && self.records.contains_key(&key) {
+            return Err(IndexedDBError::Constraint);
+        }
+        for &(ref name, ref keys) in &index_keys {
+            let index = match self.indexes.get(name) {
+                Some(index) => index,
+                None => continue,
+            };
+            if !index.metadata.unique {
+                continue;
+            }
+            let taken = index
+                .entries
+                .iter()
+                .any(|&(ref index_key, ref primary_key)| {
+                    *primary_key != key && keys.contains(index_key)
+                });
+            if taken {
+                return Err(IndexedDBError::Constraint);
+            }
+        }
+        let previous = PreviousRecord {
+            key: key.clone(),
+            index_keys: self.remove_index_entries(&key),
+            value: self.records.insert(key.clone(), value),
+        };
+        for (name, keys) in index_keys {
+            if let Some(index) = self.indexes.get_mut(&name) {
+                for index_key in keys {
+                    index.entries.insert((index_key, key.clone()));
+                }
+            }
+        }
+        Ok((key, previous))
+    }
+
+    /// The records of the object store or one of its indexes in the range, sorted by key
+    /// and then by primary key.
+    fn records(
+        &self,
+        index: Option<&str>,
+        range: &IndexedDBKeyRange,
+    ) -> Result<Vec<IndexedDBRecord>, IndexedDBError> {
+        let index = match index {
+            Some(name) => self.indexes.get(name).ok_or(IndexedDBError::NotFound)?,
+            None => {
+                return Ok(self
+                    .records
+                    .iter()
+                    .filter(|&(key, _)| range.contains(key))
+                    .map(|(key, value)| IndexedDBRecord {
+                        key: key.clone(),
+                        primary_key: key.clone(),
+                        value: value.clone(),
+                    })
+                    .collect());
+            },
+        };
+        Ok(index
+            .entries
+            .iter()
+            .filter(|&&(ref key, _)| range.contains(key))
+            .filter_map(|&(ref key, ref primary_key)| {
+                self.records.get(primary_key).map(|value| IndexedDBRecord {
+                    key: key.clone(),
+                    primary_key: primary_key.clone(),
+                    value: value.clone(),
+                })
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct Database {
+    version: u64,
+    object_stores: BTreeMap<String, ObjectStore>,
+}
+
+impl Database {
+    fn metadata(&self) -> DatabaseMetadata {
+        DatabaseMetadata {
+            version: self.version,
+            object_stores: self
+                .object_stores
+                .iter()
+                .map(|(name, store)| store.metadata(name))
+                .collect(),
+        }
+    }
+
+    fn store(&self, name: &str) -> Result<&ObjectStore, IndexedDBError> {
+        self.object_stores.get(name).ok_or(IndexedDBError::NotFound)
+    }
+
+    fn store_mut(&mut self, name: &str) -> Result<&mut ObjectStore, IndexedDBError> {
+        self.object_stores
+            .get_mut(name)
+            .ok_or(IndexedDBError::NotFound)
+    }
+
+    fn records(
+        &self,
+        source: &IndexedDBSource,
+        range: &IndexedDBKeyRange,
+    ) -> Result<Vec<IndexedDBRecord>, IndexedDBError> {
+        match *source {
+            IndexedDBSource::ObjectStore(ref store) => self.store(store)?.records(None, range),
+            IndexedDBSource::Index(ref store, ref index) => {
+                self.store(store)?.records(Some(index), range)
+            },
+        }
+    }
+
+    /// Run an operation, pushing what it changed to `undo`.
+    fn run(
+        &mut self,
+        operation: IndexedDBOperation,
+        undo: &mut Vec<Undo>,
+    ) -> Result<IndexedDBOperationResult, IndexedDBError> {
+        match operation {
+            IndexedDBOperation::SetVersion(version) => {
+                undo.push(Undo::Version(self.version));
+                self.version = version;
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::CreateObjectStore(name, key_path, auto_increment) => {
+                if self.object_stores.contains_key(&name) {
+                    return Err(IndexedDBError::Constraint);
+                }
+                self.object_stores
+                    .insert(name.clone(), ObjectStore::new(key_path, auto_increment));
+                undo.push(Undo::CreateObjectStore(name));
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::DeleteObjectStore(name) => {
+                let store = self
+                    .object_stores
+                    .remove(&name)
+                    .ok_or(IndexedDBError::NotFound)?;
+                undo.push(Undo::DeleteObjectStore(name, store));
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::CreateIndex(store_name, metadata, keys) => {
+                let store = self.store_mut(&store_name)?;
+                if store.indexes.contains_key(&metadata.name) {
+                    return Err(IndexedDBError::Constraint);
+                }
+                let mut entries = BTreeSet::new();
+                for (primary_key, index_keys) in keys {
+                    for index_key in index_keys {
+                        entries.insert((index_key, primary_key.clone()));
+                    }
+                }
+                if metadata.unique {
+                    let keys: BTreeSet<_> = entries.iter().map(|&(ref key, _)| key).collect();
+                    if keys.len() != entries.len() {
+                        return Err(IndexedDBError::Constraint);
+                    }
+                }
+                store.indexes.insert(
+                    metadata.name.clone(),
+                    Index {
+                        metadata: metadata.clone(),
+                        entries: entries,
+                    },
+                );
+                undo.push(Undo::CreateIndex(store_name, metadata.name));
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::DeleteIndex(store_name, name) => {
+                let index = self
+                    .store_mut(&store_name)?
+                    .indexes
+                    .remove(&name)
+                    .ok_or(IndexedDBError::NotFound)?;
+                undo.push(Undo::DeleteIndex(store_name, name, index));
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::GenerateKey(store_name) => {
+                let store = self.store_mut(&store_name)?;
+                let current_number = store.current_number;
+                let key = store.generate_key()?;
+                undo.push(Undo::Records(store_name, current_number, vec![]));
+                Ok(IndexedDBOperationResult::Key(key))
+            },
+            IndexedDBOperation::Put {
+                store: store_name,
+                key,
+                value,
+                index_keys,
+                no_overwrite,
+            } => {
+                let store = self.store_mut(&store_name)?;
+                let current_number = store.current_number;
+                match store.store_record(key, value, index_keys, no_overwrite) {
+                    Ok((key, previous)) => {
+                        undo.push(Undo::Records(store_name, current_number, vec![previous]));
+                        Ok(IndexedDBOperationResult::Key(key))
+                    },
+                    Err(error) => {
+                        // A generated key is used up even if the record isn't stored.
+                        if store.current_number != current_number {
+                            undo.push(Undo::Records(store_name, current_number, vec![]));
+                        }
+                        Err(error)
+                    },
+                }
+            },
+            IndexedDBOperation::Delete(store_name, range) => {
+                let store = self.store_mut(&store_name)?;
+                let keys: Vec<_> = store
+                    .records
+                    .keys()
+                    .filter(|key| range.contains(key))
+                    .cloned()
+                    .collect();
+                let mut previous = vec![];
+                for key in keys {
+                    previous.push(PreviousRecord {
+                        value: store.records.remove(&key),
+                        index_keys: store.remove_index_entries(&key),
+                        key: key,
+                    });
+                }
+                if !previous.is_empty() {
+                    undo.push(Undo::Records(store_name, store.current_number, previous));
+                }
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::Clear(store_name) => {
+                let store = self.store_mut(&store_name)?;
+                let records = mem::replace(&mut store.records, BTreeMap::new());
+                let entries = store
+                    .indexes
+                    .iter_mut()
+                    .map(|(name, index)| {
+                        (
+                            name.clone(),
+                            mem::replace(&mut index.entries, BTreeSet::new()),
+                        )
+                    })
+                    .collect();
+                undo.push(Undo::Clear(store_name, records, entries));
+                Ok(IndexedDBOperationResult::None)
+            },
+            IndexedDBOperation::Get(source, range) => {
+                let record = self.records(&source, &range)?.into_iter().next();
+                Ok(IndexedDBOperationResult::Record(record))
+            },
+            IndexedDBOperation::GetAll(source, range, count) => {
+                let mut records = self.records(&source, &range)?;
+                if let Some(count) = count.filter(|&count| count > 0) {
+                    records.truncate(count as usize);
+                }
+                Ok(IndexedDBOperationResult::Records(records))
+            },
+            IndexedDBOperation::Count(source, range) => {
+                let count = self.records(&source, &range)?.len();
+                Ok(IndexedDBOperationResult::Count(count as u64))
+            },
+            IndexedDBOperation::Iterate {
+                source,
+                range,
+                direction,
+                position,
+                count,
+            } => {
+                let records = self.records(&source, &range)?;
+                let record = iterate(records, direction, position, count);
+                Ok(IndexedDBOperationResult::Record(record))
+            },
+        }
+    }
+}
+
+impl Database {
+    /// Undo the change of an operation, once those of the later operations were undone.
+    fn undo(&mut self, undo: Undo) {
+        match undo {
+            Undo::Version(version) => self.version = version,
+            Undo::CreateObjectStore(name) => {
+                self.object_stores.remove(&name);
+            },
+            Undo::DeleteObjectStore(name, store) => {
+                self.object_stores.insert(name, store);
+            },
+            Undo::CreateIndex(store, name) => {
+                if let Some(store) = self.object_stores.get_mut(&store) {
+                    store.indexes.remove(&name);
+                }
+            },
+            Undo::DeleteIndex(store, name, index) => {
+                if let Some(store) = self.object_stores.get_mut(&store) {
+                    store.indexes.insert(name, index);
+                }
+            },
+            Undo::Records(store, current_number, records) => {
+                if let Some(store) = self.object_stores.get_mut(&store) {
+                    store.restore_records(current_number, records);
+                }
+            },
+            Undo::Clear(store, records, entries) => {
+                if let Some(store) = self.object_stores.get_mut(&store) {
+                    store.records = records;
+                    for (name, entries) in entries {
+                        if let Some(index) = store.indexes.get_mut(&name) {
+                            index.entries = entries;
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// The state of a record before an operation stored or deleted it.
+struct PreviousRecord {
+    key: IndexedDBKeyType,
+    /// The value of the record, or `None` if it did not exist.
+    value: Option<Vec<u8>>,
+    /// The keys of the record in the indexes of its object store, with their names.
+    index_keys: Vec<(String, IndexedDBKeyType)>,
+}
+
+/// What an operation changed, to restore if its transaction is aborted. Only what the
+/// operation replaced or removed is kept, rather than a copy of the object stores.
+enum Undo {
+    Version(u64),
+    CreateObjectStore(String),
+    DeleteObjectStore(String, ObjectStore),
+    CreateIndex(String, String),
+    DeleteIndex(String, String, Index),
+    /// The records of an object store that an operation stored or deleted, and the
+    /// current number of its key generator before the operation.
+    Records(String, u64, Vec<PreviousRecord>),
+    /// The records and the index entries of a cleared object store.
+    Clear(
+        String,
+        BTreeMap<IndexedDBKeyType, Vec<u8>>,
+        Vec<(String, BTreeSet<(IndexedDBKeyType, IndexedDBKeyType)>)>,
+    ),
+}
+
+/// The databases of an origin, as saved in the snapshot file of the origin.
+#[derive(Default, Deserialize, Serialize)]
+struct OriginDatabases {
+    /// The sequence number of the last journal entry included in the snapshot.
+    sequence: u64,
+    databases: HashMap<String, Database>,
+}
+
+impl OriginDatabases {
+    fn apply(&mut self, name: String, change: JournalChange) {
+        match change {
+            JournalChange::Commit(operations) => {
+                let database = self.databases.entry(name).or_insert_with(Database::default);
+                let mut undo = vec![];
+                for operation in operations {
+                    let _ = database.run(operation, &mut undo);
+                }
+            },
+            JournalChange::Delete => {
+                self.databases.remove(&name);
+            },
+        }
+    }
+
+    /// Apply the entries of the journal at `path` that the snapshot doesn't include yet. A
+    /// partially written last entry, left by a crash, is ignored.
+    fn replay_journal(&mut self, path: &Path) {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        for line in BufReader::new(file).lines() {
+            let entry: JournalEntry =
+                match line.ok().and_then(|line| serde_json::from_str(&line).ok()) {
+                    Some(entry) => entry,
+                    None => break,
+                };
+            if entry.sequence <= self.sequence {
+                continue;
+            }
+            self.sequence = entry.sequence;
+            self.apply(entry.name, entry.change);
+        }
+    }
+}
+
+/// A change made durable to a database of an origin, appended to the journal of the
+/// origin rather than rewriting all of its databases.
+#[derive(Deserialize, Serialize)]
+struct JournalEntry {
+    sequence: u64,
+    /// The name of the database.
+    name: String,
+    change: JournalChange,
+}
+
+#[derive(Deserialize, Serialize)]
+enum JournalChange {
+    /// The operations of a committed transaction which changed the database, to run
+    /// again in order.
+    Commit(Vec<IndexedDBOperation>),
+    Delete,
+}
+
+/// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>
+fn iterate(
+    records: Vec<IndexedDBRecord>,
+    direction: CursorDirection,
+    position: Option<(IndexedDBKeyType, IndexedDBKeyType)>,
+    count: u32,
+) -> Option<IndexedDBRecord> {
+    let skip = count.saturating_sub(1) as usize;
+    let unique =
+        direction == CursorDirection::NextUnique || direction == CursorDirection::PrevUnique;
+    let mut candidates = records;
+    if unique {
+        // Only the first record of each key is visited, in both directions.
+        candidates.dedup_by(|record, previous| record.key == previous.key);
+    }
+    let after = |record: &IndexedDBRecord| match position {
+        Some((ref key, _)) if unique => record.key > *key,
+        Some((ref key, ref primary_key)) => (&record.key, &record.primary_key) > (key, primary_key),
+        None => true,
+    };
+    let before = |record: &IndexedDBRecord| match position {
+        Some((ref key, _)) if unique => record.key < *key,
+        Some((ref key, ref primary_key)) => (&record.key, &record.primary_key) < (key, primary_key),
+        None => true,
+    };
+    match direction {
+        CursorDirection::Next | CursorDirection::NextUnique => candidates
+            .into_iter()
+            .filter(|record| after(record))
+            .nth(skip),
+        CursorDirection::Prev | CursorDirection::PrevUnique => candidates
+            .into_iter()
+            .rev()
+            .filter(|record| before(record))
+            .nth(skip),
+    }
+}
+
+/// A message about a transaction, handled once the transaction has started.
+enum TransactionRequest {
+    Operation(IndexedDBOperation),
+    Commit,
+    Abort,
+}
+
+struct Transaction {
+    id: Uuid,
+    origin: String,
+    name: String,
+    mode: IndexedDBTxnMode,
+    /// The object stores the transaction can use, or `None` if it can use all of them.
+    scope: Option<Vec<String>>,
+    replies: IpcSender<IndexedDBTxnReply>,
+    /// Whether the transactions it overlaps with and which were created before it are
+    /// finished, so that its messages can be handled.
+    started: bool,
+    /// The messages received before the transaction started.
+    queued: VecDeque<TransactionRequest>,
+    /// How to undo the changes of the transaction, in the order they were made.
+    undo: Vec<Undo>,
+    /// The operations of the transaction which changed the database, to append to the
+    /// journal when it commits.
+    changes: Vec<IndexedDBOperation>,
+}
+
+impl Transaction {
+    /// Whether the two transactions cannot run at the same time, because they use the
+    /// same object stores and one of them can change them.
+    /// <https://w3c.github.io/IndexedDB/#transaction-scheduling>
+    fn overlaps(&self, other: &Transaction) -> bool {
+        if self.origin != other.origin || self.name != other.name {
+            return false;
+        }
+        if self.mode == IndexedDBTxnMode::Readonly && other.mode == IndexedDBTxnMode::Readonly {
+            return false;
+        }
+        match (&self.scope, &other.scope) {
+            (&Some(ref scope), &Some(ref other_scope)) => {
+                scope.iter().any(|store| other_scope.contains(store))
+            },
+            _ => true,
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#connection>
+struct Connection {
+    id: Uuid,
+    origin: String,
+    name: String,
+    sender: IpcSender<IndexedDBConnectionMsg>,
+}
+
+/// A database to delete once the connections to it are closed.
+/// <https://w3c.github.io/IndexedDB/#delete-a-database>
+struct PendingDeletion {
+    origin: String,
+    name: String,
+    version: u64,
+    sender: IpcSender<Option<u64>>,
+    /// Where to send the version of the database if connections block the deletion, until
+    /// it is sent.
+    blocked: Option<IpcSender<u64>>,
+    /// The connections that were open when the deletion was requested.
+    connections: Vec<Uuid>,
+    /// The connections whose versionchange event was not fired yet.
+    awaiting: Vec<Uuid>,
+}
+
+struct IndexedDBManager {
+    port: IpcReceiver<IndexedDBThreadMsg>,
+    /// The databases of each origin whose databases were used.
+    databases: HashMap<String, OriginDatabases>,
+    /// The transactions that are not finished, in the order they were created.
+    transactions: Vec<Transaction>,
+    /// The connections that are not closed.
+    connections: Vec<Connection>,
+    pending_deletions: Vec<PendingDeletion>,
+    config_dir: Option<PathBuf>,
+}
+
+impl IndexedDBManager {
+    fn new(port: IpcReceiver<IndexedDBThreadMsg>, config_dir: Option<PathBuf>) -> IndexedDBManager {
+        IndexedDBManager {
+            port: port,
+            databases: HashMap::new(),
+            transactions: vec![],
+            connections: vec![],
+            pending_deletions: vec![],
+            config_dir: config_dir,
+        }
+    }
+}
+
+/// The name of the files the databases of an origin are saved to in the config directory,
+/// without their extension. The snapshot of the databases is saved as JSON, and the
+/// changes made since then are appended to a journal.
+fn origin_file_stem(origin: &str) -> String {
+    let mut file_name = String::from("indexeddb/");
+    for byte in origin.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-' {
+            file_name.push(byte as char);
+        } else {
+            file_name.push_str(&format!("_{:02x}", byte));
+        }
+    }
+    file_name
+}
+
+impl IndexedDBManager {
+    fn start(&mut self) {
+        loop {
+            match self.port.recv().unwrap() {
+                IndexedDBThreadMsg::Open(sender, origin, name, id, connection) => {
+                    let origin = origin.ascii_serialization();
+                    let _ = sender.send(self.open(&origin, name.clone()));
+                    self.connections.push(Connection {
+                        id: id,
+                        origin: origin,
+                        name: name,
+                        sender: connection,
+                    });
+                },
+                IndexedDBThreadMsg::CloseConnection(id) => {
+                    self.connections.retain(|connection| connection.id != id);
+                    self.connection_done(id);
+                },
+                IndexedDBThreadMsg::VersionChangeFired(id) => self.connection_done(id),
+                IndexedDBThreadMsg::DeleteDatabase(sender, blocked, origin, name) => {
+                    self.request_deletion(origin.ascii_serialization(), name, sender, blocked);
+                },
+                IndexedDBThreadMsg::BeginTransaction {
+                    id,
+                    origin,
+                    name,
+                    mode,
+                    scope,
+                    replies,
+                } => {
+                    let origin = origin.ascii_serialization();
+                    // Make sure the databases of the origin are read.
+                    self.databases(&origin);
+                    self.transactions.push(Transaction {
+                        id: id,
+                        origin: origin,
+                        name: name,
+                        mode: mode,
+                        scope: scope,
+                        replies: replies,
+                        started: false,
+                        queued: VecDeque::new(),
+                        undo: vec![],
+                        changes: vec![],
+                    });
+                    self.start_transactions();
+                },
+                IndexedDBThreadMsg::Operation(transaction, operation) => {
+                    self.handle(transaction, TransactionRequest::Operation(operation));
+                },
+                IndexedDBThreadMsg::Commit(transaction) => {
+                    self.handle(transaction, TransactionRequest::Commit);
+                },
+                IndexedDBThreadMsg::Abort(transaction) => {
+                    self.handle(transaction, TransactionRequest::Abort);
+                },
+                IndexedDBThreadMsg::Exit(sender) => {
+                    // Nothing to do since committed transactions are saved eagerly.
+                    let _ = sender.send(());
+                    break;
+                },
+            }
+        }
+    }
+
+    /// The databases of an origin, read from its files the first time they are used.
+    fn databases(&mut self, origin: &str) -> &mut HashMap<String, Database> {
+        let config_dir = &self.config_dir;
+        &mut self
+            .databases
+            .entry(origin.to_owned())
+            .or_insert_with(|| {
+                let mut saved = OriginDatabases::default();
+                if let Some(ref config_dir) = *config_dir {
+                    let stem = origin_file_stem(origin);
+                    let snapshot = format!("{}.json", stem);
+                    if config_dir.join(&snapshot).exists() {
+                        resource_thread::read_json_from_file(&mut saved, config_dir, &snapshot);
+                    }
+                    saved.replay_journal(&config_dir.join(format!("{}.journal", stem)));
+                }
+                saved
+            })
+            .databases
+    }
+
+    /// Make a change to a database durable, by appending it to the journal of its origin.
+    /// The journal is folded into the snapshot once it grows larger than it.
+    fn append_to_journal(&mut self, origin: &str, name: String, change: JournalChange) {
+        let (config_dir, saved) = match (&self.config_dir, self.databases.get_mut(origin)) {
+            (&Some(ref config_dir), Some(saved)) => (config_dir, saved),
+            _ => return,
+        };
+        let stem = origin_file_stem(origin);
+        let journal_path = config_dir.join(format!("{}.journal", stem));
+        saved.sequence += 1;
+        let entry = JournalEntry {
+            sequence: saved.sequence,
+            name: name,
+            change: change,
+        };
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(error) => {
+                warn!("couldn't serialize an IndexedDB journal entry: {}", error);
+                return;
+            },
+        };
+        line.push('\n');
+        let appended = fs::create_dir_all(config_dir.join("indexeddb"))
+            .and_then(|_| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&journal_path)
+            })
+            .and_then(|mut journal| journal.write_all(line.as_bytes()));
+        if let Err(error) = appended {
+            warn!("couldn't write to {}: {}", journal_path.display(), error);
+            return;
+        }
+
+        let snapshot = format!("{}.json", stem);
+        let size = |path: &Path| {
+            fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0)
+        };
+        let threshold = size(&config_dir.join(&snapshot)).max(MIN_JOURNAL_SIZE_TO_COMPACT);
+        if size(&journal_path) <= threshold {
+            return;
+        }
+        // The snapshot is replaced at once, and records the last entry it includes, so that
+        // the journal can be removed afterwards without losing or repeating changes.
+        let temporary = format!("{}.json.tmp", stem);
+        resource_thread::write_json_to_file(&*saved, config_dir, &temporary);
+        let compacted = fs::rename(config_dir.join(&temporary), config_dir.join(&snapshot))
+            .and_then(|_| fs::remove_file(&journal_path));
+        if let Err(error) = compacted {
+            warn!("couldn't compact {}: {}", journal_path.display(), error);
+        }
+    }
+
+    fn open(&mut self, origin: &str, name: String) -> DatabaseMetadata {
+        self.databases(origin)
+            .entry(name)
+            .or_insert_with(Database::default)
+            .metadata()
+    }
+
+    /// Fire versionchange events at the connections to a database, and delete it once they
+    /// are closed.
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>, steps 4-8
+    fn request_deletion(
+        &mut self,
+        origin: String,
+        name: String,
+        sender: IpcSender<Option<u64>>,
+        blocked: IpcSender<u64>,
+    ) {
+        let version = self
+            .databases(&origin)
+            .get(&name)
+            .map_or(0, |database| database.version);
+        let mut connections = vec![];
+        self.connections.retain(|connection| {
+            if connection.origin != origin || connection.name != name {
+                return true;
+            }
+            // A connection which can't be sent messages anymore was dropped with its global.
+            let message = IndexedDBConnectionMsg::VersionChange(version, None);
+            if connection.sender.send(message).is_err() {
+                return false;
+            }
+            connections.push(connection.id);
+            true
+        });
+        self.pending_deletions.push(PendingDeletion {
+            origin: origin,
+            name: name,
+            version: version,
+            sender: sender,
+            blocked: Some(blocked),
+            awaiting: connections.clone(),
+            connections: connections,
+        });
+        self.process_deletions();
+    }
+
+    /// A connection was closed, or fired the versionchange event it was asked to.
+    fn connection_done(&mut self, id: Uuid) {
+        for deletion in &mut self.pending_deletions {
+            deletion.awaiting.retain(|&connection| connection != id);
+        }
+        self.process_deletions();
+    }
+
+    /// Let the deletions know whether the connections they wait for are blocking them, and
+    /// delete the databases that have no connections left.
+    fn process_deletions(&mut self) {
+        let connections = &self.connections;
+        for deletion in &mut self.pending_deletions {
+            deletion
+                .connections
+                .retain(|&id| connections.iter().any(|connection| connection.id == id));
+            if deletion.connections.is_empty() || !deletion.awaiting.is_empty() {
+                continue;
+            }
+            if let Some(blocked) = deletion.blocked.take() {
+                let _ = blocked.send(deletion.version);
+            }
+        }
+        let (unblocked, pending): (Vec<_>, Vec<_>) =
+            mem::replace(&mut self.pending_deletions, vec![])
+                .into_iter()
+                .partition(|deletion| deletion.connections.is_empty());
+        self.pending_deletions = pending;
+        for deletion in unblocked {
+            let version = self.delete_database(&deletion.origin, &deletion.name);
+            let _ = deletion.sender.send(version);
+        }
+    }
+
+    /// Delete a database, returning its version. Its transactions keep failing until they
+    /// are finished.
+    fn delete_database(&mut self, origin: &str, name: &str) -> Option<u64> {
+        let database = self.databases(origin).remove(name)?;
+        self.append_to_journal(origin, name.to_owned(), JournalChange::Delete);
+        Some(database.version)
+    }
+
+    /// Start the transactions which no transaction created before them overlaps with, and
+    /// handle the messages they received until then.
+    fn start_transactions(&mut self) {
+        loop {
+            let startable = (0..self.transactions.len()).find(|&index| {
+                let transaction = &self.transactions[index];
+                !transaction.started &&
+                    !self.transactions[..index]
+                        .iter()
+                        .any(|other| other.overlaps(transaction))
+            });
+            let index = match startable {
+                Some(index) => index,
+                None => break,
+            };
+            let id = self.transactions[index].id;
+            self.transactions[index].started = true;
+            let queued = mem::replace(&mut self.transactions[index].queued, VecDeque::new());
+            for request in queued {
+                self.handle(id, request);
+            }
+        }
+    }
+
+    fn handle(&mut self, id: Uuid, request: TransactionRequest) {
+        let index = match self.transactions.iter().position(|t| t.id == id) {
+            Some(index) => index,
+            None => {
+                warn!("Message for unknown IndexedDB transaction {}", id);
+                return;
+            },
+        };
+        if !self.transactions[index].started {
+            self.transactions[index].queued.push_back(request);
+            return;
+        }
+        match request {
+            TransactionRequest::Operation(operation) => {
+                let result = self.run(index, operation);
+                let _ = self.transactions[index]
+                    .replies
+                    .send(IndexedDBTxnReply::Operation(result));
+            },
+            TransactionRequest::Commit => {
+                let transaction = self.transactions.remove(index);
+                if !transaction.changes.is_empty() {
+                    self.append_to_journal(
+                        &transaction.origin,
+                        transaction.name,
+                        JournalChange::Commit(transaction.changes),
+                    );
+                }
+                let _ = transaction.replies.send(IndexedDBTxnReply::Committed);
+                self.start_transactions();
+            },
+            TransactionRequest::Abort => {
+                let transaction = self.transactions.remove(index);
+                self.restore(&transaction.origin, &transaction.name, transaction.undo);
+                let _ = transaction.replies.send(IndexedDBTxnReply::Aborted);
+                self.start_transactions();
+            },
+        }
+    }
+
+    fn run(
+        &mut self,
+        index: usize,
+        operation: IndexedDBOperation,
+    ) -> Result<IndexedDBOperationResult, IndexedDBError> {
+        let transaction = &mut self.transactions[index];
+        let database = self
+            .databases
+            .get_mut(&transaction.origin)
+            .and_then(|saved| saved.databases.get_mut(&transaction.name))
+            .ok_or(IndexedDBError::TransactionFinished)?;
+        let change = match operation {
+            IndexedDBOperation::Get(..) |
+            IndexedDBOperation::GetAll(..) |
+            IndexedDBOperation::Count(..) |
+            IndexedDBOperation::Iterate { .. } => None,
+            _ => Some(operation.clone()),
+        };
+        let undo_len = transaction.undo.len();
+        let result = database.run(operation, &mut transaction.undo);
+        if transaction.undo.len() > undo_len {
+            transaction.changes.extend(change);
+        }
+        result
+    }
+
+    /// Undo the changes of an aborted transaction.
+    fn restore(&mut self, origin: &str, name: &str, undo: Vec<Undo>) {
+        let database = match self
+            .databases
+            .get_mut(origin)
+            .and_then(|saved| saved.databases.get_mut(name))
+        {
+            Some(database) => database,
+            None => return,
+        };
+        for undo in undo.into_iter().rev() {
+            database.undo(undo);
+        }
+    }
+}
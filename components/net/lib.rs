@@ -24,13 +24,19 @@ pub mod cookie;
 pub mod cookie_storage;
 mod data_loader;
 mod decoder;
+mod disk_cache;
 pub mod filemanager_thread;
+pub mod har;
 mod hosts;
 pub mod hsts;
 pub mod http_cache;
 pub mod http_loader;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod mime_classifier;
+pub mod network_archive;
+pub mod proxy;
+mod request_interceptor;
 pub mod resource_thread;
 mod storage_thread;
 pub mod subresource_integrity;
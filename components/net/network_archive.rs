@@ -0,0 +1,183 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Recording of network responses into an archive file, and replay of them without any
+//! network access, for reproducible runs.
+
+use http::{HeaderMap, StatusCode};
+use net_traits::request::Request;
+use net_traits::response::{Response, ResponseBody};
+use net_traits::{NetworkError, ResourceFetchTiming};
+use servo_config::opts::{NetworkArchiveMode, ReplayMissPolicy};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::sync::Mutex;
+
+/// What requests are matched by: their method, URL and body.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+struct ArchiveKey {
+    method: String,
+    url: String,
+    body: Option<Vec<u8>>,
+}
+
+impl ArchiveKey {
+    fn new(request: &Request) -> ArchiveKey {
+        ArchiveKey {
+            method: request.method.as_str().to_owned(),
+            url: request.current_url().into_string(),
+            body: request.body.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ArchivedResponse {
+    status: u16,
+    status_text: Vec<u8>,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+/// A request and its response, as stored in the archive file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ArchiveEntry {
+    request: ArchiveKey,
+    response: ArchivedResponse,
+}
+
+pub struct NetworkArchive {
+    mode: NetworkArchiveMode,
+    responses: Mutex<HashMap<ArchiveKey, ArchivedResponse>>,
+}
+
+impl NetworkArchive {
+    /// Create an archive in the given mode, reading the archive file when replaying it.
+    pub fn new(mode: NetworkArchiveMode) -> NetworkArchive {
+        let mut responses = HashMap::new();
+        if let NetworkArchiveMode::Replay(ref path, _) = mode {
+            let entries: Vec<ArchiveEntry> = match File::open(path)
+                .map_err(|e| e.to_string())
+                .and_then(|file| {
+                    serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())
+                }) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Couldn't read network archive {:?} ({})", path, e);
+                    vec![]
+                },
+            };
+            responses.extend(
+                entries
+                    .into_iter()
+                    .map(|entry| (entry.request, entry.response)),
+            );
+        }
+        NetworkArchive {
+            mode,
+            responses: Mutex::new(responses),
+        }
+    }
+
+    /// Start recording the response to `request` once its headers are received, if this
+    /// archive is being recorded. The returned entry has to be completed with the body.
+    pub fn record(&self, request: &Request, response: &Response) -> Option<ArchiveEntry> {
+        if let NetworkArchiveMode::Replay(..) = self.mode {
+            return None;
+        }
+        let (status, ref status_text) = *response.raw_status.as_ref()?;
+        Some(ArchiveEntry {
+            request: ArchiveKey::new(request),
+            response: ArchivedResponse {
+                status,
+                status_text: status_text.clone(),
+                headers: response.headers.clone(),
+                body: vec![],
+            },
+        })
+    }
+
+    /// Store a recorded entry, now that its whole response body was received. A later
+    /// response to the same request replaces an earlier one.
+    pub fn complete(&self, mut entry: ArchiveEntry, body: Vec<u8>) {
+        entry.response.body = body;
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(entry.request, entry.response);
+    }
+
+    /// The response to use for `request` when replaying this archive, or `None` if the
+    /// request has to go to the network.
+    pub fn replay(&self, request: &Request) -> Option<Response> {
+        let miss_policy = match self.mode {
+            NetworkArchiveMode::Record(_) => return None,
+            NetworkArchiveMode::Replay(_, miss_policy) => miss_policy,
+        };
+        let archived = self
+            .responses
+            .lock()
+            .unwrap()
+            .get(&ArchiveKey::new(request))
+            .cloned();
+        let archived = match (archived, miss_policy) {
+            (Some(archived), _) => archived,
+            (None, ReplayMissPolicy::Passthrough) => return None,
+            (None, ReplayMissPolicy::Error) => {
+                return Some(Response::network_error(NetworkError::Internal(format!(
+                    "{} is not in the network archive",
+                    request.current_url()
+                ))));
+            },
+            (None, ReplayMissPolicy::NotFound) => ArchivedResponse {
+                status: StatusCode::NOT_FOUND.as_u16(),
+                status_text: b"Not Found".to_vec(),
+                headers: HeaderMap::new(),
+                body: vec![],
+            },
+        };
+
+        let mut response = Response::new(
+            request.current_url(),
+            ResourceFetchTiming::new(request.timing_type()),
+        );
+        if let Ok(status) = StatusCode::from_u16(archived.status) {
+            response.status = Some((
+                status,
+                String::from_utf8_lossy(&archived.status_text).into_owned(),
+            ));
+        }
+        response.raw_status = Some((archived.status, archived.status_text));
+        response.headers = archived.headers;
+        response.referrer = request.referrer.to_url().cloned();
+        response.referrer_policy = request.referrer_policy.clone();
+        *response.body.lock().unwrap() = ResponseBody::Done(archived.body);
+        Some(response)
+    }
+
+    /// Write the recorded responses to the archive file, if this archive is being recorded.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match self.mode {
+            NetworkArchiveMode::Record(ref path) => path,
+            NetworkArchiveMode::Replay(..) => return Ok(()),
+        };
+        let entries: Vec<ArchiveEntry> = self
+            .responses
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(request, response)| ArchiveEntry {
+                request: request.clone(),
+                response: response.clone(),
+            })
+            .collect();
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, &entries).map_err(Into::into)
+    }
+}
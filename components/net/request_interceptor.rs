@@ -0,0 +1,98 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::fetch::methods::FetchContext;
+use crossbeam_channel::RecvTimeoutError;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use net_traits::request::Request;
+use net_traits::request_interceptor::RequestInterceptor;
+use net_traits::request_interceptor::{InterceptedRequest, InterceptedRequestAction};
+use net_traits::response::{Response, ResponseBody};
+use net_traits::{NetworkError, ResourceFetchTiming};
+use std::time::{Duration, Instant};
+
+/// How long the embedder has to decide what to do with an intercepted request, after
+/// which the request fails.
+const INTERCEPTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a fetch waiting for the embedder checks whether it was cancelled.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Hand `request` to the embedder if it is intercepted, and wait until it decides what
+/// to do with it, the fetch is cancelled or the embedder took too long. The request is
+/// updated in place if the embedder modified it, and the response to use instead of
+/// fetching it is returned if the embedder fulfilled or failed it.
+pub fn intercept_request(
+    interceptor: &RequestInterceptor,
+    request: &mut Request,
+    context: &FetchContext,
+) -> Option<Response> {
+    if !interceptor.intercepts(request) {
+        return None;
+    }
+
+    let (response_chan, response_port) = ipc::channel().unwrap();
+    let intercepted = InterceptedRequest {
+        url: request.current_url(),
+        method: request.method.clone(),
+        headers: request.headers.clone(),
+        destination: request.destination,
+        pipeline_id: request.pipeline_id,
+        response_chan,
+    };
+    if let Err(e) = interceptor.sender.send(intercepted) {
+        warn!(
+            "Failed to send intercepted request to the embedder ({}).",
+            e
+        );
+        return None;
+    }
+    let response_port = ROUTER.route_ipc_receiver_to_new_crossbeam_receiver(response_port);
+    let deadline = Instant::now() + INTERCEPTION_TIMEOUT;
+    let action = loop {
+        match response_port.recv_timeout(CANCELLATION_POLL_INTERVAL) {
+            Ok(action) => break action,
+            Err(RecvTimeoutError::Timeout) => {
+                if context.cancellation_listener.lock().unwrap().cancelled() {
+                    return Some(Response::network_error(NetworkError::LoadCancelled));
+                }
+                if Instant::now() >= deadline {
+                    warn!("The embedder did not decide what to do with an intercepted request.");
+                    return Some(Response::network_error(NetworkError::Internal(
+                        "Request interception timed out".into(),
+                    )));
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                warn!("Failed to receive interception decision.");
+                return None;
+            },
+        }
+    };
+
+    match action {
+        InterceptedRequestAction::Continue => None,
+        InterceptedRequestAction::Modify { url, headers } => {
+            *request.current_url_mut() = url;
+            request.headers = headers;
+            None
+        },
+        InterceptedRequestAction::Fulfill(custom_response) => {
+            let mut response = Response::new(
+                request.current_url(),
+                ResourceFetchTiming::new(request.timing_type()),
+            );
+            let (status, reason) = custom_response.raw_status;
+            response.status = Some((status, reason.clone()));
+            response.raw_status = Some((status.as_u16(), reason.into_bytes()));
+            response.headers = custom_response.headers;
+            *response.body.lock().unwrap() = ResponseBody::Done(custom_response.body);
+            Some(response)
+        },
+        InterceptedRequestAction::Fail(reason) => {
+            Some(Response::network_error(NetworkError::Internal(reason)))
+        },
+    }
+}
@@ -4,29 +4,35 @@
 
 //! A thread that takes a URL and streams back the binary data.
 
-use crate::connector::{create_http_client, create_ssl_connector_builder};
+use crate::connector::{add_client_certificate, create_http_client, create_ssl_connector_builder};
+use crate::connector::{CertificateExceptions, CertificateVerifier};
 use crate::cookie;
 use crate::cookie_storage::CookieStorage;
 use crate::fetch::cors_cache::CorsCache;
 use crate::fetch::methods::{fetch, CancellationListener, FetchContext};
 use crate::filemanager_thread::FileManager;
+use crate::har::HarRecorder;
 use crate::hsts::HstsList;
 use crate::http_cache::HttpCache;
 use crate::http_loader::{http_redirect_fetch, HttpState, HANDLE};
+use crate::indexeddb_thread::IndexedDBThreadFactory;
+use crate::network_archive::NetworkArchive;
+use crate::proxy::ProxyConfig;
 use crate::storage_thread::StorageThreadFactory;
 use crate::websocket_loader;
 use crossbeam_channel::Sender;
 use devtools_traits::DevtoolsControlMsg;
 use embedder_traits::resources::{self, Resource};
-use embedder_traits::EmbedderProxy;
+use embedder_traits::{EmbedderMsg, EmbedderProxy};
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcReceiver, IpcReceiverSet, IpcSender};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
 use net_traits::request::{Destination, RequestBuilder};
 use net_traits::response::{Response, ResponseInit};
 use net_traits::storage_thread::StorageThreadMsg;
 use net_traits::WebSocketNetworkEvent;
-use net_traits::{CookieSource, CoreResourceMsg, CoreResourceThread};
+use net_traits::{CookieContext, CookieSource, CoreResourceMsg, CoreResourceThread};
 use net_traits::{CustomResponseMediator, FetchChannels};
 use net_traits::{FetchResponseMsg, ResourceThreads, WebSocketDomAction};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
@@ -34,6 +40,7 @@ use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::mem::{Report, ReportKind, ReportsChan};
 use profile_traits::time::ProfilerChan;
 use serde::{Deserialize, Serialize};
+use servo_config::opts::NetworkArchiveMode;
 use servo_url::ServoUrl;
 use std::borrow::{Cow, ToOwned};
 use std::collections::HashMap;
@@ -54,6 +61,7 @@ pub fn new_resource_threads(
     embedder_proxy: EmbedderProxy,
     config_dir: Option<PathBuf>,
     certificate_path: Option<String>,
+    network_archive: Option<NetworkArchiveMode>,
 ) -> (ResourceThreads, ResourceThreads) {
     let (public_core, private_core) = new_core_resource_thread(
         user_agent,
@@ -63,11 +71,15 @@ pub fn new_resource_threads(
         embedder_proxy,
         config_dir.clone(),
         certificate_path,
+        network_archive,
     );
-    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(config_dir);
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(config_dir.clone());
+    let idb: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(config_dir);
+    // The databases of private browsing are only kept in memory.
+    let private_idb: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None);
     (
-        ResourceThreads::new(public_core, storage.clone()),
-        ResourceThreads::new(private_core, storage),
+        ResourceThreads::new(public_core, storage.clone(), idb),
+        ResourceThreads::new(private_core, storage, private_idb),
     )
 }
 
@@ -80,6 +92,7 @@ pub fn new_core_resource_thread(
     embedder_proxy: EmbedderProxy,
     config_dir: Option<PathBuf>,
     certificate_path: Option<String>,
+    network_archive: Option<NetworkArchiveMode>,
 ) -> (CoreResourceThread, CoreResourceThread) {
     let (public_setup_chan, public_setup_port) = ipc::channel().unwrap();
     let (private_setup_chan, private_setup_port) = ipc::channel().unwrap();
@@ -100,6 +113,7 @@ pub fn new_core_resource_thread(
                 resource_manager,
                 config_dir,
                 certificate_path,
+                network_archive,
             };
 
             mem_profiler_chan.run_with_memory_reporting(
@@ -117,39 +131,114 @@ struct ResourceChannelManager {
     resource_manager: CoreResourceManager,
     config_dir: Option<PathBuf>,
     certificate_path: Option<String>,
+    network_archive: Option<NetworkArchiveMode>,
 }
 
 fn create_http_states(
     config_dir: Option<&Path>,
     certificate_path: Option<String>,
+    network_archive: Option<NetworkArchiveMode>,
 ) -> (Arc<HttpState>, Arc<HttpState>) {
     let mut hsts_list = HstsList::from_servo_preload();
     let mut auth_cache = AuthCache::new();
-    let http_cache = HttpCache::new();
+    let mut http_cache = HttpCache::new();
     let mut cookie_jar = CookieStorage::new(150);
+    let mut certificate_exceptions = CertificateExceptions::new();
     if let Some(config_dir) = config_dir {
         read_json_from_file(&mut auth_cache, config_dir, "auth_cache.json");
+        read_json_from_file(
+            &mut certificate_exceptions,
+            config_dir,
+            "certificate_exceptions.json",
+        );
         read_json_from_file(&mut hsts_list, config_dir, "hsts_list.json");
         read_json_from_file(&mut cookie_jar, config_dir, "cookie_jar.json");
+        if pref!(network.http_cache.disk.enabled) {
+            let max_size = pref!(network.http_cache.disk.max_size).max(0) as u64;
+            http_cache = HttpCache::new_with_disk_cache(config_dir.join("http_cache"), max_size);
+        }
     }
+    let max_cookies = pref!(network.cookies.max_count).max(0) as usize;
+    let max_cookie_bytes = pref!(network.cookies.max_bytes).max(0) as usize;
+    cookie_jar.set_global_limits(max_cookies, max_cookie_bytes);
 
     let certs = match certificate_path {
         Some(ref path) => fs::read_to_string(path).expect("Couldn't not find certificate file"),
         None => resources::read_string(Resource::SSLCertificates),
     };
 
-    let ssl_connector_builder = create_ssl_connector_builder(&certs);
+    let proxy_config = ProxyConfig::from_prefs();
+    let private_proxy_config = proxy_config.clone();
+    let proxy_config = Arc::new(proxy_config);
+
+    // Only the requests of the public browsing session are recorded.
+    let har_recorder = if pref!(network.har.enabled) {
+        Some(Arc::new(HarRecorder::new()))
+    } else {
+        None
+    };
+
+    // Both sessions record into, or replay from, the same archive.
+    let network_archive = network_archive.map(|mode| Arc::new(NetworkArchive::new(mode)));
+
+    let client_certificate_path = pref!(network.tls.client_certificate);
+    let client_certificate = if client_certificate_path.is_empty() {
+        None
+    } else {
+        match fs::read_to_string(&client_certificate_path) {
+            Ok(pem) => Some(pem),
+            Err(e) => {
+                warn!(
+                    "Couldn't read client certificate {} ({})",
+                    client_certificate_path, e
+                );
+                None
+            },
+        }
+    };
+    let new_ssl_connector_builder = || {
+        let mut ssl_connector_builder = create_ssl_connector_builder(&certs);
+        if let Some(ref pem) = client_certificate {
+            if let Err(e) = add_client_certificate(&mut ssl_connector_builder, pem) {
+                warn!("Couldn't use client certificate ({})", e);
+            }
+        }
+        ssl_connector_builder
+    };
+
+    // The private session starts without the persisted certificate exceptions.
+    let certificate_verifier = CertificateVerifier::new(certificate_exceptions);
+
+    let ssl_connector_builder = new_ssl_connector_builder();
     let http_state = HttpState {
         cookie_jar: RwLock::new(cookie_jar),
         auth_cache: RwLock::new(auth_cache),
+        proxy_auth_cache: RwLock::new(HashMap::new()),
         http_cache: RwLock::new(http_cache),
         hsts_list: RwLock::new(hsts_list),
         history_states: RwLock::new(HashMap::new()),
-        client: create_http_client(ssl_connector_builder, HANDLE.lock().unwrap().executor()),
+        proxy_config: proxy_config.clone(),
+        request_interceptor: RwLock::new(None),
+        har_recorder,
+        network_archive: network_archive.clone(),
+        client: create_http_client(
+            ssl_connector_builder,
+            proxy_config,
+            certificate_verifier.clone(),
+            HANDLE.lock().unwrap().executor(),
+        ),
+        certificate_verifier,
     };
 
-    let private_ssl_client = create_ssl_connector_builder(&certs);
-    let private_http_state = HttpState::new(private_ssl_client);
+    let private_ssl_client = new_ssl_connector_builder();
+    let mut private_http_state =
+        HttpState::new_with_proxy_config(private_ssl_client, private_proxy_config);
+    private_http_state.network_archive = network_archive;
+    private_http_state
+        .cookie_jar
+        .write()
+        .unwrap()
+        .set_global_limits(max_cookies, max_cookie_bytes);
 
     (Arc::new(http_state), Arc::new(private_http_state))
 }
@@ -165,6 +254,7 @@ impl ResourceChannelManager {
         let (public_http_state, private_http_state) = create_http_states(
             self.config_dir.as_ref().map(Deref::deref),
             self.certificate_path.clone(),
+            self.network_archive.clone(),
         );
 
         let mut rx_set = IpcReceiverSet::new().unwrap();
@@ -257,28 +347,36 @@ impl ResourceChannelManager {
             CoreResourceMsg::FetchRedirect(req_init, res_init, sender, cancel_chan) => self
                 .resource_manager
                 .fetch(req_init, Some(res_init), sender, http_state, cancel_chan),
-            CoreResourceMsg::SetCookieForUrl(request, cookie, source) => self
+            CoreResourceMsg::SetCookieForUrl(request, cookie, source, context) => self
                 .resource_manager
-                .set_cookie_for_url(&request, cookie.into_inner(), source, http_state),
-            CoreResourceMsg::SetCookiesForUrl(request, cookies, source) => {
+                .set_cookie_for_url(&request, cookie.into_inner(), source, &context, http_state),
+            CoreResourceMsg::SetCookiesForUrl(request, cookies, source, context) => {
                 for cookie in cookies {
                     self.resource_manager.set_cookie_for_url(
                         &request,
                         cookie.into_inner(),
                         source,
+                        &context,
                         http_state,
                     );
                 }
             },
-            CoreResourceMsg::GetCookiesForUrl(url, consumer, source) => {
+            CoreResourceMsg::GetCookiesForUrl(url, consumer, source, context) => {
                 let mut cookie_jar = http_state.cookie_jar.write().unwrap();
                 consumer
-                    .send(cookie_jar.cookies_for_url(&url, source))
+                    .send(cookie_jar.cookies_for_url_in_context(&url, source, &context))
                     .unwrap();
             },
             CoreResourceMsg::NetworkMediator(mediator_chan) => {
                 self.resource_manager.swmanager_chan = Some(mediator_chan)
             },
+            CoreResourceMsg::SetRequestInterceptor(interceptor) => {
+                *http_state.request_interceptor.write().unwrap() = interceptor;
+            },
+            CoreResourceMsg::SaveHar(path) => save_har(&http_state, &path),
+            CoreResourceMsg::PromptCertificateException(host, sender) => self
+                .resource_manager
+                .prompt_certificate_exception(host, sender, http_state),
             CoreResourceMsg::GetCookiesDataForUrl(url, consumer, source) => {
                 let mut cookie_jar = http_state.cookie_jar.write().unwrap();
                 let cookies = cookie_jar
@@ -323,6 +421,24 @@ impl ResourceChannelManager {
                         Ok(hsts) => write_json_to_file(&*hsts, config_dir, "hsts_list.json"),
                         Err(_) => warn!("Error writing hsts list to disk"),
                     }
+                    write_json_to_file(
+                        &http_state.certificate_verifier.exceptions(),
+                        config_dir,
+                        "certificate_exceptions.json",
+                    );
+                    match http_state.http_cache.read() {
+                        Ok(http_cache) => http_cache.flush(),
+                        Err(_) => warn!("Error writing http cache to disk"),
+                    }
+                }
+                if let Some(ref archive) = http_state.network_archive {
+                    if let Err(e) = archive.save() {
+                        warn!("Error writing network archive to disk ({})", e);
+                    }
+                }
+                let har_path = pref!(network.har.path);
+                if !har_path.is_empty() {
+                    save_har(&http_state, Path::new(&har_path));
                 }
                 let _ = sender.send(());
                 return false;
@@ -332,6 +448,18 @@ impl ResourceChannelManager {
     }
 }
 
+/// Write the requests recorded in `http_state` as a HAR file at `path`.
+fn save_har(http_state: &HttpState, path: &Path) {
+    match http_state.har_recorder {
+        Some(ref recorder) => {
+            if let Err(e) = recorder.write_to_file(path) {
+                warn!("Error writing HAR to {:?}: {}", path, e);
+            }
+        },
+        None => warn!("Not writing HAR to {:?}, recording is disabled", path),
+    }
+}
+
 pub fn read_json_from_file<T>(data: &mut T, config_dir: &Path, filename: &str)
 where
     T: for<'de> Deserialize<'de>,
@@ -416,6 +544,7 @@ pub struct CoreResourceManager {
     devtools_chan: Option<Sender<DevtoolsControlMsg>>,
     swmanager_chan: Option<IpcSender<CustomResponseMediator>>,
     filemanager: FileManager,
+    embedder_proxy: EmbedderProxy,
     fetch_pool: rayon::ThreadPool,
     certificate_path: Option<String>,
 }
@@ -436,22 +565,64 @@ impl CoreResourceManager {
             user_agent: user_agent,
             devtools_chan: devtools_channel,
             swmanager_chan: None,
-            filemanager: FileManager::new(embedder_proxy),
+            filemanager: FileManager::new(embedder_proxy.clone()),
+            embedder_proxy,
             fetch_pool: pool,
             certificate_path,
         }
     }
 
+    /// Ask the embedder whether to trust the certificate last rejected for `host`, making an
+    /// exception for it if the user agrees, and send the answer to `sender`. The certificate
+    /// is never taken from the content process, which could otherwise trust any certificate.
+    fn prompt_certificate_exception(
+        &self,
+        host: String,
+        sender: IpcSender<bool>,
+        http_state: &Arc<HttpState>,
+    ) {
+        let error = match http_state.certificate_verifier.last_rejected(&host) {
+            Some(error) => error,
+            None => {
+                let _ = sender.send(false);
+                return;
+            },
+        };
+        let embedder_proxy = self.embedder_proxy.clone();
+        let certificate_verifier = http_state.certificate_verifier.clone();
+        thread::Builder::new()
+            .name("certificate exception".to_owned())
+            .spawn(move || {
+                let (embedder_sender, embedder_receiver) =
+                    ipc::channel().expect("Failed to create IPC channel!");
+                embedder_proxy.send((
+                    None,
+                    EmbedderMsg::AllowCertificateException(
+                        error.host.clone(),
+                        error.description(),
+                        embedder_sender,
+                    ),
+                ));
+                let allow = embedder_receiver.recv().unwrap_or(false);
+                if allow {
+                    certificate_verifier.add_exception(error.host, error.certificate);
+                }
+                let _ = sender.send(allow);
+            })
+            .expect("Thread spawning failed");
+    }
+
     fn set_cookie_for_url(
         &mut self,
         request: &ServoUrl,
         cookie: cookie_rs::Cookie<'static>,
         source: CookieSource,
+        context: &CookieContext,
         http_state: &Arc<HttpState>,
     ) {
         if let Some(cookie) = cookie::Cookie::new_wrapped(cookie, request, source) {
             let mut cookie_jar = http_state.cookie_jar.write().unwrap();
-            cookie_jar.push(cookie, request, source)
+            cookie_jar.push_in_context(cookie, request, source, context)
         }
     }
 
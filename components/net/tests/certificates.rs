@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::{fetch_url, make_ssl_server, new_fetch_context, response_body, Server};
+use hyper::{Body, Request as HyperRequest, Response as HyperResponse};
+use net::connector::{add_client_certificate, create_ssl_connector_builder};
+use net_traits::response::Response;
+use net_traits::{CertificateError, CertificateErrorKind, NetworkError};
+use servo_url::ServoUrl;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn certificate_path() -> PathBuf {
+    Path::new("../../resources/self_signed_certificate_for_testing.crt")
+        .canonicalize()
+        .unwrap()
+}
+
+fn private_key_path() -> PathBuf {
+    Path::new("../../resources/privatekey_for_testing.key")
+        .canonicalize()
+        .unwrap()
+}
+
+/// Start a server for `localhost` with a self-signed certificate, and return its HTTPS URL.
+fn make_self_signed_server() -> (Server, ServoUrl) {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = b"Yay!".to_vec().into();
+    };
+    let (server, mut url) = make_ssl_server(handler, certificate_path(), private_key_path());
+    url.as_mut_url().set_scheme("https").unwrap();
+    (server, url)
+}
+
+fn certificate_error(response: &Response) -> CertificateError {
+    match response.get_network_error() {
+        Some(&NetworkError::SslValidation(_, ref error)) => error.clone(),
+        error => panic!("expected a certificate error, got {:?}", error),
+    }
+}
+
+#[test]
+fn test_self_signed_certificate_is_reported() {
+    let (server, url) = make_self_signed_server();
+    let mut context = new_fetch_context(None, None);
+    let response = fetch_url(url, &mut context);
+    let _ = server.close();
+
+    let error = certificate_error(&response);
+    assert_eq!(error.kind, CertificateErrorKind::SelfSigned);
+    assert_eq!(error.host, "localhost");
+    assert!(error.certificate.starts_with("-----BEGIN CERTIFICATE-----"));
+    assert!(!error.reason.is_empty());
+
+    // Only the certificate rejected by net itself can be prompted about.
+    let verifier = &context.state.certificate_verifier;
+    assert_eq!(verifier.last_rejected("localhost"), Some(error));
+    assert!(verifier.last_rejected("example.com").is_none());
+}
+
+#[test]
+fn test_certificate_exception_allows_connection() {
+    let (server, url) = make_self_signed_server();
+    let mut context = new_fetch_context(None, None);
+    let error = certificate_error(&fetch_url(url.clone(), &mut context));
+
+    context
+        .state
+        .certificate_verifier
+        .add_exception(error.host, error.certificate);
+    let response = fetch_url(url, &mut context);
+    let _ = server.close();
+
+    assert!(!response.is_network_error());
+    assert_eq!(response_body(&response), b"Yay!");
+}
+
+#[test]
+fn test_add_client_certificate() {
+    let certificate = fs::read_to_string(certificate_path()).unwrap();
+    let private_key = fs::read_to_string(private_key_path()).unwrap();
+
+    let mut ssl_connector_builder = create_ssl_connector_builder(&certificate);
+    let identity = format!("{}\n{}", certificate, private_key);
+    assert!(add_client_certificate(&mut ssl_connector_builder, &identity).is_ok());
+
+    let mut ssl_connector_builder = create_ssl_connector_builder(&certificate);
+    assert!(add_client_certificate(&mut ssl_connector_builder, &certificate).is_err());
+}
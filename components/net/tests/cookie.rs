@@ -4,7 +4,7 @@
 
 use net::cookie::Cookie;
 use net::cookie_storage::CookieStorage;
-use net_traits::CookieSource;
+use net_traits::{CookieContext, CookieSource};
 use servo_url::ServoUrl;
 
 #[test]
@@ -475,3 +475,172 @@ fn test_cookie_eviction_all_nonsecure_new_nonsecure() {
         "extra2=bar; extra3=bar; extra4=bar; extra5=bar; foo=bar"
     );
 }
+
+fn cookie_context(url: &str, site_for_cookies: &str, top_level_navigation: bool) -> CookieContext {
+    CookieContext::new(
+        &ServoUrl::parse(url).unwrap(),
+        Some(&ServoUrl::parse(site_for_cookies).unwrap()),
+        top_level_navigation,
+        true,
+        true,
+    )
+}
+
+#[test]
+fn test_cookie_context_same_site() {
+    let context = cookie_context("https://a.example.com/", "https://b.example.com/", false);
+    assert!(context.same_site);
+    assert!(context.partition_key.is_none());
+
+    let context = cookie_context("https://example.com/", "https://other.org/", false);
+    assert!(!context.same_site);
+    assert_eq!(context.partition_key, Some("other.org".to_owned()));
+
+    // Top-level navigations are never partitioned.
+    let context = cookie_context("https://example.com/", "https://other.org/", true);
+    assert!(context.safe_top_level_navigation);
+    assert!(context.partition_key.is_none());
+}
+
+#[test]
+fn test_same_site_cookies_not_sent_cross_site() {
+    let url = ServoUrl::parse("https://example.com/").unwrap();
+    let mut storage = CookieStorage::new(5);
+    add_cookie_to_storage(&mut storage, &url, "strict=1; SameSite=Strict");
+    add_cookie_to_storage(&mut storage, &url, "lax=1; SameSite=Lax");
+    add_cookie_to_storage(&mut storage, &url, "none=1");
+
+    let source = CookieSource::HTTP;
+    let same_site = cookie_context("https://example.com/", "https://www.example.com/", false);
+    assert_eq!(
+        storage
+            .cookies_for_url_in_context(&url, source, &same_site)
+            .unwrap(),
+        "strict=1; lax=1; none=1"
+    );
+
+    let navigation = CookieContext {
+        partition_key: None,
+        ..cookie_context("https://example.com/", "https://other.org/", true)
+    };
+    assert_eq!(
+        storage
+            .cookies_for_url_in_context(&url, source, &navigation)
+            .unwrap(),
+        "lax=1; none=1"
+    );
+
+    let subresource = CookieContext {
+        partition_key: None,
+        ..cookie_context("https://example.com/", "https://other.org/", false)
+    };
+    assert_eq!(
+        storage
+            .cookies_for_url_in_context(&url, source, &subresource)
+            .unwrap(),
+        "none=1"
+    );
+}
+
+#[test]
+fn test_same_site_cookies_not_set_cross_site() {
+    let url = ServoUrl::parse("https://example.com/").unwrap();
+    let source = CookieSource::HTTP;
+    let context = CookieContext {
+        partition_key: None,
+        ..cookie_context("https://example.com/", "https://other.org/", false)
+    };
+    let mut storage = CookieStorage::new(5);
+    for cookie_str in &["strict=1; SameSite=Strict", "none=1"] {
+        let cookie = Cookie::from_cookie_string((*cookie_str).to_owned(), &url, source).unwrap();
+        storage.push_in_context(cookie, &url, source, &context);
+    }
+    assert_eq!(storage.cookies_for_url(&url, source).unwrap(), "none=1");
+}
+
+#[test]
+fn test_partitioned_cookies() {
+    let url = ServoUrl::parse("https://tracker.example/").unwrap();
+    let source = CookieSource::HTTP;
+    let embedded_in_a = cookie_context("https://tracker.example/", "https://a.example/", false);
+    let embedded_in_b = cookie_context("https://tracker.example/", "https://b.example/", false);
+
+    let mut storage = CookieStorage::new(5);
+    let cookie = Cookie::from_cookie_string("id=a".to_owned(), &url, source).unwrap();
+    storage.push_in_context(cookie, &url, source, &embedded_in_a);
+    let cookie = Cookie::from_cookie_string("id=b".to_owned(), &url, source).unwrap();
+    storage.push_in_context(cookie, &url, source, &embedded_in_b);
+
+    assert_eq!(
+        storage
+            .cookies_for_url_in_context(&url, source, &embedded_in_a)
+            .unwrap(),
+        "id=a"
+    );
+    assert_eq!(
+        storage
+            .cookies_for_url_in_context(&url, source, &embedded_in_b)
+            .unwrap(),
+        "id=b"
+    );
+    assert!(storage.cookies_for_url(&url, source).is_none());
+}
+
+#[test]
+fn test_global_cookie_limit_evicts_least_recently_used() {
+    let mut storage = CookieStorage::new(5);
+    storage.set_global_limits(3, 0);
+    let a = ServoUrl::parse("https://a.example/").unwrap();
+    let b = ServoUrl::parse("https://b.example/").unwrap();
+    let c = ServoUrl::parse("https://c.example/").unwrap();
+    let source = CookieSource::HTTP;
+
+    add_cookie_to_storage(&mut storage, &a, "a=1");
+    add_cookie_to_storage(&mut storage, &b, "b=1");
+    add_cookie_to_storage(&mut storage, &c, "c=1");
+    // Reading the cookies of a.example makes them the most recently used.
+    assert_eq!(storage.cookies_for_url(&a, source).unwrap(), "a=1");
+    add_cookie_to_storage(&mut storage, &c, "d=1");
+
+    assert_eq!(storage.cookies_for_url(&a, source).unwrap(), "a=1");
+    assert!(storage.cookies_for_url(&b, source).is_none());
+    assert_eq!(storage.cookies_for_url(&c, source).unwrap(), "c=1; d=1");
+}
+
+#[test]
+fn test_global_byte_limit() {
+    let mut storage = CookieStorage::new(5);
+    storage.set_global_limits(0, 10);
+    let a = ServoUrl::parse("https://a.example/").unwrap();
+    let b = ServoUrl::parse("https://b.example/").unwrap();
+    let source = CookieSource::HTTP;
+
+    add_cookie_to_storage(&mut storage, &a, "first=12");
+    add_cookie_to_storage(&mut storage, &b, "second=1");
+
+    assert!(storage.cookies_for_url(&a, source).is_none());
+    assert_eq!(storage.cookies_for_url(&b, source).unwrap(), "second=1");
+}
+
+#[test]
+fn test_same_site_cookies_set_by_cross_site_navigations() {
+    let url = ServoUrl::parse("https://example.com/").unwrap();
+    let source = CookieSource::HTTP;
+    let mut storage = CookieStorage::new(5);
+    for &(cookie_str, top_level_navigation) in &[
+        ("strict=1; SameSite=Strict", true),
+        ("lax=1; SameSite=Lax", false),
+    ] {
+        // Unlike when cookies are sent, the method of the navigation doesn't matter.
+        let context = CookieContext::new(
+            &url,
+            Some(&ServoUrl::parse("https://other.org/").unwrap()),
+            top_level_navigation,
+            false,
+            false,
+        );
+        let cookie = Cookie::from_cookie_string(cookie_str.to_owned(), &url, source).unwrap();
+        storage.push_in_context(cookie, &url, source, &context);
+    }
+    assert_eq!(storage.cookies_for_url(&url, source).unwrap(), "strict=1");
+}
@@ -30,6 +30,7 @@ use net::fetch::methods::{self, CancellationListener, FetchContext};
 use net::filemanager_thread::FileManager;
 use net::hsts::HstsEntry;
 use net::test::HttpState;
+use net_traits::content_security_policy::{CspList, PolicyDisposition, PolicySource, Violation};
 use net_traits::request::{Destination, Origin, RedirectMode, Referrer, Request, RequestMode};
 use net_traits::response::{CacheState, Response, ResponseBody, ResponseType};
 use net_traits::{
@@ -82,6 +83,29 @@ fn test_fetch_on_bad_port_is_network_error() {
     )
 }
 
+#[test]
+fn test_fetch_blocked_by_content_security_policy_is_network_error() {
+    let url = ServoUrl::parse("http://www.example.com/image.png").unwrap();
+    let origin = ServoUrl::parse("http://www.example.org").unwrap().origin();
+    let mut request = Request::new(url, Some(Origin::Origin(origin)), None);
+    request.referrer = Referrer::NoReferrer;
+    request.destination = Destination::Image;
+    let mut csp_list = CspList::default();
+    csp_list.append_serialized(
+        "img-src 'self'",
+        PolicySource::Header,
+        PolicyDisposition::Enforce,
+    );
+    request.csp_list = Some(csp_list);
+    let fetch_response = fetch(&mut request, None);
+    assert!(fetch_response.is_network_error());
+    let fetch_error = fetch_response.get_network_error().unwrap();
+    assert_eq!(
+        fetch_error,
+        &NetworkError::Internal("Blocked by Content-Security-Policy".into())
+    )
+}
+
 #[test]
 fn test_fetch_response_body_matches_const_message() {
     static MESSAGE: &'static [u8] = b"Hello World!";
@@ -107,6 +131,23 @@ fn test_fetch_response_body_matches_const_message() {
     };
 }
 
+#[test]
+fn test_fetch_response_reports_next_hop_protocol() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = b"Yay!".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    let fetch_response = fetch(&mut request, None);
+    let _ = server.close();
+
+    assert!(!fetch_response.is_network_error());
+    assert_eq!(fetch_response.next_hop_protocol(), Some("http/1.1"));
+}
+
 #[test]
 fn test_fetch_aboutblank() {
     let url = ServoUrl::parse("about:blank").unwrap();
@@ -143,6 +184,7 @@ fn test_fetch_blob() {
             assert_eq!(self.buffer, self.expected);
             let _ = self.sender.send(response.clone());
         }
+        fn process_csp_violations(&mut self, _: &Request, _: Vec<Violation>) {}
     }
 
     let context = new_fetch_context(None, None);
@@ -0,0 +1,129 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::{fetch_url, make_server, new_fetch_context};
+use headers::{CacheControl, HeaderMapExt};
+use http::header::{self, HeaderValue};
+use http::StatusCode;
+use hyper::{Body, Request as HyperRequest, Response as HyperResponse};
+use net::fetch::methods::FetchContext;
+use net::har::HarRecorder;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn recording_fetch_context() -> (FetchContext, Arc<HarRecorder>) {
+    let recorder = Arc::new(HarRecorder::new());
+    let mut context = new_fetch_context(None, None);
+    Arc::get_mut(&mut context.state).unwrap().har_recorder = Some(recorder.clone());
+    (context, recorder)
+}
+
+#[test]
+fn test_har_records_network_request() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        response.headers_mut().insert(
+            header::SET_COOKIE,
+            HeaderValue::from_static("session=1; Path=/; HttpOnly"),
+        );
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        *response.body_mut() = b"Yay!".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let (mut context, recorder) = recording_fetch_context();
+    assert!(!fetch_url(url.join("resource?a=1&b=2").unwrap(), &mut context).is_network_error());
+    let _ = server.close();
+
+    let entries = recorder.entries();
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry.request.method, "GET");
+    assert!(entry.request.url.ends_with("/resource?a=1&b=2"));
+    assert_eq!(entry.request.query_string.len(), 2);
+    assert_eq!(entry.request.query_string[1].name, "b");
+    assert_eq!(entry.response.status, 200);
+    assert_eq!(entry.response.status_text, "OK");
+    assert_eq!(entry.response.content.size, 4);
+    assert_eq!(entry.response.content.mime_type, "text/plain");
+    assert_eq!(entry.response.cookies[0].name, "session");
+    assert_eq!(entry.response.cookies[0].http_only, Some(true));
+    assert!(!entry.from_cache);
+    assert!(entry.timings.wait >= 0 && entry.timings.receive >= 0);
+    assert_eq!(entry.time, entry.timings.wait + entry.timings.receive);
+}
+
+#[test]
+fn test_har_records_redirects() {
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        if request.uri().path() == "/old" {
+            *response.status_mut() = StatusCode::MOVED_PERMANENTLY;
+            response
+                .headers_mut()
+                .insert(header::LOCATION, HeaderValue::from_static("/new"));
+        } else {
+            *response.body_mut() = b"moved".to_vec().into();
+        }
+    };
+    let (server, url) = make_server(handler);
+
+    let (mut context, recorder) = recording_fetch_context();
+    assert!(!fetch_url(url.join("old").unwrap(), &mut context).is_network_error());
+    let _ = server.close();
+
+    let entries = recorder.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].response.status, 301);
+    assert_eq!(entries[0].response.redirect_url, "/new");
+    assert!(entries[1].request.url.ends_with("/new"));
+    assert_eq!(entries[1].response.redirect_url, "");
+}
+
+#[test]
+fn test_har_records_cache_hits() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        response
+            .headers_mut()
+            .typed_insert(CacheControl::new().with_max_age(Duration::from_secs(3600)));
+        *response.body_mut() = b"cached".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let (mut context, recorder) = recording_fetch_context();
+    assert!(!fetch_url(url.clone(), &mut context).is_network_error());
+    assert!(!fetch_url(url, &mut context).is_network_error());
+    let _ = server.close();
+
+    let entries = recorder.entries();
+    assert_eq!(entries.len(), 2);
+    assert!(!entries[0].from_cache);
+    assert!(entries[1].from_cache);
+    assert_eq!(entries[1].response.status, 200);
+    assert_eq!(entries[1].response.content.size, 6);
+}
+
+#[test]
+fn test_har_serialization() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = b"Yay!".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let (mut context, recorder) = recording_fetch_context();
+    assert!(!fetch_url(url, &mut context).is_network_error());
+    let _ = server.close();
+
+    let har: serde_json::Value = serde_json::from_str(&recorder.to_json()).unwrap();
+    let log = &har["log"];
+    assert_eq!(log["version"], "1.2");
+    assert_eq!(log["creator"]["name"], "Servo");
+    let entry = &log["entries"][0];
+    assert!(entry["startedDateTime"].is_string());
+    assert_eq!(entry["request"]["httpVersion"], "HTTP/1.1");
+    assert_eq!(entry["response"]["redirectURL"], "");
+    assert_eq!(entry["response"]["content"]["size"], 4);
+    assert_eq!(entry["timings"]["dns"], -1);
+    assert_eq!(entry["_fromCache"], false);
+}
@@ -3,14 +3,17 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crossbeam_channel::unbounded;
-use http::header::{HeaderValue, EXPIRES};
+use http::header::{HeaderValue, CACHE_CONTROL, EXPIRES};
 use http::StatusCode;
 use msg::constellation_msg::TEST_PIPELINE_ID;
-use net::http_cache::HttpCache;
+use net::http_cache::{CachedResponse, HttpCache};
 use net_traits::request::{Origin, Request};
 use net_traits::response::{Response, ResponseBody};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use servo_url::ServoUrl;
+use std::env;
+use std::fs;
+use uuid::Uuid;
 
 #[test]
 fn test_refreshing_resource_sets_done_chan_the_appropriate_value() {
@@ -48,3 +51,195 @@ fn test_refreshing_resource_sets_done_chan_the_appropriate_value() {
         }
     })
 }
+
+#[test]
+fn test_disk_cache_persists_responses_across_instances() {
+    let directory = env::temp_dir().join(format!("servo-http-cache-{}", Uuid::new_v4()));
+    let url = ServoUrl::parse("https://servo.org").unwrap();
+    let request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.clone().origin())),
+        Some(TEST_PIPELINE_ID),
+    );
+    let timing = ResourceFetchTiming::new(ResourceTimingType::Navigation);
+    let mut response = Response::new(url.clone(), timing);
+    response
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static("max-age=3600"));
+    *response.body.lock().unwrap() = ResponseBody::Done(b"cached".to_vec());
+    {
+        let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+        cache.store(&request, &response);
+        cache.flush();
+    }
+    // A new cache, as created in a later session, serves the stored response.
+    let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+    let mut done_chan = None;
+    let cached_response = cache
+        .construct_response(&request, &mut done_chan)
+        .expect("the response should have been persisted");
+    assert!(!cached_response.needs_validation);
+    assert!(done_chan.is_none());
+    assert_eq!(
+        *cached_response.response.body.lock().unwrap(),
+        ResponseBody::Done(b"cached".to_vec())
+    );
+    let _ = fs::remove_dir_all(directory);
+}
+
+#[test]
+fn test_disk_cache_hits_are_kept_in_memory() {
+    let directory = env::temp_dir().join(format!("servo-http-cache-{}", Uuid::new_v4()));
+    let url = ServoUrl::parse("https://servo.org").unwrap();
+    let request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.clone().origin())),
+        Some(TEST_PIPELINE_ID),
+    );
+    let timing = ResourceFetchTiming::new(ResourceTimingType::Navigation);
+    let mut response = Response::new(url.clone(), timing);
+    response
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static("max-age=3600"));
+    *response.body.lock().unwrap() = ResponseBody::Done(b"cached".to_vec());
+    {
+        let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+        cache.store(&request, &response);
+        cache.flush();
+    }
+    let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+    let mut done_chan = None;
+    assert!(cache.construct_response(&request, &mut done_chan).is_some());
+    // Later lookups don't need the body on disk anymore.
+    fs::remove_dir_all(&directory).unwrap();
+    let cached_response = cache
+        .construct_response(&request, &mut done_chan)
+        .expect("the response should have been kept in memory");
+    assert_eq!(
+        *cached_response.response.body.lock().unwrap(),
+        ResponseBody::Done(b"cached".to_vec())
+    );
+}
+
+#[test]
+fn test_disk_cache_replaces_changed_bodies_of_the_same_size() {
+    let directory = env::temp_dir().join(format!("servo-http-cache-{}", Uuid::new_v4()));
+    let url = ServoUrl::parse("https://servo.org").unwrap();
+    let request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.clone().origin())),
+        Some(TEST_PIPELINE_ID),
+    );
+    {
+        let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+        // Both responses are validated within the same second.
+        for body in &[b"before", b"after!"] {
+            let timing = ResourceFetchTiming::new(ResourceTimingType::Navigation);
+            let mut response = Response::new(url.clone(), timing);
+            response
+                .headers
+                .insert(CACHE_CONTROL, HeaderValue::from_static("max-age=3600"));
+            *response.body.lock().unwrap() = ResponseBody::Done(body.to_vec());
+            cache.store(&request, &response);
+        }
+        cache.flush();
+    }
+    let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 1024);
+    let mut done_chan = None;
+    let cached_response = cache
+        .construct_response(&request, &mut done_chan)
+        .expect("the response should have been persisted");
+    assert_eq!(
+        *cached_response.response.body.lock().unwrap(),
+        ResponseBody::Done(b"after!".to_vec())
+    );
+    let _ = fs::remove_dir_all(directory);
+}
+
+#[test]
+fn test_disk_cache_evicts_least_recently_used_responses() {
+    let directory = env::temp_dir().join(format!("servo-http-cache-{}", Uuid::new_v4()));
+    let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 10);
+    let urls = vec![
+        ServoUrl::parse("https://servo.org/first").unwrap(),
+        ServoUrl::parse("https://servo.org/second").unwrap(),
+    ];
+    let requests: Vec<Request> = urls
+        .iter()
+        .map(|url| {
+            Request::new(
+                url.clone(),
+                Some(Origin::Origin(url.clone().origin())),
+                Some(TEST_PIPELINE_ID),
+            )
+        })
+        .collect();
+    for (url, request) in urls.iter().zip(requests.iter()) {
+        let timing = ResourceFetchTiming::new(ResourceTimingType::Navigation);
+        let mut response = Response::new(url.clone(), timing);
+        response
+            .headers
+            .insert(CACHE_CONTROL, HeaderValue::from_static("max-age=3600"));
+        *response.body.lock().unwrap() = ResponseBody::Done(b"sixbyt".to_vec());
+        cache.store(request, &response);
+    }
+    cache.flush();
+    // Only the most recently stored response fits under the size cap.
+    let mut cache = HttpCache::new_with_disk_cache(directory.clone(), 10);
+    let mut done_chan = None;
+    assert!(cache
+        .construct_response(&requests[0], &mut done_chan)
+        .is_none());
+    assert!(cache
+        .construct_response(&requests[1], &mut done_chan)
+        .is_some());
+    let _ = fs::remove_dir_all(directory);
+}
+
+fn construct_stale_response(cache_control: &'static str) -> CachedResponse {
+    let url = ServoUrl::parse("https://servo.org").unwrap();
+    let request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.clone().origin())),
+        Some(TEST_PIPELINE_ID),
+    );
+    let timing = ResourceFetchTiming::new(ResourceTimingType::Navigation);
+    let mut response = Response::new(url.clone(), timing);
+    // Stored responses are stale right away with `max-age=0`.
+    response
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    *response.body.lock().unwrap() = ResponseBody::Done(b"stale".to_vec());
+    let mut cache = HttpCache::new();
+    cache.store(&request, &response);
+    let mut done_chan = None;
+    cache
+        .construct_response(&request, &mut done_chan)
+        .expect("the response should have been stored")
+}
+
+#[test]
+fn test_stale_while_revalidate_serves_stale_response() {
+    let cached_response = construct_stale_response("max-age=0");
+    assert!(cached_response.needs_validation);
+    assert!(!cached_response.revalidate_in_background);
+
+    let cached_response = construct_stale_response("max-age=0, stale-while-revalidate=60");
+    assert!(!cached_response.needs_validation);
+    assert!(cached_response.revalidate_in_background);
+}
+
+#[test]
+fn test_stale_if_error_allows_stale_response_on_error() {
+    let cached_response = construct_stale_response("max-age=0");
+    assert!(!cached_response.usable_on_error);
+
+    let cached_response = construct_stale_response("max-age=0, stale-if-error=60");
+    assert!(cached_response.needs_validation);
+    assert!(cached_response.usable_on_error);
+
+    // A fresh response doesn't need a window to be used.
+    let cached_response = construct_stale_response("max-age=3600, stale-if-error=60");
+    assert!(!cached_response.needs_validation);
+    assert!(!cached_response.usable_on_error);
+}
@@ -27,6 +27,7 @@ use hyper::{Request as HyperRequest, Response as HyperResponse};
 use msg::constellation_msg::TEST_PIPELINE_ID;
 use net::cookie::Cookie;
 use net::cookie_storage::CookieStorage;
+use net::fetch::methods::FetchContext;
 use net::resource_thread::AuthCacheEntry;
 use net::test::replace_host_table;
 use net_traits::request::{CredentialsMode, Destination, RequestBuilder, RequestMode};
@@ -36,7 +37,7 @@ use servo_url::{ImmutableOrigin, ServoUrl};
 use std::collections::HashMap;
 use std::io::Write;
 use std::str;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
@@ -678,6 +679,56 @@ fn test_load_sets_requests_cookies_header_for_url_by_getting_cookies_from_the_re
         .is_success());
 }
 
+#[test]
+fn test_load_does_not_send_same_site_cookies_cross_site() {
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        assert_eq!(
+            request.headers().get(header::COOKIE).unwrap().as_bytes(),
+            b"unrestricted=1"
+        );
+        *response.body_mut() = b"Yay!".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let mut context = new_fetch_context(None, None);
+
+    {
+        let mut cookie_jar = context.state.cookie_jar.write().unwrap();
+        for cookie_str in &[
+            "strict=1; SameSite=Strict",
+            "lax=1; SameSite=Lax",
+            "unrestricted=1",
+        ] {
+            let cookie =
+                Cookie::from_cookie_string((*cookie_str).to_owned(), &url, CookieSource::HTTP)
+                    .unwrap();
+            cookie_jar.push(cookie, &url, CookieSource::HTTP);
+        }
+    }
+
+    let mut request = RequestBuilder::new(url.clone())
+        .method(Method::GET)
+        .body(None)
+        .destination(Destination::Image)
+        .origin(mock_origin())
+        .pipeline_id(Some(TEST_PIPELINE_ID))
+        .credentials_mode(CredentialsMode::Include)
+        .site_for_cookies(Some(ServoUrl::parse("http://other.example/").unwrap()))
+        .build();
+
+    let response = fetch_with_context(&mut request, &mut context);
+
+    let _ = server.close();
+
+    assert!(response
+        .internal_response
+        .unwrap()
+        .status
+        .unwrap()
+        .0
+        .is_success());
+}
+
 #[test]
 fn test_load_sends_cookie_if_nonhttp() {
     let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
@@ -1421,3 +1472,85 @@ fn test_origin_set() {
 
     let _ = server.close();
 }
+
+fn fetch_body(url: &ServoUrl, context: &mut FetchContext) -> Vec<u8> {
+    let mut request = RequestBuilder::new(url.clone())
+        .method(Method::GET)
+        .body(None)
+        .destination(Destination::Document)
+        .origin(url.origin())
+        .pipeline_id(Some(TEST_PIPELINE_ID))
+        .build();
+    let response = fetch_with_context(&mut request, context);
+    assert!(!response.is_network_error());
+    let response = response.actual_response();
+    assert!(response.status.as_ref().unwrap().0.is_success());
+    match *response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => body.clone(),
+        _ => panic!("response body not complete"),
+    }
+}
+
+#[test]
+fn test_stale_while_revalidate_serves_stale_response_and_revalidates_it() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let handler_counter = counter.clone();
+    let (revalidation_sender, revalidation_receiver) = unbounded();
+    let (release_sender, release_receiver) = unbounded();
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        let count = handler_counter.fetch_add(1, Ordering::SeqCst) + 1;
+        if count > 1 {
+            // Hold the revalidation until the test checked that it is in flight.
+            let _ = revalidation_sender.send(request.headers().get(header::CACHE_CONTROL).cloned());
+            let _ = release_receiver.recv();
+        }
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("max-age=0, stale-while-revalidate=60"),
+        );
+        *response.body_mut() = format!("v{}", count).into_bytes().into();
+    };
+    let (server, url) = make_server(handler);
+    let mut context = new_fetch_context(None, None);
+
+    assert_eq!(fetch_body(&url, &mut context), b"v1");
+    // The stale response is served right away, while it is revalidated in the background.
+    assert_eq!(fetch_body(&url, &mut context), b"v1");
+    assert_eq!(
+        revalidation_receiver.recv().unwrap(),
+        Some(HeaderValue::from_static("max-age=0"))
+    );
+    // A resource is only revalidated once at a time.
+    assert_eq!(fetch_body(&url, &mut context), b"v1");
+    release_sender.send(()).unwrap();
+    let _ = server.close();
+
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_stale_if_error_serves_stale_response_on_server_error() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let handler_counter = counter.clone();
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        if handler_counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            response.headers_mut().insert(
+                header::CACHE_CONTROL,
+                HeaderValue::from_static("max-age=0, stale-if-error=60"),
+            );
+            *response.body_mut() = b"stale".to_vec().into();
+        } else {
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            *response.body_mut() = b"unavailable".to_vec().into();
+        }
+    };
+    let (server, url) = make_server(handler);
+    let mut context = new_fetch_context(None, None);
+
+    assert_eq!(fetch_body(&url, &mut context), b"stale");
+    assert_eq!(fetch_body(&url, &mut context), b"stale");
+    let _ = server.close();
+
+    // The stale response was revalidated, and the error ignored.
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,444 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net::indexeddb_thread::IndexedDBThreadFactory;
+use net_traits::indexeddb_thread::{CursorDirection, IndexMetadata, IndexedDBError};
+use net_traits::indexeddb_thread::{IndexedDBConnectionMsg, KeyPath};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType, IndexedDBOperation};
+use net_traits::indexeddb_thread::{IndexedDBOperationResult, IndexedDBSource};
+use net_traits::indexeddb_thread::{IndexedDBThreadMsg, IndexedDBTxnMode, IndexedDBTxnReply};
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use uuid::Uuid;
+
+fn origin() -> ImmutableOrigin {
+    ServoUrl::parse("https://example.com").unwrap().origin()
+}
+
+/// Open a connection to the database, returning its id and the receiver of its messages.
+fn open(thread: &IpcSender<IndexedDBThreadMsg>) -> (Uuid, IpcReceiver<IndexedDBConnectionMsg>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (connection_sender, connection_receiver) = ipc::channel().unwrap();
+    let id = Uuid::new_v4();
+    thread
+        .send(IndexedDBThreadMsg::Open(
+            sender,
+            origin(),
+            "db".to_owned(),
+            id,
+            connection_sender,
+        ))
+        .unwrap();
+    receiver.recv().unwrap();
+    (id, connection_receiver)
+}
+
+struct Transaction {
+    id: Uuid,
+    replies: IpcReceiver<IndexedDBTxnReply>,
+}
+
+/// Begin a transaction over the object store of the database, or over the whole database
+/// for versionchange transactions.
+fn begin(thread: &IpcSender<IndexedDBThreadMsg>, mode: IndexedDBTxnMode) -> Transaction {
+    let (id, _) = open(thread);
+    thread
+        .send(IndexedDBThreadMsg::CloseConnection(id))
+        .unwrap();
+    let scope = match mode {
+        IndexedDBTxnMode::Versionchange => None,
+        _ => Some(vec!["store".to_owned()]),
+    };
+    let (sender, receiver) = ipc::channel().unwrap();
+    let id = Uuid::new_v4();
+    thread
+        .send(IndexedDBThreadMsg::BeginTransaction {
+            id: id,
+            origin: origin(),
+            name: "db".to_owned(),
+            mode: mode,
+            scope: scope,
+            replies: sender,
+        })
+        .unwrap();
+    Transaction {
+        id: id,
+        replies: receiver,
+    }
+}
+
+fn operation_result(transaction: &Transaction) -> Result<IndexedDBOperationResult, IndexedDBError> {
+    match transaction.replies.recv().unwrap() {
+        IndexedDBTxnReply::Operation(result) => result,
+        reply => panic!("unexpected reply {:?}", reply),
+    }
+}
+
+fn run(
+    thread: &IpcSender<IndexedDBThreadMsg>,
+    transaction: &Transaction,
+    operation: IndexedDBOperation,
+) -> Result<IndexedDBOperationResult, IndexedDBError> {
+    thread
+        .send(IndexedDBThreadMsg::Operation(transaction.id, operation))
+        .unwrap();
+    operation_result(transaction)
+}
+
+fn finish(thread: &IpcSender<IndexedDBThreadMsg>, transaction: &Transaction, commit: bool) {
+    let msg = if commit {
+        IndexedDBThreadMsg::Commit(transaction.id)
+    } else {
+        IndexedDBThreadMsg::Abort(transaction.id)
+    };
+    thread.send(msg).unwrap();
+    match transaction.replies.recv().unwrap() {
+        IndexedDBTxnReply::Committed if commit => {},
+        IndexedDBTxnReply::Aborted if !commit => {},
+        reply => panic!("unexpected reply {:?}", reply),
+    }
+}
+
+fn get_all(
+    thread: &IpcSender<IndexedDBThreadMsg>,
+    transaction: &Transaction,
+) -> Vec<(f64, Vec<u8>)> {
+    let source = IndexedDBSource::ObjectStore("store".to_owned());
+    let operation = IndexedDBOperation::GetAll(source, IndexedDBKeyRange::unbounded(), None);
+    match run(thread, transaction, operation) {
+        Ok(IndexedDBOperationResult::Records(records)) => records
+            .into_iter()
+            .map(|record| match record.key {
+                IndexedDBKeyType::Number(key) => (key, record.value),
+                key => panic!("unexpected key {:?}", key),
+            })
+            .collect(),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+fn put(key: f64, value: &[u8], index_keys: Vec<IndexedDBKeyType>) -> IndexedDBOperation {
+    IndexedDBOperation::Put {
+        store: "store".to_owned(),
+        key: Some(IndexedDBKeyType::Number(key)),
+        value: value.to_vec(),
+        index_keys: vec![("index".to_owned(), index_keys)],
+        no_overwrite: false,
+    }
+}
+
+/// Start an IndexedDB thread with a database containing an empty object store, with an
+/// index that is unique if `unique` is set.
+fn new_database(unique: bool) -> IpcSender<IndexedDBThreadMsg> {
+    let thread: IpcSender<IndexedDBThreadMsg> = IndexedDBThreadFactory::new(None);
+    create_object_store(&thread, unique);
+    thread
+}
+
+/// Upgrade the database to version 1, creating its object store and the index of it.
+fn create_object_store(thread: &IpcSender<IndexedDBThreadMsg>, unique: bool) {
+    let transaction = begin(thread, IndexedDBTxnMode::Versionchange);
+    run(thread, &transaction, IndexedDBOperation::SetVersion(1)).unwrap();
+    let operation = IndexedDBOperation::CreateObjectStore("store".to_owned(), None, false);
+    run(thread, &transaction, operation).unwrap();
+    let index = IndexMetadata {
+        name: "index".to_owned(),
+        key_path: KeyPath::String("name".to_owned()),
+        unique: unique,
+        multi_entry: false,
+    };
+    let operation = IndexedDBOperation::CreateIndex("store".to_owned(), index, vec![]);
+    run(thread, &transaction, operation).unwrap();
+    finish(thread, &transaction, true);
+}
+
+fn string(value: &str) -> IndexedDBKeyType {
+    IndexedDBKeyType::String(value.to_owned())
+}
+
+#[test]
+fn test_key_ordering() {
+    let keys = vec![
+        IndexedDBKeyType::Number(-1.0),
+        IndexedDBKeyType::Number(2.0),
+        IndexedDBKeyType::Date(0.0),
+        string("B"),
+        string("a"),
+        string("\u{10000}"),
+        string("\u{ffff}"),
+        IndexedDBKeyType::Binary(vec![1]),
+        IndexedDBKeyType::Array(vec![]),
+        IndexedDBKeyType::Array(vec![IndexedDBKeyType::Number(0.0)]),
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        for other in &keys[i + 1..] {
+            assert!(key < other, "{:?} < {:?}", key, other);
+        }
+    }
+
+    let range = IndexedDBKeyRange {
+        lower: Some(IndexedDBKeyType::Number(1.0)),
+        upper: Some(IndexedDBKeyType::Number(2.0)),
+        lower_open: true,
+        upper_open: false,
+    };
+    assert!(!range.contains(&IndexedDBKeyType::Number(1.0)));
+    assert!(range.contains(&IndexedDBKeyType::Number(2.0)));
+    assert!(!range.contains(&string("1")));
+}
+
+#[test]
+fn test_abort_restores_database() {
+    let thread = new_database(false);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"one", vec![])).unwrap();
+    finish(&thread, &transaction, true);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"changed", vec![])).unwrap();
+    run(&thread, &transaction, put(2.0, b"two", vec![])).unwrap();
+    finish(&thread, &transaction, false);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readonly);
+    assert_eq!(get_all(&thread, &transaction), vec![(1.0, b"one".to_vec())]);
+    finish(&thread, &transaction, true);
+}
+
+#[test]
+fn test_abort_restores_deleted_and_cleared_records() {
+    let thread = new_database(false);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"one", vec![string("a")])).unwrap();
+    run(&thread, &transaction, put(2.0, b"two", vec![string("b")])).unwrap();
+    finish(&thread, &transaction, true);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    let range = IndexedDBKeyRange::only(IndexedDBKeyType::Number(1.0));
+    let operation = IndexedDBOperation::Delete("store".to_owned(), range);
+    run(&thread, &transaction, operation).unwrap();
+    run(&thread, &transaction, put(2.0, b"new", vec![string("c")])).unwrap();
+    let operation = IndexedDBOperation::Clear("store".to_owned());
+    run(&thread, &transaction, operation).unwrap();
+    run(&thread, &transaction, put(3.0, b"three", vec![string("a")])).unwrap();
+    finish(&thread, &transaction, false);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readonly);
+    assert_eq!(
+        get_all(&thread, &transaction),
+        vec![(1.0, b"one".to_vec()), (2.0, b"two".to_vec())]
+    );
+    let source = IndexedDBSource::Index("store".to_owned(), "index".to_owned());
+    let operation = IndexedDBOperation::GetAll(source, IndexedDBKeyRange::unbounded(), None);
+    match run(&thread, &transaction, operation) {
+        Ok(IndexedDBOperationResult::Records(records)) => assert_eq!(
+            records
+                .into_iter()
+                .map(|record| record.key)
+                .collect::<Vec<_>>(),
+            vec![string("a"), string("b")]
+        ),
+        result => panic!("unexpected result {:?}", result),
+    }
+    finish(&thread, &transaction, true);
+}
+
+#[test]
+fn test_overlapping_transactions_are_serialized() {
+    let thread = new_database(false);
+
+    let first = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &first, put(1.0, b"one", vec![])).unwrap();
+
+    // The second transaction only starts once the first one is finished, so aborting the
+    // first one keeps the changes of the second one.
+    let second = begin(&thread, IndexedDBTxnMode::Readwrite);
+    thread
+        .send(IndexedDBThreadMsg::Operation(
+            second.id,
+            put(2.0, b"two", vec![]),
+        ))
+        .unwrap();
+    finish(&thread, &first, false);
+    operation_result(&second).unwrap();
+    finish(&thread, &second, true);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readonly);
+    assert_eq!(get_all(&thread, &transaction), vec![(2.0, b"two".to_vec())]);
+    finish(&thread, &transaction, true);
+}
+
+#[test]
+fn test_unique_index_constraint() {
+    let thread = new_database(true);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"one", vec![string("a")])).unwrap();
+    // Replacing a record keeps its index key available to it.
+    run(&thread, &transaction, put(1.0, b"one", vec![string("a")])).unwrap();
+    assert_eq!(
+        run(&thread, &transaction, put(2.0, b"two", vec![string("a")])).unwrap_err(),
+        IndexedDBError::Constraint
+    );
+
+    let add = IndexedDBOperation::Put {
+        store: "store".to_owned(),
+        key: Some(IndexedDBKeyType::Number(1.0)),
+        value: vec![],
+        index_keys: vec![],
+        no_overwrite: true,
+    };
+    assert_eq!(
+        run(&thread, &transaction, add).unwrap_err(),
+        IndexedDBError::Constraint
+    );
+    finish(&thread, &transaction, true);
+}
+
+#[test]
+fn test_cursor_iteration() {
+    let thread = new_database(false);
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"one", vec![string("a")])).unwrap();
+    run(&thread, &transaction, put(2.0, b"two", vec![string("a")])).unwrap();
+    run(&thread, &transaction, put(3.0, b"three", vec![string("b")])).unwrap();
+
+    let iterate = |direction, position, count| {
+        let operation = IndexedDBOperation::Iterate {
+            source: IndexedDBSource::Index("store".to_owned(), "index".to_owned()),
+            range: IndexedDBKeyRange::unbounded(),
+            direction: direction,
+            position: position,
+            count: count,
+        };
+        match run(&thread, &transaction, operation) {
+            Ok(IndexedDBOperationResult::Record(record)) => {
+                record.map(|record| (record.key, record.primary_key))
+            },
+            result => panic!("unexpected result {:?}", result),
+        }
+    };
+
+    let first = (string("a"), IndexedDBKeyType::Number(1.0));
+    let second = (string("a"), IndexedDBKeyType::Number(2.0));
+    let third = (string("b"), IndexedDBKeyType::Number(3.0));
+    assert_eq!(iterate(CursorDirection::Next, None, 1), Some(first.clone()));
+    assert_eq!(
+        iterate(CursorDirection::Next, Some(first.clone()), 1),
+        Some(second.clone())
+    );
+    assert_eq!(
+        iterate(CursorDirection::Next, Some(first.clone()), 2),
+        Some(third.clone())
+    );
+    assert_eq!(iterate(CursorDirection::Next, Some(third.clone()), 1), None);
+    assert_eq!(
+        iterate(CursorDirection::NextUnique, Some(first.clone()), 1),
+        Some(third.clone())
+    );
+    assert_eq!(iterate(CursorDirection::Prev, None, 1), Some(third.clone()));
+    assert_eq!(
+        iterate(CursorDirection::PrevUnique, Some(third), 1),
+        Some(first)
+    );
+    finish(&thread, &transaction, true);
+}
+
+#[test]
+fn test_delete_database_waits_for_connections() {
+    let thread = new_database(false);
+    let (first, first_receiver) = open(&thread);
+    let (second, second_receiver) = open(&thread);
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (blocked_sender, blocked_receiver) = ipc::channel().unwrap();
+    thread
+        .send(IndexedDBThreadMsg::DeleteDatabase(
+            sender,
+            blocked_sender,
+            origin(),
+            "db".to_owned(),
+        ))
+        .unwrap();
+
+    // The first connection closes when it gets the versionchange event.
+    match first_receiver.recv().unwrap() {
+        IndexedDBConnectionMsg::VersionChange(1, None) => {},
+        message => panic!("unexpected message {:?}", message),
+    }
+    thread
+        .send(IndexedDBThreadMsg::CloseConnection(first))
+        .unwrap();
+    thread
+        .send(IndexedDBThreadMsg::VersionChangeFired(first))
+        .unwrap();
+
+    // The second one stays open, which blocks the deletion until it is closed.
+    match second_receiver.recv().unwrap() {
+        IndexedDBConnectionMsg::VersionChange(1, None) => {},
+        message => panic!("unexpected message {:?}", message),
+    }
+    thread
+        .send(IndexedDBThreadMsg::VersionChangeFired(second))
+        .unwrap();
+    assert_eq!(blocked_receiver.recv().unwrap(), 1);
+    assert!(receiver.try_recv().is_err());
+
+    thread
+        .send(IndexedDBThreadMsg::CloseConnection(second))
+        .unwrap();
+    assert_eq!(receiver.recv().unwrap(), Some(1));
+}
+
+#[test]
+fn test_committed_changes_are_journaled() {
+    let config_dir = env::temp_dir().join(format!("servo-indexeddb-{}", process::id()));
+    let _ = fs::remove_dir_all(&config_dir);
+    let file = |extension: &str| -> PathBuf {
+        config_dir.join(format!("indexeddb/https_3a_2f_2fexample.com.{}", extension))
+    };
+
+    let thread: IpcSender<IndexedDBThreadMsg> =
+        IndexedDBThreadFactory::new(Some(config_dir.clone()));
+    create_object_store(&thread, false);
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(1.0, b"one", vec![])).unwrap();
+    finish(&thread, &transaction, true);
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(2.0, b"two", vec![])).unwrap();
+    finish(&thread, &transaction, false);
+
+    // Committed transactions are appended to the journal, without writing a snapshot.
+    assert!(file("journal").exists());
+    assert!(!file("json").exists());
+
+    // The journal is folded into the snapshot once it gets large.
+    let large = vec![0; 1 << 20];
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    run(&thread, &transaction, put(3.0, &large, vec![])).unwrap();
+    finish(&thread, &transaction, true);
+    assert!(!file("journal").exists());
+    assert!(file("json").exists());
+
+    let transaction = begin(&thread, IndexedDBTxnMode::Readwrite);
+    let range = IndexedDBKeyRange::only(IndexedDBKeyType::Number(3.0));
+    let operation = IndexedDBOperation::Delete("store".to_owned(), range);
+    run(&thread, &transaction, operation).unwrap();
+    finish(&thread, &transaction, true);
+
+    // Another thread reads the snapshot and replays the journal.
+    let thread: IpcSender<IndexedDBThreadMsg> =
+        IndexedDBThreadFactory::new(Some(config_dir.clone()));
+    let transaction = begin(&thread, IndexedDBTxnMode::Readonly);
+    assert_eq!(get_all(&thread, &transaction), vec![(1.0, b"one".to_vec())]);
+    finish(&thread, &transaction, true);
+
+    let _ = fs::remove_dir_all(&config_dir);
+}
@@ -7,16 +7,22 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod certificates;
 mod cookie;
 mod cookie_http_state;
 mod data_loader;
 mod fetch;
 mod file_loader;
 mod filemanager_thread;
+mod har;
 mod hsts;
 mod http_cache;
 mod http_loader;
+mod indexeddb;
 mod mime_classifier;
+mod network_archive;
+mod proxy;
+mod request_interceptor;
 mod resource_thread;
 mod subresource_integrity;
 
@@ -34,8 +40,9 @@ use net::fetch::cors_cache::CorsCache;
 use net::fetch::methods::{self, CancellationListener, FetchContext};
 use net::filemanager_thread::FileManager;
 use net::test::HttpState;
-use net_traits::request::Request;
-use net_traits::response::Response;
+use net_traits::content_security_policy::Violation;
+use net_traits::request::{Origin, Referrer, Request};
+use net_traits::response::{Response, ResponseBody};
 use net_traits::{FetchTaskTarget, ResourceFetchTiming, ResourceTimingType};
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
 use servo_url::ServoUrl;
@@ -108,6 +115,7 @@ impl FetchTaskTarget for FetchResponseCollector {
     fn process_response_eof(&mut self, response: &Response) {
         let _ = self.sender.send(response.clone());
     }
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<Violation>) {}
 }
 
 fn fetch(request: &mut Request, dc: Option<Sender<DevtoolsControlMsg>>) -> Response {
@@ -123,6 +131,21 @@ fn fetch_with_context(request: &mut Request, mut context: &mut FetchContext) ->
     receiver.recv().unwrap()
 }
 
+/// Fetch `url` from its own origin, without a referrer.
+fn fetch_url(url: ServoUrl, context: &mut FetchContext) -> Response {
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    fetch_with_context(&mut request, context)
+}
+
+fn response_body(response: &Response) -> Vec<u8> {
+    match *response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => body.clone(),
+        _ => panic!("response body not complete"),
+    }
+}
+
 fn fetch_with_cors_cache(request: &mut Request, cache: &mut CorsCache) -> Response {
     let (sender, receiver) = unbounded();
     let mut target = FetchResponseCollector { sender: sender };
@@ -0,0 +1,135 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::{fetch_url, fetch_with_context, make_server, new_fetch_context, response_body};
+use http::header::HeaderValue;
+use http::StatusCode;
+use hyper::{Body, Method, Request as HyperRequest, Response as HyperResponse};
+use net::fetch::methods::FetchContext;
+use net::network_archive::NetworkArchive;
+use net_traits::request::{Origin, Referrer, Request};
+use net_traits::response::Response;
+use servo_config::opts::{NetworkArchiveMode, ReplayMissPolicy};
+use servo_url::ServoUrl;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+
+fn archive_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "servo-network-archive-{}-{}.json",
+        name,
+        process::id()
+    ))
+}
+
+fn archive_fetch_context(mode: NetworkArchiveMode) -> (FetchContext, Arc<NetworkArchive>) {
+    let archive = Arc::new(NetworkArchive::new(mode));
+    let mut context = new_fetch_context(None, None);
+    Arc::get_mut(&mut context.state).unwrap().network_archive = Some(archive.clone());
+    (context, archive)
+}
+
+fn post_url(url: ServoUrl, body: &[u8], context: &mut FetchContext) -> Response {
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.referrer = Referrer::NoReferrer;
+    request.method = Method::POST;
+    request.body = Some(body.to_vec());
+    fetch_with_context(&mut request, context)
+}
+
+/// Record the responses of a server echoing request methods and paths into the archive at `path`,
+/// and return the server's URL.
+fn record_archive(path: &Path) -> ServoUrl {
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        response
+            .headers_mut()
+            .insert("x-recorded", HeaderValue::from_static("1"));
+        let body = format!("{} {}", request.method(), request.uri().path());
+        *response.body_mut() = body.into();
+    };
+    let (server, url) = make_server(handler);
+
+    let (mut context, archive) =
+        archive_fetch_context(NetworkArchiveMode::Record(path.to_path_buf()));
+    fetch_url(url.join("get").unwrap(), &mut context);
+    post_url(url.join("post").unwrap(), b"data", &mut context);
+    archive.save().unwrap();
+    let _ = server.close();
+    url
+}
+
+#[test]
+fn test_replay_recorded_responses() {
+    let path = archive_path("replay");
+    let url = record_archive(&path);
+
+    let (mut context, _) = archive_fetch_context(NetworkArchiveMode::Replay(
+        path.clone(),
+        ReplayMissPolicy::Error,
+    ));
+    let response = fetch_url(url.join("get").unwrap(), &mut context);
+    assert_eq!(response.status.as_ref().unwrap().0, StatusCode::OK);
+    assert_eq!(response.headers.get("x-recorded").unwrap(), "1");
+    assert_eq!(response_body(&response), b"GET /get");
+
+    let response = post_url(url.join("post").unwrap(), b"data", &mut context);
+    assert_eq!(response_body(&response), b"POST /post");
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_replay_matches_request_body() {
+    let path = archive_path("body");
+    let url = record_archive(&path);
+
+    let (mut context, _) = archive_fetch_context(NetworkArchiveMode::Replay(
+        path.clone(),
+        ReplayMissPolicy::Error,
+    ));
+    let response = post_url(url.join("post").unwrap(), b"other", &mut context);
+    assert!(response.is_network_error());
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_replay_miss_policies() {
+    let path = archive_path("miss");
+    let url = record_archive(&path);
+    let missing = url.join("missing").unwrap();
+
+    let (mut context, _) = archive_fetch_context(NetworkArchiveMode::Replay(
+        path.clone(),
+        ReplayMissPolicy::Error,
+    ));
+    let response = fetch_url(missing.clone(), &mut context);
+    assert!(response.is_network_error());
+
+    let (mut context, _) = archive_fetch_context(NetworkArchiveMode::Replay(
+        path.clone(),
+        ReplayMissPolicy::NotFound,
+    ));
+    let response = fetch_url(missing, &mut context);
+    assert_eq!(response.status.as_ref().unwrap().0, StatusCode::NOT_FOUND);
+    assert_eq!(response_body(&response), b"");
+
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = b"network".to_vec().into();
+    };
+    let (server, server_url) = make_server(handler);
+    let (mut context, _) = archive_fetch_context(NetworkArchiveMode::Replay(
+        path.clone(),
+        ReplayMissPolicy::Passthrough,
+    ));
+    let response = fetch_url(server_url, &mut context);
+    let _ = server.close();
+    assert_eq!(response_body(&response), b"network");
+
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,176 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::{fetch_with_context, make_server, new_fetch_context, response_body};
+use http::header::{HeaderMap, HeaderValue};
+use http::StatusCode;
+use hyper::{Body, Request as HyperRequest, Response as HyperResponse};
+use ipc_channel::ipc;
+use net_traits::request::{Destination, Origin, Request};
+use net_traits::request_interceptor::{InterceptedRequest, InterceptedRequestAction};
+use net_traits::request_interceptor::{RequestInterceptionFilter, RequestInterceptor};
+use net_traits::response::Response;
+use net_traits::CustomResponse;
+use servo_url::ServoUrl;
+use std::thread;
+
+/// Fetch `url` with an interceptor for `filters`, answering every intercepted request
+/// with the action returned by `decide`.
+fn fetch_intercepted<F>(
+    url: ServoUrl,
+    destination: Destination,
+    filters: Vec<RequestInterceptionFilter>,
+    decide: F,
+) -> Response
+where
+    F: Fn(&InterceptedRequest) -> InterceptedRequestAction + Send + 'static,
+{
+    let (sender, receiver) = ipc::channel::<InterceptedRequest>().unwrap();
+    thread::spawn(move || {
+        while let Ok(intercepted) = receiver.recv() {
+            let action = decide(&intercepted);
+            let _ = intercepted.response_chan.send(action);
+        }
+    });
+
+    let mut context = new_fetch_context(None, None);
+    *context.state.request_interceptor.write().unwrap() =
+        Some(RequestInterceptor::new(filters, sender));
+
+    let origin = Origin::Origin(url.origin());
+    let mut request = Request::new(url, Some(origin), None);
+    request.destination = destination;
+    fetch_with_context(&mut request, &mut context)
+}
+
+#[test]
+fn test_interception_filter_matching() {
+    let url = ServoUrl::parse("https://tracker.example/pixel.gif?id=1").unwrap();
+    let filter = RequestInterceptionFilter::new("https://tracker.example/*", vec![]);
+    assert!(filter.matches(&url, Destination::Image));
+
+    let filter = RequestInterceptionFilter::new("*.gif*", vec![Destination::Image]);
+    assert!(filter.matches(&url, Destination::Image));
+    assert!(!filter.matches(&url, Destination::Script));
+
+    let filter = RequestInterceptionFilter::new("https://tracker.example/", vec![]);
+    assert!(!filter.matches(&url, Destination::Image));
+
+    let filter = RequestInterceptionFilter::new("*pixel*.png", vec![]);
+    assert!(!filter.matches(&url, Destination::Image));
+}
+
+#[test]
+fn test_intercepted_request_is_fulfilled() {
+    let url = ServoUrl::parse("http://backend.test/api").unwrap();
+    let response = fetch_intercepted(
+        url,
+        Destination::None,
+        vec![RequestInterceptionFilter::new(
+            "http://backend.test/*",
+            vec![],
+        )],
+        |_| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-stubbed", HeaderValue::from_static("1"));
+            InterceptedRequestAction::Fulfill(CustomResponse::new(
+                headers,
+                (StatusCode::CREATED, "Created".to_owned()),
+                b"stubbed".to_vec(),
+            ))
+        },
+    );
+
+    let response = response.actual_response();
+    assert_eq!(response.status.as_ref().unwrap().0, StatusCode::CREATED);
+    assert_eq!(response.headers.get("x-stubbed").unwrap(), "1");
+    assert_eq!(response_body(response), b"stubbed");
+}
+
+#[test]
+fn test_intercepted_request_is_failed() {
+    let url = ServoUrl::parse("http://tracker.test/script.js").unwrap();
+    let response = fetch_intercepted(
+        url,
+        Destination::Script,
+        vec![RequestInterceptionFilter::new(
+            "*",
+            vec![Destination::Script],
+        )],
+        |_| InterceptedRequestAction::Fail("Blocked tracker".to_owned()),
+    );
+    assert!(response.is_network_error());
+}
+
+#[test]
+fn test_intercepted_request_is_modified() {
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        let value = request
+            .headers()
+            .get("x-intercepted")
+            .map(|value| value.as_bytes().to_vec())
+            .unwrap_or_default();
+        *response.body_mut() = value.into();
+    };
+    let (server, server_url) = make_server(handler);
+
+    let url = ServoUrl::parse("http://unreachable.test/").unwrap();
+    let response = fetch_intercepted(
+        url,
+        Destination::None,
+        vec![RequestInterceptionFilter::new(
+            "http://unreachable.test/*",
+            vec![],
+        )],
+        move |intercepted| {
+            let mut headers = intercepted.headers.clone();
+            headers.insert("x-intercepted", HeaderValue::from_static("yes"));
+            InterceptedRequestAction::Modify {
+                url: server_url.clone(),
+                headers,
+            }
+        },
+    );
+    let _ = server.close();
+
+    // The rewritten URL is cross-origin, so the response is opaque.
+    assert!(!response.is_network_error());
+    assert_eq!(response_body(response.actual_response()), b"yes");
+}
+
+#[test]
+fn test_request_modified_to_a_bad_port_is_blocked() {
+    let url = ServoUrl::parse("http://unreachable.test/").unwrap();
+    let response = fetch_intercepted(
+        url,
+        Destination::None,
+        vec![RequestInterceptionFilter::new("*", vec![])],
+        |intercepted| InterceptedRequestAction::Modify {
+            url: ServoUrl::parse("http://unreachable.test:25/").unwrap(),
+            headers: intercepted.headers.clone(),
+        },
+    );
+    assert!(response.is_network_error());
+}
+
+#[test]
+fn test_unmatched_request_is_not_intercepted() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = b"network".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let response = fetch_intercepted(
+        url,
+        Destination::None,
+        vec![RequestInterceptionFilter::new(
+            "http://other.test/*",
+            vec![],
+        )],
+        |_| panic!("request should not have been intercepted"),
+    );
+    let _ = server.close();
+
+    assert_eq!(response_body(&response), b"network");
+}
@@ -28,6 +28,7 @@ fn test_exit() {
         create_embedder_proxy(),
         None,
         None,
+        None,
     );
     resource_thread.send(CoreResourceMsg::Exit(sender)).unwrap();
     receiver.recv().unwrap();
@@ -12,6 +12,7 @@ use headers::Host;
 use http::header::{self, HeaderMap, HeaderName, HeaderValue};
 use http::uri::Authority;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
+use net_traits::content_security_policy::CheckResult;
 use net_traits::request::{RequestBuilder, RequestMode};
 use net_traits::{CookieSource, MessageData};
 use net_traits::{WebSocketDomAction, WebSocketNetworkEvent};
@@ -204,6 +205,23 @@ pub fn init(
                 return;
             }
 
+            // WebSocket connections don't go through main fetch, so apply the
+            // request's CSP list here.
+            if let Some(ref csp_list) = req_builder.csp_list {
+                let mut csp_request = req_builder.clone();
+                csp_request.url = req_url.clone();
+                let (result, violations) = csp_list.should_request_be_blocked(&csp_request.build());
+                if !violations.is_empty() {
+                    let _ = resource_event_sender
+                        .send(WebSocketNetworkEvent::ReportCspViolations(violations));
+                }
+                if result == CheckResult::Blocked {
+                    debug!("Failed to establish a WebSocket connection: blocked by CSP");
+                    let _ = resource_event_sender.send(WebSocketNetworkEvent::Fail);
+                    return;
+                }
+            }
+
             let host = replace_host(req_builder.url.host_str().unwrap());
             let mut net_url = req_builder.url.clone().into_url();
             net_url.set_host(Some(&host)).unwrap();
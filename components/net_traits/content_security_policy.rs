@@ -0,0 +1,642 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! An implementation of [Content Security Policy](https://w3c.github.io/webappsec-csp/)
+//! parsing and matching, shared by the fetch implementation and the DOM.
+
+use crate::request::{Destination, Origin, Request};
+use http::HeaderMap;
+use openssl::hash::{hash, MessageDigest};
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::net::Ipv4Addr;
+
+/// <https://w3c.github.io/webappsec-csp/#policy-disposition>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PolicyDisposition {
+    Enforce,
+    Report,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#policy-source>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PolicySource {
+    Header,
+    Meta,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#directives>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct Directive {
+    pub name: String,
+    pub value: Vec<String>,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#content-security-policy-object>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct Policy {
+    pub directives: Vec<Directive>,
+    pub disposition: PolicyDisposition,
+    pub source: PolicySource,
+    /// The serialized form this policy was parsed from.
+    pub original: String,
+}
+
+/// The kinds of inline content which can be checked against a policy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InlineCheckType {
+    Script,
+    ScriptAttribute,
+    Style,
+    StyleAttribute,
+    Navigation,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#violation-resource>
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ViolationResource {
+    Inline,
+    Eval,
+    Url(ServoUrl),
+}
+
+/// <https://w3c.github.io/webappsec-csp/#violation>
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Violation {
+    pub resource: ViolationResource,
+    pub effective_directive: String,
+    pub policy: Policy,
+    /// The first 40 characters of the offending inline content, if the policy asked for it.
+    pub sample: Option<String>,
+}
+
+/// Whether a check passed, as defined in
+/// <https://w3c.github.io/webappsec-csp/#should-block-request>.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CheckResult {
+    Allowed,
+    Blocked,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#csp-list>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub struct CspList(pub Vec<Policy>);
+
+fn is_ascii_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\x0C' || c == '\r'
+}
+
+impl Directive {
+    /// <https://w3c.github.io/webappsec-csp/#directive-report-uri>
+    fn is_report_directive(&self) -> bool {
+        self.name == "report-uri" || self.name == "report-to"
+    }
+}
+
+impl Policy {
+    /// <https://w3c.github.io/webappsec-csp/#parse-serialized-policy>
+    pub fn parse(serialized: &str, source: PolicySource, disposition: PolicyDisposition) -> Policy {
+        let mut directives: Vec<Directive> = vec![];
+        for token in serialized.split(';') {
+            let token = token.trim_matches(is_ascii_whitespace);
+            if token.is_empty() {
+                continue;
+            }
+            let mut parts = token.split(is_ascii_whitespace).filter(|s| !s.is_empty());
+            let name = match parts.next() {
+                Some(name) => name.to_ascii_lowercase(),
+                None => continue,
+            };
+            if directives.iter().any(|directive| directive.name == name) {
+                continue;
+            }
+            // https://w3c.github.io/webappsec-csp/#meta-element
+            if source == PolicySource::Meta &&
+                (name == "report-uri" || name == "frame-ancestors" || name == "sandbox")
+            {
+                continue;
+            }
+            directives.push(Directive {
+                name,
+                value: parts.map(str::to_owned).collect(),
+            });
+        }
+        Policy {
+            directives,
+            disposition,
+            source,
+            original: serialized.to_owned(),
+        }
+    }
+
+    fn directive(&self, name: &str) -> Option<&Directive> {
+        self.directives
+            .iter()
+            .find(|directive| directive.name == name)
+    }
+
+    /// Returns the first directive of the fallback list which is present in this policy.
+    /// <https://w3c.github.io/webappsec-csp/#directive-fallback-list>
+    fn effective_directive(&self, fallback_list: &[&str]) -> Option<&Directive> {
+        fallback_list
+            .iter()
+            .filter_map(|name| self.directive(name))
+            .next()
+    }
+
+    /// The URLs that violations of this policy should be reported to, from its `report-uri`
+    /// directive.
+    pub fn report_uris(&self) -> Vec<String> {
+        self.directive("report-uri")
+            .map(|directive| directive.value.clone())
+            .unwrap_or_default()
+    }
+
+    /// The name of the reporting group violations should be delivered to, from its `report-to`
+    /// directive.
+    pub fn report_to(&self) -> Option<String> {
+        self.directive("report-to")
+            .and_then(|directive| directive.value.first().cloned())
+    }
+
+    fn violation(&self, resource: ViolationResource, directive: &Directive) -> Violation {
+        Violation {
+            resource,
+            effective_directive: directive.name.clone(),
+            policy: self.clone(),
+            sample: None,
+        }
+    }
+}
+
+/// Returns the directive fallback list for a request, based on its destination.
+/// <https://w3c.github.io/webappsec-csp/#effective-directive-for-a-request>
+fn request_fallback_list(request: &Request) -> Option<&'static [&'static str]> {
+    let fallback_list: &'static [&'static str] = match request.destination {
+        Destination::None => &["connect-src", "default-src"],
+        Destination::Script | Destination::Xslt => {
+            &["script-src-elem", "script-src", "default-src"]
+        },
+        Destination::Style => &["style-src-elem", "style-src", "default-src"],
+        Destination::Image => &["img-src", "default-src"],
+        Destination::Audio | Destination::Track | Destination::Video => {
+            &["media-src", "default-src"]
+        },
+        Destination::Font => &["font-src", "default-src"],
+        Destination::Manifest => &["manifest-src", "default-src"],
+        Destination::Object | Destination::Embed => &["object-src", "default-src"],
+        Destination::Worker | Destination::SharedWorker | Destination::ServiceWorker => {
+            &["worker-src", "child-src", "script-src", "default-src"]
+        },
+        // https://w3c.github.io/webappsec-csp/#directive-frame-src
+        Destination::Document if !request.top_level_navigation => {
+            &["frame-src", "child-src", "default-src"]
+        },
+        Destination::Document | Destination::Report => return None,
+    };
+    Some(fallback_list)
+}
+
+fn inline_fallback_list(type_: InlineCheckType) -> &'static [&'static str] {
+    match type_ {
+        InlineCheckType::Script | InlineCheckType::Navigation => {
+            &["script-src-elem", "script-src", "default-src"]
+        },
+        InlineCheckType::ScriptAttribute => &["script-src-attr", "script-src", "default-src"],
+        InlineCheckType::Style => &["style-src-elem", "style-src", "default-src"],
+        InlineCheckType::StyleAttribute => &["style-src-attr", "style-src", "default-src"],
+    }
+}
+
+fn is_keyword(expression: &str, keyword: &str) -> bool {
+    expression.eq_ignore_ascii_case(keyword)
+}
+
+fn is_nonce_or_hash_source(expression: &str) -> bool {
+    let expression = expression.to_ascii_lowercase();
+    expression.starts_with("'nonce-") ||
+        expression.starts_with("'sha256-") ||
+        expression.starts_with("'sha384-") ||
+        expression.starts_with("'sha512-")
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-schemes>
+fn scheme_part_matches(expression: &str, scheme: &str) -> bool {
+    let expression = expression.to_ascii_lowercase();
+    expression == scheme ||
+        (expression == "http" && scheme == "https") ||
+        (expression == "ws" && (scheme == "wss" || scheme == "http" || scheme == "https")) ||
+        (expression == "wss" && scheme == "https")
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-hosts>
+fn host_part_matches(expression: &str, host: &str) -> bool {
+    let expression = expression.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+    if expression.starts_with('*') {
+        // A bare `*` matches any host, and `*.example.com` any subdomain of
+        // `example.com`. IP addresses only match literally.
+        let is_ip_address = host.starts_with('[') || host.parse::<Ipv4Addr>().is_ok();
+        !is_ip_address && host.ends_with(&expression[1..])
+    } else {
+        expression == host
+    }
+}
+
+/// Split the host-and-port part of a host-source, such as `example.com:443`
+/// or `[::1]:8000`, into its host and port parts.
+fn split_host_and_port(host_and_port: &str) -> (&str, Option<&str>) {
+    // IPv6 addresses are enclosed in brackets, since they contain colons.
+    let host_end = if host_and_port.starts_with('[') {
+        host_and_port
+            .find(']')
+            .map_or(host_and_port.len(), |index| index + 1)
+    } else {
+        host_and_port.find(':').unwrap_or(host_and_port.len())
+    };
+    let (host, port) = host_and_port.split_at(host_end);
+    if port.starts_with(':') {
+        (host, Some(&port[1..]))
+    } else {
+        (host, None)
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-ports>
+fn port_part_matches(expression: Option<&str>, url: &ServoUrl) -> bool {
+    match expression {
+        None => url.port().is_none(),
+        Some("*") => true,
+        Some(port) => match port.parse::<u16>() {
+            Ok(port) => url.port_or_known_default() == Some(port),
+            Err(_) => false,
+        },
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-paths>
+fn path_part_matches(expression: &str, url: &ServoUrl) -> bool {
+    if expression.is_empty() || expression == "/" {
+        return true;
+    }
+    let path = url.path();
+    if expression.ends_with('/') {
+        path.starts_with(expression)
+    } else {
+        path == expression
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-url-to-source-expression>
+fn url_matches_expression(
+    url: &ServoUrl,
+    expression: &str,
+    self_origin: &ImmutableOrigin,
+    redirect_count: u32,
+) -> bool {
+    let scheme = url.scheme();
+    if expression == "*" {
+        // Local schemes have to be explicitly listed.
+        return is_network_scheme(scheme) || self_origin.scheme() == Some(scheme);
+    }
+    if is_keyword(expression, "'self'") {
+        let origin = url.origin();
+        if origin == *self_origin {
+            return true;
+        }
+        return match (self_origin, &origin) {
+            (
+                ImmutableOrigin::Tuple(self_scheme, self_host, self_port),
+                ImmutableOrigin::Tuple(scheme, host, port),
+            ) => {
+                self_host == host &&
+                    (self_port == port ||
+                        (self_scheme == "http" &&
+                            (scheme == "https" || scheme == "wss") &&
+                            url.port().is_none())) &&
+                    (self_scheme == scheme ||
+                        (self_scheme == "http" && (scheme == "https" || scheme == "wss")) ||
+                        (self_scheme == "https" && scheme == "wss"))
+            },
+            _ => false,
+        };
+    }
+    if expression.starts_with('\'') {
+        // Keywords, nonces and hashes never match URLs.
+        return false;
+    }
+
+    // scheme-source, e.g. `https:`
+    if expression.ends_with(':') && !expression.contains('/') {
+        return scheme_part_matches(&expression[..expression.len() - 1], scheme);
+    }
+
+    // host-source, e.g. `https://*.example.com:443/path/`
+    let (expression_scheme, rest) = match expression.find("://") {
+        Some(index) => (Some(&expression[..index]), &expression[index + 3..]),
+        None => (None, expression),
+    };
+    match expression_scheme {
+        Some(expression_scheme) => {
+            if !scheme_part_matches(expression_scheme, scheme) {
+                return false;
+            }
+        },
+        None => {
+            let self_scheme = self_origin.scheme().unwrap_or("");
+            if !(scheme == self_scheme || (is_http_scheme(self_scheme) && is_http_scheme(scheme))) {
+                return false;
+            }
+        },
+    }
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return false,
+    };
+    let (host_and_port, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, ""),
+    };
+    let (expression_host, expression_port) = split_host_and_port(host_and_port);
+    if !host_part_matches(expression_host, host) {
+        return false;
+    }
+    let port_matches = port_part_matches(expression_port, url) ||
+        (expression_port.is_none() &&
+            url.port_or_known_default() ==
+                expression_scheme
+                    .and_then(default_port)
+                    .or_else(|| default_port(scheme)));
+    if !port_matches {
+        return false;
+    }
+    // Paths are ignored after redirects, to avoid leaking information about the redirect target.
+    redirect_count > 0 || path_part_matches(path, url)
+}
+
+fn is_http_scheme(scheme: &str) -> bool {
+    scheme == "http" || scheme == "https"
+}
+
+fn is_network_scheme(scheme: &str) -> bool {
+    match scheme {
+        "http" | "https" | "ws" | "wss" | "ftp" => true,
+        _ => false,
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-url-to-source-list>
+pub fn url_matches_source_list(
+    url: &ServoUrl,
+    source_list: &[String],
+    self_origin: &ImmutableOrigin,
+    redirect_count: u32,
+) -> bool {
+    if source_list.len() == 1 && is_keyword(&source_list[0], "'none'") {
+        return false;
+    }
+    source_list
+        .iter()
+        .any(|expression| url_matches_expression(url, expression, self_origin, redirect_count))
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-element-to-source-list>
+fn inline_matches_source_list(
+    source_list: &[String],
+    type_: InlineCheckType,
+    nonce: Option<&str>,
+    source: &str,
+) -> bool {
+    if let Some(nonce) = nonce.filter(|nonce| !nonce.is_empty()) {
+        let nonce_source = format!("'nonce-{}'", nonce);
+        if source_list
+            .iter()
+            .any(|expression| *expression == nonce_source)
+        {
+            return true;
+        }
+    }
+    // Hashes of attributes are only honoured along with `'unsafe-hashes'`.
+    let hashes_apply = match type_ {
+        InlineCheckType::Script | InlineCheckType::Style => true,
+        InlineCheckType::ScriptAttribute |
+        InlineCheckType::StyleAttribute |
+        InlineCheckType::Navigation => source_list
+            .iter()
+            .any(|expression| is_keyword(expression, "'unsafe-hashes'")),
+    };
+    if hashes_apply &&
+        source_list
+            .iter()
+            .any(|expression| hash_source_matches(expression, source))
+    {
+        return true;
+    }
+    let allows_inline = source_list
+        .iter()
+        .any(|expression| is_keyword(expression, "'unsafe-inline'"));
+    // `'unsafe-inline'` is ignored when nonces or hashes are present.
+    allows_inline &&
+        !source_list
+            .iter()
+            .any(|expression| is_nonce_or_hash_source(expression))
+}
+
+/// <https://w3c.github.io/webappsec-csp/#match-integrity-metadata-to-source-list>
+fn hash_source_matches(expression: &str, source: &str) -> bool {
+    if !expression.starts_with('\'') || !expression.ends_with('\'') || expression.len() < 2 {
+        return false;
+    }
+    let expression = &expression[1..expression.len() - 1];
+    let dash = match expression.find('-') {
+        Some(dash) => dash,
+        None => return false,
+    };
+    let digest = match &*expression[..dash].to_ascii_lowercase() {
+        "sha256" => MessageDigest::sha256(),
+        "sha384" => MessageDigest::sha384(),
+        "sha512" => MessageDigest::sha512(),
+        _ => return false,
+    };
+    let expected = &expression[dash + 1..];
+    let actual = match hash(digest, source.as_bytes()) {
+        Ok(actual) => base64::encode(&actual),
+        Err(_) => return false,
+    };
+    // Both base64 and base64url encodings are allowed.
+    actual == expected || actual == expected.replace('-', "+").replace('_', "/")
+}
+
+impl CspList {
+    /// Parse the policies delivered by the `Content-Security-Policy` and
+    /// `Content-Security-Policy-Report-Only` headers of a response.
+    /// <https://w3c.github.io/webappsec-csp/#parse-response-csp>
+    pub fn from_headers(headers: &HeaderMap) -> CspList {
+        let mut list = CspList::default();
+        for (name, disposition) in &[
+            ("content-security-policy", PolicyDisposition::Enforce),
+            (
+                "content-security-policy-report-only",
+                PolicyDisposition::Report,
+            ),
+        ] {
+            for value in headers.get_all(*name).iter() {
+                if let Ok(value) = value.to_str() {
+                    list.append_serialized(value, PolicySource::Header, *disposition);
+                }
+            }
+        }
+        list
+    }
+
+    /// Parse a comma-separated list of serialized policies, and append them to this list.
+    /// <https://w3c.github.io/webappsec-csp/#parse-serialized-policy-list>
+    pub fn append_serialized(
+        &mut self,
+        serialized: &str,
+        source: PolicySource,
+        disposition: PolicyDisposition,
+    ) {
+        for token in serialized.split(',') {
+            let policy = Policy::parse(token, source, disposition);
+            if !policy.directives.is_empty() {
+                self.0.push(policy);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Run `check` against each policy, collecting violations, and block if any
+    /// enforced policy was violated.
+    fn check<F>(&self, mut check: F) -> (CheckResult, Vec<Violation>)
+    where
+        F: FnMut(&Policy) -> Option<Violation>,
+    {
+        let mut result = CheckResult::Allowed;
+        let mut violations = vec![];
+        for policy in &self.0 {
+            if let Some(violation) = check(policy) {
+                if policy.disposition == PolicyDisposition::Enforce {
+                    result = CheckResult::Blocked;
+                }
+                violations.push(violation);
+            }
+        }
+        (result, violations)
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-request>
+    pub fn should_request_be_blocked(&self, request: &Request) -> (CheckResult, Vec<Violation>) {
+        let fallback_list = match request_fallback_list(request) {
+            Some(fallback_list) => fallback_list,
+            None => return (CheckResult::Allowed, vec![]),
+        };
+        let self_origin = match request.origin {
+            Origin::Origin(ref origin) => origin.clone(),
+            Origin::Client => request.url().origin(),
+        };
+        let url = request.current_url();
+        self.check(|policy| {
+            let directive = policy.effective_directive(fallback_list)?;
+            if url_matches_source_list(&url, &directive.value, &self_origin, request.redirect_count)
+            {
+                return None;
+            }
+            Some(policy.violation(ViolationResource::Url(url.clone()), directive))
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-inline>
+    pub fn should_inline_be_blocked(
+        &self,
+        type_: InlineCheckType,
+        nonce: Option<&str>,
+        source: &str,
+    ) -> (CheckResult, Vec<Violation>) {
+        let fallback_list = inline_fallback_list(type_);
+        self.check(|policy| {
+            let directive = policy.effective_directive(fallback_list)?;
+            if inline_matches_source_list(&directive.value, type_, nonce, source) {
+                return None;
+            }
+            let mut violation = policy.violation(ViolationResource::Inline, directive);
+            if directive
+                .value
+                .iter()
+                .any(|expression| is_keyword(expression, "'report-sample'"))
+            {
+                violation.sample = Some(source.chars().take(40).collect());
+            }
+            Some(violation)
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+    pub fn is_js_evaluation_allowed(&self) -> (CheckResult, Vec<Violation>) {
+        self.check(|policy| {
+            let directive = policy.effective_directive(&["script-src", "default-src"])?;
+            if directive
+                .value
+                .iter()
+                .any(|expression| is_keyword(expression, "'unsafe-eval'"))
+            {
+                return None;
+            }
+            Some(policy.violation(ViolationResource::Eval, directive))
+        })
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#frame-ancestors-navigation-response>
+    ///
+    /// `ancestors` lists the origins of the ancestor navigables, from the parent up to the
+    /// top-level one, with `None` standing for an origin which is not known to this process.
+    /// Unknown origins only match a `*` source.
+    pub fn should_frame_ancestors_block(
+        &self,
+        self_origin: &ImmutableOrigin,
+        ancestors: &[Option<ImmutableOrigin>],
+    ) -> (CheckResult, Vec<Violation>) {
+        self.check(|policy| {
+            if policy.source != PolicySource::Header {
+                return None;
+            }
+            let directive = policy.directive("frame-ancestors")?;
+            for ancestor in ancestors {
+                let matches = match *ancestor {
+                    Some(ref origin) => ServoUrl::parse(&origin.ascii_serialization())
+                        .ok()
+                        .map_or(false, |url| {
+                            url_matches_source_list(&url, &directive.value, self_origin, 0)
+                        }),
+                    None => directive.value.iter().any(|expression| expression == "*"),
+                };
+                if !matches {
+                    return Some(policy.violation(ViolationResource::Inline, directive));
+                }
+            }
+            None
+        })
+    }
+
+    /// Whether any policy in this list wants its violations reported.
+    pub fn has_reporting_directives(&self) -> bool {
+        self.0.iter().any(|policy| {
+            policy
+                .directives
+                .iter()
+                .any(|directive| directive.is_report_directive())
+        })
+    }
+}
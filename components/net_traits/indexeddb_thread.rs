@@ -0,0 +1,310 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::IpcSender;
+use servo_url::ImmutableOrigin;
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// <https://w3c.github.io/IndexedDB/#key-construct>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum IndexedDBKeyType {
+    Number(f64),
+    Date(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Array(Vec<IndexedDBKeyType>),
+}
+
+impl IndexedDBKeyType {
+    /// The rank of the type of the key, for comparisons between keys of different types.
+    fn type_rank(&self) -> u8 {
+        match *self {
+            IndexedDBKeyType::Number(_) => 0,
+            IndexedDBKeyType::Date(_) => 1,
+            IndexedDBKeyType::String(_) => 2,
+            IndexedDBKeyType::Binary(_) => 3,
+            IndexedDBKeyType::Array(_) => 4,
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#compare-two-keys>
+impl Ord for IndexedDBKeyType {
+    fn cmp(&self, other: &IndexedDBKeyType) -> Ordering {
+        match (self, other) {
+            (&IndexedDBKeyType::Number(a), &IndexedDBKeyType::Number(b)) |
+            (&IndexedDBKeyType::Date(a), &IndexedDBKeyType::Date(b)) => {
+                // Keys are never NaN.
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            },
+            (&IndexedDBKeyType::String(ref a), &IndexedDBKeyType::String(ref b)) => {
+                // Strings are compared by their code units.
+                a.encode_utf16().cmp(b.encode_utf16())
+            },
+            (&IndexedDBKeyType::Binary(ref a), &IndexedDBKeyType::Binary(ref b)) => a.cmp(b),
+            (&IndexedDBKeyType::Array(ref a), &IndexedDBKeyType::Array(ref b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for IndexedDBKeyType {
+    fn partial_cmp(&self, other: &IndexedDBKeyType) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexedDBKeyType {
+    fn eq(&self, other: &IndexedDBKeyType) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexedDBKeyType {}
+
+/// <https://w3c.github.io/IndexedDB/#range-construct>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBKeyRange {
+    pub lower: Option<IndexedDBKeyType>,
+    pub upper: Option<IndexedDBKeyType>,
+    pub lower_open: bool,
+    pub upper_open: bool,
+}
+
+impl IndexedDBKeyRange {
+    /// The range containing every key.
+    pub fn unbounded() -> IndexedDBKeyRange {
+        IndexedDBKeyRange::default()
+    }
+
+    /// The range containing only `key`.
+    pub fn only(key: IndexedDBKeyType) -> IndexedDBKeyRange {
+        IndexedDBKeyRange {
+            lower: Some(key.clone()),
+            upper: Some(key),
+            lower_open: false,
+            upper_open: false,
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#in>
+    pub fn contains(&self, key: &IndexedDBKeyType) -> bool {
+        let above_lower = match self.lower {
+            Some(ref lower) if self.lower_open => key > lower,
+            Some(ref lower) => key >= lower,
+            None => true,
+        };
+        let below_upper = match self.upper {
+            Some(ref upper) if self.upper_open => key < upper,
+            Some(ref upper) => key <= upper,
+            None => true,
+        };
+        above_lower && below_upper
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#key-path-construct>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum KeyPath {
+    String(String),
+    Sequence(Vec<String>),
+}
+
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexMetadata {
+    pub name: String,
+    pub key_path: KeyPath,
+    pub unique: bool,
+    pub multi_entry: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct ObjectStoreMetadata {
+    pub name: String,
+    pub key_path: Option<KeyPath>,
+    pub auto_increment: bool,
+    pub indexes: Vec<IndexMetadata>,
+}
+
+/// The state of a database when a connection to it is opened.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct DatabaseMetadata {
+    pub version: u64,
+    pub object_stores: Vec<ObjectStoreMetadata>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBTxnMode {
+    Readonly,
+    Readwrite,
+    Versionchange,
+}
+
+/// <https://w3c.github.io/IndexedDB/#cursor-direction>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum CursorDirection {
+    Next,
+    NextUnique,
+    Prev,
+    PrevUnique,
+}
+
+/// The records an operation reads: those of an object store, or of one of its indexes.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum IndexedDBSource {
+    ObjectStore(String),
+    Index(String, String),
+}
+
+/// A record of an object store or an index. For object stores, the key is the primary key.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBRecord {
+    pub key: IndexedDBKeyType,
+    pub primary_key: IndexedDBKeyType,
+    /// The structured clone of the value.
+    pub value: Vec<u8>,
+}
+
+/// The keys a record has in each index of its object store, computed from its value.
+pub type IndexKeys = Vec<(String, Vec<IndexedDBKeyType>)>;
+
+/// An operation done as part of a transaction.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum IndexedDBOperation {
+    /// Change the version of the database, during a versionchange transaction.
+    SetVersion(u64),
+    CreateObjectStore(String, Option<KeyPath>, bool),
+    DeleteObjectStore(String),
+    /// Create an index in an object store, with the keys of the existing records in it.
+    CreateIndex(
+        String,
+        IndexMetadata,
+        Vec<(IndexedDBKeyType, Vec<IndexedDBKeyType>)>,
+    ),
+    DeleteIndex(String, String),
+    /// Generate the next key of an object store using a key generator.
+    GenerateKey(String),
+    /// Store a value in an object store, replying with its key. Without a key, one is
+    /// generated. Fails if `no_overwrite` is set and a record with that key exists.
+    Put {
+        store: String,
+        key: Option<IndexedDBKeyType>,
+        value: Vec<u8>,
+        index_keys: IndexKeys,
+        no_overwrite: bool,
+    },
+    Delete(String, IndexedDBKeyRange),
+    Clear(String),
+    /// Get the first record in the range.
+    Get(IndexedDBSource, IndexedDBKeyRange),
+    /// Get the records in the range, up to the given count.
+    GetAll(IndexedDBSource, IndexedDBKeyRange, Option<u32>),
+    Count(IndexedDBSource, IndexedDBKeyRange),
+    /// Move a cursor over the range in the given direction, from the given key and primary
+    /// key, past the given number of records.
+    Iterate {
+        source: IndexedDBSource,
+        range: IndexedDBKeyRange,
+        direction: CursorDirection,
+        position: Option<(IndexedDBKeyType, IndexedDBKeyType)>,
+        count: u32,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBOperationResult {
+    None,
+    Key(IndexedDBKeyType),
+    Record(Option<IndexedDBRecord>),
+    Records(Vec<IndexedDBRecord>),
+    Count(u64),
+}
+
+/// Why an operation failed.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBError {
+    /// A record or an index key already exists where it has to be unique.
+    Constraint,
+    /// An object store or an index does not exist.
+    NotFound,
+    /// A key generator reached its maximum value.
+    KeyGeneratorExhausted,
+    /// The transaction does not exist anymore.
+    TransactionFinished,
+}
+
+/// The replies to the messages about a transaction, sent in order over the channel given
+/// when the transaction began.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBTxnReply {
+    Operation(Result<IndexedDBOperationResult, IndexedDBError>),
+    Committed,
+    Aborted,
+}
+
+/// Messages sent to an open connection to a database.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBConnectionMsg {
+    /// Fire a versionchange event with the old and the new version of the database at the
+    /// connection, unless it is closing, and then reply with `VersionChangeFired`.
+    VersionChange(u64, Option<u64>),
+}
+
+/// Request operations on the databases of an origin.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBThreadMsg {
+    /// Open a connection with the given id to a database, creating the database with
+    /// version 0 if it does not exist. The connection is sent the messages for it until
+    /// it is closed.
+    Open(
+        IpcSender<DatabaseMetadata>,
+        ImmutableOrigin,
+        String,
+        Uuid,
+        IpcSender<IndexedDBConnectionMsg>,
+    ),
+
+    /// Close a connection, once it has no transactions left.
+    CloseConnection(Uuid),
+
+    /// A connection handled a `VersionChange` message.
+    VersionChangeFired(Uuid),
+
+    /// Delete a database once the connections to it are closed, replying with its version,
+    /// or `None` if it did not exist. If connections are still open after the versionchange
+    /// events fired at them, the version is first sent over the second channel.
+    DeleteDatabase(
+        IpcSender<Option<u64>>,
+        IpcSender<u64>,
+        ImmutableOrigin,
+        String,
+    ),
+
+    /// Create a transaction over the given object stores of a database. Its messages are
+    /// handled once the transactions created before it that overlap with it are finished,
+    /// unless they are all read-only like it.
+    BeginTransaction {
+        id: Uuid,
+        origin: ImmutableOrigin,
+        name: String,
+        mode: IndexedDBTxnMode,
+        /// The object stores of the transaction, or `None` for versionchange transactions,
+        /// which can use the whole database.
+        scope: Option<Vec<String>>,
+        replies: IpcSender<IndexedDBTxnReply>,
+    },
+
+    /// Run an operation in a transaction.
+    Operation(Uuid, IndexedDBOperation),
+
+    /// Make the changes of a transaction durable.
+    Commit(Uuid),
+
+    /// Undo the changes of a transaction.
+    Abort(Uuid),
+
+    /// send a reply when done cleaning up thread resources and then shut it down
+    Exit(IpcSender<()>),
+}
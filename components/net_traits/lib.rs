@@ -17,11 +17,15 @@ extern crate serde;
 #[macro_use]
 extern crate url;
 
+use crate::content_security_policy::Violation;
 use crate::filemanager_thread::FileManagerThreadMsg;
+use crate::indexeddb_thread::IndexedDBThreadMsg;
+use crate::pub_domains::reg_suffix;
 use crate::request::{Request, RequestBuilder};
+use crate::request_interceptor::RequestInterceptor;
 use crate::response::{HttpsState, Response, ResponseInit};
 use crate::storage_thread::StorageThreadMsg;
-use cookie::Cookie;
+use cookie::{Cookie, SameSite};
 use headers::{ContentType, HeaderMapExt, ReferrerPolicy as ReferrerPolicyHeader};
 use http::{Error as HttpError, HeaderMap};
 use hyper::Error as HyperError;
@@ -34,15 +38,19 @@ use mime::Mime;
 use msg::constellation_msg::HistoryStateId;
 use servo_url::ServoUrl;
 use std::error::Error;
+use std::path::PathBuf;
 use time::precise_time_ns;
 use url::percent_encoding;
 
 pub mod blob_url_store;
+pub mod content_security_policy;
 pub mod filemanager_thread;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod pub_domains;
 pub mod quality;
 pub mod request;
+pub mod request_interceptor;
 pub mod response;
 pub mod storage_thread;
 
@@ -157,6 +165,7 @@ pub enum FetchResponseMsg {
     ProcessResponse(Result<FetchMetadata, NetworkError>),
     ProcessResponseChunk(Vec<u8>),
     ProcessResponseEOF(Result<ResourceFetchTiming, NetworkError>),
+    ProcessCspViolations(Vec<Violation>),
 }
 
 pub trait FetchTaskTarget {
@@ -182,6 +191,11 @@ pub trait FetchTaskTarget {
     ///
     /// Fired when the response is fully fetched
     fn process_response_eof(&mut self, response: &Response);
+
+    /// <https://w3c.github.io/webappsec-csp/#report-violation>
+    ///
+    /// Fired when the request violated the Content Security Policy of its client
+    fn process_csp_violations(&mut self, request: &Request, violations: Vec<Violation>);
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -207,6 +221,7 @@ pub trait FetchResponseListener {
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>);
     fn process_response_chunk(&mut self, chunk: Vec<u8>);
     fn process_response_eof(&mut self, response: Result<ResourceFetchTiming, NetworkError>);
+    fn process_csp_violations(&mut self, violations: Vec<Violation>);
     fn resource_timing(&self) -> &ResourceFetchTiming;
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming;
     fn submit_resource_timing(&mut self);
@@ -238,6 +253,10 @@ impl FetchTaskTarget for IpcSender<FetchResponseMsg> {
                 .clone())));
         }
     }
+
+    fn process_csp_violations(&mut self, _: &Request, violations: Vec<Violation>) {
+        let _ = self.send(FetchResponseMsg::ProcessCspViolations(violations));
+    }
 }
 
 pub trait Action<Listener> {
@@ -269,6 +288,9 @@ impl<T: FetchResponseListener> Action<T> for FetchResponseMsg {
                     Err(e) => listener.process_response_eof(Err(e)),
                 }
             },
+            FetchResponseMsg::ProcessCspViolations(violations) => {
+                listener.process_csp_violations(violations)
+            },
         }
     }
 }
@@ -300,13 +322,19 @@ where
 pub struct ResourceThreads {
     core_thread: CoreResourceThread,
     storage_thread: IpcSender<StorageThreadMsg>,
+    idb_thread: IpcSender<IndexedDBThreadMsg>,
 }
 
 impl ResourceThreads {
-    pub fn new(c: CoreResourceThread, s: IpcSender<StorageThreadMsg>) -> ResourceThreads {
+    pub fn new(
+        c: CoreResourceThread,
+        s: IpcSender<StorageThreadMsg>,
+        i: IpcSender<IndexedDBThreadMsg>,
+    ) -> ResourceThreads {
         ResourceThreads {
             core_thread: c,
             storage_thread: s,
+            idb_thread: i,
         }
     }
 }
@@ -331,6 +359,16 @@ impl IpcSend<StorageThreadMsg> for ResourceThreads {
     }
 }
 
+impl IpcSend<IndexedDBThreadMsg> for ResourceThreads {
+    fn send(&self, msg: IndexedDBThreadMsg) -> IpcSendResult {
+        self.idb_thread.send(msg)
+    }
+
+    fn sender(&self) -> IpcSender<IndexedDBThreadMsg> {
+        self.idb_thread.clone()
+    }
+}
+
 // Ignore the sub-fields
 malloc_size_of_is_0!(ResourceThreads);
 
@@ -358,6 +396,7 @@ pub enum WebSocketNetworkEvent {
     MessageReceived(MessageData),
     Close(Option<u16>, String),
     Fail,
+    ReportCspViolations(Vec<Violation>),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -384,11 +423,26 @@ pub enum CoreResourceMsg {
         /* cancel_chan */ Option<IpcReceiver<()>>,
     ),
     /// Store a cookie for a given originating URL
-    SetCookieForUrl(ServoUrl, Serde<Cookie<'static>>, CookieSource),
+    SetCookieForUrl(
+        ServoUrl,
+        Serde<Cookie<'static>>,
+        CookieSource,
+        CookieContext,
+    ),
     /// Store a set of cookies for a given originating URL
-    SetCookiesForUrl(ServoUrl, Vec<Serde<Cookie<'static>>>, CookieSource),
+    SetCookiesForUrl(
+        ServoUrl,
+        Vec<Serde<Cookie<'static>>>,
+        CookieSource,
+        CookieContext,
+    ),
     /// Retrieve the stored cookies for a given URL
-    GetCookiesForUrl(ServoUrl, IpcSender<Option<String>>, CookieSource),
+    GetCookiesForUrl(
+        ServoUrl,
+        IpcSender<Option<String>>,
+        CookieSource,
+        CookieContext,
+    ),
     /// Get a cookie by name for a given originating URL
     GetCookiesDataForUrl(
         ServoUrl,
@@ -406,6 +460,14 @@ pub enum CoreResourceMsg {
     Synchronize(IpcSender<()>),
     /// Send the network sender in constellation to CoreResourceThread
     NetworkMediator(IpcSender<CustomResponseMediator>),
+    /// Start sending the requests matching the given filters to the embedder, or stop
+    /// intercepting requests if `None`.
+    SetRequestInterceptor(Option<RequestInterceptor>),
+    /// Write the requests recorded so far as a HAR file at the given path
+    SaveHar(PathBuf),
+    /// Ask the embedder whether to trust the certificate last rejected for the given host,
+    /// even though it failed verification, and send back whether an exception was made
+    PromptCertificateException(String, IpcSender<bool>),
     /// Message forwarded to file manager's handler
     ToFileManager(FileManagerThreadMsg),
     /// Break the load handler loop, send a reply when done cleaning up local resources
@@ -453,6 +515,9 @@ pub struct ResourceFetchTiming {
     pub redirect_end: u64,
     pub connect_start: u64,
     pub connect_end: u64,
+    /// The ALPN protocol ID of the protocol used to fetch the resource, if it was
+    /// fetched over the network.
+    pub next_hop_protocol: Option<String>,
 }
 
 pub enum RedirectStartValue {
@@ -476,6 +541,7 @@ pub enum ResourceAttribute {
     FetchStart,
     ConnectStart(u64),
     ConnectEnd(u64),
+    NextHopProtocol(String),
     ResponseEnd,
 }
 
@@ -500,6 +566,7 @@ impl ResourceFetchTiming {
             redirect_end: 0,
             connect_start: 0,
             connect_end: 0,
+            next_hop_protocol: None,
             response_end: 0,
         }
     }
@@ -527,6 +594,7 @@ impl ResourceFetchTiming {
             ResourceAttribute::FetchStart => self.fetch_start = precise_time_ns(),
             ResourceAttribute::ConnectStart(val) => self.connect_start = val,
             ResourceAttribute::ConnectEnd(val) => self.connect_end = val,
+            ResourceAttribute::NextHopProtocol(protocol) => self.next_hop_protocol = Some(protocol),
             ResourceAttribute::ResponseEnd => self.response_end = precise_time_ns(),
         }
     }
@@ -613,6 +681,93 @@ pub enum CookieSource {
     NonHTTP,
 }
 
+/// How the URL a cookie is being read or written for relates to the site it is being
+/// used from, which decides which `SameSite` cookies can be used and which partition of
+/// the cookie jar is used.
+/// <https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.2>
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CookieContext {
+    /// Whether the URL is same-site with the site for cookies.
+    pub same_site: bool,
+    /// Whether the URL is being loaded by a top-level navigation, whose response can
+    /// set `SameSite` cookies even if it is cross-site.
+    pub top_level_navigation: bool,
+    /// Whether the URL is being loaded by a top-level navigation with a safe method,
+    /// with which `SameSite=Lax` cookies are used even if it is cross-site.
+    pub safe_top_level_navigation: bool,
+    /// The partition of the cookie jar used, if any.
+    pub partition_key: Option<String>,
+}
+
+impl CookieContext {
+    /// The context of a URL that is its own site for cookies.
+    pub fn first_party() -> CookieContext {
+        CookieContext {
+            same_site: true,
+            top_level_navigation: false,
+            safe_top_level_navigation: false,
+            partition_key: None,
+        }
+    }
+
+    /// The context of `url` when loaded from `site_for_cookies`. Third-party cookies are
+    /// partitioned by the top-level site if `partitioned` is true.
+    pub fn new(
+        url: &ServoUrl,
+        site_for_cookies: Option<&ServoUrl>,
+        top_level_navigation: bool,
+        safe_method: bool,
+        partitioned: bool,
+    ) -> CookieContext {
+        let site_for_cookies = match site_for_cookies {
+            Some(site_for_cookies) => site_for_cookies,
+            None => return CookieContext::first_party(),
+        };
+        let same_site = site(url) == site(site_for_cookies);
+        // A top-level navigation makes its URL the top-level site, so only the cookies
+        // of subresources and nested documents are partitioned.
+        let partition_key = if partitioned && !same_site && !top_level_navigation {
+            Some(site(site_for_cookies))
+        } else {
+            None
+        };
+        CookieContext {
+            same_site,
+            top_level_navigation,
+            safe_top_level_navigation: top_level_navigation && safe_method,
+            partition_key,
+        }
+    }
+
+    /// Whether a cookie with the given `SameSite` attribute can be used in this context.
+    /// <https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.3.7.1>
+    pub fn allows(&self, same_site: Option<SameSite>) -> bool {
+        match same_site {
+            Some(SameSite::Strict) => self.same_site,
+            Some(SameSite::Lax) => self.same_site || self.safe_top_level_navigation,
+            _ => true,
+        }
+    }
+
+    /// Whether a cookie with the given `SameSite` attribute can be set in this context.
+    /// Unlike when cookies are sent, any top-level navigation can set them.
+    /// <https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.4> Step 13
+    pub fn allows_setting(&self, same_site: Option<SameSite>) -> bool {
+        match same_site {
+            Some(SameSite::Strict) | Some(SameSite::Lax) => {
+                self.same_site || self.top_level_navigation
+            },
+            _ => true,
+        }
+    }
+}
+
+/// The registrable domain of a URL's host, which is what decides whether two URLs are
+/// same-site.
+fn site(url: &ServoUrl) -> String {
+    reg_suffix(url.host_str().unwrap_or("")).to_lowercase()
+}
+
 /// Network errors that have to be exported out of the loaders
 #[derive(Clone, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
 pub enum NetworkError {
@@ -620,7 +775,48 @@ pub enum NetworkError {
     Internal(String),
     LoadCancelled,
     /// SSL validation error that has to be handled in the HTML parser
-    SslValidation(ServoUrl, String),
+    SslValidation(ServoUrl, CertificateError),
+}
+
+/// Why the certificate presented by a server failed verification.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum CertificateErrorKind {
+    Expired,
+    NotYetValid,
+    SelfSigned,
+    UnknownIssuer,
+    HostnameMismatch,
+    Other,
+}
+
+/// A certificate rejected during the TLS handshake with a host.
+#[derive(Clone, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub struct CertificateError {
+    pub kind: CertificateErrorKind,
+    pub host: String,
+    /// The rejected certificate, PEM-encoded, so that an exception can be made for it.
+    pub certificate: String,
+    /// The verification error reported by OpenSSL.
+    pub reason: String,
+}
+
+impl CertificateError {
+    /// A description of the error which can be shown to users.
+    pub fn description(&self) -> String {
+        let description = match self.kind {
+            CertificateErrorKind::Expired => "The certificate of this site has expired.",
+            CertificateErrorKind::NotYetValid => "The certificate of this site is not valid yet.",
+            CertificateErrorKind::SelfSigned => "The certificate of this site is self-signed.",
+            CertificateErrorKind::UnknownIssuer => {
+                "The certificate of this site was issued by an unknown authority."
+            },
+            CertificateErrorKind::HostnameMismatch => {
+                "The certificate of this site was issued for another host."
+            },
+            CertificateErrorKind::Other => "The certificate of this site is invalid.",
+        };
+        format!("{} ({})", description, self.reason)
+    }
 }
 
 impl NetworkError {
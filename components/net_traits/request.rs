@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::content_security_policy::CspList;
 use crate::ReferrerPolicy;
 use crate::ResourceTimingType;
 use http::HeaderMap;
@@ -178,6 +179,9 @@ pub struct RequestBuilder {
     // to keep track of redirects
     pub url_list: Vec<ServoUrl>,
     pub parser_metadata: ParserMetadata,
+    pub csp_list: Option<CspList>,
+    pub site_for_cookies: Option<ServoUrl>,
+    pub top_level_navigation: bool,
 }
 
 impl RequestBuilder {
@@ -204,6 +208,9 @@ impl RequestBuilder {
             integrity_metadata: "".to_owned(),
             url_list: vec![],
             parser_metadata: ParserMetadata::Default,
+            csp_list: None,
+            site_for_cookies: None,
+            top_level_navigation: false,
         }
     }
 
@@ -292,6 +299,21 @@ impl RequestBuilder {
         self
     }
 
+    pub fn csp_list(mut self, csp_list: Option<CspList>) -> RequestBuilder {
+        self.csp_list = csp_list;
+        self
+    }
+
+    pub fn site_for_cookies(mut self, site_for_cookies: Option<ServoUrl>) -> RequestBuilder {
+        self.site_for_cookies = site_for_cookies;
+        self
+    }
+
+    pub fn top_level_navigation(mut self, top_level_navigation: bool) -> RequestBuilder {
+        self.top_level_navigation = top_level_navigation;
+        self
+    }
+
     pub fn build(self) -> Request {
         let mut request = Request::new(
             self.url.clone(),
@@ -321,6 +343,9 @@ impl RequestBuilder {
         request.url_list = url_list;
         request.integrity_metadata = self.integrity_metadata;
         request.parser_metadata = self.parser_metadata;
+        request.csp_list = self.csp_list;
+        request.site_for_cookies = self.site_for_cookies;
+        request.top_level_navigation = self.top_level_navigation;
         request
     }
 }
@@ -388,6 +413,17 @@ pub struct Request {
     pub response_tainting: ResponseTainting,
     /// <https://fetch.spec.whatwg.org/#concept-request-parser-metadata>
     pub parser_metadata: ParserMetadata,
+    /// The [CSP list](https://w3c.github.io/webappsec-csp/#concept-settings-object-csp-list)
+    /// of the request's client.
+    pub csp_list: Option<CspList>,
+    /// The URL whose site this request is compared to when deciding whether it is
+    /// same-site: the top-level document of the request's client, or the document that
+    /// initiated a top-level navigation. `None` for requests made on behalf of the user,
+    /// which are always same-site.
+    /// <https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.2.1>
+    pub site_for_cookies: Option<ServoUrl>,
+    /// Whether this is a navigation of a top-level browsing context.
+    pub top_level_navigation: bool,
 }
 
 impl Request {
@@ -420,6 +456,9 @@ impl Request {
             parser_metadata: ParserMetadata::Default,
             redirect_count: 0,
             response_tainting: ResponseTainting::Basic,
+            csp_list: None,
+            site_for_cookies: None,
+            top_level_navigation: false,
         }
     }
 
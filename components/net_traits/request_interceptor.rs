@@ -0,0 +1,124 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Types allowing an embedder to intercept the requests made by the resource threads, and
+//! to let them continue, rewrite them, fulfil them with a synthetic response or fail them.
+
+use crate::request::{Destination, Request};
+use crate::CustomResponse;
+use http::{HeaderMap, Method};
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::PipelineId;
+use servo_url::ServoUrl;
+
+/// Selects the requests an embedder wants to intercept.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RequestInterceptionFilter {
+    /// A pattern the whole request URL has to match, in which `*` matches any sequence of
+    /// characters.
+    pub url_pattern: String,
+    /// The destinations of the requests to intercept, or all of them if empty.
+    pub destinations: Vec<Destination>,
+}
+
+impl RequestInterceptionFilter {
+    pub fn new(url_pattern: &str, destinations: Vec<Destination>) -> RequestInterceptionFilter {
+        RequestInterceptionFilter {
+            url_pattern: url_pattern.to_owned(),
+            destinations,
+        }
+    }
+
+    pub fn matches(&self, url: &ServoUrl, destination: Destination) -> bool {
+        (self.destinations.is_empty() || self.destinations.contains(&destination)) &&
+            wildcard_match(&self.url_pattern, url.as_str())
+    }
+}
+
+/// Whether `text` matches `pattern`, in which `*` matches any sequence of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // The first part is anchored at the start of the text, and the last one at its end.
+    let first = parts.next().unwrap_or("");
+    if !text.starts_with(first) {
+        return false;
+    }
+    let mut rest = &text[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Intercepts the requests matching any of its filters, by sending them to the embedder
+/// and waiting for its decision before going on with the fetch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RequestInterceptor {
+    pub filters: Vec<RequestInterceptionFilter>,
+    pub sender: IpcSender<InterceptedRequest>,
+}
+
+impl RequestInterceptor {
+    pub fn new(
+        filters: Vec<RequestInterceptionFilter>,
+        sender: IpcSender<InterceptedRequest>,
+    ) -> RequestInterceptor {
+        RequestInterceptor { filters, sender }
+    }
+
+    /// Whether `request` has to be sent to the embedder.
+    pub fn intercepts(&self, request: &Request) -> bool {
+        let url = request.current_url();
+        self.filters
+            .iter()
+            .any(|filter| filter.matches(&url, request.destination))
+    }
+}
+
+/// A request sent to the embedder, which has to answer on `response_chan`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InterceptedRequest {
+    pub url: ServoUrl,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub method: Method,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub headers: HeaderMap,
+    pub destination: Destination,
+    pub pipeline_id: Option<PipelineId>,
+    pub response_chan: IpcSender<InterceptedRequestAction>,
+}
+
+/// What the embedder decided to do with an intercepted request.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum InterceptedRequestAction {
+    /// Go on with the request unchanged.
+    Continue,
+    /// Go on with the request, using the given URL and headers instead of its own.
+    Modify {
+        url: ServoUrl,
+        #[serde(
+            deserialize_with = "::hyper_serde::deserialize",
+            serialize_with = "::hyper_serde::serialize"
+        )]
+        headers: HeaderMap,
+    },
+    /// Answer the request with a synthetic response, without going to the network.
+    Fulfill(CustomResponse),
+    /// Fail the request with a network error carrying the given reason.
+    Fail(String),
+}
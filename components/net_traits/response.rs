@@ -221,6 +221,15 @@ impl Response {
         &self.resource_timing
     }
 
+    /// The ALPN protocol ID negotiated for the connection this response was received
+    /// over, such as `h2` or `http/1.1`.
+    pub fn next_hop_protocol(&self) -> Option<&str> {
+        self.resource_timing
+            .next_hop_protocol
+            .as_ref()
+            .map(|protocol| &**protocol)
+    }
+
     /// Convert to a filtered response, of type `filter_type`.
     /// Do not use with type Error or Default
     #[cfg_attr(rustfmt, rustfmt_skip)]
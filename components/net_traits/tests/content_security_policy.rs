@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use http::header::{HeaderMap, HeaderValue};
+use net_traits::content_security_policy::{CheckResult, CspList, InlineCheckType};
+use net_traits::content_security_policy::{PolicyDisposition, PolicySource};
+use net_traits::request::{Destination, Origin, Request};
+use servo_url::ServoUrl;
+
+fn csp_list(serialized: &str) -> CspList {
+    let mut list = CspList::default();
+    list.append_serialized(serialized, PolicySource::Header, PolicyDisposition::Enforce);
+    list
+}
+
+fn request(url: &str, destination: Destination) -> Request {
+    let origin = ServoUrl::parse("https://example.com/").unwrap().origin();
+    let mut request = Request::new(
+        ServoUrl::parse(url).unwrap(),
+        Some(Origin::Origin(origin)),
+        None,
+    );
+    request.destination = destination;
+    request
+}
+
+#[test]
+fn test_parse_policy_list() {
+    let list = csp_list("script-src 'self'; img-src *, default-src 'none'");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0].directives.len(), 2);
+    assert_eq!(list.0[0].directives[0].name, "script-src");
+    assert_eq!(list.0[0].directives[0].value, vec!["'self'".to_owned()]);
+    assert_eq!(list.0[1].directives[0].name, "default-src");
+}
+
+#[test]
+fn test_parse_ignores_duplicate_directives() {
+    let list = csp_list("img-src 'none'; IMG-SRC *");
+    assert_eq!(list.0[0].directives.len(), 1);
+    assert_eq!(list.0[0].directives[0].value, vec!["'none'".to_owned()]);
+}
+
+#[test]
+fn test_meta_policy_ignores_unsupported_directives() {
+    let mut list = CspList::default();
+    list.append_serialized(
+        "frame-ancestors 'none'; report-uri /csp; img-src 'self'",
+        PolicySource::Meta,
+        PolicyDisposition::Enforce,
+    );
+    assert_eq!(list.0[0].directives.len(), 1);
+    assert_eq!(list.0[0].directives[0].name, "img-src");
+}
+
+#[test]
+fn test_from_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "content-security-policy",
+        HeaderValue::from_static("script-src 'self'"),
+    );
+    headers.insert(
+        "content-security-policy-report-only",
+        HeaderValue::from_static("img-src 'none'"),
+    );
+    let list = CspList::from_headers(&headers);
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0].disposition, PolicyDisposition::Enforce);
+    assert_eq!(list.0[1].disposition, PolicyDisposition::Report);
+}
+
+#[test]
+fn test_request_matching_self() {
+    let list = csp_list("img-src 'self'");
+    let (result, violations) =
+        list.should_request_be_blocked(&request("https://example.com/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Allowed);
+    assert!(violations.is_empty());
+
+    let (result, violations) =
+        list.should_request_be_blocked(&request("https://other.com/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].effective_directive, "img-src");
+}
+
+#[test]
+fn test_nested_navigation_uses_frame_src() {
+    let list = csp_list("frame-src https://example.com; default-src 'none'");
+    let (result, _) =
+        list.should_request_be_blocked(&request("https://example.com/", Destination::Document));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, violations) =
+        list.should_request_be_blocked(&request("https://other.com/", Destination::Document));
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations[0].effective_directive, "frame-src");
+
+    // Without frame-src, child-src and then default-src apply.
+    let (result, violations) = csp_list("child-src 'none'; default-src *")
+        .should_request_be_blocked(&request("https://other.com/", Destination::Document));
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations[0].effective_directive, "child-src");
+    let (result, _) = csp_list("default-src 'none'")
+        .should_request_be_blocked(&request("https://other.com/", Destination::Document));
+    assert_eq!(result, CheckResult::Blocked);
+
+    // Top-level navigations aren't subject to frame-src.
+    let mut top_level = request("https://other.com/", Destination::Document);
+    top_level.top_level_navigation = true;
+    let (result, _) = list.should_request_be_blocked(&top_level);
+    assert_eq!(result, CheckResult::Allowed);
+}
+
+#[test]
+fn test_request_falls_back_to_default_src() {
+    let list = csp_list("default-src https://cdn.example.com");
+    let (result, _) = list.should_request_be_blocked(&request(
+        "https://cdn.example.com/script.js",
+        Destination::Script,
+    ));
+    assert_eq!(result, CheckResult::Allowed);
+
+    let (result, violations) =
+        list.should_request_be_blocked(&request("https://evil.com/script.js", Destination::Script));
+    assert_eq!(result, CheckResult::Blocked);
+    assert_eq!(violations[0].effective_directive, "default-src");
+}
+
+#[test]
+fn test_request_matching_host_wildcard_and_scheme() {
+    let list = csp_list("img-src *.example.org data:");
+    let (result, _) = list.should_request_be_blocked(&request(
+        "https://images.example.org/a.png",
+        Destination::Image,
+    ));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) =
+        list.should_request_be_blocked(&request("data:image/png,", Destination::Image));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) =
+        list.should_request_be_blocked(&request("https://example.org/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Blocked);
+}
+
+#[test]
+fn test_request_matching_bare_host_wildcard_and_ipv6() {
+    let list = csp_list("img-src https://*:8443 http://[::1]");
+    let (result, _) = list.should_request_be_blocked(&request(
+        "https://images.example.org:8443/a.png",
+        Destination::Image,
+    ));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) =
+        list.should_request_be_blocked(&request("http://[::1]/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) =
+        list.should_request_be_blocked(&request("http://[::2]/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Blocked);
+}
+
+#[test]
+fn test_report_only_policy_does_not_block() {
+    let mut list = CspList::default();
+    list.append_serialized(
+        "img-src 'none'",
+        PolicySource::Header,
+        PolicyDisposition::Report,
+    );
+    let (result, violations) =
+        list.should_request_be_blocked(&request("https://example.com/a.png", Destination::Image));
+    assert_eq!(result, CheckResult::Allowed);
+    assert_eq!(violations.len(), 1);
+}
+
+#[test]
+fn test_inline_script_checks() {
+    let list = csp_list("script-src 'self' 'nonce-abc'");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, None, "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+    let (result, _) =
+        list.should_inline_be_blocked(InlineCheckType::Script, Some("abc"), "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+
+    let list = csp_list("script-src 'unsafe-inline'");
+    let (result, _) =
+        list.should_inline_be_blocked(InlineCheckType::ScriptAttribute, None, "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+}
+
+#[test]
+fn test_inline_script_hash_checks() {
+    let list = csp_list("script-src 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='");
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, None, "alert(1)");
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) = list.should_inline_be_blocked(InlineCheckType::Script, None, "alert(2)");
+    assert_eq!(result, CheckResult::Blocked);
+    // Event handler attributes also need `'unsafe-hashes'`.
+    let (result, _) =
+        list.should_inline_be_blocked(InlineCheckType::ScriptAttribute, None, "alert(1)");
+    assert_eq!(result, CheckResult::Blocked);
+}
+
+#[test]
+fn test_eval_requires_unsafe_eval() {
+    let (result, _) = csp_list("script-src 'self'").is_js_evaluation_allowed();
+    assert_eq!(result, CheckResult::Blocked);
+    let (result, _) = csp_list("script-src 'self' 'unsafe-eval'").is_js_evaluation_allowed();
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) = csp_list("img-src 'none'").is_js_evaluation_allowed();
+    assert_eq!(result, CheckResult::Allowed);
+}
+
+#[test]
+fn test_frame_ancestors() {
+    let self_origin = ServoUrl::parse("https://example.com/").unwrap().origin();
+    let other_origin = ServoUrl::parse("https://other.com/").unwrap().origin();
+
+    let list = csp_list("frame-ancestors 'self'");
+    let (result, _) = list.should_frame_ancestors_block(&self_origin, &[Some(self_origin.clone())]);
+    assert_eq!(result, CheckResult::Allowed);
+    let (result, _) = list.should_frame_ancestors_block(&self_origin, &[Some(other_origin)]);
+    assert_eq!(result, CheckResult::Blocked);
+    let (result, _) = list.should_frame_ancestors_block(&self_origin, &[None]);
+    assert_eq!(result, CheckResult::Blocked);
+
+    let (result, _) =
+        csp_list("frame-ancestors *").should_frame_ancestors_block(&self_origin, &[None]);
+    assert_eq!(result, CheckResult::Allowed);
+}
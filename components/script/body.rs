@@ -13,7 +13,9 @@ use crate::dom::blob::{Blob, BlobImpl};
 use crate::dom::formdata::FormData;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
 use crate::script_runtime::JSContext;
+use crate::streams::{upon_settlement, PromiseReaction};
 use js::jsapi::Heap;
 use js::jsapi::JSObject;
 use js::jsapi::JS_ClearPendingException;
@@ -22,7 +24,8 @@ use js::jsval::JSVal;
 use js::jsval::UndefinedValue;
 use js::rust::wrappers::JS_GetPendingException;
 use js::rust::wrappers::JS_ParseJSON;
-use js::typedarray::{ArrayBuffer, CreateWith};
+use js::rust::HandleValue;
+use js::typedarray::{ArrayBuffer, CreateWith, Uint8Array};
 use mime::{self, Mime};
 use std::cell::Ref;
 use std::ptr;
@@ -48,9 +51,17 @@ pub enum FetchedData {
     JSException(RootedTraceableBox<Heap<JSVal>>),
 }
 
+/// A body being consumed through its stream: the promise returned to script
+/// and the type the bytes are packaged as once they are all read.
+#[derive(JSTraceable)]
+pub struct ConsumeBodyStep(Rc<Promise>, BodyType);
+
 // https://fetch.spec.whatwg.org/#concept-body-consume-body
 #[allow(unrooted_must_root)]
-pub fn consume_body<T: BodyOperations + DomObject>(object: &T, body_type: BodyType) -> Rc<Promise> {
+pub fn consume_body<T>(object: &T, body_type: BodyType) -> Rc<Promise>
+where
+    T: BodyOperations + PromiseReaction<Step = ConsumeBodyStep>,
+{
     let in_compartment_proof = AlreadyInCompartment::assert(&object.global());
     let promise = Promise::new_in_current_compartment(
         &object.global(),
@@ -65,10 +76,14 @@ pub fn consume_body<T: BodyOperations + DomObject>(object: &T, body_type: BodyTy
         return promise;
     }
 
-    object.set_body_promise(&promise, body_type);
-
     // Steps 2-4
-    // TODO: Body does not yet have a stream.
+    if let Some(stream) = object.get_stream() {
+        let bytes = stream.read_all_bytes();
+        upon_settlement(&bytes, object, ConsumeBodyStep(promise.clone(), body_type));
+        return promise;
+    }
+
+    object.set_body_promise(&promise, body_type);
 
     consume_body_with_promise(object, body_type, &promise);
 
@@ -88,6 +103,39 @@ pub fn consume_body_with_promise<T: BodyOperations + DomObject>(
         None => return,
     };
 
+    resolve_with_package_data(object, body, body_type, promise);
+}
+
+/// Finish consuming a body whose stream was read to its end, as started by
+/// `consume_body`.
+#[allow(unsafe_code)]
+pub fn consume_stream_bytes<T: BodyOperations + DomObject>(
+    object: &T,
+    step: &ConsumeBodyStep,
+    result: Result<HandleValue, HandleValue>,
+) {
+    let ConsumeBodyStep(ref promise, body_type) = *step;
+    let bytes = match result {
+        Ok(bytes) => bytes,
+        Err(error) => return promise.reject_native(&error.get()),
+    };
+    let cx = object.global().get_cx();
+    typedarray!(in(*cx) let array: Uint8Array = bytes.to_object());
+    let bytes = match array {
+        Ok(array) => unsafe { array.as_slice().to_vec() },
+        Err(()) => {
+            return promise.reject_error(Error::Type("The body is not a byte stream".to_string()))
+        },
+    };
+    resolve_with_package_data(object, bytes, body_type, promise);
+}
+
+fn resolve_with_package_data<T: BodyOperations + DomObject>(
+    object: &T,
+    body: Vec<u8>,
+    body_type: BodyType,
+    promise: &Promise,
+) {
     let pkg_data_results =
         run_package_data_algorithm(object, body, body_type, object.get_mime_type());
 
@@ -223,5 +271,7 @@ pub trait BodyOperations {
     /// come.
     fn take_body(&self) -> Option<Vec<u8>>;
     fn is_locked(&self) -> bool;
+    /// Returns the stream of the body, if script has already asked for it.
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>>;
     fn get_mime_type(&self) -> Ref<Vec<u8>>;
 }
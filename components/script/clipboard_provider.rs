@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use embedder_traits::EmbedderMsg;
+use embedder_traits::{ClipboardContents, EmbedderMsg};
 use ipc_channel::ipc::channel;
 use script_traits::{ScriptMsg, ScriptToConstellationChan};
 
@@ -11,10 +11,27 @@ pub trait ClipboardProvider {
     fn clipboard_contents(&mut self) -> String;
     // blocking method to set the clipboard contents
     fn set_clipboard_contents(&mut self, _: String);
+
+    // blocking method to get the clipboard contents in all the formats it has
+    fn clipboard_data(&mut self) -> ClipboardContents {
+        ClipboardContents::from_text(self.clipboard_contents())
+    }
+    // blocking method to set the clipboard contents in several formats
+    fn set_clipboard_data(&mut self, contents: ClipboardContents) {
+        if let Some(text) = contents.text() {
+            self.set_clipboard_contents(text);
+        }
+    }
 }
 
 impl ClipboardProvider for ScriptToConstellationChan {
     fn clipboard_contents(&mut self) -> String {
+        self.clipboard_data().text().unwrap_or_default()
+    }
+    fn set_clipboard_contents(&mut self, s: String) {
+        self.set_clipboard_data(ClipboardContents::from_text(s));
+    }
+    fn clipboard_data(&mut self) -> ClipboardContents {
         let (tx, rx) = channel().unwrap();
         self.send(ScriptMsg::ForwardToEmbedder(
             EmbedderMsg::GetClipboardContents(tx),
@@ -22,9 +39,9 @@ impl ClipboardProvider for ScriptToConstellationChan {
         .unwrap();
         rx.recv().unwrap()
     }
-    fn set_clipboard_contents(&mut self, s: String) {
+    fn set_clipboard_data(&mut self, contents: ClipboardContents) {
         self.send(ScriptMsg::ForwardToEmbedder(
-            EmbedderMsg::SetClipboardContents(s),
+            EmbedderMsg::SetClipboardContents(contents),
         ))
         .unwrap();
     }
@@ -0,0 +1,217 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AbortSignalBinding;
+use crate::dom::bindings::codegen::Bindings::AbortSignalBinding::AbortSignalMethods;
+use crate::dom::bindings::codegen::Bindings::EventListenerBinding::EventListener;
+use crate::dom::bindings::codegen::Bindings::EventTargetBinding::EventListenerOptions;
+use crate::dom::bindings::codegen::Bindings::XMLHttpRequestBinding::XMLHttpRequestMethods;
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::response::Response;
+use crate::dom::xmlhttprequest::XMLHttpRequest;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The steps to run when a signal is aborted.
+///
+/// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum AbortAlgorithm {
+    /// Remove an event listener that was added with this signal, with the
+    /// given type and capture flag.
+    RemoveEventListener(
+        Dom<EventTarget>,
+        DOMString,
+        #[ignore_malloc_size_of = "Rc"] Rc<EventListener>,
+        bool,
+    ),
+    /// Abort a signal that follows this one.
+    Follow(Dom<AbortSignal>),
+    /// Abort an ongoing fetch: cancel it through the given channel, and
+    /// reject its promise and the body of its response.
+    Fetch(
+        #[ignore_malloc_size_of = "channels are hard"] IpcSender<()>,
+        #[ignore_malloc_size_of = "Rc"] Rc<Promise>,
+        Dom<Response>,
+    ),
+    /// Abort the ongoing request of an XMLHttpRequest, as its `abort()` method does.
+    XMLHttpRequest(Dom<XMLHttpRequest>),
+}
+
+/// A rooted snapshot of an `AbortAlgorithm`, taken so that no borrow is held
+/// while it runs.
+enum AbortAlgorithmRoot {
+    RemoveEventListener(DomRoot<EventTarget>, DOMString, Rc<EventListener>, bool),
+    Follow(DomRoot<AbortSignal>),
+    Fetch(IpcSender<()>, Rc<Promise>, DomRoot<Response>),
+    XMLHttpRequest(DomRoot<XMLHttpRequest>),
+}
+
+impl AbortAlgorithm {
+    fn root(&self) -> AbortAlgorithmRoot {
+        match *self {
+            AbortAlgorithm::RemoveEventListener(ref target, ref ty, ref listener, capture) => {
+                AbortAlgorithmRoot::RemoveEventListener(
+                    DomRoot::from_ref(&**target),
+                    ty.clone(),
+                    listener.clone(),
+                    capture,
+                )
+            },
+            AbortAlgorithm::Follow(ref signal) => {
+                AbortAlgorithmRoot::Follow(DomRoot::from_ref(&**signal))
+            },
+            AbortAlgorithm::Fetch(ref canceller, ref promise, ref response) => {
+                AbortAlgorithmRoot::Fetch(
+                    canceller.clone(),
+                    promise.clone(),
+                    DomRoot::from_ref(&**response),
+                )
+            },
+            AbortAlgorithm::XMLHttpRequest(ref xhr) => {
+                AbortAlgorithmRoot::XMLHttpRequest(DomRoot::from_ref(&**xhr))
+            },
+        }
+    }
+}
+
+impl AbortAlgorithmRoot {
+    fn run(self) {
+        match self {
+            AbortAlgorithmRoot::RemoveEventListener(target, ty, listener, capture) => {
+                target.remove_event_listener(ty, Some(listener), EventListenerOptions { capture });
+            },
+            AbortAlgorithmRoot::Follow(signal) => signal.signal_abort(),
+            // https://fetch.spec.whatwg.org/#abort-fetch
+            AbortAlgorithmRoot::Fetch(canceller, promise, response) => {
+                // The fetch may already be done, in which case nobody listens
+                // for the cancellation any more.
+                let _ = canceller.send(());
+                promise.reject_error(Error::Abort);
+                response.abort();
+            },
+            AbortAlgorithmRoot::XMLHttpRequest(xhr) => xhr.Abort(),
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-AbortSignal
+#[dom_struct]
+pub struct AbortSignal {
+    eventtarget: EventTarget,
+    aborted: Cell<bool>,
+    algorithms: DomRefCell<Vec<AbortAlgorithm>>,
+}
+
+impl AbortSignal {
+    fn new_inherited() -> AbortSignal {
+        AbortSignal {
+            eventtarget: EventTarget::new_inherited(),
+            aborted: Cell::new(false),
+            algorithms: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<AbortSignal> {
+        reflect_dom_object(
+            Box::new(AbortSignal::new_inherited()),
+            global,
+            AbortSignalBinding::Wrap,
+        )
+    }
+
+    pub fn aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-add>
+    #[allow(unrooted_must_root)]
+    pub fn add_algorithm(&self, algorithm: AbortAlgorithm) {
+        if self.aborted.get() {
+            return;
+        }
+        self.algorithms.borrow_mut().push(algorithm);
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-signal-abort>
+    pub fn signal_abort(&self) {
+        // Steps 1-2.
+        if self.aborted.replace(true) {
+            return;
+        }
+        // Steps 3-4. The algorithms are rooted up front, since running one may
+        // remove others from the list.
+        let algorithms: Vec<AbortAlgorithmRoot> = self
+            .algorithms
+            .borrow()
+            .iter()
+            .map(AbortAlgorithm::root)
+            .collect();
+        self.algorithms.borrow_mut().clear();
+        for algorithm in algorithms {
+            algorithm.run();
+        }
+        // Step 5.
+        self.upcast::<EventTarget>().fire_event(atom!("abort"));
+    }
+
+    /// Remove the algorithm that aborts the fetch of `response`, once the fetch
+    /// is finished.
+    pub fn remove_fetch_algorithm(&self, response: &Response) {
+        self.remove_algorithms(|algorithm| match *algorithm {
+            AbortAlgorithm::Fetch(_, _, ref fetched) => *fetched == response,
+            _ => false,
+        });
+    }
+
+    /// Remove the algorithm that aborts the ongoing request of `xhr`, once the
+    /// request is finished.
+    pub fn remove_xhr_algorithm(&self, xhr: &XMLHttpRequest) {
+        self.remove_algorithms(|algorithm| match *algorithm {
+            AbortAlgorithm::XMLHttpRequest(ref other) => *other == xhr,
+            _ => false,
+        });
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-remove>
+    #[allow(unrooted_must_root)]
+    fn remove_algorithms<F: Fn(&AbortAlgorithm) -> bool>(&self, matches: F) {
+        self.algorithms
+            .borrow_mut()
+            .retain(|algorithm| !matches(algorithm));
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-follow>
+    #[allow(unrooted_must_root)]
+    pub fn follow(&self, parent: &AbortSignal) {
+        if self.aborted.get() {
+            return;
+        }
+        if parent.aborted.get() {
+            return self.signal_abort();
+        }
+        parent.add_algorithm(AbortAlgorithm::Follow(Dom::from_ref(self)));
+    }
+}
+
+impl AbortSignalMethods for AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-aborted
+    fn Aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+}
@@ -344,6 +344,15 @@ impl DOMString {
         parse_floating_point_number(&self.0).is_ok()
     }
 
+    /// The number represented by a valid floating-point number string, if
+    /// this is one. Unlike the best representation, it isn't rounded.
+    pub fn parse_floating_point_number(&self) -> Option<f64> {
+        if !self.is_valid_floating_point_number_string() {
+            return None;
+        }
+        self.0.trim().parse().ok()
+    }
+
     /// https://html.spec.whatwg.org/multipage/#best-representation-of-the-number-as-a-floating-point-number
     pub fn set_best_representation_of_the_floating_point_number(&mut self) {
         if let Ok(val) = parse_floating_point_number(&self.0) {
@@ -12,6 +12,8 @@ use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::DomRoot;
 use crate::dom::blob::{Blob, BlobImpl};
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::messageport::MessagePort;
+use js::conversions::ToJSValConvertible;
 use js::glue::CopyJSStructuredCloneData;
 use js::glue::DeleteJSAutoStructuredCloneBuffer;
 use js::glue::GetLengthOfJSStructuredCloneData;
@@ -28,9 +30,12 @@ use js::jsapi::{JSObject, JS_ClearPendingException};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
 use js::jsapi::{JS_ReadBytes, JS_WriteBytes};
 use js::jsapi::{JS_ReadUint32Pair, JS_WriteUint32Pair};
+use js::jsval::UndefinedValue;
 use js::rust::wrappers::{JS_ReadStructuredClone, JS_WriteStructuredClone};
-use js::rust::{Handle, HandleValue, MutableHandleValue};
+use js::rust::{CustomAutoRooterGuard, Handle, HandleValue, MutableHandleValue};
+use js::rust::{MutableHandle, MutableHandleObject};
 use libc::size_t;
+use script_traits::{StructuredSerializedData, TransferredMessagePort};
 use std::os::raw;
 use std::ptr;
 use std::slice;
@@ -44,6 +49,7 @@ enum StructuredCloneTags {
     /// To support additional types, add new tags with values incremented from the last one before Max.
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
+    MessagePort = 0xFFFF8002,
     Max = 0xFFFFFFFF,
 }
 
@@ -189,27 +195,57 @@ unsafe extern "C" fn write_callback(
 }
 
 unsafe extern "C" fn read_transfer_callback(
-    _cx: *mut JSContext,
+    cx: *mut JSContext,
     _r: *mut JSStructuredCloneReader,
-    _tag: u32,
+    tag: u32,
     _content: *mut raw::c_void,
-    _extra_data: u64,
-    _closure: *mut raw::c_void,
-    _return_object: RawMutableHandleObject,
+    extra_data: u64,
+    closure: *mut raw::c_void,
+    return_object: RawMutableHandleObject,
 ) -> bool {
-    false
+    if tag != StructuredCloneTags::MessagePort as u32 {
+        return false;
+    }
+    let sc_holder = &mut *(closure as *mut StructuredCloneHolder);
+    // The extra data is the index of the port in the transfer list.
+    let transferred_port = match sc_holder
+        .transferred_ports
+        .get_mut(extra_data as usize)
+        .and_then(Option::take)
+    {
+        Some(transferred_port) => transferred_port,
+        None => return false,
+    };
+    let target_global = GlobalScope::from_context(cx);
+    let port = MessagePort::new_transferred(&target_global, transferred_port);
+    let return_object: MutableHandleObject = MutableHandle::from_raw(return_object);
+    return_object.set(port.reflector().get_jsobject().get());
+    sc_holder.message_ports.push(port);
+    true
 }
 
 unsafe extern "C" fn write_transfer_callback(
-    _cx: *mut JSContext,
-    _obj: RawHandleObject,
-    _closure: *mut raw::c_void,
-    _tag: *mut u32,
-    _ownership: *mut TransferableOwnership,
+    cx: *mut JSContext,
+    obj: RawHandleObject,
+    closure: *mut raw::c_void,
+    tag: *mut u32,
+    ownership: *mut TransferableOwnership,
     _content: *mut *mut raw::c_void,
-    _extra_data: *mut u64,
+    extra_data: *mut u64,
 ) -> bool {
-    false
+    let port = match root_from_handleobject::<MessagePort>(Handle::from_raw(obj), cx) {
+        Ok(port) => port,
+        Err(()) => return false,
+    };
+    if port.is_detached() {
+        return false;
+    }
+    let sc_holder = &mut *(closure as *mut StructuredCloneHolder);
+    *tag = StructuredCloneTags::MessagePort as u32;
+    *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+    *extra_data = sc_holder.ports_to_transfer.len() as u64;
+    sc_holder.ports_to_transfer.push(port);
+    true
 }
 
 unsafe extern "C" fn free_transfer_callback(
@@ -222,11 +258,11 @@ unsafe extern "C" fn free_transfer_callback(
 }
 
 unsafe extern "C" fn can_transfer_callback(
-    _cx: *mut JSContext,
-    _obj: RawHandleObject,
+    cx: *mut JSContext,
+    obj: RawHandleObject,
     _closure: *mut raw::c_void,
 ) -> bool {
-    false
+    root_from_handleobject::<MessagePort>(Handle::from_raw(obj), cx).is_ok()
 }
 
 unsafe extern "C" fn report_error_callback(_cx: *mut JSContext, _errorid: u32) {}
@@ -241,8 +277,15 @@ static STRUCTURED_CLONE_CALLBACKS: JSStructuredCloneCallbacks = JSStructuredClon
     canTransfer: Some(can_transfer_callback),
 };
 
+#[derive(Default)]
 struct StructuredCloneHolder {
     blob: Option<DomRoot<Blob>>,
+    /// The message ports in the transfer list of a write.
+    ports_to_transfer: Vec<DomRoot<MessagePort>>,
+    /// The message ports to be revived by a read, in transfer list order.
+    transferred_ports: Vec<Option<TransferredMessagePort>>,
+    /// The message ports revived by a read.
+    message_ports: Vec<DomRoot<MessagePort>>,
 }
 
 /// A buffer for a structured clone.
@@ -257,6 +300,46 @@ impl StructuredCloneData {
     // TODO: should this be unsafe?
     /// Writes a structured clone. Returns a `DataClone` error if that fails.
     pub fn write(cx: *mut JSContext, message: HandleValue) -> Fallible<StructuredCloneData> {
+        let mut sc_holder = StructuredCloneHolder::default();
+        let data = StructuredCloneData::write_clone(
+            cx,
+            message,
+            HandleValue::undefined(),
+            &mut sc_holder,
+        )?;
+        Ok(StructuredCloneData::Vector(data))
+    }
+
+    /// Writes a structured clone, transferring the objects in `transfer` along with it.
+    /// Returns a `DataClone` error if that fails.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+    pub fn write_with_transfer(
+        cx: *mut JSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> Fallible<StructuredSerializedData> {
+        rooted!(in(cx) let mut transfer_list = UndefinedValue());
+        unsafe { transfer.to_jsval(cx, transfer_list.handle_mut()) };
+        let mut sc_holder = StructuredCloneHolder::default();
+        let serialized =
+            StructuredCloneData::write_clone(cx, message, transfer_list.handle(), &mut sc_holder)?;
+        // Only detach the ports once the whole message was serialized.
+        let ports = sc_holder
+            .ports_to_transfer
+            .iter()
+            .map(|port| port.transfer())
+            .collect();
+        Ok(StructuredSerializedData { serialized, ports })
+    }
+
+    fn write_clone(
+        cx: *mut JSContext,
+        message: HandleValue,
+        transfer: HandleValue,
+        sc_holder: &mut StructuredCloneHolder,
+    ) -> Fallible<Vec<u8>> {
+        let sc_holder_ptr = sc_holder as *mut _;
         unsafe {
             let scbuf = NewJSAutoStructuredCloneBuffer(
                 StructuredCloneScope::DifferentProcess,
@@ -274,8 +357,8 @@ impl StructuredCloneData {
                 StructuredCloneScope::DifferentProcess,
                 policy,
                 &STRUCTURED_CLONE_CALLBACKS,
-                ptr::null_mut(),
-                HandleValue::undefined(),
+                sc_holder_ptr as *mut raw::c_void,
+                transfer,
             );
             if !result {
                 JS_ClearPendingException(cx);
@@ -289,7 +372,7 @@ impl StructuredCloneData {
 
             DeleteJSAutoStructuredCloneBuffer(scbuf);
 
-            Ok(StructuredCloneData::Vector(data))
+            Ok(data)
         }
     }
 
@@ -306,11 +389,16 @@ impl StructuredCloneData {
     /// Reads a structured clone.
     ///
     /// Panics if `JS_ReadStructuredClone` fails.
-    fn read_clone(global: &GlobalScope, data: *mut u64, nbytes: size_t, rval: MutableHandleValue) {
+    fn read_clone(
+        global: &GlobalScope,
+        data: *mut u64,
+        nbytes: size_t,
+        rval: MutableHandleValue,
+        sc_holder: &mut StructuredCloneHolder,
+    ) {
         let cx = global.get_cx();
         let _ac = enter_realm(&*global);
-        let mut sc_holder = StructuredCloneHolder { blob: None };
-        let sc_holder_ptr = &mut sc_holder as *mut _;
+        let sc_holder_ptr = sc_holder as *mut _;
         unsafe {
             let scbuf = NewJSAutoStructuredCloneBuffer(
                 StructuredCloneScope::DifferentProcess,
@@ -336,17 +424,41 @@ impl StructuredCloneData {
 
     /// Thunk for the actual `read_clone` method. Resolves proper variant for read_clone.
     pub fn read(self, global: &GlobalScope, rval: MutableHandleValue) {
+        let mut sc_holder = StructuredCloneHolder::default();
         match self {
             StructuredCloneData::Vector(mut vec_msg) => {
                 let nbytes = vec_msg.len();
                 let data = vec_msg.as_mut_ptr() as *mut u64;
-                StructuredCloneData::read_clone(global, data, nbytes, rval);
+                StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder);
             },
             StructuredCloneData::Struct(data, nbytes) => {
-                StructuredCloneData::read_clone(global, data, nbytes, rval)
+                StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder)
             },
         }
     }
+
+    /// Reads a structured clone, reviving the message ports transferred with it
+    /// in `global`. Returns the revived ports, in transfer list order.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structureddeserializewithtransfer>
+    pub fn read_with_transfer(
+        global: &GlobalScope,
+        data: StructuredSerializedData,
+        rval: MutableHandleValue,
+    ) -> Vec<DomRoot<MessagePort>> {
+        let StructuredSerializedData {
+            mut serialized,
+            ports,
+        } = data;
+        let mut sc_holder = StructuredCloneHolder {
+            transferred_ports: ports.into_iter().map(Some).collect(),
+            ..Default::default()
+        };
+        let nbytes = serialized.len();
+        let data = serialized.as_mut_ptr() as *mut u64;
+        StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder);
+        sc_holder.message_ports
+    }
 }
 
 unsafe impl Send for StructuredCloneData {}
@@ -74,12 +74,16 @@ use js::typedarray::TypedArrayElement;
 use media::WindowGLContext;
 use metrics::{InteractiveMetrics, InteractiveWindow};
 use mime::Mime;
-use msg::constellation_msg::{
-    BrowsingContextId, HistoryStateId, PipelineId, TopLevelBrowsingContextId,
-};
+use msg::constellation_msg::TopLevelBrowsingContextId;
+use msg::constellation_msg::{BroadcastChannelRouterId, BrowsingContextId, HistoryStateId};
+use msg::constellation_msg::{MessagePortId, MessagePortRouterId, PipelineId};
+use net_traits::content_security_policy::CspList;
 use net_traits::filemanager_thread::RelativePos;
 use net_traits::image::base::{Image, ImageMetadata};
 use net_traits::image_cache::{ImageCache, PendingImageId};
+use net_traits::indexeddb_thread::IndexedDBOperation;
+use net_traits::indexeddb_thread::{IndexMetadata, IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreMetadata};
 use net_traits::request::{Request, RequestBuilder};
 use net_traits::response::HttpsState;
 use net_traits::response::{Response, ResponseBody};
@@ -91,6 +95,7 @@ use script_layout_interface::rpc::LayoutRPC;
 use script_layout_interface::OpaqueStyleAndLayoutData;
 use script_traits::DrawAPaintImageResult;
 use script_traits::{DocumentActivity, ScriptToConstellationChan, TimerEventId, TimerSource};
+use script_traits::{StructuredSerializedData, TransferredMessagePort};
 use script_traits::{UntrustedNodeAddress, WindowSizeData, WindowSizeType};
 use selectors::matching::ElementSelectorFlags;
 use serde::{Deserialize, Serialize};
@@ -418,12 +423,16 @@ unsafe_no_jsmanaged_fields!(PropertyDeclarationBlock);
 // in one of these make sure it is propagated properly to containing structs
 unsafe_no_jsmanaged_fields!(DocumentActivity, WindowSizeData, WindowSizeType);
 unsafe_no_jsmanaged_fields!(
+    BroadcastChannelRouterId,
     BrowsingContextId,
     HistoryStateId,
+    MessagePortId,
+    MessagePortRouterId,
     PipelineId,
     TopLevelBrowsingContextId
 );
 unsafe_no_jsmanaged_fields!(TimerEventId, TimerSource);
+unsafe_no_jsmanaged_fields!(StructuredSerializedData, TransferredMessagePort);
 unsafe_no_jsmanaged_fields!(TimelineMarkerType);
 unsafe_no_jsmanaged_fields!(WorkerId);
 unsafe_no_jsmanaged_fields!(BufferQueue, QuirksMode, StrTendril);
@@ -452,6 +461,7 @@ unsafe_no_jsmanaged_fields!(Snapshot);
 unsafe_no_jsmanaged_fields!(PendingRestyle);
 unsafe_no_jsmanaged_fields!(Stylesheet);
 unsafe_no_jsmanaged_fields!(HttpsState);
+unsafe_no_jsmanaged_fields!(CspList);
 unsafe_no_jsmanaged_fields!(Request);
 unsafe_no_jsmanaged_fields!(RequestBuilder);
 unsafe_no_jsmanaged_fields!(StyleSharedRwLock);
@@ -460,6 +470,8 @@ unsafe_no_jsmanaged_fields!(ReferrerPolicy);
 unsafe_no_jsmanaged_fields!(Response);
 unsafe_no_jsmanaged_fields!(ResponseBody);
 unsafe_no_jsmanaged_fields!(ResourceThreads);
+unsafe_no_jsmanaged_fields!(IndexedDBKeyRange, IndexedDBKeyType, KeyPath);
+unsafe_no_jsmanaged_fields!(IndexMetadata, IndexedDBOperation, ObjectStoreMetadata);
 unsafe_no_jsmanaged_fields!(StatusCode);
 unsafe_no_jsmanaged_fields!(SystemTime);
 unsafe_no_jsmanaged_fields!(Instant);
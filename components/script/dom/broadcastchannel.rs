@@ -0,0 +1,123 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::compartments::enter_realm;
+use crate::dom::bindings::codegen::Bindings::BroadcastChannelBinding;
+use crate::dom::bindings::codegen::Bindings::BroadcastChannelBinding::BroadcastChannelMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::messageevent::MessageEvent;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::cell::Cell;
+
+// https://html.spec.whatwg.org/multipage/#broadcastchannel
+#[dom_struct]
+pub struct BroadcastChannel {
+    eventtarget: EventTarget,
+    name: DOMString,
+    closed: Cell<bool>,
+}
+
+impl BroadcastChannel {
+    fn new_inherited(name: DOMString) -> BroadcastChannel {
+        BroadcastChannel {
+            eventtarget: EventTarget::new_inherited(),
+            name,
+            closed: Cell::new(false),
+        }
+    }
+
+    fn new(global: &GlobalScope, name: DOMString) -> DomRoot<BroadcastChannel> {
+        let channel = reflect_dom_object(
+            Box::new(BroadcastChannel::new_inherited(name)),
+            global,
+            BroadcastChannelBinding::Wrap,
+        );
+        global.track_broadcast_channel(&channel);
+        channel
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel
+    pub fn Constructor(
+        global: &GlobalScope,
+        name: DOMString,
+    ) -> Fallible<DomRoot<BroadcastChannel>> {
+        Ok(BroadcastChannel::new(global, name))
+    }
+
+    pub fn name(&self) -> &DOMString {
+        &self.name
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-broadcastchannel-closed>
+    pub fn closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Fire a message event for a message broadcast to this channel.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage> step 10.
+    pub fn dispatch_message(&self, origin: &str, data: Vec<u8>) {
+        // Step 10.1.
+        if self.closed.get() {
+            return;
+        }
+        // Steps 10.2-10.6.
+        let global = self.global();
+        let _ac = enter_realm(self);
+        rooted!(in(*global.get_cx()) let mut message = UndefinedValue());
+        StructuredCloneData::Vector(data).read(&global, message.handle_mut());
+        MessageEvent::dispatch_jsval(
+            self.upcast(),
+            &global,
+            message.handle(),
+            Some(origin),
+            None,
+            vec![],
+        );
+    }
+}
+
+impl BroadcastChannelMethods for BroadcastChannel {
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage
+    fn PostMessage(&self, cx: JSContext, message: HandleValue) -> ErrorResult {
+        // Step 3.
+        if self.closed.get() {
+            return Err(Error::InvalidState);
+        }
+        // Steps 4-5.
+        let data = StructuredCloneData::write(*cx, message)?.move_to_arraybuffer();
+        // Steps 6-10.
+        self.global().schedule_broadcast(self, data);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-close
+    fn Close(&self) {
+        if self.closed.replace(true) {
+            return;
+        }
+        self.global().untrack_broadcast_channel(self);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-broadcastchannel-onmessage
+    event_handler!(message, GetOnmessage, SetOnmessage);
+
+    // https://html.spec.whatwg.org/multipage/#handler-broadcastchannel-onmessageerror
+    event_handler!(messageerror, GetOnmessageerror, SetOnmessageerror);
+}
@@ -0,0 +1,216 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::clipboard_provider::ClipboardProvider;
+use crate::dom::bindings::codegen::Bindings::ClipboardBinding;
+use crate::dom::bindings::codegen::Bindings::ClipboardBinding::ClipboardMethods;
+use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use crate::dom::bindings::codegen::Bindings::PermissionStatusBinding::{
+    PermissionName, PermissionState,
+};
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::refcounted::TrustedPromise;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::blob::{Blob, BlobImpl};
+use crate::dom::clipboarditem::ClipboardItem;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::permissions::request_permission_to_use;
+use crate::dom::promise::Promise;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use embedder_traits::{ClipboardContents, EmbedderMsg};
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use script_traits::ScriptMsg;
+use std::rc::Rc;
+
+// https://w3c.github.io/clipboard-apis/#clipboard-interface
+#[dom_struct]
+pub struct Clipboard {
+    eventtarget: EventTarget,
+}
+
+impl Clipboard {
+    fn new_inherited() -> Clipboard {
+        Clipboard {
+            eventtarget: EventTarget::new_inherited(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<Clipboard> {
+        reflect_dom_object(
+            Box::new(Clipboard::new_inherited()),
+            global,
+            ClipboardBinding::Wrap,
+        )
+    }
+
+    /// Whether the page is allowed to read from or write to the clipboard.
+    ///
+    /// <https://w3c.github.io/clipboard-apis/#check-clipboard-read-permission>
+    /// <https://w3c.github.io/clipboard-apis/#check-clipboard-write-permission>
+    fn is_allowed(&self, permission_name: PermissionName) -> bool {
+        let global = self.global();
+        // Only the focused document can access the clipboard.
+        if !global.as_window().Document().HasFocus() {
+            return false;
+        }
+        // Writing is allowed right after the user interacted with the page.
+        if permission_name == PermissionName::Clipboard_write &&
+            global.as_window().has_transient_activation()
+        {
+            return true;
+        }
+        match request_permission_to_use(permission_name, &global) {
+            PermissionState::Granted => true,
+            _ => false,
+        }
+    }
+
+    /// Ask the embedder for the contents of the system clipboard, and settle the
+    /// promise with them in a task once they arrive, instead of blocking the
+    /// script thread in the meantime.
+    #[allow(unrooted_must_root)]
+    fn read_clipboard_contents<F>(&self, promise: &Rc<Promise>, settle: F)
+    where
+        F: FnOnce(&Promise, ClipboardContents) + Send + 'static,
+    {
+        let global = self.global();
+        let (task_source, canceller) = global
+            .as_window()
+            .task_manager()
+            .user_interaction_task_source_with_canceller();
+        let (sender, receiver) = ipc::channel().unwrap();
+        let mut pending = Some((TrustedPromise::new(promise.clone()), settle));
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let (promise, settle) = match pending.take() {
+                    Some(pending) => pending,
+                    None => return,
+                };
+                let contents = message.to().unwrap_or_default();
+                let _ = task_source.queue_with_canceller(
+                    task!(settle_clipboard_read: move || {
+                        settle(&promise.root(), contents);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let _ = global
+            .script_to_constellation_chan()
+            .send(ScriptMsg::ForwardToEmbedder(
+                EmbedderMsg::GetClipboardContents(sender),
+            ));
+    }
+
+    fn set_clipboard_contents(&self, contents: ClipboardContents) {
+        self.global()
+            .script_to_constellation_chan()
+            .clone()
+            .set_clipboard_data(contents);
+    }
+}
+
+impl ClipboardMethods for Clipboard {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-read
+    fn Read(&self) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+        // Steps 2.1-2.2.
+        if !self.is_allowed(PermissionName::Clipboard_read) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // Steps 2.3-2.5.
+        self.read_clipboard_contents(&promise, |promise, contents| {
+            let global = promise.global();
+            let mut items = vec![];
+            if !contents.items.is_empty() {
+                let representations = contents
+                    .items
+                    .into_iter()
+                    .map(|(type_, data)| {
+                        let blob =
+                            Blob::new(&global, BlobImpl::new_from_bytes(data), type_.clone());
+                        (DOMString::from(type_), blob)
+                    })
+                    .collect();
+                items.push(ClipboardItem::new(global.as_window(), representations));
+            }
+            promise.resolve_native(&items);
+        });
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-readtext
+    fn ReadText(&self) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+        // Steps 2.1-2.2.
+        if !self.is_allowed(PermissionName::Clipboard_read) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // Steps 2.3-2.4.
+        self.read_clipboard_contents(&promise, |promise, contents| {
+            let text = contents.text().unwrap_or_default();
+            promise.resolve_native(&DOMString::from(text));
+        });
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-write
+    fn Write(&self, data: Vec<DomRoot<ClipboardItem>>) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+        // Steps 2.1-2.2.
+        if !self.is_allowed(PermissionName::Clipboard_write) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // FIXME: The clipboard of the embedder holds a single item, so writing
+        // several items at once is not supported, and rejects as it does in
+        // other engines rather than dropping all but one of them.
+        if data.len() > 1 {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // Steps 2.3-2.4.
+        let mut items = vec![];
+        for (type_, blob) in data.iter().flat_map(|item| item.representations()) {
+            match blob.get_bytes() {
+                Ok(bytes) => items.push((String::from(type_), bytes)),
+                Err(()) => {
+                    promise.reject_error(Error::NotReadable);
+                    return promise;
+                },
+            }
+        }
+        // Steps 2.5-2.6.
+        self.set_clipboard_contents(ClipboardContents { items });
+        promise.resolve_native(&());
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-writetext
+    fn WriteText(&self, data: DOMString) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+        // Steps 2.1-2.2.
+        if !self.is_allowed(PermissionName::Clipboard_write) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // Steps 2.3-2.5.
+        self.set_clipboard_contents(ClipboardContents::from_text(String::from(data)));
+        promise.resolve_native(&());
+        promise
+    }
+}
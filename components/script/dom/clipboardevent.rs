@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ClipboardEventBinding;
+use crate::dom::bindings::codegen::Bindings::ClipboardEventBinding::ClipboardEventMethods;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::datatransfer::DataTransfer;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::window::Window;
+use crate::textinput::CMD_OR_CONTROL;
+use dom_struct::dom_struct;
+use keyboard_types::{Key, Modifiers};
+use servo_atoms::Atom;
+
+/// The types of the events fired for the clipboard actions.
+///
+/// <https://w3c.github.io/clipboard-apis/#clipboard-event-copy>
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClipboardEventType {
+    Copy,
+    Cut,
+    Paste,
+}
+
+impl ClipboardEventType {
+    pub fn as_atom(&self) -> Atom {
+        match *self {
+            ClipboardEventType::Copy => atom!("copy"),
+            ClipboardEventType::Cut => atom!("cut"),
+            ClipboardEventType::Paste => atom!("paste"),
+        }
+    }
+
+    /// The clipboard action the given key combination is the shortcut of, if any.
+    pub fn for_shortcut(key: &Key, modifiers: Modifiers) -> Option<ClipboardEventType> {
+        if !modifiers.contains(CMD_OR_CONTROL) {
+            return None;
+        }
+        match *key {
+            Key::Character(ref letter) if letter.eq_ignore_ascii_case("c") => {
+                Some(ClipboardEventType::Copy)
+            },
+            Key::Character(ref letter) if letter.eq_ignore_ascii_case("x") => {
+                Some(ClipboardEventType::Cut)
+            },
+            Key::Character(ref letter) if letter.eq_ignore_ascii_case("v") => {
+                Some(ClipboardEventType::Paste)
+            },
+            _ => None,
+        }
+    }
+}
+
+#[dom_struct]
+pub struct ClipboardEvent {
+    event: Event,
+    clipboard_data: MutNullableDom<DataTransfer>,
+}
+
+impl ClipboardEvent {
+    fn new_inherited() -> ClipboardEvent {
+        ClipboardEvent {
+            event: Event::new_inherited(),
+            clipboard_data: Default::default(),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        type_: Atom,
+        bubbles: EventBubbles,
+        cancelable: EventCancelable,
+        clipboard_data: Option<&DataTransfer>,
+    ) -> DomRoot<ClipboardEvent> {
+        let ev = reflect_dom_object(
+            Box::new(ClipboardEvent::new_inherited()),
+            window,
+            ClipboardEventBinding::Wrap,
+        );
+        ev.upcast::<Event>()
+            .init_event(type_, bool::from(bubbles), bool::from(cancelable));
+        ev.clipboard_data.set(clipboard_data);
+        ev
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &ClipboardEventBinding::ClipboardEventInit,
+    ) -> Fallible<DomRoot<ClipboardEvent>> {
+        let bubbles = EventBubbles::from(init.parent.bubbles);
+        let cancelable = EventCancelable::from(init.parent.cancelable);
+        Ok(ClipboardEvent::new(
+            window,
+            Atom::from(type_),
+            bubbles,
+            cancelable,
+            init.clipboardData.as_ref().map(|data| &**data),
+        ))
+    }
+}
+
+impl ClipboardEventMethods for ClipboardEvent {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboardevent-clipboarddata
+    fn GetClipboardData(&self) -> Option<DomRoot<DataTransfer>> {
+        self.clipboard_data.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
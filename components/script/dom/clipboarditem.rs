@@ -0,0 +1,120 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ClipboardItemBinding;
+use crate::dom::bindings::codegen::Bindings::ClipboardItemBinding::ClipboardItemMethods;
+use crate::dom::bindings::codegen::UnionTypes::StringOrBlob;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::mozmap::MozMap;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::blob::{Blob, BlobImpl};
+use crate::dom::promise::Promise;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsval::{JSVal, UndefinedValue};
+use std::rc::Rc;
+
+// https://w3c.github.io/clipboard-apis/#clipboard-item-interface
+#[dom_struct]
+pub struct ClipboardItem {
+    reflector_: Reflector,
+    /// The data of this item in each of its formats, with their MIME types.
+    representations: Vec<(DOMString, Dom<Blob>)>,
+}
+
+impl ClipboardItem {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(representations: Vec<(DOMString, DomRoot<Blob>)>) -> ClipboardItem {
+        ClipboardItem {
+            reflector_: Reflector::new(),
+            representations: representations
+                .into_iter()
+                .map(|(type_, blob)| (type_, Dom::from_ref(&*blob)))
+                .collect(),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        representations: Vec<(DOMString, DomRoot<Blob>)>,
+    ) -> DomRoot<ClipboardItem> {
+        reflect_dom_object(
+            Box::new(ClipboardItem::new_inherited(representations)),
+            window,
+            ClipboardItemBinding::Wrap,
+        )
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-clipboarditem
+    pub fn Constructor(
+        window: &Window,
+        items: MozMap<StringOrBlob>,
+    ) -> Fallible<DomRoot<ClipboardItem>> {
+        // Step 1.
+        if items.is_empty() {
+            return Err(Error::Type("A clipboard item needs some data".to_owned()));
+        }
+        // Steps 2-3.
+        let representations = items
+            .iter()
+            .map(|(type_, data)| {
+                let type_ = DOMString::from(type_.to_ascii_lowercase());
+                let blob = match *data {
+                    StringOrBlob::String(ref text) => Blob::new(
+                        window.upcast(),
+                        BlobImpl::new_from_bytes(String::from(text.clone()).into_bytes()),
+                        String::from(type_.clone()),
+                    ),
+                    StringOrBlob::Blob(ref blob) => DomRoot::from_ref(&**blob),
+                };
+                (type_, blob)
+            })
+            .collect();
+        Ok(ClipboardItem::new(window, representations))
+    }
+
+    pub fn representations(&self) -> Vec<(DOMString, DomRoot<Blob>)> {
+        self.representations
+            .iter()
+            .map(|&(ref type_, ref blob)| (type_.clone(), DomRoot::from_ref(&**blob)))
+            .collect()
+    }
+}
+
+impl ClipboardItemMethods for ClipboardItem {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-types
+    #[allow(unsafe_code)]
+    fn Types(&self, cx: JSContext) -> JSVal {
+        let types: Vec<DOMString> = self
+            .representations
+            .iter()
+            .map(|&(ref type_, _)| type_.clone())
+            .collect();
+        unsafe {
+            rooted!(in(*cx) let mut types_value = UndefinedValue());
+            types.to_jsval(*cx, types_value.handle_mut());
+            types_value.get()
+        }
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-gettype
+    fn GetType(&self, type_: DOMString) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let type_ = type_.to_ascii_lowercase();
+        match self
+            .representations
+            .iter()
+            .find(|&&(ref representation_type, _)| **representation_type == *type_)
+        {
+            Some(&(_, ref blob)) => promise.resolve_native(&DomRoot::from_ref(&**blob)),
+            None => promise.reject_error(Error::NotFound),
+        }
+        promise
+    }
+}
@@ -0,0 +1,352 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DataTransferBinding;
+use crate::dom::bindings::codegen::Bindings::DataTransferBinding::DataTransferMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::blob::{Blob, BlobImpl};
+use crate::dom::element::Element;
+use crate::dom::file::File;
+use crate::dom::filelist::FileList;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use embedder_traits::{ClipboardContents, DragData};
+use js::conversions::ToJSValConvertible;
+use js::jsval::{JSVal, UndefinedValue};
+use mime_guess::guess_mime_type;
+use std::cell::Cell;
+
+/// <https://html.spec.whatwg.org/multipage/#drag-data-store-mode>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum DataTransferMode {
+    ReadWrite,
+    ReadOnly,
+    Protected,
+}
+
+/// An item of the drag data store item list.
+///
+/// <https://html.spec.whatwg.org/multipage/#drag-data-store-item-list>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum DataStoreItem {
+    /// A Plain Unicode string, with its type.
+    Text(DOMString, DOMString),
+    File(Dom<File>),
+}
+
+// https://html.spec.whatwg.org/multipage/#the-datatransfer-interface
+#[dom_struct]
+pub struct DataTransfer {
+    reflector_: Reflector,
+    mode: Cell<DataTransferMode>,
+    drop_effect: DomRefCell<DOMString>,
+    effect_allowed: DomRefCell<DOMString>,
+    items: DomRefCell<Vec<DataStoreItem>>,
+    /// The element whose rendering is shown under the cursor during a drag,
+    /// and the coordinate of the cursor within it.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-data-store-bitmap>
+    // TODO: Paint the drag image under the cursor.
+    drag_image: MutNullableDom<Element>,
+    drag_image_hot_spot: Cell<(i32, i32)>,
+}
+
+impl DataTransfer {
+    fn new_inherited(mode: DataTransferMode) -> DataTransfer {
+        DataTransfer {
+            reflector_: Reflector::new(),
+            mode: Cell::new(mode),
+            drop_effect: DomRefCell::new(DOMString::from("none")),
+            effect_allowed: DomRefCell::new(DOMString::from("none")),
+            items: DomRefCell::new(vec![]),
+            drag_image: Default::default(),
+            drag_image_hot_spot: Cell::new((0, 0)),
+        }
+    }
+
+    pub fn new(window: &Window, mode: DataTransferMode) -> DomRoot<DataTransfer> {
+        reflect_dom_object(
+            Box::new(DataTransfer::new_inherited(mode)),
+            window,
+            DataTransferBinding::Wrap,
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer
+    pub fn Constructor(window: &Window) -> Fallible<DomRoot<DataTransfer>> {
+        Ok(DataTransfer::new(window, DataTransferMode::ReadWrite))
+    }
+
+    pub fn mode(&self) -> DataTransferMode {
+        self.mode.get()
+    }
+
+    pub fn set_mode(&self, mode: DataTransferMode) {
+        self.mode.set(mode);
+    }
+
+    pub fn set_drop_effect(&self, drop_effect: &str) {
+        *self.drop_effect.borrow_mut() = DOMString::from(drop_effect);
+    }
+
+    pub fn set_effect_allowed(&self, effect_allowed: &str) {
+        *self.effect_allowed.borrow_mut() = DOMString::from(effect_allowed);
+    }
+
+    /// Add the given data of the given MIME type to the data store: as a string
+    /// if it is text, as a file otherwise.
+    pub fn add_data(&self, mime_type: &str, data: Vec<u8>) {
+        let mime_type = mime_type.to_ascii_lowercase();
+        let item = if mime_type.starts_with("text/") {
+            DataStoreItem::Text(
+                DOMString::from(mime_type),
+                DOMString::from(String::from_utf8_lossy(&data).into_owned()),
+            )
+        } else {
+            // Name the file after its type, e.g. "image.png".
+            let name = DOMString::from(mime_type.replace('/', "."));
+            let file = File::new(
+                &self.global(),
+                BlobImpl::new_from_bytes(data),
+                name,
+                None,
+                &mime_type,
+            );
+            DataStoreItem::File(Dom::from_ref(&*file))
+        };
+        self.items.borrow_mut().push(item);
+    }
+
+    /// Add the given data to the data store.
+    pub fn add_clipboard_contents(&self, contents: ClipboardContents) {
+        for (mime_type, data) in contents.items {
+            self.add_data(&mime_type, data);
+        }
+    }
+
+    /// Add the given data dragged from another application to the data store.
+    pub fn add_drag_data(&self, data: DragData) {
+        for (mime_type, text) in data.items {
+            self.add_data(&mime_type, text.into_bytes());
+        }
+        for dragged_file in data.files {
+            let mime_type = guess_mime_type(&dragged_file.name).to_string();
+            let file = File::new(
+                &self.global(),
+                BlobImpl::new_from_bytes(dragged_file.data),
+                DOMString::from(dragged_file.name),
+                None,
+                &mime_type,
+            );
+            self.items
+                .borrow_mut()
+                .push(DataStoreItem::File(Dom::from_ref(&*file)));
+        }
+    }
+
+    /// The data in the data store, in a form that can be put on the system
+    /// clipboard.
+    pub fn clipboard_contents(&self) -> ClipboardContents {
+        let items = self
+            .items
+            .borrow()
+            .iter()
+            .filter_map(|item| match *item {
+                DataStoreItem::Text(ref type_, ref data) => Some((
+                    String::from(type_.clone()),
+                    String::from(data.clone()).into_bytes(),
+                )),
+                DataStoreItem::File(ref file) => {
+                    let blob = file.upcast::<Blob>();
+                    blob.get_bytes()
+                        .ok()
+                        .map(|bytes| (blob.type_string(), bytes))
+                },
+            })
+            .collect();
+        ClipboardContents { items }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.borrow().is_empty()
+    }
+
+    fn files(&self) -> Vec<DomRoot<File>> {
+        self.items
+            .borrow()
+            .iter()
+            .filter_map(|item| match *item {
+                DataStoreItem::File(ref file) => Some(DomRoot::from_ref(&**file)),
+                DataStoreItem::Text(..) => None,
+            })
+            .collect()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-datatransfer-types>
+    fn types(&self) -> Vec<DOMString> {
+        let items = self.items.borrow();
+        let mut types: Vec<DOMString> = items
+            .iter()
+            .filter_map(|item| match *item {
+                DataStoreItem::Text(ref type_, _) => Some(type_.clone()),
+                DataStoreItem::File(_) => None,
+            })
+            .collect();
+        if items.iter().any(|item| match *item {
+            DataStoreItem::File(_) => true,
+            DataStoreItem::Text(..) => false,
+        }) {
+            types.push(DOMString::from("Files"));
+        }
+        types
+    }
+}
+
+/// Normalize the given format as in the steps 2-4 of
+/// <https://html.spec.whatwg.org/multipage/#dom-datatransfer-getdata>, and return
+/// whether it is the "url" format.
+fn normalize_format(format: &str) -> (String, bool) {
+    let format = format.to_ascii_lowercase();
+    match &*format {
+        "text" => ("text/plain".to_owned(), false),
+        "url" => ("text/uri-list".to_owned(), true),
+        _ => (format, false),
+    }
+}
+
+impl DataTransferMethods for DataTransfer {
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-dropeffect
+    fn DropEffect(&self) -> DOMString {
+        self.drop_effect.borrow().clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-dropeffect
+    fn SetDropEffect(&self, value: DOMString) {
+        match &*value {
+            "none" | "copy" | "link" | "move" => *self.drop_effect.borrow_mut() = value.clone(),
+            _ => {},
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-effectallowed
+    fn EffectAllowed(&self) -> DOMString {
+        self.effect_allowed.borrow().clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-effectallowed
+    fn SetEffectAllowed(&self, value: DOMString) {
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+        match &*value {
+            "none" | "copy" | "copyLink" | "copyMove" | "link" | "linkMove" | "move" | "all" |
+            "uninitialized" => *self.effect_allowed.borrow_mut() = value.clone(),
+            _ => {},
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-setdragimage
+    fn SetDragImage(&self, image: &Element, x: i32, y: i32) {
+        // Step 1.
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+        // Steps 2-4.
+        self.drag_image.set(Some(image));
+        self.drag_image_hot_spot.set((x, y));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-types
+    #[allow(unsafe_code)]
+    fn Types(&self, cx: JSContext) -> JSVal {
+        let types = self.types();
+        unsafe {
+            rooted!(in(*cx) let mut types_value = UndefinedValue());
+            types.to_jsval(*cx, types_value.handle_mut());
+            types_value.get()
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-getdata
+    fn GetData(&self, format: DOMString) -> DOMString {
+        // Step 1.
+        if self.mode.get() == DataTransferMode::Protected {
+            return DOMString::new();
+        }
+        // Steps 2-4.
+        let (format, convert_to_url) = normalize_format(&format);
+        // Step 5.
+        let items = self.items.borrow();
+        let data = items.iter().find_map(|item| match *item {
+            DataStoreItem::Text(ref type_, ref data) if **type_ == *format => Some(data),
+            _ => None,
+        });
+        let data = match data {
+            Some(data) => data,
+            None => return DOMString::new(),
+        };
+        // Step 6.
+        if convert_to_url {
+            let url = data
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with('#'))
+                .unwrap_or("");
+            return DOMString::from(url);
+        }
+        // Step 7.
+        data.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-setdata
+    fn SetData(&self, format: DOMString, data: DOMString) {
+        // Step 1.
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+        // Steps 2-3.
+        let (format, _) = normalize_format(&format);
+        let mut items = self.items.borrow_mut();
+        // Step 4.
+        items.retain(|item| match *item {
+            DataStoreItem::Text(ref type_, _) => **type_ != *format,
+            DataStoreItem::File(_) => true,
+        });
+        // Step 5.
+        items.push(DataStoreItem::Text(DOMString::from(format), data));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-cleardata
+    fn ClearData(&self, format: Option<DOMString>) {
+        // Step 1.
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+        let format = format.map(|format| normalize_format(&format).0);
+        // Steps 2-4.
+        self.items.borrow_mut().retain(|item| match *item {
+            DataStoreItem::Text(ref type_, _) => {
+                format.as_ref().map_or(false, |format| **type_ != **format)
+            },
+            DataStoreItem::File(_) => true,
+        });
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-files
+    fn Files(&self) -> DomRoot<FileList> {
+        let files = if self.mode.get() == DataTransferMode::Protected {
+            vec![]
+        } else {
+            self.files()
+        };
+        FileList::new(self.global().as_window(), files)
+    }
+}
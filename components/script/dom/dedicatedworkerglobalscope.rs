@@ -10,6 +10,7 @@ use crate::dom::abstractworkerglobalscope::{SendableWorkerScriptChan, WorkerThre
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding;
 use crate::dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding::DedicatedWorkerGlobalScopeMethods;
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::PostMessageOptions;
 use crate::dom::bindings::codegen::Bindings::WorkerBinding::WorkerType;
 use crate::dom::bindings::error::{ErrorInfo, ErrorResult};
 use crate::dom::bindings::inheritance::Castable;
@@ -17,6 +18,7 @@ use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{DomRoot, RootCollection, ThreadLocalStackRoots};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventtarget::EventTarget;
@@ -37,10 +39,11 @@ use dom_struct::dom_struct;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use js::jsapi::JSContext;
+use js::jsapi::JSObject;
 use js::jsapi::JS_AddInterruptCallback;
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
-use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId};
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard, HandleValue};
+use msg::constellation_msg::{PipelineId, PipelineNamespace, TopLevelBrowsingContextId};
 use net_traits::image_cache::ImageCache;
 use net_traits::request::{CredentialsMode, Destination, ParserMetadata};
 use net_traits::request::{Referrer, RequestBuilder, RequestMode};
@@ -309,6 +312,7 @@ impl DedicatedWorkerGlobalScope {
         let top_level_browsing_context_id = TopLevelBrowsingContextId::installed();
         let current_global = GlobalScope::current().expect("No current global object");
         let origin = current_global.origin().immutable().clone();
+        let site_for_cookies = current_global.site_for_cookies();
         let parent = current_global.runtime_handle();
 
         thread::Builder::new()
@@ -319,6 +323,7 @@ impl DedicatedWorkerGlobalScope {
                 if let Some(top_level_browsing_context_id) = top_level_browsing_context_id {
                     TopLevelBrowsingContextId::install(top_level_browsing_context_id);
                 }
+                PipelineNamespace::install(init.pipeline_namespace_id);
 
                 let roots = RootCollection::new();
                 let _stack_roots = ThreadLocalStackRoots::new(&roots);
@@ -340,7 +345,8 @@ impl DedicatedWorkerGlobalScope {
                     .pipeline_id(pipeline_id)
                     .referrer(referrer)
                     .referrer_policy(referrer_policy)
-                    .origin(origin);
+                    .origin(origin)
+                    .site_for_cookies(Some(site_for_cookies.clone()));
 
                 let runtime = unsafe { new_child_runtime(parent) };
 
@@ -380,6 +386,7 @@ impl DedicatedWorkerGlobalScope {
                 // registration (#6631), so we instead use a random number and cross our fingers.
                 let scope = global.upcast::<WorkerGlobalScope>();
                 let global_scope = global.upcast::<GlobalScope>();
+                global_scope.set_top_level_url(Some(site_for_cookies));
 
                 let (metadata, bytes) = match load_whole_resource(
                     request,
@@ -431,6 +438,9 @@ impl DedicatedWorkerGlobalScope {
                             while !scope.is_closing() {
                                 run_worker_event_loop(&*global, Some(&worker));
                             }
+                            global_scope.remove_message_ports();
+                            global_scope.remove_broadcast_channels();
+                            global_scope.close_indexeddb_connections();
                         },
                         reporter_name,
                         parent_sender,
@@ -467,8 +477,19 @@ impl DedicatedWorkerGlobalScope {
                 let target = self.upcast();
                 let _ac = enter_realm(self);
                 rooted!(in(*scope.get_cx()) let mut message = UndefinedValue());
-                data.read(scope.upcast(), message.handle_mut());
-                MessageEvent::dispatch_jsval(target, scope.upcast(), message.handle(), None, None);
+                let ports = StructuredCloneData::read_with_transfer(
+                    scope.upcast(),
+                    data,
+                    message.handle_mut(),
+                );
+                MessageEvent::dispatch_jsval(
+                    target,
+                    scope.upcast(),
+                    message.handle(),
+                    None,
+                    None,
+                    ports,
+                );
             },
             WorkerScriptMsg::Common(msg) => {
                 self.upcast::<WorkerGlobalScope>().process_event(msg);
@@ -559,10 +580,15 @@ unsafe extern "C" fn interrupt_callback(cx: *mut JSContext) -> bool {
     !worker.is_closing()
 }
 
-impl DedicatedWorkerGlobalScopeMethods for DedicatedWorkerGlobalScope {
+impl DedicatedWorkerGlobalScope {
     // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-postmessage
-    fn PostMessage(&self, cx: SafeJSContext, message: HandleValue) -> ErrorResult {
-        let data = StructuredCloneData::write(*cx, message)?;
+    fn post_message_impl(
+        &self,
+        cx: SafeJSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        let data = StructuredCloneData::write_with_transfer(*cx, message, transfer)?;
         let worker = self.worker.borrow().as_ref().unwrap().clone();
         let pipeline_id = self.upcast::<GlobalScope>().pipeline_id();
         let task = Box::new(task!(post_worker_message: move || {
@@ -579,6 +605,31 @@ impl DedicatedWorkerGlobalScopeMethods for DedicatedWorkerGlobalScope {
             .unwrap();
         Ok(())
     }
+}
+
+impl DedicatedWorkerGlobalScopeMethods for DedicatedWorkerGlobalScope {
+    // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-postmessage
+    fn PostMessage(
+        &self,
+        cx: SafeJSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        self.post_message_impl(cx, message, transfer)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-postmessage
+    fn PostMessage_(
+        &self,
+        cx: SafeJSContext,
+        message: HandleValue,
+        options: RootedTraceableBox<PostMessageOptions>,
+    ) -> ErrorResult {
+        let mut rooted =
+            CustomAutoRooter::new(options.transfer.iter().map(|object| object.get()).collect());
+        let transfer = rooted.root(*cx);
+        self.post_message_impl(cx, message, transfer)
+    }
 
     // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-close
     fn Close(&self) {
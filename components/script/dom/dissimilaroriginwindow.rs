@@ -4,20 +4,23 @@
 
 use crate::dom::bindings::codegen::Bindings::DissimilarOriginWindowBinding;
 use crate::dom::bindings::codegen::Bindings::DissimilarOriginWindowBinding::DissimilarOriginWindowMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowPostMessageOptions;
 use crate::dom::bindings::error::{Error, ErrorResult};
 use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::dissimilaroriginlocation::DissimilarOriginLocation;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::windowproxy::WindowProxy;
 use crate::script_runtime::JSContext;
 use dom_struct::dom_struct;
 use ipc_channel::ipc;
+use js::jsapi::JSObject;
 use js::jsval::{JSVal, UndefinedValue};
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard, HandleValue};
 use msg::constellation_msg::PipelineId;
-use script_traits::ScriptMsg;
+use script_traits::{ScriptMsg, StructuredSerializedData};
 use servo_url::ImmutableOrigin;
 use servo_url::ServoUrl;
 
@@ -134,27 +137,33 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    fn PostMessage(&self, cx: JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
-        // Step 3-5.
-        let origin = match &origin[..] {
-            "*" => None,
-            "/" => {
-                // TODO: Should be the origin of the incumbent settings object.
-                None
-            },
-            url => match ServoUrl::parse(&url) {
-                Ok(url) => Some(url.origin()),
-                Err(_) => return Err(Error::Syntax),
-            },
-        };
-
-        // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(*cx, message)?;
-
-        // Step 9.
-        self.post_message(origin, data);
-        Ok(())
+    fn PostMessage(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        target_origin: DOMString,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        self.post_message_impl(cx, message, &target_origin, transfer)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-window-postmessage-options
+    fn PostMessage_(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        options: RootedTraceableBox<WindowPostMessageOptions>,
+    ) -> ErrorResult {
+        let mut rooted = CustomAutoRooter::new(
+            options
+                .parent
+                .transfer
+                .iter()
+                .map(|object| object.get())
+                .collect(),
+        );
+        let transfer = rooted.root(*cx);
+        self.post_message_impl(cx, message, &options.targetOrigin, transfer)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-opener
@@ -186,7 +195,36 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
 }
 
 impl DissimilarOriginWindow {
-    pub fn post_message(&self, origin: Option<ImmutableOrigin>, data: StructuredCloneData) {
+    // https://html.spec.whatwg.org/multipage/#window-post-message-steps
+    fn post_message_impl(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        target_origin: &str,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        // Step 3-5.
+        let origin = match target_origin {
+            "*" => None,
+            "/" => {
+                // TODO: Should be the origin of the incumbent settings object.
+                None
+            },
+            url => match ServoUrl::parse(&url) {
+                Ok(url) => Some(url.origin()),
+                Err(_) => return Err(Error::Syntax),
+            },
+        };
+
+        // Step 1-2, 6-8.
+        let data = StructuredCloneData::write_with_transfer(*cx, message, transfer)?;
+
+        // Step 9.
+        self.post_message(origin, data);
+        Ok(())
+    }
+
+    pub fn post_message(&self, origin: Option<ImmutableOrigin>, data: StructuredSerializedData) {
         let incumbent = match GlobalScope::incumbent() {
             None => return warn!("postMessage called with no incumbent global"),
             Some(incumbent) => incumbent,
@@ -195,7 +233,7 @@ impl DissimilarOriginWindow {
             target: self.window_proxy.browsing_context_id(),
             source: incumbent.pipeline_id(),
             target_origin: origin,
-            data: data.move_to_arraybuffer(),
+            data,
         };
         let _ = incumbent.script_to_constellation_chan().send(msg);
     }
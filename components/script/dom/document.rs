@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::clipboard_provider::ClipboardProvider;
 use crate::compartments::{AlreadyInCompartment, InCompartment};
 use crate::document_loader::{DocumentLoader, LoadType};
 use crate::dom::activation::{synthetic_click_activation, ActivationSource};
@@ -10,12 +11,14 @@ use crate::dom::beforeunloadevent::BeforeUnloadEvent;
 use crate::dom::bindings::callback::ExceptionHandling;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::BeforeUnloadEventBinding::BeforeUnloadEventBinding::BeforeUnloadEventMethods;
+use crate::dom::bindings::codegen::Bindings::DataTransferBinding::DataTransferMethods;
 use crate::dom::bindings::codegen::Bindings::DocumentBinding;
 use crate::dom::bindings::codegen::Bindings::DocumentBinding::ElementCreationOptions;
 use crate::dom::bindings::codegen::Bindings::DocumentBinding::{
     DocumentMethods, DocumentReadyState,
 };
 use crate::dom::bindings::codegen::Bindings::EventBinding::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
 use crate::dom::bindings::codegen::Bindings::HTMLIFrameElementBinding::HTMLIFrameElementBinding::HTMLIFrameElementMethods;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use crate::dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
@@ -26,7 +29,7 @@ use crate::dom::bindings::codegen::Bindings::WindowBinding::{
     FrameRequestCallback, ScrollBehavior, WindowMethods,
 };
 use crate::dom::bindings::codegen::UnionTypes::NodeOrString;
-use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::error::{Error, ErrorInfo, ErrorResult, Fallible};
 use crate::dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use crate::dom::bindings::num::Finite;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
@@ -38,16 +41,19 @@ use crate::dom::bindings::xmlname::{
     namespace_from_domstring, validate_and_extract, xml_name_type,
 };
 use crate::dom::cdatasection::CDATASection;
+use crate::dom::clipboardevent::{ClipboardEvent, ClipboardEventType};
 use crate::dom::closeevent::CloseEvent;
 use crate::dom::comment::Comment;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::cssstylesheet::CSSStyleSheet;
 use crate::dom::customelementregistry::CustomElementDefinition;
 use crate::dom::customevent::CustomEvent;
+use crate::dom::datatransfer::{DataTransfer, DataTransferMode};
 use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::documentorshadowroot::{DocumentOrShadowRoot, StyleSheetInDocument};
 use crate::dom::documenttype::DocumentType;
 use crate::dom::domimplementation::DOMImplementation;
+use crate::dom::dragevent::DragEvent;
 use crate::dom::element::CustomElementCreationMode;
 use crate::dom::element::{
     Element, ElementCreator, ElementPerformFullscreenEnter, ElementPerformFullscreenExit,
@@ -70,8 +76,11 @@ use crate::dom::htmlheadelement::HTMLHeadElement;
 use crate::dom::htmlhtmlelement::HTMLHtmlElement;
 use crate::dom::htmliframeelement::HTMLIFrameElement;
 use crate::dom::htmlimageelement::HTMLImageElement;
+use crate::dom::htmlinputelement::HTMLInputElement;
 use crate::dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
+use crate::dom::htmltextareaelement::HTMLTextAreaElement;
 use crate::dom::htmltitleelement::HTMLTitleElement;
+use crate::dom::intersectionobserver::IntersectionObserver;
 use crate::dom::keyboardevent::KeyboardEvent;
 use crate::dom::location::Location;
 use crate::dom::messageevent::MessageEvent;
@@ -86,6 +95,8 @@ use crate::dom::processinginstruction::ProcessingInstruction;
 use crate::dom::progressevent::ProgressEvent;
 use crate::dom::promise::Promise;
 use crate::dom::range::Range;
+use crate::dom::resizeobserver::ResizeObserver;
+use crate::dom::selection::Selection;
 use crate::dom::servoparser::ServoParser;
 use crate::dom::shadowroot::ShadowRoot;
 use crate::dom::storageevent::StorageEvent;
@@ -112,13 +123,14 @@ use crate::timers::OneshotTimerCallback;
 use cookie::Cookie;
 use devtools_traits::ScriptToDevtoolsControlMsg;
 use dom_struct::dom_struct;
-use embedder_traits::EmbedderMsg;
+use embedder_traits::{DragData, EmbedderMsg};
 use encoding_rs::{Encoding, UTF_8};
 use euclid::default::Point2D;
 use html5ever::{LocalName, Namespace, QualName};
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcSender};
 use js::jsapi::{JSObject, JSRuntime};
+use js::jsval::UndefinedValue;
 use keyboard_types::{Key, KeyState, Modifiers};
 use metrics::{
     InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory,
@@ -131,7 +143,7 @@ use net_traits::request::RequestBuilder;
 use net_traits::response::HttpsState;
 use net_traits::CookieSource::NonHTTP;
 use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
-use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy};
+use net_traits::{CookieContext, FetchResponseMsg, IpcSend, ReferrerPolicy};
 use num_traits::ToPrimitive;
 use profile_traits::ipc as profile_ipc;
 use profile_traits::time::{TimerMetadata, TimerMetadataFrameType, TimerMetadataReflowType};
@@ -156,6 +168,7 @@ use std::rc::Rc;
 use std::time::{Duration, Instant};
 use style::attr::AttrValue;
 use style::context::QuirksMode;
+use style::element_state::ElementState;
 use style::invalidation::element::restyle_hints::RestyleHint;
 use style::media_queries::{Device, MediaType};
 use style::selector_parser::{RestyleDamage, Snapshot};
@@ -176,6 +189,10 @@ const SPURIOUS_ANIMATION_FRAME_THRESHOLD: u8 = 5;
 /// The amount of time between fake `requestAnimationFrame()`s.
 const FAKE_REQUEST_ANIMATION_FRAME_DELAY: u64 = 16;
 
+/// The distance, in CSS pixels, the mouse has to move with the primary button
+/// pressed over a draggable element before the element is dragged.
+const DRAG_START_DISTANCE: f32 = 4.0;
+
 pub enum TouchEventResult {
     Processed(bool),
     Forwarded,
@@ -338,6 +355,9 @@ pub struct Document {
     /// <https://w3c.github.io/uievents/#event-type-dblclick>
     #[ignore_malloc_size_of = "Defined in std"]
     last_click_info: DomRefCell<Option<(Instant, Point2D<f32>)>>,
+    /// Whether the last user interaction with this document was through the
+    /// keyboard, in which case focused elements match `:focus-visible`.
+    last_interaction_was_keyboard: Cell<bool>,
     /// <https://html.spec.whatwg.org/multipage/#ignore-destructive-writes-counter>
     ignore_destructive_writes_counter: Cell<u32>,
     /// <https://html.spec.whatwg.org/multipage/#ignore-opens-during-unload-counter>
@@ -394,6 +414,16 @@ pub struct Document {
     /// where `id` needs to match any of the registered ShadowRoots
     /// hosting the media controls UI.
     media_controls: DomRefCell<HashMap<String, Dom<ShadowRoot>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservers>
+    intersection_observers: DomRefCell<Vec<Dom<IntersectionObserver>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservertaskqueued>
+    intersection_observer_task_queued: Cell<bool>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-document-resizeobservers-slot>
+    resize_observers: DomRefCell<Vec<Dom<ResizeObserver>>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: MutNullableDom<Selection>,
+    /// The drag-and-drop operation the user is making in this document.
+    drag_state: DragState,
 }
 
 #[derive(JSTraceable, MallocSizeOf)]
@@ -859,6 +889,25 @@ impl Document {
         }
     }
 
+    /// Runs the focus fixup rule once the focused element was removed from the
+    /// document, `parent` being the parent of the root of the removed subtree.
+    /// <https://html.spec.whatwg.org/multipage/#focus-fixup-rule>
+    pub fn focused_element_removed(&self, parent: &Node) {
+        if let Some(ref elem) = self.focused.get() {
+            // This only reaches the ancestors in the removed subtree.
+            elem.set_focus_state(false);
+        }
+        self.focused.set(None);
+
+        // https://drafts.csswg.org/selectors-4/#the-focus-within-pseudo
+        for ancestor in parent
+            .inclusive_ancestors(ShadowIncluding::Yes)
+            .filter_map(DomRoot::downcast::<Element>)
+        {
+            ancestor.set_state(ElementState::IN_FOCUS_WITHIN_STATE, false);
+        }
+    }
+
     /// Reassign the focus context to the element that last requested focus during this
     /// transaction, or none if no elements requested it.
     pub fn commit_focus_transaction(&self, focus_type: FocusType) {
@@ -881,6 +930,12 @@ impl Document {
 
         if let Some(ref elem) = self.focused.get() {
             elem.set_focus_state(true);
+            // https://drafts.csswg.org/selectors-4/#the-focus-visible-pseudo
+            // Elements that take text input always show a focus indicator,
+            // others only when the keyboard was used to move the focus.
+            elem.set_focus_visible_state(
+                elem.input_method_type().is_some() || self.last_interaction_was_keyboard.get(),
+            );
             let node = elem.upcast::<Node>();
             // FIXME: pass appropriate relatedTarget
             self.fire_focus_event(FocusEventType::Focus, node, None);
@@ -943,8 +998,9 @@ impl Document {
         };
         debug!("{}: at {:?}", mouse_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address
+            .map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+        let el = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors(ShadowIncluding::No)
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -956,8 +1012,14 @@ impl Document {
 
         let node = el.upcast::<Node>();
         debug!("{} on {:?}", mouse_event_type_string, node.debug_str());
+        self.last_interaction_was_keyboard.set(false);
         // Prevent click event if form control element is disabled.
         if let MouseEventType::Click = mouse_event_type {
+            // No click follows a drag.
+            if self.drag_state.suppress_click.replace(false) {
+                return;
+            }
+
             if el.click_event_filter_by_disabled_state() {
                 return;
             }
@@ -998,20 +1060,48 @@ impl Document {
         match mouse_event_type {
             MouseEventType::Click => el.authentic_click_activation(event),
             MouseEventType::MouseDown => {
+                self.window.notify_activation();
                 if let Some(a) = activatable {
                     a.enter_formal_activation_state();
                 }
 
+                self.drag_state.suppress_click.set(false);
                 let target = node.upcast();
-                event.fire(target);
+                if event.fire(target) == EventStatus::NotCanceled && !Document::is_text_control(&el)
+                {
+                    // Pressing the mouse button over a draggable element prepares a
+                    // drag, rather than a selection.
+                    if let Some(source) = Document::drag_source(&el) {
+                        self.drag_state.source.set(Some(&*source));
+                        self.drag_state.start_point.set(Some(client_point));
+                    } else if let (Some(hit_node), Some(selection)) =
+                        (hit_node, self.GetSelection())
+                    {
+                        selection.handle_mouse_down(&hit_node, client_point, point_in_node);
+                    }
+                }
             },
             MouseEventType::MouseUp => {
                 if let Some(a) = activatable {
                     a.exit_formal_activation_state();
                 }
 
-                let target = node.upcast();
-                event.fire(target);
+                // Releasing the mouse button drops what the user drags, and no mouse
+                // event is dispatched during a drag.
+                if self.drag_state.data_store.get().is_some() {
+                    self.end_drag(client_point, pressed_mouse_buttons, false);
+                    self.drag_state.suppress_click.set(true);
+                } else {
+                    self.drag_state.source.set(None);
+                    self.drag_state.start_point.set(None);
+
+                    let target = node.upcast();
+                    event.fire(target);
+
+                    if let Some(selection) = self.selection.get() {
+                        selection.handle_mouse_up();
+                    }
+                }
             },
         }
 
@@ -1137,13 +1227,23 @@ impl Document {
             Some(client_point) => client_point,
         };
 
-        let maybe_new_target = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address
+            .map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+        let maybe_new_target = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors(ShadowIncluding::No)
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
         });
 
+        // No mouse event is dispatched while the user drags something.
+        if self.handle_drag_mouse_move(
+            client_point,
+            maybe_new_target.as_deref(),
+            pressed_mouse_buttons,
+        ) {
+            return;
+        }
+
         // Send mousemove event to topmost target, unless it's an iframe, in which case the
         // compositor should have also sent an event to the inner document.
         let new_target = match maybe_new_target {
@@ -1158,6 +1258,14 @@ impl Document {
             pressed_mouse_buttons,
         );
 
+        // Extend the selection the user is making, while the primary button is
+        // pressed.
+        if pressed_mouse_buttons & 1 != 0 {
+            if let (Some(hit_node), Some(selection)) = (hit_node.as_ref(), self.selection.get()) {
+                selection.handle_mouse_move(hit_node, client_point);
+            }
+        }
+
         // Nothing more to do here, mousemove is sent,
         // and the element under the mouse hasn't changed.
         if maybe_new_target == prev_mouse_over_target.get() {
@@ -1230,6 +1338,384 @@ impl Document {
             .reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
     }
 
+    /// The draggable element the given element is in, if any.
+    fn drag_source(element: &Element) -> Option<DomRoot<Element>> {
+        element
+            .upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<HTMLElement>)
+            .find(|element| element.Draggable())
+            .map(DomRoot::upcast)
+    }
+
+    /// Start or continue the drag of the user when the mouse moves over the given
+    /// element, and return whether a drag is in progress.
+    fn handle_drag_mouse_move(
+        &self,
+        client_point: Point2D<f32>,
+        target: Option<&Element>,
+        pressed_mouse_buttons: u16,
+    ) -> bool {
+        let dragging = self.drag_state.data_store.get().is_some();
+        if pressed_mouse_buttons & 1 == 0 {
+            // The mouse button was released outside of this document.
+            if dragging {
+                self.end_drag(client_point, pressed_mouse_buttons, true);
+            }
+            self.drag_state.source.set(None);
+            self.drag_state.start_point.set(None);
+            return false;
+        }
+
+        if !dragging {
+            let start_point = match self.drag_state.start_point.get() {
+                Some(start_point) => start_point,
+                None => return false,
+            };
+            let line = client_point - start_point;
+            if line.dot(line).sqrt() < DRAG_START_DISTANCE {
+                return false;
+            }
+            if !self.start_drag(client_point, pressed_mouse_buttons) {
+                return false;
+            }
+        }
+
+        self.continue_drag(client_point, target, pressed_mouse_buttons);
+        self.window
+            .reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
+        true
+    }
+
+    /// Start dragging the element the mouse button was pressed over, and return
+    /// whether the page let the drag start.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dnd-start>
+    fn start_drag(&self, client_point: Point2D<f32>, pressed_mouse_buttons: u16) -> bool {
+        self.drag_state.start_point.set(None);
+        let source = match self.drag_state.source.get() {
+            Some(source) => source,
+            None => return false,
+        };
+
+        let data_store = DataTransfer::new(&self.window, DataTransferMode::ReadWrite);
+        data_store.set_effect_allowed("uninitialized");
+        // Dragging an image or a link drags its URL.
+        let url_attribute = if source.is::<HTMLImageElement>() {
+            Some(local_name!("src"))
+        } else if source.is::<HTMLAnchorElement>() {
+            Some(local_name!("href"))
+        } else {
+            None
+        };
+        if let Some(url_attribute) = url_attribute {
+            let url = source.get_url_attribute(&url_attribute).0;
+            data_store.add_data("text/uri-list", url.clone().into_bytes());
+            data_store.add_data("text/plain", url.into_bytes());
+        }
+
+        let status = self.fire_drag_event(
+            "dragstart",
+            source.upcast(),
+            client_point,
+            pressed_mouse_buttons,
+            &data_store,
+            None,
+        );
+        if status == EventStatus::Canceled {
+            self.drag_state.source.set(None);
+            return false;
+        }
+
+        self.drag_state.data_store.set(Some(&*data_store));
+        *self.drag_state.operation.borrow_mut() = DOMString::from("none");
+        true
+    }
+
+    /// Run an iteration of the drag-and-drop processing model, with the mouse over
+    /// the given element.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+    fn continue_drag(
+        &self,
+        client_point: Point2D<f32>,
+        immediate_user_selection: Option<&Element>,
+        pressed_mouse_buttons: u16,
+    ) {
+        let (source, data_store) = match (
+            self.drag_state.source.get(),
+            self.drag_state.data_store.get(),
+        ) {
+            (Some(source), Some(data_store)) => (source, data_store),
+            _ => return,
+        };
+
+        let status = self.fire_drag_event(
+            "drag",
+            source.upcast(),
+            client_point,
+            pressed_mouse_buttons,
+            &data_store,
+            None,
+        );
+        if status == EventStatus::Canceled {
+            *self.drag_state.operation.borrow_mut() = DOMString::from("none");
+            return;
+        }
+
+        // The current target element follows the element under the mouse.
+        let previous_target = self.drag_state.target.get();
+        if immediate_user_selection != previous_target.as_deref() {
+            if let Some(new_target) = immediate_user_selection {
+                self.fire_drag_event(
+                    "dragenter",
+                    new_target.upcast(),
+                    client_point,
+                    pressed_mouse_buttons,
+                    &data_store,
+                    previous_target
+                        .as_ref()
+                        .map(|target| target.upcast::<EventTarget>()),
+                );
+            }
+            self.drag_state.target.set(immediate_user_selection);
+            if let Some(previous_target) = previous_target {
+                self.fire_drag_event(
+                    "dragleave",
+                    previous_target.upcast(),
+                    client_point,
+                    pressed_mouse_buttons,
+                    &data_store,
+                    immediate_user_selection.map(|target| target.upcast::<EventTarget>()),
+                );
+            }
+        }
+
+        let operation = self.fire_dragover(
+            immediate_user_selection,
+            client_point,
+            pressed_mouse_buttons,
+            &data_store,
+        );
+        *self.drag_state.operation.borrow_mut() = operation;
+    }
+
+    /// End the drag in progress: drop the dragged data on the current target
+    /// element if it accepts it, unless the drag is `canceled`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+    fn end_drag(&self, client_point: Point2D<f32>, pressed_mouse_buttons: u16, canceled: bool) {
+        let source = self.drag_state.source.take();
+        let data_store = self.drag_state.data_store.take();
+        let target = self.drag_state.target.take();
+        let operation = mem::replace(
+            &mut *self.drag_state.operation.borrow_mut(),
+            DOMString::from("none"),
+        );
+        let (source, data_store) = match (source, data_store) {
+            (Some(source), Some(data_store)) => (source, data_store),
+            _ => return,
+        };
+
+        let operation = if canceled {
+            DOMString::from("none")
+        } else {
+            operation
+        };
+        let operation = self.drop_on_target(
+            target.as_deref(),
+            operation,
+            client_point,
+            pressed_mouse_buttons,
+            &data_store,
+        );
+
+        data_store.set_drop_effect(&operation);
+        self.fire_drag_event(
+            "dragend",
+            source.upcast(),
+            client_point,
+            pressed_mouse_buttons,
+            &data_store,
+            None,
+        );
+    }
+
+    /// Fire a `dragover` event at the current target element, and return the drag
+    /// operation it selected.
+    fn fire_dragover(
+        &self,
+        target: Option<&Element>,
+        client_point: Point2D<f32>,
+        pressed_mouse_buttons: u16,
+        data_store: &DataTransfer,
+    ) -> DOMString {
+        let target = match target {
+            Some(target) => target,
+            None => return DOMString::from("none"),
+        };
+        let status = self.fire_drag_event(
+            "dragover",
+            target.upcast(),
+            client_point,
+            pressed_mouse_buttons,
+            data_store,
+            None,
+        );
+        // Only the targets that cancel the event accept the dragged data.
+        if status == EventStatus::NotCanceled {
+            return DOMString::from("none");
+        }
+        DOMString::from(drag_operation(
+            &data_store.EffectAllowed(),
+            &data_store.DropEffect(),
+        ))
+    }
+
+    /// Drop the dragged data on the current target element if it accepts it, or
+    /// leave it otherwise, and return the resulting drag operation.
+    fn drop_on_target(
+        &self,
+        target: Option<&Element>,
+        operation: DOMString,
+        client_point: Point2D<f32>,
+        pressed_mouse_buttons: u16,
+        data_store: &DataTransfer,
+    ) -> DOMString {
+        let target = match target {
+            Some(target) => target,
+            None => return DOMString::from("none"),
+        };
+        if &*operation == "none" {
+            self.fire_drag_event(
+                "dragleave",
+                target.upcast(),
+                client_point,
+                pressed_mouse_buttons,
+                data_store,
+                None,
+            );
+            return operation;
+        }
+
+        data_store.set_drop_effect(&operation);
+        let status = self.fire_drag_event(
+            "drop",
+            target.upcast(),
+            client_point,
+            pressed_mouse_buttons,
+            data_store,
+            None,
+        );
+        match status {
+            EventStatus::Canceled => data_store.DropEffect(),
+            EventStatus::NotCanceled => DOMString::from("none"),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#fire-a-dnd-event>
+    fn fire_drag_event(
+        &self,
+        type_: &str,
+        target: &EventTarget,
+        client_point: Point2D<f32>,
+        pressed_mouse_buttons: u16,
+        data_store: &DataTransfer,
+        related_target: Option<&EventTarget>,
+    ) -> EventStatus {
+        // Steps 2-3.
+        let (mode, cancelable) = match type_ {
+            "dragstart" => (DataTransferMode::ReadWrite, EventCancelable::Cancelable),
+            "drop" => (DataTransferMode::ReadOnly, EventCancelable::Cancelable),
+            "dragleave" | "dragend" => {
+                (DataTransferMode::Protected, EventCancelable::NotCancelable)
+            },
+            _ => (DataTransferMode::Protected, EventCancelable::Cancelable),
+        };
+        data_store.set_mode(mode);
+        // Step 4. The drop effect of the drop and dragend events is the current
+        // drag operation, which the caller sets.
+        match type_ {
+            "dragenter" | "dragover" => {
+                data_store.set_drop_effect(default_drop_effect(&data_store.EffectAllowed()))
+            },
+            "drop" | "dragend" => {},
+            _ => data_store.set_drop_effect("none"),
+        }
+
+        // Steps 5-10.
+        let client_x = client_point.x as i32;
+        let client_y = client_point.y as i32;
+        let event = DragEvent::new(
+            &self.window,
+            DOMString::from(type_),
+            EventBubbles::Bubbles,
+            cancelable,
+            Some(&self.window),
+            0i32,
+            client_x,
+            client_y,
+            client_x,
+            client_y,
+            false,
+            false,
+            false,
+            false,
+            0i16,
+            pressed_mouse_buttons,
+            related_target,
+            Some(data_store),
+        );
+        let event = event.upcast::<Event>();
+        event.set_trusted(true);
+        let status = event.fire(target);
+
+        // Step 11.
+        data_store.set_mode(DataTransferMode::Protected);
+        status
+    }
+
+    /// Handle data dragged from another application and dropped on the page.
+    #[allow(unsafe_code)]
+    pub fn handle_drop_event(
+        &self,
+        js_runtime: *mut JSRuntime,
+        client_point: Point2D<f32>,
+        node_address: Option<UntrustedNodeAddress>,
+        data: DragData,
+    ) {
+        let target = node_address.and_then(|address| {
+            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+            node.inclusive_ancestors(ShadowIncluding::No)
+                .filter_map(DomRoot::downcast::<Element>)
+                .next()
+        });
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let data_store = DataTransfer::new(&self.window, DataTransferMode::Protected);
+        data_store.set_effect_allowed("uninitialized");
+        data_store.add_drag_data(data);
+
+        // The page only learns about the drag when the data is dropped, so the
+        // target is entered right before the drop.
+        self.fire_drag_event(
+            "dragenter",
+            target.upcast(),
+            client_point,
+            0,
+            &data_store,
+            None,
+        );
+        let operation = self.fire_dragover(Some(&*target), client_point, 0, &data_store);
+        self.drop_on_target(Some(&*target), operation, client_point, 0, &data_store);
+
+        self.window
+            .reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
+    }
+
     #[allow(unsafe_code)]
     pub fn handle_wheel_event(
         &self,
@@ -1389,6 +1875,11 @@ impl Document {
 
     /// The entry point for all key processing for web content
     pub fn dispatch_key_event(&self, keyboard_event: ::keyboard_types::KeyboardEvent) {
+        self.last_interaction_was_keyboard.set(true);
+        // https://html.spec.whatwg.org/multipage/#activation-triggering-input-event
+        if keyboard_event.state == KeyState::Down && keyboard_event.key != Key::Escape {
+            self.window.notify_activation();
+        }
         let focused = self.get_focused_element();
         let body = self.GetBody();
 
@@ -1482,6 +1973,14 @@ impl Document {
                         }
                     }
                 },
+                _ if keyboard_event.state == KeyState::Down => {
+                    if let Some(event_type) = ClipboardEventType::for_shortcut(
+                        &keyboard_event.key,
+                        keyboard_event.modifiers,
+                    ) {
+                        self.handle_clipboard_shortcut(event_type);
+                    }
+                },
                 _ => (),
             }
         }
@@ -2402,6 +2901,18 @@ impl Document {
         event.fire(target);
     }
 
+    /// How the document's URL relates to its site for cookies, which decides the
+    /// `SameSite` cookies that `document.cookie` can read and write.
+    fn cookie_context(&self) -> CookieContext {
+        CookieContext::new(
+            &self.url(),
+            Some(&self.window.upcast::<GlobalScope>().site_for_cookies()),
+            false, /* top-level navigation */
+            true,  /* safe method */
+            pref!(network.cookies.partitioned),
+        )
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#cookie-averse-document-object>
     pub fn is_cookie_averse(&self) -> bool {
         !self.has_browsing_context || !url_has_network_scheme(&self.url())
@@ -2483,6 +2994,254 @@ impl Document {
             debug_assert!(false, "Trying to unregister unknown media controls");
         }
     }
+
+    /// Start computing the intersections of the targets of an observer with its root,
+    /// once it observes a target in this document's browsing context.
+    pub fn add_intersection_observer(&self, observer: &IntersectionObserver) {
+        let mut observers = self.intersection_observers.borrow_mut();
+        if !observers.iter().any(|o| &**o == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    pub fn remove_intersection_observer(&self, observer: &IntersectionObserver) {
+        self.intersection_observers
+            .borrow_mut()
+            .retain(|o| &**o != observer);
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    pub fn update_intersection_observations(&self) {
+        if self.intersection_observers.borrow().is_empty() {
+            return;
+        }
+        // Step 1.
+        let time = *self.window.Performance().Now();
+        // Step 2.
+        let observers: Vec<DomRoot<IntersectionObserver>> = self
+            .intersection_observers
+            .borrow()
+            .iter()
+            .map(|o| DomRoot::from_ref(&**o))
+            .collect();
+        for observer in observers {
+            observer.update_observations(time);
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersection-observer-task>
+    pub fn queue_intersection_observer_task(&self) {
+        // Steps 1-2.
+        if self.intersection_observer_task_queued.replace(true) {
+            return;
+        }
+        // Step 3.
+        let document = Trusted::new(self);
+        let _ = self
+            .window
+            .task_manager()
+            .dom_manipulation_task_source()
+            .queue(
+                task!(notify_intersection_observers: move || {
+                    document.root().notify_intersection_observers();
+                }),
+                self.window.upcast(),
+            );
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    fn notify_intersection_observers(&self) {
+        // Step 1.
+        self.intersection_observer_task_queued.set(false);
+        // Steps 2-3.
+        let observers: Vec<DomRoot<IntersectionObserver>> = self
+            .intersection_observers
+            .borrow()
+            .iter()
+            .map(|o| DomRoot::from_ref(&**o))
+            .collect();
+        for observer in observers {
+            observer.invoke_callback();
+        }
+    }
+
+    /// Start gathering the observations of a resize observer, once it observes a
+    /// target in this document's browsing context.
+    pub fn add_resize_observer(&self, observer: &ResizeObserver) {
+        let mut observers = self.resize_observers.borrow_mut();
+        if !observers.iter().any(|o| &**o == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    pub fn remove_resize_observer(&self, observer: &ResizeObserver) {
+        self.resize_observers
+            .borrow_mut()
+            .retain(|o| &**o != observer);
+    }
+
+    fn resize_observers(&self) -> Vec<DomRoot<ResizeObserver>> {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .map(|o| DomRoot::from_ref(&**o))
+            .collect()
+    }
+
+    /// The resize observer steps of the event loop processing model, from
+    /// <https://drafts.csswg.org/resize-observer/#html-event-loop>.
+    pub fn update_resize_observations(&self) {
+        if self.resize_observers.borrow().is_empty() {
+            return;
+        }
+        // Step 1.
+        let mut depth = 0;
+        // Step 2.
+        self.gather_active_resize_observations_at_depth(depth);
+        // Step 3.
+        while self.has_active_resize_observations() {
+            depth = self.broadcast_active_resize_observations();
+            self.gather_active_resize_observations_at_depth(depth);
+        }
+        // Step 4.
+        if self.has_skipped_resize_observations() {
+            self.deliver_resize_loop_error_notification();
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    fn gather_active_resize_observations_at_depth(&self, depth: usize) {
+        for observer in self.resize_observers() {
+            observer.gather_active_observations_at_depth(depth);
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    fn has_active_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|o| o.has_active_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    fn has_skipped_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|o| o.has_skipped_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    fn broadcast_active_resize_observations(&self) -> usize {
+        // Steps 1-2.
+        self.resize_observers()
+            .iter()
+            .map(|observer| observer.broadcast_active_observations())
+            .min()
+            .unwrap_or(usize::max_value())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#deliver-resize-error>
+    fn deliver_resize_loop_error_notification(&self) {
+        let global = self.window.upcast::<GlobalScope>();
+        let error_info = ErrorInfo {
+            message: "ResizeObserver loop limit exceeded".to_owned(),
+            filename: String::new(),
+            lineno: 0,
+            column: 0,
+        };
+        let cx = global.get_cx();
+        rooted!(in(*cx) let error = UndefinedValue());
+        global.report_an_error(error_info, error.handle());
+    }
+
+    /// Report the changes of the selection of this document, if any, since the
+    /// last time they were reported. Called before every reflow for display, so
+    /// that the highlight painted by layout is up to date.
+    pub fn update_selection(&self) {
+        if let Some(selection) = self.selection.get() {
+            selection.update();
+        }
+    }
+
+    /// Whether the given element is a text control, which handles the
+    /// selection and the clipboard shortcuts by itself.
+    fn is_text_control(element: &Element) -> bool {
+        element.is::<HTMLInputElement>() || element.is::<HTMLTextAreaElement>()
+    }
+
+    /// Run the clipboard action of a keyboard shortcut, unless a text control is
+    /// focused, since text controls run their own.
+    fn handle_clipboard_shortcut(&self, event_type: ClipboardEventType) {
+        if let Some(focused) = self.get_focused_element() {
+            if Document::is_text_control(&focused) {
+                return;
+            }
+        }
+        if self.fire_clipboard_event(event_type) && event_type == ClipboardEventType::Copy {
+            self.copy_selection();
+        }
+    }
+
+    /// Copy the text of the selection to the clipboard, as the default action of
+    /// the copy event.
+    fn copy_selection(&self) {
+        let text = match self.selection.get().and_then(|s| s.text_to_copy()) {
+            Some(text) => text,
+            None => return,
+        };
+        self.window
+            .upcast::<GlobalScope>()
+            .script_to_constellation_chan()
+            .clone()
+            .set_clipboard_contents(text);
+    }
+
+    /// Fire a clipboard event of the given type, and return whether the default
+    /// action of the clipboard action should run.
+    ///
+    /// <https://w3c.github.io/clipboard-apis/#fire-a-clipboard-event>
+    pub fn fire_clipboard_event(&self, event_type: ClipboardEventType) -> bool {
+        let mut clipboard = self
+            .window
+            .upcast::<GlobalScope>()
+            .script_to_constellation_chan()
+            .clone();
+        // Step 5.
+        let target = self
+            .get_focused_element()
+            .or_else(|| self.GetBody().map(DomRoot::upcast::<Element>))
+            .or_else(|| self.GetDocumentElement());
+        let target = match target {
+            Some(target) => target,
+            None => return true,
+        };
+        // Steps 6-8.
+        let clipboard_data = DataTransfer::new(&self.window, DataTransferMode::ReadWrite);
+        if event_type == ClipboardEventType::Paste {
+            clipboard_data.add_clipboard_contents(clipboard.clipboard_data());
+            clipboard_data.set_mode(DataTransferMode::ReadOnly);
+        }
+        // Steps 9-10.
+        let event = ClipboardEvent::new(
+            &self.window,
+            event_type.as_atom(),
+            EventBubbles::Bubbles,
+            EventCancelable::Cancelable,
+            Some(&clipboard_data),
+        );
+        let status = event.upcast::<Event>().fire(target.upcast());
+        clipboard_data.set_mode(DataTransferMode::Protected);
+        if status == EventStatus::NotCanceled {
+            return true;
+        }
+        // The listeners provided the data to copy in place of the default action.
+        if event_type != ClipboardEventType::Paste && !clipboard_data.is_empty() {
+            clipboard.set_clipboard_data(clipboard_data.clipboard_contents());
+        }
+        false
+    }
 }
 
 #[derive(MallocSizeOf, PartialEq)]
@@ -2756,6 +3515,7 @@ impl Document {
             referrer_policy: Cell::new(referrer_policy),
             target_element: MutNullableDom::new(None),
             last_click_info: DomRefCell::new(None),
+            last_interaction_was_keyboard: Cell::new(false),
             ignore_destructive_writes_counter: Default::default(),
             ignore_opens_during_unload_counter: Default::default(),
             spurious_animation_frames: Cell::new(0),
@@ -2777,6 +3537,11 @@ impl Document {
             shadow_roots: DomRefCell::new(HashSet::new()),
             shadow_roots_styles_changed: Cell::new(false),
             media_controls: DomRefCell::new(HashMap::new()),
+            intersection_observers: DomRefCell::new(vec![]),
+            intersection_observer_task_queued: Cell::new(false),
+            resize_observers: DomRefCell::new(vec![]),
+            selection: Default::default(),
+            drag_state: Default::default(),
         }
     }
 
@@ -3295,6 +4060,7 @@ impl Document {
             .send(Msg::AddStylesheet(
                 sheet.clone(),
                 insertion_point.as_ref().map(|s| s.sheet.clone()),
+                self.window.upcast::<GlobalScope>().get_csp_list(),
             ))
             .unwrap();
 
@@ -3379,6 +4145,14 @@ impl DocumentMethods for Document {
         )
     }
 
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        if !self.has_browsing_context() {
+            return None;
+        }
+        Some(self.selection.or_init(|| Selection::new(self)))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-hasfocus
     fn HasFocus(&self) -> bool {
         // Step 1-2.
@@ -3739,6 +4513,7 @@ impl DocumentMethods for Document {
             "customevent" => Ok(DomRoot::upcast(CustomEvent::new_uninitialized(
                 self.window.upcast(),
             ))),
+            "dragevent" => Ok(DomRoot::upcast(DragEvent::new_uninitialized(&self.window))),
             "errorevent" => Ok(DomRoot::upcast(ErrorEvent::new_uninitialized(
                 self.window.upcast(),
             ))),
@@ -4168,7 +4943,7 @@ impl DocumentMethods for Document {
             .window
             .upcast::<GlobalScope>()
             .resource_threads()
-            .send(GetCookiesForUrl(url, tx, NonHTTP));
+            .send(GetCookiesForUrl(url, tx, NonHTTP, self.cookie_context()));
         let cookies = rx.recv().unwrap();
         Ok(cookies.map_or(DOMString::new(), DOMString::from))
     }
@@ -4193,7 +4968,12 @@ impl DocumentMethods for Document {
             .window
             .upcast::<GlobalScope>()
             .resource_threads()
-            .send(SetCookiesForUrl(self.url(), cookies, NonHTTP));
+            .send(SetCookiesForUrl(
+                self.url(),
+                cookies,
+                NonHTTP,
+                self.cookie_context(),
+            ));
         Ok(())
     }
 
@@ -4748,3 +5528,55 @@ impl PendingScript {
             .map(|result| (DomRoot::from_ref(&*self.element), result))
     }
 }
+
+/// The state of the drag-and-drop operation the user is making in a document.
+///
+/// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+struct DragState {
+    /// The draggable element the primary mouse button was pressed over, which
+    /// becomes the source node once the drag starts.
+    source: MutNullableDom<Element>,
+    /// Where the mouse button was pressed, until the drag starts.
+    start_point: Cell<Option<Point2D<f32>>>,
+    /// The drag data store, while a drag is in progress.
+    data_store: MutNullableDom<DataTransfer>,
+    /// <https://html.spec.whatwg.org/multipage/#current-target-element>
+    target: MutNullableDom<Element>,
+    /// <https://html.spec.whatwg.org/multipage/#current-drag-operation>
+    operation: DomRefCell<DOMString>,
+    /// Whether a drag just ended, so the click that follows is not dispatched.
+    suppress_click: Cell<bool>,
+}
+
+/// The drop effect a `dragenter` or `dragover` event starts with.
+///
+/// <https://html.spec.whatwg.org/multipage/#dom-datatransfer-dropeffect>
+fn default_drop_effect(effect_allowed: &str) -> &'static str {
+    match effect_allowed {
+        "copy" | "copyLink" | "copyMove" | "all" | "uninitialized" => "copy",
+        "link" | "linkMove" => "link",
+        "move" => "move",
+        _ => "none",
+    }
+}
+
+/// The drag operation a drop target selected with its drop effect, if the drag
+/// source allows it.
+///
+/// <https://html.spec.whatwg.org/multipage/#current-drag-operation>
+fn drag_operation<'a>(effect_allowed: &str, drop_effect: &'a str) -> &'a str {
+    let allowed = match effect_allowed {
+        "all" | "uninitialized" => true,
+        "copyLink" => drop_effect == "copy" || drop_effect == "link",
+        "copyMove" => drop_effect == "copy" || drop_effect == "move",
+        "linkMove" => drop_effect == "link" || drop_effect == "move",
+        _ => effect_allowed == drop_effect,
+    };
+    if allowed {
+        drop_effect
+    } else {
+        "none"
+    }
+}
@@ -38,6 +38,12 @@ pub enum DOMErrorName {
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
     NotReadableError,
     OperationError,
+    TransactionInactiveError,
+    ReadOnlyError,
+    VersionError,
+    ConstraintError,
+    DataError,
+    NotAllowedError,
 }
 
 impl DOMErrorName {
@@ -66,6 +72,12 @@ impl DOMErrorName {
             "DataCloneError" => Some(DOMErrorName::DataCloneError),
             "NotReadableError" => Some(DOMErrorName::NotReadableError),
             "OperationError" => Some(DOMErrorName::OperationError),
+            "TransactionInactiveError" => Some(DOMErrorName::TransactionInactiveError),
+            "ReadOnlyError" => Some(DOMErrorName::ReadOnlyError),
+            "VersionError" => Some(DOMErrorName::VersionError),
+            "ConstraintError" => Some(DOMErrorName::ConstraintError),
+            "DataError" => Some(DOMErrorName::DataError),
+            "NotAllowedError" => Some(DOMErrorName::NotAllowedError),
             _ => None,
         }
     }
@@ -112,6 +124,16 @@ impl DOMException {
             DOMErrorName::OperationError => {
                 "The operation failed for an operation-specific reason."
             },
+            DOMErrorName::TransactionInactiveError => {
+                "The transaction is not active, or has finished."
+            },
+            DOMErrorName::ReadOnlyError => "The transaction is read-only.",
+            DOMErrorName::VersionError => "The database version is higher than the one requested.",
+            DOMErrorName::ConstraintError => "The operation failed to satisfy a constraint.",
+            DOMErrorName::DataError => "The data provided does not meet requirements.",
+            DOMErrorName::NotAllowedError => {
+                "The request is not allowed by the user agent or the platform."
+            },
         };
 
         (
@@ -41,6 +41,7 @@ use crate::dom::domrect::DOMRect;
 use crate::dom::domtokenlist::DOMTokenList;
 use crate::dom::event::Event;
 use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlanchorelement::HTMLAnchorElement;
 use crate::dom::htmlbodyelement::{HTMLBodyElement, HTMLBodyElementLayoutHelpers};
 use crate::dom::htmlbuttonelement::HTMLButtonElement;
@@ -96,6 +97,7 @@ use html5ever::{LocalName, Namespace, Prefix, QualName};
 use js::jsapi::Heap;
 use js::jsval::JSVal;
 use msg::constellation_msg::InputMethodType;
+use net_traits::content_security_policy::InlineCheckType;
 use net_traits::request::CorsSettings;
 use ref_filter_map::ref_filter_map;
 use script_layout_interface::message::ReflowGoal;
@@ -315,6 +317,43 @@ impl Element {
         }
     }
 
+    /// Restyles the elements whose `:has()` selectors may have started or
+    /// stopped matching because the children of this element changed.
+    fn restyle_relative_selector_anchors(&self) {
+        let doc = self.node.owner_doc();
+        let inclusive_ancestor_elements = self
+            .upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<Self>);
+
+        for element in inclusive_ancestor_elements {
+            let flags = element.selector_flags.get();
+            if flags.intersects(ElementSelectorFlags::HAS_RELATIVE_SELECTOR_SIBLINGS) {
+                // A child of this element may be the anchor, and the elements
+                // depending on it are among its later siblings.
+                let mut restyle = doc.ensure_pending_restyle(&element);
+                restyle.hint.insert(RestyleHint::RESTYLE_DESCENDANTS);
+                restyle.damage = RestyleDamage::rebuild_and_reflow();
+            }
+
+            if !flags.intersects(ElementSelectorFlags::HAS_RELATIVE_SELECTOR) {
+                continue;
+            }
+
+            // The anchor may be matched by selectors that reach its descendants
+            // or later siblings too, so restyle all of those.
+            let anchor_and_later_siblings = element
+                .upcast::<Node>()
+                .inclusively_following_siblings()
+                .filter_map(DomRoot::downcast::<Self>);
+            for sibling in anchor_and_later_siblings {
+                let mut restyle = doc.ensure_pending_restyle(&sibling);
+                restyle.hint.insert(RestyleHint::restyle_subtree());
+                restyle.damage = RestyleDamage::rebuild_and_reflow();
+            }
+        }
+    }
+
     pub fn set_is(&self, is: LocalName) {
         *self.is.borrow_mut() = Some(is);
     }
@@ -395,7 +434,7 @@ impl Element {
     }
 
     // https://drafts.csswg.org/cssom-view/#potentially-scrollable
-    fn potentially_scrollable(&self) -> bool {
+    pub fn potentially_scrollable(&self) -> bool {
         self.has_css_layout_box() && !self.has_any_visible_overflow()
     }
 
@@ -1275,7 +1314,7 @@ impl Element {
     }
 
     pub fn is_focusable_area(&self) -> bool {
-        if self.is_actually_disabled() {
+        if self.is_actually_disabled() || !self.is_connected() {
             return false;
         }
         // TODO: Check whether the element is being rendered (i.e. not hidden).
@@ -2703,7 +2742,7 @@ impl VirtualMethods for Element {
                             _ => false,
                         };
 
-                        let block = if is_declaration {
+                        if is_declaration {
                             let mut value = AttrValue::String(String::new());
                             attr.swap_value(&mut value);
                             let (serialization, block) = match value {
@@ -2712,18 +2751,26 @@ impl VirtualMethods for Element {
                             };
                             let mut value = AttrValue::String(serialization);
                             attr.swap_value(&mut value);
-                            block
+                            Some(block)
                         } else {
                             let win = window_from_node(self);
-                            Arc::new(doc.style_shared_lock().wrap(parse_style_attribute(
+                            if win.upcast::<GlobalScope>().should_inline_be_blocked(
+                                InlineCheckType::StyleAttribute,
+                                None,
                                 &attr.value(),
-                                &doc.base_url(),
-                                win.css_error_reporter(),
-                                doc.quirks_mode(),
-                            )))
-                        };
-
-                        Some(block)
+                            ) {
+                                None
+                            } else {
+                                Some(Arc::new(doc.style_shared_lock().wrap(
+                                    parse_style_attribute(
+                                        &attr.value(),
+                                        &doc.base_url(),
+                                        win.css_error_reporter(),
+                                        doc.quirks_mode(),
+                                    ),
+                                )))
+                            }
+                        }
                     },
                     AttributeMutation::Removed => None,
                 };
@@ -2859,6 +2906,9 @@ impl VirtualMethods for Element {
         if fullscreen.as_deref() == Some(self) {
             doc.exit_fullscreen();
         }
+        if doc.get_focused_element().as_deref() == Some(self) {
+            doc.focused_element_removed(context.parent);
+        }
         if let Some(ref value) = *self.id_attribute.borrow() {
             doc.unregister_named_element(self, value.clone());
         }
@@ -2891,6 +2941,8 @@ impl VirtualMethods for Element {
                 }
             }
         }
+
+        self.restyle_relative_selector_anchors();
     }
 
     fn adopting_steps(&self, old_doc: &Document) {
@@ -2955,6 +3007,10 @@ impl<'a> SelectorsElement for DomRoot<Element> {
             .next()
     }
 
+    fn first_element_child(&self) -> Option<DomRoot<Element>> {
+        self.node.children().filter_map(DomRoot::downcast).next()
+    }
+
     fn attr_matches(
         &self,
         ns: &NamespaceConstraint<&Namespace>,
@@ -3041,7 +3097,16 @@ impl<'a> SelectorsElement for DomRoot<Element> {
             NonTSPseudoClass::Indeterminate |
             NonTSPseudoClass::ReadWrite |
             NonTSPseudoClass::PlaceholderShown |
-            NonTSPseudoClass::Target => Element::state(self).contains(pseudo_class.state_flag()),
+            NonTSPseudoClass::Target |
+            NonTSPseudoClass::FocusVisible |
+            NonTSPseudoClass::FocusWithin |
+            NonTSPseudoClass::Valid |
+            NonTSPseudoClass::Invalid |
+            NonTSPseudoClass::Required |
+            NonTSPseudoClass::Optional |
+            NonTSPseudoClass::InRange |
+            NonTSPseudoClass::OutOfRange |
+            NonTSPseudoClass::Default => Element::state(self).contains(pseudo_class.state_flag()),
         }
     }
 
@@ -3298,6 +3363,29 @@ impl Element {
     pub fn set_focus_state(&self, value: bool) {
         self.set_state(ElementState::IN_FOCUS_STATE, value);
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+
+        // https://drafts.csswg.org/selectors-4/#the-focus-within-pseudo
+        for ancestor in self
+            .upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::Yes)
+            .filter_map(DomRoot::downcast::<Element>)
+        {
+            ancestor.set_state(ElementState::IN_FOCUS_WITHIN_STATE, value);
+        }
+        if !value {
+            self.set_focus_visible_state(false);
+        }
+    }
+
+    pub fn focus_visible_state(&self) -> bool {
+        self.state
+            .get()
+            .contains(ElementState::IN_FOCUS_VISIBLE_STATE)
+    }
+
+    /// <https://drafts.csswg.org/selectors-4/#the-focus-visible-pseudo>
+    pub fn set_focus_visible_state(&self, value: bool) {
+        self.set_state(ElementState::IN_FOCUS_VISIBLE_STATE, value)
     }
 
     pub fn hover_state(&self) -> bool {
@@ -3345,6 +3433,39 @@ impl Element {
         }
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#selector-required>
+    /// <https://html.spec.whatwg.org/multipage/#selector-optional>
+    ///
+    /// `None` for elements that match neither.
+    pub fn set_required_state(&self, required: Option<bool>) {
+        self.set_state(ElementState::IN_REQUIRED_STATE, required == Some(true));
+        self.set_state(ElementState::IN_OPTIONAL_STATE, required == Some(false));
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#selector-valid>
+    /// <https://html.spec.whatwg.org/multipage/#selector-invalid>
+    ///
+    /// `None` for elements that match neither, such as the ones that are
+    /// barred from constraint validation.
+    pub fn set_validity_state(&self, valid: Option<bool>) {
+        self.set_state(ElementState::IN_VALID_STATE, valid == Some(true));
+        self.set_state(ElementState::IN_INVALID_STATE, valid == Some(false));
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#selector-in-range>
+    /// <https://html.spec.whatwg.org/multipage/#selector-out-of-range>
+    ///
+    /// `None` for elements that match neither.
+    pub fn set_range_state(&self, in_range: Option<bool>) {
+        self.set_state(ElementState::IN_INRANGE_STATE, in_range == Some(true));
+        self.set_state(ElementState::IN_OUTOFRANGE_STATE, in_range == Some(false));
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#selector-default>
+    pub fn set_default_state(&self, value: bool) {
+        self.set_state(ElementState::IN_DEFAULT_STATE, value)
+    }
+
     pub fn set_target_state(&self, value: bool) {
         self.set_state(ElementState::IN_TARGET_STATE, value)
     }
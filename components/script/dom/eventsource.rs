@@ -31,6 +31,7 @@ use ipc_channel::router::ROUTER;
 use js::conversions::ToJSValConvertible;
 use js::jsval::UndefinedValue;
 use mime::{self, Mime};
+use net_traits::content_security_policy::Violation;
 use net_traits::request::{CacheMode, CorsSettings, CredentialsMode};
 use net_traits::request::{RequestBuilder, RequestMode};
 use net_traits::{CoreResourceMsg, FetchChannels, FetchMetadata};
@@ -237,6 +238,7 @@ impl EventSourceContext {
                 DOMString::from(self.origin.clone()),
                 None,
                 event_source.last_event_id.borrow().clone(),
+                vec![],
             )
         };
         // Step 7
@@ -403,6 +405,11 @@ impl FetchResponseListener for EventSourceContext {
         self.reestablish_the_connection();
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -519,6 +526,8 @@ impl EventSource {
         let mut request = RequestBuilder::new(url_record)
             .origin(global.origin().immutable().clone())
             .pipeline_id(Some(global.pipeline_id()))
+            .csp_list(global.get_csp_list())
+            .site_for_cookies(Some(global.site_for_cookies()))
             // https://html.spec.whatwg.org/multipage/#create-a-potential-cors-request
             .use_url_credentials(true)
             .mode(RequestMode::CorsMode)
@@ -3,23 +3,29 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use crate::dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceBinding::EventSourceMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use crate::dom::bindings::conversions::{root_from_object, root_from_object_static};
 use crate::dom::bindings::error::{report_pending_exception, ErrorInfo};
 use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::settings_stack::{entry_global, incumbent_global, AutoEntryScript};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::weakref::DOMTracker;
+use crate::dom::broadcastchannel::BroadcastChannel;
 use crate::dom::crypto::Crypto;
 use crate::dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventsource::EventSource;
 use crate::dom::eventtarget::EventTarget;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbfactory::IDBFactory;
+use crate::dom::messageport::MessagePort;
 use crate::dom::paintworkletglobalscope::PaintWorkletGlobalScope;
 use crate::dom::performance::Performance;
 use crate::dom::window::Window;
@@ -28,19 +34,22 @@ use crate::dom::workletglobalscope::WorkletGlobalScope;
 use crate::microtask::{Microtask, MicrotaskQueue};
 use crate::script_runtime::{CommonScriptMsg, JSContext as SafeJSContext, ScriptChan, ScriptPort};
 use crate::script_thread::{MainThreadScriptChan, ScriptThread};
+use crate::security_manager::{CSPViolationReporter, ReportingEndpoints};
 use crate::task::TaskCanceller;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
-use crate::task_source::TaskSourceName;
+use crate::task_source::{TaskSource, TaskSourceName};
 use crate::timers::{IsInterval, OneshotTimerCallback, OneshotTimerHandle};
 use crate::timers::{OneshotTimers, TimerCallback};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use dom_struct::dom_struct;
-use ipc_channel::ipc::IpcSender;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
 use js::glue::{IsWrapper, UnwrapObjectDynamic};
 use js::jsapi::JSObject;
 use js::jsapi::{CurrentGlobalOrNull, GetNonCCWObjectGlobal};
@@ -52,10 +61,14 @@ use js::rust::{get_object_class, CompileOptionsWrapper, ParentRuntime, Runtime};
 use js::rust::{HandleValue, MutableHandleValue};
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use msg::constellation_msg::PipelineId;
+use msg::constellation_msg::{BroadcastChannelRouterId, MessagePortId, MessagePortRouterId};
+use net_traits::content_security_policy::{CheckResult, CspList, InlineCheckType, Violation};
 use net_traits::image_cache::ImageCache;
 use net_traits::{CoreResourceThread, IpcSend, ResourceThreads};
 use profile_traits::{mem as profile_mem, time as profile_time};
-use script_traits::{MsDuration, ScriptToConstellationChan, TimerEvent};
+use script_traits::ScriptToConstellationChan;
+use script_traits::{BroadcastMsg, MessagePortMsg, MsDuration, ScriptMsg};
+use script_traits::{StructuredSerializedData, TimerEvent};
 use script_traits::{TimerEventId, TimerSchedulerMsg, TimerSource};
 use servo_url::{MutableOrigin, ServoUrl};
 use std::borrow::Cow;
@@ -63,6 +76,7 @@ use std::cell::Cell;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::ffi::CString;
+use std::mem;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -81,6 +95,7 @@ impl Drop for AutoCloseWorker {
 pub struct GlobalScope {
     eventtarget: EventTarget,
     crypto: MutNullableDom<Crypto>,
+    indexed_db: MutNullableDom<IDBFactory>,
     next_worker_id: Cell<WorkerId>,
 
     /// Pipeline id associated with this global.
@@ -140,6 +155,25 @@ pub struct GlobalScope {
     /// Vector storing references of all eventsources.
     event_source_tracker: DOMTracker<EventSource>,
 
+    /// The message ports owned by this global, keyed by their id.
+    message_ports: DomRefCell<HashMap<MessagePortId, Dom<MessagePort>>>,
+
+    /// The id of the router through which the constellation delivers messages
+    /// to the ports of this global, created when the first port is tracked.
+    message_port_router_id: Cell<Option<MessagePortRouterId>>,
+
+    /// The broadcast channels of this global that are not closed, by name, in
+    /// creation order.
+    broadcast_channels: DomRefCell<HashMap<String, Vec<Dom<BroadcastChannel>>>>,
+
+    /// The id of the router through which the constellation delivers the messages
+    /// broadcast to the channels of this global, created when the first channel is tracked.
+    broadcast_channel_router_id: Cell<Option<BroadcastChannelRouterId>>,
+
+    /// The IndexedDB connections of this global that are not closed, which are closed
+    /// when the global goes away.
+    indexeddb_connections: DomRefCell<Vec<Dom<IDBDatabase>>>,
+
     /// Storage for watching rejected promises waiting for some client to
     /// consume their rejection.
     /// Promises in this list have been rejected in the last turn of the
@@ -164,6 +198,17 @@ pub struct GlobalScope {
 
     /// An optional string allowing the user agent to be set for testing.
     user_agent: Cow<'static, str>,
+
+    /// <https://w3c.github.io/webappsec-csp/#global-object-csp-list>
+    csp_list: DomRefCell<CspList>,
+
+    /// The endpoint groups declared by the `Report-To` header of the response
+    /// this global was created from.
+    reporting_endpoints: DomRefCell<ReportingEndpoints>,
+
+    /// The URL of the top-level document, if this global is not itself a top-level
+    /// document.
+    top_level_url: DomRefCell<Option<ServoUrl>>,
 }
 
 impl GlobalScope {
@@ -184,6 +229,7 @@ impl GlobalScope {
         Self {
             eventtarget: EventTarget::new_inherited(),
             crypto: Default::default(),
+            indexed_db: Default::default(),
             next_worker_id: Cell::new(WorkerId(0)),
             pipeline_id,
             devtools_wants_updates: Default::default(),
@@ -200,10 +246,18 @@ impl GlobalScope {
             microtask_queue,
             list_auto_close_worker: Default::default(),
             event_source_tracker: DOMTracker::new(),
+            message_ports: DomRefCell::new(HashMap::new()),
+            message_port_router_id: Cell::new(None),
+            broadcast_channels: DomRefCell::new(HashMap::new()),
+            broadcast_channel_router_id: Cell::new(None),
+            indexeddb_connections: DomRefCell::new(vec![]),
             uncaught_rejections: Default::default(),
             consumed_rejections: Default::default(),
             is_headless,
             user_agent,
+            csp_list: Default::default(),
+            reporting_endpoints: Default::default(),
+            top_level_url: Default::default(),
         }
     }
 
@@ -232,6 +286,260 @@ impl GlobalScope {
         canceled_any_fetch
     }
 
+    /// Start managing `port`, so that messages sent to it through the constellation
+    /// are delivered to this global.
+    pub fn track_message_port(&self, port: &MessagePort) {
+        let router_id = self.message_port_router_id();
+        self.message_ports
+            .borrow_mut()
+            .insert(port.message_port_id(), Dom::from_ref(port));
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePort(router_id, port.message_port_id()));
+    }
+
+    /// Stop managing `port`, either because it was closed or shipped to another global.
+    pub fn untrack_message_port(&self, port: &MessagePort) {
+        self.message_ports
+            .borrow_mut()
+            .remove(&port.message_port_id());
+    }
+
+    /// Stop managing all ports of this global, and let the constellation know that
+    /// messages can no longer be routed here.
+    pub fn remove_message_ports(&self) {
+        self.message_ports.borrow_mut().clear();
+        if let Some(router_id) = self.message_port_router_id.take() {
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::RemoveMessagePortRouter(router_id));
+        }
+    }
+
+    /// Deliver a message received from the constellation to one of our ports.
+    pub fn route_message_port_msg(&self, msg: MessagePortMsg) {
+        match msg {
+            MessagePortMsg::NewTask(port_id, data) => {
+                let port = self
+                    .message_ports
+                    .borrow()
+                    .get(&port_id)
+                    .map(|port| DomRoot::from_ref(&**port));
+                match port {
+                    Some(port) => port.handle_incoming(data),
+                    None => self.reroute_port_message(port_id, data),
+                }
+            },
+        }
+    }
+
+    /// Send a message for a port that is not managed here anymore back to the
+    /// constellation, which knows where the port went.
+    pub fn reroute_port_message(&self, port_id: MessagePortId, data: StructuredSerializedData) {
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::RerouteMessagePort(port_id, data));
+    }
+
+    /// Returns the id of the message port router of this global, setting the
+    /// router up first if needed.
+    fn message_port_router_id(&self) -> MessagePortRouterId {
+        if let Some(router_id) = self.message_port_router_id.get() {
+            return router_id;
+        }
+        let router_id = MessagePortRouterId::new();
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let global = Trusted::new(self);
+        let task_source = self.port_message_queue();
+        let canceller = self.task_canceller(TaskSourceName::PortMessage);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let msg: MessagePortMsg = match message.to() {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        warn!("Error receiving a MessagePortMsg: {:?}", err);
+                        return;
+                    },
+                };
+                let global = global.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(process_port_message: move || {
+                        global.root().route_message_port_msg(msg);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePortRouter(router_id, sender));
+        self.message_port_router_id.set(Some(router_id));
+        router_id
+    }
+
+    /// Start delivering the messages broadcast to the name of `channel` to it.
+    pub fn track_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let router_id = self.broadcast_channel_router_id();
+        let name = String::from(channel.name().clone());
+        let mut channels = self.broadcast_channels.borrow_mut();
+        let channels = channels.entry(name.clone()).or_insert_with(Vec::new);
+        if channels.is_empty() {
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::NewBroadcastChannelNameInRouter(router_id, name));
+        }
+        channels.push(Dom::from_ref(channel));
+    }
+
+    /// Stop delivering messages to `channel`, which was closed.
+    pub fn untrack_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let name = String::from(channel.name().clone());
+        let mut channels = self.broadcast_channels.borrow_mut();
+        let now_empty = match channels.get_mut(&name) {
+            Some(channels) => {
+                channels.retain(|other| *other != channel);
+                channels.is_empty()
+            },
+            None => return,
+        };
+        if !now_empty {
+            return;
+        }
+        channels.remove(&name);
+        if let Some(router_id) = self.broadcast_channel_router_id.get() {
+            let _ = self.script_to_constellation_chan().send(
+                ScriptMsg::RemoveBroadcastChannelNameInRouter(router_id, name),
+            );
+        }
+    }
+
+    /// Broadcast a message posted to `source` to the other channels of the same name
+    /// and origin, in this global and, through the constellation, in all others.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    pub fn schedule_broadcast(&self, source: &BroadcastChannel, data: Vec<u8>) {
+        let msg = BroadcastMsg {
+            origin: self.origin().immutable().clone(),
+            channel_name: String::from(source.name().clone()),
+            data,
+        };
+        self.broadcast_message_event(msg.clone(), Some(source));
+        let router_id = self.broadcast_channel_router_id();
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::ScheduleBroadcast(router_id, msg));
+    }
+
+    /// Queue a task to fire a message event at each channel of this global
+    /// subscribed to the channel of `msg`, other than `source`.
+    pub fn broadcast_message_event(&self, msg: BroadcastMsg, source: Option<&BroadcastChannel>) {
+        // Only globals that are eligible for messaging are destinations.
+        if let Some(window) = self.downcast::<Window>() {
+            if !window.Document().is_fully_active() {
+                return;
+            }
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            if worker.is_closing() {
+                return;
+            }
+        }
+        let destinations: Vec<Trusted<BroadcastChannel>> =
+            match self.broadcast_channels.borrow().get(&msg.channel_name) {
+                Some(channels) => channels
+                    .iter()
+                    .filter(|channel| source.map_or(true, |source| **channel != source))
+                    .map(|channel| Trusted::new(&**channel))
+                    .collect(),
+                None => return,
+            };
+        let origin = msg.origin.ascii_serialization();
+        let task_source = self.dom_manipulation_task_source();
+        for channel in destinations {
+            let origin = origin.clone();
+            let data = msg.data.clone();
+            let _ = task_source.queue(
+                task!(broadcast_message_event: move || {
+                    channel.root().dispatch_message(&origin, data);
+                }),
+                self,
+            );
+        }
+    }
+
+    /// Stop delivering messages to the channels of this global, and let the
+    /// constellation know that messages can no longer be broadcast here.
+    pub fn remove_broadcast_channels(&self) {
+        self.broadcast_channels.borrow_mut().clear();
+        if let Some(router_id) = self.broadcast_channel_router_id.take() {
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::RemoveBroadcastChannelRouter(router_id));
+        }
+    }
+
+    pub fn track_indexeddb_connection(&self, connection: &IDBDatabase) {
+        self.indexeddb_connections
+            .borrow_mut()
+            .push(Dom::from_ref(connection));
+    }
+
+    pub fn untrack_indexeddb_connection(&self, connection: &IDBDatabase) {
+        self.indexeddb_connections
+            .borrow_mut()
+            .retain(|other| *other != connection);
+    }
+
+    /// Close the IndexedDB connections of this global, so that they don't block the
+    /// deletion of their databases.
+    pub fn close_indexeddb_connections(&self) {
+        let connections = mem::replace(&mut *self.indexeddb_connections.borrow_mut(), vec![]);
+        for connection in connections {
+            connection.close_connection();
+        }
+    }
+
+    /// Returns the id of the broadcast channel router of this global, setting the
+    /// router up first if needed.
+    fn broadcast_channel_router_id(&self) -> BroadcastChannelRouterId {
+        if let Some(router_id) = self.broadcast_channel_router_id.get() {
+            return router_id;
+        }
+        let router_id = BroadcastChannelRouterId::new();
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let global = Trusted::new(self);
+        let task_source = self.dom_manipulation_task_source();
+        let canceller = self.task_canceller(TaskSourceName::DOMManipulation);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let msg: BroadcastMsg = match message.to() {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        warn!("Error receiving a BroadcastMsg: {:?}", err);
+                        return;
+                    },
+                };
+                let global = global.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(process_broadcast_message: move || {
+                        global.root().broadcast_message_event(msg, None);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let origin = self.origin().immutable().clone();
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewBroadcastChannelRouter(
+                router_id, sender, origin,
+            ));
+        self.broadcast_channel_router_id.set(Some(router_id));
+        router_id
+    }
+
     /// Returns the global scope of the realm that the given DOM object's reflector
     /// was created in.
     #[allow(unsafe_code)]
@@ -319,6 +627,10 @@ impl GlobalScope {
         self.crypto.or_init(|| Crypto::new(self))
     }
 
+    pub fn indexed_db(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db.or_init(|| IDBFactory::new(self))
+    }
+
     /// Get next worker id.
     pub fn get_next_worker_id(&self) -> WorkerId {
         let worker_id = self.next_worker_id.get();
@@ -515,6 +827,18 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// `ScriptChan` to send messages to the port message queue of
+    /// this global scope.
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.task_manager().port_message_queue();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.port_message_queue();
+        }
+        unreachable!();
+    }
+
     /// `ScriptChan` to send messages to the remote-event task source of
     /// this global scope.
     pub fn remote_event_task_source(&self) -> RemoteEventTaskSource {
@@ -818,6 +1142,93 @@ impl GlobalScope {
     pub fn get_user_agent(&self) -> Cow<'static, str> {
         self.user_agent.clone()
     }
+
+    /// The CSP list of this global, to be attached to the requests it makes, or `None`
+    /// if no policy applies to it.
+    pub fn get_csp_list(&self) -> Option<CspList> {
+        let csp_list = self.csp_list.borrow();
+        if csp_list.is_empty() {
+            None
+        } else {
+            Some(csp_list.clone())
+        }
+    }
+
+    /// Start enforcing or monitoring the policies of `csp_list`, in addition to the
+    /// ones already applying to this global.
+    pub fn append_csp_list(&self, csp_list: CspList) {
+        self.csp_list.borrow_mut().0.extend(csp_list.0);
+    }
+
+    pub fn set_reporting_endpoints(&self, endpoints: ReportingEndpoints) {
+        *self.reporting_endpoints.borrow_mut() = endpoints;
+    }
+
+    /// The URL of the endpoint of the reporting group named `group`, if any.
+    pub fn reporting_endpoint(&self, group: &str) -> Option<ServoUrl> {
+        self.reporting_endpoints.borrow().get(group).cloned()
+    }
+
+    pub fn set_top_level_url(&self, url: Option<ServoUrl>) {
+        *self.top_level_url.borrow_mut() = url;
+    }
+
+    /// The URL whose site the requests made by this global are compared to when
+    /// deciding which `SameSite` cookies they can use.
+    /// <https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-03#section-5.2.1>
+    pub fn site_for_cookies(&self) -> ServoUrl {
+        self.top_level_url
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| self.get_url())
+    }
+
+    /// The referrer to include in reports sent on behalf of this global.
+    pub fn referrer_for_reports(&self) -> String {
+        match self.downcast::<Window>() {
+            Some(window) => window.Document().Referrer().into(),
+            None => String::new(),
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#report-violation>
+    pub fn report_csp_violations(&self, violations: Vec<Violation>) {
+        let target = match self.downcast::<Window>() {
+            Some(window) => DomRoot::upcast::<EventTarget>(window.Document()),
+            None => DomRoot::from_ref(self.upcast::<EventTarget>()),
+        };
+        let task_source = self.dom_manipulation_task_source();
+        for violation in violations {
+            let task = CSPViolationReporter::new(&target, violation);
+            let _ = task_source.queue(task, self);
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#should-block-inline>
+    ///
+    /// Reports any violation, and returns whether the inline content must not run.
+    pub fn should_inline_be_blocked(
+        &self,
+        type_: InlineCheckType,
+        nonce: Option<&str>,
+        source: &str,
+    ) -> bool {
+        let (result, violations) = self
+            .csp_list
+            .borrow()
+            .should_inline_be_blocked(type_, nonce, source);
+        self.report_csp_violations(violations);
+        result == CheckResult::Blocked
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+    ///
+    /// Reports any violation, and returns whether strings may be compiled into code.
+    pub fn is_js_evaluation_allowed(&self) -> bool {
+        let (result, violations) = self.csp_list.borrow().is_js_evaluation_allowed();
+        self.report_csp_violations(violations);
+        result == CheckResult::Allowed
+    }
 }
 
 fn timestamp_in_ms(time: Timespec) -> u64 {
@@ -52,7 +52,7 @@ impl HTMLButtonElement {
     ) -> HTMLButtonElement {
         HTMLButtonElement {
             htmlelement: HTMLElement::new_inherited_with_state(
-                ElementState::IN_ENABLED_STATE,
+                ElementState::IN_ENABLED_STATE | ElementState::IN_VALID_STATE,
                 local_name,
                 prefix,
                 document,
@@ -230,6 +230,11 @@ impl VirtualMethods for HTMLButtonElement {
             },
             _ => {},
         }
+
+        match attr.local_name() {
+            &local_name!("disabled") | &local_name!("type") => self.update_validity_state(),
+            _ => {},
+        }
     }
 
     fn bind_to_tree(&self, context: &BindContext) {
@@ -239,6 +244,7 @@ impl VirtualMethods for HTMLButtonElement {
 
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.update_validity_state();
     }
 
     fn unbind_from_tree(&self, context: &UnbindContext) {
@@ -254,6 +260,7 @@ impl VirtualMethods for HTMLButtonElement {
         } else {
             el.check_disabled_attribute();
         }
+        self.update_validity_state();
     }
 }
 
@@ -272,8 +279,13 @@ impl FormControl for HTMLButtonElement {
 }
 
 impl Validatable for HTMLButtonElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
     fn is_instance_validatable(&self) -> bool {
-        true
+        // https://html.spec.whatwg.org/multipage/#the-button-element:barred-from-constraint-validation
+        self.button_type.get() == ButtonType::Submit
     }
     fn validate(&self, validate_flags: ValidationFlags) -> bool {
         if validate_flags.is_empty() {}
@@ -171,6 +171,35 @@ impl HTMLElementMethods for HTMLElement {
     // https://html.spec.whatwg.org/multipage/#dom-hidden
     make_bool_setter!(SetHidden, "hidden");
 
+    // https://html.spec.whatwg.org/multipage/#dom-draggable
+    fn Draggable(&self) -> bool {
+        let element = self.upcast::<Element>();
+        match &*element
+            .get_string_attribute(&local_name!("draggable"))
+            .to_ascii_lowercase()
+        {
+            "true" => true,
+            "false" => false,
+            // Images and links are draggable by default.
+            _ => match self.upcast::<Node>().type_id() {
+                NodeTypeId::Element(ElementTypeId::HTMLElement(
+                    HTMLElementTypeId::HTMLImageElement,
+                )) => true,
+                NodeTypeId::Element(ElementTypeId::HTMLElement(
+                    HTMLElementTypeId::HTMLAnchorElement,
+                )) => element.has_attribute(&local_name!("href")),
+                _ => false,
+            },
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-draggable
+    fn SetDraggable(&self, value: bool) {
+        let value = if value { "true" } else { "false" };
+        self.upcast::<Element>()
+            .set_string_attribute(&local_name!("draggable"), DOMString::from(value));
+    }
+
     // https://html.spec.whatwg.org/multipage/#globaleventhandlers
     global_event_handlers!(NoOnload);
 
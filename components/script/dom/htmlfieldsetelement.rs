@@ -14,6 +14,7 @@ use crate::dom::htmlelement::HTMLElement;
 use crate::dom::htmlformelement::{FormControl, HTMLFormElement};
 use crate::dom::htmllegendelement::HTMLLegendElement;
 use crate::dom::node::{window_from_node, Node, ShadowIncluding};
+use crate::dom::validation::Validatable;
 use crate::dom::validitystate::ValidityState;
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
@@ -145,18 +146,20 @@ impl VirtualMethods for HTMLFieldSetElement {
                             _ => false,
                         })
                 });
-                if disabled_state {
-                    for field in fields {
-                        let el = field.downcast::<Element>().unwrap();
+                for field in fields {
+                    let el = field.downcast::<Element>().unwrap();
+                    if disabled_state {
                         el.set_disabled_state(true);
                         el.set_enabled_state(false);
-                    }
-                } else {
-                    for field in fields {
-                        let el = field.downcast::<Element>().unwrap();
+                    } else {
                         el.check_disabled_attribute();
                         el.check_ancestors_disabled_state_for_form_control();
                     }
+                    // Disabled form controls are barred from constraint
+                    // validation.
+                    if let Some(validatable) = el.as_maybe_validatable() {
+                        validatable.update_validity_state();
+                    }
                 }
             },
             &local_name!("form") => {
@@ -406,6 +406,7 @@ impl HTMLFormElement {
             Some(Referrer::ReferrerUrl(target_document.url())),
             target_document.get_referrer_policy(),
         );
+        load_data.site_for_cookies = Some(doc.global().site_for_cookies());
 
         // Step 22
         match (&*scheme, method) {
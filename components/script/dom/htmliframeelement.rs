@@ -269,13 +269,16 @@ impl HTMLIFrameElement {
         };
 
         let document = document_from_node(self);
-        let load_data = LoadData::new(
+        let mut load_data = LoadData::new(
             LoadOrigin::Script(document.origin().immutable().clone()),
             url,
             creator_pipeline_id,
             Some(Referrer::ReferrerUrl(document.url())),
             document.get_referrer_policy(),
         );
+        load_data.site_for_cookies = Some(window.upcast::<GlobalScope>().site_for_cookies());
+        // https://w3c.github.io/webappsec-csp/#directive-frame-src
+        load_data.csp_list = window.upcast::<GlobalScope>().get_csp_list();
 
         let pipeline_id = self.pipeline_id();
         // If the initial `about:blank` page is the current page, load with replacement enabled,
@@ -54,11 +54,12 @@ use html5ever::{LocalName, Prefix};
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use mime::{self, Mime};
+use net_traits::content_security_policy::Violation;
 use net_traits::image::base::{Image, ImageMetadata};
 use net_traits::image_cache::UsePlaceholder;
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageOrMetadataAvailable};
 use net_traits::image_cache::{ImageResponder, ImageResponse, ImageState, PendingImageId};
-use net_traits::request::RequestBuilder;
+use net_traits::request::{Destination, RequestBuilder};
 use net_traits::{FetchMetadata, FetchResponseListener, FetchResponseMsg, NetworkError};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use num_traits::ToPrimitive;
@@ -230,6 +231,11 @@ impl FetchResponseListener for ImageContext {
             .notify_pending_response(self.id, FetchResponseMsg::ProcessResponseEOF(response));
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -328,8 +334,11 @@ impl HTMLImageElement {
         );
 
         let request = RequestBuilder::new(img_url.clone())
+            .destination(Destination::Image)
             .origin(document.origin().immutable().clone())
-            .pipeline_id(Some(document.global().pipeline_id()));
+            .pipeline_id(Some(document.global().pipeline_id()))
+            .csp_list(document.global().get_csp_list())
+            .site_for_cookies(Some(document.global().site_for_cookies()));
 
         // This is a background load because the load blocker already fulfills the
         // purpose of delaying the document's load event.
@@ -16,6 +16,7 @@ use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom};
 use crate::dom::bindings::str::{DOMString, USVString};
+use crate::dom::clipboardevent::ClipboardEventType;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::document::Document;
 use crate::dom::element::{
@@ -60,6 +61,7 @@ use script_traits::ScriptToConstellationChan;
 use servo_atoms::Atom;
 use std::borrow::ToOwned;
 use std::cell::Cell;
+use std::iter;
 use std::ops::Range;
 use style::attr::AttrValue;
 use style::element_state::ElementState;
@@ -126,6 +128,21 @@ impl InputType {
         self.is_textual() || *self == InputType::Password
     }
 
+    // https://html.spec.whatwg.org/multipage/#the-required-attribute
+    fn is_required_applicable(&self) -> bool {
+        match *self {
+            InputType::Button |
+            InputType::Color |
+            InputType::Hidden |
+            InputType::Image |
+            InputType::Range |
+            InputType::Reset |
+            InputType::Submit => false,
+
+            _ => true,
+        }
+    }
+
     fn to_str(&self) -> &str {
         match *self {
             InputType::Button => "button",
@@ -280,7 +297,10 @@ impl HTMLInputElement {
             .clone();
         HTMLInputElement {
             htmlelement: HTMLElement::new_inherited_with_state(
-                ElementState::IN_ENABLED_STATE | ElementState::IN_READ_WRITE_STATE,
+                ElementState::IN_ENABLED_STATE |
+                    ElementState::IN_READ_WRITE_STATE |
+                    ElementState::IN_OPTIONAL_STATE |
+                    ElementState::IN_VALID_STATE,
                 local_name,
                 prefix,
                 document,
@@ -666,6 +686,8 @@ impl HTMLInputElementMethods for HTMLInputElement {
             },
         }
 
+        self.update_placeholder_shown_state();
+        self.update_form_states();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
         Ok(())
     }
@@ -871,6 +893,19 @@ impl HTMLInputElementMethods for HTMLInputElement {
 
 #[allow(unsafe_code)]
 fn broadcast_radio_checked(broadcaster: &HTMLInputElement, group: Option<&Atom>) {
+    for_each_other_in_radio_group(broadcaster, group, |r| {
+        if r.Checked() {
+            r.SetChecked(false);
+        }
+    });
+}
+
+/// Calls `f` with each of the other radio buttons in the group of `input`.
+fn for_each_other_in_radio_group(
+    input: &HTMLInputElement,
+    group: Option<&Atom>,
+    f: impl FnMut(&HTMLInputElement),
+) {
     match group {
         None | Some(&atom!("")) => {
             // Radio input elements with a missing or empty name are alone in their
@@ -881,29 +916,28 @@ fn broadcast_radio_checked(broadcaster: &HTMLInputElement, group: Option<&Atom>)
     }
 
     //TODO: if not in document, use root ancestor instead of document
-    let owner = broadcaster.form_owner();
-    let doc = document_from_node(broadcaster);
+    let owner = input.form_owner();
+    let doc = document_from_node(input);
 
     // This function is a workaround for lifetime constraint difficulties.
-    fn do_broadcast(
+    fn do_for_each(
         doc_node: &Node,
-        broadcaster: &HTMLInputElement,
+        input: &HTMLInputElement,
         owner: Option<&HTMLFormElement>,
         group: Option<&Atom>,
+        mut f: impl FnMut(&HTMLInputElement),
     ) {
         let iter = doc_node
             .query_selector_iter(DOMString::from("input[type=radio]"))
             .unwrap()
             .filter_map(DomRoot::downcast::<HTMLInputElement>)
-            .filter(|r| in_same_group(&r, owner, group) && broadcaster != &**r);
-        for ref r in iter {
-            if r.Checked() {
-                r.SetChecked(false);
-            }
+            .filter(|r| in_same_group(&r, owner, group) && input != &**r);
+        for r in iter {
+            f(&r);
         }
     }
 
-    do_broadcast(doc.upcast(), broadcaster, owner.as_deref(), group)
+    do_for_each(doc.upcast(), input, owner.as_deref(), group, f)
 }
 
 // https://html.spec.whatwg.org/multipage/#radio-button-group
@@ -921,6 +955,17 @@ fn in_same_group(
     }
 }
 
+/// Whether the radio button group made of `input` and `others` is missing a
+/// value, which is the case if any button of the group is required and none is
+/// checked.
+fn radio_group_value_missing(
+    input: &HTMLInputElement,
+    others: &[DomRoot<HTMLInputElement>],
+) -> bool {
+    let group = || iter::once(input).chain(others.iter().map(|r| &**r));
+    group().any(|r| r.Required()) && !group().any(|r| r.Checked())
+}
+
 impl HTMLInputElement {
     fn radio_group_updated(&self, group: Option<&Atom>) {
         if self.Checked() {
@@ -1024,6 +1069,7 @@ impl HTMLInputElement {
             broadcast_radio_checked(self, self.radio_group_name().as_ref());
         }
 
+        self.update_form_states();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
         //TODO: dispatch change event
     }
@@ -1047,6 +1093,8 @@ impl HTMLInputElement {
         }
         self.textinput.borrow_mut().set_content(self.DefaultValue());
         self.value_dirty.set(false);
+        self.update_placeholder_shown_state();
+        self.update_form_states();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 
@@ -1062,6 +1110,136 @@ impl HTMLInputElement {
         el.set_placeholder_shown_state(has_placeholder && !has_value);
     }
 
+    // https://html.spec.whatwg.org/multipage/#concept-input-min
+    fn minimum(&self) -> Option<f64> {
+        match self.input_type() {
+            InputType::Number => self.Min().parse_floating_point_number(),
+            InputType::Range => Some(self.Min().parse_floating_point_number().unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-max
+    fn maximum(&self) -> Option<f64> {
+        match self.input_type() {
+            InputType::Number => self.Max().parse_floating_point_number(),
+            InputType::Range => Some(self.Max().parse_floating_point_number().unwrap_or(100.0)),
+            _ => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#have-range-limitations
+    fn has_range_limitations(&self) -> bool {
+        self.minimum().is_some() || self.maximum().is_some()
+    }
+
+    /// The constraints given by the type, value and attributes of this
+    /// element that it doesn't satisfy.
+    fn validity_failures(&self) -> ValidationFlags {
+        let mut failures = ValidationFlags::empty();
+        let input_type = self.input_type();
+
+        // https://html.spec.whatwg.org/multipage/#suffering-from-being-missing
+        if input_type.is_required_applicable() {
+            let missing = match input_type {
+                InputType::Checkbox => self.Required() && !self.Checked(),
+                InputType::Radio => self.radio_group_value_missing(),
+                InputType::File => {
+                    self.Required() &&
+                        self.filelist
+                            .get()
+                            .map_or(true, |files| files.Length() == 0)
+                },
+                _ => self.Required() && self.Value().is_empty(),
+            };
+            if missing {
+                failures.insert(ValidationFlags::VALUE_MISSING);
+            }
+        }
+
+        // https://html.spec.whatwg.org/multipage/#suffering-from-an-underflow
+        // https://html.spec.whatwg.org/multipage/#suffering-from-an-overflow
+        if let Some(value) = self.Value().parse_floating_point_number() {
+            if self.minimum().map_or(false, |minimum| value < minimum) {
+                failures.insert(ValidationFlags::RANGE_UNDERFLOW);
+            }
+            if self.maximum().map_or(false, |maximum| value > maximum) {
+                failures.insert(ValidationFlags::RANGE_OVERFLOW);
+            }
+        }
+
+        failures
+    }
+
+    /// Updates the states matched by `:required`, `:optional`, `:in-range`,
+    /// `:out-of-range`, `:default`, `:valid` and `:invalid`, after a change
+    /// to the type, value, checkedness or attributes of this element.
+    fn update_form_states(&self) {
+        let el = self.upcast::<Element>();
+        let input_type = self.input_type();
+
+        el.set_required_state(if input_type.is_required_applicable() {
+            Some(self.Required())
+        } else {
+            None
+        });
+
+        // https://html.spec.whatwg.org/multipage/#selector-in-range
+        let is_candidate = self.is_validation_candidate();
+        el.set_range_state(if is_candidate && self.has_range_limitations() {
+            let range_failures = ValidationFlags::RANGE_UNDERFLOW | ValidationFlags::RANGE_OVERFLOW;
+            Some(!self.validity_failures().intersects(range_failures))
+        } else {
+            None
+        });
+
+        // TODO: submit buttons that are the default button of their form.
+        el.set_default_state(match input_type {
+            InputType::Checkbox | InputType::Radio => self.DefaultChecked(),
+            _ => false,
+        });
+
+        if input_type == InputType::Radio {
+            self.update_radio_group_validity_states();
+        } else {
+            self.update_validity_state();
+        }
+    }
+
+    /// The other buttons of the radio button group of this element.
+    fn other_radio_group_members(&self) -> Vec<DomRoot<HTMLInputElement>> {
+        let mut others = vec![];
+        for_each_other_in_radio_group(self, self.radio_group_name().as_ref(), |r| {
+            others.push(DomRoot::from_ref(r))
+        });
+        others
+    }
+
+    // https://html.spec.whatwg.org/multipage/#radio-button-state-(type=radio):suffering-from-being-missing
+    fn radio_group_value_missing(&self) -> bool {
+        radio_group_value_missing(self, &self.other_radio_group_members())
+    }
+
+    /// Updates the validity states of all the buttons of the radio button
+    /// group of this element.
+    ///
+    /// Whether a radio button is missing a value depends on all the buttons of
+    /// its group, so this finds them once rather than once per button.
+    fn update_radio_group_validity_states(&self) {
+        let others = self.other_radio_group_members();
+        // Being missing is the only constraint that applies to radio buttons.
+        let valid = !radio_group_value_missing(self, &others);
+        let group = iter::once(self).chain(others.iter().map(|r| &**r));
+        for r in group {
+            let el = r.upcast::<Element>();
+            el.set_validity_state(if r.is_validation_candidate() {
+                Some(valid)
+            } else {
+                None
+            });
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#file-upload-state-(type=file)
     // Select files by invoking UI or by passed in argument
     fn select_files(&self, opt_test_paths: Option<Vec<DOMString>>) {
@@ -1126,6 +1304,7 @@ impl HTMLInputElement {
         } else {
             let filelist = FileList::new(&window, files);
             self.filelist.set(Some(&filelist));
+            self.update_form_states();
 
             target.fire_bubbling_event(atom!("input"));
             target.fire_bubbling_event(atom!("change"));
@@ -1419,6 +1598,19 @@ impl VirtualMethods for HTMLInputElement {
             },
             _ => {},
         }
+
+        match attr.local_name() {
+            &local_name!("checked") |
+            &local_name!("disabled") |
+            &local_name!("max") |
+            &local_name!("min") |
+            &local_name!("name") |
+            &local_name!("readonly") |
+            &local_name!("required") |
+            &local_name!("type") |
+            &local_name!("value") => self.update_form_states(),
+            _ => {},
+        }
     }
 
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
@@ -1446,6 +1638,7 @@ impl VirtualMethods for HTMLInputElement {
         }
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.update_form_states();
     }
 
     fn unbind_from_tree(&self, context: &UnbindContext) {
@@ -1461,6 +1654,7 @@ impl VirtualMethods for HTMLInputElement {
         } else {
             el.check_disabled_attribute();
         }
+        self.update_form_states();
     }
 
     fn handle_event(&self, event: &Event) {
@@ -1501,9 +1695,19 @@ impl VirtualMethods for HTMLInputElement {
             self.input_type().is_textual_or_password()
         {
             if let Some(keyevent) = event.downcast::<KeyboardEvent>() {
+                // The page can replace the clipboard actions of their shortcuts.
+                let clipboard_action_allowed =
+                    ClipboardEventType::for_shortcut(&keyevent.key(), keyevent.modifiers())
+                        .map_or(true, |event_type| {
+                            document_from_node(self).fire_clipboard_event(event_type)
+                        });
                 // This can't be inlined, as holding on to textinput.borrow_mut()
                 // during self.implicit_submission will cause a panic.
-                let action = self.textinput.borrow_mut().handle_keydown(keyevent);
+                let action = if clipboard_action_allowed {
+                    self.textinput.borrow_mut().handle_keydown(keyevent)
+                } else {
+                    Nothing
+                };
                 match action {
                     TriggerDefaultAction => {
                         self.implicit_submission(
@@ -1516,6 +1720,7 @@ impl VirtualMethods for HTMLInputElement {
                     DispatchInput => {
                         self.value_dirty.set(true);
                         self.update_placeholder_shown_state();
+                        self.update_form_states();
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     },
@@ -1579,13 +1784,22 @@ impl FormControl for HTMLInputElement {
 }
 
 impl Validatable for HTMLInputElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
     fn is_instance_validatable(&self) -> bool {
         // https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation
-        true
+        match self.input_type() {
+            InputType::Hidden | InputType::Reset | InputType::Button => false,
+            // https://html.spec.whatwg.org/multipage/#the-readonly-attribute:barred-from-constraint-validation
+            input_type => !(input_type.is_textual_or_password() && self.ReadOnly()),
+        }
     }
-    fn validate(&self, _validate_flags: ValidationFlags) -> bool {
-        // call stub methods defined in validityState.rs file here according to the flags set in validate_flags
-        true
+
+    fn validate(&self, validate_flags: ValidationFlags) -> bool {
+        // TODO: check the constraints behind the other flags.
+        !self.validity_failures().intersects(validate_flags)
     }
 }
 
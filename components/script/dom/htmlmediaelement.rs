@@ -72,6 +72,7 @@ use http::header::{self, HeaderMap, HeaderValue};
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use media::{glplayer_channel, GLPlayerMsg, GLPlayerMsgForward};
+use net_traits::content_security_policy::Violation;
 use net_traits::image::base::Image;
 use net_traits::image_cache::ImageResponse;
 use net_traits::request::{CredentialsMode, Destination, Referrer, RequestBuilder, RequestMode};
@@ -822,6 +823,8 @@ impl HTMLMediaElement {
             .use_url_credentials(true)
             .origin(document.origin().immutable().clone())
             .pipeline_id(Some(self.global().pipeline_id()))
+            .csp_list(self.global().get_csp_list())
+            .site_for_cookies(Some(self.global().site_for_cookies()))
             .referrer(Some(Referrer::ReferrerUrl(document.url())))
             .referrer_policy(document.get_referrer_policy());
 
@@ -2680,6 +2683,11 @@ impl FetchResponseListener for HTMLMediaElementFetchListener {
         }
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -13,6 +13,7 @@ use crate::dom::bindings::str::DOMString;
 use crate::dom::cssstylesheet::CSSStyleSheet;
 use crate::dom::document::Document;
 use crate::dom::element::{AttributeMutation, Element};
+use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlelement::HTMLElement;
 use crate::dom::htmlheadelement::HTMLHeadElement;
 use crate::dom::node::{
@@ -22,6 +23,7 @@ use crate::dom::node::{
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use net_traits::content_security_policy::{CspList, PolicyDisposition, PolicySource};
 use parking_lot::RwLock;
 use servo_arc::Arc;
 use servo_config::pref;
@@ -98,6 +100,45 @@ impl HTMLMetaElement {
                 self.apply_referrer();
             }
         }
+
+        if let Some(ref http_equiv) = element.get_attribute(&ns!(), &local_name!("http-equiv")) {
+            if http_equiv
+                .value()
+                .eq_ignore_ascii_case("content-security-policy")
+            {
+                self.apply_csp_list();
+            }
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#attr-meta-http-equiv-content-security-policy>
+    fn apply_csp_list(&self) {
+        // Step 1.
+        let parent = self.upcast::<Node>().GetParentElement();
+        if !parent.map_or(false, |parent| parent.is::<HTMLHeadElement>()) {
+            return;
+        }
+
+        // Step 2.
+        let element = self.upcast::<Element>();
+        let content = match element.get_attribute(&ns!(), &local_name!("content")) {
+            Some(content) => content,
+            None => return,
+        };
+        if content.value().is_empty() {
+            return;
+        }
+
+        // Steps 3-5.
+        let mut csp_list = CspList::default();
+        csp_list.append_serialized(
+            &content.value(),
+            PolicySource::Meta,
+            PolicyDisposition::Enforce,
+        );
+        window_from_node(self)
+            .upcast::<GlobalScope>()
+            .append_csp_list(csp_list);
     }
 
     #[allow(unrooted_must_root)]
@@ -103,8 +103,13 @@ impl HTMLObjectElementMethods for HTMLObjectElement {
 }
 
 impl Validatable for HTMLObjectElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
     fn is_instance_validatable(&self) -> bool {
-        true
+        // https://html.spec.whatwg.org/multipage/#the-object-element:barred-from-constraint-validation
+        false
     }
     fn validate(&self, validate_flags: ValidationFlags) -> bool {
         if validate_flags.is_empty() {}
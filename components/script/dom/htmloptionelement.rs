@@ -21,6 +21,7 @@ use crate::dom::htmlscriptelement::HTMLScriptElement;
 use crate::dom::htmlselectelement::HTMLSelectElement;
 use crate::dom::node::{BindContext, Node, ShadowIncluding, UnbindContext};
 use crate::dom::text::Text;
+use crate::dom::validation::Validatable;
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
@@ -80,17 +81,20 @@ impl HTMLOptionElement {
         self.dirtiness.set(dirtiness);
     }
 
-    fn pick_if_selected_and_reset(&self) {
-        if let Some(select) = self
-            .upcast::<Node>()
+    fn owner_select_element(&self) -> Option<DomRoot<HTMLSelectElement>> {
+        self.upcast::<Node>()
             .ancestors()
             .filter_map(DomRoot::downcast::<HTMLSelectElement>)
             .next()
-        {
+    }
+
+    fn pick_if_selected_and_reset(&self) {
+        if let Some(select) = self.owner_select_element() {
             if self.Selected() {
                 select.pick_option(self);
             }
             select.ask_for_reset();
+            select.update_validity_state();
         }
     }
 }
@@ -230,6 +234,18 @@ impl VirtualMethods for HTMLOptionElement {
                         }
                     },
                 }
+                // https://html.spec.whatwg.org/multipage/#selector-default
+                self.upcast::<Element>()
+                    .set_default_state(!mutation.is_removal());
+                if let Some(select) = self.owner_select_element() {
+                    select.update_validity_state();
+                }
+            },
+            // The value decides whether this is a placeholder label option.
+            &local_name!("value") => {
+                if let Some(select) = self.owner_select_element() {
+                    select.update_validity_state();
+                }
             },
             _ => {},
         }
@@ -256,6 +272,7 @@ impl VirtualMethods for HTMLOptionElement {
             .next()
         {
             select.ask_for_reset();
+            select.update_validity_state();
         }
 
         let node = self.upcast::<Node>();
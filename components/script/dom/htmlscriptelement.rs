@@ -32,6 +32,7 @@ use html5ever::{LocalName, Prefix};
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use js::jsval::UndefinedValue;
+use net_traits::content_security_policy::{InlineCheckType, Violation};
 use net_traits::request::{
     CorsSettings, CredentialsMode, Destination, Referrer, RequestBuilder, RequestMode,
 };
@@ -247,6 +248,11 @@ impl FetchResponseListener for ScriptContext {
         document.finish_load(LoadType::Script(self.url.clone()));
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -307,6 +313,8 @@ fn fetch_a_classic_script(
         })
         .origin(doc.origin().immutable().clone())
         .pipeline_id(Some(script.global().pipeline_id()))
+        .csp_list(script.global().get_csp_list())
+        .site_for_cookies(Some(script.global().site_for_cookies()))
         .referrer(Some(Referrer::ReferrerUrl(doc.url())))
         .referrer_policy(doc.get_referrer_policy())
         .integrity_metadata(integrity_metadata);
@@ -402,7 +410,16 @@ impl HTMLScriptElement {
 
         // TODO: Step 11: nomodule content attribute
 
-        // TODO(#4577): Step 12: CSP.
+        // Step 12.
+        if !element.has_attribute(&local_name!("src")) {
+            let nonce = element.get_string_attribute(&local_name!("nonce"));
+            if doc
+                .global()
+                .should_inline_be_blocked(InlineCheckType::Script, Some(&*nonce), &text)
+            {
+                return;
+            }
+        }
 
         // Step 13.
         let for_attribute = element.get_attribute(&ns!(), &local_name!("for"));
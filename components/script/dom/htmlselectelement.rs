@@ -73,7 +73,9 @@ impl HTMLSelectElement {
     ) -> HTMLSelectElement {
         HTMLSelectElement {
             htmlelement: HTMLElement::new_inherited_with_state(
-                ElementState::IN_ENABLED_STATE,
+                ElementState::IN_ENABLED_STATE |
+                    ElementState::IN_OPTIONAL_STATE |
+                    ElementState::IN_VALID_STATE,
                 local_name,
                 prefix,
                 document,
@@ -119,6 +121,7 @@ impl HTMLSelectElement {
             opt.set_dirtiness(false);
         }
         self.ask_for_reset();
+        self.update_validity_state();
     }
 
     // https://html.spec.whatwg.org/multipage/#ask-for-a-reset
@@ -180,6 +183,23 @@ impl HTMLSelectElement {
         }
     }
 
+    // https://html.spec.whatwg.org/multipage/#attr-select-required
+    fn is_required(&self) -> bool {
+        self.upcast::<Element>()
+            .has_attribute(&local_name!("required"))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#placeholder-label-option
+    fn placeholder_label_option(&self) -> Option<DomRoot<HTMLOptionElement>> {
+        if !self.is_required() || self.Multiple() || self.display_size() != 1 {
+            return None;
+        }
+
+        self.list_of_options().next().filter(|option| {
+            option.Value().is_empty() && self.upcast::<Node>().is_parent_of(option.upcast())
+        })
+    }
+
     // https://html.spec.whatwg.org/multipage/#concept-select-size
     fn display_size(&self) -> u32 {
         if self.Size() == 0 {
@@ -323,6 +343,7 @@ impl HTMLSelectElementMethods for HTMLSelectElement {
         for opt in opt_iter {
             opt.set_selectedness(false);
         }
+        self.update_validity_state();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-select-selectedindex
@@ -349,6 +370,7 @@ impl HTMLSelectElementMethods for HTMLSelectElement {
                 opt.set_selectedness(false);
             }
         }
+        self.update_validity_state();
     }
 }
 
@@ -373,7 +395,15 @@ impl VirtualMethods for HTMLSelectElement {
                         el.check_ancestors_disabled_state_for_form_control();
                     },
                 }
+                self.update_validity_state();
+            },
+            &local_name!("required") => {
+                self.upcast::<Element>()
+                    .set_required_state(Some(!mutation.is_removal()));
+                self.update_validity_state();
             },
+            // These decide whether there's a placeholder label option.
+            &local_name!("multiple") | &local_name!("size") => self.update_validity_state(),
             &local_name!("form") => {
                 self.form_attribute_mutated(mutation);
             },
@@ -388,6 +418,7 @@ impl VirtualMethods for HTMLSelectElement {
 
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.update_validity_state();
     }
 
     fn unbind_from_tree(&self, context: &UnbindContext) {
@@ -403,6 +434,7 @@ impl VirtualMethods for HTMLSelectElement {
         } else {
             el.check_disabled_attribute();
         }
+        self.update_validity_state();
     }
 
     fn parse_plain_attribute(&self, local_name: &LocalName, value: DOMString) -> AttrValue {
@@ -431,13 +463,23 @@ impl FormControl for HTMLSelectElement {
 }
 
 impl Validatable for HTMLSelectElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
     fn is_instance_validatable(&self) -> bool {
         true
     }
     fn validate(&self, validate_flags: ValidationFlags) -> bool {
-        if validate_flags.is_empty() {}
-        // Need more flag check for different validation types later
-        true
+        // https://html.spec.whatwg.org/multipage/#the-select-element:suffering-from-being-missing
+        // TODO: check the constraints behind the other flags.
+        let value_missing = self.is_required() && {
+            let placeholder = self.placeholder_label_option();
+            !self
+                .list_of_options()
+                .any(|option| option.Selected() && Some(&option) != placeholder.as_ref())
+        };
+        !(value_missing && validate_flags.contains(ValidationFlags::VALUE_MISSING))
     }
 }
 
@@ -13,6 +13,7 @@ use crate::dom::bindings::error::ErrorResult;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::root::{DomRoot, LayoutDom, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
+use crate::dom::clipboardevent::ClipboardEventType;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::document::Document;
 use crate::dom::element::RawLayoutElementHelpers;
@@ -30,6 +31,7 @@ use crate::dom::node::{
 use crate::dom::nodelist::NodeList;
 use crate::dom::textcontrol::{TextControlElement, TextControlSelection};
 use crate::dom::validation::Validatable;
+use crate::dom::validitystate::ValidationFlags;
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::textinput::{
     Direction, KeyReaction, Lines, SelectionDirection, TextInput, UTF16CodeUnits, UTF8Bytes,
@@ -137,7 +139,10 @@ impl HTMLTextAreaElement {
             .clone();
         HTMLTextAreaElement {
             htmlelement: HTMLElement::new_inherited_with_state(
-                ElementState::IN_ENABLED_STATE | ElementState::IN_READ_WRITE_STATE,
+                ElementState::IN_ENABLED_STATE |
+                    ElementState::IN_READ_WRITE_STATE |
+                    ElementState::IN_OPTIONAL_STATE |
+                    ElementState::IN_VALID_STATE,
                 local_name,
                 prefix,
                 document,
@@ -177,6 +182,14 @@ impl HTMLTextAreaElement {
         let el = self.upcast::<Element>();
         el.set_placeholder_shown_state(has_placeholder && !has_value);
     }
+
+    /// Updates the states matched by `:required`, `:optional`, `:valid` and
+    /// `:invalid`, after a change to the value or attributes of this element.
+    fn update_form_states(&self) {
+        self.upcast::<Element>()
+            .set_required_state(Some(self.Required()));
+        self.update_validity_state();
+    }
 }
 
 impl TextControlElement for HTMLTextAreaElement {
@@ -290,22 +303,26 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea-value
     fn SetValue(&self, value: DOMString) {
-        let mut textinput = self.textinput.borrow_mut();
+        {
+            let mut textinput = self.textinput.borrow_mut();
 
-        // Step 1
-        let old_value = textinput.get_content();
+            // Step 1
+            let old_value = textinput.get_content();
 
-        // Step 2
-        textinput.set_content(value);
+            // Step 2
+            textinput.set_content(value);
 
-        // Step 3
-        self.value_dirty.set(true);
+            // Step 3
+            self.value_dirty.set(true);
 
-        if old_value != textinput.get_content() {
-            // Step 4
-            textinput.clear_selection_to_limit(Direction::Forward);
+            if old_value != textinput.get_content() {
+                // Step 4
+                textinput.clear_selection_to_limit(Direction::Forward);
+            }
         }
 
+        self.update_placeholder_shown_state();
+        self.update_form_states();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 
@@ -382,9 +399,10 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 impl HTMLTextAreaElement {
     pub fn reset(&self) {
         // https://html.spec.whatwg.org/multipage/#the-textarea-element:concept-form-reset-control
-        let mut textinput = self.textinput.borrow_mut();
-        textinput.set_content(self.DefaultValue());
+        self.textinput.borrow_mut().set_content(self.DefaultValue());
         self.value_dirty.set(false);
+        self.update_placeholder_shown_state();
+        self.update_form_states();
     }
 
     #[allow(unrooted_must_root)]
@@ -471,6 +489,13 @@ impl VirtualMethods for HTMLTextAreaElement {
             },
             _ => {},
         }
+
+        match *attr.local_name() {
+            local_name!("disabled") | local_name!("readonly") | local_name!("required") => {
+                self.update_form_states()
+            },
+            _ => {},
+        }
     }
 
     fn bind_to_tree(&self, context: &BindContext) {
@@ -480,6 +505,7 @@ impl VirtualMethods for HTMLTextAreaElement {
 
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.update_form_states();
     }
 
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
@@ -512,6 +538,7 @@ impl VirtualMethods for HTMLTextAreaElement {
         } else {
             el.check_disabled_attribute();
         }
+        self.update_form_states();
     }
 
     // The cloning steps for textarea elements must propagate the raw value
@@ -552,14 +579,25 @@ impl VirtualMethods for HTMLTextAreaElement {
             document_from_node(self).request_focus(self.upcast());
         } else if event.type_() == atom!("keydown") && !event.DefaultPrevented() {
             if let Some(kevent) = event.downcast::<KeyboardEvent>() {
+                // The page can replace the clipboard actions of their shortcuts.
+                let clipboard_action_allowed =
+                    ClipboardEventType::for_shortcut(&kevent.key(), kevent.modifiers())
+                        .map_or(true, |event_type| {
+                            document_from_node(self).fire_clipboard_event(event_type)
+                        });
                 // This can't be inlined, as holding on to textinput.borrow_mut()
                 // during self.implicit_submission will cause a panic.
-                let action = self.textinput.borrow_mut().handle_keydown(kevent);
+                let action = if clipboard_action_allowed {
+                    self.textinput.borrow_mut().handle_keydown(kevent)
+                } else {
+                    KeyReaction::Nothing
+                };
                 match action {
                     KeyReaction::TriggerDefaultAction => (),
                     KeyReaction::DispatchInput => {
                         self.value_dirty.set(true);
                         self.update_placeholder_shown_state();
+                        self.update_form_states();
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     },
@@ -625,4 +663,20 @@ impl FormControl for HTMLTextAreaElement {
     }
 }
 
-impl Validatable for HTMLTextAreaElement {}
+impl Validatable for HTMLTextAreaElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
+    fn is_instance_validatable(&self) -> bool {
+        // https://html.spec.whatwg.org/multipage/#the-textarea-element:barred-from-constraint-validation
+        !self.ReadOnly()
+    }
+
+    fn validate(&self, validate_flags: ValidationFlags) -> bool {
+        // https://html.spec.whatwg.org/multipage/#the-textarea-element:suffering-from-being-missing
+        // TODO: check the constraints behind the other flags.
+        let value_missing = self.Required() && self.Value().is_empty();
+        !(value_missing && validate_flags.contains(ValidationFlags::VALUE_MISSING))
+    }
+}
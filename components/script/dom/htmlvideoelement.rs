@@ -26,6 +26,7 @@ use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
+use net_traits::content_security_policy::Violation;
 use net_traits::image_cache::UsePlaceholder;
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageOrMetadataAvailable};
 use net_traits::image_cache::{ImageResponse, ImageState, PendingImageId};
@@ -167,7 +168,9 @@ impl HTMLVideoElement {
             .credentials_mode(CredentialsMode::Include)
             .use_url_credentials(true)
             .origin(document.origin().immutable().clone())
-            .pipeline_id(Some(document.global().pipeline_id()));
+            .pipeline_id(Some(document.global().pipeline_id()))
+            .csp_list(document.global().get_csp_list())
+            .site_for_cookies(Some(document.global().site_for_cookies()));
 
         // Step 5.
         // This delay must be independent from the ones created by HTMLMediaElement during
@@ -329,6 +332,11 @@ impl FetchResponseListener for PosterFrameFetchContext {
             .notify_pending_response(self.id, FetchResponseMsg::ProcessResponseEOF(response));
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
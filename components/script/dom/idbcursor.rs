@@ -0,0 +1,371 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndex;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::idbcursorwithvalue::IDBCursorWithValue;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource, IDBResultKind};
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{convert_value_to_key, extract_key, key_type_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::Heap;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{CursorDirection, IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBOperation, IndexedDBRecord, IndexedDBSource};
+use std::cell::Cell;
+
+/// The object store or index a cursor iterates over.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum IDBCursorSource {
+    ObjectStore(Dom<IDBObjectStore>),
+    Index(Dom<IDBIndex>),
+}
+
+// https://w3c.github.io/IndexedDB/#cursor-interface
+#[dom_struct]
+pub struct IDBCursor {
+    reflector_: Reflector,
+    source: IDBCursorSource,
+    direction: IDBCursorDirection,
+    range: IndexedDBKeyRange,
+    request: Dom<IDBRequest>,
+    /// The key and primary key of the record the cursor is at.
+    position: DomRefCell<Option<(IndexedDBKeyType, IndexedDBKeyType)>>,
+    #[ignore_malloc_size_of = "mozjs"]
+    key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "mozjs"]
+    primary_key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "mozjs"]
+    value: Heap<JSVal>,
+    got_value: Cell<bool>,
+    /// Whether the cursor only iterates over keys, without values.
+    key_only: bool,
+}
+
+impl IDBCursor {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        source: IDBCursorSource,
+        direction: IDBCursorDirection,
+        range: IndexedDBKeyRange,
+        request: &IDBRequest,
+        key_only: bool,
+    ) -> IDBCursor {
+        IDBCursor {
+            reflector_: Reflector::new(),
+            source: source,
+            direction: direction,
+            range: range,
+            request: Dom::from_ref(request),
+            position: DomRefCell::new(None),
+            key: Heap::default(),
+            primary_key: Heap::default(),
+            value: Heap::default(),
+            got_value: Cell::new(false),
+            key_only: key_only,
+        }
+    }
+
+    /// Create a cursor for `request`, and move it to its first record.
+    /// <https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor>, steps 7-11
+    #[allow(unrooted_must_root)]
+    pub fn open(
+        request: &IDBRequest,
+        source: IDBCursorSource,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) {
+        let global = request.global();
+        let cursor = if key_only {
+            reflect_dom_object(
+                Box::new(IDBCursor::new_inherited(
+                    source, direction, range, request, true,
+                )),
+                &*global,
+                IDBCursorBinding::Wrap,
+            )
+        } else {
+            DomRoot::upcast(IDBCursorWithValue::new(
+                &global, source, direction, range, request,
+            ))
+        };
+        request.set_cursor(&cursor);
+        let range = cursor.range.clone();
+        cursor.iterate(range, 1);
+    }
+
+    pub fn value(&self) -> JSVal {
+        self.value.get()
+    }
+
+    fn transaction(&self) -> DomRoot<IDBTransaction> {
+        self.effective_object_store().transaction()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#cursor-effective-object-store>
+    fn effective_object_store(&self) -> DomRoot<IDBObjectStore> {
+        match self.source {
+            IDBCursorSource::ObjectStore(ref store) => DomRoot::from_ref(&**store),
+            IDBCursorSource::Index(ref index) => index.object_store(),
+        }
+    }
+
+    fn source_operand(&self) -> IndexedDBSource {
+        match self.source {
+            IDBCursorSource::ObjectStore(ref store) => IndexedDBSource::ObjectStore(store.name()),
+            IDBCursorSource::Index(ref index) => index.source(),
+        }
+    }
+
+    fn cursor_direction(&self) -> CursorDirection {
+        match self.direction {
+            IDBCursorDirection::Next => CursorDirection::Next,
+            IDBCursorDirection::Nextunique => CursorDirection::NextUnique,
+            IDBCursorDirection::Prev => CursorDirection::Prev,
+            IDBCursorDirection::Prevunique => CursorDirection::PrevUnique,
+        }
+    }
+
+    fn is_forward(&self) -> bool {
+        match self.direction {
+            IDBCursorDirection::Next | IDBCursorDirection::Nextunique => true,
+            IDBCursorDirection::Prev | IDBCursorDirection::Prevunique => false,
+        }
+    }
+
+    /// Check that the cursor can be used, which requires its transaction to be active and
+    /// its source to still exist.
+    fn check_usable(&self) -> Fallible<DomRoot<IDBTransaction>> {
+        let transaction = self.transaction();
+        if !transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        match self.source {
+            IDBCursorSource::ObjectStore(ref store) => {
+                store.metadata()?;
+            },
+            IDBCursorSource::Index(ref index) => index.check_exists()?,
+        }
+        Ok(transaction)
+    }
+
+    /// Move the cursor over `range`, past `count` records from its position, as in
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>.
+    fn iterate(&self, range: IndexedDBKeyRange, count: u32) {
+        self.got_value.set(false);
+        self.request.reset();
+        let operation = IndexedDBOperation::Iterate {
+            source: self.source_operand(),
+            range: range,
+            direction: self.cursor_direction(),
+            position: self.position.borrow().clone(),
+            count: count,
+        };
+        self.transaction()
+            .execute(&self.request, operation, IDBResultKind::Cursor);
+    }
+
+    /// Move the cursor to `record`, returning whether there is such a record.
+    pub fn set_position(&self, record: Option<IndexedDBRecord>) -> bool {
+        let record = match record {
+            Some(record) => record,
+            None => {
+                *self.position.borrow_mut() = None;
+                self.key.set(UndefinedValue());
+                self.primary_key.set(UndefinedValue());
+                self.value.set(UndefinedValue());
+                return false;
+            },
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        key_type_to_jsval(cx, &record.key, value.handle_mut());
+        self.key.set(value.get());
+        key_type_to_jsval(cx, &record.primary_key, value.handle_mut());
+        self.primary_key.set(value.get());
+        if !self.key_only {
+            StructuredCloneData::Vector(record.value).read(&global, value.handle_mut());
+            self.value.set(value.get());
+        }
+        *self.position.borrow_mut() = Some((record.key, record.primary_key));
+        self.got_value.set(true);
+        true
+    }
+}
+
+impl IDBCursorMethods for IDBCursor {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-source
+    fn Source(&self) -> IDBObjectStoreOrIDBIndex {
+        match self.source {
+            IDBCursorSource::ObjectStore(ref store) => {
+                IDBObjectStoreOrIDBIndex::IDBObjectStore(DomRoot::from_ref(&**store))
+            },
+            IDBCursorSource::Index(ref index) => {
+                IDBObjectStoreOrIDBIndex::IDBIndex(DomRoot::from_ref(&**index))
+            },
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-direction
+    fn Direction(&self) -> IDBCursorDirection {
+        self.direction
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-key
+    fn Key(&self, _cx: JSContext) -> JSVal {
+        self.key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-primarykey
+    fn PrimaryKey(&self, _cx: JSContext) -> JSVal {
+        self.primary_key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-request
+    fn Request(&self) -> DomRoot<IDBRequest> {
+        DomRoot::from_ref(&*self.request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-advance
+    fn Advance(&self, count: u32) -> ErrorResult {
+        // Step 1.
+        if count == 0 {
+            return Err(Error::Type(
+                "The count must be greater than zero".to_owned(),
+            ));
+        }
+        // Steps 2-4.
+        self.check_usable()?;
+        // Step 5.
+        if !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+        // Steps 6-9.
+        self.iterate(self.range.clone(), count);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continue
+    fn Continue(&self, cx: JSContext, key: HandleValue) -> ErrorResult {
+        // Steps 1-3.
+        self.check_usable()?;
+        // Step 4.
+        if !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+        // Step 5.
+        if key.is_undefined() {
+            self.iterate(self.range.clone(), 1);
+            return Ok(());
+        }
+        let key = convert_value_to_key(cx, key)?;
+        let forward = self.is_forward();
+        if let Some((ref position, _)) = *self.position.borrow() {
+            if (forward && key <= *position) || (!forward && key >= *position) {
+                return Err(Error::Data);
+            }
+        }
+        // Steps 6-9. The cursor moves to the first record at or past the key, in the
+        // direction of the cursor, which is the first record of a narrower range.
+        let mut range = self.range.clone();
+        if forward {
+            if range.lower.as_ref().map_or(true, |lower| *lower < key) {
+                range.lower = Some(key);
+                range.lower_open = false;
+            }
+        } else if range.upper.as_ref().map_or(true, |upper| *upper > key) {
+            range.upper = Some(key);
+            range.upper_open = false;
+        }
+        *self.position.borrow_mut() = None;
+        self.iterate(range, 1);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-update
+    fn Update(&self, cx: JSContext, value: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-3.
+        let transaction = self.check_usable()?;
+        if transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        // Steps 4-5.
+        if !self.got_value.get() || self.key_only {
+            return Err(Error::InvalidState);
+        }
+        let primary_key = match *self.position.borrow() {
+            Some((_, ref primary_key)) => primary_key.clone(),
+            None => return Err(Error::InvalidState),
+        };
+        // Steps 6-7.
+        let global = self.global();
+        let serialized = StructuredCloneData::write(*cx, value)?.move_to_arraybuffer();
+        rooted!(in(*cx) let mut clone = UndefinedValue());
+        StructuredCloneData::Vector(serialized.clone()).read(&global, clone.handle_mut());
+        // Step 8.
+        let store = self.effective_object_store();
+        let metadata = store.metadata()?;
+        if let Some(ref key_path) = metadata.key_path {
+            match extract_key(cx, clone.handle(), key_path)? {
+                Some(ref key) if *key == primary_key => {},
+                _ => return Err(Error::Data),
+            }
+        }
+        // Steps 9-10.
+        let operation = IndexedDBOperation::Put {
+            store: store.name(),
+            key: Some(primary_key),
+            value: serialized,
+            index_keys: IDBObjectStore::index_keys(cx, clone.handle(), &metadata),
+            no_overwrite: false,
+        };
+        Ok(transaction.make_request(
+            IDBRequestSource::Cursor(Dom::from_ref(self)),
+            operation,
+            IDBResultKind::Key,
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-delete
+    fn Delete(&self) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-3.
+        let transaction = self.check_usable()?;
+        if transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        // Steps 4-5.
+        if !self.got_value.get() || self.key_only {
+            return Err(Error::InvalidState);
+        }
+        let primary_key = match *self.position.borrow() {
+            Some((_, ref primary_key)) => primary_key.clone(),
+            None => return Err(Error::InvalidState),
+        };
+        // Steps 6-7.
+        let operation = IndexedDBOperation::Delete(
+            self.effective_object_store().name(),
+            IndexedDBKeyRange::only(primary_key),
+        );
+        Ok(transaction.make_request(
+            IDBRequestSource::Cursor(Dom::from_ref(self)),
+            operation,
+            IDBResultKind::Undefined,
+        ))
+    }
+}
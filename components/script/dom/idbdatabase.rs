@@ -0,0 +1,332 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBDatabaseMethods;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBObjectStoreParameters;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::indexed_db::{is_valid_key_path, key_path_from_union};
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcReceiver;
+use ipc_channel::router::ROUTER;
+use net_traits::indexeddb_thread::{DatabaseMetadata, IndexMetadata, IndexedDBOperation};
+use net_traits::indexeddb_thread::{IndexedDBConnectionMsg, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreMetadata};
+use net_traits::IpcSend;
+use std::cell::Cell;
+use uuid::Uuid;
+
+// https://w3c.github.io/IndexedDB/#database-interface
+#[dom_struct]
+pub struct IDBDatabase {
+    eventtarget: EventTarget,
+    /// The id of the connection in the IndexedDB thread.
+    #[ignore_malloc_size_of = "Defined in uuid"]
+    id: Uuid,
+    name: DOMString,
+    version: Cell<u64>,
+    /// The object stores of the database, as seen by this connection.
+    object_stores: DomRefCell<Vec<ObjectStoreMetadata>>,
+    /// <https://w3c.github.io/IndexedDB/#connection-close-pending-flag>
+    close_pending: Cell<bool>,
+    upgrade_transaction: MutNullableDom<IDBTransaction>,
+}
+
+impl IDBDatabase {
+    fn new_inherited(name: DOMString, metadata: DatabaseMetadata, id: Uuid) -> IDBDatabase {
+        IDBDatabase {
+            eventtarget: EventTarget::new_inherited(),
+            id: id,
+            name: name,
+            version: Cell::new(metadata.version),
+            object_stores: DomRefCell::new(metadata.object_stores),
+            close_pending: Cell::new(false),
+            upgrade_transaction: Default::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        name: DOMString,
+        metadata: DatabaseMetadata,
+        id: Uuid,
+        receiver: IpcReceiver<IndexedDBConnectionMsg>,
+    ) -> DomRoot<IDBDatabase> {
+        let connection = reflect_dom_object(
+            Box::new(IDBDatabase::new_inherited(name, metadata, id)),
+            global,
+            IDBDatabaseBinding::Wrap,
+        );
+        global.track_indexeddb_connection(&connection);
+
+        let task_source = global.dom_manipulation_task_source();
+        let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+        let trusted = Trusted::new(&*connection);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let IndexedDBConnectionMsg::VersionChange(old_version, new_version) =
+                    message.to().unwrap();
+                let trusted = trusted.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(indexeddb_versionchange: move || {
+                        trusted.root().handle_versionchange(old_version, new_version);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        connection
+    }
+
+    /// Fire a versionchange event at the connection, unless it is closing, and tell the
+    /// IndexedDB thread it was fired.
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>, steps 4-5
+    fn handle_versionchange(&self, old_version: u64, new_version: Option<u64>) {
+        let global = self.global();
+        if !self.close_pending.get() {
+            let event = IDBVersionChangeEvent::new(
+                &global,
+                atom!("versionchange"),
+                EventBubbles::DoesNotBubble,
+                EventCancelable::NotCancelable,
+                old_version,
+                new_version,
+            );
+            event.upcast::<Event>().fire(self.upcast());
+        }
+        let _ = global
+            .resource_threads()
+            .send(IndexedDBThreadMsg::VersionChangeFired(self.id));
+    }
+
+    pub fn set_version(&self, version: u64) {
+        self.version.set(version);
+    }
+
+    pub fn set_upgrade_transaction(&self, transaction: Option<&IDBTransaction>) {
+        self.upgrade_transaction.set(transaction);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.close_pending.get()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    pub fn close_connection(&self) {
+        if self.close_pending.get() {
+            return;
+        }
+        self.close_pending.set(true);
+        let global = self.global();
+        let _ = global
+            .resource_threads()
+            .send(IndexedDBThreadMsg::CloseConnection(self.id));
+        global.untrack_indexeddb_connection(self);
+    }
+
+    /// The names of the object stores of the database, sorted.
+    pub fn object_store_names(&self) -> Vec<DOMString> {
+        let mut names: Vec<DOMString> = self
+            .object_stores
+            .borrow()
+            .iter()
+            .map(|store| DOMString::from(store.name.clone()))
+            .collect();
+        names.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+        names
+    }
+
+    pub fn object_store(&self, name: &str) -> Option<ObjectStoreMetadata> {
+        self.object_stores
+            .borrow()
+            .iter()
+            .find(|store| store.name == name)
+            .cloned()
+    }
+
+    pub fn add_index(&self, store: &str, index: IndexMetadata) {
+        if let Some(store) = self
+            .object_stores
+            .borrow_mut()
+            .iter_mut()
+            .find(|metadata| metadata.name == store)
+        {
+            store.indexes.push(index);
+        }
+    }
+
+    pub fn remove_index(&self, store: &str, index: &str) {
+        if let Some(store) = self
+            .object_stores
+            .borrow_mut()
+            .iter_mut()
+            .find(|metadata| metadata.name == store)
+        {
+            store.indexes.retain(|metadata| metadata.name != index);
+        }
+    }
+
+    /// The upgrade transaction of the connection, if it can run operations.
+    fn active_upgrade_transaction(&self) -> Fallible<DomRoot<IDBTransaction>> {
+        let transaction = self.upgrade_transaction.get().ok_or(Error::InvalidState)?;
+        if !transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(transaction)
+    }
+}
+
+impl IDBDatabaseMethods for IDBDatabase {
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-version
+    fn Version(&self) -> u64 {
+        self.version.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        DOMStringList::new(&self.global(), self.object_store_names())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-transaction
+    fn Transaction(
+        &self,
+        store_names: StringOrStringSequence,
+        mode: IDBTransactionMode,
+    ) -> Fallible<DomRoot<IDBTransaction>> {
+        // Step 1.
+        if self.upgrade_transaction.get().is_some() {
+            return Err(Error::InvalidState);
+        }
+        // Step 2.
+        if self.close_pending.get() {
+            return Err(Error::InvalidState);
+        }
+        // Steps 3-4.
+        let mut scope = match store_names {
+            StringOrStringSequence::String(name) => vec![name],
+            StringOrStringSequence::StringSequence(names) => names,
+        };
+        scope.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+        scope.dedup();
+        if scope.iter().any(|name| self.object_store(name).is_none()) {
+            return Err(Error::NotFound);
+        }
+        // Step 5.
+        if scope.is_empty() {
+            return Err(Error::InvalidAccess);
+        }
+        // Step 6.
+        if mode == IDBTransactionMode::Versionchange {
+            return Err(Error::Type(
+                "Transactions cannot be created in versionchange mode".to_owned(),
+            ));
+        }
+        // Steps 7-8.
+        Ok(IDBTransaction::new(&self.global(), self, mode, scope))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-close
+    fn Close(&self) {
+        self.close_connection();
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-createobjectstore
+    fn CreateObjectStore(
+        &self,
+        name: DOMString,
+        options: &IDBObjectStoreParameters,
+    ) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Steps 1-4.
+        let transaction = self.active_upgrade_transaction()?;
+        // Steps 5-6.
+        let key_path = options.keyPath.as_ref().map(key_path_from_union);
+        if let Some(ref key_path) = key_path {
+            if !is_valid_key_path(key_path) {
+                return Err(Error::Syntax);
+            }
+        }
+        // Step 7.
+        if self.object_store(&name).is_some() {
+            return Err(Error::Constraint);
+        }
+        // Step 8.
+        let auto_increment = options.autoIncrement;
+        match key_path {
+            Some(KeyPath::String(ref key_path)) if auto_increment && key_path.is_empty() => {
+                return Err(Error::InvalidAccess);
+            },
+            Some(KeyPath::Sequence(_)) if auto_increment => {
+                return Err(Error::InvalidAccess);
+            },
+            _ => {},
+        }
+        // Step 9.
+        let operation = IndexedDBOperation::CreateObjectStore(
+            name.to_string(),
+            key_path.clone(),
+            auto_increment,
+        );
+        transaction.change_schema(operation);
+        self.object_stores.borrow_mut().push(ObjectStoreMetadata {
+            name: name.to_string(),
+            key_path: key_path,
+            auto_increment: auto_increment,
+            indexes: vec![],
+        });
+        // Step 10.
+        transaction.ObjectStore(name)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-deleteobjectstore
+    fn DeleteObjectStore(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-4.
+        let transaction = self.active_upgrade_transaction()?;
+        // Step 5.
+        if self.object_store(&name).is_none() {
+            return Err(Error::NotFound);
+        }
+        // Step 6.
+        self.object_stores
+            .borrow_mut()
+            .retain(|store| *store.name != *name);
+        transaction.forget_object_store(&name);
+        // Step 7.
+        transaction.change_schema(IndexedDBOperation::DeleteObjectStore(name.to_string()));
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onclose
+    event_handler!(close, GetOnclose, SetOnclose);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onversionchange
+    event_handler!(versionchange, GetOnversionchange, SetOnversionchange);
+}
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBFactoryBinding;
+use crate::dom::bindings::codegen::Bindings::IDBFactoryBinding::IDBFactoryMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::indexed_db::convert_value_to_key;
+use crate::script_runtime::JSContext;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use js::rust::HandleValue;
+use std::cmp::Ordering;
+
+// https://w3c.github.io/IndexedDB/#factory-interface
+#[dom_struct]
+pub struct IDBFactory {
+    reflector_: Reflector,
+}
+
+impl IDBFactory {
+    fn new_inherited() -> IDBFactory {
+        IDBFactory {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBFactory> {
+        reflect_dom_object(
+            Box::new(IDBFactory::new_inherited()),
+            global,
+            IDBFactoryBinding::Wrap,
+        )
+    }
+
+    /// Check that the global can access databases, which requires a tuple origin.
+    fn check_origin(&self) -> Fallible<()> {
+        if !self.global().origin().immutable().is_tuple() {
+            return Err(Error::Security);
+        }
+        Ok(())
+    }
+}
+
+impl IDBFactoryMethods for IDBFactory {
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-open
+    fn Open(&self, name: DOMString, version: Option<u64>) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        // Step 1.
+        if version == Some(0) {
+            return Err(Error::Type(
+                "The version must be greater than zero".to_owned(),
+            ));
+        }
+        // Steps 2-3.
+        self.check_origin()?;
+        // Steps 4-5.
+        let global = self.global();
+        let request = IDBOpenDBRequest::new(&global);
+        let trusted = Trusted::new(&*request);
+        let name = String::from(name);
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(open_database: move || {
+                trusted.root().open_database(name, version);
+            }),
+            &global,
+        );
+        // Step 6.
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-deletedatabase
+    fn DeleteDatabase(&self, name: DOMString) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        // Steps 1-2.
+        self.check_origin()?;
+        // Steps 3-4.
+        let global = self.global();
+        let request = IDBOpenDBRequest::new(&global);
+        let trusted = Trusted::new(&*request);
+        let name = String::from(name);
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(delete_database: move || {
+                trusted.root().delete_database(name);
+            }),
+            &global,
+        );
+        // Step 5.
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-cmp
+    fn Cmp(&self, cx: JSContext, first: HandleValue, second: HandleValue) -> Fallible<i16> {
+        let first = convert_value_to_key(cx, first)?;
+        let second = convert_value_to_key(cx, second)?;
+        Ok(match first.cmp(&second) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        })
+    }
+}
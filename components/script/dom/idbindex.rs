@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBIndexBinding;
+use crate::dom::bindings::codegen::Bindings::IDBIndexBinding::IDBIndexMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::{IDBCursor, IDBCursorSource};
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource, IDBResultKind};
+use crate::indexed_db::{convert_value_to_key_range, key_path_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexMetadata, IndexedDBOperation, IndexedDBSource};
+
+// https://w3c.github.io/IndexedDB/#index-interface
+#[dom_struct]
+pub struct IDBIndex {
+    reflector_: Reflector,
+    object_store: Dom<IDBObjectStore>,
+    name: DOMString,
+    index: IndexMetadata,
+}
+
+impl IDBIndex {
+    fn new_inherited(object_store: &IDBObjectStore, index: IndexMetadata) -> IDBIndex {
+        IDBIndex {
+            reflector_: Reflector::new(),
+            object_store: Dom::from_ref(object_store),
+            name: DOMString::from(index.name.clone()),
+            index: index,
+        }
+    }
+
+    /// Create a handle to the index of `object_store` with the given name, which must exist.
+    pub fn new(
+        global: &GlobalScope,
+        object_store: &IDBObjectStore,
+        name: DOMString,
+    ) -> DomRoot<IDBIndex> {
+        let index = object_store
+            .metadata()
+            .ok()
+            .and_then(|metadata| {
+                metadata
+                    .indexes
+                    .into_iter()
+                    .find(|index| *index.name == *name)
+            })
+            .expect("Handle to an index that does not exist");
+        reflect_dom_object(
+            Box::new(IDBIndex::new_inherited(object_store, index)),
+            global,
+            IDBIndexBinding::Wrap,
+        )
+    }
+
+    pub fn object_store(&self) -> DomRoot<IDBObjectStore> {
+        DomRoot::from_ref(&*self.object_store)
+    }
+
+    pub fn source(&self) -> IndexedDBSource {
+        IndexedDBSource::Index(self.object_store.name(), self.index.name.clone())
+    }
+
+    /// Check that the index still exists.
+    pub fn check_exists(&self) -> Fallible<()> {
+        let metadata = self.object_store.metadata()?;
+        if !metadata
+            .indexes
+            .iter()
+            .any(|index| index.name == self.index.name)
+        {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// Check that records can be read from the index.
+    fn check_readable(&self) -> Fallible<()> {
+        self.check_exists()?;
+        if !self.object_store.transaction().is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(())
+    }
+
+    fn make_request(
+        &self,
+        operation: IndexedDBOperation,
+        kind: IDBResultKind,
+    ) -> DomRoot<IDBRequest> {
+        self.object_store.transaction().make_request(
+            IDBRequestSource::Index(Dom::from_ref(self)),
+            operation,
+            kind,
+        )
+    }
+
+    fn open_cursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let request = IDBRequest::new(
+            &self.global(),
+            IDBRequestSource::Index(Dom::from_ref(self)),
+            &self.object_store.transaction(),
+        );
+        IDBCursor::open(
+            &request,
+            IDBCursorSource::Index(Dom::from_ref(self)),
+            range,
+            direction,
+            key_only,
+        );
+        Ok(request)
+    }
+}
+
+impl IDBIndexMethods for IDBIndex {
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-objectstore
+    fn ObjectStore(&self) -> DomRoot<IDBObjectStore> {
+        self.object_store()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        key_path_to_jsval(cx, &self.index.key_path, value.handle_mut());
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-multientry
+    fn MultiEntry(&self) -> bool {
+        self.index.multi_entry
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-unique
+    fn Unique(&self) -> bool {
+        self.index.unique
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        let operation = IndexedDBOperation::Get(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::Value))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        let operation = IndexedDBOperation::Get(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::PrimaryKey))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count != 0);
+        let operation = IndexedDBOperation::GetAll(self.source(), range, count);
+        Ok(self.make_request(operation, IDBResultKind::Values))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count != 0);
+        let operation = IndexedDBOperation::GetAll(self.source(), range, count);
+        Ok(self.make_request(operation, IDBResultKind::PrimaryKeys))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let operation = IndexedDBOperation::Count(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::Count))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, true)
+    }
+}
@@ -0,0 +1,543 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding::IDBIndexParameters;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding::IDBObjectStoreMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::domexception::DOMErrorName;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::{IDBCursor, IDBCursorSource};
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource, IDBResultKind};
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{convert_value_to_key, convert_value_to_key_range, could_inject_key};
+use crate::indexed_db::{extract_index_keys, extract_key, inject_key_into_value};
+use crate::indexed_db::{is_valid_key_path, key_path_from_union, key_path_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, NullValue, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexKeys, IndexMetadata, IndexedDBKeyRange};
+use net_traits::indexeddb_thread::{IndexedDBKeyType, IndexedDBOperation};
+use net_traits::indexeddb_thread::{IndexedDBRecord, IndexedDBSource};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreMetadata};
+use std::collections::HashMap;
+
+// https://w3c.github.io/IndexedDB/#object-store-interface
+#[dom_struct]
+pub struct IDBObjectStore {
+    reflector_: Reflector,
+    name: DOMString,
+    transaction: Dom<IDBTransaction>,
+    key_path: Option<KeyPath>,
+    auto_increment: bool,
+    indexes: DomRefCell<HashMap<DOMString, Dom<IDBIndex>>>,
+}
+
+impl IDBObjectStore {
+    fn new_inherited(
+        name: DOMString,
+        transaction: &IDBTransaction,
+        metadata: ObjectStoreMetadata,
+    ) -> IDBObjectStore {
+        IDBObjectStore {
+            reflector_: Reflector::new(),
+            name: name,
+            transaction: Dom::from_ref(transaction),
+            key_path: metadata.key_path,
+            auto_increment: metadata.auto_increment,
+            indexes: DomRefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Create a handle to the object store with the given name, which must exist.
+    pub fn new(
+        global: &GlobalScope,
+        name: DOMString,
+        transaction: &IDBTransaction,
+    ) -> DomRoot<IDBObjectStore> {
+        let metadata = transaction
+            .db()
+            .object_store(&name)
+            .expect("Handle to an object store that does not exist");
+        reflect_dom_object(
+            Box::new(IDBObjectStore::new_inherited(name, transaction, metadata)),
+            global,
+            IDBObjectStoreBinding::Wrap,
+        )
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn transaction(&self) -> DomRoot<IDBTransaction> {
+        DomRoot::from_ref(&*self.transaction)
+    }
+
+    /// The current state of the object store, or an `InvalidStateError` if it was deleted.
+    pub fn metadata(&self) -> Fallible<ObjectStoreMetadata> {
+        self.transaction
+            .db()
+            .object_store(&self.name)
+            .ok_or(Error::InvalidState)
+    }
+
+    /// Check that records can be read from the object store.
+    fn check_readable(&self) -> Fallible<ObjectStoreMetadata> {
+        let metadata = self.metadata()?;
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(metadata)
+    }
+
+    /// Check that records can be written to the object store.
+    fn check_writable(&self) -> Fallible<ObjectStoreMetadata> {
+        let metadata = self.check_readable()?;
+        if self.transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        Ok(metadata)
+    }
+
+    fn source(&self) -> IndexedDBSource {
+        IndexedDBSource::ObjectStore(self.name())
+    }
+
+    /// The keys `value` has in each index of the object store.
+    pub fn index_keys(
+        cx: JSContext,
+        value: HandleValue,
+        metadata: &ObjectStoreMetadata,
+    ) -> IndexKeys {
+        metadata
+            .indexes
+            .iter()
+            .map(|index| (index.name.clone(), extract_index_keys(cx, value, index)))
+            .collect()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#add-or-put>
+    fn add_or_put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+        no_overwrite: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        let metadata = self.check_writable()?;
+        // Step 6.
+        if self.key_path.is_some() && !key.is_undefined() {
+            return Err(Error::Data);
+        }
+        // Step 7.
+        if self.key_path.is_none() && !self.auto_increment && key.is_undefined() {
+            return Err(Error::Data);
+        }
+        // Step 8.
+        let mut key = if key.is_undefined() {
+            None
+        } else {
+            Some(convert_value_to_key(cx, key)?)
+        };
+        // Steps 9-10.
+        let global = self.global();
+        let serialized = StructuredCloneData::write(*cx, value)?.move_to_arraybuffer();
+        rooted!(in(*cx) let mut clone = UndefinedValue());
+        StructuredCloneData::Vector(serialized.clone()).read(&global, clone.handle_mut());
+
+        // Step 11.
+        let mut inject_into = None;
+        if let Some(ref key_path) = self.key_path {
+            match extract_key(cx, clone.handle(), key_path)? {
+                Some(extracted) => key = Some(extracted),
+                None => {
+                    let key_path = match *key_path {
+                        KeyPath::String(ref key_path) if self.auto_increment => key_path,
+                        _ => return Err(Error::Data),
+                    };
+                    if !could_inject_key(cx, clone.handle(), key_path) {
+                        return Err(Error::Data);
+                    }
+                    inject_into = Some(key_path.clone());
+                },
+            }
+        }
+
+        // Step 12.
+        let transaction = self.transaction();
+        let request = IDBRequest::new(
+            &global,
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            &transaction,
+        );
+
+        // A key generated for a store with inline keys is part of the stored value, so it
+        // is generated before the value is stored.
+        if let Some(key_path) = inject_into {
+            transaction.store_with_generated_key(
+                &request,
+                self,
+                serialized,
+                key_path,
+                no_overwrite,
+            );
+            return Ok(request);
+        }
+
+        let operation = IndexedDBOperation::Put {
+            store: self.name(),
+            key: key,
+            value: serialized,
+            index_keys: IDBObjectStore::index_keys(cx, clone.handle(), &metadata),
+            no_overwrite: no_overwrite,
+        };
+        transaction.execute(&request, operation, IDBResultKind::Key);
+        Ok(request)
+    }
+
+    fn make_request(
+        &self,
+        operation: IndexedDBOperation,
+        kind: IDBResultKind,
+    ) -> DomRoot<IDBRequest> {
+        self.transaction.make_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            operation,
+            kind,
+        )
+    }
+
+    fn open_cursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let request = IDBRequest::new(
+            &self.global(),
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            &self.transaction,
+        );
+        IDBCursor::open(
+            &request,
+            IDBCursorSource::ObjectStore(Dom::from_ref(self)),
+            range,
+            direction,
+            key_only,
+        );
+        Ok(request)
+    }
+
+    /// The operation storing a value with a key generated for it, which is injected into
+    /// the value at `key_path`.
+    pub fn store_with_key(
+        &self,
+        value: Vec<u8>,
+        key: IndexedDBKeyType,
+        key_path: &str,
+        no_overwrite: bool,
+    ) -> Result<IndexedDBOperation, DOMErrorName> {
+        let metadata = self
+            .metadata()
+            .map_err(|_| DOMErrorName::InvalidStateError)?;
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut clone = UndefinedValue());
+        StructuredCloneData::Vector(value).read(&global, clone.handle_mut());
+        inject_key_into_value(cx, clone.handle(), &key, key_path)
+            .map_err(|_| DOMErrorName::DataError)?;
+        let value = StructuredCloneData::write(*cx, clone.handle())
+            .map_err(|_| DOMErrorName::DataCloneError)?
+            .move_to_arraybuffer();
+        Ok(IndexedDBOperation::Put {
+            store: self.name(),
+            key: Some(key),
+            value: value,
+            index_keys: IDBObjectStore::index_keys(cx, clone.handle(), &metadata),
+            no_overwrite: no_overwrite,
+        })
+    }
+
+    /// The operation reading the records of the object store, to create an index.
+    pub fn read_all_records(&self) -> IndexedDBOperation {
+        IndexedDBOperation::GetAll(self.source(), IndexedDBKeyRange::unbounded(), None)
+    }
+
+    /// The operation creating an index, with the keys `records` of the object store have
+    /// in it.
+    pub fn index_creation(
+        &self,
+        index: IndexMetadata,
+        records: Vec<IndexedDBRecord>,
+    ) -> IndexedDBOperation {
+        let global = self.global();
+        let cx = global.get_cx();
+        let entries = records
+            .into_iter()
+            .map(|record| {
+                rooted!(in(*cx) let mut value = UndefinedValue());
+                StructuredCloneData::Vector(record.value).read(&global, value.handle_mut());
+                (
+                    record.primary_key,
+                    extract_index_keys(cx, value.handle(), &index),
+                )
+            })
+            .collect();
+        IndexedDBOperation::CreateIndex(self.name(), index, entries)
+    }
+
+    fn index_handle(&self, name: DOMString) -> DomRoot<IDBIndex> {
+        let mut indexes = self.indexes.borrow_mut();
+        if let Some(index) = indexes.get(&name) {
+            return DomRoot::from_ref(&**index);
+        }
+        let index = IDBIndex::new(&self.global(), self, name.clone());
+        indexes.insert(name, Dom::from_ref(&*index));
+        index
+    }
+}
+
+impl IDBObjectStoreMethods for IDBObjectStore {
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut value = NullValue());
+        if let Some(ref key_path) = self.key_path {
+            key_path_to_jsval(cx, key_path, value.handle_mut());
+        }
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-indexnames
+    fn IndexNames(&self) -> DomRoot<DOMStringList> {
+        let mut names: Vec<DOMString> = self
+            .metadata()
+            .map(|metadata| metadata.indexes)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|index| DOMString::from(index.name))
+            .collect();
+        names.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-transaction
+    fn Transaction(&self) -> DomRoot<IDBTransaction> {
+        self.transaction()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-autoincrement
+    fn AutoIncrement(&self) -> bool {
+        self.auto_increment
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-put
+    fn Put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.add_or_put(cx, value, key, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-add
+    fn Add(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.add_or_put(cx, value, key, true)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-delete
+    fn Delete(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        let operation = IndexedDBOperation::Delete(self.name(), range);
+        Ok(self.make_request(operation, IDBResultKind::Undefined))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-clear
+    fn Clear(&self) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let operation = IndexedDBOperation::Clear(self.name());
+        Ok(self.make_request(operation, IDBResultKind::Undefined))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        let operation = IndexedDBOperation::Get(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::Value))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        let operation = IndexedDBOperation::Get(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::PrimaryKey))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count != 0);
+        let operation = IndexedDBOperation::GetAll(self.source(), range, count);
+        Ok(self.make_request(operation, IDBResultKind::Values))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count != 0);
+        let operation = IndexedDBOperation::GetAll(self.source(), range, count);
+        Ok(self.make_request(operation, IDBResultKind::PrimaryKeys))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_readable()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let operation = IndexedDBOperation::Count(self.source(), range);
+        Ok(self.make_request(operation, IDBResultKind::Count))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, true)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-index
+    fn Index(&self, name: DOMString) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-4.
+        let metadata = self.metadata()?;
+        if self.transaction.is_finished() {
+            return Err(Error::InvalidState);
+        }
+        // Step 5.
+        if !metadata.indexes.iter().any(|index| *index.name == *name) {
+            return Err(Error::NotFound);
+        }
+        // Step 6.
+        Ok(self.index_handle(name))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex
+    fn CreateIndex(
+        &self,
+        name: DOMString,
+        key_path: StringOrStringSequence,
+        options: &IDBIndexParameters,
+    ) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-3.
+        if self.transaction.mode() != IDBTransactionMode::Versionchange {
+            return Err(Error::InvalidState);
+        }
+        let metadata = self.metadata()?;
+        // Step 4.
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        // Step 5.
+        if metadata.indexes.iter().any(|index| *index.name == *name) {
+            return Err(Error::Constraint);
+        }
+        // Step 6.
+        let key_path = key_path_from_union(&key_path);
+        if !is_valid_key_path(&key_path) {
+            return Err(Error::Syntax);
+        }
+        // Steps 7-8.
+        if let KeyPath::Sequence(_) = key_path {
+            if options.multiEntry {
+                return Err(Error::InvalidAccess);
+            }
+        }
+        // Steps 9-10.
+        let index = IndexMetadata {
+            name: name.to_string(),
+            key_path: key_path,
+            unique: options.unique,
+            multi_entry: options.multiEntry,
+        };
+        self.transaction.create_index(self, index.clone());
+        self.transaction.db().add_index(&self.name, index);
+        // Step 11.
+        Ok(self.index_handle(name))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-deleteindex
+    fn DeleteIndex(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-3.
+        if self.transaction.mode() != IDBTransactionMode::Versionchange {
+            return Err(Error::InvalidState);
+        }
+        let metadata = self.metadata()?;
+        // Step 4.
+        if !self.transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        // Step 5.
+        if !metadata.indexes.iter().any(|index| *index.name == *name) {
+            return Err(Error::NotFound);
+        }
+        // Steps 6-7.
+        self.transaction.db().remove_index(&self.name, &name);
+        self.indexes.borrow_mut().remove(&name);
+        let operation = IndexedDBOperation::DeleteIndex(self.name(), name.to_string());
+        self.transaction.change_schema(operation);
+        Ok(())
+    }
+}
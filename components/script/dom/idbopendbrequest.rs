@@ -0,0 +1,242 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding;
+use crate::dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding::IDBOpenDBRequestMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::DOMErrorName;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbrequest::IDBRequest;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use ipc_channel::ipc::{self, IpcReceiver};
+use ipc_channel::router::ROUTER;
+use js::jsval::{ObjectValue, UndefinedValue};
+use net_traits::indexeddb_thread::{DatabaseMetadata, IndexedDBConnectionMsg};
+use net_traits::indexeddb_thread::{IndexedDBOperation, IndexedDBThreadMsg};
+use net_traits::IpcSend;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use uuid::Uuid;
+
+// https://w3c.github.io/IndexedDB/#idbopendbrequest
+#[dom_struct]
+pub struct IDBOpenDBRequest {
+    idbrequest: IDBRequest,
+}
+
+impl IDBOpenDBRequest {
+    fn new_inherited() -> IDBOpenDBRequest {
+        IDBOpenDBRequest {
+            idbrequest: IDBRequest::new_inherited(None, None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBOpenDBRequest> {
+        reflect_dom_object(
+            Box::new(IDBOpenDBRequest::new_inherited()),
+            global,
+            IDBOpenDBRequestBinding::Wrap,
+        )
+    }
+
+    fn set_connection(&self, connection: &IDBDatabase) {
+        let value = ObjectValue(connection.reflector().get_jsobject().get());
+        self.idbrequest.set_result(value);
+    }
+
+    /// Call `callback` with the first message sent over `receiver` in a task.
+    fn route_indexeddb_reply<T, F>(&self, receiver: IpcReceiver<T>, callback: F)
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+        F: FnOnce(&IDBOpenDBRequest, T) + Send + 'static,
+    {
+        let global = self.global();
+        let task_source = global.dom_manipulation_task_source();
+        let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+        let trusted = Trusted::new(self);
+        let mut callback = Some(callback);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let callback = match callback.take() {
+                    Some(callback) => callback,
+                    None => return,
+                };
+                let trusted = trusted.clone();
+                let reply: T = message.to().unwrap();
+                let _ = task_source.queue_with_canceller(
+                    task!(handle_indexeddb_reply: move || {
+                        callback(&trusted.root(), reply);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+    }
+
+    /// Run the steps of <https://w3c.github.io/IndexedDB/#open-a-database> that follow the
+    /// creation of the request, and fire the resulting event at it.
+    pub fn open_database(&self, name: String, version: Option<u64>) {
+        let origin = self.global().origin().immutable().clone();
+        let id = Uuid::new_v4();
+        let (sender, receiver) = ipc::channel().unwrap();
+        let (connection_sender, connection_receiver) = ipc::channel().unwrap();
+        let message = IndexedDBThreadMsg::Open(sender, origin, name.clone(), id, connection_sender);
+        self.route_indexeddb_reply(receiver, move |request, metadata| {
+            request.database_opened(name, version, metadata, id, connection_receiver)
+        });
+        self.global().resource_threads().send(message).unwrap();
+    }
+
+    fn database_opened(
+        &self,
+        name: String,
+        version: Option<u64>,
+        metadata: DatabaseMetadata,
+        id: Uuid,
+        receiver: IpcReceiver<IndexedDBConnectionMsg>,
+    ) {
+        let global = self.global();
+        // Steps 4-5. A database that does not exist yet is created with version 0.
+        let version = version.unwrap_or(cmp::max(metadata.version, 1));
+        // Step 6.
+        if version < metadata.version {
+            let _ = global
+                .resource_threads()
+                .send(IndexedDBThreadMsg::CloseConnection(id));
+            self.idbrequest.set_error(DOMErrorName::VersionError);
+            self.upcast::<EventTarget>()
+                .fire_bubbling_cancelable_event(atom!("error"));
+            return;
+        }
+
+        // Step 7.
+        let old_version = metadata.version;
+        let connection = IDBDatabase::new(&global, DOMString::from(name), metadata, id, receiver);
+
+        // Step 10.
+        if version > old_version {
+            self.upgrade_database(&connection, old_version, version);
+            return;
+        }
+
+        self.set_connection(&connection);
+        self.upcast::<EventTarget>().fire_event(atom!("success"));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#upgrade-a-database>
+    fn upgrade_database(&self, connection: &IDBDatabase, old_version: u64, version: u64) {
+        let global = self.global();
+        // Steps 2-3.
+        let names = connection.object_store_names();
+        let transaction = IDBTransaction::new(
+            &global,
+            connection,
+            IDBTransactionMode::Versionchange,
+            names,
+        );
+        transaction.set_open_request(self);
+        connection.set_upgrade_transaction(Some(&transaction));
+
+        // Steps 5-6.
+        transaction.change_schema(IndexedDBOperation::SetVersion(version));
+        connection.set_version(version);
+
+        // Steps 7-9.
+        self.set_connection(connection);
+        self.idbrequest.set_transaction(Some(&transaction));
+
+        // Step 10.
+        let event = IDBVersionChangeEvent::new(
+            &global,
+            atom!("upgradeneeded"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            Some(version),
+        );
+        transaction.set_active(true);
+        event.upcast::<Event>().fire(self.upcast());
+        transaction.set_active(false);
+        transaction.maybe_commit();
+    }
+
+    /// Finish opening the database once its upgrade transaction is finished.
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>, steps 10.4-10.6
+    pub fn upgrade_finished(&self, connection: &IDBDatabase, committed: bool) {
+        self.idbrequest.set_transaction(None);
+        connection.set_upgrade_transaction(None);
+        if committed && !connection.is_closed() {
+            self.set_connection(connection);
+            self.upcast::<EventTarget>().fire_event(atom!("success"));
+        } else {
+            connection.close_connection();
+            self.idbrequest.set_error(DOMErrorName::AbortError);
+            self.upcast::<EventTarget>()
+                .fire_bubbling_cancelable_event(atom!("error"));
+        }
+    }
+
+    /// Run the steps of <https://w3c.github.io/IndexedDB/#delete-a-database> that follow the
+    /// creation of the request, and fire the resulting event at it.
+    pub fn delete_database(&self, name: String) {
+        let origin = self.global().origin().immutable().clone();
+        let (sender, receiver) = ipc::channel().unwrap();
+        let (blocked_sender, blocked_receiver) = ipc::channel().unwrap();
+        self.route_indexeddb_reply(receiver, |request, old_version| {
+            request.database_deleted(old_version.unwrap_or(0))
+        });
+        self.route_indexeddb_reply(blocked_receiver, |request, old_version| {
+            request.deletion_blocked(old_version)
+        });
+        let message = IndexedDBThreadMsg::DeleteDatabase(sender, blocked_sender, origin, name);
+        self.global().resource_threads().send(message).unwrap();
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>, step 6
+    fn deletion_blocked(&self, old_version: u64) {
+        let event = IDBVersionChangeEvent::new(
+            &self.global(),
+            atom!("blocked"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            None,
+        );
+        event.upcast::<Event>().fire(self.upcast());
+    }
+
+    fn database_deleted(&self, old_version: u64) {
+        let global = self.global();
+        self.idbrequest.set_result(UndefinedValue());
+        let event = IDBVersionChangeEvent::new(
+            &global,
+            atom!("success"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            None,
+        );
+        event.upcast::<Event>().fire(self.upcast());
+    }
+}
+
+impl IDBOpenDBRequestMethods for IDBOpenDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onblocked
+    event_handler!(blocked, GetOnblocked, SetOnblocked);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onupgradeneeded
+    event_handler!(upgradeneeded, GetOnupgradeneeded, SetOnupgradeneeded);
+}
@@ -0,0 +1,278 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::IDBRequestMethods;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::IDBRequestReadyState;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::event::EventStatus;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{key_type_to_jsval, values_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::Heap;
+use js::jsval::{JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::MutableHandleValue;
+use net_traits::indexeddb_thread::{IndexedDBKeyType, IndexedDBOperationResult};
+use std::cell::Cell;
+
+/// The object a request was made against.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum IDBRequestSource {
+    ObjectStore(Dom<IDBObjectStore>),
+    Index(Dom<IDBIndex>),
+    Cursor(Dom<IDBCursor>),
+}
+
+/// How the result of the operation of a request is exposed as the result of the request.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum IDBResultKind {
+    Undefined,
+    /// The key of a new record.
+    Key,
+    /// The value of the first record, or undefined.
+    Value,
+    /// The primary key of the first record, or undefined.
+    PrimaryKey,
+    Values,
+    PrimaryKeys,
+    Count,
+    /// The cursor of the request, moved to the first record, or null.
+    Cursor,
+}
+
+// https://w3c.github.io/IndexedDB/#request-api
+#[dom_struct]
+pub struct IDBRequest {
+    eventtarget: EventTarget,
+    #[ignore_malloc_size_of = "mozjs"]
+    result: Heap<JSVal>,
+    error: MutNullableDom<DOMException>,
+    source: Option<IDBRequestSource>,
+    transaction: MutNullableDom<IDBTransaction>,
+    ready_state: Cell<IDBRequestReadyState>,
+    /// The cursor this request iterates with, for requests opening a cursor.
+    cursor: MutNullableDom<IDBCursor>,
+}
+
+impl IDBRequest {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        source: Option<IDBRequestSource>,
+        transaction: Option<&IDBTransaction>,
+    ) -> IDBRequest {
+        IDBRequest {
+            eventtarget: EventTarget::new_inherited(),
+            result: Heap::default(),
+            error: Default::default(),
+            source: source,
+            transaction: MutNullableDom::new(transaction),
+            ready_state: Cell::new(IDBRequestReadyState::Pending),
+            cursor: Default::default(),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        global: &GlobalScope,
+        source: IDBRequestSource,
+        transaction: &IDBTransaction,
+    ) -> DomRoot<IDBRequest> {
+        reflect_dom_object(
+            Box::new(IDBRequest::new_inherited(Some(source), Some(transaction))),
+            global,
+            IDBRequestBinding::Wrap,
+        )
+    }
+
+    pub fn transaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    pub fn set_transaction(&self, transaction: Option<&IDBTransaction>) {
+        self.transaction.set(transaction);
+    }
+
+    pub fn set_cursor(&self, cursor: &IDBCursor) {
+        self.cursor.set(Some(cursor));
+    }
+
+    pub fn ready_state(&self) -> IDBRequestReadyState {
+        self.ready_state.get()
+    }
+
+    /// Make the request pending again, for a cursor moving to another record.
+    pub fn reset(&self) {
+        self.ready_state.set(IDBRequestReadyState::Pending);
+        self.result.set(UndefinedValue());
+        self.error.set(None);
+    }
+
+    /// Mark the request as done with the given result.
+    pub fn set_result(&self, result: JSVal) {
+        self.ready_state.set(IDBRequestReadyState::Done);
+        self.result.set(result);
+        self.error.set(None);
+    }
+
+    /// Mark the request as done with the given error.
+    pub fn set_error(&self, error: DOMErrorName) -> DomRoot<DOMException> {
+        let exception = DOMException::new(&self.global(), error);
+        self.ready_state.set(IDBRequestReadyState::Done);
+        self.result.set(UndefinedValue());
+        self.error.set(Some(&exception));
+        exception
+    }
+
+    /// Finish the request with the result of its operation, and fire the corresponding
+    /// event at it.
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    pub fn handle_result(
+        &self,
+        result: Result<IndexedDBOperationResult, DOMErrorName>,
+        kind: IDBResultKind,
+    ) {
+        let transaction = self
+            .transaction
+            .get()
+            .expect("Request without a transaction");
+        // Requests whose transaction was aborted fail, whatever their outcome.
+        let result = if transaction.is_aborted() {
+            Err(DOMErrorName::AbortError)
+        } else {
+            result
+        };
+        match result {
+            Ok(result) => {
+                let cx = self.global().get_cx();
+                rooted!(in(*cx) let mut value = UndefinedValue());
+                self.convert_result(cx, result, kind, value.handle_mut());
+                self.set_result(value.get());
+                // https://w3c.github.io/IndexedDB/#fire-a-success-event
+                transaction.set_active(true);
+                self.upcast::<EventTarget>().fire_event(atom!("success"));
+                transaction.set_active(false);
+            },
+            Err(error) => {
+                let exception = self.set_error(error);
+                // https://w3c.github.io/IndexedDB/#fire-an-error-event
+                transaction.set_active(true);
+                let event = self
+                    .upcast::<EventTarget>()
+                    .fire_bubbling_cancelable_event(atom!("error"));
+                transaction.set_active(false);
+                if event.status() == EventStatus::NotCanceled {
+                    transaction.abort(Some(&exception));
+                }
+            },
+        }
+        transaction.request_finished();
+    }
+
+    #[allow(unsafe_code)]
+    fn convert_result(
+        &self,
+        cx: JSContext,
+        result: IndexedDBOperationResult,
+        kind: IDBResultKind,
+        mut rval: MutableHandleValue,
+    ) {
+        match (kind, result) {
+            (IDBResultKind::Key, IndexedDBOperationResult::Key(key)) => {
+                key_type_to_jsval(cx, &key, rval)
+            },
+            (IDBResultKind::Value, IndexedDBOperationResult::Record(Some(record))) => {
+                StructuredCloneData::Vector(record.value).read(&self.global(), rval)
+            },
+            (IDBResultKind::PrimaryKey, IndexedDBOperationResult::Record(Some(record))) => {
+                key_type_to_jsval(cx, &record.primary_key, rval)
+            },
+            (IDBResultKind::Values, IndexedDBOperationResult::Records(records)) => {
+                let values = records.into_iter().map(|record| record.value).collect();
+                values_to_jsval(&self.global(), values, rval)
+            },
+            (IDBResultKind::PrimaryKeys, IndexedDBOperationResult::Records(records)) => {
+                let keys = records
+                    .into_iter()
+                    .map(|record| record.primary_key)
+                    .collect();
+                key_type_to_jsval(cx, &IndexedDBKeyType::Array(keys), rval)
+            },
+            (IDBResultKind::Count, IndexedDBOperationResult::Count(count)) => unsafe {
+                (count as f64).to_jsval(*cx, rval)
+            },
+            (IDBResultKind::Cursor, IndexedDBOperationResult::Record(record)) => {
+                let cursor = self.cursor.get().expect("Cursor request without a cursor");
+                if cursor.set_position(record) {
+                    rval.set(ObjectValue(cursor.reflector().get_jsobject().get()));
+                } else {
+                    rval.set(NullValue());
+                }
+            },
+            _ => rval.set(UndefinedValue()),
+        }
+    }
+}
+
+impl IDBRequestMethods for IDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-result
+    fn GetResult(&self, _cx: JSContext) -> Fallible<JSVal> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.result.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-error
+    fn GetError(&self) -> Fallible<Option<DomRoot<DOMException>>> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.error.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-source
+    fn GetSource(&self) -> Option<IDBObjectStoreOrIDBIndexOrIDBCursor> {
+        self.source.as_ref().map(|source| match *source {
+            IDBRequestSource::ObjectStore(ref store) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore(DomRoot::from_ref(&*store))
+            },
+            IDBRequestSource::Index(ref index) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(DomRoot::from_ref(&*index))
+            },
+            IDBRequestSource::Cursor(ref cursor) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(DomRoot::from_ref(&*cursor))
+            },
+        })
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-transaction
+    fn GetTransaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-readystate
+    fn ReadyState(&self) -> IDBRequestReadyState {
+        self.ready_state.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onsuccess
+    event_handler!(success, GetOnsuccess, SetOnsuccess);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
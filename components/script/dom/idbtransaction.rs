@@ -0,0 +1,564 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBDatabaseMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource, IDBResultKind};
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use net_traits::indexeddb_thread::{IndexMetadata, IndexedDBError, IndexedDBOperation};
+use net_traits::indexeddb_thread::{IndexedDBOperationResult, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTxnMode, IndexedDBTxnReply};
+use net_traits::IpcSend;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use uuid::Uuid;
+
+/// What to do with the result of an operation of a transaction.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum OperationCallback {
+    /// Deliver the result to a request.
+    Request(Dom<IDBRequest>, IDBResultKind),
+    /// Store a value in an object store with inline keys, once a key was generated for it.
+    StoreWithGeneratedKey {
+        request: Dom<IDBRequest>,
+        store: Dom<IDBObjectStore>,
+        value: Vec<u8>,
+        key_path: String,
+        no_overwrite: bool,
+    },
+    /// Create an index once the records of its object store were read.
+    CreateIndex(Dom<IDBObjectStore>, IndexMetadata),
+    /// Abort the transaction if the operation, which changes the schema of the database,
+    /// failed.
+    Schema,
+}
+
+impl OperationCallback {
+    /// Whether the operations made after this one have to wait for its result, because
+    /// another operation follows from it.
+    fn has_follow_up(&self) -> bool {
+        match *self {
+            OperationCallback::StoreWithGeneratedKey { .. } |
+            OperationCallback::CreateIndex(..) => true,
+            OperationCallback::Request(..) | OperationCallback::Schema => false,
+        }
+    }
+}
+
+fn dom_error_name(error: IndexedDBError) -> DOMErrorName {
+    match error {
+        IndexedDBError::Constraint | IndexedDBError::KeyGeneratorExhausted => {
+            DOMErrorName::ConstraintError
+        },
+        IndexedDBError::NotFound => DOMErrorName::NotFoundError,
+        IndexedDBError::TransactionFinished => DOMErrorName::InvalidStateError,
+    }
+}
+
+// https://w3c.github.io/IndexedDB/#transaction
+#[dom_struct]
+pub struct IDBTransaction {
+    eventtarget: EventTarget,
+    db: Dom<IDBDatabase>,
+    mode: IDBTransactionMode,
+    /// The names of the object stores of a transaction that is not an upgrade
+    /// transaction. Upgrade transactions have all the object stores of their database.
+    scope: Vec<DOMString>,
+    /// The identifier of the transaction in the IndexedDB thread.
+    #[ignore_malloc_size_of = "Defined in uuid"]
+    id: Uuid,
+    active: Cell<bool>,
+    /// Whether `commit()` was called, after which no request can be made.
+    committing: Cell<bool>,
+    finished: Cell<bool>,
+    aborted: Cell<bool>,
+    /// The number of requests whose result has not been delivered yet.
+    pending_requests: Cell<u32>,
+    /// What to do with the results of the operations sent to the IndexedDB thread, which
+    /// replies in order.
+    sent_operations: DomRefCell<VecDeque<OperationCallback>>,
+    /// The operations made while waiting for the result of an operation another one
+    /// follows from, which are sent once that one is sent.
+    #[ignore_malloc_size_of = "Defined in net_traits"]
+    held_operations: DomRefCell<VecDeque<(IndexedDBOperation, OperationCallback)>>,
+    waiting_for_follow_up: Cell<bool>,
+    error: MutNullableDom<DOMException>,
+    object_stores: DomRefCell<HashMap<DOMString, Dom<IDBObjectStore>>>,
+    /// The request opening the database, for upgrade transactions.
+    open_request: MutNullableDom<IDBOpenDBRequest>,
+}
+
+impl IDBTransaction {
+    fn new_inherited(
+        db: &IDBDatabase,
+        mode: IDBTransactionMode,
+        scope: Vec<DOMString>,
+        id: Uuid,
+    ) -> IDBTransaction {
+        IDBTransaction {
+            eventtarget: EventTarget::new_inherited(),
+            db: Dom::from_ref(db),
+            mode: mode,
+            scope: scope,
+            id: id,
+            active: Cell::new(true),
+            committing: Cell::new(false),
+            finished: Cell::new(false),
+            aborted: Cell::new(false),
+            pending_requests: Cell::new(0),
+            sent_operations: DomRefCell::new(VecDeque::new()),
+            held_operations: DomRefCell::new(VecDeque::new()),
+            waiting_for_follow_up: Cell::new(false),
+            error: Default::default(),
+            object_stores: DomRefCell::new(HashMap::new()),
+            open_request: Default::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        db: &IDBDatabase,
+        mode: IDBTransactionMode,
+        scope: Vec<DOMString>,
+    ) -> DomRoot<IDBTransaction> {
+        let txn_mode = match mode {
+            IDBTransactionMode::Readonly => IndexedDBTxnMode::Readonly,
+            IDBTransactionMode::Readwrite => IndexedDBTxnMode::Readwrite,
+            IDBTransactionMode::Versionchange => IndexedDBTxnMode::Versionchange,
+        };
+        let store_names = match mode {
+            IDBTransactionMode::Versionchange => None,
+            _ => Some(scope.iter().map(|name| name.to_string()).collect()),
+        };
+        let id = Uuid::new_v4();
+        let transaction = reflect_dom_object(
+            Box::new(IDBTransaction::new_inherited(db, mode, scope, id)),
+            global,
+            IDBTransactionBinding::Wrap,
+        );
+
+        // The IndexedDB thread replies to the messages about the transaction in order, over
+        // a channel of its own.
+        let (sender, receiver) = ipc::channel().unwrap();
+        let task_source = global.dom_manipulation_task_source();
+        let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+        let trusted = Trusted::new(&*transaction);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let trusted = trusted.clone();
+                let reply: IndexedDBTxnReply = message.to().unwrap();
+                let _ = task_source.queue_with_canceller(
+                    task!(handle_indexeddb_reply: move || {
+                        trusted.root().handle_reply(reply);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        global
+            .resource_threads()
+            .send(IndexedDBThreadMsg::BeginTransaction {
+                id: id,
+                origin: global.origin().immutable().clone(),
+                name: String::from(db.Name()),
+                mode: txn_mode,
+                scope: store_names,
+                replies: sender,
+            })
+            .unwrap();
+
+        // https://w3c.github.io/IndexedDB/#transaction-lifetime
+        // The transaction is inactive once control returns to the event loop.
+        let trusted = Trusted::new(&*transaction);
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(deactivate_transaction: move || {
+                let transaction = trusted.root();
+                transaction.active.set(false);
+                transaction.maybe_commit();
+            }),
+            global,
+        );
+        transaction
+    }
+
+    pub fn db(&self) -> DomRoot<IDBDatabase> {
+        DomRoot::from_ref(&*self.db)
+    }
+
+    pub fn mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+
+    /// Make the transaction active while an event is dispatched for one of its requests.
+    pub fn set_active(&self, active: bool) {
+        if !self.finished.get() && !self.committing.get() {
+            self.active.set(active);
+        }
+    }
+
+    pub fn set_open_request(&self, request: &IDBOpenDBRequest) {
+        self.open_request.set(Some(request));
+    }
+
+    fn object_store_names(&self) -> Vec<DOMString> {
+        if self.mode == IDBTransactionMode::Versionchange {
+            return self.db.object_store_names();
+        }
+        self.scope.clone()
+    }
+
+    /// Forget the object store handle of a deleted object store.
+    pub fn forget_object_store(&self, name: &DOMString) {
+        self.object_stores.borrow_mut().remove(name);
+    }
+
+    /// Make a request against `source` that runs `operation`.
+    #[allow(unrooted_must_root)]
+    pub fn make_request(
+        &self,
+        source: IDBRequestSource,
+        operation: IndexedDBOperation,
+        kind: IDBResultKind,
+    ) -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(&self.global(), source, self);
+        self.execute(&request, operation, kind);
+        request
+    }
+
+    /// Run the operation of a request, and deliver its result to the request once it is
+    /// done.
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    #[allow(unrooted_must_root)]
+    pub fn execute(
+        &self,
+        request: &IDBRequest,
+        operation: IndexedDBOperation,
+        kind: IDBResultKind,
+    ) {
+        self.pending_requests.set(self.pending_requests.get() + 1);
+        self.schedule(
+            operation,
+            OperationCallback::Request(Dom::from_ref(request), kind),
+        );
+    }
+
+    /// Generate a key for a value stored by a request in an object store with inline keys,
+    /// then store the value with the key injected at `key_path`.
+    #[allow(unrooted_must_root)]
+    pub fn store_with_generated_key(
+        &self,
+        request: &IDBRequest,
+        store: &IDBObjectStore,
+        value: Vec<u8>,
+        key_path: String,
+        no_overwrite: bool,
+    ) {
+        self.pending_requests.set(self.pending_requests.get() + 1);
+        self.schedule(
+            IndexedDBOperation::GenerateKey(store.name()),
+            OperationCallback::StoreWithGeneratedKey {
+                request: Dom::from_ref(request),
+                store: Dom::from_ref(store),
+                value: value,
+                key_path: key_path,
+                no_overwrite: no_overwrite,
+            },
+        );
+    }
+
+    /// Create an index in an object store, with the keys of the records it already has.
+    #[allow(unrooted_must_root)]
+    pub fn create_index(&self, store: &IDBObjectStore, index: IndexMetadata) {
+        self.schedule(
+            store.read_all_records(),
+            OperationCallback::CreateIndex(Dom::from_ref(store), index),
+        );
+    }
+
+    /// Change the schema of the database, aborting the transaction if that fails.
+    #[allow(unrooted_must_root)]
+    pub fn change_schema(&self, operation: IndexedDBOperation) {
+        self.schedule(operation, OperationCallback::Schema);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn schedule(&self, operation: IndexedDBOperation, callback: OperationCallback) {
+        if self.waiting_for_follow_up.get() {
+            self.held_operations
+                .borrow_mut()
+                .push_back((operation, callback));
+            return;
+        }
+        self.send(operation, callback);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn send(&self, operation: IndexedDBOperation, callback: OperationCallback) {
+        if callback.has_follow_up() {
+            self.waiting_for_follow_up.set(true);
+        }
+        self.sent_operations.borrow_mut().push_back(callback);
+        self.global()
+            .resource_threads()
+            .send(IndexedDBThreadMsg::Operation(self.id, operation))
+            .unwrap();
+    }
+
+    /// Send the operations held while waiting for the follow-up of an operation, until
+    /// one of them has a follow-up too.
+    fn send_held_operations(&self) {
+        self.waiting_for_follow_up.set(false);
+        while !self.waiting_for_follow_up.get() {
+            let next = self.held_operations.borrow_mut().pop_front();
+            match next {
+                Some((operation, callback)) => self.send(operation, callback),
+                None => break,
+            }
+        }
+    }
+
+    fn handle_reply(&self, reply: IndexedDBTxnReply) {
+        match reply {
+            IndexedDBTxnReply::Operation(result) => {
+                let callback = self
+                    .sent_operations
+                    .borrow_mut()
+                    .pop_front()
+                    .expect("Result of an IndexedDB operation that was not sent");
+                self.handle_result(callback, result.map_err(dom_error_name));
+                self.maybe_commit();
+            },
+            IndexedDBTxnReply::Committed => {
+                self.upcast::<EventTarget>().fire_event(atom!("complete"));
+                if let Some(request) = self.open_request.get() {
+                    request.upgrade_finished(&self.db, true);
+                }
+            },
+            // https://w3c.github.io/IndexedDB/#abort-a-transaction, step 6. The results of
+            // the requests of the transaction were delivered before.
+            IndexedDBTxnReply::Aborted => {
+                self.upcast::<EventTarget>()
+                    .fire_bubbling_event(atom!("abort"));
+                if let Some(request) = self.open_request.get() {
+                    request.upgrade_finished(&self.db, false);
+                }
+            },
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn handle_result(
+        &self,
+        callback: OperationCallback,
+        result: Result<IndexedDBOperationResult, DOMErrorName>,
+    ) {
+        match callback {
+            OperationCallback::Request(request, kind) => request.handle_result(result, kind),
+            OperationCallback::StoreWithGeneratedKey {
+                request,
+                store,
+                value,
+                key_path,
+                no_overwrite,
+            } => {
+                let operation = match result {
+                    _ if self.aborted.get() => Err(DOMErrorName::AbortError),
+                    Ok(IndexedDBOperationResult::Key(key)) => {
+                        store.store_with_key(value, key, &key_path, no_overwrite)
+                    },
+                    Ok(_) => unreachable!("Key generation without a key"),
+                    Err(error) => Err(error),
+                };
+                match operation {
+                    Ok(operation) => self.send(
+                        operation,
+                        OperationCallback::Request(request, IDBResultKind::Key),
+                    ),
+                    Err(error) => request.handle_result(Err(error), IDBResultKind::Key),
+                }
+                self.send_held_operations();
+            },
+            OperationCallback::CreateIndex(store, index) => {
+                if let (false, Ok(IndexedDBOperationResult::Records(records))) =
+                    (self.aborted.get(), result)
+                {
+                    let operation = store.index_creation(index, records);
+                    self.send(operation, OperationCallback::Schema);
+                }
+                self.send_held_operations();
+            },
+            OperationCallback::Schema => {
+                if let Err(error) = result {
+                    // For indexes, the existing records do not satisfy the constraints of
+                    // the index.
+                    let exception = DOMException::new(&self.global(), error);
+                    self.abort(Some(&exception));
+                }
+            },
+        }
+    }
+
+    /// Called once the event of a request of the transaction has been fired.
+    pub fn request_finished(&self) {
+        self.pending_requests.set(self.pending_requests.get() - 1);
+        self.maybe_commit();
+    }
+
+    /// Commit the transaction if it is inactive and all its operations are done.
+    /// <https://w3c.github.io/IndexedDB/#transaction-lifecycle>
+    pub fn maybe_commit(&self) {
+        if !self.finished.get() &&
+            !self.active.get() &&
+            self.pending_requests.get() == 0 &&
+            self.sent_operations.borrow().is_empty() &&
+            self.held_operations.borrow().is_empty()
+        {
+            self.commit();
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>. The complete event is
+    /// fired once the IndexedDB thread replies.
+    fn commit(&self) {
+        self.finished.set(true);
+        self.global()
+            .resource_threads()
+            .send(IndexedDBThreadMsg::Commit(self.id))
+            .unwrap();
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    pub fn abort(&self, error: Option<&DOMException>) {
+        if self.finished.get() {
+            return;
+        }
+        // Steps 1-2.
+        self.finished.set(true);
+        self.aborted.set(true);
+        self.active.set(false);
+        // The requests that are still pending fail with an AbortError when their result is
+        // delivered, before the abort event is fired. The held operations are sent so that
+        // their requests get a result too, and are undone with the others.
+        let held = mem::replace(&mut *self.held_operations.borrow_mut(), VecDeque::new());
+        for (operation, callback) in held {
+            self.send(operation, callback);
+        }
+        self.global()
+            .resource_threads()
+            .send(IndexedDBThreadMsg::Abort(self.id))
+            .unwrap();
+
+        // Step 4.
+        self.error.set(error);
+    }
+}
+
+impl IDBTransactionMethods for IDBTransaction {
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        DOMStringList::new(&self.global(), self.object_store_names())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-mode
+    fn Mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-db
+    fn Db(&self) -> DomRoot<IDBDatabase> {
+        DomRoot::from_ref(&*self.db)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-error
+    fn GetError(&self) -> Option<DomRoot<DOMException>> {
+        self.error.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstore
+    fn ObjectStore(&self, name: DOMString) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Step 1.
+        if self.finished.get() {
+            return Err(Error::InvalidState);
+        }
+        // Step 2.
+        if !self.object_store_names().contains(&name) {
+            return Err(Error::NotFound);
+        }
+        // Step 3.
+        let mut object_stores = self.object_stores.borrow_mut();
+        if let Some(store) = object_stores.get(&name) {
+            return Ok(DomRoot::from_ref(&**store));
+        }
+        let store = IDBObjectStore::new(&self.global(), name.clone(), self);
+        object_stores.insert(name, Dom::from_ref(&*store));
+        Ok(store)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-commit
+    fn Commit(&self) -> ErrorResult {
+        // Step 1.
+        if self.finished.get() || !self.active.get() {
+            return Err(Error::InvalidState);
+        }
+        // Step 2.
+        self.active.set(false);
+        self.committing.set(true);
+        self.maybe_commit();
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-abort
+    fn Abort(&self) -> ErrorResult {
+        // Step 1.
+        if self.finished.get() {
+            return Err(Error::InvalidState);
+        }
+        // Steps 2-3.
+        self.abort(None);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-oncomplete
+    event_handler!(complete, GetOncomplete, SetOncomplete);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
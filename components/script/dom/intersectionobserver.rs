@@ -0,0 +1,569 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverCallback;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverInit;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::codegen::UnionTypes::DoubleOrDoubleSequence;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domrectreadonly::DOMRectReadOnly;
+use crate::dom::element::Element;
+use crate::dom::intersectionobserverentry::IntersectionObserverEntry;
+use crate::dom::node::{window_from_node, Node};
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D, Vector2D};
+use js::conversions::ToJSValConvertible;
+use js::jsapi::Heap;
+use js::jsval::{JSVal, UndefinedValue};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// One of the four lengths of a root margin.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum MarginLength {
+    Px(f64),
+    Percent(f64),
+}
+
+impl MarginLength {
+    fn parse(token: &str) -> Option<MarginLength> {
+        if token.ends_with("px") {
+            token[..token.len() - 2].parse().ok().map(MarginLength::Px)
+        } else if token.ends_with('%') {
+            token[..token.len() - 1]
+                .parse()
+                .ok()
+                .map(MarginLength::Percent)
+        } else {
+            None
+        }
+    }
+
+    /// Resolve this length against the given size of the root intersection rectangle.
+    fn resolve(&self, size: f64) -> f64 {
+        match *self {
+            MarginLength::Px(length) => length,
+            MarginLength::Percent(percentage) => size * percentage / 100.,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match *self {
+            MarginLength::Px(length) => format!("{}px", length),
+            MarginLength::Percent(percentage) => format!("{}%", percentage),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#parse-a-root-margin>
+///
+/// The margins are returned in top, right, bottom, left order.
+fn parse_root_margin(margin: &str) -> Option<Vec<MarginLength>> {
+    let tokens = margin
+        .split_whitespace()
+        .map(MarginLength::parse)
+        .collect::<Option<Vec<_>>>()?;
+    match tokens.len() {
+        1 => Some(vec![tokens[0], tokens[0], tokens[0], tokens[0]]),
+        2 => Some(vec![tokens[0], tokens[1], tokens[0], tokens[1]]),
+        3 => Some(vec![tokens[0], tokens[1], tokens[2], tokens[1]]),
+        4 => Some(vec![tokens[0], tokens[1], tokens[2], tokens[3]]),
+        _ => None,
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersectionobserverregistration>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct IntersectionObserverRegistration {
+    target: Dom<Element>,
+    /// -1 until the first time the observations are updated.
+    previous_threshold_index: Cell<i32>,
+    previous_is_intersecting: Cell<bool>,
+}
+
+// https://w3c.github.io/IntersectionObserver/#intersection-observer-interface
+#[dom_struct]
+pub struct IntersectionObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<IntersectionObserverCallback>,
+    root: Option<Dom<Element>>,
+    /// The top, right, bottom and left margins.
+    root_margin: Vec<MarginLength>,
+    thresholds: Vec<f64>,
+    /// The thresholds as a JS array, returned by the `thresholds` attribute.
+    #[ignore_malloc_size_of = "mozjs"]
+    thresholds_array: Heap<JSVal>,
+    registrations: DomRefCell<Vec<IntersectionObserverRegistration>>,
+    queued_entries: DomRefCell<Vec<Dom<IntersectionObserverEntry>>>,
+}
+
+impl IntersectionObserver {
+    fn new_inherited(
+        callback: Rc<IntersectionObserverCallback>,
+        root: Option<&Element>,
+        root_margin: Vec<MarginLength>,
+        thresholds: Vec<f64>,
+    ) -> IntersectionObserver {
+        IntersectionObserver {
+            reflector_: Reflector::new(),
+            callback,
+            root: root.map(Dom::from_ref),
+            root_margin,
+            thresholds,
+            thresholds_array: Heap::default(),
+            registrations: DomRefCell::new(vec![]),
+            queued_entries: DomRefCell::new(vec![]),
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-intersectionobserver
+    #[allow(unsafe_code)]
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<IntersectionObserverCallback>,
+        options: &IntersectionObserverInit,
+    ) -> Fallible<DomRoot<IntersectionObserver>> {
+        // Steps 2-3.
+        let root_margin = parse_root_margin(&options.rootMargin).ok_or(Error::Syntax)?;
+        // Steps 4-6.
+        let mut thresholds = match options.threshold {
+            Some(DoubleOrDoubleSequence::Double(threshold)) => vec![*threshold],
+            Some(DoubleOrDoubleSequence::DoubleSequence(ref thresholds)) => {
+                thresholds.iter().map(|threshold| **threshold).collect()
+            },
+            None => vec![],
+        };
+        if thresholds
+            .iter()
+            .any(|threshold| *threshold < 0. || *threshold > 1.)
+        {
+            return Err(Error::Range(
+                "Thresholds must be in the range [0, 1]".to_owned(),
+            ));
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if thresholds.is_empty() {
+            thresholds.push(0.);
+        }
+
+        let observer = reflect_dom_object(
+            Box::new(IntersectionObserver::new_inherited(
+                callback,
+                options.root.as_ref().map(|root| &**root),
+                root_margin,
+                thresholds.clone(),
+            )),
+            window,
+            IntersectionObserverBinding::Wrap,
+        );
+        let cx = window.get_cx();
+        unsafe {
+            rooted!(in(*cx) let mut array = UndefinedValue());
+            thresholds.to_jsval(*cx, array.handle_mut());
+            observer.thresholds_array.set(array.get());
+        }
+        Ok(observer)
+    }
+
+    fn window(&self) -> DomRoot<Window> {
+        DomRoot::from_ref(self.global().as_window())
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#intersectionobserver-root-intersection-rectangle>
+    ///
+    /// The rectangle is in the viewport coordinates of the window it is returned with.
+    fn root_intersection_rectangle(&self) -> Option<(Rect<f64>, DomRoot<Window>)> {
+        let (rect, window) = match self.root {
+            Some(ref root) => {
+                let rect = if root.potentially_scrollable() {
+                    padding_box(root)
+                } else {
+                    border_box(root)
+                }?;
+                (rect, window_from_node(&**root))
+            },
+            None => {
+                let window = top_level_window(&self.window());
+                (viewport(&window), window)
+            },
+        };
+        let top = self.root_margin[0].resolve(rect.size.height);
+        let right = self.root_margin[1].resolve(rect.size.width);
+        let bottom = self.root_margin[2].resolve(rect.size.height);
+        let left = self.root_margin[3].resolve(rect.size.width);
+        let rect = Rect::new(
+            Point2D::new(rect.origin.x - left, rect.origin.y - top),
+            Size2D::new(
+                rect.size.width + left + right,
+                rect.size.height + top + bottom,
+            ),
+        );
+        Some((rect, window))
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#compute-the-intersection>
+    ///
+    /// Returns the intersection in the viewport coordinates of the document of the
+    /// target, or none if the target does not intersect the root.
+    fn compute_intersection(
+        &self,
+        target: &Element,
+        target_rect: Rect<f64>,
+        root_bounds: &Rect<f64>,
+    ) -> Option<Rect<f64>> {
+        // Step 1.
+        let mut intersection = target_rect;
+        // The offset of the viewport of the document of the target in the viewport
+        // coordinates of the document the walk has reached.
+        let mut offset = Vector2D::zero();
+        // Steps 2-3.
+        let mut container = DomRoot::from_ref(target.upcast::<Node>());
+        loop {
+            match container.GetParentNode() {
+                Some(parent) => {
+                    if let Some(ref root) = self.root {
+                        if *parent == *root.upcast::<Node>() {
+                            break;
+                        }
+                    }
+                    if let Some(element) = parent.downcast::<Element>() {
+                        if element.potentially_scrollable() {
+                            intersection = intersect(&intersection, &padding_box(element)?)?;
+                        }
+                    }
+                    container = parent;
+                },
+                // The walk reached a document; with the implicit root, continue in
+                // the document of the browsing context container, if any.
+                None => {
+                    if self.root.is_some() {
+                        return None;
+                    }
+                    let window = window_from_node(&*container);
+                    let frame = match window.window_proxy().frame_element() {
+                        Some(frame) => DomRoot::from_ref(frame),
+                        None => break,
+                    };
+                    intersection = intersect(&intersection, &viewport(&window))?;
+                    let frame_box = padding_box(&frame)?;
+                    let frame_offset = frame_box.origin.to_vector();
+                    intersection = intersect(&intersection.translate(frame_offset), &frame_box)?;
+                    offset += frame_offset;
+                    container = DomRoot::from_ref(frame.upcast::<Node>());
+                },
+            }
+        }
+        // Steps 4-5.
+        let intersection = intersect(&intersection, root_bounds)?;
+        // Step 6.
+        Some(intersection.translate(-offset))
+    }
+
+    /// Steps 2.1-2.2 of
+    /// <https://w3c.github.io/IntersectionObserver/#update-intersection-observations-algo>
+    /// for this observer.
+    pub fn update_observations(&self, time: f64) {
+        // Step 2.1.
+        let root_bounds = self.root_intersection_rectangle();
+        let targets = self
+            .registrations
+            .borrow()
+            .iter()
+            .map(|registration| DomRoot::from_ref(&*registration.target))
+            .collect::<Vec<_>>();
+        // Step 2.2.
+        for target in targets {
+            let mut target_rect = Rect::zero();
+            let mut intersection_rect = Rect::zero();
+            let mut is_intersecting = false;
+            // Steps 2.2.1-2.2.8.
+            if let Some((ref bounds, _)) = root_bounds {
+                if self.can_observe(&target) {
+                    if let Some(rect) = border_box(&target) {
+                        target_rect = rect;
+                        if let Some(intersection) = self.compute_intersection(&target, rect, bounds)
+                        {
+                            intersection_rect = intersection;
+                            is_intersecting = true;
+                        }
+                    }
+                }
+            }
+            // Steps 2.2.9-2.2.10.
+            let target_area = target_rect.size.width * target_rect.size.height;
+            let intersection_area = intersection_rect.size.width * intersection_rect.size.height;
+            let ratio = if target_area > 0. {
+                intersection_area / target_area
+            } else if is_intersecting {
+                1.
+            } else {
+                0.
+            };
+            // Step 2.2.11.
+            let threshold_index = self
+                .thresholds
+                .iter()
+                .position(|threshold| *threshold > ratio)
+                .unwrap_or(self.thresholds.len()) as i32;
+            // Steps 2.2.12-2.2.14.
+            let changed = {
+                let registrations = self.registrations.borrow();
+                let registration = match registrations
+                    .iter()
+                    .find(|registration| *registration.target == *target)
+                {
+                    Some(registration) => registration,
+                    None => continue,
+                };
+                let changed = registration.previous_threshold_index.get() != threshold_index ||
+                    registration.previous_is_intersecting.get() != is_intersecting;
+                registration.previous_threshold_index.set(threshold_index);
+                registration.previous_is_intersecting.set(is_intersecting);
+                changed
+            };
+            if changed {
+                let root_bounds = root_bounds.as_ref().and_then(|(bounds, window)| {
+                    let target_window = window_from_node(&*target);
+                    if target_window.origin().same_origin_domain(window.origin()) {
+                        Some(*bounds)
+                    } else {
+                        None
+                    }
+                });
+                self.queue_entry(
+                    time,
+                    root_bounds,
+                    target_rect,
+                    intersection_rect,
+                    is_intersecting,
+                    ratio,
+                    &target,
+                );
+            }
+        }
+    }
+
+    /// Whether the target can be observed against the root of this observer, as in
+    /// steps 2.2.3-2.2.4 of
+    /// <https://w3c.github.io/IntersectionObserver/#update-intersection-observations-algo>.
+    fn can_observe(&self, target: &Element) -> bool {
+        match self.root {
+            None => true,
+            Some(ref root) => root
+                .upcast::<Node>()
+                .is_ancestor_of(target.upcast::<Node>()),
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersectionobserverentry>
+    fn queue_entry(
+        &self,
+        time: f64,
+        root_bounds: Option<Rect<f64>>,
+        bounding_client_rect: Rect<f64>,
+        intersection_rect: Rect<f64>,
+        is_intersecting: bool,
+        intersection_ratio: f64,
+        target: &Element,
+    ) {
+        let window = self.window();
+        let root_bounds = root_bounds.map(|bounds| dom_rect(&window, &bounds));
+        let entry = IntersectionObserverEntry::new(
+            &window,
+            time,
+            root_bounds.as_ref().map(|bounds| &**bounds),
+            &dom_rect(&window, &bounding_client_rect),
+            &dom_rect(&window, &intersection_rect),
+            is_intersecting,
+            intersection_ratio,
+            target,
+        );
+        self.queued_entries
+            .borrow_mut()
+            .push(Dom::from_ref(&*entry));
+        window.Document().queue_intersection_observer_task();
+    }
+
+    /// Step 3 of <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    /// for this observer.
+    pub fn invoke_callback(&self) {
+        let entries = self.TakeRecords();
+        if entries.is_empty() {
+            return;
+        }
+        let _ = self
+            .callback
+            .Call_(self, entries, self, ExceptionHandling::Report);
+    }
+}
+
+impl IntersectionObserverMethods for IntersectionObserver {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root
+    fn GetRoot(&self) -> Option<DomRoot<Element>> {
+        self.root.as_ref().map(|root| DomRoot::from_ref(&**root))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin
+    fn RootMargin(&self) -> DOMString {
+        DOMString::from(
+            self.root_margin
+                .iter()
+                .map(MarginLength::serialize)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds
+    fn Thresholds(&self, _cx: JSContext) -> JSVal {
+        self.thresholds_array.get()
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observe
+    fn Observe(&self, target: &Element) {
+        // Step 1.
+        if self
+            .registrations
+            .borrow()
+            .iter()
+            .any(|registration| &*registration.target == target)
+        {
+            return;
+        }
+        // Steps 2-4.
+        self.registrations
+            .borrow_mut()
+            .push(IntersectionObserverRegistration {
+                target: Dom::from_ref(target),
+                previous_threshold_index: Cell::new(-1),
+                previous_is_intersecting: Cell::new(false),
+            });
+        self.window().Document().add_intersection_observer(self);
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.registrations
+            .borrow_mut()
+            .retain(|registration| &*registration.target != target);
+        if self.registrations.borrow().is_empty() {
+            self.window().Document().remove_intersection_observer(self);
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-disconnect
+    fn Disconnect(&self) {
+        self.registrations.borrow_mut().clear();
+        self.window().Document().remove_intersection_observer(self);
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-takerecords
+    fn TakeRecords(&self) -> Vec<DomRoot<IntersectionObserverEntry>> {
+        self.queued_entries
+            .borrow_mut()
+            .drain(..)
+            .map(|entry| DomRoot::from_ref(&*entry))
+            .collect()
+    }
+}
+
+/// The topmost window reachable from the given one through the browsing context
+/// containers of its ancestors. This is the window of the top-level browsing context,
+/// unless an ancestor runs in another script thread.
+fn top_level_window(window: &Window) -> DomRoot<Window> {
+    let mut window = DomRoot::from_ref(window);
+    loop {
+        let parent = match window.window_proxy().frame_element() {
+            Some(frame) => window_from_node(frame),
+            None => return window,
+        };
+        window = parent;
+    }
+}
+
+/// The viewport of the given window, in its own viewport coordinates.
+fn viewport(window: &Window) -> Rect<f64> {
+    let size = window.current_viewport().size;
+    Rect::new(
+        Point2D::zero(),
+        Size2D::new(size.width.to_f64_px(), size.height.to_f64_px()),
+    )
+}
+
+/// The border box of the element in the viewport coordinates of its document, or
+/// none if the element has no layout box.
+fn border_box(element: &Element) -> Option<Rect<f64>> {
+    let node = element.upcast::<Node>();
+    let window = window_from_node(node);
+    let rect = window.content_box_query(node)?;
+    // Layout boxes are positioned in the document, without any scrolling.
+    let origin = window.current_viewport().origin;
+    let mut scroll = Vector2D::new(origin.x.to_f64_px(), origin.y.to_f64_px());
+    for ancestor in node.ancestors() {
+        let offset = window.scroll_offset_query(&ancestor);
+        scroll += Vector2D::new(offset.x as f64, offset.y as f64);
+    }
+    Some(Rect::new(
+        Point2D::new(
+            rect.origin.x.to_f64_px() - scroll.x,
+            rect.origin.y.to_f64_px() - scroll.y,
+        ),
+        Size2D::new(rect.size.width.to_f64_px(), rect.size.height.to_f64_px()),
+    ))
+}
+
+/// The padding box of the element in the viewport coordinates of its document, which
+/// is the box its content is clipped to when it is a scroll container.
+fn padding_box(element: &Element) -> Option<Rect<f64>> {
+    let border_box = border_box(element)?;
+    let client_rect = element.upcast::<Node>().client_rect();
+    Some(Rect::new(
+        Point2D::new(
+            border_box.origin.x + client_rect.origin.x as f64,
+            border_box.origin.y + client_rect.origin.y as f64,
+        ),
+        Size2D::new(
+            client_rect.size.width as f64,
+            client_rect.size.height as f64,
+        ),
+    ))
+}
+
+/// The intersection of two rectangles, which also exists when they only share an
+/// edge. The intersection of two adjacent rectangles has a zero area.
+fn intersect(a: &Rect<f64>, b: &Rect<f64>) -> Option<Rect<f64>> {
+    let min_x = a.min_x().max(b.min_x());
+    let min_y = a.min_y().max(b.min_y());
+    let max_x = a.max_x().min(b.max_x());
+    let max_y = a.max_y().min(b.max_y());
+    if max_x < min_x || max_y < min_y {
+        return None;
+    }
+    Some(Rect::new(
+        Point2D::new(min_x, min_y),
+        Size2D::new(max_x - min_x, max_y - min_y),
+    ))
+}
+
+fn dom_rect(window: &Window, rect: &Rect<f64>) -> DomRoot<DOMRectReadOnly> {
+    DOMRectReadOnly::new(
+        window.upcast(),
+        rect.origin.x,
+        rect.origin.y,
+        rect.size.width,
+        rect.size.height,
+    )
+}
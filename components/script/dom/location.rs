@@ -51,13 +51,14 @@ impl Location {
         let document = self.window.Document();
         let referrer_policy = document.get_referrer_policy();
         let pipeline_id = self.window.upcast::<GlobalScope>().pipeline_id();
-        let load_data = LoadData::new(
+        let mut load_data = LoadData::new(
             LoadOrigin::Script(document.origin().immutable().clone()),
             url,
             Some(pipeline_id),
             Some(referrer),
             referrer_policy,
         );
+        load_data.site_for_cookies = Some(self.window.upcast::<GlobalScope>().site_for_cookies());
         // TODO: rethrow exceptions, set exceptions enabled flag.
         self.window
             .load_url(replacement_flag, reload_triggered, load_data);
@@ -14,11 +14,13 @@ use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::event::Event;
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::messageport::MessagePort;
 use crate::dom::windowproxy::WindowProxy;
 use crate::script_runtime::JSContext;
 use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
 use js::jsapi::{Heap, JSObject};
-use js::jsval::JSVal;
+use js::jsval::{JSVal, UndefinedValue};
 use js::rust::HandleValue;
 use servo_atoms::Atom;
 use std::ptr::NonNull;
@@ -31,6 +33,8 @@ pub struct MessageEvent {
     origin: DOMString,
     source: Option<Dom<WindowProxy>>,
     lastEventId: DOMString,
+    #[ignore_malloc_size_of = "mozjs"]
+    ports: Heap<JSVal>,
 }
 
 impl MessageEvent {
@@ -41,15 +45,18 @@ impl MessageEvent {
             DOMString::new(),
             None,
             DOMString::new(),
+            vec![],
         )
     }
 
+    #[allow(unsafe_code)]
     pub fn new_initialized(
         global: &GlobalScope,
         data: HandleValue,
         origin: DOMString,
         source: Option<&WindowProxy>,
         lastEventId: DOMString,
+        ports: Vec<DomRoot<MessagePort>>,
     ) -> DomRoot<MessageEvent> {
         let ev = Box::new(MessageEvent {
             event: Event::new_inherited(),
@@ -57,10 +64,18 @@ impl MessageEvent {
             origin: origin,
             source: source.map(Dom::from_ref),
             lastEventId: lastEventId,
+            ports: Heap::default(),
         });
         let ev = reflect_dom_object(ev, global, MessageEventBinding::Wrap);
         ev.data.set(data.get());
 
+        let cx = global.get_cx();
+        unsafe {
+            rooted!(in(*cx) let mut jsval = UndefinedValue());
+            ports.to_jsval(*cx, jsval.handle_mut());
+            ev.ports.set(jsval.get());
+        }
+
         ev
     }
 
@@ -73,8 +88,9 @@ impl MessageEvent {
         origin: DOMString,
         source: Option<&WindowProxy>,
         lastEventId: DOMString,
+        ports: Vec<DomRoot<MessagePort>>,
     ) -> DomRoot<MessageEvent> {
-        let ev = MessageEvent::new_initialized(global, data, origin, source, lastEventId);
+        let ev = MessageEvent::new_initialized(global, data, origin, source, lastEventId, ports);
         {
             let event = ev.upcast::<Event>();
             event.init_event(type_, bubbles, cancelable);
@@ -100,6 +116,7 @@ impl MessageEvent {
             init.origin.clone(),
             source.as_ref().map(|source| &**source),
             init.lastEventId.clone(),
+            init.ports.clone(),
         );
         Ok(ev)
     }
@@ -112,6 +129,7 @@ impl MessageEvent {
         message: HandleValue,
         origin: Option<&str>,
         source: Option<&WindowProxy>,
+        ports: Vec<DomRoot<MessagePort>>,
     ) {
         let messageevent = MessageEvent::new(
             scope,
@@ -122,6 +140,7 @@ impl MessageEvent {
             DOMString::from(origin.unwrap_or("")),
             source,
             DOMString::new(),
+            ports,
         );
         messageevent.upcast::<Event>().fire(target);
     }
@@ -150,6 +169,11 @@ impl MessageEventMethods for MessageEvent {
         self.lastEventId.clone()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-messageevent-ports
+    fn Ports(&self, _cx: JSContext) -> JSVal {
+        self.ports.get()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.event.IsTrusted()
@@ -0,0 +1,261 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::compartments::enter_realm;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::{
+    self, MessagePortMethods, PostMessageOptions,
+};
+use crate::dom::bindings::conversions::root_from_object;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::trace::RootedTraceableBox;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::messageevent::MessageEvent;
+use crate::script_runtime::JSContext;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use js::jsapi::JSObject;
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard, HandleValue};
+use msg::constellation_msg::MessagePortId;
+use script_traits::{ScriptMsg, StructuredSerializedData, TransferredMessagePort};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+
+// https://html.spec.whatwg.org/multipage/#messageport
+#[dom_struct]
+pub struct MessagePort {
+    eventtarget: EventTarget,
+    message_port_id: MessagePortId,
+    entangled_port: Cell<Option<MessagePortId>>,
+    /// Whether this port was shipped to another global or closed.
+    detached: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#port-message-queue>
+    enabled: Cell<bool>,
+    /// Messages received while the port message queue is disabled.
+    #[ignore_malloc_size_of = "Defined in script_traits"]
+    message_buffer: DomRefCell<VecDeque<StructuredSerializedData>>,
+}
+
+impl MessagePort {
+    fn new_inherited(
+        message_port_id: MessagePortId,
+        entangled_port: Option<MessagePortId>,
+        message_buffer: VecDeque<StructuredSerializedData>,
+    ) -> MessagePort {
+        MessagePort {
+            eventtarget: EventTarget::new_inherited(),
+            message_port_id,
+            entangled_port: Cell::new(entangled_port),
+            detached: Cell::new(false),
+            enabled: Cell::new(false),
+            message_buffer: DomRefCell::new(message_buffer),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#create-a-new-messageport-object>
+    pub fn new(owner: &GlobalScope) -> DomRoot<MessagePort> {
+        let port = MessagePort::new_inherited(MessagePortId::new(), None, VecDeque::new());
+        let port = reflect_dom_object(Box::new(port), owner, MessagePortBinding::Wrap);
+        owner.track_message_port(&port);
+        port
+    }
+
+    /// Create the port on the receiving side of a transfer.
+    pub fn new_transferred(
+        owner: &GlobalScope,
+        transferred: TransferredMessagePort,
+    ) -> DomRoot<MessagePort> {
+        let port = MessagePort::new_inherited(
+            transferred.id,
+            transferred.entangled_port,
+            transferred.message_buffer,
+        );
+        let port = reflect_dom_object(Box::new(port), owner, MessagePortBinding::Wrap);
+        owner.track_message_port(&port);
+        port
+    }
+
+    pub fn message_port_id(&self) -> MessagePortId {
+        self.message_port_id
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#entangle>
+    pub fn entangle(&self, other_id: MessagePortId) {
+        self.entangled_port.set(Some(other_id));
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.get()
+    }
+
+    /// Detach this port as part of a transfer, handing its state over to the
+    /// global that will receive it.
+    pub fn transfer(&self) -> TransferredMessagePort {
+        self.detached.set(true);
+        self.global().untrack_message_port(self);
+        let _ = self
+            .global()
+            .script_to_constellation_chan()
+            .send(ScriptMsg::MessagePortShipped(self.message_port_id));
+        TransferredMessagePort {
+            id: self.message_port_id,
+            entangled_port: self.entangled_port.get(),
+            message_buffer: mem::replace(&mut *self.message_buffer.borrow_mut(), VecDeque::new()),
+        }
+    }
+
+    /// Handle a message sent to this port by its entangled port.
+    pub fn handle_incoming(&self, data: StructuredSerializedData) {
+        if self.detached.get() {
+            self.global()
+                .reroute_port_message(self.message_port_id, data);
+            return;
+        }
+        if !self.enabled.get() {
+            self.message_buffer.borrow_mut().push_back(data);
+            return;
+        }
+
+        let global = self.global();
+        let _ac = enter_realm(self);
+        rooted!(in(*global.get_cx()) let mut message = UndefinedValue());
+        let ports = StructuredCloneData::read_with_transfer(&global, data, message.handle_mut());
+        // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+        // Step 7.1: the origin of a port message is the empty string.
+        MessageEvent::dispatch_jsval(self.upcast(), &global, message.handle(), None, None, ports);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#message-port-post-message-steps>
+    fn post_message_impl(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        // Steps 2-3.
+        let mut doomed = false;
+        for object in transfer.iter() {
+            if let Ok(port) = root_from_object::<MessagePort>(*object, *cx) {
+                if port.message_port_id == self.message_port_id {
+                    return Err(Error::DataClone);
+                }
+                if Some(port.message_port_id) == self.entangled_port.get() {
+                    doomed = true;
+                }
+            }
+        }
+
+        // Step 5.
+        let data = StructuredCloneData::write_with_transfer(*cx, message, transfer)?;
+
+        // Steps 4 and 6.
+        let entangled_port = match self.entangled_port.get() {
+            Some(entangled_port) if !doomed && !self.detached.get() => entangled_port,
+            _ => {
+                // The message is dropped, and so are the ports it carried.
+                for port in data.ports {
+                    let _ = self
+                        .global()
+                        .script_to_constellation_chan()
+                        .send(ScriptMsg::RemoveMessagePort(port.id));
+                }
+                return Ok(());
+            },
+        };
+
+        // Step 7.
+        let _ = self
+            .global()
+            .script_to_constellation_chan()
+            .send(ScriptMsg::PostMessageToEntangledPort(entangled_port, data));
+        Ok(())
+    }
+}
+
+impl MessagePortMethods for MessagePort {
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+    fn PostMessage(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        self.post_message_impl(cx, message, transfer)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+    fn PostMessage_(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        options: RootedTraceableBox<PostMessageOptions>,
+    ) -> ErrorResult {
+        let mut rooted =
+            CustomAutoRooter::new(options.transfer.iter().map(|object| object.get()).collect());
+        let guard = rooted.root(*cx);
+        self.post_message_impl(cx, message, guard)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-start
+    fn Start(&self) {
+        if self.enabled.get() {
+            return;
+        }
+        self.enabled.set(true);
+        let global = self.global();
+        let task_source = global.port_message_queue();
+        let canceller = global.task_canceller(TaskSourceName::PortMessage);
+        let pending = mem::replace(&mut *self.message_buffer.borrow_mut(), VecDeque::new());
+        for data in pending {
+            let port = Trusted::new(self);
+            let _ = task_source.queue_with_canceller(
+                task!(process_pending_port_message: move || {
+                    port.root().handle_incoming(data);
+                }),
+                &canceller,
+            );
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-close
+    fn Close(&self) {
+        if self.detached.get() {
+            return;
+        }
+        self.detached.set(true);
+        self.entangled_port.set(None);
+        self.global().untrack_message_port(self);
+        let _ = self
+            .global()
+            .script_to_constellation_chan()
+            .send(ScriptMsg::RemoveMessagePort(self.message_port_id));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn GetOnmessage(&self) -> Option<Rc<EventHandlerNonNull>> {
+        let eventtarget = self.upcast::<EventTarget>();
+        eventtarget.get_event_handler_common("message")
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn SetOnmessage(&self, listener: Option<Rc<EventHandlerNonNull>>) {
+        let eventtarget = self.upcast::<EventTarget>();
+        eventtarget.set_event_handler_common("message", listener);
+        // Setting onmessage implicitly enables the port message queue.
+        self.Start();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessageerror
+    event_handler!(messageerror, GetOnmessageerror, SetOnmessageerror);
+}
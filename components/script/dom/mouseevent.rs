@@ -140,6 +140,10 @@ impl MouseEvent {
     pub fn point_in_target(&self) -> Option<Point2D<f32>> {
         self.point_in_target.get()
     }
+
+    pub fn set_buttons(&self, buttons: u16) {
+        self.buttons.set(buttons);
+    }
 }
 
 impl MouseEventMethods for MouseEvent {
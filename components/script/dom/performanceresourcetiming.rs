@@ -57,7 +57,6 @@ pub struct PerformanceResourceTiming {
 
 // TODO(#21254): startTime
 // TODO(#21255): duration
-// TODO(#21269): next_hop
 // TODO(#21264): worker_start
 // TODO(#21258): fetch_start
 // TODO(#21259): domain_lookup_start
@@ -162,7 +161,6 @@ impl PerformanceResourceTimingMethods for PerformanceResourceTiming {
 
     // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-nexthopprotocol
     // returns the ALPN protocol ID of the network protocol used to fetch the resource
-    // when a proxy is configured
     fn NextHopProtocol(&self) -> DOMString {
         match self.next_hop {
             Some(ref protocol) => DOMString::from(protocol.clone()),
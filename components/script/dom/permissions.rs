@@ -346,6 +346,40 @@ pub fn get_descriptor_permission_state(
     state
 }
 
+// https://w3c.github.io/permissions/#request-permission-to-use
+// Used by the features that are only exposed to secure contexts, so the user is
+// asked to grant the permission instead of being warned about the context. The
+// decision is remembered, and the user is only asked again if it was dismissed.
+pub fn request_permission_to_use(
+    permission_name: PermissionName,
+    global: &GlobalScope,
+) -> PermissionState {
+    let results = global.as_window().permission_state_invocation_results();
+
+    // Steps 1-2.
+    if let Some(state) = results.borrow().get(&permission_name.to_string()) {
+        if *state != PermissionState::Prompt {
+            return *state;
+        }
+    }
+
+    // Steps 3-4.
+    let state = if pref!(dom.permissions.testing.allowed_in_nonsecure_contexts) {
+        PermissionState::Granted
+    } else {
+        prompt_user(
+            &format!("{} {} ?", REQUEST_DIALOG_MESSAGE, permission_name),
+            global.is_headless(),
+        )
+    };
+    results
+        .borrow_mut()
+        .insert(permission_name.to_string(), state);
+
+    // Step 5.
+    state
+}
+
 #[cfg(target_os = "linux")]
 fn prompt_user(message: &str, headless: bool) -> PermissionState {
     if headless {
@@ -393,5 +427,9 @@ fn allowed_in_nonsecure_contexts(permission_name: &PermissionName) -> bool {
         PermissionName::Bluetooth => false,
         // https://storage.spec.whatwg.org/#dom-permissionname-persistent-storage
         PermissionName::Persistent_storage => false,
+        // https://w3c.github.io/clipboard-apis/#dom-permissionname-clipboard-read
+        PermissionName::Clipboard_read => false,
+        // https://w3c.github.io/clipboard-apis/#dom-permissionname-clipboard-write
+        PermissionName::Clipboard_write => false,
     }
 }
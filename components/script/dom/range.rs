@@ -89,7 +89,7 @@ impl Range {
     }
 
     // https://dom.spec.whatwg.org/#contained
-    fn contains(&self, node: &Node) -> bool {
+    pub fn contains(&self, node: &Node) -> bool {
         match (
             bp_position(node, 0, &self.StartContainer(), self.StartOffset()),
             bp_position(node, node.len(), &self.EndContainer(), self.EndOffset()),
@@ -0,0 +1,688 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding::ReadableByteStreamControllerMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamController};
+use crate::dom::readablestreambyobrequest::ReadableStreamBYOBRequest;
+use crate::script_runtime::JSContext;
+use crate::streams::{call_underlying_method, check_underlying_methods, error_to_jsval};
+use crate::streams::{promise_call, promise_rejected_with, promise_rejected_with_error};
+use crate::streams::{promise_resolved_with, read_result_promise, throw_jsval};
+use crate::streams::{upon_settlement, PromiseReaction};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject, Type};
+use js::jsval::{DoubleValue, ObjectValue, UndefinedValue};
+use js::rust::{CustomAutoRooterGuard, HandleObject, HandleValue, MutableHandleValue};
+use js::typedarray::{ArrayBufferView, CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::cmp;
+use std::collections::VecDeque;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#pull-into-descriptor>
+///
+/// Unlike in the specification the buffer of the view is not transferred;
+/// bytes are copied into the view passed to `read()` instead.
+#[derive(JSTraceable, MallocSizeOf)]
+struct PullIntoDescriptor {
+    #[ignore_malloc_size_of = "mozjs"]
+    view: Box<Heap<*mut JSObject>>,
+    byte_length: usize,
+    bytes_filled: usize,
+    element_size: usize,
+}
+
+/// The steps a byte stream controller takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum ReadableByteStreamControllerStep {
+    Started,
+    Pulled,
+}
+
+// https://streams.spec.whatwg.org/#rbs-controller-class
+#[dom_struct]
+pub struct ReadableByteStreamController {
+    reflector_: Reflector,
+    stream: Dom<ReadableStream>,
+    /// Null once the algorithms of the controller are cleared.
+    #[ignore_malloc_size_of = "mozjs"]
+    underlying_source: Heap<*mut JSObject>,
+    /// The chunks enqueued by the underlying source, copied out of their views.
+    queue: DomRefCell<VecDeque<Vec<u8>>>,
+    pending_pull_intos: DomRefCell<VecDeque<PullIntoDescriptor>>,
+    byob_request: MutNullableDom<ReadableStreamBYOBRequest>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: f64,
+}
+
+/// The size in bytes of an element of a view of the given type.
+fn element_size(array_type: Type) -> usize {
+    match array_type {
+        Type::Int16 | Type::Uint16 => 2,
+        Type::Int32 | Type::Uint32 | Type::Float32 => 4,
+        Type::Float64 => 8,
+        _ => 1,
+    }
+}
+
+/// Copy `bytes` into the view `view`, starting at byte `offset`.
+#[allow(unsafe_code)]
+fn write_to_view(cx: JSContext, view: HandleObject, offset: usize, bytes: &[u8]) {
+    typedarray!(in(*cx) let array: ArrayBufferView = view.get());
+    if let Ok(mut array) = array {
+        let data = unsafe { array.as_mut_slice() };
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+/// The bytes of the view `view` between `start` and `end`.
+#[allow(unsafe_code)]
+fn read_from_view(cx: JSContext, view: HandleObject, start: usize, end: usize) -> Vec<u8> {
+    typedarray!(in(*cx) let array: ArrayBufferView = view.get());
+    match array {
+        Ok(array) => unsafe { array.as_slice()[start..end].to_vec() },
+        Err(()) => vec![],
+    }
+}
+
+/// A view of the same type as `view` over its elements from `begin` to
+/// `end`, sharing its buffer.
+fn view_subarray(
+    cx: JSContext,
+    view: HandleObject,
+    begin: usize,
+    end: Option<usize>,
+    rval: MutableHandleValue,
+) {
+    let mut args = vec![DoubleValue(begin as f64)];
+    if let Some(end) = end {
+        args.push(DoubleValue(end as f64));
+    }
+    let _ = call_underlying_method(cx, view, "subarray", &args, rval);
+}
+
+impl ReadableByteStreamController {
+    fn new_inherited(stream: &ReadableStream, strategy_hwm: f64) -> ReadableByteStreamController {
+        ReadableByteStreamController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            underlying_source: Heap::default(),
+            queue: DomRefCell::new(VecDeque::new()),
+            pending_pull_intos: DomRefCell::new(VecDeque::new()),
+            byob_request: MutNullableDom::new(None),
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm,
+        }
+    }
+
+    fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+        underlying_source: HandleObject,
+        strategy_hwm: f64,
+    ) -> DomRoot<ReadableByteStreamController> {
+        let controller = reflect_dom_object(
+            Box::new(ReadableByteStreamController::new_inherited(
+                stream,
+                strategy_hwm,
+            )),
+            global,
+            ReadableByteStreamControllerBinding::Wrap,
+        );
+        controller.underlying_source.set(underlying_source.get());
+        controller
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller-from-underlying-source>
+    pub fn set_up_from_underlying_source(
+        stream: &ReadableStream,
+        underlying_source: HandleObject,
+        strategy_hwm: f64,
+    ) -> Fallible<()> {
+        let global = stream.global();
+        let cx = global.get_cx();
+        check_underlying_methods(cx, underlying_source, &["start", "pull", "cancel"])?;
+        // TODO: autoAllocateChunkSize is not supported; reads from a default
+        // reader wait for chunks to be enqueued.
+
+        // https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller
+        let controller =
+            ReadableByteStreamController::new(&global, stream, underlying_source, strategy_hwm);
+        stream.set_controller(ReadableStreamController::Byte(Dom::from_ref(&*controller)));
+        rooted!(in(*cx) let this = ObjectValue(controller.reflector().get_jsobject().get()));
+        rooted!(in(*cx) let mut start_result = UndefinedValue());
+        call_underlying_method(
+            cx,
+            underlying_source,
+            "start",
+            &[this.get()],
+            start_result.handle_mut(),
+        )?;
+        let start_promise = promise_resolved_with(&global, start_result.handle());
+        upon_settlement(
+            &start_promise,
+            &*controller,
+            ReadableByteStreamControllerStep::Started,
+        );
+        Ok(())
+    }
+
+    fn queue_total_size(&self) -> usize {
+        self.queue.borrow().iter().map(|chunk| chunk.len()).sum()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        self.underlying_source.set(ptr::null_mut());
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-call-pull-if-needed>
+    fn call_pull_if_needed(&self) {
+        if !self.should_call_pull() {
+            return;
+        }
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+        self.pulling.set(true);
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let source = self.underlying_source.get());
+        rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+        let pull_promise = promise_call(&global, source.handle(), "pull", &[this.get()]);
+        upon_settlement(
+            &pull_promise,
+            self,
+            ReadableByteStreamControllerStep::Pulled,
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        if !self.stream.is_readable() || self.close_requested.get() || !self.started.get() {
+            return false;
+        }
+        if self.stream.has_default_reader() && self.stream.num_read_requests() > 0 {
+            return true;
+        }
+        if self.stream.has_byob_reader() && self.stream.num_read_into_requests() > 0 {
+            return true;
+        }
+        self.desired_size().map_or(false, |size| size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-get-desired-size>
+    fn desired_size(&self) -> Option<f64> {
+        if self.stream.is_errored() {
+            return None;
+        }
+        if self.stream.is_closed() {
+            return Some(0.);
+        }
+        Some(self.strategy_hwm - self.queue_total_size() as f64)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-handle-queue-drain>
+    fn handle_queue_drain(&self) {
+        if self.queue_total_size() == 0 && self.close_requested.get() {
+            self.clear_algorithms();
+            self.stream.close();
+        } else {
+            self.call_pull_if_needed();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-invalidate-byob-request>
+    fn invalidate_byob_request(&self) {
+        if let Some(request) = self.byob_request.get() {
+            request.invalidate();
+            self.byob_request.set(None);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-shift-pending-pull-into>
+    fn shift_pending_pull_into(&self) -> PullIntoDescriptor {
+        let descriptor = self
+            .pending_pull_intos
+            .borrow_mut()
+            .pop_front()
+            .expect("No pending pull-into to shift");
+        self.invalidate_byob_request();
+        descriptor
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-convert-pull-into-descriptor>
+    fn convert_pull_into_descriptor(
+        &self,
+        descriptor: &PullIntoDescriptor,
+        rval: MutableHandleValue,
+    ) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let view = descriptor.view.get());
+        let elements = descriptor.bytes_filled / descriptor.element_size;
+        view_subarray(cx, view.handle(), 0, Some(elements), rval);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-commit-pull-into-descriptor>
+    fn commit_pull_into_descriptor(&self, descriptor: &PullIntoDescriptor) {
+        let cx = self.global().get_cx();
+        let done = self.stream.is_closed();
+        rooted!(in(*cx) let mut filled_view = UndefinedValue());
+        self.convert_pull_into_descriptor(descriptor, filled_view.handle_mut());
+        self.stream
+            .fulfill_read_into_request(filled_view.handle(), done);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-fill-pull-into-descriptor-from-queue>
+    fn fill_pull_into_descriptor_from_queue(&self, descriptor: &mut PullIntoDescriptor) -> bool {
+        let cx = self.global().get_cx();
+        // Steps 1-7.
+        let current_aligned_bytes =
+            descriptor.bytes_filled - descriptor.bytes_filled % descriptor.element_size;
+        let max_bytes_to_copy = cmp::min(
+            self.queue_total_size(),
+            descriptor.byte_length - descriptor.bytes_filled,
+        );
+        let max_bytes_filled = descriptor.bytes_filled + max_bytes_to_copy;
+        let max_aligned_bytes = max_bytes_filled - max_bytes_filled % descriptor.element_size;
+        let mut total_bytes_to_copy_remaining = max_bytes_to_copy;
+        let mut ready = false;
+        if max_aligned_bytes > current_aligned_bytes {
+            total_bytes_to_copy_remaining = max_aligned_bytes - descriptor.bytes_filled;
+            ready = true;
+        }
+        // Steps 8-9.
+        rooted!(in(*cx) let view = descriptor.view.get());
+        let mut queue = self.queue.borrow_mut();
+        while total_bytes_to_copy_remaining > 0 {
+            let head = queue.front_mut().expect("The queue ran out of bytes");
+            let bytes_to_copy = cmp::min(total_bytes_to_copy_remaining, head.len());
+            write_to_view(
+                cx,
+                view.handle(),
+                descriptor.bytes_filled,
+                &head[..bytes_to_copy],
+            );
+            if head.len() == bytes_to_copy {
+                queue.pop_front();
+            } else {
+                head.drain(..bytes_to_copy);
+            }
+            descriptor.bytes_filled += bytes_to_copy;
+            total_bytes_to_copy_remaining -= bytes_to_copy;
+        }
+        // Step 10.
+        ready
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-process-pull-into-descriptors-using-queue>
+    fn process_pull_into_descriptors_using_queue(&self) {
+        loop {
+            if self.pending_pull_intos.borrow().is_empty() || self.queue_total_size() == 0 {
+                return;
+            }
+            let ready = {
+                let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+                let descriptor = pending_pull_intos.front_mut().unwrap();
+                self.fill_pull_into_descriptor_from_queue(descriptor)
+            };
+            if ready {
+                let descriptor = self.shift_pending_pull_into();
+                self.commit_pull_into_descriptor(&descriptor);
+            }
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-pull-into>
+    #[allow(unsafe_code)]
+    pub fn pull_into(&self, view: HandleObject) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Steps 2-6.
+        typedarray!(in(*cx) let array: ArrayBufferView = view.get());
+        let (element_size, byte_length) = match array {
+            Ok(array) => (
+                element_size(array.get_array_type()),
+                unsafe { array.as_slice() }.len(),
+            ),
+            Err(()) => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("Expected an ArrayBufferView".to_owned()),
+                );
+            },
+        };
+        let mut descriptor = PullIntoDescriptor {
+            view: Heap::boxed(view.get()),
+            byte_length,
+            bytes_filled: 0,
+            element_size,
+        };
+        // Step 8.
+        if !self.pending_pull_intos.borrow().is_empty() {
+            self.pending_pull_intos.borrow_mut().push_back(descriptor);
+            return self.stream.add_read_into_request();
+        }
+        // Step 9.
+        if self.stream.is_closed() {
+            rooted!(in(*cx) let mut empty_view = UndefinedValue());
+            view_subarray(cx, view, 0, Some(0), empty_view.handle_mut());
+            return read_result_promise(&global, empty_view.handle(), true);
+        }
+        // Step 10.
+        if self.queue_total_size() > 0 {
+            if self.fill_pull_into_descriptor_from_queue(&mut descriptor) {
+                rooted!(in(*cx) let mut filled_view = UndefinedValue());
+                self.convert_pull_into_descriptor(&descriptor, filled_view.handle_mut());
+                self.handle_queue_drain();
+                return read_result_promise(&global, filled_view.handle(), false);
+            }
+            if self.close_requested.get() {
+                rooted!(in(*cx) let mut error = UndefinedValue());
+                error_to_jsval(
+                    &global,
+                    Error::Type("The stream is closing".to_owned()),
+                    error.handle_mut(),
+                );
+                self.error(error.handle());
+                return promise_rejected_with(&global, error.handle());
+            }
+        }
+        // Steps 11-14.
+        self.pending_pull_intos.borrow_mut().push_back(descriptor);
+        let promise = self.stream.add_read_into_request();
+        self.call_pull_if_needed();
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-pull>
+    #[allow(unsafe_code)]
+    pub fn pull_steps(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Step 3.
+        if self.queue_total_size() > 0 {
+            let bytes = self.queue.borrow_mut().pop_front().unwrap_or_default();
+            self.handle_queue_drain();
+            rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+            let _ =
+                unsafe { Uint8Array::create(*cx, CreateWith::Slice(&bytes), view.handle_mut()) };
+            rooted!(in(*cx) let view = ObjectValue(view.get()));
+            return read_result_promise(&global, view.handle(), false);
+        }
+        // Steps 5-7.
+        let promise = self.stream.add_read_request();
+        self.call_pull_if_needed();
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-cancel>
+    pub fn cancel_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if let Some(descriptor) = self.pending_pull_intos.borrow_mut().front_mut() {
+            descriptor.bytes_filled = 0;
+        }
+        self.queue.borrow_mut().clear();
+        rooted!(in(*cx) let source = self.underlying_source.get());
+        let result = promise_call(&global, source.handle(), "cancel", &[reason.get()]);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-close>
+    fn close(&self) -> Fallible<()> {
+        // Step 3.
+        if self.queue_total_size() > 0 {
+            self.close_requested.set(true);
+            return Ok(());
+        }
+        // Step 4.
+        let partially_filled = self
+            .pending_pull_intos
+            .borrow()
+            .front()
+            .map_or(false, |descriptor| descriptor.bytes_filled > 0);
+        if partially_filled {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            error_to_jsval(
+                &global,
+                Error::Type("Cannot close with a partially filled view".to_owned()),
+                error.handle_mut(),
+            );
+            self.error(error.handle());
+            return Err(throw_jsval(cx, error.handle()));
+        }
+        // Steps 5-6.
+        self.clear_algorithms();
+        self.stream.close();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-enqueue>
+    #[allow(unsafe_code)]
+    fn enqueue(&self, bytes: Vec<u8>) {
+        let cx = self.global().get_cx();
+        if self.stream.has_default_reader() && self.stream.num_read_requests() > 0 {
+            rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+            let _ =
+                unsafe { Uint8Array::create(*cx, CreateWith::Slice(&bytes), view.handle_mut()) };
+            rooted!(in(*cx) let view = ObjectValue(view.get()));
+            self.stream.fulfill_read_request(view.handle(), false);
+        } else if self.stream.has_byob_reader() {
+            self.queue.borrow_mut().push_back(bytes);
+            self.process_pull_into_descriptors_using_queue();
+        } else {
+            self.queue.borrow_mut().push_back(bytes);
+        }
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-error>
+    fn error(&self, e: HandleValue) {
+        if !self.stream.is_readable() {
+            return;
+        }
+        self.invalidate_byob_request();
+        self.pending_pull_intos.borrow_mut().clear();
+        self.queue.borrow_mut().clear();
+        self.clear_algorithms();
+        self.stream.error(e);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond>
+    pub fn respond(&self, bytes_written: usize) -> ErrorResult {
+        if self.pending_pull_intos.borrow().is_empty() {
+            return Err(Error::Type("There is no pending read".to_owned()));
+        }
+        // https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-internal
+        if self.stream.is_closed() {
+            if bytes_written != 0 {
+                return Err(Error::Type(
+                    "Cannot write bytes to a closed stream".to_owned(),
+                ));
+            }
+            self.respond_in_closed_state();
+        } else {
+            self.respond_in_readable_state(bytes_written)?;
+        }
+        self.call_pull_if_needed();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-closed-state>
+    fn respond_in_closed_state(&self) {
+        if !self.stream.has_byob_reader() {
+            return;
+        }
+        while self.stream.num_read_into_requests() > 0 &&
+            !self.pending_pull_intos.borrow().is_empty()
+        {
+            let descriptor = self.shift_pending_pull_into();
+            self.commit_pull_into_descriptor(&descriptor);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-readable-state>
+    fn respond_in_readable_state(&self, bytes_written: usize) -> ErrorResult {
+        // Steps 1-3.
+        {
+            let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+            let descriptor = pending_pull_intos.front_mut().unwrap();
+            if descriptor.bytes_filled + bytes_written > descriptor.byte_length {
+                return Err(Error::Range(
+                    "More bytes were written than the view holds".to_owned(),
+                ));
+            }
+            descriptor.bytes_filled += bytes_written;
+            // Step 4.
+            if descriptor.bytes_filled < descriptor.element_size {
+                return Ok(());
+            }
+        }
+        // Steps 5-7.
+        let mut descriptor = self.shift_pending_pull_into();
+        let remainder_size = descriptor.bytes_filled % descriptor.element_size;
+        if remainder_size > 0 {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let view = descriptor.view.get());
+            let end = descriptor.bytes_filled;
+            let remainder = read_from_view(cx, view.handle(), end - remainder_size, end);
+            self.queue.borrow_mut().push_back(remainder);
+        }
+        // Steps 8-10.
+        descriptor.bytes_filled -= remainder_size;
+        self.commit_pull_into_descriptor(&descriptor);
+        self.process_pull_into_descriptors_using_queue();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-with-new-view>
+    ///
+    /// The bytes of `view` are copied into the view of the pending read rather
+    /// than taking over its buffer.
+    #[allow(unsafe_code)]
+    pub fn respond_with_new_view(&self, view: &ArrayBufferView) -> ErrorResult {
+        let cx = self.global().get_cx();
+        let bytes = unsafe { view.as_slice() }.to_vec();
+        rooted!(in(*cx) let mut target = ptr::null_mut::<JSObject>());
+        let offset = match self.pending_pull_intos.borrow().front() {
+            Some(descriptor) => {
+                if descriptor.bytes_filled + bytes.len() > descriptor.byte_length {
+                    return Err(Error::Range(
+                        "The view is larger than the pending read".to_owned(),
+                    ));
+                }
+                target.set(descriptor.view.get());
+                descriptor.bytes_filled
+            },
+            None => return Err(Error::Type("There is no pending read".to_owned())),
+        };
+        write_to_view(cx, target.handle(), offset, &bytes);
+        self.respond(bytes.len())
+    }
+}
+
+impl PromiseReaction for ReadableByteStreamController {
+    type Step = ReadableByteStreamControllerStep;
+
+    fn settled(
+        &self,
+        step: &ReadableByteStreamControllerStep,
+        result: Result<HandleValue, HandleValue>,
+    ) {
+        match (step, result) {
+            (ReadableByteStreamControllerStep::Started, Ok(_)) => {
+                self.started.set(true);
+                self.call_pull_if_needed();
+            },
+            (ReadableByteStreamControllerStep::Pulled, Ok(_)) => {
+                self.pulling.set(false);
+                if self.pull_again.get() {
+                    self.pull_again.set(false);
+                    self.call_pull_if_needed();
+                }
+            },
+            (_, Err(e)) => self.error(e),
+        }
+    }
+}
+
+impl ReadableByteStreamControllerMethods for ReadableByteStreamController {
+    // https://streams.spec.whatwg.org/#rbs-controller-byob-request
+    fn GetByobRequest(&self) -> Option<DomRoot<ReadableStreamBYOBRequest>> {
+        if self.byob_request.get().is_none() {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut target = ptr::null_mut::<JSObject>());
+            let begin = match self.pending_pull_intos.borrow().front() {
+                Some(descriptor) => {
+                    target.set(descriptor.view.get());
+                    descriptor.bytes_filled / descriptor.element_size
+                },
+                None => return None,
+            };
+            // The view covers the unfilled elements of the view passed to
+            // read(), rather than being a Uint8Array of its bytes.
+            rooted!(in(*cx) let mut view = UndefinedValue());
+            view_subarray(cx, target.handle(), begin, None, view.handle_mut());
+            if !view.is_object() {
+                return None;
+            }
+            rooted!(in(*cx) let view = view.to_object());
+            let request = ReadableStreamBYOBRequest::new(&global, self, view.handle());
+            self.byob_request.set(Some(&request));
+        }
+        self.byob_request.get()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-close
+    fn Close(&self) -> ErrorResult {
+        if self.close_requested.get() || !self.stream.is_readable() {
+            return Err(Error::Type("The stream cannot be closed".to_owned()));
+        }
+        self.close()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-enqueue
+    #[allow(unsafe_code)]
+    fn Enqueue(&self, chunk: CustomAutoRooterGuard<ArrayBufferView>) -> ErrorResult {
+        if self.close_requested.get() || !self.stream.is_readable() {
+            return Err(Error::Type("The stream cannot be enqueued to".to_owned()));
+        }
+        let bytes = unsafe { chunk.as_slice() }.to_vec();
+        if bytes.is_empty() {
+            return Err(Error::Type("Cannot enqueue an empty chunk".to_owned()));
+        }
+        self.enqueue(bytes);
+        Ok(())
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-error
+    fn Error(&self, _cx: JSContext, e: HandleValue) {
+        self.error(e);
+    }
+}
@@ -0,0 +1,1038 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding::{
+    ReadableStreamGetReaderOptions, ReadableStreamMethods, ReadableStreamReaderMode,
+};
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding::{
+    ReadableWritablePair, StreamPipeOptions,
+};
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding::ReadableStreamDefaultReaderMethods;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding::WritableStreamDefaultWriterMethods;
+use crate::dom::bindings::codegen::UnionTypes::ReadableStreamDefaultReaderOrReadableStreamBYOBReader as ReadableStreamReaderUnion;
+use crate::dom::bindings::conversions::{
+    get_property_jsval, ConversionResult, FromJSValConvertible, StringificationBehavior,
+};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablebytestreamcontroller::ReadableByteStreamController;
+use crate::dom::readablestreambyobreader::ReadableStreamBYOBReader;
+use crate::dom::readablestreamdefaultcontroller::{
+    ReadableStreamDefaultController, TeeBranch, UnderlyingSource,
+};
+use crate::dom::readablestreamdefaultreader::ReadableStreamDefaultReader;
+use crate::dom::writablestream::WritableStream;
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use crate::script_runtime::JSContext;
+use crate::streams::{error_to_jsval, parse_read_result, promise_rejected_with};
+use crate::streams::{promise_rejected_with_error, promise_resolved_with};
+use crate::streams::{transform_to_undefined, upon_settlement, PromiseReaction};
+use dom_struct::dom_struct;
+use js::jsapi::{HandleValueArray, Heap, JSObject, JS_NewArrayObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use js::typedarray::{CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#readablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum ReadableStreamState {
+    Readable,
+    Closed,
+    Errored,
+}
+
+/// <https://streams.spec.whatwg.org/#readablestream-controller>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum ReadableStreamController {
+    Default(Dom<ReadableStreamDefaultController>),
+    Byte(Dom<ReadableByteStreamController>),
+}
+
+/// <https://streams.spec.whatwg.org/#readablestream-reader>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum ReadableStreamReader {
+    Default(Dom<ReadableStreamDefaultReader>),
+    BYOB(Dom<ReadableStreamBYOBReader>),
+}
+
+/// The state of <https://streams.spec.whatwg.org/#readable-stream-tee>
+/// while this stream is the source of a tee.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct TeeState {
+    reader: Dom<ReadableStreamDefaultReader>,
+    branch1: Dom<ReadableStream>,
+    branch2: Dom<ReadableStream>,
+    reading: bool,
+    canceled1: bool,
+    canceled2: bool,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason1: Box<Heap<JSVal>>,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason2: Box<Heap<JSVal>>,
+    #[ignore_malloc_size_of = "Rc"]
+    cancel_promise: Rc<Promise>,
+}
+
+/// The state of <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+/// while this stream is the source of a pipe.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct PipeState {
+    reader: Dom<ReadableStreamDefaultReader>,
+    writer: Dom<WritableStreamDefaultWriter>,
+    destination: Dom<WritableStream>,
+    prevent_close: bool,
+    prevent_abort: bool,
+    prevent_cancel: bool,
+    shutting_down: bool,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    /// The promise for the last chunk written to the destination.
+    #[ignore_malloc_size_of = "Rc"]
+    current_write: Option<Rc<Promise>>,
+}
+
+/// What to do to the other end of a pipe when shutting it down.
+#[derive(Clone, Copy, JSTraceable, PartialEq)]
+pub enum PipeAction {
+    AbortDestination,
+    CancelSource,
+    CloseDestination,
+    Nothing,
+}
+
+/// The steps this stream takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum ReadableStreamStep {
+    /// A read of the source of a tee finished.
+    TeeRead,
+    /// The reader of the source of a tee closed or errored.
+    TeeClosed,
+    /// The destination of a pipe is ready to take another chunk.
+    PipeReady,
+    /// A read of the source of a pipe finished.
+    PipeRead,
+    /// A write to the destination of a pipe finished.
+    PipeWritten,
+    /// The source or the destination of a pipe closed or errored.
+    PipeClosed,
+    /// The pending writes of a pipe finished, so the action of its shutdown can run.
+    PipeWritesFinished(PipeAction, Option<Box<Heap<JSVal>>>),
+    /// The action of the shutdown of a pipe finished.
+    PipeActionFinished(Option<Box<Heap<JSVal>>>),
+    /// A read of the whole stream got another chunk.
+    ReadAllBytes(Rc<Promise>, DomRefCell<Vec<u8>>),
+}
+
+// https://streams.spec.whatwg.org/#rs-class
+#[dom_struct]
+pub struct ReadableStream {
+    reflector_: Reflector,
+    state: Cell<ReadableStreamState>,
+    #[ignore_malloc_size_of = "mozjs"]
+    stored_error: Heap<JSVal>,
+    disturbed: Cell<bool>,
+    controller: DomRefCell<Option<ReadableStreamController>>,
+    reader: DomRefCell<Option<ReadableStreamReader>>,
+    tee: DomRefCell<Option<TeeState>>,
+    pipe: DomRefCell<Option<PipeState>>,
+}
+
+impl ReadableStream {
+    fn new_inherited() -> ReadableStream {
+        ReadableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(ReadableStreamState::Readable),
+            stored_error: Heap::default(),
+            disturbed: Cell::new(false),
+            controller: DomRefCell::new(None),
+            reader: DomRefCell::new(None),
+            tee: DomRefCell::new(None),
+            pipe: DomRefCell::new(None),
+        }
+    }
+
+    /// Create a stream without a controller; one of the controller set up
+    /// algorithms must be run on it before it is handed out.
+    pub fn new(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        reflect_dom_object(
+            Box::new(ReadableStream::new_inherited()),
+            global,
+            ReadableStreamBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#rs-constructor
+    #[allow(unsafe_code)]
+    pub fn Constructor(
+        cx: JSContext,
+        global: &GlobalScope,
+        underlying_source: Option<*mut JSObject>,
+        strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<ReadableStream>> {
+        rooted!(in(*cx) let source = underlying_source.unwrap_or(ptr::null_mut()));
+        let stream = ReadableStream::new(global);
+
+        // Step 4.
+        rooted!(in(*cx) let mut type_ = UndefinedValue());
+        if !source.is_null() {
+            unsafe { get_property_jsval(*cx, source.handle(), "type", type_.handle_mut())? };
+        }
+
+        // Step 6.
+        if type_.is_undefined() {
+            let high_water_mark = crate::streams::extract_high_water_mark(strategy, 1.)?;
+            ReadableStreamDefaultController::set_up_from_underlying_source(
+                &stream,
+                source.handle(),
+                high_water_mark,
+                strategy.size.clone(),
+            )?;
+            return Ok(stream);
+        }
+
+        // Step 5.
+        let type_: DOMString = match unsafe {
+            FromJSValConvertible::from_jsval(*cx, type_.handle(), StringificationBehavior::Default)
+        } {
+            Ok(ConversionResult::Success(type_)) => type_,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into())),
+            Err(()) => return Err(Error::JSFailed),
+        };
+        if &*type_ != "bytes" {
+            return Err(Error::Range(format!(
+                "{} is not a valid stream type",
+                type_
+            )));
+        }
+        if strategy.size.is_some() {
+            return Err(Error::Range(
+                "A byte stream cannot have a size algorithm".to_owned(),
+            ));
+        }
+        let high_water_mark = crate::streams::extract_high_water_mark(strategy, 0.)?;
+        ReadableByteStreamController::set_up_from_underlying_source(
+            &stream,
+            source.handle(),
+            high_water_mark,
+        )?;
+        Ok(stream)
+    }
+
+    /// Create a stream whose chunks are pushed from native code, such as the
+    /// body of a fetched response.
+    pub fn new_with_native_source(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        let stream = ReadableStream::new(global);
+        ReadableStreamDefaultController::set_up(&stream, UnderlyingSource::Native, 1., None)
+            .expect("Native sources cannot throw when starting");
+        stream
+    }
+
+    /// Enqueue `bytes` as a Uint8Array chunk of a stream with a native source.
+    #[allow(unsafe_code)]
+    pub fn enqueue_native(&self, bytes: &[u8]) {
+        let controller = match self.default_controller() {
+            Some(controller) => controller,
+            None => return,
+        };
+        if !controller.can_close_or_enqueue() {
+            return;
+        }
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut chunk = ptr::null_mut::<JSObject>());
+        let _ = unsafe { Uint8Array::create(*cx, CreateWith::Slice(bytes), chunk.handle_mut()) };
+        rooted!(in(*cx) let chunk = ObjectValue(chunk.get()));
+        let _ = controller.enqueue(chunk.handle());
+    }
+
+    /// Close a stream with a native source once all its chunks are enqueued.
+    pub fn close_native(&self) {
+        if let Some(controller) = self.default_controller() {
+            if controller.can_close_or_enqueue() {
+                controller.close();
+            }
+        }
+    }
+
+    /// Error a stream with a native source.
+    pub fn error_native(&self, error: Error) {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut e = UndefinedValue());
+        error_to_jsval(&global, error, e.handle_mut());
+        if let Some(controller) = self.default_controller() {
+            controller.error(e.handle());
+        }
+    }
+
+    pub fn set_controller(&self, controller: ReadableStreamController) {
+        *self.controller.borrow_mut() = Some(controller);
+    }
+
+    pub fn default_controller(&self) -> Option<DomRoot<ReadableStreamDefaultController>> {
+        match *self.controller.borrow() {
+            Some(ReadableStreamController::Default(ref controller)) => {
+                Some(DomRoot::from_ref(&**controller))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn byte_controller(&self) -> Option<DomRoot<ReadableByteStreamController>> {
+        match *self.controller.borrow() {
+            Some(ReadableStreamController::Byte(ref controller)) => {
+                Some(DomRoot::from_ref(&**controller))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn set_reader(&self, reader: Option<ReadableStreamReader>) {
+        *self.reader.borrow_mut() = reader;
+    }
+
+    fn default_reader(&self) -> Option<DomRoot<ReadableStreamDefaultReader>> {
+        match *self.reader.borrow() {
+            Some(ReadableStreamReader::Default(ref reader)) => Some(DomRoot::from_ref(&**reader)),
+            _ => None,
+        }
+    }
+
+    fn byob_reader(&self) -> Option<DomRoot<ReadableStreamBYOBReader>> {
+        match *self.reader.borrow() {
+            Some(ReadableStreamReader::BYOB(ref reader)) => Some(DomRoot::from_ref(&**reader)),
+            _ => None,
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.reader.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-disturbed>
+    pub fn is_disturbed(&self) -> bool {
+        self.disturbed.get()
+    }
+
+    pub fn set_disturbed(&self) {
+        self.disturbed.set(true);
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.state.get() == ReadableStreamState::Readable
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.get() == ReadableStreamState::Closed
+    }
+
+    pub fn is_errored(&self) -> bool {
+        self.state.get() == ReadableStreamState::Errored
+    }
+
+    pub fn stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    /// <https://streams.spec.whatwg.org/#has-default-reader>
+    pub fn has_default_reader(&self) -> bool {
+        self.default_reader().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-has-byob-reader>
+    pub fn has_byob_reader(&self) -> bool {
+        self.byob_reader().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-request>
+    pub fn add_read_request(&self) -> Rc<Promise> {
+        self.default_reader()
+            .expect("Adding a read request without a default reader")
+            .add_read_request()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-into-request>
+    pub fn add_read_into_request(&self) -> Rc<Promise> {
+        self.byob_reader()
+            .expect("Adding a read-into request without a BYOB reader")
+            .add_read_into_request()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-request>
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        if let Some(reader) = self.default_reader() {
+            reader.fulfill_read_request(chunk, done);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-into-request>
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        if let Some(reader) = self.byob_reader() {
+            reader.fulfill_read_into_request(chunk, done);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-requests>
+    pub fn num_read_requests(&self) -> usize {
+        self.default_reader()
+            .map_or(0, |reader| reader.num_read_requests())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-into-requests>
+    pub fn num_read_into_requests(&self) -> usize {
+        self.byob_reader()
+            .map_or(0, |reader| reader.num_read_into_requests())
+    }
+
+    /// The `[[PullSteps]]` of the controller of this stream.
+    pub fn pull_steps(&self) -> Rc<Promise> {
+        if let Some(controller) = self.default_controller() {
+            return controller.pull_steps();
+        }
+        self.byte_controller()
+            .expect("A stream without a controller")
+            .pull_steps()
+    }
+
+    /// The `[[CancelSteps]]` of the controller of this stream.
+    fn cancel_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        if let Some(controller) = self.default_controller() {
+            return controller.cancel_steps(reason);
+        }
+        self.byte_controller()
+            .expect("A stream without a controller")
+            .cancel_steps(reason)
+    }
+
+    /// <https://streams.spec.whatwg.org/#acquire-readable-stream-reader>
+    pub fn acquire_default_reader(&self) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        ReadableStreamDefaultReader::new(&self.global(), self)
+    }
+
+    /// <https://streams.spec.whatwg.org/#acquire-readable-stream-byob-reader>
+    pub fn acquire_byob_reader(&self) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        ReadableStreamBYOBReader::new(&self.global(), self)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-cancel>
+    pub fn cancel(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Step 1.
+        self.disturbed.set(true);
+        match self.state.get() {
+            // Step 2.
+            ReadableStreamState::Closed => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+            // Step 3.
+            ReadableStreamState::Errored => {
+                rooted!(in(*cx) let mut error = UndefinedValue());
+                self.stored_error(error.handle_mut());
+                promise_rejected_with(&global, error.handle())
+            },
+            // Steps 4-6.
+            ReadableStreamState::Readable => {
+                self.close();
+                let source_cancel = self.cancel_steps(reason);
+                transform_to_undefined(&global, &source_cancel)
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-close>
+    pub fn close(&self) {
+        // Steps 1-2.
+        assert!(self.is_readable());
+        self.state.set(ReadableStreamState::Closed);
+        // Steps 3-6.
+        if let Some(reader) = self.default_reader() {
+            reader.close_read_requests();
+            reader.resolve_closed();
+        } else if let Some(reader) = self.byob_reader() {
+            reader.resolve_closed();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-error>
+    pub fn error(&self, e: HandleValue) {
+        // Steps 1-3.
+        assert!(self.is_readable());
+        self.state.set(ReadableStreamState::Errored);
+        self.stored_error.set(e.get());
+        // Steps 4-8.
+        if let Some(reader) = self.default_reader() {
+            reader.error_read_requests(e);
+            reader.reject_closed(e);
+        } else if let Some(reader) = self.byob_reader() {
+            reader.error_read_into_requests(e);
+            reader.reject_closed(e);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-tee>
+    pub fn tee(&self) -> Fallible<(DomRoot<ReadableStream>, DomRoot<ReadableStream>)> {
+        let global = self.global();
+        // Step 3.
+        let reader = self.acquire_default_reader()?;
+        // Steps 4-11.
+        let branch1 = ReadableStream::new(&global);
+        let branch2 = ReadableStream::new(&global);
+        *self.tee.borrow_mut() = Some(TeeState {
+            reader: Dom::from_ref(&*reader),
+            branch1: Dom::from_ref(&*branch1),
+            branch2: Dom::from_ref(&*branch2),
+            reading: false,
+            canceled1: false,
+            canceled2: false,
+            reason1: Heap::boxed(UndefinedValue()),
+            reason2: Heap::boxed(UndefinedValue()),
+            cancel_promise: Promise::new(&global),
+        });
+        // Steps 12-18.
+        ReadableStreamDefaultController::set_up(
+            &branch1,
+            UnderlyingSource::Tee(Dom::from_ref(self), TeeBranch::First),
+            1.,
+            None,
+        )?;
+        ReadableStreamDefaultController::set_up(
+            &branch2,
+            UnderlyingSource::Tee(Dom::from_ref(self), TeeBranch::Second),
+            1.,
+            None,
+        )?;
+        // Step 19.
+        upon_settlement(&reader.Closed(), self, ReadableStreamStep::TeeClosed);
+        // Step 20.
+        Ok((branch1, branch2))
+    }
+
+    /// The pull algorithm of the branches of a tee of this stream.
+    pub fn tee_pull(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        let reader = {
+            let mut tee = self.tee.borrow_mut();
+            let tee = tee
+                .as_mut()
+                .expect("Pulling from a tee that does not exist");
+            if tee.reading {
+                return promise_resolved_with(&global, undefined.handle());
+            }
+            tee.reading = true;
+            DomRoot::from_ref(&*tee.reader)
+        };
+        upon_settlement(&reader.read(), self, ReadableStreamStep::TeeRead);
+        promise_resolved_with(&global, undefined.handle())
+    }
+
+    /// The cancel algorithm of the given branch of a tee of this stream.
+    #[allow(unsafe_code)]
+    pub fn tee_cancel(&self, branch: TeeBranch, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let mut reasons = [UndefinedValue(), UndefinedValue()];
+        let cancel_promise = {
+            let mut tee = self.tee.borrow_mut();
+            let tee = tee.as_mut().expect("Canceling a tee that does not exist");
+            match branch {
+                TeeBranch::First => {
+                    tee.canceled1 = true;
+                    tee.reason1.set(reason.get());
+                },
+                TeeBranch::Second => {
+                    tee.canceled2 = true;
+                    tee.reason2.set(reason.get());
+                },
+            }
+            if !(tee.canceled1 && tee.canceled2) {
+                return tee.cancel_promise.clone();
+            }
+            reasons[0] = tee.reason1.get();
+            reasons[1] = tee.reason2.get();
+            tee.cancel_promise.clone()
+        };
+        let reasons = unsafe { HandleValueArray::from_rooted_slice(&reasons) };
+        rooted!(in(*cx) let composite = unsafe { JS_NewArrayObject(*cx, &reasons) });
+        rooted!(in(*cx) let composite = ObjectValue(composite.get()));
+        let cancel_result = self.cancel(composite.handle());
+        rooted!(in(*cx) let cancel_result = ObjectValue(cancel_result.reflector().get_jsobject().get()));
+        unsafe { cancel_promise.resolve(*cx, cancel_result.handle()) };
+        cancel_promise
+    }
+
+    fn tee_read(&self, result: HandleValue) {
+        let cx = self.global().get_cx();
+        let (branches, canceled) = {
+            let mut tee = self.tee.borrow_mut();
+            let tee = tee
+                .as_mut()
+                .expect("Reading from a tee that does not exist");
+            tee.reading = false;
+            (
+                [
+                    DomRoot::from_ref(&*tee.branch1),
+                    DomRoot::from_ref(&*tee.branch2),
+                ],
+                [tee.canceled1, tee.canceled2],
+            )
+        };
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        let done = parse_read_result(cx, result, value.handle_mut());
+        for (branch, canceled) in branches.iter().zip(canceled.iter()) {
+            if *canceled {
+                continue;
+            }
+            let controller = match branch.default_controller() {
+                Some(controller) => controller,
+                None => continue,
+            };
+            if !controller.can_close_or_enqueue() {
+                continue;
+            }
+            if done {
+                controller.close();
+            } else {
+                let _ = controller.enqueue(value.handle());
+            }
+        }
+    }
+
+    fn tee_closed(&self, reason: HandleValue) {
+        let branches = match *self.tee.borrow() {
+            Some(ref tee) => [
+                DomRoot::from_ref(&*tee.branch1),
+                DomRoot::from_ref(&*tee.branch2),
+            ],
+            None => return,
+        };
+        for branch in branches.iter() {
+            if let Some(controller) = branch.default_controller() {
+                controller.error(reason);
+            }
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    pub fn pipe_to(
+        &self,
+        destination: &WritableStream,
+        prevent_close: bool,
+        prevent_abort: bool,
+        prevent_cancel: bool,
+    ) -> Rc<Promise> {
+        let global = self.global();
+        // Steps 5-6.
+        let reader = match self.acquire_default_reader() {
+            Ok(reader) => reader,
+            Err(error) => return promise_rejected_with_error(&global, error),
+        };
+        let writer = match destination.acquire_writer() {
+            Ok(writer) => writer,
+            Err(error) => {
+                reader.release();
+                return promise_rejected_with_error(&global, error);
+            },
+        };
+        // Steps 7-9.
+        self.disturbed.set(true);
+        let promise = Promise::new(&global);
+        *self.pipe.borrow_mut() = Some(PipeState {
+            reader: Dom::from_ref(&*reader),
+            writer: Dom::from_ref(&*writer),
+            destination: Dom::from_ref(destination),
+            prevent_close,
+            prevent_abort,
+            prevent_cancel,
+            shutting_down: false,
+            promise: promise.clone(),
+            current_write: None,
+        });
+        // Step 14.
+        upon_settlement(&reader.Closed(), self, ReadableStreamStep::PipeClosed);
+        upon_settlement(&writer.Closed(), self, ReadableStreamStep::PipeClosed);
+        if !self.pipe_check_state() {
+            self.pipe_wait_ready();
+        }
+        // Step 15.
+        promise
+    }
+
+    fn pipe_parts(
+        &self,
+    ) -> Option<(
+        DomRoot<ReadableStreamDefaultReader>,
+        DomRoot<WritableStreamDefaultWriter>,
+        DomRoot<WritableStream>,
+    )> {
+        match *self.pipe.borrow() {
+            Some(ref pipe) if !pipe.shutting_down => Some((
+                DomRoot::from_ref(&*pipe.reader),
+                DomRoot::from_ref(&*pipe.writer),
+                DomRoot::from_ref(&*pipe.destination),
+            )),
+            _ => None,
+        }
+    }
+
+    fn pipe_wait_ready(&self) {
+        if let Some((_, writer, _)) = self.pipe_parts() {
+            upon_settlement(&writer.Ready(), self, ReadableStreamStep::PipeReady);
+        }
+    }
+
+    /// Check the error and close conditions of a pipe, shutting it down if one
+    /// holds. Returns whether the pipe is shutting down.
+    fn pipe_check_state(&self) -> bool {
+        let (destination, prevent_close, prevent_abort, prevent_cancel) = match *self.pipe.borrow()
+        {
+            Some(ref pipe) if !pipe.shutting_down => (
+                DomRoot::from_ref(&*pipe.destination),
+                pipe.prevent_close,
+                pipe.prevent_abort,
+                pipe.prevent_cancel,
+            ),
+            _ => return true,
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+
+        // Errors must be propagated forward.
+        if self.is_errored() {
+            self.stored_error(error.handle_mut());
+            let action = if prevent_abort {
+                PipeAction::Nothing
+            } else {
+                PipeAction::AbortDestination
+            };
+            self.pipe_shutdown(action, Some(error.handle()));
+            return true;
+        }
+
+        // Errors must be propagated backward.
+        if destination.is_errored() || destination.is_erroring() {
+            destination.stored_error(error.handle_mut());
+            let action = if prevent_cancel {
+                PipeAction::Nothing
+            } else {
+                PipeAction::CancelSource
+            };
+            self.pipe_shutdown(action, Some(error.handle()));
+            return true;
+        }
+
+        // Closing must be propagated forward.
+        if self.is_closed() {
+            let action = if prevent_close {
+                PipeAction::Nothing
+            } else {
+                PipeAction::CloseDestination
+            };
+            self.pipe_shutdown(action, None);
+            return true;
+        }
+
+        // Closing must be propagated backward.
+        if destination.close_queued_or_in_flight() || destination.is_closed() {
+            error_to_jsval(
+                &global,
+                Error::Type("The destination of the pipe is closed".to_owned()),
+                error.handle_mut(),
+            );
+            let action = if prevent_cancel {
+                PipeAction::Nothing
+            } else {
+                PipeAction::CancelSource
+            };
+            self.pipe_shutdown(action, Some(error.handle()));
+            return true;
+        }
+
+        false
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-shutdown-with-action>
+    fn pipe_shutdown(&self, action: PipeAction, error: Option<HandleValue>) {
+        let (destination, current_write) = {
+            let mut pipe = self.pipe.borrow_mut();
+            let pipe = match pipe.as_mut() {
+                Some(pipe) if !pipe.shutting_down => pipe,
+                _ => return,
+            };
+            pipe.shutting_down = true;
+            (
+                DomRoot::from_ref(&*pipe.destination),
+                pipe.current_write.clone(),
+            )
+        };
+        let error = error.map(|error| Heap::boxed(error.get()));
+        if destination.is_writable() && !destination.close_queued_or_in_flight() {
+            if let Some(current_write) = current_write {
+                if !current_write.is_fulfilled() {
+                    let step = ReadableStreamStep::PipeWritesFinished(action, error);
+                    upon_settlement(&current_write, self, step);
+                    return;
+                }
+            }
+        }
+        self.pipe_perform_action(action, error);
+    }
+
+    fn pipe_perform_action(&self, action: PipeAction, error: Option<Box<Heap<JSVal>>>) {
+        let (writer, destination) = match *self.pipe.borrow() {
+            Some(ref pipe) => (
+                DomRoot::from_ref(&*pipe.writer),
+                DomRoot::from_ref(&*pipe.destination),
+            ),
+            None => return,
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut reason = UndefinedValue());
+        if let Some(ref error) = error {
+            reason.set(error.get());
+        }
+        let promise = match action {
+            PipeAction::Nothing => return self.pipe_finalize(error),
+            PipeAction::AbortDestination => destination.abort(reason.handle()),
+            PipeAction::CancelSource => self.cancel(reason.handle()),
+            PipeAction::CloseDestination => writer.close_with_error_propagation(),
+        };
+        upon_settlement(
+            &promise,
+            self,
+            ReadableStreamStep::PipeActionFinished(error),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-finalize>
+    fn pipe_finalize(&self, error: Option<Box<Heap<JSVal>>>) {
+        let pipe = match self.pipe.borrow_mut().take() {
+            Some(pipe) => pipe,
+            None => return,
+        };
+        pipe.writer.release();
+        pipe.reader.release();
+        match error {
+            Some(error) => pipe.promise.reject_native(&error.get()),
+            None => pipe.promise.resolve_native(&()),
+        }
+    }
+
+    fn pipe_read(&self, result: HandleValue) {
+        let (_, writer, _) = match self.pipe_parts() {
+            Some(parts) => parts,
+            None => return,
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        if parse_read_result(cx, result, chunk.handle_mut()) {
+            self.pipe_check_state();
+            return;
+        }
+        let write = writer.write(chunk.handle());
+        if let Some(ref mut pipe) = *self.pipe.borrow_mut() {
+            pipe.current_write = Some(write.clone());
+        }
+        upon_settlement(&write, self, ReadableStreamStep::PipeWritten);
+        self.pipe_wait_ready();
+    }
+
+    /// Read every chunk of this stream, which must all be Uint8Arrays, and
+    /// resolve the returned promise with their concatenation.
+    pub fn read_all_bytes(&self) -> Rc<Promise> {
+        let global = self.global();
+        let reader = match self.acquire_default_reader() {
+            Ok(reader) => reader,
+            Err(error) => return promise_rejected_with_error(&global, error),
+        };
+        let promise = Promise::new(&global);
+        let step = ReadableStreamStep::ReadAllBytes(promise.clone(), DomRefCell::new(vec![]));
+        upon_settlement(&reader.read(), self, step);
+        promise
+    }
+
+    #[allow(unsafe_code)]
+    fn read_all_bytes_chunk(
+        &self,
+        promise: &Rc<Promise>,
+        bytes: &DomRefCell<Vec<u8>>,
+        result: HandleValue,
+    ) {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        let mut bytes = mem::replace(&mut *bytes.borrow_mut(), vec![]);
+        if parse_read_result(cx, result, chunk.handle_mut()) {
+            rooted!(in(*cx) let mut array = ptr::null_mut::<JSObject>());
+            let _ =
+                unsafe { Uint8Array::create(*cx, CreateWith::Slice(&bytes), array.handle_mut()) };
+            rooted!(in(*cx) let array = ObjectValue(array.get()));
+            return promise.resolve_native(&array.get());
+        }
+        if !chunk.is_object() {
+            return promise.reject_error(Error::Type("Chunks must be Uint8Arrays".to_owned()));
+        }
+        typedarray!(in(*cx) let array: Uint8Array = chunk.to_object());
+        match array {
+            Ok(array) => bytes.extend_from_slice(unsafe { array.as_slice() }),
+            Err(()) => {
+                return promise.reject_error(Error::Type("Chunks must be Uint8Arrays".to_owned()));
+            },
+        }
+        let reader = match self.default_reader() {
+            Some(reader) => reader,
+            None => return promise.reject_error(Error::Type("The reader was released".to_owned())),
+        };
+        let step = ReadableStreamStep::ReadAllBytes(promise.clone(), DomRefCell::new(bytes));
+        upon_settlement(&reader.read(), self, step);
+    }
+}
+
+impl PromiseReaction for ReadableStream {
+    type Step = ReadableStreamStep;
+
+    fn settled(&self, step: &ReadableStreamStep, result: Result<HandleValue, HandleValue>) {
+        match (step, result) {
+            (ReadableStreamStep::TeeRead, Ok(result)) => self.tee_read(result),
+            (ReadableStreamStep::TeeRead, Err(_)) => {},
+            (ReadableStreamStep::TeeClosed, Ok(_)) => {},
+            (ReadableStreamStep::TeeClosed, Err(reason)) => self.tee_closed(reason),
+            (ReadableStreamStep::PipeReady, Ok(_)) => {
+                if let Some((reader, _, _)) = self.pipe_parts() {
+                    upon_settlement(&reader.read(), self, ReadableStreamStep::PipeRead);
+                }
+            },
+            (ReadableStreamStep::PipeRead, Ok(result)) => self.pipe_read(result),
+            (ReadableStreamStep::PipeReady, Err(_)) |
+            (ReadableStreamStep::PipeRead, Err(_)) |
+            (ReadableStreamStep::PipeWritten, _) |
+            (ReadableStreamStep::PipeClosed, _) => {
+                self.pipe_check_state();
+            },
+            (ReadableStreamStep::PipeWritesFinished(action, error), _) => {
+                let error = error.as_ref().map(|error| Heap::boxed(error.get()));
+                self.pipe_perform_action(*action, error);
+            },
+            (ReadableStreamStep::PipeActionFinished(error), Ok(_)) => {
+                let error = error.as_ref().map(|error| Heap::boxed(error.get()));
+                self.pipe_finalize(error);
+            },
+            (ReadableStreamStep::PipeActionFinished(_), Err(new_error)) => {
+                self.pipe_finalize(Some(Heap::boxed(new_error.get())));
+            },
+            (ReadableStreamStep::ReadAllBytes(promise, bytes), Ok(result)) => {
+                self.read_all_bytes_chunk(promise, bytes, result);
+            },
+            (ReadableStreamStep::ReadAllBytes(promise, _), Err(error)) => {
+                promise.reject_native(&error.get());
+            },
+        }
+    }
+}
+
+impl ReadableStreamMethods for ReadableStream {
+    // https://streams.spec.whatwg.org/#rs-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    // https://streams.spec.whatwg.org/#rs-cancel
+    fn Cancel(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is locked".to_owned()),
+            );
+        }
+        self.cancel(reason)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-get-reader
+    fn GetReader(
+        &self,
+        options: &ReadableStreamGetReaderOptions,
+    ) -> Fallible<ReadableStreamReaderUnion> {
+        match options.mode {
+            None => Ok(ReadableStreamReaderUnion::ReadableStreamDefaultReader(
+                self.acquire_default_reader()?,
+            )),
+            Some(ReadableStreamReaderMode::Byob) => Ok(
+                ReadableStreamReaderUnion::ReadableStreamBYOBReader(self.acquire_byob_reader()?),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#rs-pipe-through
+    fn PipeThrough(
+        &self,
+        transform: &ReadableWritablePair,
+        options: &StreamPipeOptions,
+    ) -> Fallible<DomRoot<ReadableStream>> {
+        // Steps 2-3.
+        if self.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        if transform.writable.is_locked() {
+            return Err(Error::Type("The writable side is locked".to_owned()));
+        }
+        // Steps 4-5.
+        let _ = self.pipe_to(
+            &transform.writable,
+            options.preventClose,
+            options.preventAbort,
+            options.preventCancel,
+        );
+        // Step 6.
+        Ok(transform.readable.clone())
+    }
+
+    // https://streams.spec.whatwg.org/#rs-pipe-to
+    fn PipeTo(&self, destination: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        // Steps 2-3.
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is locked".to_owned()),
+            );
+        }
+        if destination.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The destination is locked".to_owned()),
+            );
+        }
+        // Steps 4-9.
+        self.pipe_to(
+            destination,
+            options.preventClose,
+            options.preventAbort,
+            options.preventCancel,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#rs-tee
+    fn Tee(&self) -> Fallible<Vec<DomRoot<ReadableStream>>> {
+        let (branch1, branch2) = self.tee()?;
+        Ok(vec![branch1, branch2])
+    }
+}
@@ -0,0 +1,198 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding::ReadableStreamBYOBReaderMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamReader};
+use crate::dom::readablestreamdefaultreader::{initial_closed_promise, release_closed_promise};
+use crate::script_runtime::JSContext;
+use crate::streams::{create_read_result, promise_rejected_with, promise_rejected_with_error};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use js::typedarray::ArrayBufferView;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+
+// https://streams.spec.whatwg.org/#byob-reader-class
+#[dom_struct]
+pub struct ReadableStreamBYOBReader {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    read_into_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamBYOBReader {
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamBYOBReader {
+        ReadableStreamBYOBReader {
+            reflector_: Reflector::new(),
+            stream: MutNullableDom::new(None),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_into_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Lock `stream`, which must be a byte stream, to a new reader, as in
+    /// <https://streams.spec.whatwg.org/#byob-reader-constructor>.
+    pub fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        if stream.byte_controller().is_none() {
+            return Err(Error::Type(
+                "A BYOB reader needs a readable byte stream".to_owned(),
+            ));
+        }
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+        let closed_promise = initial_closed_promise(global, stream);
+        let reader = reflect_dom_object(
+            Box::new(ReadableStreamBYOBReader::new_inherited(closed_promise)),
+            global,
+            ReadableStreamBYOBReaderBinding::Wrap,
+        );
+        reader.stream.set(Some(stream));
+        stream.set_reader(Some(ReadableStreamReader::BYOB(Dom::from_ref(&*reader))));
+        Ok(reader)
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        ReadableStreamBYOBReader::new(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-into-request>
+    pub fn add_read_into_request(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        self.read_into_requests
+            .borrow_mut()
+            .push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-into-request>
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        let promise = match self.read_into_requests.borrow_mut().pop_front() {
+            Some(promise) => promise,
+            None => return,
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut result = UndefinedValue());
+        create_read_result(cx, chunk, done, result.handle_mut());
+        promise.resolve_native(&result.get());
+    }
+
+    pub fn num_read_into_requests(&self) -> usize {
+        self.read_into_requests.borrow().len()
+    }
+
+    /// Reject every pending read-into request, when the stream errors.
+    pub fn error_read_into_requests(&self, e: HandleValue) {
+        let requests = mem::replace(&mut *self.read_into_requests.borrow_mut(), VecDeque::new());
+        for promise in requests {
+            promise.reject_native(&e.get());
+        }
+    }
+
+    pub fn resolve_closed(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed(&self, e: HandleValue) {
+        self.closed_promise.borrow().reject_native(&e.get());
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        release_closed_promise(&self.global(), &stream, &self.closed_promise);
+        stream.set_reader(None);
+        self.stream.set(None);
+    }
+}
+
+impl ReadableStreamBYOBReaderMethods for ReadableStreamBYOBReader {
+    // https://streams.spec.whatwg.org/#byob-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-cancel
+    fn Cancel(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(reason),
+            None => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The reader was released".to_owned()),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-read
+    #[allow(unsafe_code)]
+    fn Read(&self, view: CustomAutoRooterGuard<ArrayBufferView>) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Step 2.
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The reader was released".to_owned()),
+                );
+            },
+        };
+        // Step 5.
+        if unsafe { view.as_slice() }.is_empty() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("Cannot read into an empty view".to_owned()),
+            );
+        }
+        // https://streams.spec.whatwg.org/#readable-stream-byob-reader-read
+        stream.set_disturbed();
+        if stream.is_errored() {
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            stream.stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+        rooted!(in(*cx) let view = *view.underlying_object());
+        stream
+            .byte_controller()
+            .expect("A BYOB reader of a stream without a byte controller")
+            .pull_into(view.handle())
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-release-lock
+    fn ReleaseLock(&self) -> ErrorResult {
+        if self.stream.get().is_none() {
+            return Ok(());
+        }
+        if !self.read_into_requests.borrow().is_empty() {
+            return Err(Error::Type(
+                "Cannot release a reader with pending reads".to_owned(),
+            ));
+        }
+        self.release();
+        Ok(())
+    }
+}
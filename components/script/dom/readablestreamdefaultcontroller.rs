@@ -0,0 +1,434 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding::ReadableStreamDefaultControllerMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamController};
+use crate::dom::transformstream::TransformStream;
+use crate::script_runtime::JSContext;
+use crate::streams::{call_underlying_method, check_underlying_methods, chunk_size};
+use crate::streams::{error_to_jsval, promise_call, promise_resolved_with, read_result_promise};
+use crate::streams::{throw_jsval, upon_settlement, PromiseReaction, QueueWithSizes};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleObject, MutableHandleValue};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// Which of the two streams returned by
+/// <https://streams.spec.whatwg.org/#readable-stream-tee> a source feeds.
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum TeeBranch {
+    First,
+    Second,
+}
+
+/// Where the algorithms of a default controller come from.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum UnderlyingSource {
+    /// An underlying source object passed to the `ReadableStream` constructor.
+    Js(#[ignore_malloc_size_of = "mozjs"] Box<Heap<*mut JSObject>>),
+    /// A source whose chunks are pushed from native code.
+    Native,
+    /// A branch of a tee of the given stream.
+    Tee(Dom<ReadableStream>, TeeBranch),
+    /// The readable side of the given transform stream.
+    Transform(Dom<TransformStream>),
+}
+
+/// A rooted snapshot of an `UnderlyingSource`, taken so that no borrow is
+/// held while its algorithms run script.
+enum SourceRoot {
+    Js,
+    Native,
+    Tee(DomRoot<ReadableStream>, TeeBranch),
+    Transform(DomRoot<TransformStream>),
+}
+
+/// The steps a default controller takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum ReadableStreamDefaultControllerStep {
+    Started,
+    Pulled,
+}
+
+// https://streams.spec.whatwg.org/#rs-default-controller-class
+#[dom_struct]
+pub struct ReadableStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<ReadableStream>,
+    /// `None` once the algorithms of the controller are cleared.
+    underlying_source: DomRefCell<Option<UnderlyingSource>>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    strategy_size: DomRefCell<Option<Rc<QueuingStrategySize>>>,
+}
+
+impl ReadableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        stream: &ReadableStream,
+        underlying_source: UnderlyingSource,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> ReadableStreamDefaultController {
+        ReadableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            underlying_source: DomRefCell::new(Some(underlying_source)),
+            queue: DomRefCell::new(QueueWithSizes::new()),
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm,
+            strategy_size: DomRefCell::new(strategy_size),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+        underlying_source: UnderlyingSource,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> DomRoot<ReadableStreamDefaultController> {
+        reflect_dom_object(
+            Box::new(ReadableStreamDefaultController::new_inherited(
+                stream,
+                underlying_source,
+                strategy_hwm,
+                strategy_size,
+            )),
+            global,
+            ReadableStreamDefaultControllerBinding::Wrap,
+        )
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-controller>
+    #[allow(unrooted_must_root)]
+    pub fn set_up(
+        stream: &ReadableStream,
+        underlying_source: UnderlyingSource,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let global = stream.global();
+        let cx = global.get_cx();
+        // Steps 2-9.
+        let controller = ReadableStreamDefaultController::new(
+            &global,
+            stream,
+            underlying_source,
+            strategy_hwm,
+            strategy_size,
+        );
+        stream.set_controller(ReadableStreamController::Default(Dom::from_ref(
+            &*controller,
+        )));
+        // Steps 10-11.
+        rooted!(in(*cx) let mut start_result = UndefinedValue());
+        controller.start_algorithm(start_result.handle_mut())?;
+        // Steps 12-13.
+        let start_promise = promise_resolved_with(&global, start_result.handle());
+        upon_settlement(
+            &start_promise,
+            &*controller,
+            ReadableStreamDefaultControllerStep::Started,
+        );
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-controller-from-underlying-source>
+    pub fn set_up_from_underlying_source(
+        stream: &ReadableStream,
+        underlying_source: HandleObject,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let cx = stream.global().get_cx();
+        check_underlying_methods(cx, underlying_source, &["start", "pull", "cancel"])?;
+        ReadableStreamDefaultController::set_up(
+            stream,
+            UnderlyingSource::Js(Heap::boxed(underlying_source.get())),
+            strategy_hwm,
+            strategy_size,
+        )
+    }
+
+    fn source(&self, mut object: MutableHandleObject) -> SourceRoot {
+        match *self.underlying_source.borrow() {
+            Some(UnderlyingSource::Js(ref source)) => {
+                object.set(source.get());
+                SourceRoot::Js
+            },
+            Some(UnderlyingSource::Tee(ref stream, branch)) => {
+                SourceRoot::Tee(DomRoot::from_ref(&**stream), branch)
+            },
+            Some(UnderlyingSource::Transform(ref stream)) => {
+                SourceRoot::Transform(DomRoot::from_ref(&**stream))
+            },
+            Some(UnderlyingSource::Native) | None => SourceRoot::Native,
+        }
+    }
+
+    fn start_algorithm(&self, mut rval: MutableHandleValue) -> Fallible<()> {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.source(object.handle_mut()) {
+            SourceRoot::Js => {
+                rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+                call_underlying_method(cx, object.handle(), "start", &[this.get()], rval)
+            },
+            SourceRoot::Transform(stream) => {
+                let start_promise = stream.start_promise();
+                rval.set(ObjectValue(start_promise.reflector().get_jsobject().get()));
+                Ok(())
+            },
+            SourceRoot::Native | SourceRoot::Tee(..) => {
+                rval.set(UndefinedValue());
+                Ok(())
+            },
+        }
+    }
+
+    fn pull_algorithm(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.source(object.handle_mut()) {
+            SourceRoot::Js => {
+                rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+                promise_call(&global, object.handle(), "pull", &[this.get()])
+            },
+            SourceRoot::Tee(stream, _) => stream.tee_pull(),
+            SourceRoot::Transform(stream) => stream.source_pull(),
+            SourceRoot::Native => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+        }
+    }
+
+    fn cancel_algorithm(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.source(object.handle_mut()) {
+            SourceRoot::Js => promise_call(&global, object.handle(), "cancel", &[reason.get()]),
+            SourceRoot::Tee(stream, branch) => stream.tee_cancel(branch, reason),
+            SourceRoot::Transform(stream) => stream.source_cancel(reason),
+            SourceRoot::Native => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.underlying_source.borrow_mut() = None;
+        *self.strategy_size.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-call-pull-if-needed>
+    fn call_pull_if_needed(&self) {
+        // Steps 1-2.
+        if !self.should_call_pull() {
+            return;
+        }
+        // Step 3.
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+        // Steps 4-8.
+        self.pulling.set(true);
+        let pull_promise = self.pull_algorithm();
+        upon_settlement(
+            &pull_promise,
+            self,
+            ReadableStreamDefaultControllerStep::Pulled,
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        if !self.can_close_or_enqueue() || !self.started.get() {
+            return false;
+        }
+        if self.stream.is_locked() && self.stream.num_read_requests() > 0 {
+            return true;
+        }
+        self.desired_size().map_or(false, |size| size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-has-backpressure>
+    pub fn has_backpressure(&self) -> bool {
+        !self.should_call_pull()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-can-close-or-enqueue>
+    pub fn can_close_or_enqueue(&self) -> bool {
+        !self.close_requested.get() && self.stream.is_readable()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-get-desired-size>
+    pub fn desired_size(&self) -> Option<f64> {
+        if self.stream.is_errored() {
+            return None;
+        }
+        if self.stream.is_closed() {
+            return Some(0.);
+        }
+        Some(self.strategy_hwm - self.queue.borrow().total_size())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-close>
+    pub fn close(&self) {
+        // Steps 2-3.
+        self.close_requested.set(true);
+        // Step 4.
+        if self.queue.borrow().is_empty() {
+            self.clear_algorithms();
+            self.stream.close();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-enqueue>
+    pub fn enqueue(&self, chunk: HandleValue) -> Fallible<()> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Step 4.
+        if self.stream.is_locked() && self.stream.num_read_requests() > 0 {
+            self.stream.fulfill_read_request(chunk, false);
+        } else {
+            // Step 5.
+            let size = self.strategy_size.borrow().clone();
+            let result = chunk_size(size.as_ref().map(|size| &**size), chunk)
+                .and_then(|size| self.queue.borrow_mut().enqueue_value(chunk, size));
+            if let Err(error) = result {
+                rooted!(in(*cx) let mut error_value = UndefinedValue());
+                error_to_jsval(&global, error, error_value.handle_mut());
+                self.error(error_value.handle());
+                return Err(throw_jsval(cx, error_value.handle()));
+            }
+        }
+        // Step 6.
+        self.call_pull_if_needed();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-error>
+    pub fn error(&self, e: HandleValue) {
+        // Step 2.
+        if !self.stream.is_readable() {
+            return;
+        }
+        // Steps 3-5.
+        self.queue.borrow_mut().reset();
+        self.clear_algorithms();
+        self.stream.error(e);
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-cancel>
+    pub fn cancel_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        self.queue.borrow_mut().reset();
+        let result = self.cancel_algorithm(reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-pull>
+    pub fn pull_steps(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Step 2.
+        if !self.queue.borrow().is_empty() {
+            rooted!(in(*cx) let mut chunk = UndefinedValue());
+            self.queue.borrow_mut().dequeue_value(chunk.handle_mut());
+            if self.close_requested.get() && self.queue.borrow().is_empty() {
+                self.clear_algorithms();
+                self.stream.close();
+            } else {
+                self.call_pull_if_needed();
+            }
+            return read_result_promise(&global, chunk.handle(), false);
+        }
+        // Steps 3-5.
+        let pending_promise = self.stream.add_read_request();
+        self.call_pull_if_needed();
+        pending_promise
+    }
+}
+
+impl PromiseReaction for ReadableStreamDefaultController {
+    type Step = ReadableStreamDefaultControllerStep;
+
+    fn settled(
+        &self,
+        step: &ReadableStreamDefaultControllerStep,
+        result: Result<HandleValue, HandleValue>,
+    ) {
+        match (step, result) {
+            (ReadableStreamDefaultControllerStep::Started, Ok(_)) => {
+                self.started.set(true);
+                self.call_pull_if_needed();
+            },
+            (ReadableStreamDefaultControllerStep::Pulled, Ok(_)) => {
+                self.pulling.set(false);
+                if self.pull_again.get() {
+                    self.pull_again.set(false);
+                    self.call_pull_if_needed();
+                }
+            },
+            (_, Err(e)) => self.error(e),
+        }
+    }
+}
+
+impl ReadableStreamDefaultControllerMethods for ReadableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#rs-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-close
+    fn Close(&self) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be closed".to_owned()));
+        }
+        self.close();
+        Ok(())
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-enqueue
+    fn Enqueue(&self, _cx: JSContext, chunk: HandleValue) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be enqueued to".to_owned()));
+        }
+        self.enqueue(chunk)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-error
+    fn Error(&self, _cx: JSContext, e: HandleValue) {
+        self.error(e);
+    }
+}
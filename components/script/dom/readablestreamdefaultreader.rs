@@ -0,0 +1,238 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding::ReadableStreamDefaultReaderMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamReader};
+use crate::script_runtime::JSContext;
+use crate::streams::{create_read_result, promise_rejected_with, promise_rejected_with_error};
+use crate::streams::{error_to_jsval, promise_resolved_with, read_result_promise};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+
+// https://streams.spec.whatwg.org/#default-reader-class
+#[dom_struct]
+pub struct ReadableStreamDefaultReader {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    read_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamDefaultReader {
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamDefaultReader {
+        ReadableStreamDefaultReader {
+            reflector_: Reflector::new(),
+            stream: MutNullableDom::new(None),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Lock `stream` to a new reader, as in
+    /// <https://streams.spec.whatwg.org/#default-reader-constructor>.
+    pub fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+        let closed_promise = initial_closed_promise(global, stream);
+        let reader = reflect_dom_object(
+            Box::new(ReadableStreamDefaultReader::new_inherited(closed_promise)),
+            global,
+            ReadableStreamDefaultReaderBinding::Wrap,
+        );
+        reader.stream.set(Some(stream));
+        stream.set_reader(Some(ReadableStreamReader::Default(Dom::from_ref(&*reader))));
+        Ok(reader)
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        ReadableStreamDefaultReader::new(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-request>
+    pub fn add_read_request(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        self.read_requests.borrow_mut().push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-request>
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        let promise = match self.read_requests.borrow_mut().pop_front() {
+            Some(promise) => promise,
+            None => return,
+        };
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut result = UndefinedValue());
+        create_read_result(cx, chunk, done, result.handle_mut());
+        promise.resolve_native(&result.get());
+    }
+
+    pub fn num_read_requests(&self) -> usize {
+        self.read_requests.borrow().len()
+    }
+
+    /// Resolve every pending read request as done, when the stream closes.
+    pub fn close_read_requests(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        rooted!(in(*cx) let mut result = UndefinedValue());
+        create_read_result(cx, undefined.handle(), true, result.handle_mut());
+        let requests = mem::replace(&mut *self.read_requests.borrow_mut(), VecDeque::new());
+        for promise in requests {
+            promise.resolve_native(&result.get());
+        }
+    }
+
+    /// Reject every pending read request, when the stream errors.
+    pub fn error_read_requests(&self, e: HandleValue) {
+        let requests = mem::replace(&mut *self.read_requests.borrow_mut(), VecDeque::new());
+        for promise in requests {
+            promise.reject_native(&e.get());
+        }
+    }
+
+    pub fn resolve_closed(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed(&self, e: HandleValue) {
+        self.closed_promise.borrow().reject_native(&e.get());
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-reader-read>
+    pub fn read(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The reader was released".to_owned()),
+                );
+            },
+        };
+        // Step 3.
+        stream.set_disturbed();
+        // Step 4.
+        if stream.is_closed() {
+            rooted!(in(*cx) let undefined = UndefinedValue());
+            return read_result_promise(&global, undefined.handle(), true);
+        }
+        // Step 5.
+        if stream.is_errored() {
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            stream.stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+        // Step 7.
+        stream.pull_steps()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        release_closed_promise(&self.global(), &stream, &self.closed_promise);
+        stream.set_reader(None);
+        self.stream.set(None);
+    }
+}
+
+/// The closed promise of a new reader of `stream`, as in
+/// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-initialize>.
+pub fn initial_closed_promise(global: &GlobalScope, stream: &ReadableStream) -> Rc<Promise> {
+    let cx = global.get_cx();
+    if stream.is_readable() {
+        return Promise::new(global);
+    }
+    if stream.is_closed() {
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        return promise_resolved_with(global, undefined.handle());
+    }
+    rooted!(in(*cx) let mut error = UndefinedValue());
+    stream.stored_error(error.handle_mut());
+    promise_rejected_with(global, error.handle())
+}
+
+/// Reject the closed promise of a reader that releases `stream`, as in
+/// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>.
+pub fn release_closed_promise(
+    global: &GlobalScope,
+    stream: &ReadableStream,
+    closed_promise: &DomRefCell<Rc<Promise>>,
+) {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut error = UndefinedValue());
+    error_to_jsval(
+        global,
+        Error::Type("The reader was released".to_owned()),
+        error.handle_mut(),
+    );
+    if stream.is_readable() {
+        closed_promise.borrow().reject_native(&error.get());
+    } else {
+        *closed_promise.borrow_mut() = promise_rejected_with(global, error.handle());
+    }
+}
+
+impl ReadableStreamDefaultReaderMethods for ReadableStreamDefaultReader {
+    // https://streams.spec.whatwg.org/#default-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-cancel
+    fn Cancel(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(reason),
+            None => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The reader was released".to_owned()),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-read
+    fn Read(&self) -> Rc<Promise> {
+        self.read()
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-release-lock
+    fn ReleaseLock(&self) -> ErrorResult {
+        if self.stream.get().is_none() {
+            return Ok(());
+        }
+        if !self.read_requests.borrow().is_empty() {
+            return Err(Error::Type(
+                "Cannot release a reader with pending reads".to_owned(),
+            ));
+        }
+        self.release();
+        Ok(())
+    }
+}
@@ -2,7 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::body::{consume_body, BodyOperations, BodyType};
+use crate::body::{consume_body, consume_stream_bytes, BodyOperations, BodyType, ConsumeBodyStep};
+use crate::dom::abortsignal::AbortSignal;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use crate::dom::bindings::codegen::Bindings::RequestBinding;
@@ -23,10 +24,13 @@ use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::{Guard, Headers};
 use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
 use crate::dom::xmlhttprequest::Extractable;
+use crate::streams::PromiseReaction;
 use dom_struct::dom_struct;
 use http::method::InvalidMethod;
 use http::Method as HttpMethod;
+use js::rust::HandleValue;
 use net_traits::request::CacheMode as NetTraitsRequestCache;
 use net_traits::request::CredentialsMode as NetTraitsRequestCredentials;
 use net_traits::request::Destination as NetTraitsRequestDestination;
@@ -48,6 +52,9 @@ pub struct Request {
     body_used: Cell<bool>,
     headers: MutNullableDom<Headers>,
     mime_type: DomRefCell<Vec<u8>>,
+    signal: MutNullableDom<AbortSignal>,
+    /// The stream of the body, created once script asks for it.
+    stream: MutNullableDom<ReadableStream>,
     #[ignore_malloc_size_of = "Rc"]
     body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
 }
@@ -60,6 +67,8 @@ impl Request {
             body_used: Cell::new(false),
             headers: Default::default(),
             mime_type: DomRefCell::new("".to_string().into_bytes()),
+            signal: MutNullableDom::new(None),
+            stream: MutNullableDom::new(None),
             body_promise: DomRefCell::new(None),
         }
     }
@@ -90,6 +99,8 @@ impl Request {
         // Step 4
         let base_url = global.api_base_url();
 
+        let mut signal = None;
+
         match input {
             // Step 5
             RequestInfo::USVString(USVString(ref usv_string)) => {
@@ -119,6 +130,7 @@ impl Request {
                 }
                 // Step 6.2
                 temporary_request = input_request.request.borrow().clone();
+                signal = Some(input_request.signal());
             },
         }
 
@@ -170,6 +182,7 @@ impl Request {
             init.redirect.is_some() ||
             init.referrer.is_some() ||
             init.referrerPolicy.is_some() ||
+            init.signal.is_some() ||
             !init.window.handle().is_undefined()
         {
             // Step 13.1
@@ -293,9 +306,17 @@ impl Request {
             request.method = method;
         }
 
+        // The signal of init, when present, replaces the one of the input.
+        if let Some(ref init_signal) = init.signal {
+            signal = init_signal.clone();
+        }
+
         // Step 26
         let r = Request::from_net_request(global, request);
         r.headers.or_init(|| Headers::for_request(&r.global()));
+        if let Some(signal) = signal {
+            r.signal().follow(&signal);
+        }
 
         // Step 27
         let mut headers_copy = r.Headers();
@@ -429,9 +450,7 @@ impl Request {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
     }
 }
 
@@ -461,9 +480,14 @@ impl Request {
             .Headers()
             .fill(Some(HeadersInit::Headers(r.Headers())))?;
         r_clone.Headers().set_guard(headers_guard);
+        r_clone.signal().follow(&r.signal());
         Ok(r_clone)
     }
 
+    pub fn signal(&self) -> DomRoot<AbortSignal> {
+        self.signal.or_init(|| AbortSignal::new(&self.global()))
+    }
+
     pub fn get_request(&self) -> NetTraitsRequest {
         self.request.borrow().clone()
     }
@@ -515,16 +539,18 @@ fn includes_credentials(input: &ServoUrl) -> bool {
     !input.username().is_empty() || input.password().is_some()
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-disturbed
-fn request_is_disturbed(_input: &Request) -> bool {
-    false
+fn request_is_disturbed(input: &Request) -> bool {
+    input.body_used.get() ||
+        input
+            .stream
+            .get()
+            .map_or(false, |stream| stream.is_disturbed())
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-locked
-fn request_is_locked(_input: &Request) -> bool {
-    false
+fn request_is_locked(input: &Request) -> bool {
+    input.locked()
 }
 
 impl RequestMethods for Request {
@@ -601,9 +627,36 @@ impl RequestMethods for Request {
         DOMString::from_string(r.integrity_metadata.clone())
     }
 
+    // https://fetch.spec.whatwg.org/#dom-request-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        self.signal()
+    }
+
+    // https://fetch.spec.whatwg.org/#dom-body-body
+    fn GetBody(&self) -> Option<DomRoot<ReadableStream>> {
+        if let Some(stream) = self.stream.get() {
+            return Some(stream);
+        }
+        // The bytes stay in the request too, as they are what gets sent if
+        // the request is fetched.
+        let bytes = match self.request.borrow().body {
+            Some(ref bytes) => bytes.clone(),
+            None => return None,
+        };
+        let stream = ReadableStream::new_with_native_source(&self.global());
+        if self.body_used.get() {
+            stream.set_disturbed();
+        } else if !bytes.is_empty() {
+            stream.enqueue_native(&bytes);
+        }
+        stream.close_native();
+        self.stream.set(Some(&stream));
+        Some(stream)
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        request_is_disturbed(self)
     }
 
     // https://fetch.spec.whatwg.org/#dom-request-clone
@@ -667,11 +720,23 @@ impl BodyOperations for Request {
         Some(body.unwrap_or(vec![]))
     }
 
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
+
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
 }
 
+impl PromiseReaction for Request {
+    type Step = ConsumeBodyStep;
+
+    fn settled(&self, step: &ConsumeBodyStep, result: Result<HandleValue, HandleValue>) {
+        consume_stream_bytes(self, step, result);
+    }
+}
+
 impl Into<NetTraitsRequestCache> for RequestCache {
     fn into(self) -> NetTraitsRequestCache {
         match self {
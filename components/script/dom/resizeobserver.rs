@@ -0,0 +1,296 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverBoxOptions;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverCallback;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverMethods;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverOptions;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::domrectreadonly::DOMRectReadOnly;
+use crate::dom::element::Element;
+use crate::dom::node::{window_from_node, Node, ShadowIncluding};
+use crate::dom::resizeobserverentry::ResizeObserverEntry;
+use crate::dom::resizeobserversize::ResizeObserverSize;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::rc::Rc;
+use style::properties::{LonghandId, PropertyId};
+
+/// The inline and block sizes of a box of an element.
+type BoxSize = (f64, f64);
+
+/// The geometry of the boxes of an element that resize observers report, in
+/// CSS pixels.
+struct BoxGeometry {
+    border_box_size: BoxSize,
+    content_box_size: BoxSize,
+    /// The offset of the content box from the padding box.
+    content_box_offset: (f64, f64),
+    /// The width and height of the content box.
+    content_rect_size: (f64, f64),
+}
+
+impl BoxGeometry {
+    /// The geometry of the boxes of the given element, as computed by the last
+    /// layout. An element without a layout box has empty boxes.
+    fn of(element: &Element) -> BoxGeometry {
+        let node = element.upcast::<Node>();
+        let window = window_from_node(node);
+        let (border_box, style) = match (window.content_box_query(node), element.style()) {
+            (Some(border_box), Some(style)) => (border_box, style),
+            _ => {
+                return BoxGeometry {
+                    border_box_size: (0., 0.),
+                    content_box_size: (0., 0.),
+                    content_box_offset: (0., 0.),
+                    content_rect_size: (0., 0.),
+                };
+            },
+        };
+        let border = style.get_border();
+        let padding = |longhand| {
+            let value = window.resolved_style_query(
+                node.to_trusted_node_address(),
+                None,
+                PropertyId::Longhand(longhand),
+            );
+            value.trim_end_matches("px").parse::<f64>().unwrap_or(0.)
+        };
+        let padding_top = padding(LonghandId::PaddingTop);
+        let padding_right = padding(LonghandId::PaddingRight);
+        let padding_bottom = padding(LonghandId::PaddingBottom);
+        let padding_left = padding(LonghandId::PaddingLeft);
+
+        let width = border_box.size.width.to_f64_px();
+        let height = border_box.size.height.to_f64_px();
+        let content_width = (width -
+            f64::from(border.border_left_width.px()) -
+            f64::from(border.border_right_width.px()) -
+            padding_left -
+            padding_right)
+            .max(0.);
+        let content_height = (height -
+            f64::from(border.border_top_width.px()) -
+            f64::from(border.border_bottom_width.px()) -
+            padding_top -
+            padding_bottom)
+            .max(0.);
+
+        let logical = |width, height| {
+            if style.writing_mode.is_vertical() {
+                (height, width)
+            } else {
+                (width, height)
+            }
+        };
+        BoxGeometry {
+            border_box_size: logical(width, height),
+            content_box_size: logical(content_width, content_height),
+            content_box_offset: (padding_left, padding_top),
+            content_rect_size: (content_width, content_height),
+        }
+    }
+
+    fn size(&self, observed_box: ResizeObserverBoxOptions) -> BoxSize {
+        match observed_box {
+            ResizeObserverBoxOptions::Border_box => self.border_box_size,
+            ResizeObserverBoxOptions::Content_box => self.content_box_size,
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#resizeobservation>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ResizeObservation {
+    target: Dom<Element>,
+    observed_box: ResizeObserverBoxOptions,
+    last_reported_size: Cell<BoxSize>,
+}
+
+impl ResizeObservation {
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobservation-isactive>
+    fn is_active(&self) -> bool {
+        BoxGeometry::of(&self.target).size(self.observed_box) != self.last_reported_size.get()
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#calculate-depth-for-node>
+fn depth(node: &Node) -> usize {
+    node.inclusive_ancestors(ShadowIncluding::Yes).count() - 1
+}
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-interface
+#[dom_struct]
+pub struct ResizeObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<ResizeObserverCallback>,
+    observation_targets: DomRefCell<Vec<ResizeObservation>>,
+    active_targets: DomRefCell<Vec<Dom<Element>>>,
+    /// Whether some observations were skipped because their targets were not
+    /// deeper than the depth the observations were last gathered at.
+    has_skipped_targets: Cell<bool>,
+}
+
+impl ResizeObserver {
+    fn new_inherited(callback: Rc<ResizeObserverCallback>) -> ResizeObserver {
+        ResizeObserver {
+            reflector_: Reflector::new(),
+            callback,
+            observation_targets: DomRefCell::new(vec![]),
+            active_targets: DomRefCell::new(vec![]),
+            has_skipped_targets: Cell::new(false),
+        }
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-resizeobserver
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<ResizeObserverCallback>,
+    ) -> Fallible<DomRoot<ResizeObserver>> {
+        Ok(reflect_dom_object(
+            Box::new(ResizeObserver::new_inherited(callback)),
+            window,
+            ResizeObserverBinding::Wrap,
+        ))
+    }
+
+    fn window(&self) -> DomRoot<Window> {
+        DomRoot::from_ref(self.global().as_window())
+    }
+
+    /// The part of <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    /// that concerns this observer.
+    pub fn gather_active_observations_at_depth(&self, depth_limit: usize) {
+        // Steps 2.1-2.2.
+        let mut active_targets = self.active_targets.borrow_mut();
+        active_targets.clear();
+        self.has_skipped_targets.set(false);
+        // Step 2.3.
+        for observation in self.observation_targets.borrow().iter() {
+            if !observation.is_active() {
+                continue;
+            }
+            if depth(observation.target.upcast()) > depth_limit {
+                active_targets.push(Dom::from_ref(&*observation.target));
+            } else {
+                self.has_skipped_targets.set(true);
+            }
+        }
+    }
+
+    pub fn has_active_observations(&self) -> bool {
+        !self.active_targets.borrow().is_empty()
+    }
+
+    pub fn has_skipped_observations(&self) -> bool {
+        self.has_skipped_targets.get()
+    }
+
+    /// The part of <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    /// that concerns this observer. Returns the depth of the shallowest target an
+    /// entry was delivered for.
+    pub fn broadcast_active_observations(&self) -> usize {
+        let mut shallowest_target_depth = usize::max_value();
+        // Step 2.1.
+        let targets: Vec<DomRoot<Element>> = self
+            .active_targets
+            .borrow_mut()
+            .drain(..)
+            .map(|target| DomRoot::from_ref(&*target))
+            .collect();
+        if targets.is_empty() {
+            return shallowest_target_depth;
+        }
+        // Steps 2.2-2.3.
+        let window = self.window();
+        let mut entries = vec![];
+        for target in targets {
+            let geometry = BoxGeometry::of(&target);
+            // Steps 2.3.1-2.3.3.
+            let content_rect = DOMRectReadOnly::new(
+                window.upcast(),
+                geometry.content_box_offset.0,
+                geometry.content_box_offset.1,
+                geometry.content_rect_size.0,
+                geometry.content_rect_size.1,
+            );
+            let (inline_size, block_size) = geometry.border_box_size;
+            let border_box_size = ResizeObserverSize::new(&window, inline_size, block_size);
+            let (inline_size, block_size) = geometry.content_box_size;
+            let content_box_size = ResizeObserverSize::new(&window, inline_size, block_size);
+            entries.push(ResizeObserverEntry::new(
+                &window,
+                &target,
+                &content_rect,
+                &border_box_size,
+                &content_box_size,
+            ));
+            // Step 2.3.4.
+            if let Some(observation) = self
+                .observation_targets
+                .borrow()
+                .iter()
+                .find(|observation| *observation.target == *target)
+            {
+                observation
+                    .last_reported_size
+                    .set(geometry.size(observation.observed_box));
+            }
+            // Steps 2.3.5-2.3.6.
+            shallowest_target_depth = shallowest_target_depth.min(depth(target.upcast()));
+        }
+        // Step 2.4.
+        let _ = self
+            .callback
+            .Call_(self, entries, self, ExceptionHandling::Report);
+        shallowest_target_depth
+    }
+}
+
+impl ResizeObserverMethods for ResizeObserver {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observe
+    fn Observe(&self, target: &Element, options: &ResizeObserverOptions) {
+        // Step 1.
+        self.Unobserve(target);
+        // Steps 2-3.
+        self.observation_targets
+            .borrow_mut()
+            .push(ResizeObservation {
+                target: Dom::from_ref(target),
+                observed_box: options.box_,
+                last_reported_size: Cell::new((0., 0.)),
+            });
+        self.window().Document().add_resize_observer(self);
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets
+            .borrow_mut()
+            .retain(|observation| &*observation.target != target);
+        self.active_targets
+            .borrow_mut()
+            .retain(|active_target| &**active_target != target);
+        if self.observation_targets.borrow().is_empty() {
+            self.window().Document().remove_resize_observer(self);
+        }
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-disconnect
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+        self.active_targets.borrow_mut().clear();
+        self.window().Document().remove_resize_observer(self);
+    }
+}
@@ -0,0 +1,87 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ResizeObserverEntryBinding;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverEntryBinding::ResizeObserverEntryMethods;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::domrectreadonly::DOMRectReadOnly;
+use crate::dom::element::Element;
+use crate::dom::resizeobserversize::ResizeObserverSize;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::Heap;
+use js::jsval::{JSVal, UndefinedValue};
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-entry-interface
+#[dom_struct]
+pub struct ResizeObserverEntry {
+    reflector_: Reflector,
+    target: Dom<Element>,
+    content_rect: Dom<DOMRectReadOnly>,
+    #[ignore_malloc_size_of = "mozjs"]
+    border_box_size: Heap<JSVal>,
+    #[ignore_malloc_size_of = "mozjs"]
+    content_box_size: Heap<JSVal>,
+}
+
+impl ResizeObserverEntry {
+    fn new_inherited(target: &Element, content_rect: &DOMRectReadOnly) -> ResizeObserverEntry {
+        ResizeObserverEntry {
+            reflector_: Reflector::new(),
+            target: Dom::from_ref(target),
+            content_rect: Dom::from_ref(content_rect),
+            border_box_size: Heap::default(),
+            content_box_size: Heap::default(),
+        }
+    }
+
+    #[allow(unsafe_code)]
+    pub fn new(
+        window: &Window,
+        target: &Element,
+        content_rect: &DOMRectReadOnly,
+        border_box_size: &ResizeObserverSize,
+        content_box_size: &ResizeObserverSize,
+    ) -> DomRoot<ResizeObserverEntry> {
+        let entry = reflect_dom_object(
+            Box::new(ResizeObserverEntry::new_inherited(target, content_rect)),
+            window,
+            ResizeObserverEntryBinding::Wrap,
+        );
+        let cx = window.get_cx();
+        unsafe {
+            rooted!(in(*cx) let mut jsval = UndefinedValue());
+            vec![DomRoot::from_ref(border_box_size)].to_jsval(*cx, jsval.handle_mut());
+            entry.border_box_size.set(jsval.get());
+            vec![DomRoot::from_ref(content_box_size)].to_jsval(*cx, jsval.handle_mut());
+            entry.content_box_size.set(jsval.get());
+        }
+        entry
+    }
+}
+
+impl ResizeObserverEntryMethods for ResizeObserverEntry {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentrect
+    fn ContentRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.content_rect)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-borderboxsize
+    fn BorderBoxSize(&self, _cx: JSContext) -> JSVal {
+        self.border_box_size.get()
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentboxsize
+    fn ContentBoxSize(&self, _cx: JSContext) -> JSVal {
+        self.content_box_size.get()
+    }
+}
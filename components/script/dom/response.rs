@@ -2,7 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::body::{consume_body, consume_body_with_promise, BodyOperations, BodyType};
+use crate::body::{consume_body, consume_body_with_promise, consume_stream_bytes};
+use crate::body::{BodyOperations, BodyType, ConsumeBodyStep};
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use crate::dom::bindings::codegen::Bindings::ResponseBinding;
@@ -18,11 +19,14 @@ use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::{is_obs_text, is_vchar};
 use crate::dom::headers::{Guard, Headers};
 use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
 use crate::dom::xmlhttprequest::Extractable;
+use crate::streams::PromiseReaction;
 use dom_struct::dom_struct;
 use http::header::HeaderMap as HyperHeaders;
 use hyper::StatusCode;
 use hyper_serde::Serde;
+use js::rust::HandleValue;
 use net_traits::response::ResponseBody as NetTraitsResponseBody;
 use servo_url::ServoUrl;
 use std::cell::{Cell, Ref};
@@ -46,6 +50,12 @@ pub struct Response {
     url_list: DomRefCell<Vec<ServoUrl>>,
     // For now use the existing NetTraitsResponseBody enum
     body: DomRefCell<NetTraitsResponseBody>,
+    /// The stream of the body exposed to script, created once script asks for
+    /// it. It is a branch of `native_stream` once the response is cloned.
+    stream: MutNullableDom<ReadableStream>,
+    /// The stream the body bytes arriving from the network are handed to,
+    /// instead of being kept in `body`, once script asked for the stream.
+    native_stream: MutNullableDom<ReadableStream>,
     #[ignore_malloc_size_of = "Rc"]
     body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
 }
@@ -63,6 +73,8 @@ impl Response {
             url: DomRefCell::new(None),
             url_list: DomRefCell::new(vec![]),
             body: DomRefCell::new(NetTraitsResponseBody::Empty),
+            stream: MutNullableDom::new(None),
+            native_stream: MutNullableDom::new(None),
             body_promise: DomRefCell::new(None),
         }
     }
@@ -210,9 +222,15 @@ impl Response {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
+    }
+
+    // https://fetch.spec.whatwg.org/#concept-body-disturbed
+    fn disturbed(&self) -> bool {
+        self.body_used.get() ||
+            self.stream
+                .get()
+                .map_or(false, |stream| stream.is_disturbed())
     }
 }
 
@@ -242,11 +260,23 @@ impl BodyOperations for Response {
         }
     }
 
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
+
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
 }
 
+impl PromiseReaction for Response {
+    type Step = ConsumeBodyStep;
+
+    fn settled(&self, step: &ConsumeBodyStep, result: Result<HandleValue, HandleValue>) {
+        consume_stream_bytes(self, step, result);
+    }
+}
+
 // https://fetch.spec.whatwg.org/#redirect-status
 fn is_redirect_status(status: u16) -> bool {
     status == 301 || status == 302 || status == 303 || status == 307 || status == 308
@@ -326,7 +356,7 @@ impl ResponseMethods for Response {
     // https://fetch.spec.whatwg.org/#dom-response-clone
     fn Clone(&self) -> Fallible<DomRoot<Response>> {
         // Step 1
-        if self.is_locked() || self.body_used.get() {
+        if self.is_locked() || self.disturbed() {
             return Err(Error::Type("cannot clone a disturbed response".to_string()));
         }
 
@@ -346,20 +376,65 @@ impl ResponseMethods for Response {
         *new_response.url.borrow_mut() = self.url.borrow().clone();
         *new_response.url_list.borrow_mut() = self.url_list.borrow().clone();
 
-        if *self.body.borrow() != NetTraitsResponseBody::Empty {
+        // A body that is still arriving can only be shared through its stream.
+        let receiving = match *self.body.borrow() {
+            NetTraitsResponseBody::Receiving(_) => true,
+            _ => false,
+        };
+        if receiving {
+            self.GetBody();
+        }
+        if let Some(stream) = self.stream.get() {
+            let (first, second) = stream.tee()?;
+            self.stream.set(Some(&first));
+            new_response.stream.set(Some(&second));
+        } else if *self.body.borrow() != NetTraitsResponseBody::Empty {
             *new_response.body.borrow_mut() = self.body.borrow().clone();
         }
 
         // Step 3
-        // TODO: This step relies on promises, which are still unimplemented.
+        // TODO: `entry settings object` is not implemented in Servo yet.
 
         // Step 4
         Ok(new_response)
     }
 
+    // https://fetch.spec.whatwg.org/#dom-body-body
+    fn GetBody(&self) -> Option<DomRoot<ReadableStream>> {
+        if let Some(stream) = self.stream.get() {
+            return Some(stream);
+        }
+        let global = self.global();
+        let stream = if self.body_used.get() {
+            // The body was consumed before script asked for its stream.
+            let stream = ReadableStream::new_with_native_source(&global);
+            stream.close_native();
+            stream.set_disturbed();
+            stream
+        } else {
+            let body = mem::replace(&mut *self.body.borrow_mut(), NetTraitsResponseBody::Empty);
+            let (bytes, done) = match body {
+                NetTraitsResponseBody::Empty => return None,
+                NetTraitsResponseBody::Receiving(bytes) => (bytes, false),
+                NetTraitsResponseBody::Done(bytes) => (bytes, true),
+            };
+            let stream = ReadableStream::new_with_native_source(&global);
+            if !bytes.is_empty() {
+                stream.enqueue_native(&bytes);
+            }
+            if done {
+                stream.close_native();
+            }
+            stream
+        };
+        self.native_stream.set(Some(&stream));
+        self.stream.set(Some(&stream));
+        Some(stream)
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        self.disturbed()
     }
 
     // https://fetch.spec.whatwg.org/#dom-body-text
@@ -412,9 +487,63 @@ impl Response {
         *self.url.borrow_mut() = Some(final_url);
     }
 
+    /// Mark the body as arriving from the network, so that it is not null
+    /// before its first chunk.
+    pub fn start_receiving_body(&self) {
+        *self.body.borrow_mut() = NetTraitsResponseBody::Receiving(vec![]);
+    }
+
+    /// Hand a chunk of the body to the stream if script is reading it, or
+    /// keep it until the whole body is consumed otherwise.
+    pub fn stream_chunk(&self, chunk: Vec<u8>) {
+        if let Some(stream) = self.native_stream.get() {
+            if stream.is_readable() {
+                return stream.enqueue_native(&chunk);
+            }
+        }
+        let mut body = self.body.borrow_mut();
+        match *body {
+            NetTraitsResponseBody::Receiving(ref mut bytes) => bytes.extend_from_slice(&chunk),
+            _ => *body = NetTraitsResponseBody::Receiving(chunk),
+        }
+    }
+
+    /// Error the body of a response whose fetch was aborted.
+    pub fn abort(&self) {
+        self.error_body(Error::Abort);
+    }
+
+    /// Error the body of a response whose fetch failed while the body was
+    /// arriving.
+    pub fn fail(&self) {
+        self.error_body(Error::Type(
+            "Network error while receiving the body".to_owned(),
+        ));
+    }
+
+    fn error_body(&self, error: Error) {
+        if let Some(stream) = self.native_stream.get() {
+            if stream.is_readable() {
+                stream.error_native(error.clone());
+            }
+        }
+        if let Some((p, _)) = self.body_promise.borrow_mut().take() {
+            p.reject_error(error);
+        }
+    }
+
     #[allow(unrooted_must_root)]
-    pub fn finish(&self, body: Vec<u8>) {
-        *self.body.borrow_mut() = NetTraitsResponseBody::Done(body);
+    pub fn finish(&self) {
+        if let Some(stream) = self.native_stream.get() {
+            stream.close_native();
+        }
+        let body = mem::replace(&mut *self.body.borrow_mut(), NetTraitsResponseBody::Empty);
+        *self.body.borrow_mut() = match body {
+            NetTraitsResponseBody::Receiving(bytes) | NetTraitsResponseBody::Done(bytes) => {
+                NetTraitsResponseBody::Done(bytes)
+            },
+            NetTraitsResponseBody::Empty => NetTraitsResponseBody::Done(vec![]),
+        };
         if let Some((p, body_type)) = self.body_promise.borrow_mut().take() {
             consume_body_with_promise(self, body_type, &p);
         }
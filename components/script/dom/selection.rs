@@ -0,0 +1,608 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use crate::dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::characterdata::CharacterData;
+use crate::dom::document::Document;
+use crate::dom::event::{EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::node::{window_from_node, Node, ShadowIncluding};
+use crate::dom::range::Range;
+use crate::dom::text::Text;
+use dom_struct::dom_struct;
+use euclid::default::Point2D;
+use script_layout_interface::rpc::TextIndexResponse;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// <https://w3c.github.io/selection-api/#dfn-direction>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum Direction {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+// https://w3c.github.io/selection-api/#selection-interface
+#[dom_struct]
+pub struct Selection {
+    reflector_: Reflector,
+    document: Dom<Document>,
+    range: MutNullableDom<Range>,
+    direction: Cell<Direction>,
+    /// The boundary points of the range the last time its changes were reported.
+    /// The range can change without the selection knowing, when the nodes around
+    /// its boundary points are mutated.
+    reported_boundaries: DomRefCell<Option<(Dom<Node>, u32, Dom<Node>, u32)>>,
+    /// The text nodes that are currently painted as selected.
+    highlighted_nodes: DomRefCell<Vec<Dom<Text>>>,
+    /// Whether the user is selecting with the mouse.
+    dragging: Cell<bool>,
+}
+
+impl Selection {
+    fn new_inherited(document: &Document) -> Selection {
+        Selection {
+            reflector_: Reflector::new(),
+            document: Dom::from_ref(document),
+            range: MutNullableDom::new(None),
+            direction: Cell::new(Direction::Directionless),
+            reported_boundaries: DomRefCell::new(None),
+            highlighted_nodes: DomRefCell::new(vec![]),
+            dragging: Cell::new(false),
+        }
+    }
+
+    pub fn new(document: &Document) -> DomRoot<Selection> {
+        reflect_dom_object(
+            Box::new(Selection::new_inherited(document)),
+            document.window(),
+            SelectionBinding::Wrap,
+        )
+    }
+
+    /// Whether the root of the node is the document of this selection.
+    fn is_in_document(&self, node: &Node) -> bool {
+        node.inclusive_ancestors(ShadowIncluding::No)
+            .last()
+            .map_or(false, |root| &*root == self.document.upcast::<Node>())
+    }
+
+    fn set_range(&self, range: Option<&Range>, direction: Direction) {
+        self.range.set(range);
+        self.direction.set(direction);
+    }
+
+    /// Set the range of this selection to a collapsed range at the given
+    /// boundary point.
+    fn collapse_to(&self, node: &Node, offset: u32) {
+        let range = Range::new(&self.document, node, offset, node, offset);
+        self.set_range(Some(&range), Direction::Directionless);
+    }
+
+    /// Set the range of this selection to the range between the given anchor and
+    /// focus, as in steps 7-11 of
+    /// <https://w3c.github.io/selection-api/#dom-selection-setbaseandextent>.
+    fn set_anchor_and_focus(
+        &self,
+        anchor_node: &Node,
+        anchor_offset: u32,
+        focus_node: &Node,
+        focus_offset: u32,
+    ) {
+        let anchor = Range::new(
+            &self.document,
+            anchor_node,
+            anchor_offset,
+            anchor_node,
+            anchor_offset,
+        );
+        let (range, direction) = match anchor.ComparePoint(focus_node, focus_offset) {
+            // The anchor and the focus are in different trees.
+            Err(_) => return self.collapse_to(focus_node, focus_offset),
+            Ok(-1) => (
+                Range::new(
+                    &self.document,
+                    focus_node,
+                    focus_offset,
+                    anchor_node,
+                    anchor_offset,
+                ),
+                Direction::Backwards,
+            ),
+            Ok(_) => (
+                Range::new(
+                    &self.document,
+                    anchor_node,
+                    anchor_offset,
+                    focus_node,
+                    focus_offset,
+                ),
+                Direction::Forwards,
+            ),
+        };
+        self.set_range(Some(&range), direction);
+    }
+
+    /// Report the changes of the range of this selection since the last time
+    /// they were reported, whether they were made through this interface or by
+    /// mutating the nodes around the range: repaint the selected text, and queue
+    /// a `selectionchange` event.
+    #[allow(unrooted_must_root)]
+    pub fn update(&self) {
+        let boundaries = self.range.get().map(|range| {
+            (
+                Dom::from_ref(&*range.StartContainer()),
+                range.StartOffset(),
+                Dom::from_ref(&*range.EndContainer()),
+                range.EndOffset(),
+            )
+        });
+        if *self.reported_boundaries.borrow() == boundaries {
+            return;
+        }
+        *self.reported_boundaries.borrow_mut() = boundaries;
+        self.update_highlight();
+
+        // https://w3c.github.io/selection-api/#selectionchange-event
+        let window = self.document.window();
+        window
+            .task_manager()
+            .user_interaction_task_source()
+            .queue_event(
+                self.document.upcast(),
+                atom!("selectionchange"),
+                EventBubbles::DoesNotBubble,
+                EventCancelable::NotCancelable,
+                window,
+            );
+    }
+
+    /// Mark the text in the range of this selection as selected for layout, and
+    /// the text that was selected before as not selected any more.
+    fn update_highlight(&self) {
+        let selected = match self.range.get() {
+            Some(ref range) if !range.Collapsed() => selected_text(range),
+            _ => vec![],
+        };
+        let previous: Vec<DomRoot<Text>> = self
+            .highlighted_nodes
+            .borrow_mut()
+            .drain(..)
+            .map(|text| DomRoot::from_ref(&*text))
+            .collect();
+        for text in previous {
+            if !selected
+                .iter()
+                .any(|&(ref selected, _, _)| *selected == text)
+            {
+                text.set_selection(None);
+            }
+        }
+        let mut highlighted_nodes = self.highlighted_nodes.borrow_mut();
+        for (text, start, end) in selected {
+            let byte_range = {
+                let data = text.upcast::<CharacterData>().data();
+                byte_offset(&data, start)..byte_offset(&data, end)
+            };
+            text.set_selection(Some(byte_range));
+            highlighted_nodes.push(Dom::from_ref(&*text));
+        }
+    }
+
+    /// Start selecting with the mouse at the given point of the given node, as
+    /// the default action of a `mousedown` event.
+    pub fn handle_mouse_down(
+        &self,
+        node: &Node,
+        client_point: Point2D<f32>,
+        point_in_node: Option<Point2D<f32>>,
+    ) {
+        // https://w3c.github.io/selection-api/#selectstart-event
+        let event = node
+            .upcast::<EventTarget>()
+            .fire_bubbling_cancelable_event(atom!("selectstart"));
+        if event.DefaultPrevented() {
+            return;
+        }
+        let (node, offset) = caret_position(node, client_point, point_in_node);
+        if self.is_in_document(&node) {
+            self.collapse_to(&node, offset);
+            self.dragging.set(true);
+        }
+    }
+
+    /// Extend the selection the user is making with the mouse, if any, to the
+    /// given point of the given node.
+    pub fn handle_mouse_move(&self, node: &Node, client_point: Point2D<f32>) {
+        if !self.dragging.get() {
+            return;
+        }
+        let (node, offset) = caret_position(node, client_point, None);
+        if let Some(range) = self.range.get() {
+            if self.is_in_document(&node) {
+                let (anchor_node, anchor_offset) = match self.direction.get() {
+                    Direction::Backwards => (range.EndContainer(), range.EndOffset()),
+                    _ => (range.StartContainer(), range.StartOffset()),
+                };
+                self.set_anchor_and_focus(&anchor_node, anchor_offset, &node, offset);
+            }
+        }
+    }
+
+    pub fn handle_mouse_up(&self) {
+        self.dragging.set(false);
+    }
+
+    /// The text this selection would be serialized to, if it is not collapsed.
+    pub fn text_to_copy(&self) -> Option<String> {
+        match self.range.get() {
+            Some(ref range) if !range.Collapsed() => Some(String::from(range.Stringifier())),
+            _ => None,
+        }
+    }
+}
+
+impl SelectionMethods for Selection {
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    fn GetAnchorNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.EndContainer(),
+            _ => range.StartContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchoroffset
+    fn AnchorOffset(&self) -> u32 {
+        self.range
+            .get()
+            .map_or(0, |range| match self.direction.get() {
+                Direction::Backwards => range.EndOffset(),
+                _ => range.StartOffset(),
+            })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    fn GetFocusNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.StartContainer(),
+            _ => range.EndContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusoffset
+    fn FocusOffset(&self) -> u32 {
+        self.range
+            .get()
+            .map_or(0, |range| match self.direction.get() {
+                Direction::Backwards => range.StartOffset(),
+                _ => range.EndOffset(),
+            })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    fn IsCollapsed(&self) -> bool {
+        self.range.get().map_or(true, |range| range.Collapsed())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-rangecount
+    fn RangeCount(&self) -> u32 {
+        if self.range.get().is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-type
+    fn Type(&self) -> DOMString {
+        DOMString::from(match self.range.get() {
+            None => "None",
+            Some(ref range) if range.Collapsed() => "Caret",
+            Some(_) => "Range",
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    fn GetRangeAt(&self, index: u32) -> Fallible<DomRoot<Range>> {
+        match self.range.get() {
+            Some(range) if index == 0 => Ok(range),
+            _ => Err(Error::IndexSize),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-addrange
+    fn AddRange(&self, range: &Range) {
+        // Step 1.
+        if !self.is_in_document(&range.StartContainer()) {
+            return;
+        }
+        // Step 2.
+        if self.range.get().is_some() {
+            return;
+        }
+        // Step 3.
+        self.set_range(Some(range), Direction::Directionless);
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removerange
+    fn RemoveRange(&self, range: &Range) -> ErrorResult {
+        match self.range.get() {
+            Some(ref current) if &**current == range => {
+                self.set_range(None, Direction::Directionless);
+                Ok(())
+            },
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removeallranges
+    fn RemoveAllRanges(&self) {
+        self.set_range(None, Direction::Directionless);
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-empty
+    fn Empty(&self) {
+        self.RemoveAllRanges();
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapse
+    fn Collapse(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        // Step 1.
+        let node = match node {
+            Some(node) => node,
+            None => {
+                self.RemoveAllRanges();
+                return Ok(());
+            },
+        };
+        // Step 2.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Step 3.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Steps 4-5.
+        self.collapse_to(node, offset);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setposition
+    fn SetPosition(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        self.Collapse(node, offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetostart
+    fn CollapseToStart(&self) -> ErrorResult {
+        // Step 1.
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        // Steps 2-3.
+        self.collapse_to(&range.StartContainer(), range.StartOffset());
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetoend
+    fn CollapseToEnd(&self) -> ErrorResult {
+        // Step 1.
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        // Steps 2-3.
+        self.collapse_to(&range.EndContainer(), range.EndOffset());
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-extend
+    fn Extend(&self, node: &Node, offset: u32) -> ErrorResult {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Step 2.
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Steps 3-11.
+        let (anchor_node, anchor_offset) = match self.direction.get() {
+            Direction::Backwards => (range.EndContainer(), range.EndOffset()),
+            _ => (range.StartContainer(), range.StartOffset()),
+        };
+        self.set_anchor_and_focus(&anchor_node, anchor_offset, node, offset);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setbaseandextent
+    fn SetBaseAndExtent(
+        &self,
+        anchor_node: &Node,
+        anchor_offset: u32,
+        focus_node: &Node,
+        focus_offset: u32,
+    ) -> ErrorResult {
+        // Step 1.
+        if anchor_offset > anchor_node.len() || focus_offset > focus_node.len() {
+            return Err(Error::IndexSize);
+        }
+        if anchor_node.is_doctype() || focus_node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 2.
+        if !self.is_in_document(anchor_node) || !self.is_in_document(focus_node) {
+            return Ok(());
+        }
+        // Steps 3-11.
+        self.set_anchor_and_focus(anchor_node, anchor_offset, focus_node, focus_offset);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-selectallchildren
+    fn SelectAllChildren(&self, node: &Node) -> ErrorResult {
+        // Step 1.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 2.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Steps 3-6.
+        let child_count = node.children_count();
+        let range = Range::new(&self.document, node, 0, node, child_count);
+        self.set_range(Some(&range), Direction::Forwards);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-deletefromdocument
+    fn DeleteFromDocument(&self) -> ErrorResult {
+        match self.range.get() {
+            Some(range) => range.DeleteContents(),
+            None => Ok(()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-containsnode
+    fn ContainsNode(&self, node: &Node, allow_partial_containment: bool) -> bool {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return false;
+        }
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return false,
+        };
+        // Steps 2-3.
+        let start = range.ComparePoint(node, 0);
+        let end = range.ComparePoint(node, node.len());
+        match (start, end) {
+            (Ok(start), Ok(end)) if allow_partial_containment => start != 1 && end != -1,
+            (Ok(start), Ok(end)) => start == 0 && end == 0,
+            _ => false,
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-stringifier
+    fn Stringifier(&self) -> DOMString {
+        self.range
+            .get()
+            .map_or_else(DOMString::new, |range| range.Stringifier())
+    }
+}
+
+/// The text nodes the given range covers, with the range of code units of their
+/// data that it covers.
+fn selected_text(range: &Range) -> Vec<(DomRoot<Text>, u32, u32)> {
+    let start_node = range.StartContainer();
+    let end_node = range.EndContainer();
+    let mut selected = vec![];
+
+    if let Some(text) = start_node.downcast::<Text>() {
+        if start_node == end_node {
+            selected.push((
+                DomRoot::from_ref(text),
+                range.StartOffset(),
+                range.EndOffset(),
+            ));
+            return selected;
+        }
+        selected.push((
+            DomRoot::from_ref(text),
+            range.StartOffset(),
+            start_node.len(),
+        ));
+    }
+
+    let ancestor = range.CommonAncestorContainer();
+    for text in start_node
+        .following_nodes(&ancestor)
+        .filter_map(DomRoot::downcast::<Text>)
+    {
+        if range.contains(text.upcast()) {
+            let length = text.upcast::<Node>().len();
+            selected.push((text, 0, length));
+        }
+    }
+
+    if let Some(text) = end_node.downcast::<Text>() {
+        selected.push((DomRoot::from_ref(text), 0, range.EndOffset()));
+    }
+    selected
+}
+
+/// The byte offset in the given string of the given offset in UTF-16 code units.
+fn byte_offset(data: &str, offset: u32) -> usize {
+    let mut code_units = 0;
+    for (index, character) in data.char_indices() {
+        if code_units >= offset {
+            return index;
+        }
+        code_units += character.len_utf16() as u32;
+    }
+    data.len()
+}
+
+/// The boundary point of the caret at the given point of the given node.
+///
+/// Layout can only tell where the caret is in text; in other nodes, the caret is
+/// before or after the node, depending on which half of it the point is in.
+fn caret_position(
+    node: &Node,
+    client_point: Point2D<f32>,
+    point_in_node: Option<Point2D<f32>>,
+) -> (DomRoot<Node>, u32) {
+    let window = window_from_node(node);
+    let border_box = match window.content_box_query(node) {
+        Some(border_box) => border_box,
+        None => return (DomRoot::from_ref(node), 0),
+    };
+    let point_in_node = point_in_node.unwrap_or_else(|| {
+        let scroll_offset = window.current_viewport().origin;
+        Point2D::new(
+            client_point.x + scroll_offset.x.to_f32_px() - border_box.origin.x.to_f32_px(),
+            client_point.y + scroll_offset.y.to_f32_px() - border_box.origin.y.to_f32_px(),
+        )
+    });
+
+    if let Some(text) = node.downcast::<Text>() {
+        let TextIndexResponse(index) = window.text_index_query(node, point_in_node);
+        let data = text.upcast::<CharacterData>().data();
+        let offset = match index {
+            Some(index) => data
+                .chars()
+                .take(index)
+                .map(|character| character.len_utf16() as u32)
+                .sum(),
+            None => 0,
+        };
+        return (DomRoot::from_ref(node), offset);
+    }
+
+    match node.GetParentNode() {
+        Some(parent) => {
+            let after = point_in_node
+                .x
+                .partial_cmp(&(border_box.size.width.to_f32_px() / 2.)) ==
+                Some(Ordering::Greater);
+            let index = node.index();
+            (parent, if after { index + 1 } else { index })
+        },
+        None => (DomRoot::from_ref(node), 0),
+    }
+}
@@ -11,6 +11,7 @@ use crate::dom::bindings::codegen::Bindings::HTMLImageElementBinding::HTMLImageE
 use crate::dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use crate::dom::bindings::codegen::Bindings::ServoParserBinding;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::refcounted::Trusted;
 use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
@@ -35,6 +36,7 @@ use crate::dom::text::Text;
 use crate::dom::virtualmethods::vtable_for;
 use crate::network_listener::PreInvoke;
 use crate::script_thread::ScriptThread;
+use crate::task_source::TaskSource;
 use dom_struct::dom_struct;
 use embedder_traits::resources::{self, Resource};
 use encoding_rs::Encoding;
@@ -44,8 +46,12 @@ use html5ever::tendril::{ByteTendril, StrTendril, TendrilSink};
 use html5ever::tree_builder::{ElementFlags, NextParserState, NodeOrText, QuirksMode, TreeSink};
 use html5ever::{Attribute, ExpandedName, LocalName, QualName};
 use hyper_serde::Serde;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
 use mime::{self, Mime};
 use msg::constellation_msg::PipelineId;
+use net_traits::content_security_policy::{CheckResult, Violation};
+use net_traits::{CertificateError, CoreResourceMsg, IpcSend};
 use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use profile_traits::time::{
@@ -663,6 +669,64 @@ impl ParserContext {
     }
 }
 
+/// <https://w3c.github.io/webappsec-csp/#frame-ancestors-navigation-response>
+fn is_blocked_by_frame_ancestors(document: &Document) -> bool {
+    let window = document.window();
+    let global = window.upcast::<GlobalScope>();
+    let csp_list = match global.get_csp_list() {
+        Some(csp_list) => csp_list,
+        None => return false,
+    };
+    let mut ancestors = vec![];
+    let window_proxy = window.window_proxy();
+    let mut ancestor = window_proxy.parent();
+    while let Some(proxy) = ancestor {
+        ancestors.push(
+            proxy
+                .document()
+                .map(|document| document.origin().immutable().clone()),
+        );
+        ancestor = proxy.parent();
+    }
+    if ancestors.is_empty() {
+        return false;
+    }
+    let (result, violations) =
+        csp_list.should_frame_ancestors_block(document.origin().immutable(), &ancestors);
+    global.report_csp_violations(violations);
+    result == CheckResult::Blocked
+}
+
+/// Ask the embedder, through the resource thread, whether to trust the certificate which
+/// failed verification, and load the document again if an exception was made for it.
+fn handle_certificate_error(document: &Document, error: &CertificateError) {
+    let window = document.window();
+    let (sender, receiver) = ipc::channel().unwrap();
+    let _ = window.upcast::<GlobalScope>().resource_threads().send(
+        CoreResourceMsg::PromptCertificateException(error.host.clone(), sender),
+    );
+
+    let (task_source, canceller) = window
+        .task_manager()
+        .networking_task_source_with_canceller();
+    let window = Trusted::new(window);
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            if !message.to().unwrap_or(false) {
+                return;
+            }
+            let window = window.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(reload_with_certificate_exception: move || {
+                    window.root().Location().reload_without_origin_check();
+                }),
+                &canceller,
+            );
+        }),
+    );
+}
+
 impl FetchResponseListener for ParserContext {
     fn process_request_body(&mut self) {}
 
@@ -676,8 +740,8 @@ impl FetchResponseListener for ParserContext {
                 FetchMetadata::Unfiltered(m) => m,
                 FetchMetadata::Filtered { unsafe_, .. } => unsafe_,
             }),
-            Err(NetworkError::SslValidation(url, reason)) => {
-                ssl_error = Some(reason);
+            Err(NetworkError::SslValidation(url, error)) => {
+                ssl_error = Some(error);
                 let mut meta = Metadata::default(url);
                 let mime: Option<Mime> = "text/html".parse().ok();
                 meta.set_content_type(mime.as_ref());
@@ -707,6 +771,15 @@ impl FetchResponseListener for ParserContext {
 
         self.parser = Some(Trusted::new(&*parser));
 
+        if is_blocked_by_frame_ancestors(&parser.document) {
+            self.is_synthesized_document = true;
+            let page = resources::read_string(Resource::NetErrorHTML);
+            let page = page.replace("${reason}", "Blocked by Content-Security-Policy");
+            parser.push_string_input_chunk(page);
+            parser.parse_sync();
+            return;
+        }
+
         match content_type {
             Some(ref mime) if mime.type_() == mime::IMAGE => {
                 self.is_synthesized_document = true;
@@ -731,12 +804,15 @@ impl FetchResponseListener for ParserContext {
             },
             Some(ref mime) if mime.type_() == mime::TEXT && mime.subtype() == mime::HTML => {
                 // Handle text/html
-                if let Some(reason) = ssl_error {
+                if let Some(error) = ssl_error {
                     self.is_synthesized_document = true;
                     let page = resources::read_string(Resource::BadCertHTML);
-                    let page = page.replace("${reason}", &reason);
+                    let page = page
+                        .replace("${host}", &error.host)
+                        .replace("${reason}", &error.description());
                     parser.push_string_input_chunk(page);
                     parser.parse_sync();
+                    handle_certificate_error(&parser.document, &error);
                 }
                 if let Some(reason) = network_error {
                     self.is_synthesized_document = true;
@@ -818,6 +894,15 @@ impl FetchResponseListener for ParserContext {
         self.submit_resource_timing();
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let parser = match self.parser.as_ref() {
+            Some(parser) => parser.root(),
+            None => return,
+        };
+        let global = parser.document.window().upcast::<GlobalScope>();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
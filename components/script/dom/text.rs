@@ -9,24 +9,30 @@ use crate::dom::bindings::codegen::Bindings::TextBinding::{self, TextMethods};
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::error::{Error, Fallible};
 use crate::dom::bindings::inheritance::Castable;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{DomRoot, LayoutDom};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::characterdata::CharacterData;
 use crate::dom::document::Document;
-use crate::dom::node::Node;
+use crate::dom::node::{Node, NodeDamage};
 use crate::dom::window::Window;
 use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::ops::Range;
 
 /// An HTML text node.
 #[dom_struct]
 pub struct Text {
     characterdata: CharacterData,
+    /// The byte range of the data of this node that the selection of its
+    /// document covers, if any.
+    selection: Cell<Option<(usize, usize)>>,
 }
 
 impl Text {
     pub fn new_inherited(text: DOMString, document: &Document) -> Text {
         Text {
             characterdata: CharacterData::new_inherited(text, document),
+            selection: Cell::new(None),
         }
     }
 
@@ -42,6 +48,31 @@ impl Text {
         let document = window.Document();
         Ok(Text::new(text, &document))
     }
+
+    /// Set the byte range of the data of this node that is selected, and
+    /// repaint it.
+    pub fn set_selection(&self, selection: Option<Range<usize>>) {
+        let selection = selection.map(|range| (range.start, range.end));
+        if self.selection.get() != selection {
+            self.selection.set(selection);
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+}
+
+pub trait LayoutTextHelpers {
+    unsafe fn selection_for_layout(&self) -> Option<Range<usize>>;
+}
+
+#[allow(unsafe_code)]
+impl LayoutTextHelpers for LayoutDom<Text> {
+    #[inline]
+    unsafe fn selection_for_layout(&self) -> Option<Range<usize>> {
+        (*self.unsafe_get())
+            .selection
+            .get()
+            .map(|(start, end)| start..end)
+    }
 }
 
 impl TextMethods for Text {
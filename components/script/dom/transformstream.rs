@@ -0,0 +1,313 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding;
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding::TransformStreamMethods;
+use crate::dom::bindings::conversions::get_property_jsval;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
+use crate::dom::readablestreamdefaultcontroller::{
+    ReadableStreamDefaultController, UnderlyingSource,
+};
+use crate::dom::transformstreamdefaultcontroller::TransformStreamDefaultController;
+use crate::dom::writablestream::WritableStream;
+use crate::dom::writablestreamdefaultcontroller::{
+    UnderlyingSink, WritableStreamDefaultController,
+};
+use crate::script_runtime::JSContext;
+use crate::streams::PromiseReaction;
+use crate::streams::{extract_high_water_mark, promise_resolved_with, upon_settlement};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// The steps a transform stream takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum TransformStreamStep {
+    /// Backpressure was relieved, so the chunk can be transformed; the
+    /// promise is the one returned by the write algorithm.
+    BackpressureRelieved(Box<Heap<JSVal>>, Rc<Promise>),
+    /// The transformation of a chunk finished.
+    Transformed(Rc<Promise>),
+    /// The flush algorithm of the transformer finished.
+    Flushed(Rc<Promise>),
+}
+
+// https://streams.spec.whatwg.org/#ts-class
+#[dom_struct]
+pub struct TransformStream {
+    reflector_: Reflector,
+    readable: MutNullableDom<ReadableStream>,
+    writable: MutNullableDom<WritableStream>,
+    controller: MutNullableDom<TransformStreamDefaultController>,
+    backpressure: Cell<bool>,
+    #[ignore_malloc_size_of = "Rc"]
+    backpressure_change_promise: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    start_promise: Rc<Promise>,
+}
+
+impl TransformStream {
+    fn new_inherited(start_promise: Rc<Promise>) -> TransformStream {
+        TransformStream {
+            reflector_: Reflector::new(),
+            readable: MutNullableDom::new(None),
+            writable: MutNullableDom::new(None),
+            controller: MutNullableDom::new(None),
+            backpressure: Cell::new(false),
+            backpressure_change_promise: DomRefCell::new(None),
+            start_promise,
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#ts-constructor
+    #[allow(unrooted_must_root, unsafe_code)]
+    pub fn Constructor(
+        cx: JSContext,
+        global: &GlobalScope,
+        transformer: Option<*mut JSObject>,
+        writable_strategy: &QueuingStrategy,
+        readable_strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<TransformStream>> {
+        rooted!(in(*cx) let transformer = transformer.unwrap_or(ptr::null_mut()));
+        // Steps 2-5.
+        if !transformer.is_null() {
+            for name in &["readableType", "writableType"] {
+                rooted!(in(*cx) let mut type_ = UndefinedValue());
+                unsafe { get_property_jsval(*cx, transformer.handle(), name, type_.handle_mut())? };
+                if !type_.is_undefined() {
+                    return Err(Error::Range(format!("Invalid {}", name)));
+                }
+            }
+        }
+        // Steps 6-11.
+        let writable_hwm = extract_high_water_mark(writable_strategy, 1.)?;
+        let readable_hwm = extract_high_water_mark(readable_strategy, 0.)?;
+
+        // https://streams.spec.whatwg.org/#initialize-transform-stream
+        let stream = reflect_dom_object(
+            Box::new(TransformStream::new_inherited(Promise::new(global))),
+            global,
+            TransformStreamBinding::Wrap,
+        );
+        let writable = WritableStream::new(global);
+        WritableStreamDefaultController::set_up(
+            &writable,
+            UnderlyingSink::Transform(Dom::from_ref(&*stream)),
+            writable_hwm,
+            writable_strategy.size.clone(),
+        )?;
+        stream.writable.set(Some(&writable));
+        let readable = ReadableStream::new(global);
+        ReadableStreamDefaultController::set_up(
+            &readable,
+            UnderlyingSource::Transform(Dom::from_ref(&*stream)),
+            readable_hwm,
+            readable_strategy.size.clone(),
+        )?;
+        stream.readable.set(Some(&readable));
+        stream.set_backpressure(true);
+
+        // Steps 12-13.
+        let controller = TransformStreamDefaultController::set_up_from_transformer(
+            &stream,
+            transformer.handle(),
+        )?;
+        stream.controller.set(Some(&controller));
+        rooted!(in(*cx) let mut start_result = UndefinedValue());
+        controller.start(start_result.handle_mut())?;
+        unsafe { stream.start_promise.resolve(*cx, start_result.handle()) };
+        Ok(stream)
+    }
+
+    /// The promise the start algorithms of both sides of the stream return.
+    pub fn start_promise(&self) -> Rc<Promise> {
+        self.start_promise.clone()
+    }
+
+    pub fn readable(&self) -> DomRoot<ReadableStream> {
+        self.readable
+            .get()
+            .expect("A transform stream without a readable side")
+    }
+
+    pub fn readable_controller(&self) -> DomRoot<ReadableStreamDefaultController> {
+        self.readable()
+            .default_controller()
+            .expect("The readable side of a transform stream has no default controller")
+    }
+
+    fn writable(&self) -> DomRoot<WritableStream> {
+        self.writable
+            .get()
+            .expect("A transform stream without a writable side")
+    }
+
+    fn controller(&self) -> DomRoot<TransformStreamDefaultController> {
+        self.controller
+            .get()
+            .expect("A transform stream without a controller")
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error>
+    pub fn error(&self, e: HandleValue) {
+        self.readable_controller().error(e);
+        self.error_writable_and_unblock_write(e);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error-writable-and-unblock-write>
+    pub fn error_writable_and_unblock_write(&self, e: HandleValue) {
+        if let Some(controller) = self.controller.get() {
+            controller.clear_algorithms();
+        }
+        self.writable().controller().error_if_needed(e);
+        if self.backpressure.get() {
+            self.set_backpressure(false);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-set-backpressure>
+    pub fn set_backpressure(&self, backpressure: bool) {
+        let promise = Promise::new(&self.global());
+        if let Some(previous) = self
+            .backpressure_change_promise
+            .borrow_mut()
+            .replace(promise)
+        {
+            previous.resolve_native(&());
+        }
+        self.backpressure.set(backpressure);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-write-algorithm>
+    pub fn sink_write(&self, chunk: HandleValue) -> Rc<Promise> {
+        if !self.backpressure.get() {
+            return self.controller().perform_transform(chunk);
+        }
+        let backpressure_change_promise = self
+            .backpressure_change_promise
+            .borrow()
+            .clone()
+            .expect("Backpressure without a backpressure change promise");
+        let promise = Promise::new(&self.global());
+        upon_settlement(
+            &backpressure_change_promise,
+            self,
+            TransformStreamStep::BackpressureRelieved(Heap::boxed(chunk.get()), promise.clone()),
+        );
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-abort-algorithm>
+    pub fn sink_abort(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error(reason);
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        promise_resolved_with(&self.global(), undefined.handle())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-close-algorithm>
+    pub fn sink_close(&self) -> Rc<Promise> {
+        let controller = self.controller();
+        let flush_promise = controller.flush();
+        controller.clear_algorithms();
+        let promise = Promise::new(&self.global());
+        upon_settlement(
+            &flush_promise,
+            self,
+            TransformStreamStep::Flushed(promise.clone()),
+        );
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-pull>
+    pub fn source_pull(&self) -> Rc<Promise> {
+        self.set_backpressure(false);
+        self.backpressure_change_promise
+            .borrow()
+            .clone()
+            .expect("No backpressure change promise")
+    }
+
+    /// The cancel algorithm of the readable side, as in
+    /// <https://streams.spec.whatwg.org/#initialize-transform-stream>.
+    pub fn source_cancel(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error_writable_and_unblock_write(reason);
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        promise_resolved_with(&self.global(), undefined.handle())
+    }
+}
+
+impl PromiseReaction for TransformStream {
+    type Step = TransformStreamStep;
+
+    fn settled(&self, step: &TransformStreamStep, result: Result<HandleValue, HandleValue>) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        match (step, result) {
+            (TransformStreamStep::BackpressureRelieved(chunk, promise), _) => {
+                let writable = self.writable();
+                if writable.is_erroring() {
+                    writable.stored_error(error.handle_mut());
+                    return promise.reject_native(&error.get());
+                }
+                rooted!(in(*cx) let chunk = chunk.get());
+                let transformed = self.controller().perform_transform(chunk.handle());
+                upon_settlement(
+                    &transformed,
+                    self,
+                    TransformStreamStep::Transformed(promise.clone()),
+                );
+            },
+            (TransformStreamStep::Transformed(promise), Ok(_)) => promise.resolve_native(&()),
+            (TransformStreamStep::Transformed(promise), Err(reason)) => {
+                promise.reject_native(&reason.get())
+            },
+            (TransformStreamStep::Flushed(promise), Ok(_)) => {
+                let readable = self.readable();
+                if readable.is_errored() {
+                    readable.stored_error(error.handle_mut());
+                    return promise.reject_native(&error.get());
+                }
+                let controller = self.readable_controller();
+                if controller.can_close_or_enqueue() {
+                    controller.close();
+                }
+                promise.resolve_native(&());
+            },
+            (TransformStreamStep::Flushed(promise), Err(reason)) => {
+                self.error(reason);
+                self.readable().stored_error(error.handle_mut());
+                promise.reject_native(&error.get());
+            },
+        }
+    }
+}
+
+impl TransformStreamMethods for TransformStream {
+    // https://streams.spec.whatwg.org/#ts-readable
+    fn Readable(&self) -> DomRoot<ReadableStream> {
+        self.readable()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.writable()
+    }
+}
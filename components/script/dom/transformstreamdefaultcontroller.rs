@@ -0,0 +1,218 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding::TransformStreamDefaultControllerMethods;
+use crate::dom::bindings::conversions::get_property_jsval;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::promise::Promise;
+use crate::dom::transformstream::TransformStream;
+use crate::script_runtime::JSContext;
+use crate::streams::{call_underlying_method, check_underlying_methods, error_to_jsval};
+use crate::streams::{promise_call, promise_rejected_with_error, promise_resolved_with};
+use crate::streams::{throw_jsval, upon_settlement, PromiseReaction};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use std::ptr;
+use std::rc::Rc;
+
+/// The steps a transform stream controller takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum TransformStreamDefaultControllerStep {
+    /// The transform method of the transformer finished; the promise is the
+    /// one returned by the transform algorithm.
+    Transformed(Rc<Promise>),
+}
+
+// https://streams.spec.whatwg.org/#ts-default-controller-class
+#[dom_struct]
+pub struct TransformStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<TransformStream>,
+    /// Null once the algorithms of the controller are cleared.
+    #[ignore_malloc_size_of = "mozjs"]
+    transformer: Heap<*mut JSObject>,
+}
+
+impl TransformStreamDefaultController {
+    fn new_inherited(stream: &TransformStream) -> TransformStreamDefaultController {
+        TransformStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            transformer: Heap::default(),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller-from-transformer>
+    pub fn set_up_from_transformer(
+        stream: &TransformStream,
+        transformer: HandleObject,
+    ) -> Fallible<DomRoot<TransformStreamDefaultController>> {
+        let global = stream.global();
+        let cx = global.get_cx();
+        check_underlying_methods(cx, transformer, &["start", "transform", "flush"])?;
+        let controller = reflect_dom_object(
+            Box::new(TransformStreamDefaultController::new_inherited(stream)),
+            &global,
+            TransformStreamDefaultControllerBinding::Wrap,
+        );
+        controller.transformer.set(transformer.get());
+        Ok(controller)
+    }
+
+    /// Call the start method of the transformer.
+    pub fn start(&self, rval: MutableHandleValue) -> Fallible<()> {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let transformer = self.transformer.get());
+        rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+        call_underlying_method(cx, transformer.handle(), "start", &[this.get()], rval)
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-clear-algorithms>
+    pub fn clear_algorithms(&self) {
+        self.transformer.set(ptr::null_mut());
+    }
+
+    /// The flush algorithm of the transformer.
+    pub fn flush(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let transformer = self.transformer.get());
+        rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+        promise_call(&global, transformer.handle(), "flush", &[this.get()])
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-perform-transform>
+    #[allow(unsafe_code)]
+    pub fn perform_transform(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let transformer = self.transformer.get());
+        rooted!(in(*cx) let mut method = UndefinedValue());
+        if !transformer.is_null() {
+            let result = unsafe {
+                get_property_jsval(*cx, transformer.handle(), "transform", method.handle_mut())
+            };
+            if let Err(error) = result {
+                return promise_rejected_with_error(&global, error);
+            }
+        }
+        // Without a transform method, chunks are enqueued unchanged.
+        if method.is_undefined() {
+            return match self.enqueue(chunk) {
+                Ok(()) => {
+                    rooted!(in(*cx) let undefined = UndefinedValue());
+                    promise_resolved_with(&global, undefined.handle())
+                },
+                Err(error) => promise_rejected_with_error(&global, error),
+            };
+        }
+        rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+        let transform_promise = promise_call(
+            &global,
+            transformer.handle(),
+            "transform",
+            &[chunk.get(), this.get()],
+        );
+        let promise = Promise::new(&global);
+        upon_settlement(
+            &transform_promise,
+            self,
+            TransformStreamDefaultControllerStep::Transformed(promise.clone()),
+        );
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-enqueue>
+    fn enqueue(&self, chunk: HandleValue) -> ErrorResult {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Steps 2-3.
+        let readable_controller = self.stream.readable_controller();
+        if !readable_controller.can_close_or_enqueue() {
+            return Err(Error::Type(
+                "The readable side cannot be enqueued to".to_owned(),
+            ));
+        }
+        // Steps 4-5.
+        if let Err(error) = readable_controller.enqueue(chunk) {
+            rooted!(in(*cx) let mut error_value = UndefinedValue());
+            error_to_jsval(&global, error, error_value.handle_mut());
+            self.stream
+                .error_writable_and_unblock_write(error_value.handle());
+            self.stream
+                .readable()
+                .stored_error(error_value.handle_mut());
+            return Err(throw_jsval(cx, error_value.handle()));
+        }
+        // Steps 6-7.
+        let backpressure = readable_controller.has_backpressure();
+        if backpressure != self.stream.backpressure() {
+            self.stream.set_backpressure(true);
+        }
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-terminate>
+    fn terminate(&self) {
+        let global = self.global();
+        let cx = global.get_cx();
+        let readable_controller = self.stream.readable_controller();
+        if readable_controller.can_close_or_enqueue() {
+            readable_controller.close();
+        }
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        error_to_jsval(
+            &global,
+            Error::Type("The transform stream was terminated".to_owned()),
+            error.handle_mut(),
+        );
+        self.stream.error_writable_and_unblock_write(error.handle());
+    }
+}
+
+impl PromiseReaction for TransformStreamDefaultController {
+    type Step = TransformStreamDefaultControllerStep;
+
+    fn settled(
+        &self,
+        step: &TransformStreamDefaultControllerStep,
+        result: Result<HandleValue, HandleValue>,
+    ) {
+        let TransformStreamDefaultControllerStep::Transformed(ref promise) = *step;
+        match result {
+            Ok(_) => promise.resolve_native(&()),
+            Err(reason) => {
+                self.stream.error(reason);
+                promise.reject_native(&reason.get());
+            },
+        }
+    }
+}
+
+impl TransformStreamDefaultControllerMethods for TransformStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ts-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.stream.readable_controller().desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-enqueue
+    fn Enqueue(&self, _cx: JSContext, chunk: HandleValue) -> ErrorResult {
+        self.enqueue(chunk)
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-error
+    fn Error(&self, _cx: JSContext, reason: HandleValue) {
+        self.stream.error(reason);
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-terminate
+    fn Terminate(&self) {
+        self.terminate();
+    }
+}
@@ -1,13 +1,34 @@
 /* This Source Code Form is subject to the terms of the Mozilla Public
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+use crate::dom::element::Element;
 use crate::dom::validitystate::ValidationFlags;
 
 pub trait Validatable {
+    fn as_element(&self) -> &Element;
+
     fn is_instance_validatable(&self) -> bool {
         true
     }
     fn validate(&self, _validate_flags: ValidationFlags) -> bool {
         true
     }
+
+    // https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation
+    fn is_validation_candidate(&self) -> bool {
+        // Disabled elements are barred from constraint validation, and match
+        // neither `:valid` nor `:invalid`.
+        !self.as_element().disabled_state() && self.is_instance_validatable()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#selector-valid
+    // https://html.spec.whatwg.org/multipage/#selector-invalid
+    fn update_validity_state(&self) {
+        let valid = if self.is_validation_candidate() {
+            Some(self.validate(ValidationFlags::all()))
+        } else {
+            None
+        };
+        self.as_element().set_validity_state(valid);
+    }
 }
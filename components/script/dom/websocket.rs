@@ -32,6 +32,7 @@ use js::jsapi::{JSAutoRealm, JSObject};
 use js::jsval::UndefinedValue;
 use js::rust::CustomAutoRooterGuard;
 use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use net_traits::content_security_policy::Violation;
 use net_traits::request::{Referrer, RequestBuilder, RequestMode};
 use net_traits::MessageData;
 use net_traits::{CoreResourceMsg, FetchChannels};
@@ -204,7 +205,8 @@ impl WebSocket {
         let request = RequestBuilder::new(url_record)
             .origin(global.origin().immutable().clone())
             .mode(RequestMode::WebSocket { protocols })
-            .referrer(Some(Referrer::NoReferrer));
+            .referrer(Some(Referrer::NoReferrer))
+            .csp_list(global.get_csp_list());
 
         let channels = FetchChannels::WebSocket {
             event_sender: resource_event_sender,
@@ -240,6 +242,15 @@ impl WebSocket {
                 WebSocketNetworkEvent::Fail => {
                     fail_the_websocket_connection(address.clone(), &task_source, &canceller);
                 },
+                WebSocketNetworkEvent::ReportCspViolations(violations) => {
+                    let report_thread = ReportCspViolationsTask {
+                        address: address.clone(),
+                        violations,
+                    };
+                    task_source
+                        .queue_with_canceller(report_thread, &canceller)
+                        .unwrap();
+                },
                 WebSocketNetworkEvent::Close(code, reason) => {
                     close_the_websocket_connection(
                         address.clone(),
@@ -482,6 +493,18 @@ impl TaskOnce for ConnectionEstablishedTask {
     }
 }
 
+struct ReportCspViolationsTask {
+    address: Trusted<WebSocket>,
+    violations: Vec<Violation>,
+}
+
+impl TaskOnce for ReportCspViolationsTask {
+    fn run_once(self) {
+        let ws = self.address.root();
+        ws.global().report_csp_violations(self.violations);
+    }
+}
+
 struct BufferedAmountTask {
     address: Trusted<WebSocket>,
 }
@@ -598,6 +621,7 @@ impl TaskOnce for MessageReceivedTask {
                 message.handle(),
                 Some(&ws.origin().ascii_serialization()),
                 None,
+                vec![],
             );
         }
     }
@@ -12,6 +12,7 @@ use crate::dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::His
 use crate::dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
 use crate::dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowPostMessageOptions;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::{
     self, FrameRequestCallback, WindowMethods,
 };
@@ -39,6 +40,7 @@ use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::hashchangeevent::HashChangeEvent;
 use crate::dom::history::History;
+use crate::dom::idbfactory::IDBFactory;
 use crate::dom::location::Location;
 use crate::dom::mediaquerylist::{MediaQueryList, MediaQueryListMatchState};
 use crate::dom::mediaquerylistevent::MediaQueryListEvent;
@@ -48,6 +50,7 @@ use crate::dom::node::{document_from_node, from_untrusted_node_address, Node, No
 use crate::dom::performance::Performance;
 use crate::dom::promise::Promise;
 use crate::dom::screen::Screen;
+use crate::dom::selection::Selection;
 use crate::dom::storage::Storage;
 use crate::dom::testrunner::TestRunner;
 use crate::dom::webglrenderingcontext::WebGLCommandSender;
@@ -80,12 +83,13 @@ use euclid::{Point2D, Rect, Scale, Size2D, Vector2D};
 use ipc_channel::ipc::{channel, IpcSender};
 use ipc_channel::router::ROUTER;
 use js::jsapi::JSAutoRealm;
+use js::jsapi::JSObject;
 use js::jsapi::JSPROP_ENUMERATE;
 use js::jsapi::{GCReason, JS_GC};
 use js::jsval::JSVal;
 use js::jsval::UndefinedValue;
 use js::rust::wrappers::JS_DefineProperty;
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard, HandleValue};
 use media::WindowGLContext;
 use msg::constellation_msg::PipelineId;
 use net_traits::image_cache::{ImageCache, ImageResponder, ImageResponse};
@@ -105,7 +109,7 @@ use script_layout_interface::{PendingImageState, TrustedNodeAddress};
 use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
 use script_traits::{ConstellationControlMsg, DocumentState, HistoryEntryReplacement, LoadData};
 use script_traits::{ScriptMsg, ScriptToConstellationChan, ScrollState, TimerEvent, TimerEventId};
-use script_traits::{TimerSchedulerMsg, WindowSizeData, WindowSizeType};
+use script_traits::{StructuredSerializedData, TimerSchedulerMsg, WindowSizeData, WindowSizeType};
 use selectors::attr::CaseSensitivity;
 use servo_geometry::{f32_rect_to_au_rect, MaxRect};
 use servo_url::{Host, ImmutableOrigin, MutableOrigin, ServoUrl};
@@ -136,6 +140,10 @@ use webrender_api::units::{DeviceIntPoint, DeviceIntSize, LayoutPixel};
 use webrender_api::{DocumentId, ExternalScrollId, RenderApiSender};
 use webvr_traits::WebVRMsg;
 
+/// How long, in nanoseconds, a user interaction lets the page use the APIs which require
+/// transient activation.
+const TRANSIENT_ACTIVATION_DURATION: u64 = 5_000_000_000;
+
 /// Current state of the window object
 #[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
 enum WindowState {
@@ -187,6 +195,9 @@ pub struct Window {
     performance: MutNullableDom<Performance>,
     navigation_start: Cell<u64>,
     navigation_start_precise: Cell<u64>,
+    /// The time at which the user last interacted with the page, in nanoseconds.
+    /// <https://html.spec.whatwg.org/multipage/#last-activation-timestamp>
+    last_activation_timestamp: Cell<Option<u64>>,
     screen: MutNullableDom<Screen>,
     session_storage: MutNullableDom<Storage>,
     local_storage: MutNullableDom<Storage>,
@@ -689,6 +700,11 @@ impl WindowMethods for Window {
             .expect("Document accessed before initialization.")
     }
 
+    // https://w3c.github.io/selection-api/#dom-window-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        self.Document().GetSelection()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-history
     fn History(&self) -> DomRoot<History> {
         self.history.or_init(|| History::new(self))
@@ -722,6 +738,11 @@ impl WindowMethods for Window {
         self.upcast::<GlobalScope>().crypto()
     }
 
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.upcast::<GlobalScope>().indexed_db()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-frameelement
     fn GetFrameElement(&self) -> Option<DomRoot<Element>> {
         // Steps 1-3.
@@ -912,27 +933,33 @@ impl WindowMethods for Window {
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    fn PostMessage(&self, cx: JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
-        let source_global = GlobalScope::incumbent().expect("no incumbent global??");
-        let source = source_global.as_window();
-
-        // Step 3-5.
-        let origin = match &origin[..] {
-            "*" => None,
-            "/" => Some(source.Document().origin().immutable().clone()),
-            url => match ServoUrl::parse(&url) {
-                Ok(url) => Some(url.origin().clone()),
-                Err(_) => return Err(Error::Syntax),
-            },
-        };
-
-        // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(*cx, message)?;
+    fn PostMessage(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        target_origin: DOMString,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        self.post_message_impl(cx, message, &target_origin, transfer)
+    }
 
-        // Step 9.
-        self.post_message(origin, &*source.window_proxy(), data);
-        Ok(())
+    // https://html.spec.whatwg.org/multipage/#dom-window-postmessage-options
+    fn PostMessage_(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        options: RootedTraceableBox<WindowPostMessageOptions>,
+    ) -> ErrorResult {
+        let mut rooted = CustomAutoRooter::new(
+            options
+                .parent
+                .transfer
+                .iter()
+                .map(|object| object.get())
+                .collect(),
+        );
+        let transfer = rooted.root(*cx);
+        self.post_message_impl(cx, message, &options.targetOrigin, transfer)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-window-captureevents
@@ -1239,6 +1266,21 @@ impl Window {
         self.navigation_start_precise.get()
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#activation-notification>
+    pub fn notify_activation(&self) {
+        self.last_activation_timestamp
+            .set(Some(time::precise_time_ns()));
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#transient-activation>
+    pub fn has_transient_activation(&self) -> bool {
+        self.last_activation_timestamp
+            .get()
+            .map_or(false, |timestamp| {
+                time::precise_time_ns() - timestamp < TRANSIENT_ACTIVATION_DURATION
+            })
+    }
+
     pub fn has_document(&self) -> bool {
         self.document.get().is_some()
     }
@@ -1282,6 +1324,11 @@ impl Window {
             custom_elements.teardown();
         }
 
+        // Stop routing port and broadcast messages to this window.
+        self.upcast::<GlobalScope>().remove_message_ports();
+        self.upcast::<GlobalScope>().remove_broadcast_channels();
+        self.upcast::<GlobalScope>().close_indexeddb_connections();
+
         // The above code may not catch all DOM objects (e.g. DOM
         // objects removed from the tree that haven't been collected
         // yet). There should not be any such DOM nodes with layout
@@ -1560,6 +1607,11 @@ impl Window {
         self.Document().ensure_safe_to_run_script_or_layout();
         let for_display = reflow_goal == ReflowGoal::Full;
 
+        // Make sure the selection painted is the current one.
+        if for_display {
+            self.Document().update_selection();
+        }
+
         let mut issued_reflow = false;
         if !for_display || self.Document().needs_reflow() {
             issued_reflow = self.force_reflow(reflow_goal, reason);
@@ -2137,6 +2189,7 @@ impl Window {
             performance: Default::default(),
             navigation_start: Cell::new(navigation_start),
             navigation_start_precise: Cell::new(navigation_start_precise),
+            last_activation_timestamp: Cell::new(None),
             screen: Default::default(),
             session_storage: Default::default(),
             local_storage: Default::default(),
@@ -2263,12 +2316,41 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
 }
 
 impl Window {
+    // https://html.spec.whatwg.org/multipage/#window-post-message-steps
+    fn post_message_impl(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        target_origin: &str,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        let source_global = GlobalScope::incumbent().expect("no incumbent global??");
+        let source = source_global.as_window();
+
+        // Step 3-5.
+        let origin = match target_origin {
+            "*" => None,
+            "/" => Some(source.Document().origin().immutable().clone()),
+            url => match ServoUrl::parse(&url) {
+                Ok(url) => Some(url.origin().clone()),
+                Err(_) => return Err(Error::Syntax),
+            },
+        };
+
+        // Step 1-2, 6-8.
+        let data = StructuredCloneData::write_with_transfer(*cx, message, transfer)?;
+
+        // Step 9.
+        self.post_message(origin, &*source.window_proxy(), data);
+        Ok(())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage step 7.
     pub fn post_message(
         &self,
         target_origin: Option<ImmutableOrigin>,
         source: &WindowProxy,
-        serialize_with_transfer_result: StructuredCloneData,
+        serialize_with_transfer_result: StructuredSerializedData,
     ) {
         let this = Trusted::new(self);
         let source = Trusted::new(source);
@@ -2288,14 +2370,13 @@ impl Window {
             let obj = this.reflector().get_jsobject();
             let _ac = JSAutoRealm::new(*cx, obj.get());
             rooted!(in(*cx) let mut message_clone = UndefinedValue());
-            serialize_with_transfer_result.read(
+            // Step 7.6.
+            let new_ports = StructuredCloneData::read_with_transfer(
                 this.upcast(),
+                serialize_with_transfer_result,
                 message_clone.handle_mut(),
             );
 
-            // Step 7.6.
-            // TODO: MessagePort array.
-
             // Step 7.7.
             // TODO(#12719): Set the other attributes.
             MessageEvent::dispatch_jsval(
@@ -2304,6 +2385,7 @@ impl Window {
                 message_clone.handle(),
                 None,
                 Some(&*source),
+                new_ports,
             );
         });
         // FIXME(nox): Why are errors silenced here?
@@ -446,13 +446,14 @@ impl WindowProxy {
             // Step 14.5
             let referrer_policy = target_document.get_referrer_policy();
             let pipeline_id = target_window.upcast::<GlobalScope>().pipeline_id();
-            let load_data = LoadData::new(
+            let mut load_data = LoadData::new(
                 LoadOrigin::Script(existing_document.origin().immutable().clone()),
                 url,
                 Some(pipeline_id),
                 Some(referrer),
                 referrer_policy,
             );
+            load_data.site_for_cookies = Some(existing_document.global().site_for_cookies());
             let replacement_flag = if new {
                 HistoryEntryReplacement::Enabled
             } else {
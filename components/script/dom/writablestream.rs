@@ -0,0 +1,466 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding::WritableStreamMethods;
+use crate::dom::bindings::conversions::get_property_jsval;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::writablestreamdefaultcontroller::WritableStreamDefaultController;
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use crate::script_runtime::JSContext;
+use crate::streams::{extract_high_water_mark, promise_rejected_with_error, promise_resolved_with};
+use crate::streams::{upon_settlement, PromiseReaction};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#writablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum WritableStreamState {
+    Writable,
+    Closed,
+    Erroring,
+    Errored,
+}
+
+/// <https://streams.spec.whatwg.org/#pending-abort-request>
+#[derive(JSTraceable, MallocSizeOf)]
+struct PendingAbortRequest {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    #[ignore_malloc_size_of = "mozjs"]
+    reason: Box<Heap<JSVal>>,
+    was_already_erroring: bool,
+}
+
+/// The steps a writable stream takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum WritableStreamStep {
+    /// The abort algorithm of the underlying sink finished.
+    Aborted(Rc<Promise>),
+}
+
+// https://streams.spec.whatwg.org/#ws-class
+#[dom_struct]
+pub struct WritableStream {
+    reflector_: Reflector,
+    state: Cell<WritableStreamState>,
+    #[ignore_malloc_size_of = "mozjs"]
+    stored_error: Heap<JSVal>,
+    writer: MutNullableDom<WritableStreamDefaultWriter>,
+    controller: MutNullableDom<WritableStreamDefaultController>,
+    backpressure: Cell<bool>,
+    #[ignore_malloc_size_of = "Rc"]
+    write_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_write_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    close_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_close_request: DomRefCell<Option<Rc<Promise>>>,
+    pending_abort_request: DomRefCell<Option<PendingAbortRequest>>,
+}
+
+impl WritableStream {
+    fn new_inherited() -> WritableStream {
+        WritableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(WritableStreamState::Writable),
+            stored_error: Heap::default(),
+            writer: MutNullableDom::new(None),
+            controller: MutNullableDom::new(None),
+            backpressure: Cell::new(false),
+            write_requests: DomRefCell::new(VecDeque::new()),
+            in_flight_write_request: DomRefCell::new(None),
+            close_request: DomRefCell::new(None),
+            in_flight_close_request: DomRefCell::new(None),
+            pending_abort_request: DomRefCell::new(None),
+        }
+    }
+
+    /// Create a stream without a controller; the controller set up algorithm
+    /// must be run on it before it is handed out.
+    pub fn new(global: &GlobalScope) -> DomRoot<WritableStream> {
+        reflect_dom_object(
+            Box::new(WritableStream::new_inherited()),
+            global,
+            WritableStreamBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#ws-constructor
+    #[allow(unsafe_code)]
+    pub fn Constructor(
+        cx: JSContext,
+        global: &GlobalScope,
+        underlying_sink: Option<*mut JSObject>,
+        strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<WritableStream>> {
+        rooted!(in(*cx) let sink = underlying_sink.unwrap_or(ptr::null_mut()));
+        // Steps 2-3.
+        if !sink.is_null() {
+            rooted!(in(*cx) let mut type_ = UndefinedValue());
+            unsafe { get_property_jsval(*cx, sink.handle(), "type", type_.handle_mut())? };
+            if !type_.is_undefined() {
+                return Err(Error::Range(
+                    "Invalid type for a writable stream".to_owned(),
+                ));
+            }
+        }
+        // Steps 4-6.
+        let high_water_mark = extract_high_water_mark(strategy, 1.)?;
+        let stream = WritableStream::new(global);
+        WritableStreamDefaultController::set_up_from_underlying_sink(
+            &stream,
+            sink.handle(),
+            high_water_mark,
+            strategy.size.clone(),
+        )?;
+        Ok(stream)
+    }
+
+    pub fn set_controller(&self, controller: &WritableStreamDefaultController) {
+        self.controller.set(Some(controller));
+    }
+
+    pub fn controller(&self) -> DomRoot<WritableStreamDefaultController> {
+        self.controller
+            .get()
+            .expect("A writable stream without a controller")
+    }
+
+    pub fn set_writer(&self, writer: Option<&WritableStreamDefaultWriter>) {
+        self.writer.set(writer);
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-writable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.writer.get().is_some()
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.state.get() == WritableStreamState::Writable
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.get() == WritableStreamState::Closed
+    }
+
+    pub fn is_erroring(&self) -> bool {
+        self.state.get() == WritableStreamState::Erroring
+    }
+
+    pub fn is_errored(&self) -> bool {
+        self.state.get() == WritableStreamState::Errored
+    }
+
+    pub fn stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#acquire-writable-stream-default-writer>
+    pub fn acquire_writer(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(&self.global(), self)
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-abort>
+    pub fn abort(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Steps 1-3.
+        if self.is_closed() || self.is_errored() {
+            rooted!(in(*cx) let undefined = UndefinedValue());
+            return promise_resolved_with(&global, undefined.handle());
+        }
+        // Step 4.
+        if let Some(ref request) = *self.pending_abort_request.borrow() {
+            return request.promise.clone();
+        }
+        // Steps 5-7.
+        let was_already_erroring = self.is_erroring();
+        rooted!(in(*cx) let mut reason_value = reason.get());
+        if was_already_erroring {
+            reason_value.set(UndefinedValue());
+        }
+        // Steps 8-9.
+        let promise = Promise::new(&global);
+        *self.pending_abort_request.borrow_mut() = Some(PendingAbortRequest {
+            promise: promise.clone(),
+            reason: Heap::boxed(reason_value.get()),
+            was_already_erroring,
+        });
+        // Step 10.
+        if !was_already_erroring {
+            self.start_erroring(reason_value.handle());
+        }
+        // Step 11.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close>
+    pub fn close(&self) -> Rc<Promise> {
+        let global = self.global();
+        // Step 3.
+        if self.is_closed() || self.is_errored() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The stream is already closed".to_owned()),
+            );
+        }
+        // Steps 5-7.
+        let promise = Promise::new(&global);
+        *self.close_request.borrow_mut() = Some(promise.clone());
+        // Step 8.
+        if let Some(writer) = self.writer.get() {
+            if self.backpressure.get() && self.is_writable() {
+                writer.resolve_ready();
+            }
+        }
+        // Step 9.
+        self.controller().close();
+        // Step 10.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-add-write-request>
+    pub fn add_write_request(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        self.write_requests.borrow_mut().push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-deal-with-rejection>
+    pub fn deal_with_rejection(&self, error: HandleValue) {
+        if self.is_writable() {
+            self.start_erroring(error);
+            return;
+        }
+        self.finish_erroring();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-start-erroring>
+    pub fn start_erroring(&self, reason: HandleValue) {
+        // Steps 4-5.
+        self.state.set(WritableStreamState::Erroring);
+        self.stored_error.set(reason.get());
+        // Steps 6-7.
+        if let Some(writer) = self.writer.get() {
+            writer.ensure_ready_promise_rejected(reason);
+        }
+        // Step 8.
+        if !self.has_operation_marked_in_flight() && self.controller().started() {
+            self.finish_erroring();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-erroring>
+    pub fn finish_erroring(&self) {
+        let global = self.global();
+        let cx = global.get_cx();
+        // Steps 3-4.
+        self.state.set(WritableStreamState::Errored);
+        self.controller().error_steps();
+        // Steps 5-7.
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        self.stored_error(stored_error.handle_mut());
+        let write_requests = mem::replace(&mut *self.write_requests.borrow_mut(), VecDeque::new());
+        for request in write_requests {
+            request.reject_native(&stored_error.get());
+        }
+        // Step 8.
+        let abort_request = match self.pending_abort_request.borrow_mut().take() {
+            Some(abort_request) => abort_request,
+            None => {
+                self.reject_close_and_closed_promise_if_needed();
+                return;
+            },
+        };
+        // Step 11.
+        if abort_request.was_already_erroring {
+            abort_request.promise.reject_native(&stored_error.get());
+            self.reject_close_and_closed_promise_if_needed();
+            return;
+        }
+        // Steps 12-14.
+        rooted!(in(*cx) let reason = abort_request.reason.get());
+        let promise = self.controller().abort_steps(reason.handle());
+        upon_settlement(
+            &promise,
+            self,
+            WritableStreamStep::Aborted(abort_request.promise),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write>
+    pub fn finish_in_flight_write(&self) {
+        if let Some(request) = self.in_flight_write_request.borrow_mut().take() {
+            request.resolve_native(&());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write-with-error>
+    pub fn finish_in_flight_write_with_error(&self, error: HandleValue) {
+        if let Some(request) = self.in_flight_write_request.borrow_mut().take() {
+            request.reject_native(&error.get());
+        }
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close>
+    pub fn finish_in_flight_close(&self) {
+        // Steps 1-3.
+        if let Some(request) = self.in_flight_close_request.borrow_mut().take() {
+            request.resolve_native(&());
+        }
+        // Step 5.
+        if self.is_erroring() {
+            self.stored_error.set(UndefinedValue());
+            if let Some(abort_request) = self.pending_abort_request.borrow_mut().take() {
+                abort_request.promise.resolve_native(&());
+            }
+        }
+        // Steps 6-8.
+        self.state.set(WritableStreamState::Closed);
+        if let Some(writer) = self.writer.get() {
+            writer.resolve_closed();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close-with-error>
+    pub fn finish_in_flight_close_with_error(&self, error: HandleValue) {
+        if let Some(request) = self.in_flight_close_request.borrow_mut().take() {
+            request.reject_native(&error.get());
+        }
+        if let Some(abort_request) = self.pending_abort_request.borrow_mut().take() {
+            abort_request.promise.reject_native(&error.get());
+        }
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close-queued-or-in-flight>
+    pub fn close_queued_or_in_flight(&self) -> bool {
+        self.close_request.borrow().is_some() || self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-has-operation-marked-in-flight>
+    pub fn has_operation_marked_in_flight(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some() ||
+            self.in_flight_close_request.borrow().is_some()
+    }
+
+    pub fn has_in_flight_write_request(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-close-request-in-flight>
+    pub fn mark_close_request_in_flight(&self) {
+        let request = self.close_request.borrow_mut().take();
+        *self.in_flight_close_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-first-write-request-in-flight>
+    pub fn mark_first_write_request_in_flight(&self) {
+        let request = self.write_requests.borrow_mut().pop_front();
+        *self.in_flight_write_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-reject-close-and-closed-promise-if-needed>
+    fn reject_close_and_closed_promise_if_needed(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        self.stored_error(stored_error.handle_mut());
+        if let Some(request) = self.close_request.borrow_mut().take() {
+            request.reject_native(&stored_error.get());
+        }
+        if let Some(writer) = self.writer.get() {
+            writer.reject_closed(stored_error.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-update-backpressure>
+    pub fn update_backpressure(&self, backpressure: bool) {
+        if let Some(writer) = self.writer.get() {
+            if backpressure != self.backpressure.get() {
+                if backpressure {
+                    writer.reset_ready_promise();
+                } else {
+                    writer.resolve_ready();
+                }
+            }
+        }
+        self.backpressure.set(backpressure);
+    }
+}
+
+impl PromiseReaction for WritableStream {
+    type Step = WritableStreamStep;
+
+    fn settled(&self, step: &WritableStreamStep, result: Result<HandleValue, HandleValue>) {
+        match *step {
+            WritableStreamStep::Aborted(ref promise) => {
+                match result {
+                    Ok(_) => promise.resolve_native(&()),
+                    Err(reason) => promise.reject_native(&reason.get()),
+                }
+                self.reject_close_and_closed_promise_if_needed();
+            },
+        }
+    }
+}
+
+impl WritableStreamMethods for WritableStream {
+    // https://streams.spec.whatwg.org/#ws-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-abort
+    fn Abort(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is locked".to_owned()),
+            );
+        }
+        self.abort(reason)
+    }
+
+    // https://streams.spec.whatwg.org/#ws-close
+    fn Close(&self) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is locked".to_owned()),
+            );
+        }
+        if self.close_queued_or_in_flight() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is already closing".to_owned()),
+            );
+        }
+        self.close()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-get-writer
+    fn GetWriter(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        self.acquire_writer()
+    }
+}
@@ -0,0 +1,413 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding::WritableStreamDefaultControllerMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::promise::Promise;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use crate::streams::{call_underlying_method, check_underlying_methods, chunk_size};
+use crate::streams::{error_to_jsval, promise_call, promise_resolved_with, upon_settlement};
+use crate::streams::{PromiseReaction, QueueWithSizes};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleObject, MutableHandleValue};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// Where the algorithms of a writable stream controller come from.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum UnderlyingSink {
+    /// An underlying sink object passed to the `WritableStream` constructor.
+    Js(#[ignore_malloc_size_of = "mozjs"] Box<Heap<*mut JSObject>>),
+    /// The writable side of the given transform stream.
+    Transform(Dom<TransformStream>),
+}
+
+/// A rooted snapshot of an `UnderlyingSink`, taken so that no borrow is held
+/// while its algorithms run script.
+enum SinkRoot {
+    Js,
+    Transform(DomRoot<TransformStream>),
+}
+
+/// The steps a writable stream controller takes when a promise it waits on settles.
+#[derive(JSTraceable)]
+pub enum WritableStreamDefaultControllerStep {
+    Started,
+    Written,
+    Closed,
+}
+
+// https://streams.spec.whatwg.org/#ws-default-controller-class
+#[dom_struct]
+pub struct WritableStreamDefaultController {
+    reflector_: Reflector,
+    stream: Dom<WritableStream>,
+    /// `None` once the algorithms of the controller are cleared.
+    underlying_sink: DomRefCell<Option<UnderlyingSink>>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    strategy_hwm: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    strategy_size: DomRefCell<Option<Rc<QueuingStrategySize>>>,
+}
+
+impl WritableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        stream: &WritableStream,
+        underlying_sink: UnderlyingSink,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> WritableStreamDefaultController {
+        WritableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Dom::from_ref(stream),
+            underlying_sink: DomRefCell::new(Some(underlying_sink)),
+            queue: DomRefCell::new(QueueWithSizes::new()),
+            started: Cell::new(false),
+            strategy_hwm,
+            strategy_size: DomRefCell::new(strategy_size),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller>
+    #[allow(unrooted_must_root)]
+    pub fn set_up(
+        stream: &WritableStream,
+        underlying_sink: UnderlyingSink,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let global = stream.global();
+        let cx = global.get_cx();
+        // Steps 2-13.
+        let controller = reflect_dom_object(
+            Box::new(WritableStreamDefaultController::new_inherited(
+                stream,
+                underlying_sink,
+                strategy_hwm,
+                strategy_size,
+            )),
+            &global,
+            WritableStreamDefaultControllerBinding::Wrap,
+        );
+        stream.set_controller(&controller);
+        // Steps 14-15.
+        stream.update_backpressure(controller.get_backpressure());
+        // Steps 16-17.
+        rooted!(in(*cx) let mut start_result = UndefinedValue());
+        controller.start_algorithm(start_result.handle_mut())?;
+        let start_promise = promise_resolved_with(&global, start_result.handle());
+        upon_settlement(
+            &start_promise,
+            &*controller,
+            WritableStreamDefaultControllerStep::Started,
+        );
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller-from-underlying-sink>
+    pub fn set_up_from_underlying_sink(
+        stream: &WritableStream,
+        underlying_sink: HandleObject,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let cx = stream.global().get_cx();
+        check_underlying_methods(cx, underlying_sink, &["start", "write", "close", "abort"])?;
+        WritableStreamDefaultController::set_up(
+            stream,
+            UnderlyingSink::Js(Heap::boxed(underlying_sink.get())),
+            strategy_hwm,
+            strategy_size,
+        )
+    }
+
+    pub fn started(&self) -> bool {
+        self.started.get()
+    }
+
+    fn sink(&self, mut object: MutableHandleObject) -> Option<SinkRoot> {
+        match *self.underlying_sink.borrow() {
+            Some(UnderlyingSink::Js(ref sink)) => {
+                object.set(sink.get());
+                Some(SinkRoot::Js)
+            },
+            Some(UnderlyingSink::Transform(ref stream)) => {
+                Some(SinkRoot::Transform(DomRoot::from_ref(&**stream)))
+            },
+            None => None,
+        }
+    }
+
+    fn start_algorithm(&self, mut rval: MutableHandleValue) -> Fallible<()> {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.sink(object.handle_mut()) {
+            Some(SinkRoot::Js) => {
+                rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+                call_underlying_method(cx, object.handle(), "start", &[this.get()], rval)
+            },
+            Some(SinkRoot::Transform(stream)) => {
+                let start_promise = stream.start_promise();
+                rval.set(ObjectValue(start_promise.reflector().get_jsobject().get()));
+                Ok(())
+            },
+            None => {
+                rval.set(UndefinedValue());
+                Ok(())
+            },
+        }
+    }
+
+    fn write_algorithm(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.sink(object.handle_mut()) {
+            Some(SinkRoot::Js) => {
+                rooted!(in(*cx) let this = ObjectValue(self.reflector().get_jsobject().get()));
+                promise_call(
+                    &global,
+                    object.handle(),
+                    "write",
+                    &[chunk.get(), this.get()],
+                )
+            },
+            Some(SinkRoot::Transform(stream)) => stream.sink_write(chunk),
+            None => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+        }
+    }
+
+    fn close_algorithm(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.sink(object.handle_mut()) {
+            Some(SinkRoot::Js) => promise_call(&global, object.handle(), "close", &[]),
+            Some(SinkRoot::Transform(stream)) => stream.sink_close(),
+            None => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+        }
+    }
+
+    fn abort_algorithm(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut object = ptr::null_mut::<JSObject>());
+        match self.sink(object.handle_mut()) {
+            Some(SinkRoot::Js) => promise_call(&global, object.handle(), "abort", &[reason.get()]),
+            Some(SinkRoot::Transform(stream)) => stream.sink_abort(reason),
+            None => {
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                promise_resolved_with(&global, undefined.handle())
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.underlying_sink.borrow_mut() = None;
+        *self.strategy_size.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-abort>
+    pub fn abort_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let result = self.abort_algorithm(reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-error>
+    pub fn error_steps(&self) {
+        self.queue.borrow_mut().reset();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-close>
+    pub fn close(&self) {
+        self.queue.borrow_mut().enqueue_close_sentinel();
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-chunk-size>
+    pub fn get_chunk_size(&self, chunk: HandleValue) -> f64 {
+        let size = self.strategy_size.borrow().clone();
+        match chunk_size(size.as_ref().map(|size| &**size), chunk) {
+            Ok(size) => size,
+            Err(error) => {
+                let global = self.global();
+                let cx = global.get_cx();
+                rooted!(in(*cx) let mut error_value = UndefinedValue());
+                error_to_jsval(&global, error, error_value.handle_mut());
+                self.error_if_needed(error_value.handle());
+                1.
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-desired-size>
+    pub fn desired_size(&self) -> f64 {
+        self.strategy_hwm - self.queue.borrow().total_size()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-write>
+    pub fn write(&self, chunk: HandleValue, chunk_size: f64) {
+        // Steps 1-2.
+        let result = self.queue.borrow_mut().enqueue_value(chunk, chunk_size);
+        if let Err(error) = result {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error_value = UndefinedValue());
+            error_to_jsval(&global, error, error_value.handle_mut());
+            self.error_if_needed(error_value.handle());
+            return;
+        }
+        // Steps 3-4.
+        if !self.stream.close_queued_or_in_flight() && self.stream.is_writable() {
+            self.stream.update_backpressure(self.get_backpressure());
+        }
+        // Step 5.
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-advance-queue-if-needed>
+    fn advance_queue_if_needed(&self) {
+        // Steps 2-3.
+        if !self.started.get() || self.stream.has_in_flight_write_request() {
+            return;
+        }
+        // Step 5.
+        if self.stream.is_erroring() {
+            self.stream.finish_erroring();
+            return;
+        }
+        if !self.stream.is_writable() {
+            return;
+        }
+        // Steps 6-9.
+        if self.queue.borrow().is_empty() {
+            return;
+        }
+        if self.queue.borrow().peek_is_close_sentinel() {
+            self.process_close();
+        } else {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let mut chunk = UndefinedValue());
+            self.queue.borrow().peek_value(chunk.handle_mut());
+            self.process_write(chunk.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error-if-needed>
+    pub fn error_if_needed(&self, error: HandleValue) {
+        if self.stream.is_writable() {
+            self.error(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-close>
+    fn process_close(&self) {
+        let cx = self.global().get_cx();
+        self.stream.mark_close_request_in_flight();
+        rooted!(in(*cx) let mut sentinel = UndefinedValue());
+        self.queue.borrow_mut().dequeue_value(sentinel.handle_mut());
+        let sink_close_promise = self.close_algorithm();
+        self.clear_algorithms();
+        upon_settlement(
+            &sink_close_promise,
+            self,
+            WritableStreamDefaultControllerStep::Closed,
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-write>
+    fn process_write(&self, chunk: HandleValue) {
+        self.stream.mark_first_write_request_in_flight();
+        let sink_write_promise = self.write_algorithm(chunk);
+        upon_settlement(
+            &sink_write_promise,
+            self,
+            WritableStreamDefaultControllerStep::Written,
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-backpressure>
+    fn get_backpressure(&self) -> bool {
+        self.desired_size() <= 0.
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error>
+    fn error(&self, error: HandleValue) {
+        self.clear_algorithms();
+        self.stream.start_erroring(error);
+    }
+}
+
+impl PromiseReaction for WritableStreamDefaultController {
+    type Step = WritableStreamDefaultControllerStep;
+
+    fn settled(
+        &self,
+        step: &WritableStreamDefaultControllerStep,
+        result: Result<HandleValue, HandleValue>,
+    ) {
+        match (step, result) {
+            (WritableStreamDefaultControllerStep::Started, Ok(_)) => {
+                self.started.set(true);
+                self.advance_queue_if_needed();
+            },
+            (WritableStreamDefaultControllerStep::Started, Err(reason)) => {
+                self.started.set(true);
+                self.stream.deal_with_rejection(reason);
+            },
+            (WritableStreamDefaultControllerStep::Written, Ok(_)) => {
+                self.stream.finish_in_flight_write();
+                let cx = self.global().get_cx();
+                rooted!(in(*cx) let mut chunk = UndefinedValue());
+                self.queue.borrow_mut().dequeue_value(chunk.handle_mut());
+                if !self.stream.close_queued_or_in_flight() && self.stream.is_writable() {
+                    self.stream.update_backpressure(self.get_backpressure());
+                }
+                self.advance_queue_if_needed();
+            },
+            (WritableStreamDefaultControllerStep::Written, Err(reason)) => {
+                if self.stream.is_writable() {
+                    self.clear_algorithms();
+                }
+                self.stream.finish_in_flight_write_with_error(reason);
+            },
+            (WritableStreamDefaultControllerStep::Closed, Ok(_)) => {
+                self.stream.finish_in_flight_close();
+            },
+            (WritableStreamDefaultControllerStep::Closed, Err(reason)) => {
+                self.stream.finish_in_flight_close_with_error(reason);
+            },
+        }
+    }
+}
+
+impl WritableStreamDefaultControllerMethods for WritableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ws-default-controller-error
+    fn Error(&self, _cx: JSContext, e: HandleValue) {
+        if self.stream.is_writable() {
+            self.error(e);
+        }
+    }
+}
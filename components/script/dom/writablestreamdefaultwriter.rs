@@ -0,0 +1,303 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding::WritableStreamDefaultWriterMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use crate::streams::promise_resolved_with;
+use crate::streams::{error_to_jsval, promise_rejected_with, promise_rejected_with_error};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::rc::Rc;
+
+// https://streams.spec.whatwg.org/#default-writer-class
+#[dom_struct]
+pub struct WritableStreamDefaultWriter {
+    reflector_: Reflector,
+    stream: MutNullableDom<WritableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+}
+
+impl WritableStreamDefaultWriter {
+    fn new_inherited(
+        ready_promise: Rc<Promise>,
+        closed_promise: Rc<Promise>,
+    ) -> WritableStreamDefaultWriter {
+        WritableStreamDefaultWriter {
+            reflector_: Reflector::new(),
+            stream: MutNullableDom::new(None),
+            ready_promise: DomRefCell::new(ready_promise),
+            closed_promise: DomRefCell::new(closed_promise),
+        }
+    }
+
+    /// Lock `stream` to a new writer, as in
+    /// <https://streams.spec.whatwg.org/#default-writer-constructor>.
+    pub fn new(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        let cx = global.get_cx();
+        // Step 2.
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+        // Steps 3-9.
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        stream.stored_error(stored_error.handle_mut());
+        let (ready_promise, closed_promise) = if stream.is_writable() {
+            let ready_promise = if !stream.close_queued_or_in_flight() && stream.backpressure() {
+                Promise::new(global)
+            } else {
+                promise_resolved_with(global, undefined.handle())
+            };
+            (ready_promise, Promise::new(global))
+        } else if stream.is_erroring() {
+            (
+                promise_rejected_with(global, stored_error.handle()),
+                Promise::new(global),
+            )
+        } else if stream.is_closed() {
+            (
+                promise_resolved_with(global, undefined.handle()),
+                promise_resolved_with(global, undefined.handle()),
+            )
+        } else {
+            (
+                promise_rejected_with(global, stored_error.handle()),
+                promise_rejected_with(global, stored_error.handle()),
+            )
+        };
+        let writer = reflect_dom_object(
+            Box::new(WritableStreamDefaultWriter::new_inherited(
+                ready_promise,
+                closed_promise,
+            )),
+            global,
+            WritableStreamDefaultWriterBinding::Wrap,
+        );
+        writer.stream.set(Some(stream));
+        stream.set_writer(Some(&writer));
+        Ok(writer)
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(global, stream)
+    }
+
+    pub fn resolve_ready(&self) {
+        self.ready_promise.borrow().resolve_native(&());
+    }
+
+    /// Replace the ready promise with a pending one, when backpressure applies.
+    pub fn reset_ready_promise(&self) {
+        *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+    }
+
+    pub fn resolve_closed(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed(&self, error: HandleValue) {
+        self.closed_promise.borrow().reject_native(&error.get());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-ready-promise-rejected>
+    pub fn ensure_ready_promise_rejected(&self, error: HandleValue) {
+        ensure_promise_rejected(&self.global(), &self.ready_promise, error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-closed-promise-rejected>
+    fn ensure_closed_promise_rejected(&self, error: HandleValue) {
+        ensure_promise_rejected(&self.global(), &self.closed_promise, error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-get-desired-size>
+    fn desired_size(&self, stream: &WritableStream) -> Option<f64> {
+        if stream.is_errored() || stream.is_erroring() {
+            return None;
+        }
+        if stream.is_closed() {
+            return Some(0.);
+        }
+        Some(stream.controller().desired_size())
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-close-with-error-propagation>
+    pub fn close_with_error_propagation(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The writer was released".to_owned()),
+                );
+            },
+        };
+        if stream.close_queued_or_in_flight() || stream.is_closed() {
+            rooted!(in(*cx) let undefined = UndefinedValue());
+            return promise_resolved_with(&global, undefined.handle());
+        }
+        if stream.is_errored() {
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            stream.stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+        stream.close()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-release>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut release_error = UndefinedValue());
+        error_to_jsval(
+            &global,
+            Error::Type("The writer was released".to_owned()),
+            release_error.handle_mut(),
+        );
+        self.ensure_ready_promise_rejected(release_error.handle());
+        self.ensure_closed_promise_rejected(release_error.handle());
+        stream.set_writer(None);
+        self.stream.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-write>
+    pub fn write(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The writer was released".to_owned()),
+                );
+            },
+        };
+        // Steps 3-4.
+        let controller = stream.controller();
+        let chunk_size = controller.get_chunk_size(chunk);
+        // Step 5.
+        if self.stream.get().map_or(true, |current| current != stream) {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The writer was released".to_owned()),
+            );
+        }
+        // Steps 6-9.
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        if stream.is_errored() || stream.is_erroring() {
+            stream.stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+        if stream.close_queued_or_in_flight() || stream.is_closed() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The stream is closing".to_owned()),
+            );
+        }
+        // Steps 10-12.
+        let promise = stream.add_write_request();
+        controller.write(chunk, chunk_size);
+        promise
+    }
+}
+
+/// Reject `promise` with `error` if it is pending, or replace it with a
+/// promise rejected with `error` otherwise.
+fn ensure_promise_rejected(
+    global: &GlobalScope,
+    promise: &DomRefCell<Rc<Promise>>,
+    error: HandleValue,
+) {
+    if !promise.borrow().is_fulfilled() {
+        promise.borrow().reject_native(&error.get());
+    } else {
+        *promise.borrow_mut() = promise_rejected_with(global, error);
+    }
+}
+
+impl WritableStreamDefaultWriterMethods for WritableStreamDefaultWriter {
+    // https://streams.spec.whatwg.org/#default-writer-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-desired-size
+    fn GetDesiredSize(&self) -> Fallible<Option<f64>> {
+        match self.stream.get() {
+            Some(stream) => Ok(self.desired_size(&stream)),
+            None => Err(Error::Type("The writer was released".to_owned())),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-abort
+    fn Abort(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.abort(reason),
+            None => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The writer was released".to_owned()),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-close
+    fn Close(&self) -> Rc<Promise> {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &self.global(),
+                    Error::Type("The writer was released".to_owned()),
+                );
+            },
+        };
+        if stream.close_queued_or_in_flight() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is already closing".to_owned()),
+            );
+        }
+        stream.close()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-write
+    fn Write(&self, _cx: JSContext, chunk: HandleValue) -> Rc<Promise> {
+        self.write(chunk)
+    }
+}
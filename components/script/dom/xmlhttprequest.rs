@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::document_loader::DocumentLoader;
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::BlobBinding::BlobBinding::BlobMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
@@ -59,6 +60,7 @@ use js::jsval::{JSVal, NullValue, UndefinedValue};
 use js::rust::wrappers::JS_ParseJSON;
 use js::typedarray::{ArrayBuffer, CreateWith};
 use mime::{self, Mime, Name};
+use net_traits::content_security_policy::Violation;
 use net_traits::request::{CredentialsMode, Destination, Referrer, RequestBuilder, RequestMode};
 use net_traits::trim_http_whitespace;
 use net_traits::CoreResourceMsg::Fetch;
@@ -166,6 +168,9 @@ pub struct XMLHttpRequest {
     referrer_url: Option<ServoUrl>,
     referrer_policy: Option<ReferrerPolicy>,
     canceller: DomRefCell<FetchCanceller>,
+    signal: MutNullableDom<AbortSignal>,
+    /// The signal the ongoing request was sent with, whose abort aborts it.
+    request_signal: MutNullableDom<AbortSignal>,
 }
 
 impl XMLHttpRequest {
@@ -212,6 +217,8 @@ impl XMLHttpRequest {
             referrer_url: referrer_url,
             referrer_policy: referrer_policy,
             canceller: DomRefCell::new(Default::default()),
+            signal: Default::default(),
+            request_signal: Default::default(),
         }
     }
     pub fn new(global: &GlobalScope) -> DomRoot<XMLHttpRequest> {
@@ -270,6 +277,11 @@ impl XMLHttpRequest {
                 *self.sync_status.borrow_mut() = Some(rv);
             }
 
+            fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+                let global = &self.resource_timing_global();
+                global.report_csp_violations(violations);
+            }
+
             fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
                 &mut self.resource_timing
             }
@@ -553,6 +565,7 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
     }
 
     // https://xhr.spec.whatwg.org/#the-send()-method
+    #[allow(unrooted_must_root)]
     fn Send(&self, data: Option<DocumentOrBodyInit>) -> ErrorResult {
         // Step 1, 2
         if self.ready_state.get() != XMLHttpRequestState::Opened || self.send_flag.get() {
@@ -604,6 +617,20 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
         // Step 8
         self.send_flag.set(true);
 
+        // Not part of the XHR standard: the request is aborted along with its signal.
+        if let Some(signal) = self.signal.get() {
+            if signal.aborted() {
+                self.Abort();
+                return if self.sync.get() {
+                    Err(Error::Abort)
+                } else {
+                    Ok(())
+                };
+            }
+            signal.add_algorithm(AbortAlgorithm::XMLHttpRequest(Dom::from_ref(self)));
+            self.request_signal.set(Some(&signal));
+        }
+
         // Step 9
         if !self.sync.get() {
             // If one of the event handlers below aborts the fetch by calling
@@ -658,7 +685,9 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
                     .map(|referrer_url| Referrer::ReferrerUrl(referrer_url)),
             )
             .referrer_policy(self.referrer_policy.clone())
-            .pipeline_id(Some(self.global().pipeline_id()));
+            .pipeline_id(Some(self.global().pipeline_id()))
+            .csp_list(self.global().get_csp_list())
+            .site_for_cookies(Some(self.global().site_for_cookies()));
 
         // step 4 (second half)
         match extracted_or_serialized {
@@ -759,6 +788,16 @@ impl XMLHttpRequestMethods for XMLHttpRequest {
         self.ready_state.set(XMLHttpRequestState::Unsent);
     }
 
+    // Not part of the XHR standard, see XMLHttpRequest.webidl.
+    fn GetSignal(&self) -> Option<DomRoot<AbortSignal>> {
+        self.signal.get()
+    }
+
+    // Not part of the XHR standard, see XMLHttpRequest.webidl.
+    fn SetSignal(&self, signal: Option<&AbortSignal>) {
+        self.signal.set(signal);
+    }
+
     // https://xhr.spec.whatwg.org/#the-responseurl-attribute
     fn ResponseURL(&self) -> USVString {
         USVString(self.response_url.borrow().clone())
@@ -1128,6 +1167,7 @@ impl XMLHttpRequest {
 
                 self.cancel_timeout();
                 self.canceller.borrow_mut().ignore();
+                self.forget_request_signal();
 
                 // Part of step 11, send() (processing response end of file)
                 // XXXManishearth handle errors, if any (substep 2)
@@ -1145,6 +1185,7 @@ impl XMLHttpRequest {
             XHRProgress::Errored(_, e) => {
                 self.cancel_timeout();
                 self.canceller.borrow_mut().ignore();
+                self.forget_request_signal();
 
                 self.discard_subsequent_responses();
                 self.send_flag.set(false);
@@ -1175,11 +1216,19 @@ impl XMLHttpRequest {
 
     fn terminate_ongoing_fetch(&self) {
         self.canceller.borrow_mut().cancel();
+        self.forget_request_signal();
         let GenerationId(prev_id) = self.generation_id.get();
         self.generation_id.set(GenerationId(prev_id + 1));
         self.response_status.set(Ok(()));
     }
 
+    /// Stop aborting the request once the signal it was sent with is aborted.
+    fn forget_request_signal(&self) {
+        if let Some(signal) = self.request_signal.take() {
+            signal.remove_xhr_algorithm(self);
+        }
+    }
+
     fn dispatch_progress_event(&self, upload: bool, type_: Atom, loaded: u64, total: Option<u64>) {
         let (total_length, length_computable) = if self
             .response_headers
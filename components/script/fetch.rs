@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::compartments::{enter_realm, InCompartment};
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInfo;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use crate::dom::bindings::codegen::Bindings::ResponseBinding::ResponseBinding::ResponseMethods;
@@ -11,7 +12,7 @@ use crate::dom::bindings::error::Error;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::Guard;
@@ -26,6 +27,7 @@ use crate::network_listener::{
 use crate::task_source::TaskSourceName;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
+use net_traits::content_security_policy::Violation;
 use net_traits::request::RequestBuilder;
 use net_traits::request::{Request as NetTraitsRequest, ServiceWorkersMode};
 use net_traits::CoreResourceMsg::Fetch as NetTraitsFetch;
@@ -34,14 +36,14 @@ use net_traits::{FetchChannels, FetchResponseListener, NetworkError};
 use net_traits::{FetchMetadata, FilteredMetadata, Metadata};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use servo_url::ServoUrl;
-use std::mem;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 struct FetchContext {
     fetch_promise: Option<TrustedPromise>,
     response_object: Trusted<Response>,
-    body: Vec<u8>,
+    /// The signal whose abort aborts the fetch, until it is finished.
+    signal: Trusted<AbortSignal>,
     resource_timing: ResourceFetchTiming,
 }
 
@@ -98,6 +100,7 @@ impl Drop for FetchCanceller {
 }
 
 fn request_init_from_request(request: NetTraitsRequest) -> RequestBuilder {
+    let global = GlobalScope::current().expect("No current global object");
     RequestBuilder {
         method: request.method.clone(),
         url: request.url(),
@@ -112,11 +115,7 @@ fn request_init_from_request(request: NetTraitsRequest) -> RequestBuilder {
         use_cors_preflight: request.use_cors_preflight,
         credentials_mode: request.credentials_mode,
         use_url_credentials: request.use_url_credentials,
-        origin: GlobalScope::current()
-            .expect("No current global object")
-            .origin()
-            .immutable()
-            .clone(),
+        origin: global.origin().immutable().clone(),
         referrer: Some(request.referrer.clone()),
         referrer_policy: request.referrer_policy,
         pipeline_id: request.pipeline_id,
@@ -124,6 +123,9 @@ fn request_init_from_request(request: NetTraitsRequest) -> RequestBuilder {
         integrity_metadata: "".to_owned(),
         url_list: vec![],
         parser_metadata: request.parser_metadata,
+        csp_list: global.get_csp_list(),
+        site_for_cookies: Some(global.site_for_cookies()),
+        top_level_navigation: false,
     }
 }
 
@@ -142,13 +144,22 @@ pub fn Fetch(
     let response = Response::new(global);
 
     // Step 2
-    let request = match Request::Constructor(global, input, init) {
+    let request_object = match Request::Constructor(global, input, init) {
         Err(e) => {
             promise.reject_error(e);
             return promise;
         },
-        Ok(r) => r.get_request(),
+        Ok(r) => r,
     };
+    let request = request_object.get_request();
+
+    // An already aborted signal rejects the fetch before it starts.
+    let signal = request_object.signal();
+    if signal.aborted() {
+        promise.reject_error(Error::Abort);
+        return promise;
+    }
+
     let timing_type = request.timing_type();
 
     let mut request_init = request_init_from_request(request);
@@ -161,12 +172,20 @@ pub fn Fetch(
     // Step 4
     response.Headers().set_guard(Guard::Immutable);
 
+    // Aborting the signal cancels the fetch in the resource thread.
+    let (cancel_sender, cancel_receiver) = ipc::channel().unwrap();
+    signal.add_algorithm(AbortAlgorithm::Fetch(
+        cancel_sender,
+        promise.clone(),
+        Dom::from_ref(&*response),
+    ));
+
     // Step 5
     let (action_sender, action_receiver) = ipc::channel().unwrap();
     let fetch_context = Arc::new(Mutex::new(FetchContext {
         fetch_promise: Some(TrustedPromise::new(promise.clone())),
         response_object: Trusted::new(&*response),
-        body: vec![],
+        signal: Trusted::new(&*signal),
         resource_timing: ResourceFetchTiming::new(timing_type),
     }));
     let listener = NetworkListener {
@@ -184,7 +203,7 @@ pub fn Fetch(
     core_resource_thread
         .send(NetTraitsFetch(
             request_init,
-            FetchChannels::ResponseMsg(action_sender, None),
+            FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver)),
         ))
         .unwrap();
 
@@ -216,7 +235,9 @@ impl FetchResponseListener for FetchContext {
             Err(_) => {
                 promise.reject_error(Error::Type("Network error occurred".to_string()));
                 self.fetch_promise = Some(TrustedPromise::new(promise));
-                self.response_object.root().set_type(DOMResponseType::Error);
+                let response = self.response_object.root();
+                response.set_type(DOMResponseType::Error);
+                self.signal.root().remove_fetch_algorithm(&response);
                 return;
             },
             // Step 4.2
@@ -247,23 +268,36 @@ impl FetchResponseListener for FetchContext {
                 },
             },
         }
+        self.response_object.root().start_receiving_body();
         // Step 4.3
         promise.resolve_native(&self.response_object.root());
         self.fetch_promise = Some(TrustedPromise::new(promise));
     }
 
-    fn process_response_chunk(&mut self, mut chunk: Vec<u8>) {
-        self.body.append(&mut chunk);
+    fn process_response_chunk(&mut self, chunk: Vec<u8>) {
+        let response = self.response_object.root();
+        let _ac = enter_realm(&*response);
+        response.stream_chunk(chunk);
     }
 
-    fn process_response_eof(&mut self, _response: Result<ResourceFetchTiming, NetworkError>) {
+    fn process_response_eof(&mut self, result: Result<ResourceFetchTiming, NetworkError>) {
         let response = self.response_object.root();
         let _ac = enter_realm(&*response);
-        response.finish(mem::replace(&mut self.body, vec![]));
+        match result {
+            Ok(_) => response.finish(),
+            Err(_) => response.fail(),
+        }
+        // Aborting the signal has no effect on a finished fetch.
+        self.signal.root().remove_fetch_algorithm(&response);
         // TODO
         // ... trailerObject is not supported in Servo yet.
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -319,7 +353,9 @@ pub fn load_whole_resource(
     let mut metadata = None;
     loop {
         match action_receiver.recv().unwrap() {
-            FetchResponseMsg::ProcessRequestBody | FetchResponseMsg::ProcessRequestEOF => (),
+            FetchResponseMsg::ProcessRequestBody |
+            FetchResponseMsg::ProcessRequestEOF |
+            FetchResponseMsg::ProcessCspViolations(..) => (),
             FetchResponseMsg::ProcessResponse(Ok(m)) => {
                 metadata = Some(match m {
                     FetchMetadata::Unfiltered(m) => m,
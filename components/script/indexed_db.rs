@@ -0,0 +1,384 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conversions between JavaScript values and the keys and key paths of IndexedDB.
+
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::conversions::{get_property_jsval, jsstring_to_str, root_from_object};
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::utils::set_dictionary_property;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbkeyrange::IDBKeyRange;
+use crate::script_runtime::JSContext as SafeJSContext;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::{ClippedTime, JSContext, JSObject, JS_NewPlainObject, NewDateObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::wrappers::{DateGetMsecSinceEpoch, JS_IsArrayObject, ObjectIsDate};
+use js::rust::{HandleValue, MutableHandleValue};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use net_traits::indexeddb_thread::{IndexMetadata, IndexedDBKeyRange, IndexedDBKeyType, KeyPath};
+use std::ptr;
+
+/// A key, converted to a JavaScript value as in
+/// <https://w3c.github.io/IndexedDB/#convert-a-key-to-a-value>.
+struct KeyValue<'a>(&'a IndexedDBKeyType);
+
+impl<'a> ToJSValConvertible for KeyValue<'a> {
+    #[allow(unsafe_code)]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        match *self.0 {
+            IndexedDBKeyType::Number(number) => number.to_jsval(cx, rval),
+            IndexedDBKeyType::String(ref string) => string.to_jsval(cx, rval),
+            IndexedDBKeyType::Date(time) => {
+                rooted!(in(cx) let date = NewDateObject(cx, ClippedTime { t: time }));
+                rval.set(ObjectValue(date.get()));
+            },
+            IndexedDBKeyType::Binary(ref bytes) => {
+                rooted!(in(cx) let mut buffer = ptr::null_mut::<JSObject>());
+                assert!(ArrayBuffer::create(
+                    cx,
+                    CreateWith::Slice(&bytes[..]),
+                    buffer.handle_mut()
+                )
+                .is_ok());
+                rval.set(ObjectValue(buffer.get()));
+            },
+            IndexedDBKeyType::Array(ref keys) => keys
+                .iter()
+                .map(KeyValue)
+                .collect::<Vec<_>>()
+                .to_jsval(cx, rval),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-key-to-a-value>
+#[allow(unsafe_code)]
+pub fn key_type_to_jsval(cx: SafeJSContext, key: &IndexedDBKeyType, rval: MutableHandleValue) {
+    unsafe { KeyValue(key).to_jsval(*cx, rval) }
+}
+
+/// Read the structured clones in `values` into a JavaScript array.
+#[allow(unsafe_code)]
+pub fn values_to_jsval(global: &GlobalScope, values: Vec<Vec<u8>>, mut rval: MutableHandleValue) {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut array_value = UndefinedValue());
+    unsafe { Vec::<JSVal>::new().to_jsval(*cx, array_value.handle_mut()) };
+    rooted!(in(*cx) let array = array_value.to_object());
+    for (index, value) in values.into_iter().enumerate() {
+        rooted!(in(*cx) let mut element = UndefinedValue());
+        StructuredCloneData::Vector(value).read(global, element.handle_mut());
+        let _ = set_dictionary_property(*cx, array.handle(), &index.to_string(), element.handle());
+    }
+    rval.set(array_value.get());
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key>
+pub fn convert_value_to_key(
+    cx: SafeJSContext,
+    input: HandleValue,
+) -> Result<IndexedDBKeyType, Error> {
+    convert_value_to_key_with_seen(cx, input, &mut vec![])
+}
+
+#[allow(unsafe_code)]
+fn convert_value_to_key_with_seen(
+    cx: SafeJSContext,
+    input: HandleValue,
+    seen: &mut Vec<*mut JSObject>,
+) -> Result<IndexedDBKeyType, Error> {
+    if input.is_number() {
+        let number = input.to_number();
+        if number.is_nan() {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyType::Number(number));
+    }
+    if input.is_string() {
+        let string = unsafe { jsstring_to_str(*cx, input.to_string()) };
+        return Ok(IndexedDBKeyType::String(string.into()));
+    }
+    if !input.is_object() {
+        return Err(Error::Data);
+    }
+
+    rooted!(in(*cx) let object = input.to_object());
+    if seen.contains(&object.get()) {
+        return Err(Error::Data);
+    }
+    unsafe {
+        let mut is_date = false;
+        if !ObjectIsDate(*cx, object.handle(), &mut is_date) {
+            return Err(Error::JSFailed);
+        }
+        if is_date {
+            let mut time = 0.;
+            if !DateGetMsecSinceEpoch(*cx, object.handle(), &mut time) {
+                return Err(Error::JSFailed);
+            }
+            if time.is_nan() {
+                return Err(Error::Data);
+            }
+            return Ok(IndexedDBKeyType::Date(time));
+        }
+
+        typedarray!(in(*cx) let buffer: ArrayBuffer = object.get());
+        if let Ok(buffer) = buffer {
+            return Ok(IndexedDBKeyType::Binary(buffer.to_vec()));
+        }
+        typedarray!(in(*cx) let view: ArrayBufferView = object.get());
+        if let Ok(view) = view {
+            return Ok(IndexedDBKeyType::Binary(view.to_vec()));
+        }
+
+        let mut is_array = false;
+        if !JS_IsArrayObject(*cx, input, &mut is_array) {
+            return Err(Error::JSFailed);
+        }
+        if !is_array {
+            return Err(Error::Data);
+        }
+        rooted!(in(*cx) let mut length = UndefinedValue());
+        get_property_jsval(*cx, object.handle(), "length", length.handle_mut())?;
+        seen.push(object.get());
+        let mut keys = vec![];
+        for index in 0..length.to_number() as u32 {
+            rooted!(in(*cx) let mut entry = UndefinedValue());
+            get_property_jsval(*cx, object.handle(), &index.to_string(), entry.handle_mut())?;
+            keys.push(convert_value_to_key_with_seen(cx, entry.handle(), seen)?);
+        }
+        seen.pop();
+        Ok(IndexedDBKeyType::Array(keys))
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key-range>
+#[allow(unsafe_code)]
+pub fn convert_value_to_key_range(
+    cx: SafeJSContext,
+    input: HandleValue,
+    null_disallowed: bool,
+) -> Result<IndexedDBKeyRange, Error> {
+    // Step 1.
+    if input.is_object() {
+        if let Ok(range) = root_from_object::<IDBKeyRange>(input.to_object(), *cx) {
+            return Ok(range.inner().clone());
+        }
+    }
+    // Step 2.
+    if input.is_undefined() || input.is_null() {
+        if null_disallowed {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyRange::unbounded());
+    }
+    // Steps 3-5.
+    Ok(IndexedDBKeyRange::only(convert_value_to_key(cx, input)?))
+}
+
+/// <https://w3c.github.io/IndexedDB/#valid-key-path>
+pub fn is_valid_key_path(key_path: &KeyPath) -> bool {
+    // Identifiers are restricted to ASCII letters, digits, `$` and `_`.
+    fn is_identifier(identifier: &str) -> bool {
+        let mut chars = identifier.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '$' || c == '_' => {},
+            _ => return false,
+        }
+        chars.all(|c| c.is_ascii_alphanumeric() || c == '$' || c == '_')
+    }
+    fn is_valid_string(key_path: &str) -> bool {
+        key_path.is_empty() || key_path.split('.').all(is_identifier)
+    }
+    match *key_path {
+        KeyPath::String(ref key_path) => is_valid_string(key_path),
+        KeyPath::Sequence(ref key_paths) => {
+            !key_paths.is_empty() && key_paths.iter().all(|key_path| is_valid_string(key_path))
+        },
+    }
+}
+
+pub fn key_path_from_union(key_path: &StringOrStringSequence) -> KeyPath {
+    match *key_path {
+        StringOrStringSequence::String(ref key_path) => KeyPath::String(key_path.to_string()),
+        StringOrStringSequence::StringSequence(ref key_paths) => KeyPath::Sequence(
+            key_paths
+                .iter()
+                .map(|key_path| key_path.to_string())
+                .collect(),
+        ),
+    }
+}
+
+#[allow(unsafe_code)]
+pub fn key_path_to_jsval(cx: SafeJSContext, key_path: &KeyPath, rval: MutableHandleValue) {
+    unsafe {
+        match *key_path {
+            KeyPath::String(ref key_path) => key_path.to_jsval(*cx, rval),
+            KeyPath::Sequence(ref key_paths) => key_paths.to_jsval(*cx, rval),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#evaluate-a-key-path-on-a-value>, for a key path that
+/// is a string. Returns whether the key path resolves to a value.
+#[allow(unsafe_code)]
+fn evaluate_key_path_on_value(
+    cx: SafeJSContext,
+    value: HandleValue,
+    key_path: &str,
+    mut rval: MutableHandleValue,
+) -> Result<bool, Error> {
+    rooted!(in(*cx) let mut current = value.get());
+    if !key_path.is_empty() {
+        for identifier in key_path.split('.') {
+            if current.is_string() && identifier == "length" {
+                let string = unsafe { jsstring_to_str(*cx, current.to_string()) };
+                let length = string.encode_utf16().count() as f64;
+                unsafe { length.to_jsval(*cx, current.handle_mut()) };
+                continue;
+            }
+            if !current.is_object() {
+                return Ok(false);
+            }
+            rooted!(in(*cx) let object = current.to_object());
+            unsafe { get_property_jsval(*cx, object.handle(), identifier, current.handle_mut())? };
+            if current.is_undefined() {
+                return Ok(false);
+            }
+        }
+    }
+    rval.set(current.get());
+    Ok(true)
+}
+
+/// <https://w3c.github.io/IndexedDB/#extract-a-key-from-a-value-using-a-key-path>
+/// Returns `Ok(None)` if the key path does not resolve to a value, and a `DataError` if
+/// it resolves to a value that is not a valid key.
+pub fn extract_key(
+    cx: SafeJSContext,
+    value: HandleValue,
+    key_path: &KeyPath,
+) -> Result<Option<IndexedDBKeyType>, Error> {
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    match *key_path {
+        KeyPath::String(ref key_path) => {
+            if !evaluate_key_path_on_value(cx, value, key_path, result.handle_mut())? {
+                return Ok(None);
+            }
+            convert_value_to_key(cx, result.handle()).map(Some)
+        },
+        KeyPath::Sequence(ref key_paths) => {
+            let mut keys = vec![];
+            for key_path in key_paths {
+                if !evaluate_key_path_on_value(cx, value, key_path, result.handle_mut())? {
+                    return Ok(None);
+                }
+                keys.push(convert_value_to_key(cx, result.handle())?);
+            }
+            Ok(Some(IndexedDBKeyType::Array(keys)))
+        },
+    }
+}
+
+/// The keys a value has in an index, as computed in step 5 of
+/// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>. A value whose
+/// key path does not resolve to a valid key is not in the index.
+#[allow(unsafe_code)]
+pub fn extract_index_keys(
+    cx: SafeJSContext,
+    value: HandleValue,
+    index: &IndexMetadata,
+) -> Vec<IndexedDBKeyType> {
+    let key_path = match index.key_path {
+        KeyPath::String(ref key_path) if index.multi_entry => key_path,
+        ref key_path => {
+            return extract_key(cx, value, key_path)
+                .ok()
+                .and_then(|key| key)
+                .into_iter()
+                .collect();
+        },
+    };
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    match evaluate_key_path_on_value(cx, value, key_path, result.handle_mut()) {
+        Ok(true) => {},
+        _ => return vec![],
+    }
+    let mut is_array = false;
+    if unsafe { !JS_IsArrayObject(*cx, result.handle(), &mut is_array) } || !is_array {
+        return convert_value_to_key(cx, result.handle())
+            .ok()
+            .into_iter()
+            .collect();
+    }
+    // https://w3c.github.io/IndexedDB/#convert-a-value-to-a-multientry-key
+    let mut keys: Vec<IndexedDBKeyType> = vec![];
+    if let Ok(IndexedDBKeyType::Array(entries)) = convert_value_to_key(cx, result.handle()) {
+        for key in entries {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+/// <https://w3c.github.io/IndexedDB/#check-that-a-key-could-be-injected-into-a-value>
+#[allow(unsafe_code)]
+pub fn could_inject_key(cx: SafeJSContext, value: HandleValue, key_path: &str) -> bool {
+    let mut identifiers: Vec<&str> = key_path.split('.').collect();
+    identifiers.pop();
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return false;
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        let result =
+            unsafe { get_property_jsval(*cx, object.handle(), identifier, current.handle_mut()) };
+        if result.is_err() {
+            return false;
+        }
+        if current.is_undefined() {
+            return true;
+        }
+    }
+    current.is_object()
+}
+
+/// <https://w3c.github.io/IndexedDB/#inject-a-key-into-a-value-using-a-key-path>
+#[allow(unsafe_code)]
+pub fn inject_key_into_value(
+    cx: SafeJSContext,
+    value: HandleValue,
+    key: &IndexedDBKeyType,
+    key_path: &str,
+) -> Result<(), Error> {
+    let mut identifiers: Vec<&str> = key_path.split('.').collect();
+    let last = identifiers.pop().ok_or(Error::Data)?;
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return Err(Error::Data);
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        unsafe { get_property_jsval(*cx, object.handle(), identifier, current.handle_mut())? };
+        if current.is_undefined() {
+            rooted!(in(*cx) let child = unsafe { JS_NewPlainObject(*cx) });
+            current.set(ObjectValue(child.get()));
+            set_dictionary_property(*cx, object.handle(), identifier, current.handle())
+                .map_err(|_| Error::JSFailed)?;
+        }
+    }
+    if !current.is_object() {
+        return Err(Error::Data);
+    }
+    rooted!(in(*cx) let object = current.to_object());
+    rooted!(in(*cx) let mut key_value = UndefinedValue());
+    key_type_to_jsval(cx, key, key_value.handle_mut());
+    set_dictionary_property(*cx, object.handle(), last, key_value.handle())
+        .map_err(|_| Error::JSFailed)
+}
@@ -58,6 +58,7 @@ mod dom;
 mod compartments;
 pub mod fetch;
 mod image_listener;
+mod indexed_db;
 mod layout_image;
 mod mem;
 mod microtask;
@@ -65,8 +66,10 @@ mod network_listener;
 pub mod script_runtime;
 #[allow(unsafe_code)]
 pub mod script_thread;
+mod security_manager;
 mod serviceworker_manager;
 mod serviceworkerjob;
+mod streams;
 mod stylesheet_loader;
 mod stylesheet_set;
 mod task_manager;
@@ -4,6 +4,7 @@
 
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::performanceentry::PerformanceEntry;
 use crate::dom::performanceresourcetiming::{InitiatorType, PerformanceResourceTiming};
@@ -58,8 +59,12 @@ pub fn submit_timing_data(
     initiator_type: InitiatorType,
     resource_timing: &ResourceFetchTiming,
 ) {
+    let next_hop = resource_timing
+        .next_hop_protocol
+        .clone()
+        .map(DOMString::from);
     let performance_entry =
-        PerformanceResourceTiming::new(global, url, initiator_type, None, resource_timing);
+        PerformanceResourceTiming::new(global, url, initiator_type, next_hop, resource_timing);
     global
         .performance()
         .queue_entry(performance_entry.upcast::<PerformanceEntry>(), true);
@@ -36,6 +36,7 @@ use js::jsapi::{
     JSJitCompilerOption, JS_SetOffthreadIonCompilationEnabled, JS_SetParallelParsingEnabled,
 };
 use js::jsapi::{JSObject, PromiseRejectionHandlingState, SetPreserveWrapperCallback};
+use js::jsapi::{JSSecurityCallbacks, JS_SetSecurityCallbacks};
 use js::jsapi::{SetJobQueue, SetProcessBuildIdOp, SetPromiseRejectionTrackerCallback};
 use js::panic::wrap_panic;
 use js::rust::wrappers::{GetPromiseIsHandled, GetPromiseResult};
@@ -150,6 +151,23 @@ unsafe extern "C" fn get_incumbent_global(_: *const c_void, _: *mut RawJSContext
     )
 }
 
+#[allow(unsafe_code)]
+/// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+unsafe extern "C" fn content_security_policy_allows(cx: *mut RawJSContext) -> bool {
+    wrap_panic(
+        AssertUnwindSafe(|| {
+            let global = GlobalScope::from_context(cx);
+            global.is_js_evaluation_allowed()
+        }),
+        false,
+    )
+}
+
+static SECURITY_CALLBACKS: JSSecurityCallbacks = JSSecurityCallbacks {
+    contentSecurityPolicyAllows: Some(content_security_policy_allows),
+    subsumes: None,
+};
+
 #[allow(unsafe_code)]
 unsafe extern "C" fn empty(extra: *const c_void) -> bool {
     wrap_panic(
@@ -405,6 +423,7 @@ unsafe fn new_rt_and_cx_with_parent(parent: Option<ParentRuntime>) -> Runtime {
     }
     SetDOMCallbacks(cx, &DOM_CALLBACKS);
     SetPreserveWrapperCallback(cx, Some(empty_wrapper_callback));
+    JS_SetSecurityCallbacks(cx, &SECURITY_CALLBACKS);
     // Pre barriers aren't working correctly at the moment
     DisableIncrementalGC(cx);
 
@@ -38,7 +38,6 @@ use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::ThreadLocalStackRoots;
 use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootCollection};
 use crate::dom::bindings::str::DOMString;
-use crate::dom::bindings::structuredclone::StructuredCloneData;
 use crate::dom::bindings::trace::JSTraceable;
 use crate::dom::bindings::utils::WRAP_CALLBACKS;
 use crate::dom::customelementregistry::{
@@ -72,6 +71,7 @@ use crate::fetch::FetchCanceller;
 use crate::microtask::{Microtask, MicrotaskQueue};
 use crate::script_runtime::{get_reports, new_rt_and_cx, JSContext, Runtime, ScriptPort};
 use crate::script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
+use crate::security_manager::parse_report_to_headers;
 use crate::serviceworkerjob::{Job, JobQueue};
 use crate::task_manager::TaskManager;
 use crate::task_queue::{QueuedTask, QueuedTaskConversion, TaskQueue};
@@ -81,6 +81,7 @@ use crate::task_source::history_traversal::HistoryTraversalTaskSource;
 use crate::task_source::media_element::MediaElementTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::user_interaction::UserInteractionTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
@@ -115,6 +116,7 @@ use msg::constellation_msg::{
 use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
 use msg::constellation_msg::{HangAnnotation, MonitoredComponentId, MonitoredComponentType};
 use msg::constellation_msg::{PipelineNamespace, TopLevelBrowsingContextId};
+use net_traits::content_security_policy::{CspList, InlineCheckType};
 use net_traits::image_cache::{ImageCache, PendingImageResponse};
 use net_traits::request::{CredentialsMode, Destination, RedirectMode, RequestBuilder};
 use net_traits::storage_thread::StorageType;
@@ -128,8 +130,8 @@ use profile_traits::time::{self as profile_time, profile, ProfilerCategory};
 use script_layout_interface::message::{self, LayoutThreadInit, Msg, ReflowGoal};
 use script_traits::webdriver_msg::WebDriverScriptCommand;
 use script_traits::CompositorEvent::{
-    CompositionEvent, KeyboardEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent, TouchEvent,
-    WheelEvent,
+    CompositionEvent, DropEvent, KeyboardEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent,
+    TouchEvent, WheelEvent,
 };
 use script_traits::{CompositorEvent, ConstellationControlMsg};
 use script_traits::{
@@ -138,7 +140,8 @@ use script_traits::{
 use script_traits::{InitialScriptState, JsEvalResult, LayoutMsg, LoadData, LoadOrigin};
 use script_traits::{MouseButton, MouseEventType, NewLayoutInfo};
 use script_traits::{Painter, ProgressiveWebMetricType, ScriptMsg, ScriptThreadFactory};
-use script_traits::{ScriptToConstellationChan, TimerEvent, TimerSchedulerMsg};
+use script_traits::{ScriptToConstellationChan, StructuredSerializedData};
+use script_traits::{TimerEvent, TimerSchedulerMsg};
 use script_traits::{TimerSource, TouchEventType, TouchId, UntrustedNodeAddress, WheelDelta};
 use script_traits::{UpdatePipelineIdReason, WindowSizeData, WindowSizeType};
 use servo_atoms::Atom;
@@ -215,6 +218,8 @@ struct InProgressLoad {
     canceller: FetchCanceller,
     /// Flag for sharing with the layout thread that is not yet created.
     layout_is_busy: Arc<AtomicBool>,
+    /// The site for cookies of the document that initiated this load, if any.
+    site_for_cookies: Option<ServoUrl>,
 }
 
 impl InProgressLoad {
@@ -252,6 +257,7 @@ impl InProgressLoad {
             navigation_start_precise: navigation_start_precise,
             canceller: Default::default(),
             layout_is_busy: layout_is_busy,
+            site_for_cookies: None,
         }
     }
 }
@@ -566,6 +572,8 @@ pub struct ScriptThread {
 
     performance_timeline_task_sender: Box<dyn ScriptChan>,
 
+    port_message_sender: Box<dyn ScriptChan>,
+
     remote_event_task_sender: Box<dyn ScriptChan>,
 
     /// A channel to hand out to threads that need to respond to a message from the script thread.
@@ -969,9 +977,15 @@ impl ScriptThread {
                 let sender = script_thread.script_sender.clone();
                 let task = task!(navigate_javascript: move || {
                     // Important re security. See https://github.com/servo/servo/issues/23373
-                    // TODO: check according to https://w3c.github.io/webappsec-csp/#should-block-navigation-request
                     if let Some(window) = trusted_global.root().downcast::<Window>() {
-                        if ScriptThread::check_load_origin(&load_data.load_origin, &window.get_url().origin()) {
+                        // https://w3c.github.io/webappsec-csp/#should-block-navigation-request
+                        let source = &load_data.url.as_str()["javascript:".len()..];
+                        let blocked_by_csp = window
+                            .upcast::<GlobalScope>()
+                            .should_inline_be_blocked(InlineCheckType::Navigation, None, source);
+                        if !blocked_by_csp &&
+                            ScriptThread::check_load_origin(&load_data.load_origin, &window.get_url().origin())
+                        {
                             ScriptThread::eval_js_url(&trusted_global.root(), &mut load_data);
                             sender
                                 .send((pipeline_id, ScriptMsg::LoadUrl(load_data, replace)))
@@ -1258,6 +1272,7 @@ impl ScriptThread {
             networking_task_sender: boxed_script_sender.clone(),
             file_reading_task_sender: boxed_script_sender.clone(),
             performance_timeline_task_sender: boxed_script_sender.clone(),
+            port_message_sender: boxed_script_sender.clone(),
             remote_event_task_sender: boxed_script_sender.clone(),
 
             history_traversal_task_sender: chan.clone(),
@@ -1554,6 +1569,25 @@ impl ScriptThread {
             }
         }
 
+        // Resize observer callbacks run script, which may add or remove documents,
+        // so the documents are not borrowed while they run.
+        let documents: Vec<DomRoot<Document>> = self
+            .documents
+            .borrow()
+            .iter()
+            .map(|(_, document)| document)
+            .collect();
+        for document in documents {
+            if !document.is_fully_active() {
+                continue;
+            }
+            // https://drafts.csswg.org/resize-observer/#html-event-loop
+            document.update_resize_observations();
+            // Step 14
+            // https://w3c.github.io/IntersectionObserver/#event-loop
+            document.update_intersection_observations();
+        }
+
         true
     }
 
@@ -2446,7 +2480,7 @@ impl ScriptThread {
         source_pipeline_id: PipelineId,
         source_browsing_context: TopLevelBrowsingContextId,
         origin: Option<ImmutableOrigin>,
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     ) {
         match { self.documents.borrow().find_window(pipeline_id) } {
             None => return warn!("postMessage after target pipeline {} closed.", pipeline_id),
@@ -2468,7 +2502,7 @@ impl ScriptThread {
                     Some(source) => source,
                 };
                 // FIXME(#22512): enqueues a task; unnecessary delay.
-                window.post_message(origin, &*source, StructuredCloneData::Vector(data))
+                window.post_message(origin, &*source, data)
             },
         }
     }
@@ -2689,6 +2723,10 @@ impl ScriptThread {
         PerformanceTimelineTaskSource(self.performance_timeline_task_sender.clone(), pipeline_id)
     }
 
+    pub fn port_message_queue(&self, pipeline_id: PipelineId) -> PortMessageQueue {
+        PortMessageQueue(self.port_message_sender.clone(), pipeline_id)
+    }
+
     pub fn history_traversal_task_source(
         &self,
         pipeline_id: PipelineId,
@@ -3104,6 +3142,7 @@ impl ScriptThread {
             self.networking_task_source(incomplete.pipeline_id),
             self.performance_timeline_task_source(incomplete.pipeline_id)
                 .clone(),
+            self.port_message_queue(incomplete.pipeline_id),
             self.user_interaction_task_source(incomplete.pipeline_id),
             self.remote_event_task_source(incomplete.pipeline_id),
             self.websocket_task_source(incomplete.pipeline_id),
@@ -3208,6 +3247,21 @@ impl ScriptThread {
             .and_then(|h| h.typed_get::<ReferrerPolicyHeader>())
             .map(ReferrerPolicy::from);
 
+        // The site for cookies of a nested document is the one of its parent, which is
+        // the URL of the top-level document.
+        if incomplete.parent_info.is_some() {
+            window
+                .upcast::<GlobalScope>()
+                .set_top_level_url(incomplete.site_for_cookies.clone());
+        }
+
+        // https://w3c.github.io/webappsec-csp/#initialize-document-csp
+        if let Some(headers) = metadata.headers.as_ref().map(Serde::deref) {
+            let global = window.upcast::<GlobalScope>();
+            global.append_csp_list(CspList::from_headers(headers));
+            global.set_reporting_endpoints(parse_report_to_headers(headers, &final_url));
+        }
+
         let document = Document::new(
             &window,
             HasBrowsingContext::Yes,
@@ -3430,6 +3484,14 @@ impl ScriptThread {
                 self.handle_wheel_event(pipeline_id, delta, point, node_address);
             },
 
+            DropEvent(point, node_address, data) => {
+                let document = match { self.documents.borrow().find_document(pipeline_id) } {
+                    Some(document) => document,
+                    None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
+                };
+                document.handle_drop_event(self.js_runtime.rt(), point, node_address, data);
+            },
+
             KeyboardEvent(key_event) => {
                 let document = match { self.documents.borrow().find_document(pipeline_id) } {
                     Some(document) => document,
@@ -3619,7 +3681,11 @@ impl ScriptThread {
             .headers(load_data.headers)
             .body(load_data.data)
             .redirect_mode(RedirectMode::Manual)
-            .origin(incomplete.origin.immutable().clone());
+            .origin(incomplete.origin.immutable().clone())
+            .site_for_cookies(load_data.site_for_cookies.clone())
+            .csp_list(load_data.csp_list)
+            .top_level_navigation(incomplete.parent_info.is_none());
+        incomplete.site_for_cookies = load_data.site_for_cookies;
 
         let context = ParserContext::new(id, load_data.url);
         self.incomplete_parser_contexts
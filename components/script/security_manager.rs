@@ -0,0 +1,305 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Reporting of Content Security Policy violations, through `securitypolicyviolation`
+//! events and reports sent to the endpoints named by the violated policy.
+//! <https://w3c.github.io/webappsec-csp/#reporting>
+
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventInit;
+use crate::dom::bindings::codegen::Bindings::SecurityPolicyViolationEventBinding::{
+    SecurityPolicyViolationEventDisposition, SecurityPolicyViolationEventInit,
+};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::DomObject;
+use crate::dom::bindings::str::{DOMString, USVString};
+use crate::dom::event::Event;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::securitypolicyviolationevent::SecurityPolicyViolationEvent;
+use crate::task::TaskOnce;
+use headers::{ContentType, HeaderMapExt};
+use http::header::HeaderMap;
+use hyper::Method;
+use net_traits::content_security_policy::{PolicyDisposition, Violation, ViolationResource};
+use net_traits::request::{CredentialsMode, Destination, RequestBuilder, RequestMode};
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+
+/// The reporting endpoints of a global, keyed by group name.
+/// <https://w3c.github.io/reporting/#endpoint-group>
+pub type ReportingEndpoints = HashMap<String, ServoUrl>;
+
+/// <https://w3c.github.io/webappsec-csp/#report-violation>
+pub struct CSPViolationReporter {
+    target: Trusted<EventTarget>,
+    violation: Violation,
+}
+
+/// The fields of a violation which are exposed to content, serialized as the body of
+/// a `csp-violation` report.
+/// <https://w3c.github.io/webappsec-csp/#deprecated-serialize-violation>
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecurityPolicyViolationReport {
+    #[serde(rename = "documentURL")]
+    document_url: String,
+    referrer: String,
+    #[serde(rename = "blockedURL")]
+    blocked_url: String,
+    effective_directive: String,
+    original_policy: String,
+    source_file: String,
+    sample: String,
+    disposition: String,
+    status_code: u16,
+    line_number: u32,
+    column_number: u32,
+}
+
+/// The legacy report format, sent to `report-uri` endpoints.
+#[derive(Serialize)]
+struct CSPReport<'a> {
+    #[serde(rename = "csp-report")]
+    csp_report: CSPReportBody<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CSPReportBody<'a> {
+    document_uri: &'a str,
+    referrer: &'a str,
+    violated_directive: &'a str,
+    effective_directive: &'a str,
+    original_policy: &'a str,
+    disposition: &'a str,
+    blocked_uri: &'a str,
+    status_code: u16,
+    source_file: &'a str,
+    line_number: u32,
+    column_number: u32,
+    script_sample: &'a str,
+}
+
+/// A report delivered to a `report-to` endpoint.
+/// <https://w3c.github.io/reporting/#serialize-reports>
+#[derive(Serialize)]
+struct Report<'a> {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    age: u64,
+    url: &'a str,
+    user_agent: &'a str,
+    body: &'a SecurityPolicyViolationReport,
+}
+
+impl CSPViolationReporter {
+    pub fn new(target: &EventTarget, violation: Violation) -> CSPViolationReporter {
+        CSPViolationReporter {
+            target: Trusted::new(target),
+            violation,
+        }
+    }
+
+    fn report(&self, global: &GlobalScope) -> SecurityPolicyViolationReport {
+        let document_url = global.get_url();
+        let blocked_url = match self.violation.resource {
+            ViolationResource::Inline => "inline".to_owned(),
+            ViolationResource::Eval => "eval".to_owned(),
+            ViolationResource::Url(ref url) => strip_url_for_reports(url),
+        };
+        let disposition = match self.violation.policy.disposition {
+            PolicyDisposition::Enforce => "enforce",
+            PolicyDisposition::Report => "report",
+        };
+        SecurityPolicyViolationReport {
+            document_url: strip_url_for_reports(&document_url),
+            referrer: global.referrer_for_reports(),
+            blocked_url,
+            effective_directive: self.violation.effective_directive.clone(),
+            original_policy: self.violation.policy.original.clone(),
+            source_file: String::new(),
+            sample: self.violation.sample.clone().unwrap_or_default(),
+            disposition: disposition.to_owned(),
+            // TODO: the status code of the response the global was created from.
+            status_code: 0,
+            line_number: 0,
+            column_number: 0,
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#report-violation> step 3.1
+    fn fire_violation_event(&self, global: &GlobalScope, report: &SecurityPolicyViolationReport) {
+        let disposition = match self.violation.policy.disposition {
+            PolicyDisposition::Enforce => SecurityPolicyViolationEventDisposition::Enforce,
+            PolicyDisposition::Report => SecurityPolicyViolationEventDisposition::Report,
+        };
+        let init = SecurityPolicyViolationEventInit {
+            parent: EventInit {
+                bubbles: true,
+                cancelable: false,
+            },
+            documentURI: USVString(report.document_url.clone()),
+            referrer: USVString(report.referrer.clone()),
+            blockedURI: USVString(report.blocked_url.clone()),
+            violatedDirective: DOMString::from(report.effective_directive.clone()),
+            effectiveDirective: DOMString::from(report.effective_directive.clone()),
+            originalPolicy: DOMString::from(report.original_policy.clone()),
+            sourceFile: USVString(report.source_file.clone()),
+            sample: DOMString::from(report.sample.clone()),
+            disposition,
+            statusCode: report.status_code,
+            lineNumber: report.line_number,
+            columnNumber: report.column_number,
+        };
+        let event =
+            SecurityPolicyViolationEvent::new(global, atom!("securitypolicyviolation"), &init);
+        event.upcast::<Event>().fire(&*self.target.root());
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#report-violation> step 3.2
+    fn send_report_uri_reports(
+        &self,
+        global: &GlobalScope,
+        report: &SecurityPolicyViolationReport,
+    ) {
+        let report_uris = self.violation.policy.report_uris();
+        if report_uris.is_empty() {
+            return;
+        }
+        let body = CSPReport {
+            csp_report: CSPReportBody {
+                document_uri: &report.document_url,
+                referrer: &report.referrer,
+                violated_directive: &report.effective_directive,
+                effective_directive: &report.effective_directive,
+                original_policy: &report.original_policy,
+                disposition: &report.disposition,
+                blocked_uri: &report.blocked_url,
+                status_code: report.status_code,
+                source_file: &report.source_file,
+                line_number: report.line_number,
+                column_number: report.column_number,
+                script_sample: &report.sample,
+            },
+        };
+        let body = match serde_json::to_vec(&body) {
+            Ok(body) => body,
+            Err(_) => return,
+        };
+        let base_url = global.api_base_url();
+        for report_uri in report_uris {
+            let url = match base_url.join(&report_uri) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            send_report(global, url, "application/csp-report", body.clone());
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#report-violation> step 3.3
+    fn send_report_to_report(&self, global: &GlobalScope, report: &SecurityPolicyViolationReport) {
+        let endpoint = match self
+            .violation
+            .policy
+            .report_to()
+            .and_then(|group| global.reporting_endpoint(&group))
+        {
+            Some(endpoint) => endpoint,
+            None => return,
+        };
+        let user_agent = global.get_user_agent();
+        let reports = [Report {
+            type_: "csp-violation",
+            age: 0,
+            url: &report.document_url,
+            user_agent: &user_agent,
+            body: report,
+        }];
+        if let Ok(body) = serde_json::to_vec(&reports) {
+            send_report(global, endpoint, "application/reports+json", body);
+        }
+    }
+}
+
+impl TaskOnce for CSPViolationReporter {
+    fn run_once(self) {
+        let target = self.target.root();
+        let global = target.global();
+        let report = self.report(&global);
+        self.fire_violation_event(&global, &report);
+        self.send_report_uri_reports(&global, &report);
+        self.send_report_to_report(&global, &report);
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#strip-url-for-use-in-reports>
+fn strip_url_for_reports(url: &ServoUrl) -> String {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return url.scheme().to_owned();
+    }
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.into_string()
+}
+
+/// Send a report in the background, ignoring the response.
+fn send_report(global: &GlobalScope, url: ServoUrl, content_type: &str, body: Vec<u8>) {
+    let mut headers = HeaderMap::new();
+    if let Ok(mime) = content_type.parse() {
+        headers.typed_insert(ContentType::from(mime));
+    }
+    let request = RequestBuilder::new(url)
+        .method(Method::POST)
+        .headers(headers)
+        .body(Some(body))
+        .destination(Destination::Report)
+        .mode(RequestMode::NoCors)
+        .credentials_mode(CredentialsMode::CredentialsSameOrigin)
+        .origin(global.origin().immutable().clone())
+        .pipeline_id(Some(global.pipeline_id()));
+    net_traits::fetch_async(request, &global.core_resource_thread(), |_| {});
+}
+
+/// Parse the endpoint groups declared by the `Report-To` headers of a response.
+/// <https://w3c.github.io/reporting/#process-header>
+pub fn parse_report_to_headers(headers: &HeaderMap, base_url: &ServoUrl) -> ReportingEndpoints {
+    #[derive(Deserialize)]
+    struct Endpoint {
+        url: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Group {
+        group: Option<String>,
+        endpoints: Vec<Endpoint>,
+    }
+
+    let mut endpoints = ReportingEndpoints::new();
+    for value in headers.get_all("report-to").iter() {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        // The header is a comma-separated list of JSON objects.
+        let groups: Vec<Group> = match serde_json::from_str(&format!("[{}]", value)) {
+            Ok(groups) => groups,
+            Err(_) => continue,
+        };
+        for group in groups {
+            let name = group.group.unwrap_or_else(|| "default".to_owned());
+            let url = group
+                .endpoints
+                .iter()
+                .filter_map(|endpoint| base_url.join(&endpoint.url).ok())
+                .next();
+            if let Some(url) = url {
+                endpoints.entry(name).or_insert(url);
+            }
+        }
+    }
+    endpoints
+}
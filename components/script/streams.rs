@@ -0,0 +1,380 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Machinery shared by the readable, writable and transform streams of
+//! <https://streams.spec.whatwg.org/>.
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::{
+    QueuingStrategy, QueuingStrategySize,
+};
+use crate::dom::bindings::conversions::get_property_jsval;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::DomObject;
+use crate::dom::bindings::root::Dom;
+use crate::dom::bindings::trace::JSTraceable;
+use crate::dom::bindings::utils::set_dictionary_property;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::script_runtime::JSContext as SafeJSContext;
+use js::jsapi::JS_NewPlainObject;
+use js::jsapi::{HandleValueArray, Heap, IsCallable, JSContext, JSObject, JSTracer};
+use js::jsval::{BooleanValue, JSVal, ObjectValue, UndefinedValue};
+use js::rust::wrappers::{Call, JS_SetPendingException};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// An entry of a queue-with-sizes.
+#[derive(JSTraceable, MallocSizeOf)]
+enum QueueEntry {
+    Value(#[ignore_malloc_size_of = "mozjs"] Box<Heap<JSVal>>, f64),
+    /// <https://streams.spec.whatwg.org/#close-sentinel>
+    CloseSentinel,
+}
+
+/// <https://streams.spec.whatwg.org/#queue-with-sizes>
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct QueueWithSizes {
+    queue: VecDeque<QueueEntry>,
+    total_size: f64,
+}
+
+impl QueueWithSizes {
+    pub fn new() -> QueueWithSizes {
+        QueueWithSizes {
+            queue: VecDeque::new(),
+            total_size: 0.,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn total_size(&self) -> f64 {
+        self.total_size
+    }
+
+    /// <https://streams.spec.whatwg.org/#enqueue-value-with-size>
+    pub fn enqueue_value(&mut self, value: HandleValue, size: f64) -> Fallible<()> {
+        if !size.is_finite() || size < 0. {
+            return Err(Error::Range(
+                "The size of a chunk must be a finite, non-negative number".to_owned(),
+            ));
+        }
+        self.queue
+            .push_back(QueueEntry::Value(Heap::boxed(value.get()), size));
+        self.total_size += size;
+        Ok(())
+    }
+
+    pub fn enqueue_close_sentinel(&mut self) {
+        self.queue.push_back(QueueEntry::CloseSentinel);
+    }
+
+    /// <https://streams.spec.whatwg.org/#dequeue-value>
+    pub fn dequeue_value(&mut self, mut rval: MutableHandleValue) {
+        match self.queue.pop_front() {
+            Some(QueueEntry::Value(value, size)) => {
+                // Rounding errors must not leave a negative total behind.
+                self.total_size = (self.total_size - size).max(0.);
+                rval.set(value.get());
+            },
+            _ => rval.set(UndefinedValue()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#peek-queue-value>
+    pub fn peek_value(&self, mut rval: MutableHandleValue) {
+        match self.queue.front() {
+            Some(QueueEntry::Value(value, _)) => rval.set(value.get()),
+            _ => rval.set(UndefinedValue()),
+        }
+    }
+
+    pub fn peek_is_close_sentinel(&self) -> bool {
+        match self.queue.front() {
+            Some(QueueEntry::CloseSentinel) => true,
+            _ => false,
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#reset-queue>
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.total_size = 0.;
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#validate-and-normalize-high-water-mark>
+pub fn extract_high_water_mark(strategy: &QueuingStrategy, default: f64) -> Fallible<f64> {
+    match strategy.highWaterMark {
+        None => Ok(default),
+        Some(high_water_mark) if high_water_mark.is_nan() || high_water_mark < 0. => Err(
+            Error::Range("The high water mark must be a non-negative number".to_owned()),
+        ),
+        Some(high_water_mark) => Ok(high_water_mark),
+    }
+}
+
+/// Run the size algorithm of a queuing strategy on `chunk`, leaving any
+/// exception it throws pending. Without a size algorithm every chunk has size 1.
+pub fn chunk_size(size: Option<&QueuingStrategySize>, chunk: HandleValue) -> Fallible<f64> {
+    match size {
+        Some(size) => size.Call__(chunk, ExceptionHandling::Rethrow),
+        None => Ok(1.),
+    }
+}
+
+/// Convert `error` to the JS value it would throw. A `JSFailed` error takes
+/// the exception pending on the context.
+#[allow(unsafe_code)]
+pub fn error_to_jsval(global: &GlobalScope, error: Error, rval: MutableHandleValue) {
+    let cx = global.get_cx();
+    unsafe { error.to_jsval(*cx, global, rval) }
+}
+
+/// Make `error` the pending exception again, for an algorithm that needs to
+/// rethrow it after handling it.
+#[allow(unsafe_code)]
+pub fn throw_jsval(cx: SafeJSContext, error: HandleValue) -> Error {
+    unsafe { JS_SetPendingException(*cx, error) };
+    Error::JSFailed
+}
+
+/// Check that the method `name` of an underlying source, sink or transformer
+/// is either absent or callable, as in
+/// <https://streams.spec.whatwg.org/#create-algorithm-from-underlying-method>.
+#[allow(unsafe_code)]
+pub fn check_underlying_methods(
+    cx: SafeJSContext,
+    object: HandleObject,
+    names: &[&str],
+) -> Fallible<()> {
+    if object.is_null() {
+        return Ok(());
+    }
+    for name in names {
+        rooted!(in(*cx) let mut method = UndefinedValue());
+        unsafe { get_property_jsval(*cx, object, name, method.handle_mut())? };
+        if method.is_undefined() {
+            continue;
+        }
+        if !method.is_object() || unsafe { !IsCallable(method.to_object()) } {
+            return Err(Error::Type(format!("{} is not a function", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Call the method `name` of `object` with `args`, leaving any exception it
+/// throws pending. A missing method, or a null object, returns undefined.
+#[allow(unsafe_code)]
+pub fn call_underlying_method(
+    cx: SafeJSContext,
+    object: HandleObject,
+    name: &str,
+    args: &[JSVal],
+    mut rval: MutableHandleValue,
+) -> Fallible<()> {
+    rval.set(UndefinedValue());
+    if object.is_null() {
+        return Ok(());
+    }
+    rooted!(in(*cx) let mut method = UndefinedValue());
+    unsafe { get_property_jsval(*cx, object, name, method.handle_mut())? };
+    if method.is_undefined() {
+        return Ok(());
+    }
+    if !method.is_object() || unsafe { !IsCallable(method.to_object()) } {
+        return Err(Error::Type(format!("{} is not a function", name)));
+    }
+    rooted!(in(*cx) let this = ObjectValue(object.get()));
+    let args = unsafe { HandleValueArray::from_rooted_slice(args) };
+    if unsafe { !Call(*cx, this.handle(), method.handle(), &args, rval) } {
+        return Err(Error::JSFailed);
+    }
+    Ok(())
+}
+
+/// <https://streams.spec.whatwg.org/#promise-call>
+pub fn promise_call(
+    global: &GlobalScope,
+    object: HandleObject,
+    name: &str,
+    args: &[JSVal],
+) -> Rc<Promise> {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    match call_underlying_method(cx, object, name, args, result.handle_mut()) {
+        Ok(()) => promise_resolved_with(global, result.handle()),
+        Err(error) => promise_rejected_with_error(global, error),
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#promise-resolve>
+#[allow(unsafe_code)]
+pub fn promise_resolved_with(global: &GlobalScope, value: HandleValue) -> Rc<Promise> {
+    let cx = global.get_cx();
+    unsafe { Promise::new_resolved(global, *cx, value) }.expect("Resolving a promise failed")
+}
+
+/// <https://streams.spec.whatwg.org/#promise-reject>
+#[allow(unsafe_code)]
+pub fn promise_rejected_with(global: &GlobalScope, reason: HandleValue) -> Rc<Promise> {
+    let cx = global.get_cx();
+    unsafe { Promise::new_rejected(global, *cx, reason) }.expect("Rejecting a promise failed")
+}
+
+pub fn promise_rejected_with_error(global: &GlobalScope, error: Error) -> Rc<Promise> {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut reason = UndefinedValue());
+    error_to_jsval(global, error, reason.handle_mut());
+    promise_rejected_with(global, reason.handle())
+}
+
+/// <https://streams.spec.whatwg.org/#readable-stream-create-read-result>
+#[allow(unsafe_code)]
+pub fn create_read_result(
+    cx: SafeJSContext,
+    value: HandleValue,
+    done: bool,
+    mut rval: MutableHandleValue,
+) {
+    rooted!(in(*cx) let result = unsafe { JS_NewPlainObject(*cx) });
+    rooted!(in(*cx) let done = BooleanValue(done));
+    let _ = set_dictionary_property(*cx, result.handle(), "value", value);
+    let _ = set_dictionary_property(*cx, result.handle(), "done", done.handle());
+    rval.set(ObjectValue(result.get()));
+}
+
+/// A promise resolved with a read result for `value` and `done`.
+pub fn read_result_promise(global: &GlobalScope, value: HandleValue, done: bool) -> Rc<Promise> {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    create_read_result(cx, value, done, result.handle_mut());
+    promise_resolved_with(global, result.handle())
+}
+
+/// Split a read result into its value, stored in `value`, and its done flag.
+#[allow(unsafe_code)]
+pub fn parse_read_result(
+    cx: SafeJSContext,
+    result: HandleValue,
+    mut value: MutableHandleValue,
+) -> bool {
+    value.set(UndefinedValue());
+    if !result.is_object() {
+        return true;
+    }
+    rooted!(in(*cx) let object = result.to_object());
+    rooted!(in(*cx) let mut done = UndefinedValue());
+    unsafe {
+        let _ = get_property_jsval(*cx, object.handle(), "value", value);
+        let _ = get_property_jsval(*cx, object.handle(), "done", done.handle_mut());
+    }
+    done.is_boolean() && done.to_boolean()
+}
+
+/// A DOM object taking further steps once a promise it waits on settles.
+pub trait PromiseReaction: DomObject {
+    /// What a reaction carries from its registration to the settlement.
+    type Step: JSTraceable + 'static;
+
+    /// Run the fulfillment (`Ok`) or rejection (`Err`) steps for `step`.
+    fn settled(&self, step: &Self::Step, result: Result<HandleValue, HandleValue>);
+}
+
+#[must_root]
+struct ReactionHandler<T: PromiseReaction> {
+    target: Dom<T>,
+    step: Rc<T::Step>,
+    fulfilled: bool,
+}
+
+#[allow(unsafe_code)]
+unsafe impl<T: PromiseReaction> JSTraceable for ReactionHandler<T> {
+    unsafe fn trace(&self, trc: *mut JSTracer) {
+        self.target.trace(trc);
+        self.step.trace(trc);
+    }
+}
+
+impl<T: PromiseReaction> MallocSizeOf for ReactionHandler<T> {
+    fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+        // See comment on MallocSizeOf for Dom<T>.
+        0
+    }
+}
+
+impl<T: PromiseReaction> Callback for ReactionHandler<T> {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        let result = if self.fulfilled { Ok(v) } else { Err(v) };
+        self.target.settled(&self.step, result);
+    }
+}
+
+/// Run the steps of `target` for `step` once `promise` settles, as in
+/// <https://streams.spec.whatwg.org/#upon-fulfillment> and
+/// <https://streams.spec.whatwg.org/#upon-rejection>.
+#[allow(unrooted_must_root)]
+pub fn upon_settlement<T: PromiseReaction>(promise: &Promise, target: &T, step: T::Step) {
+    let step = Rc::new(step);
+    let on_fulfilled = ReactionHandler {
+        target: Dom::from_ref(target),
+        step: step.clone(),
+        fulfilled: true,
+    };
+    let on_rejected = ReactionHandler {
+        target: Dom::from_ref(target),
+        step: step,
+        fulfilled: false,
+    };
+    let handler = PromiseNativeHandler::new(
+        &target.global(),
+        Some(Box::new(on_fulfilled)),
+        Some(Box::new(on_rejected)),
+    );
+    promise.append_native_handler(&handler);
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct SettleWithUndefined {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    fulfilled: bool,
+}
+
+impl Callback for SettleWithUndefined {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: *mut JSContext, v: HandleValue) {
+        if self.fulfilled {
+            self.promise.resolve_native(&());
+        } else {
+            unsafe { self.promise.reject(cx, v) };
+        }
+    }
+}
+
+/// A promise fulfilled with undefined once `promise` is fulfilled, and
+/// rejected with the same reason once it is rejected.
+pub fn transform_to_undefined(global: &GlobalScope, promise: &Promise) -> Rc<Promise> {
+    let result = Promise::new(global);
+    let handler = PromiseNativeHandler::new(
+        global,
+        Some(Box::new(SettleWithUndefined {
+            promise: result.clone(),
+            fulfilled: true,
+        })),
+        Some(Box::new(SettleWithUndefined {
+            promise: result.clone(),
+            fulfilled: false,
+        })),
+    );
+    promise.append_native_handler(&handler);
+    result
+}
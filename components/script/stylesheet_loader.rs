@@ -22,6 +22,7 @@ use encoding_rs::UTF_8;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use mime::{self, Mime};
+use net_traits::content_security_policy::Violation;
 use net_traits::request::{
     CorsSettings, CredentialsMode, Destination, Referrer, RequestBuilder, RequestMode,
 };
@@ -221,6 +222,11 @@ impl FetchResponseListener for StylesheetContext {
         }
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations);
+    }
+
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming {
         &mut self.resource_timing
     }
@@ -334,6 +340,8 @@ impl<'a> StylesheetLoader<'a> {
             })
             .origin(document.origin().immutable().clone())
             .pipeline_id(Some(self.elem.global().pipeline_id()))
+            .csp_list(self.elem.global().get_csp_list())
+            .site_for_cookies(Some(self.elem.global().site_for_cookies()))
             .referrer(Some(Referrer::ReferrerUrl(document.url())))
             .referrer_policy(referrer_policy)
             .integrity_metadata(integrity_metadata);
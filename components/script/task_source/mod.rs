@@ -8,6 +8,7 @@ pub mod history_traversal;
 pub mod media_element;
 pub mod networking;
 pub mod performance_timeline;
+pub mod port_message;
 pub mod remote_event;
 pub mod user_interaction;
 pub mod websocket;
@@ -32,6 +33,7 @@ pub enum TaskSourceName {
     RemoteEvent,
     MediaElement,
     Websocket,
+    PortMessage,
 }
 
 impl TaskSourceName {
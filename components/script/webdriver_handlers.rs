@@ -41,7 +41,7 @@ use msg::constellation_msg::BrowsingContextId;
 use msg::constellation_msg::PipelineId;
 use net_traits::CookieSource::{NonHTTP, HTTP};
 use net_traits::CoreResourceMsg::{DeleteCookies, GetCookiesDataForUrl, SetCookieForUrl};
-use net_traits::IpcSend;
+use net_traits::{CookieContext, IpcSend};
 use script_traits::webdriver_msg::WebDriverCookieError;
 use script_traits::webdriver_msg::{
     WebDriverFrameId, WebDriverJSError, WebDriverJSResult, WebDriverJSValue,
@@ -571,7 +571,12 @@ pub fn handle_add_cookie(
                     .window()
                     .upcast::<GlobalScope>()
                     .resource_threads()
-                    .send(SetCookieForUrl(url, Serde(cookie), method));
+                    .send(SetCookieForUrl(
+                        url,
+                        Serde(cookie),
+                        method,
+                        CookieContext::first_party(),
+                    ));
                 Ok(())
             },
             (false, None) => {
@@ -579,7 +584,12 @@ pub fn handle_add_cookie(
                     .window()
                     .upcast::<GlobalScope>()
                     .resource_threads()
-                    .send(SetCookieForUrl(url, Serde(cookie), method));
+                    .send(SetCookieForUrl(
+                        url,
+                        Serde(cookie),
+                        method,
+                        CookieContext::first_party(),
+                    ));
                 Ok(())
             },
             (_, _) => Err(WebDriverCookieError::UnableToSetCookie),
@@ -11,6 +11,7 @@ use gfx_traits::Epoch;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
 use metrics::PaintTimeMetrics;
 use msg::constellation_msg::{BackgroundHangMonitorRegister, PipelineId};
+use net_traits::content_security_policy::CspList;
 use net_traits::image_cache::ImageCache;
 use profile_traits::mem::ReportsChan;
 use script_traits::Painter;
@@ -31,8 +32,13 @@ use style::stylesheets::Stylesheet;
 pub enum Msg {
     /// Adds the given stylesheet to the document. The second stylesheet is the
     /// insertion point (if it exists, the sheet needs to be inserted before
-    /// it).
-    AddStylesheet(ServoArc<Stylesheet>, Option<ServoArc<Stylesheet>>),
+    /// it). The CSP list is the document's, and applies to web font loads
+    /// started by the sheet's `@font-face` rules.
+    AddStylesheet(
+        ServoArc<Stylesheet>,
+        Option<ServoArc<Stylesheet>>,
+        Option<CspList>,
+    ),
 
     /// Removes a stylesheet from the document.
     RemoveStylesheet(ServoArc<Stylesheet>),
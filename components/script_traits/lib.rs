@@ -24,7 +24,7 @@ use bluetooth_traits::BluetoothRequest;
 use canvas_traits::webgl::WebGLPipeline;
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use devtools_traits::{DevtoolScriptControlMsg, ScriptToDevtoolsControlMsg, WorkerId};
-use embedder_traits::{Cursor, EventLoopWaker};
+use embedder_traits::{Cursor, DragData, EventLoopWaker};
 use euclid::{
     default::{Point2D, Rect},
     Length, Scale, Size2D, Vector2D,
@@ -39,11 +39,13 @@ use keyboard_types::{CompositionEvent, KeyboardEvent};
 use libc::c_void;
 use media::WindowGLContext;
 use msg::constellation_msg::BackgroundHangMonitorRegister;
-use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
+use msg::constellation_msg::{BrowsingContextId, HistoryStateId, MessagePortId, PipelineId};
 use msg::constellation_msg::{PipelineNamespaceId, TopLevelBrowsingContextId, TraversalDirection};
+use net_traits::content_security_policy::CspList;
 use net_traits::image::base::Image;
 use net_traits::image_cache::ImageCache;
 use net_traits::request::Referrer;
+use net_traits::request_interceptor::RequestInterceptor;
 use net_traits::storage_thread::StorageType;
 use net_traits::{FetchResponseMsg, ReferrerPolicy, ResourceThreads};
 use pixels::PixelFormat;
@@ -54,8 +56,9 @@ use servo_atoms::Atom;
 use servo_url::ImmutableOrigin;
 use servo_url::ServoUrl;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
@@ -164,6 +167,11 @@ pub struct LoadData {
     pub referrer: Option<Referrer>,
     /// The referrer policy.
     pub referrer_policy: Option<ReferrerPolicy>,
+    /// The site for cookies of the document that initiated the load, if any.
+    pub site_for_cookies: Option<ServoUrl>,
+    /// The policies of the document embedding a nested browsing context, which
+    /// apply to its navigations.
+    pub csp_list: Option<CspList>,
 }
 
 /// The result of evaluating a javascript scheme url.
@@ -195,6 +203,8 @@ impl LoadData {
             js_eval_result: None,
             referrer: referrer,
             referrer_policy: referrer_policy,
+            site_for_cookies: None,
+            csp_list: None,
         }
     }
 }
@@ -330,7 +340,7 @@ pub enum ConstellationControlMsg {
         /// The expected origin of the target.
         target_origin: Option<ImmutableOrigin>,
         /// The data to be posted.
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     },
     /// Updates the current pipeline ID of a given iframe.
     /// First PipelineId is for the parent, second is the new PipelineId for the frame.
@@ -546,6 +556,8 @@ pub enum CompositorEvent {
     ),
     /// A wheel event was generated with a delta in the X, Y, and/or Z directions
     WheelEvent(WheelDelta, Point2D<f32>, Option<UntrustedNodeAddress>),
+    /// Data dragged from another application was dropped at a point.
+    DropEvent(Point2D<f32>, Option<UntrustedNodeAddress>, DragData),
     /// A key was pressed.
     KeyboardEvent(KeyboardEvent),
     /// An event from the IME is dispatched.
@@ -866,6 +878,10 @@ pub enum ConstellationMsg {
     DisableProfiler,
     /// Request to exit from fullscreen mode
     ExitFullScreen(TopLevelBrowsingContextId),
+    /// Start or stop intercepting the requests of all browsing contexts.
+    SetRequestInterceptor(Option<RequestInterceptor>),
+    /// Write the network requests recorded so far as a HAR file.
+    SaveHar(PathBuf),
 }
 
 impl fmt::Debug for ConstellationMsg {
@@ -896,6 +912,8 @@ impl fmt::Debug for ConstellationMsg {
             EnableProfiler(..) => "EnableProfiler",
             DisableProfiler => "DisableProfiler",
             ExitFullScreen(..) => "ExitFullScreen",
+            SetRequestInterceptor(..) => "SetRequestInterceptor",
+            SaveHar(..) => "SaveHar",
         };
         write!(formatter, "ConstellationMsg::{}", variant)
     }
@@ -922,6 +940,8 @@ pub struct WorkerGlobalScopeInit {
     pub worker_id: WorkerId,
     /// The pipeline id
     pub pipeline_id: PipelineId,
+    /// The pipeline namespace for the worker thread
+    pub pipeline_namespace_id: PipelineNamespaceId,
     /// The origin
     pub origin: ImmutableOrigin,
     /// True if headless mode
@@ -1008,3 +1028,43 @@ impl ScriptToConstellationChan {
         self.sender.send((self.pipeline_id, msg))
     }
 }
+
+/// A structured clone serialized for delivery to another global, along with the
+/// message ports that were transferred with it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StructuredSerializedData {
+    /// The serialized structured clone.
+    pub serialized: Vec<u8>,
+    /// The message ports transferred with the message, in transfer list order.
+    pub ports: Vec<TransferredMessagePort>,
+}
+
+/// The state of a message port that is shipped along with a message when the port
+/// is transferred to another global, possibly in another process.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferredMessagePort {
+    /// The id of the port.
+    pub id: MessagePortId,
+    /// The id of the port it is entangled with, if any.
+    pub entangled_port: Option<MessagePortId>,
+    /// Messages received by the port before its message queue was started.
+    pub message_buffer: VecDeque<StructuredSerializedData>,
+}
+
+/// Messages from the constellation to the message port router of a global.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessagePortMsg {
+    /// Deliver a message to one of the ports owned by the global.
+    NewTask(MessagePortId, StructuredSerializedData),
+}
+
+/// A message broadcast to the channels of a given name and origin.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BroadcastMsg {
+    /// The origin of the global that posted the message.
+    pub origin: ImmutableOrigin,
+    /// The name of the channel the message was posted to.
+    pub channel_name: String,
+    /// The serialized message.
+    pub data: Vec<u8>,
+}
@@ -4,10 +4,13 @@
 
 use crate::AnimationState;
 use crate::AuxiliaryBrowsingContextLoadInfo;
+use crate::BroadcastMsg;
 use crate::DocumentState;
 use crate::IFrameLoadInfoWithData;
 use crate::LayoutControlMsg;
 use crate::LoadData;
+use crate::MessagePortMsg;
+use crate::StructuredSerializedData;
 use crate::WindowSizeType;
 use crate::WorkerGlobalScopeInit;
 use crate::WorkerScriptLoadOrigin;
@@ -18,8 +21,10 @@ use euclid::default::Size2D as UntypedSize2D;
 use euclid::Size2D;
 use gfx_traits::Epoch;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
-use msg::constellation_msg::{BrowsingContextId, PipelineId, TopLevelBrowsingContextId};
+use msg::constellation_msg::{BroadcastChannelRouterId, BrowsingContextId};
 use msg::constellation_msg::{HistoryStateId, TraversalDirection};
+use msg::constellation_msg::{MessagePortId, MessagePortRouterId, PipelineNamespaceId};
+use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId};
 use net_traits::request::RequestBuilder;
 use net_traits::storage_thread::StorageType;
 use net_traits::CoreResourceMsg;
@@ -144,6 +149,8 @@ pub enum ScriptMsg {
         PipelineId,
         IpcSender<Option<(BrowsingContextId, Option<PipelineId>)>>,
     ),
+    /// Get a new pipeline namespace, for a worker thread.
+    GetPipelineNamespaceId(IpcSender<PipelineNamespaceId>),
     /// Get the nth child browsing context ID for a given browsing context, sorted in tree order.
     GetChildBrowsingContextId(
         BrowsingContextId,
@@ -167,8 +174,44 @@ pub enum ScriptMsg {
         /// The expected origin of the target.
         target_origin: Option<ImmutableOrigin>,
         /// The data to be posted.
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     },
+    /// A global has started owning message ports, and will receive messages for them
+    /// through the given router.
+    NewMessagePortRouter(MessagePortRouterId, IpcSender<MessagePortMsg>),
+    /// A global no longer owns any message ports.
+    RemoveMessagePortRouter(MessagePortRouterId),
+    /// A message port was created, or has arrived after a transfer, in the global
+    /// owning the given router.
+    NewMessagePort(MessagePortRouterId, MessagePortId),
+    /// Entangle two message ports, so that messages posted to one are delivered to the other.
+    EntanglePorts(MessagePortId, MessagePortId),
+    /// A message port is being transferred to another global. Messages for it are
+    /// buffered until it arrives.
+    MessagePortShipped(MessagePortId),
+    /// Post a message to the port entangled with the given port.
+    PostMessageToEntangledPort(MessagePortId, StructuredSerializedData),
+    /// Deliver a message to the given port, which was not owned by the global
+    /// it had been routed to.
+    RerouteMessagePort(MessagePortId, StructuredSerializedData),
+    /// A message port was closed.
+    RemoveMessagePort(MessagePortId),
+    /// A global has started using broadcast channels, and will receive the messages
+    /// broadcast to them through the given router.
+    NewBroadcastChannelRouter(
+        BroadcastChannelRouterId,
+        IpcSender<BroadcastMsg>,
+        ImmutableOrigin,
+    ),
+    /// A global no longer uses any broadcast channels.
+    RemoveBroadcastChannelRouter(BroadcastChannelRouterId),
+    /// A global has subscribed to the channel with the given name.
+    NewBroadcastChannelNameInRouter(BroadcastChannelRouterId, String),
+    /// A global has closed the last of its channels with the given name.
+    RemoveBroadcastChannelNameInRouter(BroadcastChannelRouterId, String),
+    /// Broadcast a message to the globals subscribed to its channel, other than
+    /// the one owning the given router.
+    ScheduleBroadcast(BroadcastChannelRouterId, BroadcastMsg),
     /// Inform the constellation that a fragment was navigated to and whether or not it was a replacement navigation.
     NavigatedToFragment(ServoUrl, HistoryEntryReplacement),
     /// HTMLIFrameElement Forward or Back traversal.
@@ -234,11 +277,25 @@ impl fmt::Debug for ScriptMsg {
             Focus => "Focus",
             GetBrowsingContextInfo(..) => "GetBrowsingContextInfo",
             GetTopForBrowsingContext(..) => "GetParentBrowsingContext",
+            GetPipelineNamespaceId(..) => "GetPipelineNamespaceId",
             GetChildBrowsingContextId(..) => "GetChildBrowsingContextId",
             LoadComplete => "LoadComplete",
             LoadUrl(..) => "LoadUrl",
             AbortLoadUrl => "AbortLoadUrl",
             PostMessage { .. } => "PostMessage",
+            NewMessagePortRouter(..) => "NewMessagePortRouter",
+            RemoveMessagePortRouter(..) => "RemoveMessagePortRouter",
+            NewMessagePort(..) => "NewMessagePort",
+            EntanglePorts(..) => "EntanglePorts",
+            MessagePortShipped(..) => "MessagePortShipped",
+            PostMessageToEntangledPort(..) => "PostMessageToEntangledPort",
+            RerouteMessagePort(..) => "RerouteMessagePort",
+            RemoveMessagePort(..) => "RemoveMessagePort",
+            NewBroadcastChannelRouter(..) => "NewBroadcastChannelRouter",
+            RemoveBroadcastChannelRouter(..) => "RemoveBroadcastChannelRouter",
+            NewBroadcastChannelNameInRouter(..) => "NewBroadcastChannelNameInRouter",
+            RemoveBroadcastChannelNameInRouter(..) => "RemoveBroadcastChannelNameInRouter",
+            ScheduleBroadcast(..) => "ScheduleBroadcast",
             NavigatedToFragment(..) => "NavigatedToFragment",
             TraverseHistory(..) => "TraverseHistory",
             PushHistoryState(..) => "PushHistoryState",
@@ -338,6 +338,22 @@ where
                     simple_selector_specificity(&ss, specificity);
                 }
             },
+            Component::Is(ref list) | Component::Has(ref list) => {
+                // https://drafts.csswg.org/selectors-4/#specificity-rules:
+                //
+                //     The specificity of an :is(), :not(), or :has()
+                //     pseudo-class is replaced by the specificity of the most
+                //     specific complex selector in its selector list argument.
+                let max = list
+                    .iter()
+                    .map(|selector| selector.specificity())
+                    .max()
+                    .unwrap_or(0);
+                *specificity += Specificity::from(max);
+            },
+            Component::Where(..) | Component::RelativeSelectorAnchor => {
+                // Does not affect specificity
+            },
         }
     }
 
@@ -120,6 +120,10 @@ where
     /// The current shadow host we're collecting :host rules for.
     pub current_host: Option<OpaqueElement>,
 
+    /// The anchor of the relative selector we're matching, that is, the
+    /// element whose :has(..) selector is being matched, if any.
+    relative_selector_anchor: Option<OpaqueElement>,
+
     /// Controls how matching for links is handled.
     visited_handling: VisitedHandlingMode,
 
@@ -181,6 +185,7 @@ where
             classes_and_ids_case_sensitivity: quirks_mode.classes_and_ids_case_sensitivity(),
             scope_element: None,
             current_host: None,
+            relative_selector_anchor: None,
             nesting_level: 0,
             in_negation: false,
             pseudo_element_matching_fn: None,
@@ -254,6 +259,25 @@ where
         result
     }
 
+    /// Runs F with a deeper nesting level, matching relative selectors
+    /// anchored at the given element, for a :has(..) selector.
+    #[inline]
+    pub fn nest_for_relative_selector<F, R>(&mut self, anchor: OpaqueElement, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let original_anchor = self.relative_selector_anchor.replace(anchor);
+        let result = self.nest(f);
+        self.relative_selector_anchor = original_anchor;
+        result
+    }
+
+    /// Returns the anchor of the relative selector we're matching, if any.
+    #[inline]
+    pub fn relative_selector_anchor(&self) -> Option<OpaqueElement> {
+        self.relative_selector_anchor
+    }
+
     #[inline]
     pub fn visited_handling(&self) -> VisitedHandlingMode {
         self.visited_handling
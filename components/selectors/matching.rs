@@ -40,20 +40,32 @@ bitflags! {
         /// The element has an empty selector, so when a child is appended we
         /// might need to restyle the parent completely.
         const HAS_EMPTY_SELECTOR = 1 << 3;
+
+        /// The element is the anchor of a `:has()` selector, so when any of its
+        /// descendants is added or removed we might need to restyle it, and
+        /// the elements whose style depends on it.
+        const HAS_RELATIVE_SELECTOR = 1 << 4;
+
+        /// A child of the element is the anchor of a `:has()` selector that
+        /// looks at its later siblings, so when a child is added or removed we
+        /// might need to restyle all the children.
+        const HAS_RELATIVE_SELECTOR_SIBLINGS = 1 << 5;
     }
 }
 
 impl ElementSelectorFlags {
     /// Returns the subset of flags that apply to the element.
     pub fn for_self(self) -> ElementSelectorFlags {
-        self & (ElementSelectorFlags::HAS_EMPTY_SELECTOR)
+        self & (ElementSelectorFlags::HAS_EMPTY_SELECTOR |
+            ElementSelectorFlags::HAS_RELATIVE_SELECTOR)
     }
 
     /// Returns the subset of flags that apply to the parent.
     pub fn for_parent(self) -> ElementSelectorFlags {
         self & (ElementSelectorFlags::HAS_SLOW_SELECTOR |
             ElementSelectorFlags::HAS_SLOW_SELECTOR_LATER_SIBLINGS |
-            ElementSelectorFlags::HAS_EDGE_CHILD_SELECTOR)
+            ElementSelectorFlags::HAS_EDGE_CHILD_SELECTOR |
+            ElementSelectorFlags::HAS_RELATIVE_SELECTOR_SIBLINGS)
     }
 }
 
@@ -376,6 +388,9 @@ fn matches_hover_and_active_quirk<Impl: SelectorImpl>(
         Component::Class(_) |
         Component::PseudoElement(_) |
         Component::Negation(_) |
+        Component::Is(_) |
+        Component::Where(_) |
+        Component::Has(_) |
         Component::FirstChild |
         Component::LastChild |
         Component::OnlyChild |
@@ -822,7 +837,125 @@ where
                 .iter()
                 .all(|ss| matches_simple_selector(ss, element, &mut local_context, flags_setter))
         }),
+        Component::Is(ref list) | Component::Where(ref list) => context.shared.nest(|context| {
+            list.iter().any(|selector| {
+                matches_complex_selector(selector.iter(), element, context, flags_setter)
+            })
+        }),
+        Component::Has(ref list) => {
+            context
+                .shared
+                .nest_for_relative_selector(element.opaque(), |context| {
+                    list.iter().any(|selector| {
+                        matches_relative_selector(selector, element, context, flags_setter)
+                    })
+                })
+        },
+        // Outside of :has(..), for example when a relative selector is
+        // matched on its own for invalidation, any element may be the anchor.
+        Component::RelativeSelectorAnchor => context
+            .shared
+            .relative_selector_anchor()
+            .map_or(true, |anchor| anchor == element.opaque()),
+    }
+}
+
+/// Whether any of the elements that a relative selector anchored at `anchor`
+/// could match does match it.
+///
+/// Only the subtrees of the children or of the later siblings of the anchor
+/// are looked at, as the combinators of the selector allow.
+fn matches_relative_selector<E, F>(
+    selector: &Selector<E::Impl>,
+    anchor: &E,
+    context: &mut MatchingContext<E::Impl>,
+    flags_setter: &mut F,
+) -> bool
+where
+    E: Element,
+    F: FnMut(&E, ElementSelectorFlags),
+{
+    // The anchor comes first in parse order, and then the leading combinator.
+    let leading_combinator = selector.combinator_at_parse_order(1);
+    let mut reaches_descendants = false;
+    let mut reaches_later_siblings = false;
+    for combinator in selector
+        .iter_raw_parse_order_from(2)
+        .filter_map(|component| component.as_combinator())
+    {
+        if combinator.is_sibling() {
+            reaches_later_siblings = true;
+        } else {
+            reaches_descendants = true;
+        }
+    }
+
+    flags_setter(
+        anchor,
+        if leading_combinator.is_sibling() {
+            ElementSelectorFlags::HAS_RELATIVE_SELECTOR_SIBLINGS
+        } else {
+            ElementSelectorFlags::HAS_RELATIVE_SELECTOR
+        },
+    );
+
+    let (mut candidate, match_descendants) = if leading_combinator.is_sibling() {
+        (anchor.next_sibling_element(), reaches_descendants)
+    } else {
+        (
+            anchor.first_element_child(),
+            leading_combinator == Combinator::Descendant || reaches_descendants,
+        )
+    };
+    let only_next_sibling =
+        leading_combinator == Combinator::NextSibling && !reaches_later_siblings;
+
+    while let Some(element) = candidate {
+        if matches_relative_selector_subtree(
+            selector,
+            &element,
+            match_descendants,
+            context,
+            flags_setter,
+        ) {
+            return true;
+        }
+        if only_next_sibling {
+            break;
+        }
+        candidate = element.next_sibling_element();
+    }
+    false
+}
+
+/// Whether `element`, or one of its descendants if `match_descendants` is
+/// true, matches the given relative selector.
+fn matches_relative_selector_subtree<E, F>(
+    selector: &Selector<E::Impl>,
+    element: &E,
+    match_descendants: bool,
+    context: &mut MatchingContext<E::Impl>,
+    flags_setter: &mut F,
+) -> bool
+where
+    E: Element,
+    F: FnMut(&E, ElementSelectorFlags),
+{
+    if matches_complex_selector(selector.iter(), element, context, flags_setter) {
+        return true;
     }
+    if !match_descendants {
+        return false;
+    }
+
+    let mut child = element.first_element_child();
+    while let Some(element) = child {
+        if matches_relative_selector_subtree(selector, &element, true, context, flags_setter) {
+            return true;
+        }
+        child = element.next_sibling_element();
+    }
+    false
 }
 
 #[inline(always)]
@@ -9,6 +9,7 @@ use crate::bloom::BLOOM_HASH_MASK;
 use crate::builder::{SelectorBuilder, SelectorFlags, SpecificityAndFlags};
 use crate::context::QuirksMode;
 use crate::sink::Push;
+use crate::visitor::SelectorListKind;
 pub use crate::visitor::{SelectorVisitor, Visit};
 use cssparser::{parse_nth, serialize_identifier};
 use cssparser::{BasicParseError, BasicParseErrorKind, ParseError, ParseErrorKind};
@@ -94,6 +95,9 @@ bitflags! {
         /// disallowed. If this flag is set, `AFTER_PSEUDO_ELEMENT` must be set
         /// as well.
         const AFTER_NON_STATEFUL_PSEUDO_ELEMENT = 1 << 4;
+        /// Whether we're inside the argument of a :has() pseudo-class, in
+        /// which no other :has() is allowed.
+        const DISALLOW_RELATIVE_SELECTOR = 1 << 5;
         /// Whether we are after any of the pseudo-like things.
         const AFTER_PSEUDO = Self::AFTER_PART.bits | Self::AFTER_SLOTTED.bits | Self::AFTER_PSEUDO_ELEMENT.bits;
     }
@@ -310,10 +314,9 @@ impl<Impl: SelectorImpl> SelectorList<Impl> {
     {
         let mut values = SmallVec::new();
         loop {
-            values.push(
-                input
-                    .parse_until_before(Delimiter::Comma, |input| parse_selector(parser, input))?,
-            );
+            values.push(input.parse_until_before(Delimiter::Comma, |input| {
+                parse_selector(parser, input, SelectorParsingState::empty())
+            })?);
             match input.next() {
                 Err(_) => return Ok(SelectorList(values)),
                 Ok(&Token::Comma) => continue,
@@ -338,7 +341,7 @@ where
     Impl: SelectorImpl,
 {
     let location = input.current_source_location();
-    let selector = parse_selector(parser, input)?;
+    let selector = parse_selector(parser, input, SelectorParsingState::empty())?;
 
     // Ensure they're actually all compound selectors without pseudo-elements.
     if selector.has_pseudo_element() {
@@ -504,6 +507,21 @@ where
                     }
                 }
             },
+            Is(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Is, list) {
+                    return false;
+                }
+            },
+            Where(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Where, list) {
+                    return false;
+                }
+            },
+            Has(ref list) => {
+                if !visitor.visit_selector_list(SelectorListKind::Has, list) {
+                    return false;
+                }
+            },
 
             AttributeInNoNamespaceExists {
                 ref local_name,
@@ -985,6 +1003,32 @@ pub enum Component<Impl: SelectorImpl> {
     /// visit_complex_selector, and what the consumers of those APIs should do
     /// about the presence of combinators in negation.
     Negation(ThinBoxedSlice<Component<Impl>>),
+    /// The `:is()` pseudo-class, which matches the elements any of the
+    /// complex selectors of its argument match:
+    ///
+    /// https://drafts.csswg.org/selectors-4/#matches
+    ///
+    /// Its specificity is the one of the most specific of those selectors.
+    Is(ThinBoxedSlice<Selector<Impl>>),
+    /// The `:where()` pseudo-class, which is like `:is()`, but with zero
+    /// specificity:
+    ///
+    /// https://drafts.csswg.org/selectors-4/#zero-matches
+    Where(ThinBoxedSlice<Selector<Impl>>),
+    /// The `:has()` pseudo-class, which matches the elements that anchor
+    /// any of the relative selectors of its argument:
+    ///
+    /// https://drafts.csswg.org/selectors-4/#relational
+    ///
+    /// Each relative selector is stored as a complex selector whose leftmost
+    /// compound selector is a `RelativeSelectorAnchor`, followed by the
+    /// leading combinator, which is a descendant combinator if omitted.
+    Has(ThinBoxedSlice<Selector<Impl>>),
+    /// The element a relative selector of `:has()` is matched from. Only
+    /// found as the leftmost compound selector of those.
+    ///
+    /// Matches any element when not matching a `:has()` selector.
+    RelativeSelectorAnchor,
     FirstChild,
     LastChild,
     OnlyChild,
@@ -1165,6 +1209,20 @@ impl<Impl: SelectorImpl> ToCss for Selector<Impl> {
                 continue;
             }
 
+            // The anchor of a relative selector is implicit, and so is its
+            // combinator if it's a descendant one.
+            if let [Component::RelativeSelectorAnchor] = *compound {
+                match combinators.next() {
+                    Some(Combinator::Descendant) | None => {},
+                    Some(combinator) => {
+                        let mut serialized = String::new();
+                        combinator.to_css(&mut serialized)?;
+                        dest.write_str(serialized.trim_start())?;
+                    },
+                }
+                continue;
+            }
+
             // 1. If there is only one simple selector in the compound selectors
             //    which is a universal selector, append the result of
             //    serializing the universal selector to s.
@@ -1364,6 +1422,25 @@ impl<Impl: SelectorImpl> ToCss for Component<Impl> {
                 dest.write_str(")")
             },
 
+            Is(ref list) | Where(ref list) | Has(ref list) => {
+                match *self {
+                    Is(..) => dest.write_str(":is(")?,
+                    Where(..) => dest.write_str(":where(")?,
+                    Has(..) => dest.write_str(":has(")?,
+                    _ => unreachable!(),
+                }
+                let mut iter = list.iter();
+                if let Some(first) = iter.next() {
+                    first.to_css(dest)?;
+                }
+                for selector in iter {
+                    dest.write_str(", ")?;
+                    selector.to_css(dest)?;
+                }
+                dest.write_char(')')
+            },
+            RelativeSelectorAnchor => Ok(()),
+
             FirstChild => dest.write_str(":first-child"),
             LastChild => dest.write_str(":last-child"),
             OnlyChild => dest.write_str(":only-child"),
@@ -1471,19 +1548,34 @@ fn display_to_css_identifier<T: Display, W: fmt::Write>(x: &T, dest: &mut W) ->
 fn parse_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
 ) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
     Impl: SelectorImpl,
 {
     let mut builder = SelectorBuilder::default();
+    parse_selector_with_builder(parser, input, state, &mut builder)
+}
 
+/// Parses a selector, appending it to the simple selectors and combinators
+/// already in `builder`.
+fn parse_selector_with_builder<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    nesting_state: SelectorParsingState,
+    builder: &mut SelectorBuilder<Impl>,
+) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
     let mut has_pseudo_element = false;
     let mut slotted = false;
     let mut part = false;
     'outer_loop: loop {
         // Parse a sequence of simple selectors.
-        let state = match parse_compound_selector(parser, input, &mut builder)? {
+        let state = match parse_compound_selector(parser, input, nesting_state, builder)? {
             Some(state) => state,
             None => {
                 return Err(input.new_custom_error(if builder.has_combinators() {
@@ -1549,7 +1641,7 @@ impl<Impl: SelectorImpl> Selector<Impl> {
     where
         P: Parser<'i, Impl = Impl>,
     {
-        parse_selector(parser, input)
+        parse_selector(parser, input, SelectorParsingState::empty())
     }
 }
 
@@ -1996,6 +2088,7 @@ where
 fn parse_compound_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
+    mut state: SelectorParsingState,
     builder: &mut SelectorBuilder<Impl>,
 ) -> Result<Option<SelectorParsingState>, ParseError<'i, P::Error>>
 where
@@ -2016,7 +2109,6 @@ where
         empty = false;
     }
 
-    let mut state = SelectorParsingState::empty();
     loop {
         let parse_result = match parse_one_simple_selector(parser, input, state)? {
             None => break,
@@ -2083,14 +2175,122 @@ where
                     SelectorParseErrorKind::UnexpectedIdent("not".into())
                 ));
             }
-            debug_assert!(state.is_empty());
             return parse_negation(parser, input)
         },
+        "is" => return parse_is_or_where(parser, input, state, Component::Is),
+        "where" => return parse_is_or_where(parser, input, state, Component::Where),
+        "has" => return parse_has(parser, input, state),
         _ => {}
     }
     P::parse_non_ts_functional_pseudo_class(parser, name, input).map(Component::NonTSPseudoClass)
 }
 
+/// Parses the argument of `:is()` or `:where()`, a list of complex selectors
+/// without pseudo-elements.
+fn parse_is_or_where<'i, 't, P, Impl, F>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+    component: F,
+) -> Result<Component<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+    F: FnOnce(ThinBoxedSlice<Selector<Impl>>) -> Component<Impl>,
+{
+    // Like another :not(), the nested selectors could contain negations,
+    // which can't nest.
+    if state.intersects(SelectorParsingState::INSIDE_NEGATION) {
+        return Err(input.new_custom_error(SelectorParseErrorKind::InvalidState));
+    }
+    // Only the restrictions coming from an enclosing :has() apply to the
+    // nested selectors.
+    let nesting_state = state & SelectorParsingState::DISALLOW_RELATIVE_SELECTOR;
+    let selectors = input.parse_comma_separated(|input| {
+        let location = input.current_source_location();
+        let selector = parse_selector(parser, input, nesting_state)?;
+        check_no_pseudo_element(&selector, location)?;
+        Ok(selector)
+    })?;
+    Ok(component(selectors.into_boxed_slice().into()))
+}
+
+/// Parses the argument of `:has()`, a list of relative selectors without
+/// pseudo-elements.
+fn parse_has<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+    state: SelectorParsingState,
+) -> Result<Component<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
+    // :has() doesn't nest.
+    if state.intersects(
+        SelectorParsingState::INSIDE_NEGATION | SelectorParsingState::DISALLOW_RELATIVE_SELECTOR,
+    ) {
+        return Err(input.new_custom_error(SelectorParseErrorKind::InvalidState));
+    }
+    let selectors = input.parse_comma_separated(|input| {
+        let location = input.current_source_location();
+        let selector = parse_relative_selector(parser, input)?;
+        check_no_pseudo_element(&selector, location)?;
+        Ok(selector)
+    })?;
+    Ok(Component::Has(selectors.into_boxed_slice().into()))
+}
+
+/// relative_selector : combinator? selector ;
+///
+/// See the comment on `Component::Has` about how relative selectors are
+/// represented.
+fn parse_relative_selector<'i, 't, P, Impl>(
+    parser: &P,
+    input: &mut CssParser<'i, 't>,
+) -> Result<Selector<Impl>, ParseError<'i, P::Error>>
+where
+    P: Parser<'i, Impl = Impl>,
+    Impl: SelectorImpl,
+{
+    input.skip_whitespace();
+    let combinator = if input.try(|input| input.expect_delim('>')).is_ok() {
+        Combinator::Child
+    } else if input.try(|input| input.expect_delim('+')).is_ok() {
+        Combinator::NextSibling
+    } else if input.try(|input| input.expect_delim('~')).is_ok() {
+        Combinator::LaterSibling
+    } else {
+        Combinator::Descendant
+    };
+
+    let mut builder = SelectorBuilder::default();
+    builder.push_simple_selector(Component::RelativeSelectorAnchor);
+    builder.push_combinator(combinator);
+    parse_selector_with_builder(
+        parser,
+        input,
+        SelectorParsingState::DISALLOW_RELATIVE_SELECTOR,
+        &mut builder,
+    )
+}
+
+fn check_no_pseudo_element<'i, Impl, E>(
+    selector: &Selector<Impl>,
+    location: SourceLocation,
+) -> Result<(), ParseError<'i, E>>
+where
+    Impl: SelectorImpl,
+    E: From<SelectorParseErrorKind<'i>>,
+{
+    if selector.has_pseudo_element() || selector.is_slotted() || selector.is_part() {
+        return Err(
+            location.new_custom_error(SelectorParseErrorKind::PseudoElementInComplexSelector)
+        );
+    }
+    Ok(())
+}
+
 fn parse_nth_pseudo_class<'i, 't, Impl, F>(
     input: &mut CssParser<'i, 't>,
     selector: F,
@@ -3064,6 +3264,82 @@ pub mod tests {
         assert!(parse("slot::slotted(div,foo)").is_err());
     }
 
+    #[test]
+    fn test_nested_selector_lists() {
+        let selector_specificity = |input| parse(input).unwrap().0[0].specificity();
+
+        assert_eq!(
+            selector_specificity(":is(#foo, .bar)"),
+            specificity(1, 0, 0)
+        );
+        assert_eq!(
+            selector_specificity("e:is(.foo .bar, f)"),
+            specificity(0, 2, 1)
+        );
+        assert_eq!(
+            selector_specificity(":where(#foo, .bar)"),
+            specificity(0, 0, 0)
+        );
+        assert_eq!(
+            selector_specificity("e:where(#foo) .bar"),
+            specificity(0, 1, 1)
+        );
+        assert_eq!(
+            selector_specificity(":has(> .foo, #bar)"),
+            specificity(1, 0, 0)
+        );
+        assert_eq!(
+            selector_specificity(":is(:where(#foo), e)"),
+            specificity(0, 0, 1)
+        );
+
+        assert!(parse(":is()").is_err());
+        assert!(parse(":where(.foo,)").is_err());
+        assert!(parse(":is(::before)").is_err());
+        assert!(parse(":is(.foo)::before").is_ok());
+        assert!(parse(":not(:is(.foo))").is_err());
+        assert!(parse("::before:is(.foo)").is_err());
+
+        assert!(parse(":has(.foo .bar)").is_ok());
+        assert!(parse(":has(> .foo)").is_ok());
+        assert!(parse(":has(+ .foo, ~ .bar)").is_ok());
+        assert!(parse(":has()").is_err());
+        assert!(parse(":has(>)").is_err());
+        assert!(parse(":has(> > .foo)").is_err());
+        assert!(parse(":has(::before)").is_err());
+        assert!(parse(":has(:has(.foo))").is_err());
+        assert!(parse(":has(:is(:has(.foo)))").is_err());
+        assert!(parse(":is(:has(.foo))").is_ok());
+        assert_eq!(
+            parse_expected(":HAS( >.foo,.bar )", Some(":has(> .foo, .bar)")),
+            parse(":has(> .foo, .bar)")
+        );
+
+        assert_eq!(
+            parse(":has(+ e)"),
+            Ok(SelectorList::from_vec(vec![Selector::from_vec(
+                vec![Component::Has(
+                    vec![Selector::from_vec(
+                        vec![
+                            Component::RelativeSelectorAnchor,
+                            Component::Combinator(Combinator::NextSibling),
+                            Component::LocalName(LocalName {
+                                name: DummyAtom::from("e"),
+                                lower_name: DummyAtom::from("e"),
+                            }),
+                        ],
+                        specificity(0, 0, 1),
+                        Default::default(),
+                    )]
+                    .into_boxed_slice()
+                    .into(),
+                )],
+                specificity(0, 0, 1),
+                Default::default(),
+            )]))
+        );
+    }
+
     #[test]
     fn test_pseudo_iter() {
         let selector = &parse("q::before").unwrap().0[0];
@@ -3131,5 +3407,10 @@ pub mod tests {
         let mut test_visitor = TestVisitor { seen: vec![] };
         parse("::before:hover").unwrap().0[0].visit(&mut test_visitor);
         assert!(test_visitor.seen.contains(&":hover".into()));
+
+        let mut test_visitor = TestVisitor { seen: vec![] };
+        parse(":is(.foo, :has(> :hover))").unwrap().0[0].visit(&mut test_visitor);
+        assert!(test_visitor.seen.contains(&".foo".into()));
+        assert!(test_visitor.seen.contains(&":hover".into()));
     }
 }
@@ -7,7 +7,7 @@
 #![deny(missing_docs)]
 
 use crate::attr::NamespaceConstraint;
-use crate::parser::{Combinator, Component, SelectorImpl};
+use crate::parser::{Combinator, Component, Selector, SelectorImpl};
 
 /// A trait to visit selector properties.
 ///
@@ -41,6 +41,40 @@ pub trait SelectorVisitor {
     fn visit_complex_selector(&mut self, _combinator_to_right: Option<Combinator>) -> bool {
         true
     }
+
+    /// Visits a list of selectors nested in a simple selector, like the
+    /// arguments of `:is()`, `:where()` or `:has()`.
+    ///
+    /// By default, visits each of them as if it was part of the selector
+    /// containing the list.
+    fn visit_selector_list(
+        &mut self,
+        _list_kind: SelectorListKind,
+        list: &[Selector<Self::Impl>],
+    ) -> bool
+    where
+        Self: Sized,
+        <Self::Impl as SelectorImpl>::NonTSPseudoClass: Visit<Impl = Self::Impl>,
+    {
+        for nested in list {
+            if !nested.visit(self) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The kind of pseudo-class a nested selector list is the argument of.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectorListKind {
+    /// `:is()`.
+    Is,
+    /// `:where()`.
+    Where,
+    /// `:has()`, whose selectors are relative to the element they are
+    /// matched against.
+    Has,
 }
 
 /// Enables traversing selector components stored in various types
@@ -609,6 +609,10 @@ where
                 self.compositor.on_wheel_event(delta, location);
             },
 
+            WindowEvent::Drop(location, data) => {
+                self.compositor.on_drop_event(location, data);
+            },
+
             WindowEvent::Scroll(delta, cursor, phase) => {
                 self.compositor.on_scroll_event(delta, cursor, phase);
             },
@@ -670,6 +674,23 @@ where
                 }
             },
 
+            WindowEvent::SetRequestInterceptor(interceptor) => {
+                let msg = ConstellationMsg::SetRequestInterceptor(interceptor);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!(
+                        "Sending request interceptor to constellation failed ({:?}).",
+                        e
+                    );
+                }
+            },
+
+            WindowEvent::SaveHar(path) => {
+                let msg = ConstellationMsg::SaveHar(path);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending HAR export to constellation failed ({:?}).", e);
+                }
+            },
+
             WindowEvent::ToggleWebRenderDebug(option) => {
                 self.compositor.toggle_webrender_debug(option);
             },
@@ -852,6 +873,7 @@ fn create_constellation(
         embedder_proxy.clone(),
         config_dir,
         opts.certificate_path.clone(),
+        opts.network_archive.clone(),
     );
     let font_cache_thread = FontCacheThread::new(
         public_resource_threads.sender(),
@@ -70,7 +70,10 @@ bitflags! {
         /// <https://html.spec.whatwg.org/multipage/#selector-optional>
         const IN_OPTIONAL_STATE = 1 << 22;
         /// <https://html.spec.whatwg.org/multipage/#selector-read-write>
-        const IN_READ_WRITE_STATE = 1 << 22;
+        ///
+        /// The same bit as `IN_MOZ_READWRITE_STATE`, which Gecko uses for
+        /// `:read-write` too.
+        const IN_READ_WRITE_STATE = 1 << 30;
         /// <https://html.spec.whatwg.org/multipage/#selector-defined>
         const IN_DEFINED_STATE = 1 << 23;
         /// <https://html.spec.whatwg.org/multipage/#selector-visited>
@@ -137,6 +140,8 @@ bitflags! {
         const IN_AUTOFILL_STATE = 1 << 50;
         /// Non-standard & undocumented.
         const IN_AUTOFILL_PREVIEW_STATE = 1 << 51;
+        /// <https://drafts.csswg.org/selectors-4/#the-focus-visible-pseudo>
+        const IN_FOCUS_VISIBLE_STATE = 1 << 52;
     }
 }
 
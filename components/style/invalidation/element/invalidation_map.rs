@@ -6,6 +6,7 @@
 
 use crate::context::QuirksMode;
 use crate::element_state::{DocumentState, ElementState};
+use crate::hash::FxHashMap;
 use crate::selector_map::{MaybeCaseInsensitiveHashMap, SelectorMap, SelectorMapEntry};
 use crate::selector_parser::SelectorImpl;
 use crate::{Atom, LocalName, Namespace};
@@ -14,7 +15,7 @@ use hashglobe::FailedAllocationError;
 use selectors::attr::NamespaceConstraint;
 use selectors::parser::{Combinator, Component};
 use selectors::parser::{Selector, SelectorIter, Visit};
-use selectors::visitor::SelectorVisitor;
+use selectors::visitor::{SelectorListKind, SelectorVisitor};
 use smallvec::SmallVec;
 
 /// Mapping between (partial) CompoundSelectors (and the combinator to their
@@ -35,6 +36,10 @@ use smallvec::SmallVec;
 /// This allows us to quickly scan through the dependency sites of all style
 /// rules and determine the maximum effect that a given state or attribute
 /// change may have on the style of elements in the document.
+///
+/// Selectors nested in `:is()`, `:where()` or `:has()` get their own
+/// dependencies, whose parent is the dependency for the compound selector
+/// containing them, see `InvalidationMap::parent_dependency`.
 #[derive(Clone, Debug, MallocSizeOf)]
 pub struct Dependency {
     /// The dependency selector.
@@ -49,6 +54,24 @@ pub struct Dependency {
     pub selector_offset: usize,
 }
 
+/// The dependency for the compound selector a selector nested in `:is()`,
+/// `:where()` or `:has()` is part of.
+#[derive(Clone, Debug, MallocSizeOf)]
+pub struct ParentDependency {
+    /// The dependency for the compound selector.
+    pub dependency: Dependency,
+
+    /// The parent of that dependency, if its selector is nested too.
+    pub parent: Option<Box<ParentDependency>>,
+}
+
+impl ParentDependency {
+    /// Returns the parent of this dependency, if any.
+    pub fn parent(&self) -> Option<&ParentDependency> {
+        self.parent.as_ref().map(|parent| &**parent)
+    }
+}
+
 /// The kind of elements down the tree this dependency may affect.
 #[derive(Debug, Eq, PartialEq)]
 pub enum DependencyInvalidationKind {
@@ -174,6 +197,18 @@ pub struct InvalidationMap {
     /// `other_attribute_affecting_selectors` too even if only the `id` has
     /// changed.
     pub has_id_attribute_selectors: bool,
+    /// The dependencies of the arguments of `:has()` selectors, if any.
+    ///
+    /// These are kept apart, since they invalidate the anchor elements, which
+    /// are ancestors or previous siblings of the element that changed.
+    pub relative_selector_map: Option<Box<InvalidationMap>>,
+    /// The parents of the dependencies of nested selectors in this map, keyed
+    /// by the address of the nested selector.
+    ///
+    /// These live in a side table rather than in each `Dependency`, which is
+    /// stored for every compound selector and would otherwise grow for the
+    /// sake of the few nested ones.
+    parent_dependencies: FxHashMap<usize, ParentDependency>,
 }
 
 impl InvalidationMap {
@@ -187,7 +222,22 @@ impl InvalidationMap {
             other_attribute_affecting_selectors: SelectorMap::new(),
             has_class_attribute_selectors: false,
             has_id_attribute_selectors: false,
+            relative_selector_map: None,
+            parent_dependencies: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the dependency for the compound selector the selector of
+    /// `dependency` is nested in, if any.
+    ///
+    /// `dependency` must be one of the dependencies of this map.
+    #[inline]
+    pub fn parent_dependency(&self, dependency: &Dependency) -> Option<&ParentDependency> {
+        if self.parent_dependencies.is_empty() {
+            return None;
         }
+        self.parent_dependencies
+            .get(&nested_selector_key(&dependency.selector))
     }
 
     /// Returns the number of dependencies stored in the invalidation map.
@@ -200,7 +250,10 @@ impl InvalidationMap {
                 .fold(0, |accum, (_, ref v)| accum + v.len()) +
             self.class_to_selector
                 .iter()
-                .fold(0, |accum, (_, ref v)| accum + v.len())
+                .fold(0, |accum, (_, ref v)| accum + v.len()) +
+            self.relative_selector_map
+                .as_ref()
+                .map_or(0, |map| map.len())
     }
 
     /// Clears this map, leaving it empty.
@@ -212,6 +265,8 @@ impl InvalidationMap {
         self.other_attribute_affecting_selectors.clear();
         self.has_id_attribute_selectors = false;
         self.has_class_attribute_selectors = false;
+        self.relative_selector_map = None;
+        self.parent_dependencies.clear();
     }
 
     /// Adds a selector to this `InvalidationMap`.  Returns Err(..) to
@@ -223,12 +278,41 @@ impl InvalidationMap {
     ) -> Result<(), FailedAllocationError> {
         debug!("InvalidationMap::note_selector({:?})", selector);
 
+        let mut document_state = DocumentState::empty();
+        self.note_selector_with_parent(selector, None, quirks_mode, &mut document_state)?;
+
+        if !document_state.is_empty() {
+            self.document_state_selectors
+                .try_push(DocumentStateDependency {
+                    state: document_state,
+                    selector: selector.clone(),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the dependencies of a selector, which may be nested in the
+    /// compound selector of the `parent` dependency.
+    ///
+    /// Document state dependencies are collected in `document_state`, since
+    /// they're noted for the outermost selector.
+    fn note_selector_with_parent(
+        &mut self,
+        selector: &Selector<SelectorImpl>,
+        parent: Option<&ParentDependency>,
+        quirks_mode: QuirksMode,
+        document_state: &mut DocumentState,
+    ) -> Result<(), FailedAllocationError> {
+        if let Some(parent) = parent {
+            self.parent_dependencies
+                .try_insert(nested_selector_key(selector), parent.clone())?;
+        }
+
         let mut iter = selector.iter();
         let mut combinator;
         let mut index = 0;
 
-        let mut document_state = DocumentState::empty();
-
         loop {
             let sequence_start = index;
 
@@ -236,20 +320,18 @@ impl InvalidationMap {
                 classes: SmallVec::new(),
                 ids: SmallVec::new(),
                 state: ElementState::empty(),
-                document_state: &mut document_state,
+                document_state: &mut *document_state,
                 other_attributes: false,
                 has_id_attribute_selectors: false,
                 has_class_attribute_selectors: false,
+                nested_selectors: SmallVec::new(),
             };
 
             // Visit all the simple selectors in this sequence.
             //
-            // Note that this works because we can't have combinators nested
-            // inside simple selectors (i.e. in :not() or :-moz-any()).
-            //
-            // If we ever support that we'll need to visit nested complex
-            // selectors as well, in order to mark them as affecting descendants
-            // at least.
+            // Complex selectors nested inside simple selectors (i.e. in :is())
+            // aren't visited, but collected to note their own dependencies
+            // below.
             for ss in &mut iter {
                 ss.visit(&mut compound_visitor);
                 index += 1; // Account for the simple selector.
@@ -301,6 +383,31 @@ impl InvalidationMap {
                 )?;
             }
 
+            if !compound_visitor.nested_selectors.is_empty() {
+                let nested_parent = ParentDependency {
+                    dependency: Dependency {
+                        selector: selector.clone(),
+                        selector_offset: sequence_start,
+                    },
+                    parent: parent.map(|parent| Box::new(parent.clone())),
+                };
+
+                for (list_kind, nested) in compound_visitor.nested_selectors {
+                    let map = match list_kind {
+                        SelectorListKind::Has => &mut **self
+                            .relative_selector_map
+                            .get_or_insert_with(|| Box::new(InvalidationMap::new())),
+                        SelectorListKind::Is | SelectorListKind::Where => &mut *self,
+                    };
+                    map.note_selector_with_parent(
+                        &nested,
+                        Some(&nested_parent),
+                        quirks_mode,
+                        document_state,
+                    )?;
+                }
+            }
+
             combinator = iter.next_sequence();
             if combinator.is_none() {
                 break;
@@ -309,18 +416,16 @@ impl InvalidationMap {
             index += 1; // Account for the combinator.
         }
 
-        if !document_state.is_empty() {
-            self.document_state_selectors
-                .try_push(DocumentStateDependency {
-                    state: document_state,
-                    selector: selector.clone(),
-                })?;
-        }
-
         Ok(())
     }
 }
 
+/// Returns the key of a nested selector in `InvalidationMap::parent_dependencies`.
+#[inline]
+fn nested_selector_key(selector: &Selector<SelectorImpl>) -> usize {
+    selector.thin_arc_heap_ptr() as usize
+}
+
 /// A struct that collects invalidations for a given compound selector.
 struct CompoundSelectorDependencyCollector<'a> {
     /// The state this compound selector is affected by.
@@ -354,6 +459,10 @@ struct CompoundSelectorDependencyCollector<'a> {
 
     /// Whether there were attribute selectors with the class attribute.
     has_class_attribute_selectors: bool,
+
+    /// The selectors nested in this compound selector, along with the kind of
+    /// list they're part of.
+    nested_selectors: SmallVec<[(SelectorListKind, Selector<SelectorImpl>); 1]>,
 }
 
 impl<'a> SelectorVisitor for CompoundSelectorDependencyCollector<'a> {
@@ -385,6 +494,16 @@ impl<'a> SelectorVisitor for CompoundSelectorDependencyCollector<'a> {
         true
     }
 
+    fn visit_selector_list(
+        &mut self,
+        list_kind: SelectorListKind,
+        list: &[Selector<SelectorImpl>],
+    ) -> bool {
+        self.nested_selectors
+            .extend(list.iter().map(|selector| (list_kind, selector.clone())));
+        true
+    }
+
     fn visit_attribute_selector(
         &mut self,
         constraint: &NamespaceConstraint<&Namespace>,
@@ -7,6 +7,9 @@
 
 use crate::context::StackLimitChecker;
 use crate::dom::{TElement, TNode, TShadowRoot};
+use crate::invalidation::element::invalidation_map::{
+    Dependency, DependencyInvalidationKind, ParentDependency,
+};
 use crate::selector_parser::SelectorImpl;
 use selectors::matching::matches_compound_selector_from;
 use selectors::matching::{CompoundSelectorMatchingResult, MatchingContext};
@@ -139,6 +142,9 @@ pub struct Invalidation<'a> {
     /// this one if the generated invalidation is effective for all the siblings
     /// or descendants after us.
     matched_by_any_previous: bool,
+    /// The dependency for the compound selector the selector is nested in, if
+    /// any, which needs to be processed once the selector fully matches.
+    parent: Option<&'a ParentDependency>,
 }
 
 impl<'a> Invalidation<'a> {
@@ -148,6 +154,25 @@ impl<'a> Invalidation<'a> {
             selector,
             offset,
             matched_by_any_previous: false,
+            parent: None,
+        }
+    }
+
+    /// Create a new invalidation for the compound selector to the left of the
+    /// one of a given dependency, with the dependency for the compound selector
+    /// its selector is nested in, if any.
+    pub fn from_dependency(
+        dependency: &'a Dependency,
+        parent: Option<&'a ParentDependency>,
+    ) -> Self {
+        debug_assert_ne!(dependency.selector_offset, 0);
+        debug_assert_ne!(dependency.selector_offset, dependency.selector.len());
+
+        Self {
+            selector: &dependency.selector,
+            offset: dependency.selector.len() - dependency.selector_offset + 1,
+            matched_by_any_previous: false,
+            parent,
         }
     }
 
@@ -746,7 +771,14 @@ where
             CompoundSelectorMatchingResult::FullyMatched => {
                 debug!(" > Invalidation matched completely");
                 matched = true;
-                invalidated_self = true;
+                invalidated_self = match invalidation.parent {
+                    Some(parent) => self.process_parent_dependency(
+                        parent,
+                        descendant_invalidations,
+                        sibling_invalidations,
+                    ),
+                    None => true,
+                };
             },
             CompoundSelectorMatchingResult::Matched {
                 next_combinator_offset,
@@ -823,6 +855,7 @@ where
                     selector: invalidation.selector,
                     offset: next_combinator_offset + 1,
                     matched_by_any_previous: false,
+                    parent: invalidation.parent,
                 };
 
                 debug!(
@@ -929,4 +962,52 @@ where
             matched,
         }
     }
+
+    /// Processes the dependency for the compound selector an invalidation's
+    /// selector is nested in (i.e. in :is()), once that selector fully matched
+    /// the current element.
+    ///
+    /// Returns whether invalidated the style of the element.
+    fn process_parent_dependency(
+        &mut self,
+        parent: &'b ParentDependency,
+        descendant_invalidations: &mut DescendantInvalidationLists<'b>,
+        sibling_invalidations: &mut InvalidationVector<'b>,
+    ) -> bool {
+        let invalidation_kind = parent.dependency.invalidation_kind();
+        if matches!(invalidation_kind, DependencyInvalidationKind::Element) {
+            return match parent.parent() {
+                Some(grandparent) => self.process_parent_dependency(
+                    grandparent,
+                    descendant_invalidations,
+                    sibling_invalidations,
+                ),
+                None => true,
+            };
+        }
+
+        let invalidation = Invalidation::from_dependency(&parent.dependency, parent.parent());
+        match invalidation_kind {
+            DependencyInvalidationKind::Element => unreachable!(),
+            DependencyInvalidationKind::ElementAndDescendants => {
+                descendant_invalidations.dom_descendants.push(invalidation);
+                return true;
+            },
+            DependencyInvalidationKind::Descendants => {
+                descendant_invalidations.dom_descendants.push(invalidation);
+            },
+            DependencyInvalidationKind::Siblings => {
+                sibling_invalidations.push(invalidation);
+            },
+            DependencyInvalidationKind::Parts => {
+                descendant_invalidations.parts.push(invalidation);
+            },
+            DependencyInvalidationKind::SlottedElements => {
+                descendant_invalidations
+                    .slotted_descendants
+                    .push(invalidation);
+            },
+        }
+        false
+    }
 }
@@ -21,6 +21,7 @@ use crate::{Atom, WeakAtom};
 use selectors::attr::CaseSensitivity;
 use selectors::matching::matches_selector;
 use selectors::matching::{MatchingContext, MatchingMode, VisitedHandlingMode};
+use selectors::parser::Combinator;
 use selectors::NthIndexCache;
 use smallvec::SmallVec;
 
@@ -42,6 +43,7 @@ where
     descendant_invalidations: &'a mut DescendantInvalidationLists<'selectors>,
     sibling_invalidations: &'a mut InvalidationVector<'selectors>,
     invalidates_self: bool,
+    relative_selector_anchors_only: bool,
 }
 
 /// An invalidation processor for style changes due to state and attribute
@@ -51,6 +53,9 @@ pub struct StateAndAttrInvalidationProcessor<'a, 'b: 'a, E: TElement> {
     element: E,
     data: &'a mut ElementData,
     matching_context: MatchingContext<'a, E::Impl>,
+    /// Whether we only look at the dependencies of `:has()` arguments, to
+    /// invalidate their anchors.
+    relative_selector_anchors_only: bool,
 }
 
 impl<'a, 'b: 'a, E: TElement + 'b> StateAndAttrInvalidationProcessor<'a, 'b, E> {
@@ -74,10 +79,52 @@ impl<'a, 'b: 'a, E: TElement + 'b> StateAndAttrInvalidationProcessor<'a, 'b, E>
             element,
             data,
             matching_context,
+            relative_selector_anchors_only: false,
         }
     }
 }
 
+/// Invalidates the style of the elements that may match a `:has()` selector
+/// differently due to the state and attribute changes of a given element.
+///
+/// The anchors of relative selectors are ancestors or previous siblings of the
+/// element that changed, which the traversal styles before getting to it, so
+/// this needs to run before the traversal.
+///
+/// DOM insertions and removals are handled through the
+/// `HAS_RELATIVE_SELECTOR` selector flags instead.
+pub fn invalidate_relative_selector_anchors<E>(element: E, shared_context: &SharedStyleContext)
+where
+    E: TElement,
+{
+    if shared_context.traversal_flags.for_animation_only() ||
+        !element.has_snapshot() ||
+        element.handled_snapshot()
+    {
+        return;
+    }
+
+    let mut data = match element.mutate_data() {
+        Some(data) => data,
+        None => return,
+    };
+
+    let mut nth_index_cache = NthIndexCache::default();
+    let mut processor = StateAndAttrInvalidationProcessor::new(
+        shared_context,
+        element,
+        &mut data,
+        &mut nth_index_cache,
+    );
+    processor.relative_selector_anchors_only = true;
+    processor.collect_invalidations(
+        element,
+        &mut InvalidationVector::new(),
+        &mut DescendantInvalidationLists::default(),
+        &mut InvalidationVector::new(),
+    );
+}
+
 /// Whether we should process the descendants of a given element for style
 /// invalidation.
 pub fn should_process_descendants(data: &ElementData) -> bool {
@@ -158,7 +205,9 @@ where
         // If we the visited state changed, we force a restyle here. Matching
         // doesn't depend on the actual visited state at all, so we can't look
         // at matching results to decide what to do for this case.
-        if state_changes.intersects(ElementState::IN_VISITED_OR_UNVISITED_STATE) {
+        if !self.relative_selector_anchors_only &&
+            state_changes.intersects(ElementState::IN_VISITED_OR_UNVISITED_STATE)
+        {
             trace!(" > visitedness change, force subtree restyle");
             // We can't just return here because there may also be attribute
             // changes as well that imply additional hints for siblings.
@@ -243,6 +292,7 @@ where
                 descendant_invalidations,
                 sibling_invalidations,
                 invalidates_self: false,
+                relative_selector_anchors_only: self.relative_selector_anchors_only,
             };
 
             let document_origins = if !matches_document_author_rules {
@@ -266,6 +316,10 @@ where
             collector.invalidates_self
         };
 
+        if self.relative_selector_anchors_only {
+            return false;
+        }
+
         // If we generated a ton of descendant invalidations, it's probably not
         // worth to go ahead and try to process them.
         //
@@ -325,12 +379,21 @@ where
     'selectors: 'a,
 {
     fn collect_dependencies_in_invalidation_map(&mut self, map: &'selectors InvalidationMap) {
+        let map = if self.relative_selector_anchors_only {
+            match map.relative_selector_map {
+                Some(ref relative_selector_map) => &**relative_selector_map,
+                None => return,
+            }
+        } else {
+            map
+        };
+
         let quirks_mode = self.matching_context.quirks_mode();
         let removed_id = self.removed_id;
         if let Some(ref id) = removed_id {
             if let Some(deps) = map.id_to_selector.get(id, quirks_mode) {
                 for dep in deps {
-                    self.scan_dependency(dep);
+                    self.scan_dependency(dep, map.parent_dependency(dep));
                 }
             }
         }
@@ -339,7 +402,7 @@ where
         if let Some(ref id) = added_id {
             if let Some(deps) = map.id_to_selector.get(id, quirks_mode) {
                 for dep in deps {
-                    self.scan_dependency(dep);
+                    self.scan_dependency(dep, map.parent_dependency(dep));
                 }
             }
         }
@@ -347,7 +410,7 @@ where
         for class in self.classes_added.iter().chain(self.classes_removed.iter()) {
             if let Some(deps) = map.class_to_selector.get(class, quirks_mode) {
                 for dep in deps {
-                    self.scan_dependency(dep);
+                    self.scan_dependency(dep, map.parent_dependency(dep));
                 }
             }
         }
@@ -357,23 +420,27 @@ where
             (self.snapshot.id_changed() && map.has_id_attribute_selectors);
 
         if should_examine_attribute_selector_map {
-            self.collect_dependencies_in_map(&map.other_attribute_affecting_selectors)
+            self.collect_dependencies_in_map(map, &map.other_attribute_affecting_selectors)
         }
 
         let state_changes = self.state_changes;
         if !state_changes.is_empty() {
-            self.collect_state_dependencies(&map.state_affecting_selectors, state_changes)
+            self.collect_state_dependencies(map, &map.state_affecting_selectors, state_changes)
         }
     }
 
-    fn collect_dependencies_in_map(&mut self, map: &'selectors SelectorMap<Dependency>) {
+    fn collect_dependencies_in_map(
+        &mut self,
+        invalidation_map: &'selectors InvalidationMap,
+        map: &'selectors SelectorMap<Dependency>,
+    ) {
         map.lookup_with_additional(
             self.lookup_element,
             self.matching_context.quirks_mode(),
             self.removed_id,
             self.classes_removed,
             |dependency| {
-                self.scan_dependency(dependency);
+                self.scan_dependency(dependency, invalidation_map.parent_dependency(dependency));
                 true
             },
         );
@@ -381,6 +448,7 @@ where
 
     fn collect_state_dependencies(
         &mut self,
+        invalidation_map: &'selectors InvalidationMap,
         map: &'selectors SelectorMap<StateDependency>,
         state_changes: ElementState,
     ) {
@@ -393,7 +461,10 @@ where
                 if !dependency.state.intersects(state_changes) {
                     return true;
                 }
-                self.scan_dependency(&dependency.dep);
+                self.scan_dependency(
+                    &dependency.dep,
+                    invalidation_map.parent_dependency(&dependency.dep),
+                );
                 true
             },
         );
@@ -424,37 +495,52 @@ where
         matched_then != matches_now
     }
 
-    fn scan_dependency(&mut self, dependency: &'selectors Dependency) {
+    fn scan_dependency(
+        &mut self,
+        dependency: &'selectors Dependency,
+        parent: Option<&'selectors ParentDependency>,
+    ) {
         debug!(
             "TreeStyleInvalidator::scan_dependency({:?}, {:?})",
             self.element, dependency
         );
 
-        if !self.dependency_may_be_relevant(dependency) {
+        if !self.dependency_may_be_relevant(dependency, parent) {
             return;
         }
 
         if self.check_dependency(dependency) {
-            return self.note_dependency(dependency);
+            return self.note_dependency(dependency, parent);
         }
     }
 
-    fn note_dependency(&mut self, dependency: &'selectors Dependency) {
-        debug_assert!(self.dependency_may_be_relevant(dependency));
+    fn note_dependency(
+        &mut self,
+        dependency: &'selectors Dependency,
+        parent: Option<&'selectors ParentDependency>,
+    ) {
+        debug_assert!(self.dependency_may_be_relevant(dependency, parent));
+
+        if self.relative_selector_anchors_only {
+            let parent = parent.expect("Relative selector dependency without a parent?");
+            return self.note_relative_selector_dependency(dependency, parent);
+        }
 
         let invalidation_kind = dependency.invalidation_kind();
         if matches!(invalidation_kind, DependencyInvalidationKind::Element) {
+            // A selector nested in :is() or :where() started or stopped
+            // matching, so the compound selector containing it may have too.
+            if let Some(parent) = parent {
+                if self.dependency_may_be_relevant(&parent.dependency, parent.parent()) {
+                    self.note_dependency(&parent.dependency, parent.parent());
+                }
+                return;
+            }
             self.invalidates_self = true;
             return;
         }
 
-        debug_assert_ne!(dependency.selector_offset, 0);
-        debug_assert_ne!(dependency.selector_offset, dependency.selector.len());
-
-        let invalidation = Invalidation::new(
-            &dependency.selector,
-            dependency.selector.len() - dependency.selector_offset + 1,
-        );
+        let invalidation = Invalidation::from_dependency(dependency, parent);
 
         match invalidation_kind {
             DependencyInvalidationKind::Element => unreachable!(),
@@ -483,11 +569,103 @@ where
         }
     }
 
+    /// Invalidates the elements that may be the anchor of the relative
+    /// selector `dependency` is part of, given that its compound selector
+    /// started or stopped matching the current element.
+    fn note_relative_selector_dependency(
+        &self,
+        dependency: &Dependency,
+        parent: &ParentDependency,
+    ) {
+        // Look at the combinators between the dependency and the anchor to
+        // find where the anchor may be.
+        let mut ancestor_combinators = 0;
+        let mut sibling_combinators = 0;
+        let mut exact_position = true;
+        let mut iter = dependency.selector.iter_from(dependency.selector_offset);
+        loop {
+            for _ in &mut iter {}
+            match iter.next_sequence() {
+                None => break,
+                Some(Combinator::Child) => ancestor_combinators += 1,
+                Some(Combinator::NextSibling) => sibling_combinators += 1,
+                Some(Combinator::Descendant) => {
+                    ancestor_combinators += 1;
+                    exact_position = false;
+                },
+                Some(Combinator::LaterSibling) => {
+                    sibling_combinators += 1;
+                    exact_position = false;
+                },
+                Some(Combinator::PseudoElement) |
+                Some(Combinator::SlotAssignment) |
+                Some(Combinator::Part) => {
+                    unreachable!("Pseudo-elements are not allowed in relative selectors")
+                },
+            }
+        }
+
+        if sibling_combinators == 0 {
+            let mut current = self.element.parent_element();
+            let mut depth = 1;
+            while let Some(ancestor) = current {
+                if !exact_position || depth == ancestor_combinators {
+                    invalidate_relative_selector_anchor(ancestor, parent);
+                    if exact_position {
+                        break;
+                    }
+                }
+                current = ancestor.parent_element();
+                depth += 1;
+            }
+            return;
+        }
+
+        // The anchor is a previous sibling of the element, or if there are
+        // ancestor combinators too, an ancestor or a previous sibling of one.
+        let exact_position = exact_position && ancestor_combinators == 0;
+        let mut current = Some(self.element);
+        while let Some(element) = current {
+            if element != self.element {
+                invalidate_relative_selector_anchor(element, parent);
+            }
+            let mut sibling = element.prev_sibling_element();
+            let mut distance = 1;
+            while let Some(previous) = sibling {
+                if !exact_position || distance == sibling_combinators {
+                    invalidate_relative_selector_anchor(previous, parent);
+                    if exact_position {
+                        break;
+                    }
+                }
+                sibling = previous.prev_sibling_element();
+                distance += 1;
+            }
+            if ancestor_combinators == 0 {
+                break;
+            }
+            current = element.parent_element();
+        }
+    }
+
     /// Returns whether `dependency` may cause us to invalidate the style of
     /// more elements than what we've already invalidated.
-    fn dependency_may_be_relevant(&self, dependency: &Dependency) -> bool {
+    fn dependency_may_be_relevant(
+        &self,
+        dependency: &Dependency,
+        parent: Option<&ParentDependency>,
+    ) -> bool {
+        if self.relative_selector_anchors_only {
+            return true;
+        }
+
         match dependency.invalidation_kind() {
-            DependencyInvalidationKind::Element => !self.invalidates_self,
+            DependencyInvalidationKind::Element => match parent {
+                Some(parent) => {
+                    self.dependency_may_be_relevant(&parent.dependency, parent.parent())
+                },
+                None => !self.invalidates_self,
+            },
             DependencyInvalidationKind::SlottedElements => self.element.is_html_slot_element(),
             DependencyInvalidationKind::Parts => self.element.shadow_root().is_some(),
             DependencyInvalidationKind::ElementAndDescendants |
@@ -496,3 +674,51 @@ where
         }
     }
 }
+
+/// Invalidates the style of the anchor of a relative selector, given the
+/// dependency for the compound selector containing the `:has()` selector.
+fn invalidate_relative_selector_anchor<E>(anchor: E, dependency: &ParentDependency)
+where
+    E: TElement,
+{
+    match dependency.dependency.invalidation_kind() {
+        DependencyInvalidationKind::Element => match dependency.parent() {
+            Some(parent) => invalidate_relative_selector_anchor(anchor, parent),
+            None => restyle_before_traversal(anchor, RestyleHint::RESTYLE_SELF),
+        },
+        DependencyInvalidationKind::Siblings => {
+            let mut sibling = anchor.next_sibling_element();
+            while let Some(element) = sibling {
+                restyle_before_traversal(element, RestyleHint::restyle_subtree());
+                sibling = element.next_sibling_element();
+            }
+        },
+        DependencyInvalidationKind::ElementAndDescendants |
+        DependencyInvalidationKind::Descendants |
+        DependencyInvalidationKind::SlottedElements |
+        DependencyInvalidationKind::Parts => {
+            restyle_before_traversal(anchor, RestyleHint::restyle_subtree());
+        },
+    }
+}
+
+/// Inserts a restyle hint for an element, and makes sure the traversal gets
+/// to it.
+fn restyle_before_traversal<E>(element: E, hint: RestyleHint)
+where
+    E: TElement,
+{
+    match element.mutate_data() {
+        Some(mut data) => data.hint.insert(hint),
+        None => return,
+    }
+
+    let mut current = element.traversal_parent();
+    while let Some(parent) = current {
+        if parent.has_dirty_descendants() {
+            break;
+        }
+        unsafe { parent.set_dirty_descendants() };
+        current = parent.traversal_parent();
+    }
+}
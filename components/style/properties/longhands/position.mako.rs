@@ -15,7 +15,6 @@
         "LengthPercentageOrAuto",
         "computed::LengthPercentageOrAuto::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         spec="https://www.w3.org/TR/CSS2/visuren.html#propdef-%s" % side,
         flags="GETCS_NEEDS_LAYOUT_FLUSH",
         animation_value_type="ComputedValue",
@@ -142,6 +141,15 @@ ${helpers.single_keyword(
         animation_value_type="discrete",
         servo_restyle_damage="reflow",
     )}
+
+    ${helpers.single_keyword(
+        "justify-items",
+        "stretch start end center",
+        engines="servo-2013",
+        spec="https://drafts.csswg.org/css-align/#propdef-justify-items",
+        animation_value_type="discrete",
+        servo_restyle_damage="reflow",
+    )}
 % endif
 % if engine == "gecko":
     ${helpers.predefined_type(
@@ -218,6 +226,15 @@ ${helpers.predefined_type(
         animation_value_type="discrete",
         servo_restyle_damage = "reflow",
     )}
+
+    ${helpers.single_keyword(
+        "justify-self",
+        "auto stretch start end center",
+        engines="servo-2013",
+        spec="https://drafts.csswg.org/css-align/#justify-self-property",
+        animation_value_type="discrete",
+        servo_restyle_damage="reflow",
+    )}
 % endif
 % if engine == "gecko":
     ${helpers.predefined_type(
@@ -279,7 +296,6 @@ ${helpers.predefined_type(
         "Size",
         "computed::Size::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="size",
         allow_quirks="No" if logical else "Yes",
@@ -294,7 +310,6 @@ ${helpers.predefined_type(
         "Size",
         "computed::Size::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="min-size",
         allow_quirks="No" if logical else "Yes",
@@ -307,7 +322,6 @@ ${helpers.predefined_type(
         "MaxSize",
         "computed::MaxSize::none()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="max-size",
         allow_quirks="No" if logical else "Yes",
@@ -354,9 +368,10 @@ ${helpers.predefined_type(
             "grid-%s-%s" % (kind, range),
             "GridLine",
             "Default::default()",
-            engines="gecko",
+            engines="gecko servo-2013",
             animation_value_type="discrete",
             spec="https://drafts.csswg.org/css-grid/#propdef-grid-%s-%s" % (kind, range),
+            servo_restyle_damage="reflow",
         )}
     % endfor
 
@@ -366,21 +381,23 @@ ${helpers.predefined_type(
         "grid-auto-%ss" % kind,
         "TrackSize",
         "Default::default()",
-        engines="gecko",
+        engines="gecko servo-2013",
         animation_value_type="discrete",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-%ss" % kind,
         boxed=True,
+        servo_restyle_damage="reflow",
     )}
 
     ${helpers.predefined_type(
         "grid-template-%ss" % kind,
         "GridTemplateComponent",
         "specified::GenericGridTemplateComponent::None",
-        engines="gecko",
+        engines="gecko servo-2013",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-%ss" % kind,
         boxed=True,
         flags="GETCS_NEEDS_LAYOUT_FLUSH",
         animation_value_type="ComputedValue",
+        servo_restyle_damage="reflow",
     )}
 
 % endfor
@@ -389,18 +406,20 @@ ${helpers.predefined_type(
     "grid-auto-flow",
     "GridAutoFlow",
     "computed::GridAutoFlow::row()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-flow",
+    servo_restyle_damage="reflow",
 )}
 
 ${helpers.predefined_type(
     "grid-template-areas",
     "GridTemplateAreas",
     "computed::GridTemplateAreas::none()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-areas",
+    servo_restyle_damage="reflow",
 )}
 
 ${helpers.predefined_type(
@@ -408,9 +427,8 @@ ${helpers.predefined_type(
     "length::NonNegativeLengthPercentageOrNormal",
     "computed::length::NonNegativeLengthPercentageOrNormal::normal()",
     engines="gecko servo-2013",
-    alias="grid-column-gap" if engine == "gecko" else "",
+    alias="grid-column-gap",
     extra_prefixes="moz",
-    servo_2013_pref="layout.columns.enabled",
     spec="https://drafts.csswg.org/css-align-3/#propdef-column-gap",
     animation_value_type="NonNegativeLengthPercentageOrNormal",
     servo_restyle_damage="reflow",
@@ -421,7 +439,7 @@ ${helpers.predefined_type(
     "row-gap",
     "length::NonNegativeLengthPercentageOrNormal",
     "computed::length::NonNegativeLengthPercentageOrNormal::normal()",
-    engines="gecko",
+    engines="gecko servo-2013",
     alias="grid-row-gap",
     spec="https://drafts.csswg.org/css-align-3/#propdef-row-gap",
     animation_value_type="NonNegativeLengthPercentageOrNormal",
@@ -272,19 +272,28 @@ pub enum NonTSPseudoClass {
     Active,
     AnyLink,
     Checked,
+    Default,
     Disabled,
     Enabled,
     Focus,
+    FocusVisible,
+    FocusWithin,
     Fullscreen,
     Hover,
+    InRange,
     Indeterminate,
+    Invalid,
     Lang(Lang),
     Link,
+    Optional,
+    OutOfRange,
     PlaceholderShown,
     ReadWrite,
     ReadOnly,
+    Required,
     ServoNonZeroBorder,
     Target,
+    Valid,
     Visited,
 }
 
@@ -321,18 +330,27 @@ impl ToCss for NonTSPseudoClass {
             Active => ":active",
             AnyLink => ":any-link",
             Checked => ":checked",
+            Default => ":default",
             Disabled => ":disabled",
             Enabled => ":enabled",
             Focus => ":focus",
+            FocusVisible => ":focus-visible",
+            FocusWithin => ":focus-within",
             Fullscreen => ":fullscreen",
             Hover => ":hover",
+            InRange => ":in-range",
             Indeterminate => ":indeterminate",
+            Invalid => ":invalid",
             Link => ":link",
+            Optional => ":optional",
+            OutOfRange => ":out-of-range",
             PlaceholderShown => ":placeholder-shown",
             ReadWrite => ":read-write",
             ReadOnly => ":read-only",
+            Required => ":required",
             ServoNonZeroBorder => ":-servo-nonzero-border",
             Target => ":target",
+            Valid => ":valid",
             Visited => ":visited",
             Lang(_) => unreachable!(),
         })
@@ -358,6 +376,8 @@ impl NonTSPseudoClass {
         match *self {
             Active => ElementState::IN_ACTIVE_STATE,
             Focus => ElementState::IN_FOCUS_STATE,
+            FocusVisible => ElementState::IN_FOCUS_VISIBLE_STATE,
+            FocusWithin => ElementState::IN_FOCUS_WITHIN_STATE,
             Fullscreen => ElementState::IN_FULLSCREEN_STATE,
             Hover => ElementState::IN_HOVER_STATE,
             Enabled => ElementState::IN_ENABLED_STATE,
@@ -367,6 +387,13 @@ impl NonTSPseudoClass {
             ReadOnly | ReadWrite => ElementState::IN_READ_WRITE_STATE,
             PlaceholderShown => ElementState::IN_PLACEHOLDER_SHOWN_STATE,
             Target => ElementState::IN_TARGET_STATE,
+            Valid => ElementState::IN_VALID_STATE,
+            Invalid => ElementState::IN_INVALID_STATE,
+            Required => ElementState::IN_REQUIRED_STATE,
+            Optional => ElementState::IN_OPTIONAL_STATE,
+            InRange => ElementState::IN_INRANGE_STATE,
+            OutOfRange => ElementState::IN_OUTOFRANGE_STATE,
+            Default => ElementState::IN_DEFAULT_STATE,
 
             AnyLink | Lang(_) | Link | Visited | ServoNonZeroBorder => ElementState::empty(),
         }
@@ -425,17 +452,26 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
             "active" => Active,
             "any-link" => AnyLink,
             "checked" => Checked,
+            "default" => Default,
             "disabled" => Disabled,
             "enabled" => Enabled,
             "focus" => Focus,
+            "focus-visible" => FocusVisible,
+            "focus-within" => FocusWithin,
             "fullscreen" => Fullscreen,
             "hover" => Hover,
+            "in-range" => InRange,
             "indeterminate" => Indeterminate,
+            "invalid" => Invalid,
             "link" => Link,
+            "optional" => Optional,
+            "out-of-range" => OutOfRange,
             "placeholder-shown" => PlaceholderShown,
             "read-write" => ReadWrite,
             "read-only" => ReadOnly,
+            "required" => Required,
             "target" => Target,
+            "valid" => Valid,
             "visited" => Visited,
             "-servo-nonzero-border" => {
                 if !self.in_user_agent_stylesheet() {
@@ -44,7 +44,7 @@ use selectors::matching::VisitedHandlingMode;
 use selectors::matching::{matches_selector, ElementSelectorFlags, MatchingContext, MatchingMode};
 use selectors::parser::{AncestorHashes, Combinator, Component, Selector};
 use selectors::parser::{SelectorIter, Visit};
-use selectors::visitor::SelectorVisitor;
+use selectors::visitor::{SelectorListKind, SelectorVisitor};
 use selectors::NthIndexCache;
 use servo_arc::{Arc, ArcBorrow};
 use smallbitvec::SmallBitVec;
@@ -1584,9 +1584,8 @@ impl<'a> SelectorVisitor for StylistSelectorVisitor<'a> {
         self.needs_revalidation =
             self.needs_revalidation || combinator.map_or(false, |c| c.is_sibling());
 
-        // NOTE(emilio): This works properly right now because we can't store
-        // complex selectors in nested selectors, otherwise we may need to
-        // rethink this.
+        // NOTE: Complex selectors nested in `:is()`, `:where()` or
+        // `:has()` restore this flag once visited, see visit_selector_list.
         //
         // Also, note that this call happens before we visit any of the simple
         // selectors in the next ComplexSelector, so we can use this to skip
@@ -1597,6 +1596,30 @@ impl<'a> SelectorVisitor for StylistSelectorVisitor<'a> {
         true
     }
 
+    fn visit_selector_list(
+        &mut self,
+        list_kind: SelectorListKind,
+        list: &[Selector<SelectorImpl>],
+    ) -> bool {
+        // Whether `:has()` matches depends on the descendants or siblings of
+        // the element, which elements sharing style don't have in common.
+        let is_relative = list_kind == SelectorListKind::Has;
+        self.needs_revalidation = self.needs_revalidation || is_relative;
+
+        for nested in list {
+            // The compounds of a relative selector never match the element
+            // itself, so their ids shouldn't be mapped.
+            let passed_rightmost_selector = self.passed_rightmost_selector;
+            self.passed_rightmost_selector = passed_rightmost_selector || is_relative;
+            let result = nested.visit(self);
+            self.passed_rightmost_selector = passed_rightmost_selector;
+            if !result {
+                return false;
+            }
+        }
+        true
+    }
+
     fn visit_attribute_selector(
         &mut self,
         _ns: &NamespaceConstraint<&Namespace>,
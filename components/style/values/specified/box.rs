@@ -115,9 +115,9 @@ pub enum Display {
     #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     #[parse(aliases = "-webkit-inline-flex")]
     InlineFlex,
-    #[cfg(feature = "gecko")]
+    #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     Grid,
-    #[cfg(feature = "gecko")]
+    #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     InlineGrid,
     #[cfg(feature = "gecko")]
     Ruby,
@@ -184,7 +184,7 @@ impl Display {
         match *self {
             Display::InlineBlock => true,
             #[cfg(feature = "servo-layout-2013")]
-            Display::InlineFlex | Display::InlineTable => true,
+            Display::InlineFlex | Display::InlineGrid | Display::InlineTable => true,
             _ => false,
         }
     }
@@ -197,7 +197,7 @@ impl Display {
         match *self {
             #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             Display::Flex | Display::InlineFlex => true,
-            #[cfg(feature = "gecko")]
+            #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             Display::Grid | Display::InlineGrid => true,
             _ => false,
         }
@@ -248,9 +248,9 @@ impl Display {
             Display::InlineTable => Display::Table,
             #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             Display::InlineFlex => Display::Flex,
-
-            #[cfg(feature = "gecko")]
+            #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             Display::InlineGrid => Display::Grid,
+
             #[cfg(feature = "gecko")]
             Display::WebkitInlineBox => Display::WebkitBox,
 
@@ -262,10 +262,10 @@ impl Display {
             // These are not changed by blockification.
             Display::None | Display::Block => *self,
             #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
-            Display::Flex | Display::ListItem | Display::Table => *self,
+            Display::Flex | Display::Grid | Display::ListItem | Display::Table => *self,
 
             #[cfg(feature = "gecko")]
-            Display::Contents | Display::FlowRoot | Display::Grid | Display::WebkitBox => *self,
+            Display::Contents | Display::FlowRoot | Display::WebkitBox => *self,
 
             // Everything else becomes block.
             _ => Display::Block,
@@ -7,7 +7,7 @@ use crate::window_trait::{WindowPortsMethods, LINE_HEIGHT};
 use euclid::{Point2D, Vector2D};
 use keyboard_types::{Key, KeyboardEvent, Modifiers, ShortcutMatcher};
 use servo::compositing::windowing::{WebRenderDebugOption, WindowEvent};
-use servo::embedder_traits::{EmbedderMsg, FilterPattern};
+use servo::embedder_traits::{ClipboardContents, EmbedderMsg, FilterPattern};
 use servo::msg::constellation_msg::TopLevelBrowsingContextId as BrowserId;
 use servo::msg::constellation_msg::TraversalDirection;
 use servo::net_traits::pub_domains::is_reg_domain;
@@ -24,7 +24,7 @@ use std::mem;
 use std::rc::Rc;
 use std::thread;
 use std::time::Duration;
-use tinyfiledialogs::{self, MessageBoxIcon};
+use tinyfiledialogs::{self, MessageBoxIcon, YesNo};
 
 pub struct Browser<Window: WindowPortsMethods + ?Sized> {
     current_url: Option<ServoUrl>,
@@ -328,6 +328,15 @@ where
                             .push(WindowEvent::SendError(browser_id, reason));
                     }
                 },
+                EmbedderMsg::AllowCertificateException(host, reason, sender) => {
+                    let allow = !opts::get().headless && allow_certificate_exception(host, reason);
+                    if let Err(e) = sender.send(allow) {
+                        let reason =
+                            format!("Failed to send AllowCertificateException response: {}", e);
+                        self.event_queue
+                            .push(WindowEvent::SendError(browser_id, reason));
+                    }
+                },
                 EmbedderMsg::AllowNavigationRequest(pipeline_id, _url) => {
                     if let Some(_browser_id) = browser_id {
                         self.event_queue
@@ -366,12 +375,15 @@ where
                         },
                         None => "".to_owned(),
                     };
-                    if let Err(e) = sender.send(contents) {
+                    // The system clipboard is only accessed as text.
+                    if let Err(e) = sender.send(ClipboardContents::from_text(contents)) {
                         warn!("Failed to send clipboard ({})", e);
                     }
                 }
-                EmbedderMsg::SetClipboardContents(text) => {
-                    if let Some(ref mut ctx) = self.clipboard_ctx {
+                EmbedderMsg::SetClipboardContents(contents) => {
+                    if let (Some(ref mut ctx), Some(text)) =
+                        (self.clipboard_ctx.as_mut(), contents.text())
+                    {
                         if let Err(e) = ctx.set_contents(text) {
                             warn!("Error setting clipboard contents ({})", e);
                         }
@@ -485,6 +497,26 @@ fn platform_get_selected_devices(devices: Vec<String>) -> Option<String> {
     None
 }
 
+fn allow_certificate_exception(host: String, reason: String) -> bool {
+    thread::Builder::new()
+        .name("display certificate dialog".to_owned())
+        .spawn(move || {
+            let message = format!(
+                "{}\n\nDo you want to trust this certificate for {} anyway?",
+                reason, host
+            );
+            tinyfiledialogs::message_box_yes_no(
+                "Certificate error",
+                &message,
+                MessageBoxIcon::Warning,
+                YesNo::No,
+            ) == YesNo::Yes
+        })
+        .unwrap()
+        .join()
+        .expect("Thread spawning failed")
+}
+
 fn get_selected_files(patterns: Vec<FilterPattern>, multiple_files: bool) -> Option<Vec<String>> {
     let picker_name = if multiple_files {
         "Pick files"
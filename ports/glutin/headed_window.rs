@@ -23,7 +23,7 @@ use image;
 use keyboard_types::{Key, KeyState, KeyboardEvent};
 use servo::compositing::windowing::{AnimationState, MouseWindowEvent, WindowEvent};
 use servo::compositing::windowing::{EmbedderCoordinates, WindowMethods};
-use servo::embedder_traits::Cursor;
+use servo::embedder_traits::{Cursor, DragData, DraggedFile};
 use servo::script_traits::{TouchEventType, WheelMode, WheelDelta};
 use servo::servo_config::{opts, pref};
 use servo::servo_geometry::DeviceIndependentPixel;
@@ -32,8 +32,12 @@ use servo::webrender_api::ScrollLocation;
 use servo::webrender_api::units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize};
 use servo_media::player::context::{GlApi, GlContext as PlayerGLContext, NativeDisplay};
 use std::cell::{Cell, RefCell};
+use std::fs;
 use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
 #[cfg(target_os = "windows")]
 use winapi;
 
@@ -63,6 +67,8 @@ pub struct Window {
     mouse_down_point: Cell<Point2D<i32, DevicePixel>>,
     primary_monitor: glutin::MonitorId,
     event_queue: RefCell<Vec<WindowEvent>>,
+    /// Files dropped onto the window, once read by a `DroppedFileReader` thread.
+    dropped_files: Arc<Mutex<Vec<DraggedFile>>>,
     mouse_pos: Cell<Point2D<i32, DevicePixel>>,
     last_pressed: Cell<Option<KeyboardEvent>>,
     animation_state: Cell<AnimationState>,
@@ -171,6 +177,7 @@ impl Window {
             gl_context: RefCell::new(context),
             events_loop,
             event_queue: RefCell::new(vec![]),
+            dropped_files: Arc::new(Mutex::new(vec![])),
             mouse_down_button: Cell::new(None),
             mouse_down_point: Cell::new(Point2D::new(0, 0)),
             mouse_pos: Cell::new(Point2D::new(0, 0)),
@@ -271,6 +278,50 @@ impl Window {
             .push(WindowEvent::MouseWindowEventClass(event));
     }
 
+    /// Helper function to handle a file dropped from another application
+    fn handle_dropped_file(&self, path: PathBuf) {
+        // Dropped files can be arbitrarily large, so read them off the UI thread.
+        let dropped_files = self.dropped_files.clone();
+        let waker = self.events_loop.borrow().create_event_loop_waker();
+        thread::Builder::new()
+            .name("DroppedFileReader".to_owned())
+            .spawn(move || {
+                let data = match fs::read(&path) {
+                    Ok(data) => data,
+                    Err(e) => return warn!("Error reading dropped file {:?} ({})", path, e),
+                };
+                let name = path
+                    .file_name()
+                    .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+                dropped_files
+                    .lock()
+                    .unwrap()
+                    .push(DraggedFile { name, data });
+                waker.wake();
+            })
+            .expect("Thread spawning failed");
+    }
+
+    /// Queue a drop event for the files that have been read since the last call.
+    fn queue_dropped_files(&self) {
+        let files = mem::replace(&mut *self.dropped_files.lock().unwrap(), vec![]);
+        if files.is_empty() {
+            return;
+        }
+        // winit doesn't report where a file was dropped, nor does it move the
+        // cursor during the drag, so use the cursor position as of the read
+        // finishing, which includes the pointer events that follow the drop.
+        let pos = self.mouse_pos.get();
+        let position = Point2D::new(pos.x as f32, pos.y as f32);
+        let drag_data = DragData {
+            items: vec![],
+            files,
+        };
+        self.event_queue
+            .borrow_mut()
+            .push(WindowEvent::Drop(position, drag_data));
+    }
+
     fn device_hidpi_factor(&self) -> Scale<f32, DeviceIndependentPixel, DevicePixel> {
         Scale::new(self.gl_context.borrow().window().get_hidpi_factor() as f32)
     }
@@ -288,11 +339,12 @@ impl Window {
 
 impl WindowPortsMethods for Window {
     fn get_events(&self) -> Vec<WindowEvent> {
+        self.queue_dropped_files();
         mem::replace(&mut *self.event_queue.borrow_mut(), Vec::new())
     }
 
     fn has_events(&self) -> bool {
-        !self.event_queue.borrow().is_empty()
+        !self.event_queue.borrow().is_empty() || !self.dropped_files.lock().unwrap().is_empty()
     }
 
     fn page_height(&self) -> f32 {
@@ -437,6 +489,7 @@ impl WindowPortsMethods for Window {
                 self.event_queue.borrow_mut().push(wheel_event);
                 self.event_queue.borrow_mut().push(scroll_event);
             },
+            glutin::WindowEvent::DroppedFile(path) => self.handle_dropped_file(path),
             glutin::WindowEvent::Touch(touch) => {
                 use servo::script_traits::TouchId;
 
@@ -14,7 +14,7 @@ use servo::compositing::windowing::{
     WindowMethods,
 };
 use servo::embedder_traits::resources::{self, Resource, ResourceReaderMethods};
-use servo::embedder_traits::EmbedderMsg;
+use servo::embedder_traits::{ClipboardContents, EmbedderMsg};
 use servo::euclid::{Point2D, Rect, Scale, Size2D, Vector2D};
 use servo::keyboard_types::{Key, KeyState, KeyboardEvent};
 use servo::msg::constellation_msg::TraversalDirection;
@@ -517,6 +517,9 @@ impl ServoGlue {
                 EmbedderMsg::AllowUnload(sender) => {
                     let _ = sender.send(true);
                 },
+                EmbedderMsg::AllowCertificateException(_, _, sender) => {
+                    let _ = sender.send(false);
+                },
                 EmbedderMsg::Alert(message, sender) => {
                     info!("Alert: {}", message);
                     self.callbacks.host_callbacks.on_alert(message);
@@ -539,10 +542,14 @@ impl ServoGlue {
                 },
                 EmbedderMsg::GetClipboardContents(sender) => {
                     let contents = self.callbacks.host_callbacks.get_clipboard_contents();
-                    let _ = sender.send(contents.unwrap_or("".to_owned()));
+                    let _ = sender.send(ClipboardContents::from_text(
+                        contents.unwrap_or("".to_owned()),
+                    ));
                 },
-                EmbedderMsg::SetClipboardContents(text) => {
-                    self.callbacks.host_callbacks.set_clipboard_contents(text);
+                EmbedderMsg::SetClipboardContents(contents) => {
+                    if let Some(text) = contents.text() {
+                        self.callbacks.host_callbacks.set_clipboard_contents(text);
+                    }
                 },
                 EmbedderMsg::CloseBrowser => {
                     // TODO: close the appropriate "tab".
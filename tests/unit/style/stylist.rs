@@ -122,6 +122,12 @@ fn test_revalidation_selectors() {
         "span ~ div",
         // Selectors in the ancestor chain (needed for cousin sharing).
         "p:first-child span",
+        // Nested selector lists.
+        "div:is(span, p)",
+        "div:where(span p)",
+        "div:is(span + p)",
+        "div:where(:first-child)",
+        "div:has(span)",
     ])
     .into_iter()
     .filter(|s| needs_revalidation_for_testing(&s))
@@ -158,6 +164,10 @@ fn test_revalidation_selectors() {
         "span ~ div",
         // Selectors in the ancestor chain (needed for cousin sharing).
         "p:first-child span",
+        // Nested selector lists.
+        "div:is(span + p)",
+        "div:where(:first-child)",
+        "div:has(span)",
     ])
     .into_iter()
     .collect::<Vec<_>>();
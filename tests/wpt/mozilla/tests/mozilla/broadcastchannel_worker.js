@@ -0,0 +1,7 @@
+var channel = new BroadcastChannel("worker");
+channel.onmessage = function(e) {
+  if (e.data == "ping") {
+    channel.postMessage("pong");
+  }
+};
+channel.postMessage("ready");